@@ -0,0 +1,202 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{error, info};
+use protocol::amqp::method::basic::{BasicDeliver, BasicMethod};
+use protocol::amqp::method::AmqpMethod;
+use storage_adapter::storage::StorageAdapter;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+
+use crate::handler::cache::{AmqpCacheManager, QueueConsumer, UnackedDelivery};
+use crate::handler::content::build_content_frames;
+use crate::handler::queue::QueueManager;
+
+// Upper bound on the time a message waits when a notification is missed
+const DISPATCH_INTERVAL_MS: u64 = 100;
+
+/// Pushes the messages of every queue with consumers, round robin across the
+/// consumers that still have prefetch capacity.
+pub struct QueueDispatcher<S> {
+    cache_manager: Arc<AmqpCacheManager>,
+    queue_manager: Arc<QueueManager<S>>,
+}
+
+impl<S> QueueDispatcher<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(cache_manager: Arc<AmqpCacheManager>, queue_manager: Arc<QueueManager<S>>) -> Self {
+        QueueDispatcher {
+            cache_manager,
+            queue_manager,
+        }
+    }
+
+    pub async fn start(&self, stop_sx: broadcast::Sender<bool>) {
+        let mut stop_rx = stop_sx.subscribe();
+        let notify = self.queue_manager.notify.clone();
+        loop {
+            select! {
+                val = stop_rx.recv() => {
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("AMQP queue dispatcher stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = notify.notified() => {}
+                _ = sleep(Duration::from_millis(DISPATCH_INTERVAL_MS)) => {}
+            }
+
+            let queues: Vec<String> = self
+                .cache_manager
+                .queue_consumers
+                .iter()
+                .filter(|raw| !raw.value().is_empty())
+                .map(|raw| raw.key().clone())
+                .collect();
+            for queue_name in queues {
+                self.dispatch_queue(&queue_name).await;
+            }
+        }
+    }
+
+    async fn dispatch_queue(&self, queue_name: &str) {
+        loop {
+            let consumer = match self.select_consumer(queue_name) {
+                Some(consumer) => consumer,
+                None => return,
+            };
+
+            let (offset, redelivered, message) =
+                match self.queue_manager.next_message(queue_name).await {
+                    Ok(Some(data)) => data,
+                    Ok(None) => return,
+                    Err(e) => {
+                        error!(
+                            "Failed to read message of queue {}, error message: {}",
+                            queue_name, e
+                        );
+                        return;
+                    }
+                };
+
+            let delivery_tag = match self
+                .cache_manager
+                .channel_info
+                .get_mut(&(consumer.connection_id, consumer.channel_id))
+            {
+                Some(mut channel) if !channel.closing => {
+                    let delivery_tag = channel.next_delivery_tag();
+                    if !consumer.no_ack {
+                        channel.unacked.insert(
+                            delivery_tag,
+                            UnackedDelivery {
+                                queue_name: queue_name.to_string(),
+                                offset,
+                            },
+                        );
+                    }
+                    Some(delivery_tag)
+                }
+                _ => None,
+            };
+            let connection = self.cache_manager.get_connection(consumer.connection_id);
+            let (delivery_tag, connection) = match (delivery_tag, connection) {
+                (Some(delivery_tag), Some(connection)) => (delivery_tag, connection),
+                _ => {
+                    // The consumer went away between selection and delivery
+                    self.requeue(queue_name, offset).await;
+                    return;
+                }
+            };
+
+            if consumer.no_ack {
+                if let Err(e) = self.queue_manager.ack(queue_name, &[offset]).await {
+                    error!(
+                        "Failed to commit offset of queue {}, error message: {}",
+                        queue_name, e
+                    );
+                }
+            }
+
+            let deliver = AmqpMethod::Basic(BasicMethod::Deliver(BasicDeliver {
+                consumer_tag: consumer.consumer_tag.clone(),
+                delivery_tag,
+                redelivered,
+                exchange: message.exchange.clone(),
+                routing_key: message.routing_key.clone(),
+            }));
+            let frames = match build_content_frames(
+                consumer.channel_id,
+                deliver,
+                &message,
+                connection.frame_max,
+            ) {
+                Ok(frames) => frames,
+                Err(e) => {
+                    error!(
+                        "Failed to build delivery of queue {}, error message: {}",
+                        queue_name, e
+                    );
+                    continue;
+                }
+            };
+            for frame in frames {
+                if connection.write_sx.send(frame).await.is_err() {
+                    // Unacked deliveries are requeued when the connection is cleaned up
+                    break;
+                }
+            }
+        }
+    }
+
+    fn select_consumer(&self, queue_name: &str) -> Option<QueueConsumer> {
+        let consumers = self.cache_manager.get_queue_consumers(queue_name);
+        if consumers.is_empty() {
+            return None;
+        }
+        let start = self.queue_manager.next_consumer_cursor(queue_name);
+        (0..consumers.len())
+            .map(|i| &consumers[(start + i) % consumers.len()])
+            .find(|consumer| {
+                match self
+                    .cache_manager
+                    .channel_info
+                    .get(&(consumer.connection_id, consumer.channel_id))
+                {
+                    Some(channel) => {
+                        !channel.closing && (consumer.no_ack || channel.has_capacity())
+                    }
+                    None => false,
+                }
+            })
+            .cloned()
+    }
+
+    async fn requeue(&self, queue_name: &str, offset: u64) {
+        if let Err(e) = self.queue_manager.requeue(queue_name, &[offset]).await {
+            error!(
+                "Failed to requeue message of queue {}, error message: {}",
+                queue_name, e
+            );
+        }
+    }
+}
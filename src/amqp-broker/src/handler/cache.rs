@@ -0,0 +1,368 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bytes::BytesMut;
+use common_base::config::broker_amqp::broker_amqp_conf;
+use dashmap::DashMap;
+use metadata_struct::amqp::binding::AmqpBinding;
+use metadata_struct::amqp::exchange::{AmqpExchange, AmqpExchangeType};
+use metadata_struct::amqp::queue::AmqpQueue;
+use protocol::amqp::common::{AmqpFrame, ContentHeader};
+use protocol::amqp::method::basic::BasicPublish;
+use tokio::sync::mpsc::Sender;
+
+static CONNECTION_ID_BUILD: AtomicU64 = AtomicU64::new(1);
+
+pub const DEFAULT_EXCHANGE: &str = "";
+
+/// Names starting with "amq." are reserved for the predefined exchanges
+pub fn is_reserved_exchange(exchange_name: &str) -> bool {
+    exchange_name.is_empty() || exchange_name.starts_with("amq.")
+}
+
+#[derive(Clone)]
+pub struct AmqpConnection {
+    pub connection_id: u64,
+    pub addr: SocketAddr,
+    pub login_user: String,
+    pub is_open: bool,
+    pub channel_max: u16,
+    pub frame_max: u32,
+    pub heartbeat: u16,
+    pub write_sx: Sender<AmqpFrame>,
+    pub stop_sx: Sender<bool>,
+}
+
+impl AmqpConnection {
+    pub fn new(addr: SocketAddr, write_sx: Sender<AmqpFrame>, stop_sx: Sender<bool>) -> Self {
+        let conf = broker_amqp_conf();
+        AmqpConnection {
+            connection_id: CONNECTION_ID_BUILD.fetch_add(1, Ordering::Relaxed),
+            addr,
+            login_user: String::new(),
+            is_open: false,
+            channel_max: conf.network.channel_max,
+            frame_max: conf.network.frame_max,
+            heartbeat: conf.network.heartbeat,
+            write_sx,
+            stop_sx,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ChannelConsumer {
+    pub queue_name: String,
+    pub no_ack: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct UnackedDelivery {
+    pub queue_name: String,
+    pub offset: u64,
+}
+
+/// Content of a basic.publish being assembled from its method, header and body frames
+#[derive(Clone, Debug)]
+pub struct PendingPublish {
+    pub publish: BasicPublish,
+    pub header: Option<ContentHeader>,
+    pub body: BytesMut,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AmqpChannel {
+    pub confirm_mode: bool,
+    // Sequence number of the last message published in confirm mode
+    pub publish_seq: u64,
+    // Zero means no limit on unacknowledged deliveries
+    pub prefetch_count: u16,
+    pub delivery_tag: u64,
+    // (consumer_tag, ChannelConsumer)
+    pub consumers: HashMap<String, ChannelConsumer>,
+    // (delivery_tag, UnackedDelivery)
+    pub unacked: BTreeMap<u64, UnackedDelivery>,
+    pub pending_publish: Option<PendingPublish>,
+    // Set once the broker has sent channel.close and waits for channel.close-ok
+    pub closing: bool,
+}
+
+impl AmqpChannel {
+    pub fn next_delivery_tag(&mut self) -> u64 {
+        self.delivery_tag += 1;
+        self.delivery_tag
+    }
+
+    pub fn has_capacity(&self) -> bool {
+        self.prefetch_count == 0 || self.unacked.len() < self.prefetch_count as usize
+    }
+
+    /// Removes the deliveries settled by an ack, nack or reject
+    pub fn take_unacked(&mut self, delivery_tag: u64, multiple: bool) -> Vec<UnackedDelivery> {
+        if multiple {
+            // A delivery tag of zero with multiple set means all outstanding deliveries
+            let end = if delivery_tag == 0 {
+                u64::MAX
+            } else {
+                delivery_tag
+            };
+            let tags: Vec<u64> = self.unacked.range(..=end).map(|(tag, _)| *tag).collect();
+            return tags
+                .into_iter()
+                .filter_map(|tag| self.unacked.remove(&tag))
+                .collect();
+        }
+        self.unacked.remove(&delivery_tag).into_iter().collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueueConsumer {
+    pub connection_id: u64,
+    pub channel_id: u16,
+    pub consumer_tag: String,
+    pub no_ack: bool,
+}
+
+#[derive(Default)]
+pub struct AmqpCacheManager {
+    // (connection_id, AmqpConnection)
+    pub connection_info: DashMap<u64, AmqpConnection>,
+    // ((connection_id, channel_id), AmqpChannel)
+    pub channel_info: DashMap<(u64, u16), AmqpChannel>,
+    // (exchange_name, AmqpExchange)
+    pub exchange_info: DashMap<String, AmqpExchange>,
+    // (queue_name, AmqpQueue)
+    pub queue_info: DashMap<String, AmqpQueue>,
+    // (queue_name, connection_id) of exclusive queues
+    pub queue_owner: DashMap<String, u64>,
+    // (exchange_name, bindings of the exchange)
+    pub binding_info: DashMap<String, HashSet<AmqpBinding>>,
+    // (queue_name, consumers of the queue)
+    pub queue_consumers: DashMap<String, Vec<QueueConsumer>>,
+}
+
+impl AmqpCacheManager {
+    pub fn new() -> Self {
+        let cache = AmqpCacheManager::default();
+        let conf = broker_amqp_conf();
+        for (name, exchange_type) in [
+            (DEFAULT_EXCHANGE, AmqpExchangeType::Direct),
+            ("amq.direct", AmqpExchangeType::Direct),
+            ("amq.fanout", AmqpExchangeType::Fanout),
+            ("amq.topic", AmqpExchangeType::Topic),
+        ] {
+            cache.add_exchange(AmqpExchange {
+                cluster_name: conf.cluster_name.clone(),
+                exchange_name: name.to_string(),
+                exchange_type,
+                durable: true,
+                auto_delete: false,
+                internal: false,
+            });
+        }
+        cache
+    }
+
+    pub fn add_connection(&self, connection: AmqpConnection) {
+        self.connection_info
+            .insert(connection.connection_id, connection);
+    }
+
+    pub fn get_connection(&self, connection_id: u64) -> Option<AmqpConnection> {
+        self.connection_info
+            .get(&connection_id)
+            .map(|conn| conn.clone())
+    }
+
+    pub fn remove_connection(&self, connection_id: u64) {
+        self.connection_info.remove(&connection_id);
+    }
+
+    pub fn connection_count(&self) -> usize {
+        self.connection_info.len()
+    }
+
+    pub fn open_channel(&self, connection_id: u64, channel_id: u16) -> bool {
+        if self.channel_info.contains_key(&(connection_id, channel_id)) {
+            return false;
+        }
+        self.channel_info
+            .insert((connection_id, channel_id), AmqpChannel::default());
+        true
+    }
+
+    pub fn is_channel_open(&self, connection_id: u64, channel_id: u16) -> bool {
+        self.channel_info.contains_key(&(connection_id, channel_id))
+    }
+
+    pub fn remove_channel(&self, connection_id: u64, channel_id: u16) -> Option<AmqpChannel> {
+        self.channel_info
+            .remove(&(connection_id, channel_id))
+            .map(|(_, channel)| channel)
+    }
+
+    pub fn connection_channels(&self, connection_id: u64) -> Vec<u16> {
+        self.channel_info
+            .iter()
+            .filter(|raw| raw.key().0 == connection_id)
+            .map(|raw| raw.key().1)
+            .collect()
+    }
+
+    pub fn add_exchange(&self, exchange: AmqpExchange) {
+        self.exchange_info
+            .insert(exchange.exchange_name.clone(), exchange);
+    }
+
+    pub fn get_exchange(&self, exchange_name: &str) -> Option<AmqpExchange> {
+        self.exchange_info
+            .get(exchange_name)
+            .map(|exchange| exchange.clone())
+    }
+
+    pub fn remove_exchange(&self, exchange_name: &str) {
+        self.exchange_info.remove(exchange_name);
+        self.binding_info.remove(exchange_name);
+    }
+
+    pub fn add_queue(&self, queue: AmqpQueue) {
+        self.queue_info.insert(queue.queue_name.clone(), queue);
+    }
+
+    pub fn get_queue(&self, queue_name: &str) -> Option<AmqpQueue> {
+        self.queue_info.get(queue_name).map(|queue| queue.clone())
+    }
+
+    /// Removes the queue together with its bindings and consumers
+    pub fn remove_queue(&self, queue_name: &str) {
+        self.queue_info.remove(queue_name);
+        self.queue_owner.remove(queue_name);
+        self.queue_consumers.remove(queue_name);
+        for mut bindings in self.binding_info.iter_mut() {
+            bindings.retain(|binding| binding.queue_name != queue_name);
+        }
+    }
+
+    pub fn add_binding(&self, binding: AmqpBinding) {
+        self.binding_info
+            .entry(binding.exchange_name.clone())
+            .or_default()
+            .insert(binding);
+    }
+
+    pub fn remove_binding(&self, binding: &AmqpBinding) -> bool {
+        if let Some(mut bindings) = self.binding_info.get_mut(&binding.exchange_name) {
+            return bindings.remove(binding);
+        }
+        false
+    }
+
+    pub fn get_bindings(&self, exchange_name: &str) -> Vec<AmqpBinding> {
+        match self.binding_info.get(exchange_name) {
+            Some(bindings) => bindings.iter().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn get_queue_bindings(&self, queue_name: &str) -> Vec<AmqpBinding> {
+        let mut results = Vec::new();
+        for bindings in self.binding_info.iter() {
+            for binding in bindings.iter() {
+                if binding.queue_name == queue_name {
+                    results.push(binding.clone());
+                }
+            }
+        }
+        results
+    }
+
+    pub fn add_queue_consumer(&self, queue_name: &str, consumer: QueueConsumer) {
+        self.queue_consumers
+            .entry(queue_name.to_string())
+            .or_default()
+            .push(consumer);
+    }
+
+    pub fn remove_queue_consumer(
+        &self,
+        queue_name: &str,
+        connection_id: u64,
+        channel_id: u16,
+        consumer_tag: &str,
+    ) {
+        if let Some(mut consumers) = self.queue_consumers.get_mut(queue_name) {
+            consumers.retain(|consumer| {
+                !(consumer.connection_id == connection_id
+                    && consumer.channel_id == channel_id
+                    && consumer.consumer_tag == consumer_tag)
+            });
+        }
+    }
+
+    pub fn get_queue_consumers(&self, queue_name: &str) -> Vec<QueueConsumer> {
+        match self.queue_consumers.get(queue_name) {
+            Some(consumers) => consumers.clone(),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AmqpChannel, UnackedDelivery};
+
+    fn channel_with_unacked(tags: &[u64]) -> AmqpChannel {
+        let mut channel = AmqpChannel::default();
+        for tag in tags {
+            channel.unacked.insert(
+                *tag,
+                UnackedDelivery {
+                    queue_name: "q1".to_string(),
+                    offset: *tag,
+                },
+            );
+        }
+        channel
+    }
+
+    #[test]
+    fn take_unacked_test() {
+        let mut channel = channel_with_unacked(&[1, 2, 3, 4]);
+        assert_eq!(channel.take_unacked(2, false).len(), 1);
+        assert_eq!(channel.take_unacked(2, false).len(), 0);
+
+        let settled = channel.take_unacked(3, true);
+        assert_eq!(settled.len(), 2);
+        assert_eq!(channel.unacked.len(), 1);
+
+        let settled = channel.take_unacked(0, true);
+        assert_eq!(settled.len(), 1);
+        assert!(channel.unacked.is_empty());
+    }
+
+    #[test]
+    fn prefetch_capacity_test() {
+        let mut channel = channel_with_unacked(&[1, 2]);
+        assert!(channel.has_capacity());
+        channel.prefetch_count = 2;
+        assert!(!channel.has_capacity());
+        channel.take_unacked(1, false);
+        assert!(channel.has_capacity());
+    }
+}
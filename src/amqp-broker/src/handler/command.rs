@@ -0,0 +1,1456 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use bytes::BytesMut;
+use common_base::config::broker_amqp::broker_amqp_conf;
+use common_base::tools::{now_second, unique_id};
+use log::{error, warn};
+use metadata_struct::amqp::binding::AmqpBinding;
+use metadata_struct::amqp::exchange::{AmqpExchange, AmqpExchangeType};
+use metadata_struct::amqp::message::AmqpMessage;
+use metadata_struct::amqp::queue::AmqpQueue;
+use protocol::amqp::common::{AmqpFrame, ContentHeader, ProtocolVersion};
+use protocol::amqp::field::{FieldTable, FieldValue};
+use protocol::amqp::method::basic::{
+    BasicAck, BasicCancel, BasicConsume, BasicGet, BasicGetOk, BasicMethod, BasicNack,
+    BasicPublish, BasicReturn,
+};
+use protocol::amqp::method::channel::{ChannelClose, ChannelMethod};
+use protocol::amqp::method::confirm::ConfirmMethod;
+use protocol::amqp::method::connection::{
+    ConnectionClose, ConnectionMethod, ConnectionStart, ConnectionStartOk, ConnectionTune,
+};
+use protocol::amqp::method::exchange::{ExchangeDeclare, ExchangeDelete, ExchangeMethod};
+use protocol::amqp::method::queue::{
+    QueueBind, QueueDeclare, QueueDeclareOk, QueueDelete, QueueMethod, QueueUnbind,
+};
+use protocol::amqp::method::AmqpMethod;
+use storage_adapter::storage::StorageAdapter;
+
+use super::cache::{
+    is_reserved_exchange, AmqpCacheManager, ChannelConsumer, PendingPublish, QueueConsumer,
+    UnackedDelivery, DEFAULT_EXCHANGE,
+};
+use super::content::{build_content_frames, encode_content_header, parse_plain_response};
+use super::error::{AmqpBrokerError, REPLY_NO_ROUTE};
+use super::exchange::route_message;
+use super::queue::QueueManager;
+use crate::storage::keys::queue_shard_name;
+use crate::storage::metadata::AmqpMetadataStorage;
+
+static CONSUMER_TAG_BUILD: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Default)]
+pub struct CommandResponse {
+    pub frames: Vec<AmqpFrame>,
+    // Close the network connection once the frames have been written
+    pub close: bool,
+}
+
+impl CommandResponse {
+    fn frames(frames: Vec<AmqpFrame>) -> Self {
+        CommandResponse {
+            frames,
+            close: false,
+        }
+    }
+
+    fn close(frames: Vec<AmqpFrame>) -> Self {
+        CommandResponse {
+            frames,
+            close: true,
+        }
+    }
+}
+
+pub struct AmqpCommand<S> {
+    cache_manager: Arc<AmqpCacheManager>,
+    queue_manager: Arc<QueueManager<S>>,
+    metadata_storage: Arc<AmqpMetadataStorage>,
+}
+
+impl<S> AmqpCommand<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        cache_manager: Arc<AmqpCacheManager>,
+        queue_manager: Arc<QueueManager<S>>,
+        metadata_storage: Arc<AmqpMetadataStorage>,
+    ) -> Self {
+        AmqpCommand {
+            cache_manager,
+            queue_manager,
+            metadata_storage,
+        }
+    }
+
+    pub async fn apply(&self, connection_id: u64, frame: AmqpFrame) -> CommandResponse {
+        let channel_id = frame.channel();
+        let (class_id, method_id) = match &frame {
+            AmqpFrame::Method(_, method) => (method.class_id(), method.method_id()),
+            _ => (0, 0),
+        };
+
+        let result = match frame {
+            AmqpFrame::ProtocolHeader(version) => return self.protocol_header(version),
+            AmqpFrame::Heartbeat => Ok(Vec::new()),
+            AmqpFrame::Method(0, AmqpMethod::Connection(method)) => {
+                return self.connection_method(connection_id, method);
+            }
+            AmqpFrame::Method(channel_id, method) => {
+                self.channel_method(connection_id, channel_id, method).await
+            }
+            AmqpFrame::Header(channel_id, header) => {
+                self.content_header(connection_id, channel_id, header).await
+            }
+            AmqpFrame::Body(channel_id, body) => {
+                self.content_body(connection_id, channel_id, &body).await
+            }
+        };
+
+        match result {
+            Ok(frames) => CommandResponse::frames(frames),
+            Err(e) => {
+                self.error_response(connection_id, channel_id, class_id, method_id, e)
+                    .await
+            }
+        }
+    }
+
+    /// Releases every resource held by a connection that is gone
+    pub async fn connection_lost(&self, connection_id: u64) {
+        for channel_id in self.cache_manager.connection_channels(connection_id) {
+            self.close_channel(connection_id, channel_id).await;
+        }
+
+        let exclusive_queues: Vec<String> = self
+            .cache_manager
+            .queue_owner
+            .iter()
+            .filter(|raw| *raw.value() == connection_id)
+            .map(|raw| raw.key().clone())
+            .collect();
+        for queue_name in exclusive_queues {
+            if let Err(e) = self.delete_queue(&queue_name).await {
+                error!(
+                    "Failed to delete exclusive queue {}, error message: {}",
+                    queue_name, e
+                );
+            }
+        }
+        self.cache_manager.remove_connection(connection_id);
+    }
+
+    fn protocol_header(&self, version: ProtocolVersion) -> CommandResponse {
+        if !version.is_supported() {
+            // The server answers with the version it supports and closes the socket
+            return CommandResponse::close(vec![AmqpFrame::ProtocolHeader(
+                ProtocolVersion::amqp_0_9_1(),
+            )]);
+        }
+
+        let mut server_properties = FieldTable::new();
+        server_properties.insert(
+            "product".to_string(),
+            FieldValue::LongString("RobustMQ".into()),
+        );
+        server_properties.insert(
+            "version".to_string(),
+            FieldValue::LongString(env!("CARGO_PKG_VERSION").into()),
+        );
+        let mut capabilities = FieldTable::new();
+        for name in ["publisher_confirms", "basic.nack", "consumer_cancel_notify"] {
+            capabilities.insert(name.to_string(), FieldValue::Boolean(true));
+        }
+        server_properties.insert(
+            "capabilities".to_string(),
+            FieldValue::FieldTable(capabilities),
+        );
+
+        CommandResponse::frames(vec![AmqpFrame::Method(
+            0,
+            AmqpMethod::Connection(ConnectionMethod::Start(ConnectionStart {
+                version_major: 0,
+                version_minor: 9,
+                server_properties,
+                mechanisms: "PLAIN".to_string(),
+                locales: "en_US".to_string(),
+            })),
+        )])
+    }
+
+    fn connection_method(&self, connection_id: u64, method: ConnectionMethod) -> CommandResponse {
+        let mut connection = match self.cache_manager.get_connection(connection_id) {
+            Some(connection) => connection,
+            None => return CommandResponse::close(Vec::new()),
+        };
+
+        match method {
+            ConnectionMethod::StartOk(start_ok) => match self.login(&start_ok) {
+                Ok(user) => {
+                    connection.login_user = user;
+                    let tune = ConnectionTune {
+                        channel_max: connection.channel_max,
+                        frame_max: connection.frame_max,
+                        heartbeat: connection.heartbeat,
+                    };
+                    self.cache_manager.add_connection(connection);
+                    CommandResponse::frames(vec![AmqpFrame::Method(
+                        0,
+                        AmqpMethod::Connection(ConnectionMethod::Tune(tune)),
+                    )])
+                }
+                Err(e) => CommandResponse::close(vec![connection_close_frame(&e, 10, 11)]),
+            },
+
+            ConnectionMethod::TuneOk(tune) => {
+                connection.channel_max = negotiate(connection.channel_max, tune.channel_max);
+                connection.frame_max = negotiate(connection.frame_max, tune.frame_max);
+                connection.heartbeat = tune.heartbeat;
+                self.cache_manager.add_connection(connection);
+                CommandResponse::default()
+            }
+
+            ConnectionMethod::Open(_) => {
+                if connection.login_user.is_empty() {
+                    let e = AmqpBrokerError::AccessRefused("login required".to_string());
+                    return CommandResponse::close(vec![connection_close_frame(&e, 10, 40)]);
+                }
+                // Virtual hosts are not supported, every name maps to the same broker
+                connection.is_open = true;
+                self.cache_manager.add_connection(connection);
+                CommandResponse::frames(vec![AmqpFrame::Method(
+                    0,
+                    AmqpMethod::Connection(ConnectionMethod::OpenOk),
+                )])
+            }
+
+            ConnectionMethod::Close(_) => CommandResponse::close(vec![AmqpFrame::Method(
+                0,
+                AmqpMethod::Connection(ConnectionMethod::CloseOk),
+            )]),
+
+            ConnectionMethod::CloseOk => CommandResponse::close(Vec::new()),
+
+            other => {
+                let e = AmqpBrokerError::UnexpectedFrame(format!(
+                    "connection method {} is not expected from a client",
+                    other.method_id()
+                ));
+                CommandResponse::close(vec![connection_close_frame(&e, 10, other.method_id())])
+            }
+        }
+    }
+
+    fn login(&self, start_ok: &ConnectionStartOk) -> Result<String, AmqpBrokerError> {
+        if start_ok.mechanism != "PLAIN" {
+            return Err(AmqpBrokerError::AccessRefused(format!(
+                "unsupported authentication mechanism {}",
+                start_ok.mechanism
+            )));
+        }
+        let conf = broker_amqp_conf();
+        match parse_plain_response(&start_ok.response) {
+            Some((username, password))
+                if username == conf.system.default_user
+                    && password == conf.system.default_password =>
+            {
+                Ok(username)
+            }
+            _ => Err(AmqpBrokerError::AccessRefused(
+                "login was refused using authentication mechanism PLAIN".to_string(),
+            )),
+        }
+    }
+
+    async fn channel_method(
+        &self,
+        connection_id: u64,
+        channel_id: u16,
+        method: AmqpMethod,
+    ) -> Result<Vec<AmqpFrame>, AmqpBrokerError> {
+        match self.cache_manager.get_connection(connection_id) {
+            Some(connection) if connection.is_open => {
+                if channel_id > connection.channel_max && connection.channel_max > 0 {
+                    return Err(AmqpBrokerError::ChannelNotOpen(channel_id));
+                }
+            }
+            _ => {
+                return Err(AmqpBrokerError::CommandInvalid(
+                    "connection is not open".to_string(),
+                ))
+            }
+        }
+
+        if let AmqpMethod::Channel(ChannelMethod::Open) = method {
+            if !self.cache_manager.open_channel(connection_id, channel_id) {
+                return Err(AmqpBrokerError::ChannelAlreadyOpen(channel_id));
+            }
+            return Ok(vec![AmqpFrame::Method(
+                channel_id,
+                AmqpMethod::Channel(ChannelMethod::OpenOk),
+            )]);
+        }
+
+        let (closing, publishing) = match self
+            .cache_manager
+            .channel_info
+            .get(&(connection_id, channel_id))
+        {
+            Some(channel) => (channel.closing, channel.pending_publish.is_some()),
+            None => return Err(AmqpBrokerError::ChannelNotOpen(channel_id)),
+        };
+
+        // After channel.close only close and close-ok are meaningful on the channel
+        if closing {
+            match method {
+                AmqpMethod::Channel(ChannelMethod::CloseOk) => {
+                    self.cache_manager.remove_channel(connection_id, channel_id);
+                }
+                AmqpMethod::Channel(ChannelMethod::Close(_)) => {
+                    self.cache_manager.remove_channel(connection_id, channel_id);
+                    return Ok(vec![AmqpFrame::Method(
+                        channel_id,
+                        AmqpMethod::Channel(ChannelMethod::CloseOk),
+                    )]);
+                }
+                _ => {}
+            }
+            return Ok(Vec::new());
+        }
+
+        if publishing {
+            return Err(AmqpBrokerError::UnexpectedFrame(
+                "expected content header or body".to_string(),
+            ));
+        }
+
+        let frames = match method {
+            AmqpMethod::Channel(method) => self.channel(connection_id, channel_id, method).await?,
+            AmqpMethod::Exchange(method) => self.exchange(channel_id, method).await?,
+            AmqpMethod::Queue(method) => self.queue(connection_id, channel_id, method).await?,
+            AmqpMethod::Basic(method) => self.basic(connection_id, channel_id, method).await?,
+            AmqpMethod::Confirm(ConfirmMethod::Select { no_wait }) => {
+                if let Some(mut channel) = self
+                    .cache_manager
+                    .channel_info
+                    .get_mut(&(connection_id, channel_id))
+                {
+                    channel.confirm_mode = true;
+                }
+                reply(
+                    channel_id,
+                    no_wait,
+                    AmqpMethod::Confirm(ConfirmMethod::SelectOk),
+                )
+            }
+            AmqpMethod::Tx(_) => {
+                return Err(AmqpBrokerError::NotImplemented(
+                    "transactions are not supported".to_string(),
+                ))
+            }
+            other => {
+                return Err(AmqpBrokerError::CommandInvalid(format!(
+                    "method {}.{} is not expected from a client",
+                    other.class_id(),
+                    other.method_id()
+                )))
+            }
+        };
+        Ok(frames)
+    }
+
+    async fn channel(
+        &self,
+        connection_id: u64,
+        channel_id: u16,
+        method: ChannelMethod,
+    ) -> Result<Vec<AmqpFrame>, AmqpBrokerError> {
+        match method {
+            ChannelMethod::Flow(flow) => Ok(vec![AmqpFrame::Method(
+                channel_id,
+                AmqpMethod::Channel(ChannelMethod::FlowOk(flow)),
+            )]),
+            ChannelMethod::Close(_) => {
+                self.close_channel(connection_id, channel_id).await;
+                Ok(vec![AmqpFrame::Method(
+                    channel_id,
+                    AmqpMethod::Channel(ChannelMethod::CloseOk),
+                )])
+            }
+            // Sent by a client that lost track of a close it already answered
+            ChannelMethod::CloseOk | ChannelMethod::FlowOk(_) => Ok(Vec::new()),
+            other => Err(AmqpBrokerError::CommandInvalid(format!(
+                "channel method {} is not expected from a client",
+                other.method_id()
+            ))),
+        }
+    }
+
+    async fn exchange(
+        &self,
+        channel_id: u16,
+        method: ExchangeMethod,
+    ) -> Result<Vec<AmqpFrame>, AmqpBrokerError> {
+        match method {
+            ExchangeMethod::Declare(declare) => {
+                let no_wait = declare.no_wait;
+                self.declare_exchange(declare).await?;
+                Ok(reply(
+                    channel_id,
+                    no_wait,
+                    AmqpMethod::Exchange(ExchangeMethod::DeclareOk),
+                ))
+            }
+            ExchangeMethod::Delete(delete) => {
+                let no_wait = delete.no_wait;
+                self.delete_exchange(delete).await?;
+                Ok(reply(
+                    channel_id,
+                    no_wait,
+                    AmqpMethod::Exchange(ExchangeMethod::DeleteOk),
+                ))
+            }
+            ExchangeMethod::Bind(_) | ExchangeMethod::Unbind(_) => Err(
+                AmqpBrokerError::NotImplemented("exchange to exchange bindings".to_string()),
+            ),
+            other => Err(AmqpBrokerError::CommandInvalid(format!(
+                "exchange method {} is not expected from a client",
+                other.method_id()
+            ))),
+        }
+    }
+
+    async fn declare_exchange(&self, declare: ExchangeDeclare) -> Result<(), AmqpBrokerError> {
+        let existing = self.cache_manager.get_exchange(&declare.exchange);
+        if declare.passive {
+            if existing.is_none() {
+                return Err(AmqpBrokerError::ExchangeNotFound(declare.exchange));
+            }
+            return Ok(());
+        }
+
+        let exchange_type: AmqpExchangeType = declare.kind.parse().map_err(|_| {
+            AmqpBrokerError::CommandInvalid(format!("unknown exchange type '{}'", declare.kind))
+        })?;
+
+        if let Some(exchange) = existing {
+            if exchange.exchange_type != exchange_type || exchange.durable != declare.durable {
+                return Err(AmqpBrokerError::PreconditionFailed(format!(
+                    "inequivalent arguments for exchange '{}'",
+                    declare.exchange
+                )));
+            }
+            return Ok(());
+        }
+
+        if is_reserved_exchange(&declare.exchange) {
+            return Err(AmqpBrokerError::AccessRefused(format!(
+                "exchange name '{}' contains reserved prefix 'amq.'",
+                declare.exchange
+            )));
+        }
+
+        let conf = broker_amqp_conf();
+        let exchange = AmqpExchange {
+            cluster_name: conf.cluster_name.clone(),
+            exchange_name: declare.exchange,
+            exchange_type,
+            durable: declare.durable,
+            auto_delete: declare.auto_delete,
+            internal: declare.internal,
+        };
+        self.cache_manager.add_exchange(exchange.clone());
+        if exchange.durable {
+            self.metadata_storage.save_exchange(&exchange).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete_exchange(&self, delete: ExchangeDelete) -> Result<(), AmqpBrokerError> {
+        if is_reserved_exchange(&delete.exchange) {
+            return Err(AmqpBrokerError::AccessRefused(format!(
+                "cannot delete predefined exchange '{}'",
+                delete.exchange
+            )));
+        }
+        let exchange = match self.cache_manager.get_exchange(&delete.exchange) {
+            Some(exchange) => exchange,
+            None => return Err(AmqpBrokerError::ExchangeNotFound(delete.exchange)),
+        };
+        let bindings = self.cache_manager.get_bindings(&delete.exchange);
+        if delete.if_unused && !bindings.is_empty() {
+            return Err(AmqpBrokerError::PreconditionFailed(format!(
+                "exchange '{}' in use",
+                delete.exchange
+            )));
+        }
+
+        self.cache_manager.remove_exchange(&delete.exchange);
+        if exchange.durable {
+            for binding in bindings.iter() {
+                if self.is_durable_queue(&binding.queue_name) {
+                    self.metadata_storage.delete_binding(binding).await?;
+                }
+            }
+            self.metadata_storage
+                .delete_exchange(&exchange.exchange_name)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn queue(
+        &self,
+        connection_id: u64,
+        channel_id: u16,
+        method: QueueMethod,
+    ) -> Result<Vec<AmqpFrame>, AmqpBrokerError> {
+        match method {
+            QueueMethod::Declare(declare) => {
+                let no_wait = declare.no_wait;
+                let declare_ok = self.declare_queue(connection_id, declare).await?;
+                Ok(reply(
+                    channel_id,
+                    no_wait,
+                    AmqpMethod::Queue(QueueMethod::DeclareOk(declare_ok)),
+                ))
+            }
+            QueueMethod::Bind(bind) => {
+                let no_wait = bind.no_wait;
+                self.bind_queue(connection_id, bind).await?;
+                Ok(reply(
+                    channel_id,
+                    no_wait,
+                    AmqpMethod::Queue(QueueMethod::BindOk),
+                ))
+            }
+            QueueMethod::Unbind(unbind) => {
+                self.unbind_queue(connection_id, unbind).await?;
+                Ok(reply(
+                    channel_id,
+                    false,
+                    AmqpMethod::Queue(QueueMethod::UnbindOk),
+                ))
+            }
+            QueueMethod::Purge(purge) => {
+                self.check_queue_access(connection_id, &purge.queue)?;
+                let count = self.queue_manager.purge(&purge.queue).await?;
+                Ok(reply(
+                    channel_id,
+                    purge.no_wait,
+                    AmqpMethod::Queue(QueueMethod::PurgeOk(count)),
+                ))
+            }
+            QueueMethod::Delete(delete) => {
+                let no_wait = delete.no_wait;
+                let count = self.queue_delete(connection_id, delete).await?;
+                Ok(reply(
+                    channel_id,
+                    no_wait,
+                    AmqpMethod::Queue(QueueMethod::DeleteOk(count)),
+                ))
+            }
+            other => Err(AmqpBrokerError::CommandInvalid(format!(
+                "queue method {} is not expected from a client",
+                other.method_id()
+            ))),
+        }
+    }
+
+    async fn declare_queue(
+        &self,
+        connection_id: u64,
+        declare: QueueDeclare,
+    ) -> Result<QueueDeclareOk, AmqpBrokerError> {
+        let queue_name = if declare.queue.is_empty() {
+            format!("amq.gen-{}", unique_id())
+        } else {
+            declare.queue.clone()
+        };
+
+        if self.cache_manager.get_queue(&queue_name).is_some() {
+            self.check_queue_access(connection_id, &queue_name)?;
+            return Ok(self.declare_ok(queue_name));
+        }
+
+        if declare.passive {
+            return Err(AmqpBrokerError::QueueNotFound(queue_name));
+        }
+        if queue_name.starts_with("amq.") && !declare.queue.is_empty() {
+            return Err(AmqpBrokerError::AccessRefused(format!(
+                "queue name '{}' contains reserved prefix 'amq.'",
+                queue_name
+            )));
+        }
+
+        let conf = broker_amqp_conf();
+        let queue = AmqpQueue {
+            cluster_name: conf.cluster_name.clone(),
+            queue_name: queue_name.clone(),
+            shard_name: queue_shard_name(&queue_name),
+            // Exclusive queues die with their connection, so they are never durable
+            durable: declare.durable && !declare.exclusive,
+            exclusive: declare.exclusive,
+            auto_delete: declare.auto_delete,
+            create_time: now_second(),
+        };
+        self.queue_manager.create_queue_shard(&queue_name).await?;
+        self.cache_manager.add_queue(queue.clone());
+        if declare.exclusive {
+            self.cache_manager
+                .queue_owner
+                .insert(queue_name.clone(), connection_id);
+        }
+        if queue.durable {
+            self.metadata_storage.save_queue(&queue).await?;
+        }
+        Ok(self.declare_ok(queue_name))
+    }
+
+    fn declare_ok(&self, queue_name: String) -> QueueDeclareOk {
+        let consumer_count = self.cache_manager.get_queue_consumers(&queue_name).len() as u32;
+        QueueDeclareOk {
+            queue: queue_name,
+            // Ready messages are not counted, the shard only exposes offsets
+            message_count: 0,
+            consumer_count,
+        }
+    }
+
+    fn check_queue_access(
+        &self,
+        connection_id: u64,
+        queue_name: &str,
+    ) -> Result<(), AmqpBrokerError> {
+        if self.cache_manager.get_queue(queue_name).is_none() {
+            return Err(AmqpBrokerError::QueueNotFound(queue_name.to_string()));
+        }
+        if let Some(owner) = self.cache_manager.queue_owner.get(queue_name) {
+            if *owner != connection_id {
+                return Err(AmqpBrokerError::QueueLocked(queue_name.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    async fn bind_queue(&self, connection_id: u64, bind: QueueBind) -> Result<(), AmqpBrokerError> {
+        self.check_queue_access(connection_id, &bind.queue)?;
+        let exchange = match self.cache_manager.get_exchange(&bind.exchange) {
+            Some(exchange) => exchange,
+            None => return Err(AmqpBrokerError::ExchangeNotFound(bind.exchange)),
+        };
+        if exchange.exchange_name == DEFAULT_EXCHANGE {
+            return Err(AmqpBrokerError::AccessRefused(
+                "operation not permitted on the default exchange".to_string(),
+            ));
+        }
+
+        let binding = AmqpBinding {
+            exchange_name: bind.exchange,
+            queue_name: bind.queue.clone(),
+            routing_key: bind.routing_key,
+        };
+        self.cache_manager.add_binding(binding.clone());
+        if self.is_durable_binding(&exchange, &bind.queue) {
+            self.metadata_storage.save_binding(&binding).await?;
+        }
+        Ok(())
+    }
+
+    async fn unbind_queue(
+        &self,
+        connection_id: u64,
+        unbind: QueueUnbind,
+    ) -> Result<(), AmqpBrokerError> {
+        self.check_queue_access(connection_id, &unbind.queue)?;
+        let exchange = match self.cache_manager.get_exchange(&unbind.exchange) {
+            Some(exchange) => exchange,
+            None => return Err(AmqpBrokerError::ExchangeNotFound(unbind.exchange)),
+        };
+
+        let binding = AmqpBinding {
+            exchange_name: unbind.exchange,
+            queue_name: unbind.queue.clone(),
+            routing_key: unbind.routing_key,
+        };
+        if self.cache_manager.remove_binding(&binding)
+            && self.is_durable_binding(&exchange, &unbind.queue)
+        {
+            self.metadata_storage.delete_binding(&binding).await?;
+        }
+        Ok(())
+    }
+
+    fn is_durable_binding(&self, exchange: &AmqpExchange, queue_name: &str) -> bool {
+        exchange.durable && self.is_durable_queue(queue_name)
+    }
+
+    fn is_durable_queue(&self, queue_name: &str) -> bool {
+        self.cache_manager
+            .get_queue(queue_name)
+            .map(|queue| queue.durable)
+            .unwrap_or(false)
+    }
+
+    fn is_durable_exchange(&self, exchange_name: &str) -> bool {
+        self.cache_manager
+            .get_exchange(exchange_name)
+            .map(|exchange| exchange.durable)
+            .unwrap_or(false)
+    }
+
+    async fn queue_delete(
+        &self,
+        connection_id: u64,
+        delete: QueueDelete,
+    ) -> Result<u32, AmqpBrokerError> {
+        if self.cache_manager.get_queue(&delete.queue).is_none() {
+            // Deleting a queue that does not exist is not an error
+            return Ok(0);
+        }
+        self.check_queue_access(connection_id, &delete.queue)?;
+
+        if delete.if_unused
+            && !self
+                .cache_manager
+                .get_queue_consumers(&delete.queue)
+                .is_empty()
+        {
+            return Err(AmqpBrokerError::PreconditionFailed(format!(
+                "queue '{}' in use",
+                delete.queue
+            )));
+        }
+        if delete.if_empty && !self.is_queue_empty(&delete.queue).await? {
+            return Err(AmqpBrokerError::PreconditionFailed(format!(
+                "queue '{}' not empty",
+                delete.queue
+            )));
+        }
+        self.delete_queue(&delete.queue).await
+    }
+
+    async fn is_queue_empty(&self, queue_name: &str) -> Result<bool, AmqpBrokerError> {
+        let state = match self.queue_manager.get_state(queue_name) {
+            Some(state) => state,
+            None => return Ok(true),
+        };
+        if !state.unacked.is_empty() || !state.requeued.is_empty() {
+            return Ok(false);
+        }
+        let records = self
+            .queue_manager
+            .read_message(queue_name, state.next_offset, 1)
+            .await?;
+        Ok(records.is_empty())
+    }
+
+    async fn delete_queue(&self, queue_name: &str) -> Result<u32, AmqpBrokerError> {
+        let queue = match self.cache_manager.get_queue(queue_name) {
+            Some(queue) => queue,
+            None => return Ok(0),
+        };
+        let count = self.queue_manager.purge(queue_name).await?;
+
+        // Consumers still attached to the queue are cancelled by the broker
+        for consumer in self.cache_manager.get_queue_consumers(queue_name) {
+            if let Some(mut channel) = self
+                .cache_manager
+                .channel_info
+                .get_mut(&(consumer.connection_id, consumer.channel_id))
+            {
+                channel.consumers.remove(&consumer.consumer_tag);
+            }
+            if let Some(connection) = self.cache_manager.get_connection(consumer.connection_id) {
+                let cancel = AmqpFrame::Method(
+                    consumer.channel_id,
+                    AmqpMethod::Basic(BasicMethod::Cancel(BasicCancel {
+                        consumer_tag: consumer.consumer_tag.clone(),
+                        no_wait: true,
+                    })),
+                );
+                if connection.write_sx.send(cancel).await.is_err() {
+                    warn!(
+                        "Failed to notify consumer {} of the deletion of queue {}",
+                        consumer.consumer_tag, queue_name
+                    );
+                }
+            }
+        }
+
+        let bindings = self.cache_manager.get_queue_bindings(queue_name);
+        if queue.durable {
+            for binding in bindings.iter() {
+                if self.is_durable_exchange(&binding.exchange_name) {
+                    self.metadata_storage.delete_binding(binding).await?;
+                }
+            }
+        }
+        self.cache_manager.remove_queue(queue_name);
+        self.queue_manager.delete_queue_shard(queue_name).await?;
+        if queue.durable {
+            self.metadata_storage.delete_queue(queue_name).await?;
+        }
+        Ok(count)
+    }
+
+    async fn basic(
+        &self,
+        connection_id: u64,
+        channel_id: u16,
+        method: BasicMethod,
+    ) -> Result<Vec<AmqpFrame>, AmqpBrokerError> {
+        let key = (connection_id, channel_id);
+        match method {
+            BasicMethod::Qos(qos) => {
+                if let Some(mut channel) = self.cache_manager.channel_info.get_mut(&key) {
+                    channel.prefetch_count = qos.prefetch_count;
+                }
+                self.queue_manager.notify.notify_one();
+                Ok(reply(
+                    channel_id,
+                    false,
+                    AmqpMethod::Basic(BasicMethod::QosOk),
+                ))
+            }
+
+            BasicMethod::Consume(consume) => {
+                self.consume(connection_id, channel_id, consume).await?;
+                Ok(Vec::new())
+            }
+
+            BasicMethod::Cancel(cancel) => {
+                let consumer = match self.cache_manager.channel_info.get_mut(&key) {
+                    Some(mut channel) => channel.consumers.remove(&cancel.consumer_tag),
+                    None => None,
+                };
+                if let Some(consumer) = consumer {
+                    self.remove_consumer(
+                        connection_id,
+                        channel_id,
+                        &cancel.consumer_tag,
+                        &consumer.queue_name,
+                    )
+                    .await;
+                }
+                Ok(reply(
+                    channel_id,
+                    cancel.no_wait,
+                    AmqpMethod::Basic(BasicMethod::CancelOk(cancel.consumer_tag)),
+                ))
+            }
+
+            BasicMethod::Publish(publish) => {
+                if let Some(mut channel) = self.cache_manager.channel_info.get_mut(&key) {
+                    channel.pending_publish = Some(PendingPublish {
+                        publish,
+                        header: None,
+                        body: BytesMut::new(),
+                    });
+                }
+                Ok(Vec::new())
+            }
+
+            BasicMethod::Get(get) => self.get(connection_id, channel_id, get).await,
+
+            BasicMethod::Ack(ack) => {
+                let settled = self.take_unacked(key, ack.delivery_tag, ack.multiple)?;
+                self.settle(settled, false).await?;
+                Ok(Vec::new())
+            }
+
+            BasicMethod::Nack(nack) => {
+                let settled = self.take_unacked(key, nack.delivery_tag, nack.multiple)?;
+                self.settle(settled, nack.requeue).await?;
+                Ok(Vec::new())
+            }
+
+            BasicMethod::Reject(reject) => {
+                let settled = self.take_unacked(key, reject.delivery_tag, false)?;
+                self.settle(settled, reject.requeue).await?;
+                Ok(Vec::new())
+            }
+
+            // Redelivery to the original recipient is not supported, so recover
+            // always hands the outstanding deliveries back to their queues
+            BasicMethod::Recover(_) => {
+                let settled = self.take_unacked(key, 0, true)?;
+                self.settle(settled, true).await?;
+                Ok(reply(
+                    channel_id,
+                    false,
+                    AmqpMethod::Basic(BasicMethod::RecoverOk),
+                ))
+            }
+
+            BasicMethod::RecoverAsync(_) => {
+                let settled = self.take_unacked(key, 0, true)?;
+                self.settle(settled, true).await?;
+                Ok(Vec::new())
+            }
+
+            other => Err(AmqpBrokerError::CommandInvalid(format!(
+                "basic method {} is not expected from a client",
+                other.method_id()
+            ))),
+        }
+    }
+
+    async fn consume(
+        &self,
+        connection_id: u64,
+        channel_id: u16,
+        consume: BasicConsume,
+    ) -> Result<(), AmqpBrokerError> {
+        self.check_queue_access(connection_id, &consume.queue)?;
+        if consume.exclusive
+            && !self
+                .cache_manager
+                .get_queue_consumers(&consume.queue)
+                .is_empty()
+        {
+            return Err(AmqpBrokerError::AccessRefused(format!(
+                "queue '{}' in exclusive use",
+                consume.queue
+            )));
+        }
+
+        let consumer_tag = if consume.consumer_tag.is_empty() {
+            format!(
+                "amq.ctag-{}-{}",
+                connection_id,
+                CONSUMER_TAG_BUILD.fetch_add(1, Ordering::Relaxed)
+            )
+        } else {
+            consume.consumer_tag
+        };
+
+        match self
+            .cache_manager
+            .channel_info
+            .get_mut(&(connection_id, channel_id))
+        {
+            Some(mut channel) => {
+                if channel.consumers.contains_key(&consumer_tag) {
+                    return Err(AmqpBrokerError::NotAllowed(format!(
+                        "attempt to reuse consumer tag '{}'",
+                        consumer_tag
+                    )));
+                }
+                channel.consumers.insert(
+                    consumer_tag.clone(),
+                    ChannelConsumer {
+                        queue_name: consume.queue.clone(),
+                        no_ack: consume.no_ack,
+                    },
+                );
+            }
+            None => return Err(AmqpBrokerError::ChannelNotOpen(channel_id)),
+        }
+
+        // consume-ok has to reach the client before the first delivery, so it is
+        // written before the consumer becomes visible to the dispatcher
+        if !consume.no_wait {
+            if let Some(connection) = self.cache_manager.get_connection(connection_id) {
+                let frame = AmqpFrame::Method(
+                    channel_id,
+                    AmqpMethod::Basic(BasicMethod::ConsumeOk(consumer_tag.clone())),
+                );
+                if connection.write_sx.send(frame).await.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+
+        self.cache_manager.add_queue_consumer(
+            &consume.queue,
+            QueueConsumer {
+                connection_id,
+                channel_id,
+                consumer_tag,
+                no_ack: consume.no_ack,
+            },
+        );
+        self.queue_manager.notify.notify_one();
+        Ok(())
+    }
+
+    async fn remove_consumer(
+        &self,
+        connection_id: u64,
+        channel_id: u16,
+        consumer_tag: &str,
+        queue_name: &str,
+    ) {
+        self.cache_manager.remove_queue_consumer(
+            queue_name,
+            connection_id,
+            channel_id,
+            consumer_tag,
+        );
+
+        // An auto-delete queue goes away with its last consumer
+        let auto_delete = self
+            .cache_manager
+            .get_queue(queue_name)
+            .map(|queue| queue.auto_delete)
+            .unwrap_or(false);
+        if auto_delete
+            && self
+                .cache_manager
+                .get_queue_consumers(queue_name)
+                .is_empty()
+        {
+            if let Err(e) = self.delete_queue(queue_name).await {
+                error!(
+                    "Failed to delete auto-delete queue {}, error message: {}",
+                    queue_name, e
+                );
+            }
+        }
+    }
+
+    async fn get(
+        &self,
+        connection_id: u64,
+        channel_id: u16,
+        get: BasicGet,
+    ) -> Result<Vec<AmqpFrame>, AmqpBrokerError> {
+        self.check_queue_access(connection_id, &get.queue)?;
+        let (offset, redelivered, message) =
+            match self.queue_manager.next_message(&get.queue).await? {
+                Some(data) => data,
+                None => {
+                    return Ok(vec![AmqpFrame::Method(
+                        channel_id,
+                        AmqpMethod::Basic(BasicMethod::GetEmpty),
+                    )])
+                }
+            };
+
+        let delivery_tag = match self
+            .cache_manager
+            .channel_info
+            .get_mut(&(connection_id, channel_id))
+        {
+            Some(mut channel) => {
+                let delivery_tag = channel.next_delivery_tag();
+                if !get.no_ack {
+                    channel.unacked.insert(
+                        delivery_tag,
+                        UnackedDelivery {
+                            queue_name: get.queue.clone(),
+                            offset,
+                        },
+                    );
+                }
+                delivery_tag
+            }
+            None => return Err(AmqpBrokerError::ChannelNotOpen(channel_id)),
+        };
+        if get.no_ack {
+            self.queue_manager.ack(&get.queue, &[offset]).await?;
+        }
+
+        let frame_max = self.frame_max(connection_id);
+        let get_ok = AmqpMethod::Basic(BasicMethod::GetOk(BasicGetOk {
+            delivery_tag,
+            redelivered,
+            exchange: message.exchange.clone(),
+            routing_key: message.routing_key.clone(),
+            message_count: 0,
+        }));
+        build_content_frames(channel_id, get_ok, &message, frame_max)
+    }
+
+    fn take_unacked(
+        &self,
+        key: (u64, u16),
+        delivery_tag: u64,
+        multiple: bool,
+    ) -> Result<Vec<UnackedDelivery>, AmqpBrokerError> {
+        let mut channel = match self.cache_manager.channel_info.get_mut(&key) {
+            Some(channel) => channel,
+            None => return Err(AmqpBrokerError::ChannelNotOpen(key.1)),
+        };
+        let settled = channel.take_unacked(delivery_tag, multiple);
+        if settled.is_empty() && !(multiple && delivery_tag == 0) {
+            return Err(AmqpBrokerError::PreconditionFailed(format!(
+                "unknown delivery tag {}",
+                delivery_tag
+            )));
+        }
+        Ok(settled)
+    }
+
+    async fn settle(
+        &self,
+        settled: Vec<UnackedDelivery>,
+        requeue: bool,
+    ) -> Result<(), AmqpBrokerError> {
+        let mut offsets: HashMap<String, Vec<u64>> = HashMap::new();
+        for delivery in settled {
+            offsets
+                .entry(delivery.queue_name)
+                .or_default()
+                .push(delivery.offset);
+        }
+        for (queue_name, offsets) in offsets {
+            if requeue {
+                self.queue_manager.requeue(&queue_name, &offsets).await?;
+            } else {
+                self.queue_manager.ack(&queue_name, &offsets).await?;
+            }
+        }
+        // Settled deliveries free prefetch capacity
+        self.queue_manager.notify.notify_one();
+        Ok(())
+    }
+
+    async fn content_header(
+        &self,
+        connection_id: u64,
+        channel_id: u16,
+        header: ContentHeader,
+    ) -> Result<Vec<AmqpFrame>, AmqpBrokerError> {
+        let complete = {
+            let mut channel = match self
+                .cache_manager
+                .channel_info
+                .get_mut(&(connection_id, channel_id))
+            {
+                Some(channel) => channel,
+                None => return Err(AmqpBrokerError::ChannelNotOpen(channel_id)),
+            };
+            if channel.closing {
+                return Ok(Vec::new());
+            }
+            match channel.pending_publish.as_mut() {
+                Some(pending) if pending.header.is_none() => {
+                    let complete = header.body_size == 0;
+                    pending.header = Some(header);
+                    complete
+                }
+                _ => {
+                    return Err(AmqpBrokerError::UnexpectedFrame(
+                        "content header without basic.publish".to_string(),
+                    ))
+                }
+            }
+        };
+
+        if complete {
+            return self.publish(connection_id, channel_id).await;
+        }
+        Ok(Vec::new())
+    }
+
+    async fn content_body(
+        &self,
+        connection_id: u64,
+        channel_id: u16,
+        body: &[u8],
+    ) -> Result<Vec<AmqpFrame>, AmqpBrokerError> {
+        let complete = {
+            let mut channel = match self
+                .cache_manager
+                .channel_info
+                .get_mut(&(connection_id, channel_id))
+            {
+                Some(channel) => channel,
+                None => return Err(AmqpBrokerError::ChannelNotOpen(channel_id)),
+            };
+            if channel.closing {
+                return Ok(Vec::new());
+            }
+            match channel.pending_publish.as_mut() {
+                Some(PendingPublish {
+                    header: Some(header),
+                    body: pending_body,
+                    ..
+                }) => {
+                    pending_body.extend_from_slice(body);
+                    if pending_body.len() as u64 > header.body_size {
+                        return Err(AmqpBrokerError::UnexpectedFrame(
+                            "content body exceeds the size announced in the header".to_string(),
+                        ));
+                    }
+                    pending_body.len() as u64 == header.body_size
+                }
+                _ => {
+                    return Err(AmqpBrokerError::UnexpectedFrame(
+                        "content body without content header".to_string(),
+                    ))
+                }
+            }
+        };
+
+        if complete {
+            return self.publish(connection_id, channel_id).await;
+        }
+        Ok(Vec::new())
+    }
+
+    async fn publish(
+        &self,
+        connection_id: u64,
+        channel_id: u16,
+    ) -> Result<Vec<AmqpFrame>, AmqpBrokerError> {
+        let (pending, confirm_seq) = {
+            let mut channel = match self
+                .cache_manager
+                .channel_info
+                .get_mut(&(connection_id, channel_id))
+            {
+                Some(channel) => channel,
+                None => return Err(AmqpBrokerError::ChannelNotOpen(channel_id)),
+            };
+            let pending = match channel.pending_publish.take() {
+                Some(pending) => pending,
+                None => return Ok(Vec::new()),
+            };
+            let confirm_seq = if channel.confirm_mode {
+                channel.publish_seq += 1;
+                Some(channel.publish_seq)
+            } else {
+                None
+            };
+            (pending, confirm_seq)
+        };
+
+        let PendingPublish {
+            publish,
+            header,
+            body,
+        } = pending;
+        let header = match header {
+            Some(header) => header,
+            None => {
+                return Err(AmqpBrokerError::UnexpectedFrame(
+                    "content body without content header".to_string(),
+                ))
+            }
+        };
+
+        if let Some(exchange) = self.cache_manager.get_exchange(&publish.exchange) {
+            if exchange.internal {
+                return Err(AmqpBrokerError::AccessRefused(format!(
+                    "cannot publish to internal exchange '{}'",
+                    publish.exchange
+                )));
+            }
+        }
+        let queues = route_message(&self.cache_manager, &publish.exchange, &publish.routing_key)?;
+
+        let message = AmqpMessage::new(
+            publish.exchange.clone(),
+            publish.routing_key.clone(),
+            encode_content_header(&header)?,
+            body.to_vec(),
+        );
+
+        let mut frames = Vec::new();
+        if queues.is_empty() && publish.mandatory {
+            frames.extend(self.return_frames(connection_id, channel_id, &publish, &message)?);
+        }
+
+        let mut success = true;
+        for queue_name in queues.iter() {
+            if let Err(e) = self.queue_manager.write_message(queue_name, &message).await {
+                error!(
+                    "Failed to write message to queue {}, error message: {}",
+                    queue_name, e
+                );
+                success = false;
+            }
+        }
+
+        if let Some(delivery_tag) = confirm_seq {
+            let confirm = if success {
+                BasicMethod::Ack(BasicAck {
+                    delivery_tag,
+                    multiple: false,
+                })
+            } else {
+                BasicMethod::Nack(BasicNack {
+                    delivery_tag,
+                    multiple: false,
+                    requeue: false,
+                })
+            };
+            frames.push(AmqpFrame::Method(channel_id, AmqpMethod::Basic(confirm)));
+        }
+        Ok(frames)
+    }
+
+    fn return_frames(
+        &self,
+        connection_id: u64,
+        channel_id: u16,
+        publish: &BasicPublish,
+        message: &AmqpMessage,
+    ) -> Result<Vec<AmqpFrame>, AmqpBrokerError> {
+        let method = AmqpMethod::Basic(BasicMethod::Return(BasicReturn {
+            reply_code: REPLY_NO_ROUTE,
+            reply_text: "NO_ROUTE".to_string(),
+            exchange: publish.exchange.clone(),
+            routing_key: publish.routing_key.clone(),
+        }));
+        build_content_frames(channel_id, method, message, self.frame_max(connection_id))
+    }
+
+    fn frame_max(&self, connection_id: u64) -> u32 {
+        match self.cache_manager.get_connection(connection_id) {
+            Some(connection) => connection.frame_max,
+            None => broker_amqp_conf().network.frame_max,
+        }
+    }
+
+    /// Removes the consumers of the channel and hands its unacknowledged
+    /// deliveries back to their queues
+    async fn close_channel(&self, connection_id: u64, channel_id: u16) {
+        let channel = match self.cache_manager.remove_channel(connection_id, channel_id) {
+            Some(channel) => channel,
+            None => return,
+        };
+        self.release_channel(
+            connection_id,
+            channel_id,
+            channel.consumers,
+            channel.unacked.into_values().collect(),
+        )
+        .await;
+    }
+
+    async fn release_channel(
+        &self,
+        connection_id: u64,
+        channel_id: u16,
+        consumers: HashMap<String, ChannelConsumer>,
+        unacked: Vec<UnackedDelivery>,
+    ) {
+        if let Err(e) = self.settle(unacked, true).await {
+            error!(
+                "Failed to requeue the deliveries of channel {} on connection {}, error message: {}",
+                channel_id, connection_id, e
+            );
+        }
+        for (consumer_tag, consumer) in consumers {
+            self.remove_consumer(
+                connection_id,
+                channel_id,
+                &consumer_tag,
+                &consumer.queue_name,
+            )
+            .await;
+        }
+    }
+
+    async fn error_response(
+        &self,
+        connection_id: u64,
+        channel_id: u16,
+        class_id: u16,
+        method_id: u16,
+        e: AmqpBrokerError,
+    ) -> CommandResponse {
+        warn!(
+            "AMQP connection {} channel {} error: {}",
+            connection_id, channel_id, e
+        );
+
+        if channel_id == 0 || e.is_connection_error() {
+            return CommandResponse::close(vec![connection_close_frame(&e, class_id, method_id)]);
+        }
+
+        // The channel stays known until the client confirms the close
+        let released = match self
+            .cache_manager
+            .channel_info
+            .get_mut(&(connection_id, channel_id))
+        {
+            Some(mut channel) => {
+                channel.closing = true;
+                channel.pending_publish = None;
+                let consumers = std::mem::take(&mut channel.consumers);
+                let unacked = std::mem::take(&mut channel.unacked);
+                Some((consumers, unacked.into_values().collect()))
+            }
+            None => None,
+        };
+        if let Some((consumers, unacked)) = released {
+            self.release_channel(connection_id, channel_id, consumers, unacked)
+                .await;
+        }
+
+        CommandResponse::frames(vec![AmqpFrame::Method(
+            channel_id,
+            AmqpMethod::Channel(ChannelMethod::Close(ChannelClose {
+                reply_code: e.reply_code(),
+                reply_text: e.to_string(),
+                class_id,
+                method_id,
+            })),
+        )])
+    }
+}
+
+fn reply(channel_id: u16, no_wait: bool, method: AmqpMethod) -> Vec<AmqpFrame> {
+    if no_wait {
+        return Vec::new();
+    }
+    vec![AmqpFrame::Method(channel_id, method)]
+}
+
+fn connection_close_frame(e: &AmqpBrokerError, class_id: u16, method_id: u16) -> AmqpFrame {
+    AmqpFrame::Method(
+        0,
+        AmqpMethod::Connection(ConnectionMethod::Close(ConnectionClose {
+            reply_code: e.reply_code(),
+            reply_text: e.to_string(),
+            class_id,
+            method_id,
+        })),
+    )
+}
+
+/// Takes the lower of the two values, where zero means no limit
+fn negotiate<T: Ord + Default + Copy>(server: T, client: T) -> T {
+    if client == T::default() {
+        return server;
+    }
+    if server == T::default() {
+        return client;
+    }
+    server.min(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::negotiate;
+
+    #[test]
+    fn negotiate_test() {
+        assert_eq!(negotiate(131072u32, 0), 131072);
+        assert_eq!(negotiate(131072u32, 4096), 4096);
+        assert_eq!(negotiate(0u32, 4096), 4096);
+        assert_eq!(negotiate(2047u16, 65535), 2047);
+    }
+}
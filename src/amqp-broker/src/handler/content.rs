@@ -0,0 +1,116 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::{Bytes, BytesMut};
+use metadata_struct::amqp::message::AmqpMessage;
+use protocol::amqp::common::{AmqpFrame, ContentHeader, FRAME_HEADER_LEN};
+use protocol::amqp::method::AmqpMethod;
+
+use super::error::AmqpBrokerError;
+
+/// Header, end byte and payload of a body frame must all fit in frame_max
+const FRAME_OVERHEAD: u32 = FRAME_HEADER_LEN as u32 + 1;
+
+pub fn encode_content_header(header: &ContentHeader) -> Result<Vec<u8>, AmqpBrokerError> {
+    let mut buffer = BytesMut::new();
+    header.write(&mut buffer)?;
+    Ok(buffer.to_vec())
+}
+
+/// Builds the method, content header and body frames sending a stored message
+pub fn build_content_frames(
+    channel_id: u16,
+    method: AmqpMethod,
+    message: &AmqpMessage,
+    frame_max: u32,
+) -> Result<Vec<AmqpFrame>, AmqpBrokerError> {
+    let mut header = ContentHeader::read(Bytes::from(message.header.clone()))?;
+    header.body_size = message.body.len() as u64;
+
+    let mut frames = vec![
+        AmqpFrame::Method(channel_id, method),
+        AmqpFrame::Header(channel_id, header),
+    ];
+    let body = Bytes::from(message.body.clone());
+    for chunk in split_body(&body, frame_max) {
+        frames.push(AmqpFrame::Body(channel_id, chunk));
+    }
+    Ok(frames)
+}
+
+fn split_body(body: &Bytes, frame_max: u32) -> Vec<Bytes> {
+    if frame_max == 0 || body.len() as u32 <= frame_max.saturating_sub(FRAME_OVERHEAD) {
+        if body.is_empty() {
+            return Vec::new();
+        }
+        return vec![body.clone()];
+    }
+    let chunk_size = (frame_max - FRAME_OVERHEAD) as usize;
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < body.len() {
+        let end = (start + chunk_size).min(body.len());
+        chunks.push(body.slice(start..end));
+        start = end;
+    }
+    chunks
+}
+
+/// Parses the response of the PLAIN mechanism, "\0user\0password"
+pub fn parse_plain_response(response: &[u8]) -> Option<(String, String)> {
+    let mut parts = response.split(|b| *b == 0);
+    // The authorization identity is ignored
+    parts.next()?;
+    let username = String::from_utf8(parts.next()?.to_vec()).ok()?;
+    let password = String::from_utf8(parts.next()?.to_vec()).ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((username, password))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::{parse_plain_response, split_body};
+
+    #[test]
+    fn parse_plain_response_test() {
+        assert_eq!(
+            parse_plain_response(b"\0admin\0pwd123"),
+            Some(("admin".to_string(), "pwd123".to_string()))
+        );
+        assert_eq!(
+            parse_plain_response(b"admin\0admin\0"),
+            Some(("admin".to_string(), "".to_string()))
+        );
+        assert_eq!(parse_plain_response(b"admin"), None);
+        assert_eq!(parse_plain_response(b"\0a\0b\0c"), None);
+    }
+
+    #[test]
+    fn split_body_test() {
+        let body = Bytes::from(vec![1u8; 100]);
+        assert_eq!(split_body(&body, 0).len(), 1);
+        assert_eq!(split_body(&body, 108).len(), 1);
+
+        let chunks = split_body(&body, 48);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].len(), 40);
+        assert_eq!(chunks[2].len(), 20);
+
+        assert!(split_body(&Bytes::new(), 48).is_empty());
+    }
+}
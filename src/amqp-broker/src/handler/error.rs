@@ -0,0 +1,126 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::string::FromUtf8Error;
+
+use common_base::error::common::CommonError;
+use thiserror::Error;
+
+pub const REPLY_CONTENT_TOO_LARGE: u16 = 311;
+pub const REPLY_NO_ROUTE: u16 = 312;
+pub const REPLY_ACCESS_REFUSED: u16 = 403;
+pub const REPLY_NOT_FOUND: u16 = 404;
+pub const REPLY_RESOURCE_LOCKED: u16 = 405;
+pub const REPLY_PRECONDITION_FAILED: u16 = 406;
+pub const REPLY_FRAME_ERROR: u16 = 501;
+pub const REPLY_COMMAND_INVALID: u16 = 503;
+pub const REPLY_CHANNEL_ERROR: u16 = 504;
+pub const REPLY_UNEXPECTED_FRAME: u16 = 505;
+pub const REPLY_NOT_ALLOWED: u16 = 530;
+pub const REPLY_NOT_IMPLEMENTED: u16 = 540;
+pub const REPLY_INTERNAL_ERROR: u16 = 541;
+
+#[derive(Error, Debug)]
+pub enum AmqpBrokerError {
+    #[error("{0}")]
+    FromIoError(#[from] std::io::Error),
+
+    #[error("{0}")]
+    FromUtf8Error(#[from] FromUtf8Error),
+
+    #[error("{0}")]
+    FromCommonError(#[from] CommonError),
+
+    #[error("{0}")]
+    SerdeJsonError(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    FromCodecError(#[from] protocol::amqp::common::Error),
+
+    #[error("ACCESS_REFUSED - {0}")]
+    AccessRefused(String),
+
+    #[error("NOT_FOUND - no exchange '{0}'")]
+    ExchangeNotFound(String),
+
+    #[error("NOT_FOUND - no queue '{0}'")]
+    QueueNotFound(String),
+
+    #[error("RESOURCE_LOCKED - queue '{0}' is used by another connection")]
+    QueueLocked(String),
+
+    #[error("PRECONDITION_FAILED - {0}")]
+    PreconditionFailed(String),
+
+    #[error("CHANNEL_ERROR - channel {0} is not open")]
+    ChannelNotOpen(u16),
+
+    #[error("CHANNEL_ERROR - channel {0} is already open")]
+    ChannelAlreadyOpen(u16),
+
+    #[error("UNEXPECTED_FRAME - {0}")]
+    UnexpectedFrame(String),
+
+    #[error("COMMAND_INVALID - {0}")]
+    CommandInvalid(String),
+
+    #[error("NOT_ALLOWED - {0}")]
+    NotAllowed(String),
+
+    #[error("NOT_IMPLEMENTED - {0}")]
+    NotImplemented(String),
+
+    #[error("Connection ID [{0}] information not found in cache.")]
+    NotFoundConnectionInCache(u64),
+
+    #[error("{0}")]
+    CommonError(String),
+}
+
+impl AmqpBrokerError {
+    /// Reply code sent back in channel.close or connection.close
+    pub fn reply_code(&self) -> u16 {
+        match self {
+            AmqpBrokerError::AccessRefused(_) => REPLY_ACCESS_REFUSED,
+            AmqpBrokerError::ExchangeNotFound(_) | AmqpBrokerError::QueueNotFound(_) => {
+                REPLY_NOT_FOUND
+            }
+            AmqpBrokerError::QueueLocked(_) => REPLY_RESOURCE_LOCKED,
+            AmqpBrokerError::PreconditionFailed(_) => REPLY_PRECONDITION_FAILED,
+            AmqpBrokerError::ChannelNotOpen(_) | AmqpBrokerError::ChannelAlreadyOpen(_) => {
+                REPLY_CHANNEL_ERROR
+            }
+            AmqpBrokerError::UnexpectedFrame(_) => REPLY_UNEXPECTED_FRAME,
+            AmqpBrokerError::CommandInvalid(_) => REPLY_COMMAND_INVALID,
+            AmqpBrokerError::NotAllowed(_) => REPLY_NOT_ALLOWED,
+            AmqpBrokerError::FromCodecError(_) => REPLY_FRAME_ERROR,
+            AmqpBrokerError::NotImplemented(_) => REPLY_NOT_IMPLEMENTED,
+            _ => REPLY_INTERNAL_ERROR,
+        }
+    }
+
+    /// Hard errors close the whole connection, soft errors only the channel
+    pub fn is_connection_error(&self) -> bool {
+        matches!(
+            self.reply_code(),
+            REPLY_FRAME_ERROR
+                | REPLY_COMMAND_INVALID
+                | REPLY_CHANNEL_ERROR
+                | REPLY_UNEXPECTED_FRAME
+                | REPLY_NOT_ALLOWED
+                | REPLY_NOT_IMPLEMENTED
+                | REPLY_INTERNAL_ERROR
+        )
+    }
+}
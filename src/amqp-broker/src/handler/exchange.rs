@@ -0,0 +1,138 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use metadata_struct::amqp::binding::AmqpBinding;
+use metadata_struct::amqp::exchange::AmqpExchangeType;
+
+use super::cache::{AmqpCacheManager, DEFAULT_EXCHANGE};
+use super::error::AmqpBrokerError;
+
+/// Returns the names of the queues a message published to `exchange_name`
+/// with `routing_key` is delivered to.
+pub fn route_message(
+    cache_manager: &AmqpCacheManager,
+    exchange_name: &str,
+    routing_key: &str,
+) -> Result<Vec<String>, AmqpBrokerError> {
+    let exchange = match cache_manager.get_exchange(exchange_name) {
+        Some(exchange) => exchange,
+        None => return Err(AmqpBrokerError::ExchangeNotFound(exchange_name.to_string())),
+    };
+
+    // Every queue is implicitly bound to the default exchange by its own name
+    if exchange_name == DEFAULT_EXCHANGE {
+        if cache_manager.get_queue(routing_key).is_some() {
+            return Ok(vec![routing_key.to_string()]);
+        }
+        return Ok(Vec::new());
+    }
+
+    let bindings = cache_manager.get_bindings(exchange_name);
+    Ok(match_bindings(
+        &exchange.exchange_type,
+        &bindings,
+        routing_key,
+    ))
+}
+
+pub fn match_bindings(
+    exchange_type: &AmqpExchangeType,
+    bindings: &[AmqpBinding],
+    routing_key: &str,
+) -> Vec<String> {
+    let mut queues: Vec<String> = Vec::new();
+    for binding in bindings {
+        let matched = match exchange_type {
+            AmqpExchangeType::Direct => binding.routing_key == routing_key,
+            AmqpExchangeType::Fanout => true,
+            AmqpExchangeType::Topic => topic_match(&binding.routing_key, routing_key),
+        };
+        // A queue bound several times still receives a single copy
+        if matched && !queues.contains(&binding.queue_name) {
+            queues.push(binding.queue_name.clone());
+        }
+    }
+    queues
+}
+
+/// Topic exchange matching, where `*` matches exactly one word and `#`
+/// matches zero or more words.
+pub fn topic_match(pattern: &str, routing_key: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('.').collect();
+    let words: Vec<&str> = routing_key.split('.').collect();
+    words_match(&pattern, &words)
+}
+
+fn words_match(pattern: &[&str], words: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => words.is_empty(),
+        Some((&"#", rest)) => (0..=words.len()).any(|skip| words_match(rest, &words[skip..])),
+        Some((first, rest)) => match words.split_first() {
+            Some((word, words)) => (*first == "*" || first == word) && words_match(rest, words),
+            None => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metadata_struct::amqp::binding::AmqpBinding;
+    use metadata_struct::amqp::exchange::AmqpExchangeType;
+
+    use super::{match_bindings, topic_match};
+
+    #[test]
+    fn topic_match_test() {
+        assert!(topic_match("stock.usd.nyse", "stock.usd.nyse"));
+        assert!(!topic_match("stock.usd.nyse", "stock.eur.nyse"));
+
+        assert!(topic_match("stock.*.nyse", "stock.usd.nyse"));
+        assert!(!topic_match("stock.*", "stock.usd.nyse"));
+        assert!(!topic_match("*.usd", "usd"));
+
+        assert!(topic_match("#", "stock.usd.nyse"));
+        assert!(topic_match("#", ""));
+        assert!(topic_match("stock.#", "stock"));
+        assert!(topic_match("stock.#", "stock.usd.nyse"));
+        assert!(topic_match("#.nyse", "stock.usd.nyse"));
+        assert!(topic_match("stock.#.nyse", "stock.nyse"));
+        assert!(!topic_match("stock.#.nyse", "stock.usd.nasdaq"));
+        assert!(topic_match("*.#.*", "a.b"));
+        assert!(!topic_match("*.#.*", "a"));
+    }
+
+    #[test]
+    fn match_bindings_test() {
+        let binding = |queue: &str, key: &str| AmqpBinding {
+            exchange_name: "ex".to_string(),
+            queue_name: queue.to_string(),
+            routing_key: key.to_string(),
+        };
+        let bindings = vec![
+            binding("q1", "order.created"),
+            binding("q2", "order.*"),
+            binding("q2", "order.#"),
+            binding("q3", "user.created"),
+        ];
+
+        let queues = match_bindings(&AmqpExchangeType::Direct, &bindings, "order.created");
+        assert_eq!(queues, vec!["q1".to_string()]);
+
+        let queues = match_bindings(&AmqpExchangeType::Topic, &bindings, "order.created");
+        assert_eq!(queues, vec!["q1".to_string(), "q2".to_string()]);
+
+        let queues = match_bindings(&AmqpExchangeType::Fanout, &bindings, "anything");
+        assert_eq!(queues.len(), 3);
+    }
+}
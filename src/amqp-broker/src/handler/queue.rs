@@ -0,0 +1,303 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use common_base::config::broker_amqp::broker_amqp_conf;
+use common_base::error::common::CommonError;
+use dashmap::DashMap;
+use metadata_struct::adapter::read_config::ReadConfig;
+use metadata_struct::adapter::record::Record;
+use metadata_struct::amqp::message::AmqpMessage;
+use storage_adapter::storage::{ShardConfig, StorageAdapter};
+use tokio::sync::Notify;
+
+use crate::storage::keys::{queue_consumer_group, queue_shard_name};
+
+const PURGE_READ_BATCH: u64 = 1000;
+
+/// Delivery progress of a queue over its storage shard.
+///
+/// Messages are read in offset order starting at `next_offset`. Delivered but
+/// unacknowledged offsets stay in `unacked`, and offsets handed back by a
+/// nack, reject or a closed channel wait in `requeued` to be delivered again.
+/// Only the lowest offset that is not settled yet is committed, so a restart
+/// may redeliver messages but never loses one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QueueState {
+    pub next_offset: u64,
+    pub unacked: BTreeSet<u64>,
+    pub requeued: BTreeSet<u64>,
+    // Position of the next consumer in the round robin
+    pub cursor: usize,
+}
+
+impl QueueState {
+    pub fn new(next_offset: u64) -> Self {
+        QueueState {
+            next_offset,
+            ..Default::default()
+        }
+    }
+
+    pub fn commit_offset(&self) -> u64 {
+        let mut offset = self.next_offset;
+        if let Some(first) = self.unacked.first() {
+            offset = offset.min(*first);
+        }
+        if let Some(first) = self.requeued.first() {
+            offset = offset.min(*first);
+        }
+        offset
+    }
+
+    pub fn settle(&mut self, offset: u64) {
+        self.unacked.remove(&offset);
+    }
+
+    pub fn requeue(&mut self, offset: u64) {
+        if self.unacked.remove(&offset) {
+            self.requeued.insert(offset);
+        }
+    }
+}
+
+pub struct QueueManager<S> {
+    message_storage_adapter: Arc<S>,
+    // (queue_name, QueueState)
+    states: DashMap<String, QueueState>,
+    // Wakes up the dispatcher when messages are published or settled
+    pub notify: Arc<Notify>,
+}
+
+impl<S> QueueManager<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(message_storage_adapter: Arc<S>) -> Self {
+        QueueManager {
+            message_storage_adapter,
+            states: DashMap::with_capacity(8),
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    pub async fn create_queue_shard(&self, queue_name: &str) -> Result<(), CommonError> {
+        let conf = broker_amqp_conf();
+        self.message_storage_adapter
+            .create_shard(
+                conf.cluster_name.clone(),
+                queue_shard_name(queue_name),
+                ShardConfig::default(),
+            )
+            .await?;
+        self.states
+            .insert(queue_name.to_string(), QueueState::default());
+        Ok(())
+    }
+
+    pub async fn delete_queue_shard(&self, queue_name: &str) -> Result<(), CommonError> {
+        let conf = broker_amqp_conf();
+        self.states.remove(queue_name);
+        self.message_storage_adapter
+            .delete_shard(conf.cluster_name.clone(), queue_shard_name(queue_name))
+            .await
+    }
+
+    /// Restores the delivery position of a durable queue from its committed offset
+    pub async fn load_queue_state(&self, queue_name: &str) -> Result<(), CommonError> {
+        let conf = broker_amqp_conf();
+        let offsets = self
+            .message_storage_adapter
+            .get_offset_by_group(queue_consumer_group(&conf.cluster_name, queue_name))
+            .await?;
+        let next_offset = offsets.first().map(|raw| raw.offset).unwrap_or(0);
+        self.states
+            .insert(queue_name.to_string(), QueueState::new(next_offset));
+        Ok(())
+    }
+
+    pub fn get_state(&self, queue_name: &str) -> Option<QueueState> {
+        self.states.get(queue_name).map(|state| state.clone())
+    }
+
+    pub async fn write_message(
+        &self,
+        queue_name: &str,
+        message: &AmqpMessage,
+    ) -> Result<u64, CommonError> {
+        let conf = broker_amqp_conf();
+        let offset = self
+            .message_storage_adapter
+            .write(
+                conf.cluster_name.clone(),
+                queue_shard_name(queue_name),
+                message.build_record(),
+            )
+            .await?;
+        self.notify.notify_one();
+        Ok(offset)
+    }
+
+    pub async fn read_message(
+        &self,
+        queue_name: &str,
+        offset: u64,
+        max_record_num: u64,
+    ) -> Result<Vec<Record>, CommonError> {
+        let conf = broker_amqp_conf();
+        self.message_storage_adapter
+            .read_by_offset(
+                conf.cluster_name.clone(),
+                queue_shard_name(queue_name),
+                offset,
+                ReadConfig {
+                    max_record_num,
+                    max_size: u64::MAX,
+                },
+            )
+            .await
+    }
+
+    /// Picks the next message to deliver, preferring requeued messages, and
+    /// marks it as unacknowledged. Returns the offset and whether it is a redelivery.
+    pub async fn next_message(
+        &self,
+        queue_name: &str,
+    ) -> Result<Option<(u64, bool, AmqpMessage)>, CommonError> {
+        let (offset, redelivered) = {
+            let state = match self.states.get(queue_name) {
+                Some(state) => state,
+                None => return Ok(None),
+            };
+            match state.requeued.first() {
+                Some(offset) => (*offset, true),
+                None => (state.next_offset, false),
+            }
+        };
+
+        let record = match self.read_message(queue_name, offset, 1).await?.pop() {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        let record_offset = record.offset.unwrap_or(offset);
+        let message = AmqpMessage::decode_record(record)?;
+
+        if let Some(mut state) = self.states.get_mut(queue_name) {
+            if redelivered {
+                state.requeued.remove(&offset);
+            } else {
+                state.next_offset = record_offset + 1;
+            }
+            state.unacked.insert(record_offset);
+        }
+        Ok(Some((record_offset, redelivered, message)))
+    }
+
+    pub fn next_consumer_cursor(&self, queue_name: &str) -> usize {
+        match self.states.get_mut(queue_name) {
+            Some(mut state) => {
+                state.cursor = state.cursor.wrapping_add(1);
+                state.cursor
+            }
+            None => 0,
+        }
+    }
+
+    pub async fn ack(&self, queue_name: &str, offsets: &[u64]) -> Result<(), CommonError> {
+        if let Some(mut state) = self.states.get_mut(queue_name) {
+            for offset in offsets {
+                state.settle(*offset);
+            }
+        }
+        self.commit(queue_name).await
+    }
+
+    pub async fn requeue(&self, queue_name: &str, offsets: &[u64]) -> Result<(), CommonError> {
+        if let Some(mut state) = self.states.get_mut(queue_name) {
+            for offset in offsets {
+                state.requeue(*offset);
+            }
+        }
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    /// Drops all ready messages of the queue, returning how many were removed
+    pub async fn purge(&self, queue_name: &str) -> Result<u32, CommonError> {
+        let mut offset = match self.states.get(queue_name) {
+            Some(state) => state.next_offset,
+            None => return Ok(0),
+        };
+        let mut count = 0;
+        loop {
+            let records = self
+                .read_message(queue_name, offset, PURGE_READ_BATCH)
+                .await?;
+            if records.is_empty() {
+                break;
+            }
+            count += records.len() as u32;
+            offset += records.len() as u64;
+        }
+        if let Some(mut state) = self.states.get_mut(queue_name) {
+            count += state.requeued.len() as u32;
+            state.requeued.clear();
+            state.next_offset = offset;
+        }
+        self.commit(queue_name).await?;
+        Ok(count)
+    }
+
+    async fn commit(&self, queue_name: &str) -> Result<(), CommonError> {
+        let offset = match self.states.get(queue_name) {
+            Some(state) => state.commit_offset(),
+            None => return Ok(()),
+        };
+        let conf = broker_amqp_conf();
+        let mut offsets = HashMap::new();
+        offsets.insert(queue_shard_name(queue_name), offset);
+        self.message_storage_adapter
+            .commit_offset(
+                queue_consumer_group(&conf.cluster_name, queue_name),
+                conf.cluster_name.clone(),
+                offsets,
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueueState;
+
+    #[test]
+    fn commit_offset_test() {
+        let mut state = QueueState::new(5);
+        assert_eq!(state.commit_offset(), 5);
+
+        state.unacked.insert(3);
+        state.unacked.insert(4);
+        assert_eq!(state.commit_offset(), 3);
+
+        state.requeue(3);
+        assert_eq!(state.commit_offset(), 3);
+        assert!(state.requeued.contains(&3));
+
+        state.requeued.clear();
+        state.settle(4);
+        assert_eq!(state.commit_offset(), 5);
+    }
+}
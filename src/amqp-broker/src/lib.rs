@@ -11,3 +11,174 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::config::broker_amqp::broker_amqp_conf;
+use common_base::error::common::CommonError;
+use common_base::runtime::create_runtime;
+use consumer::QueueDispatcher;
+use grpc_clients::pool::ClientPool;
+use handler::cache::AmqpCacheManager;
+use handler::command::AmqpCommand;
+use handler::queue::QueueManager;
+use log::{error, info};
+use server::tcp::TcpServer;
+use storage::metadata::AmqpMetadataStorage;
+use storage_adapter::memory::MemoryStorageAdapter;
+use storage_adapter::rocksdb::RocksDBStorageAdapter;
+use storage_adapter::storage::StorageAdapter;
+use storage_adapter::StorageType;
+use tokio::runtime::Runtime;
+use tokio::signal;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+
+pub mod consumer;
+pub mod handler;
+pub mod server;
+pub mod storage;
+
+pub fn start_amqp_broker_server(stop_send: broadcast::Sender<bool>) {
+    let conf = broker_amqp_conf();
+    let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(5));
+    let storage_type = StorageType::from_str(conf.storage.storage_type.as_str())
+        .expect("Storage type not supported");
+    match storage_type {
+        StorageType::Memory => {
+            let message_storage_adapter = Arc::new(MemoryStorageAdapter::new());
+            let server = AmqpBroker::new(client_pool, message_storage_adapter);
+            server.start(stop_send);
+        }
+        StorageType::RocksDB => {
+            if conf.storage.rocksdb_data_path.is_empty() {
+                panic!("storaget type is [rocksdb],[storage.rocksdb_path] cannot be empty");
+            }
+            let message_storage_adapter = Arc::new(RocksDBStorageAdapter::new(
+                conf.storage.rocksdb_data_path.as_str(),
+                conf.storage.rocksdb_max_open_files.unwrap_or(10000),
+            ));
+            let server = AmqpBroker::new(client_pool, message_storage_adapter);
+            server.start(stop_send);
+        }
+        _ => {
+            panic!("Message data storage type configuration error, optional :memory, rocksdb");
+        }
+    }
+}
+
+pub struct AmqpBroker<S> {
+    runtime: Runtime,
+    cache_manager: Arc<AmqpCacheManager>,
+    queue_manager: Arc<QueueManager<S>>,
+    metadata_storage: Arc<AmqpMetadataStorage>,
+    command: Arc<AmqpCommand<S>>,
+}
+
+impl<S> AmqpBroker<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(client_pool: Arc<ClientPool>, message_storage_adapter: Arc<S>) -> Self {
+        let conf = broker_amqp_conf();
+        let runtime = create_runtime("amqp-broker-runtime", conf.system.runtime_worker_threads);
+
+        let cache_manager = Arc::new(AmqpCacheManager::new());
+        let queue_manager = Arc::new(QueueManager::new(message_storage_adapter));
+        let metadata_storage = Arc::new(AmqpMetadataStorage::new(client_pool));
+        let command = Arc::new(AmqpCommand::new(
+            cache_manager.clone(),
+            queue_manager.clone(),
+            metadata_storage.clone(),
+        ));
+
+        AmqpBroker {
+            runtime,
+            cache_manager,
+            queue_manager,
+            metadata_storage,
+            command,
+        }
+    }
+
+    pub fn start(&self, stop_send: broadcast::Sender<bool>) {
+        self.load_metadata();
+        self.start_dispatcher(stop_send.clone());
+        self.start_tcp_server(stop_send.clone());
+        self.awaiting_stop(stop_send);
+    }
+
+    fn load_metadata(&self) {
+        let cache_manager = self.cache_manager.clone();
+        let queue_manager = self.queue_manager.clone();
+        let metadata_storage = self.metadata_storage.clone();
+        let result: Result<(), CommonError> = self.runtime.block_on(async move {
+            for exchange in metadata_storage.list_exchange().await? {
+                cache_manager.add_exchange(exchange);
+            }
+            for queue in metadata_storage.list_queue().await? {
+                queue_manager.load_queue_state(&queue.queue_name).await?;
+                cache_manager.add_queue(queue);
+            }
+            for binding in metadata_storage.list_binding().await? {
+                cache_manager.add_binding(binding);
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            panic!(
+                "Failed to load AMQP metadata from the placement center, error message: {}",
+                e
+            );
+        }
+    }
+
+    fn start_dispatcher(&self, stop_send: broadcast::Sender<bool>) {
+        let dispatcher =
+            QueueDispatcher::new(self.cache_manager.clone(), self.queue_manager.clone());
+        self.runtime.spawn(async move {
+            dispatcher.start(stop_send).await;
+        });
+    }
+
+    fn start_tcp_server(&self, stop_send: broadcast::Sender<bool>) {
+        let conf = broker_amqp_conf();
+        let server = TcpServer::new(
+            conf.network.tcp_port,
+            conf.network.max_connection_num,
+            self.cache_manager.clone(),
+            self.command.clone(),
+            stop_send,
+        );
+        self.runtime.spawn(async move {
+            if let Err(e) = server.start().await {
+                panic!("{}", e);
+            }
+        });
+    }
+
+    fn awaiting_stop(&self, stop_send: broadcast::Sender<bool>) {
+        self.runtime.spawn(async move {
+            sleep(Duration::from_millis(5)).await;
+            info!("AMQP Broker service started successfully...");
+        });
+
+        // Wait for the stop signal
+        self.runtime.block_on(async move {
+            signal::ctrl_c().await.expect("failed to listen for event");
+            match stop_send.send(true) {
+                Ok(_) => {
+                    info!(
+                        "{}",
+                        "When ctrl + c is received, the service starts to stop"
+                    );
+                }
+                Err(_) => {
+                    error!("Failed to send stop signal");
+                }
+            }
+        });
+    }
+}
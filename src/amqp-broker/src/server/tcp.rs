@@ -0,0 +1,214 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, error, info, warn};
+use protocol::amqp::codec::AmqpCodec;
+use protocol::amqp::common::AmqpFrame;
+use storage_adapter::storage::StorageAdapter;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::timeout;
+use tokio::{io, select};
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use crate::handler::cache::{AmqpCacheManager, AmqpConnection};
+use crate::handler::command::AmqpCommand;
+use crate::handler::error::AmqpBrokerError;
+
+const WRITE_QUEUE_SIZE: usize = 1000;
+
+// Time allowed for a client to complete the handshake up to connection.open
+const HANDSHAKE_WAIT_SECONDS: u64 = 10;
+
+// Heartbeat intervals without any frame from the client before it is considered dead
+const MAX_MISSED_HEARTBEATS: u32 = 2;
+
+pub struct TcpServer<S> {
+    port: u32,
+    max_connection_num: usize,
+    cache_manager: Arc<AmqpCacheManager>,
+    command: Arc<AmqpCommand<S>>,
+    stop_sx: broadcast::Sender<bool>,
+}
+
+impl<S> TcpServer<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        port: u32,
+        max_connection_num: usize,
+        cache_manager: Arc<AmqpCacheManager>,
+        command: Arc<AmqpCommand<S>>,
+        stop_sx: broadcast::Sender<bool>,
+    ) -> Self {
+        TcpServer {
+            port,
+            max_connection_num,
+            cache_manager,
+            command,
+            stop_sx,
+        }
+    }
+
+    pub async fn start(&self) -> Result<(), AmqpBrokerError> {
+        let listener = TcpListener::bind(format!("0.0.0.0:{}", self.port)).await?;
+        info!(
+            "AMQP Broker TCP Server started successfully, listening port: {}",
+            self.port
+        );
+
+        let mut stop_rx = self.stop_sx.subscribe();
+        loop {
+            select! {
+                val = stop_rx.recv() => {
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("AMQP Broker TCP Server stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                val = listener.accept() => {
+                    match val {
+                        Ok((stream, addr)) => {
+                            if self.cache_manager.connection_count() >= self.max_connection_num {
+                                warn!(
+                                    "Total number of connections exceeds {}, connection {} is rejected",
+                                    self.max_connection_num, addr
+                                );
+                                continue;
+                            }
+                            debug!("accept tcp connection:{:?}", addr);
+                            self.process_connection(stream, addr);
+                        }
+                        Err(e) => {
+                            error!("TCP accept failed to create connection with error message :{:?}", e);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn process_connection(&self, stream: TcpStream, addr: SocketAddr) {
+        let (r_stream, w_stream) = io::split(stream);
+        let mut read_frame_stream = FramedRead::new(r_stream, AmqpCodec::new(None));
+        let mut write_frame_stream = FramedWrite::new(w_stream, AmqpCodec::new(None));
+
+        let (write_sx, mut write_rx) = mpsc::channel::<AmqpFrame>(WRITE_QUEUE_SIZE);
+        let (connection_stop_sx, mut connection_stop_rx) = mpsc::channel::<bool>(1);
+        let connection = AmqpConnection::new(addr, write_sx.clone(), connection_stop_sx);
+        let connection_id = connection.connection_id;
+        self.cache_manager.add_connection(connection);
+
+        // The write task exits once every sender of the connection has been dropped,
+        // after the frames still in the queue have been flushed.
+        tokio::spawn(async move {
+            while let Some(frame) = write_rx.recv().await {
+                if let Err(e) = write_frame_stream.send(frame).await {
+                    debug!(
+                        "Failed to write frame to connection {}, error message: {:?}",
+                        connection_id, e
+                    );
+                    break;
+                }
+            }
+        });
+
+        let cache_manager = self.cache_manager.clone();
+        let command = self.command.clone();
+        let mut stop_rx = self.stop_sx.subscribe();
+        tokio::spawn(async move {
+            let mut missed_heartbeats = 0;
+            loop {
+                let read_timeout = read_timeout(&cache_manager, connection_id);
+                select! {
+                    _ = stop_rx.recv() => {
+                        break;
+                    }
+                    _ = connection_stop_rx.recv() => {
+                        debug!("TCP connection 【{}】 stopped by the server.", connection_id);
+                        break;
+                    }
+                    val = timeout(read_timeout, read_frame_stream.next()) => {
+                        let frame = match val {
+                            Ok(Some(Ok(frame))) => frame,
+                            Ok(Some(Err(e))) => {
+                                debug!("TCP connection parsing frame format error message :{:?}", e);
+                                break;
+                            }
+                            Ok(None) => break,
+                            Err(_) => {
+                                missed_heartbeats += 1;
+                                if !is_open(&cache_manager, connection_id)
+                                    || missed_heartbeats >= MAX_MISSED_HEARTBEATS
+                                {
+                                    info!("TCP connection {} heartbeat timeout, close connection", connection_id);
+                                    break;
+                                }
+                                if write_sx.send(AmqpFrame::Heartbeat).await.is_err() {
+                                    break;
+                                }
+                                continue;
+                            }
+                        };
+                        missed_heartbeats = 0;
+
+                        let response = command.apply(connection_id, frame).await;
+                        if let Some(connection) = cache_manager.get_connection(connection_id) {
+                            read_frame_stream.decoder_mut().set_frame_max(connection.frame_max);
+                        }
+                        for frame in response.frames {
+                            if write_sx.send(frame).await.is_err() {
+                                break;
+                            }
+                        }
+                        if response.close {
+                            break;
+                        }
+                    }
+                }
+            }
+            command.connection_lost(connection_id).await;
+        });
+    }
+}
+
+fn is_open(cache_manager: &Arc<AmqpCacheManager>, connection_id: u64) -> bool {
+    match cache_manager.get_connection(connection_id) {
+        Some(connection) => connection.is_open,
+        None => false,
+    }
+}
+
+fn read_timeout(cache_manager: &Arc<AmqpCacheManager>, connection_id: u64) -> Duration {
+    match cache_manager.get_connection(connection_id) {
+        Some(connection) if connection.is_open => {
+            if connection.heartbeat == 0 {
+                // Heartbeats disabled during tuning
+                Duration::from_secs(u32::MAX as u64)
+            } else {
+                Duration::from_secs(connection.heartbeat as u64)
+            }
+        }
+        _ => Duration::from_secs(HANDSHAKE_WAIT_SECONDS),
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Names are free form strings, so '%' and '/' are escaped to keep every name in
+/// exactly one key level.
+fn key_level(name: &str) -> String {
+    name.replace('%', "%25").replace('/', "%2F")
+}
+
+pub fn exchange_prefix_key(cluster_name: &str) -> String {
+    format!("/amqp/{}/exchange/", cluster_name)
+}
+
+pub fn exchange_key(cluster_name: &str, exchange_name: &str) -> String {
+    format!(
+        "{}{}",
+        exchange_prefix_key(cluster_name),
+        key_level(exchange_name)
+    )
+}
+
+pub fn queue_prefix_key(cluster_name: &str) -> String {
+    format!("/amqp/{}/queue/", cluster_name)
+}
+
+pub fn queue_key(cluster_name: &str, queue_name: &str) -> String {
+    format!(
+        "{}{}",
+        queue_prefix_key(cluster_name),
+        key_level(queue_name)
+    )
+}
+
+pub fn binding_prefix_key(cluster_name: &str) -> String {
+    format!("/amqp/{}/binding/", cluster_name)
+}
+
+pub fn binding_key(
+    cluster_name: &str,
+    exchange_name: &str,
+    queue_name: &str,
+    routing_key: &str,
+) -> String {
+    format!(
+        "{}{}/{}/{}",
+        binding_prefix_key(cluster_name),
+        key_level(exchange_name),
+        key_level(queue_name),
+        key_level(routing_key)
+    )
+}
+
+pub fn queue_shard_name(queue_name: &str) -> String {
+    format!("amqp_queue_{}", queue_name)
+}
+
+pub fn queue_consumer_group(cluster_name: &str, queue_name: &str) -> String {
+    format!("amqp_{}_{}", cluster_name, queue_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{binding_key, binding_prefix_key, exchange_key, exchange_prefix_key, queue_key};
+
+    #[test]
+    fn object_key_test() {
+        let key = exchange_key("c1", "logs");
+        assert_eq!(key, "/amqp/c1/exchange/logs");
+        assert!(key.starts_with(&exchange_prefix_key("c1")));
+        assert_eq!(queue_key("c1", "a/b"), "/amqp/c1/queue/a%2Fb");
+    }
+
+    #[test]
+    fn binding_key_test() {
+        let key = binding_key("c1", "logs", "q1", "a.#");
+        assert_eq!(key, "/amqp/c1/binding/logs/q1/a.#");
+        assert!(key.starts_with(&binding_prefix_key("c1")));
+
+        // A slash inside a name must not make two different bindings share a key
+        assert_ne!(
+            binding_key("c1", "a/b", "c", "k"),
+            binding_key("c1", "a", "b/c", "k")
+        );
+        assert_ne!(
+            binding_key("c1", "a%2Fb", "c", "k"),
+            binding_key("c1", "a/b", "c", "k")
+        );
+    }
+}
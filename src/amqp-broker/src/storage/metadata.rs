@@ -0,0 +1,143 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::config::broker_amqp::broker_amqp_conf;
+use common_base::error::common::CommonError;
+use grpc_clients::placement::kv::call::{
+    placement_delete, placement_list_by_prefix, placement_set,
+};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::amqp::binding::AmqpBinding;
+use metadata_struct::amqp::exchange::AmqpExchange;
+use metadata_struct::amqp::queue::AmqpQueue;
+use protocol::placement_center::placement_center_kv::{
+    DeleteRequest, ListByPrefixRequest, SetRequest,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::keys::{
+    binding_key, binding_prefix_key, exchange_key, exchange_prefix_key, queue_key, queue_prefix_key,
+};
+
+/// Durable exchanges, queues and bindings kept in the placement center.
+///
+/// Every object is saved under its own key, so brokers of the same cluster only
+/// ever write or delete the objects they change and never overwrite each other.
+pub struct AmqpMetadataStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl AmqpMetadataStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        AmqpMetadataStorage { client_pool }
+    }
+
+    pub async fn list_exchange(&self) -> Result<Vec<AmqpExchange>, CommonError> {
+        let conf = broker_amqp_conf();
+        self.list(exchange_prefix_key(&conf.cluster_name)).await
+    }
+
+    pub async fn save_exchange(&self, exchange: &AmqpExchange) -> Result<(), CommonError> {
+        let conf = broker_amqp_conf();
+        self.set(
+            exchange_key(&conf.cluster_name, &exchange.exchange_name),
+            exchange,
+        )
+        .await
+    }
+
+    pub async fn delete_exchange(&self, exchange_name: &str) -> Result<(), CommonError> {
+        let conf = broker_amqp_conf();
+        self.delete(exchange_key(&conf.cluster_name, exchange_name))
+            .await
+    }
+
+    pub async fn list_queue(&self) -> Result<Vec<AmqpQueue>, CommonError> {
+        let conf = broker_amqp_conf();
+        self.list(queue_prefix_key(&conf.cluster_name)).await
+    }
+
+    pub async fn save_queue(&self, queue: &AmqpQueue) -> Result<(), CommonError> {
+        let conf = broker_amqp_conf();
+        self.set(queue_key(&conf.cluster_name, &queue.queue_name), queue)
+            .await
+    }
+
+    pub async fn delete_queue(&self, queue_name: &str) -> Result<(), CommonError> {
+        let conf = broker_amqp_conf();
+        self.delete(queue_key(&conf.cluster_name, queue_name)).await
+    }
+
+    pub async fn list_binding(&self) -> Result<Vec<AmqpBinding>, CommonError> {
+        let conf = broker_amqp_conf();
+        self.list(binding_prefix_key(&conf.cluster_name)).await
+    }
+
+    pub async fn save_binding(&self, binding: &AmqpBinding) -> Result<(), CommonError> {
+        let conf = broker_amqp_conf();
+        self.set(
+            binding_key(
+                &conf.cluster_name,
+                &binding.exchange_name,
+                &binding.queue_name,
+                &binding.routing_key,
+            ),
+            binding,
+        )
+        .await
+    }
+
+    pub async fn delete_binding(&self, binding: &AmqpBinding) -> Result<(), CommonError> {
+        let conf = broker_amqp_conf();
+        self.delete(binding_key(
+            &conf.cluster_name,
+            &binding.exchange_name,
+            &binding.queue_name,
+            &binding.routing_key,
+        ))
+        .await
+    }
+
+    async fn list<T: DeserializeOwned>(&self, prefix: String) -> Result<Vec<T>, CommonError> {
+        let conf = broker_amqp_conf();
+        let request = ListByPrefixRequest { prefix };
+        let reply =
+            placement_list_by_prefix(&self.client_pool, &conf.placement_center, request).await?;
+        let mut results = Vec::with_capacity(reply.values.len());
+        for value in reply.values {
+            results.push(serde_json::from_str(&value)?);
+        }
+        Ok(results)
+    }
+
+    async fn set<T: Serialize>(&self, key: String, value: &T) -> Result<(), CommonError> {
+        let conf = broker_amqp_conf();
+        let request = SetRequest {
+            key,
+            value: serde_json::to_string(value)?,
+        };
+        placement_set(&self.client_pool, &conf.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: String) -> Result<(), CommonError> {
+        let conf = broker_amqp_conf();
+        let request = DeleteRequest { key };
+        placement_delete(&self.client_pool, &conf.placement_center, request).await?;
+        Ok(())
+    }
+}
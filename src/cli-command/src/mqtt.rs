@@ -17,16 +17,20 @@ use std::sync::Arc;
 
 use common_base::enum_type::common_enum::SortType;
 use grpc_clients::mqtt::admin::call::{
-    cluster_status, mqtt_broker_create_user, mqtt_broker_delete_user,
-    mqtt_broker_enable_slow_subscribe, mqtt_broker_list_connection,
-    mqtt_broker_list_slow_subscribe, mqtt_broker_list_topic, mqtt_broker_list_user,
+    cluster_status, mqtt_broker_create_trace, mqtt_broker_create_user,
+    mqtt_broker_delete_retain_message, mqtt_broker_delete_trace, mqtt_broker_delete_user,
+    mqtt_broker_enable_slow_subscribe, mqtt_broker_list_alarm, mqtt_broker_list_connection,
+    mqtt_broker_list_retain_message, mqtt_broker_list_slow_subscribe, mqtt_broker_list_topic,
+    mqtt_broker_list_trace, mqtt_broker_list_user, mqtt_broker_stop_trace,
 };
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::user::MqttUser;
 use prettytable::{row, Table};
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    ClusterStatusRequest, CreateUserRequest, DeleteUserRequest, EnableSlowSubscribeRequest,
-    ListConnectionRequest, ListSlowSubscribeRequest, ListTopicRequest, ListUserRequest,
+    ClusterStatusRequest, CreateTraceRequest, CreateUserRequest, DeleteRetainMessageRequest,
+    DeleteTraceRequest, DeleteUserRequest, EnableSlowSubscribeRequest, ListAlarmRequest,
+    ListConnectionRequest, ListRetainMessageRequest, ListSlowSubscribeRequest, ListTopicRequest,
+    ListTraceRequest, ListUserRequest, StopTraceRequest,
 };
 
 use crate::{error_info, grpc_addr};
@@ -54,6 +58,19 @@ pub enum MqttActionType {
     ListSlowSubscribe(ListSlowSubscribeRequest),
 
     ListTopic(ListTopicRequest),
+
+    // retain message
+    ListRetainMessage(ListRetainMessageRequest),
+    DeleteRetainMessage(DeleteRetainMessageRequest),
+
+    // observability: alarm
+    ListAlarm(ListAlarmRequest),
+
+    // observability: trace
+    CreateTrace(CreateTraceRequest),
+    ListTrace,
+    StopTrace(StopTraceRequest),
+    DeleteTrace(DeleteTraceRequest),
 }
 
 pub struct MqttBrokerCommand {}
@@ -101,6 +118,33 @@ impl MqttBrokerCommand {
                 self.list_slow_subscribe(&client_pool, params.clone(), request.clone())
                     .await;
             }
+            MqttActionType::ListRetainMessage(ref request) => {
+                self.list_retain_message(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::DeleteRetainMessage(ref request) => {
+                self.delete_retain_message(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::ListAlarm(ref request) => {
+                self.list_alarm(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::CreateTrace(ref request) => {
+                self.create_trace(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::ListTrace => {
+                self.list_trace(&client_pool, params.clone()).await;
+            }
+            MqttActionType::StopTrace(ref request) => {
+                self.stop_trace(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::DeleteTrace(ref request) => {
+                self.delete_trace(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
         }
     }
 
@@ -251,6 +295,9 @@ impl MqttBrokerCommand {
                     "topic",
                     "sub_name",
                     "time_ms",
+                    "internal_ms",
+                    "response_ms",
+                    "node_info",
                     "create_time"
                 ]);
                 for raw in list_slow_sub_raw {
@@ -259,11 +306,20 @@ impl MqttBrokerCommand {
                         raw.topic,
                         raw.sub_name,
                         raw.time_ms,
+                        raw.internal_ms,
+                        raw.response_ms,
+                        raw.node_info,
                         raw.create_time
                     ]);
                 }
                 // output cmd
-                table.printstd()
+                table.printstd();
+                for node_error in data.node_errors {
+                    println!(
+                        "The slow subscriptions of broker {} are missing, reason: {}",
+                        node_error.node_id, node_error.error
+                    );
+                }
             }
             Err(e) => {
                 println!("MQTT broker list slow subscribe info exception");
@@ -302,6 +358,193 @@ impl MqttBrokerCommand {
             }
         }
     }
+
+    async fn list_retain_message(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: ListRetainMessageRequest,
+    ) {
+        match mqtt_broker_list_retain_message(client_pool, &grpc_addr(params.server), cli_request)
+            .await
+        {
+            Ok(data) => {
+                let mut table = Table::new();
+                table.add_row(row![
+                    "topic_name",
+                    "client_id",
+                    "qos",
+                    "payload",
+                    "create_time",
+                    "expire_at"
+                ]);
+                for raw in data.retain_messages {
+                    table.add_row(row![
+                        raw.topic_name,
+                        raw.client_id,
+                        raw.qos,
+                        String::from_utf8_lossy(&raw.payload),
+                        raw.create_time,
+                        raw.expire_at
+                    ]);
+                }
+                table.printstd();
+            }
+            Err(e) => {
+                println!("MQTT broker list retain message exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn delete_retain_message(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: DeleteRetainMessageRequest,
+    ) {
+        match mqtt_broker_delete_retain_message(client_pool, &grpc_addr(params.server), cli_request)
+            .await
+        {
+            Ok(data) => {
+                println!("deleted {} retained messages.", data.deleted_num);
+            }
+            Err(e) => {
+                println!("MQTT broker delete retain message exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn list_alarm(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: ListAlarmRequest,
+    ) {
+        match mqtt_broker_list_alarm(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(data) => {
+                let mut table = Table::new();
+                table.add_row(row![
+                    "name",
+                    "broker_id",
+                    "message",
+                    "details",
+                    "activate_time",
+                    "deactivate_time"
+                ]);
+                for raw in data.alarms {
+                    let mut details: Vec<String> = raw
+                        .details
+                        .iter()
+                        .map(|(key, value)| format!("{}={}", key, value))
+                        .collect();
+                    details.sort();
+                    table.add_row(row![
+                        raw.name,
+                        raw.broker_id,
+                        raw.message,
+                        details.join(","),
+                        raw.activate_time,
+                        raw.deactivate_time
+                    ]);
+                }
+                table.printstd();
+            }
+            Err(e) => {
+                println!("MQTT broker list alarm exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn create_trace(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: CreateTraceRequest,
+    ) {
+        let name = cli_request.name.clone();
+        match mqtt_broker_create_trace(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(_) => {
+                println!(
+                    "Trace {} started, download it from /trace/download?name={}",
+                    name, name
+                );
+            }
+            Err(e) => {
+                println!("MQTT broker create trace exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn list_trace(&self, client_pool: &ClientPool, params: MqttCliCommandParam) {
+        match mqtt_broker_list_trace(client_pool, &grpc_addr(params.server), ListTraceRequest {})
+            .await
+        {
+            Ok(data) => {
+                let mut table = Table::new();
+                table.add_row(row![
+                    "name",
+                    "trace_type",
+                    "value",
+                    "start_time",
+                    "end_time",
+                    "status"
+                ]);
+                for raw in data.traces {
+                    table.add_row(row![
+                        raw.name,
+                        raw.trace_type,
+                        raw.value,
+                        raw.start_time,
+                        raw.end_time,
+                        raw.status
+                    ]);
+                }
+                table.printstd();
+            }
+            Err(e) => {
+                println!("MQTT broker list trace exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn stop_trace(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: StopTraceRequest,
+    ) {
+        match mqtt_broker_stop_trace(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(_) => {
+                println!("Trace stopped successfully!");
+            }
+            Err(e) => {
+                println!("MQTT broker stop trace exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn delete_trace(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: DeleteTraceRequest,
+    ) {
+        match mqtt_broker_delete_trace(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(_) => {
+                println!("Trace deleted successfully!");
+            }
+            Err(e) => {
+                println!("MQTT broker delete trace exception");
+                error_info(e.to_string());
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -318,6 +561,7 @@ mod tests {
             node_info: "RobustMQ-MQTT@172.22.194.185".to_string(),
             create_time: 1733898597,
             sub_name: "/packet_tcp_ssl/7fce56aa49ef4cea90dc4be77d6a775e".to_string(),
+            ..Default::default()
         };
         list_slow_sub_raw.push(raw1);
         let raw3 = ListSlowSubScribeRaw {
@@ -327,6 +571,7 @@ mod tests {
             node_info: "RobustMQ-MQTT@172.22.194.185".to_string(),
             create_time: 1733898601,
             sub_name: "/request/131edb8526804e80b32b387fa2340d35".to_string(),
+            ..Default::default()
         };
         list_slow_sub_raw.push(raw3);
         let raw2 = ListSlowSubScribeRaw {
@@ -336,11 +581,13 @@ mod tests {
             node_info: "RobustMQ-MQTT@172.22.194.185".to_string(),
             create_time: 1733898601,
             sub_name: "/request/131edb8526804e80b32b387fa2340d35".to_string(),
+            ..Default::default()
         };
         list_slow_sub_raw.push(raw2);
 
         Ok(ListSlowSubscribeReply {
             list_slow_subscribe_raw: list_slow_sub_raw,
+            ..Default::default()
         })
     }
     #[test]
@@ -357,6 +604,7 @@ mod tests {
                 node_info: "RobustMQ-MQTT@172.22.194.185".to_string(),
                 create_time: 1733898601,
                 sub_name: "/request/131edb8526804e80b32b387fa2340d35".to_string(),
+                ..Default::default()
             },
             reply.list_slow_subscribe_raw[0]
         );
@@ -368,6 +616,7 @@ mod tests {
                 node_info: "RobustMQ-MQTT@172.22.194.185".to_string(),
                 create_time: 1733898601,
                 sub_name: "/request/131edb8526804e80b32b387fa2340d35".to_string(),
+                ..Default::default()
             },
             reply.list_slow_subscribe_raw[1]
         );
@@ -379,6 +628,7 @@ mod tests {
                 node_info: "RobustMQ-MQTT@172.22.194.185".to_string(),
                 create_time: 1733898597,
                 sub_name: "/packet_tcp_ssl/7fce56aa49ef4cea90dc4be77d6a775e".to_string(),
+                ..Default::default()
             },
             reply.list_slow_subscribe_raw[2]
         );
@@ -398,6 +648,7 @@ mod tests {
                 node_info: "RobustMQ-MQTT@172.22.194.185".to_string(),
                 create_time: 1733898601,
                 sub_name: "/request/131edb8526804e80b32b387fa2340d35".to_string(),
+                ..Default::default()
             },
             reply.list_slow_subscribe_raw[2]
         );
@@ -409,6 +660,7 @@ mod tests {
                 node_info: "RobustMQ-MQTT@172.22.194.185".to_string(),
                 create_time: 1733898601,
                 sub_name: "/request/131edb8526804e80b32b387fa2340d35".to_string(),
+                ..Default::default()
             },
             reply.list_slow_subscribe_raw[1]
         );
@@ -420,6 +672,7 @@ mod tests {
                 node_info: "RobustMQ-MQTT@172.22.194.185".to_string(),
                 create_time: 1733898597,
                 sub_name: "/packet_tcp_ssl/7fce56aa49ef4cea90dc4be77d6a775e".to_string(),
+                ..Default::default()
             },
             reply.list_slow_subscribe_raw[0]
         );
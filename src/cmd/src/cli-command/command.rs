@@ -20,7 +20,9 @@ use cli_command::placement::{
     PlacementActionType, PlacementCenterCommand, PlacementCliCommandParam,
 };
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    CreateUserRequest, DeleteUserRequest, ListTopicRequest,
+    CreateTraceRequest, CreateUserRequest, DeleteRetainMessageRequest, DeleteTraceRequest,
+    DeleteUserRequest, ListAlarmRequest, ListRetainMessageRequest, ListTopicRequest,
+    StopTraceRequest,
 };
 use protocol::placement_center::placement_center_openraft::{
     AddLearnerRequest, ChangeMembershipRequest, Node,
@@ -73,9 +75,22 @@ enum MQTTAction {
 
     ListTopic(ListTopicArgs),
 
+    // Retained messages
+    ListRetainMessage(ListRetainMessageArgs),
+    DeleteRetainMessage(DeleteRetainMessageArgs),
+
     // observability: slow-sub feat
     #[clap(name = "slow-sub")]
     SlowSub(SlowSubArgs),
+
+    // observability: alarms
+    ListAlarm(ListAlarmArgs),
+
+    // observability: message trace
+    CreateTrace(CreateTraceArgs),
+    ListTrace,
+    StopTrace(TraceNameArgs),
+    DeleteTrace(TraceNameArgs),
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -96,6 +111,68 @@ struct ListTopicArgs {
     match_option: MatchOption,
 }
 
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: list retained messages matching a topic filter", long_about = None)]
+#[command(next_line_help = true)]
+struct ListRetainMessageArgs {
+    #[arg(short, long, default_value_t = String::from("#"))]
+    topic_filter: String,
+
+    #[arg(short, long, default_value_t = 100)]
+    limit: u32,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: delete retained messages matching a topic filter", long_about = None)]
+#[command(next_line_help = true)]
+struct DeleteRetainMessageArgs {
+    #[arg(short, long, required = true)]
+    topic_filter: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: list active alarms and alarm history", long_about = None)]
+#[command(next_line_help = true)]
+struct ListAlarmArgs {
+    #[arg(short, long, default_value_t = false)]
+    active: bool,
+
+    #[arg(short, long, default_value_t = 100)]
+    limit: u32,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum TraceType {
+    ClientId,
+    Topic,
+    Ip,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: trace the messages of a client id, topic filter or peer ip", long_about = None)]
+#[command(next_line_help = true)]
+struct CreateTraceArgs {
+    #[arg(short, long, required = true)]
+    name: String,
+
+    #[arg(short, long, required = true)]
+    trace_type: TraceType,
+
+    #[arg(short, long, required = true)]
+    value: String,
+
+    #[arg(short, long, default_value_t = 600)]
+    duration_sec: u64,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: stop or delete a trace", long_about = None)]
+#[command(next_line_help = true)]
+struct TraceNameArgs {
+    #[arg(short, long, required = true)]
+    name: String,
+}
+
 #[derive(clap::Args, Debug)]
 #[command(author="RobustMQ",  about="Command line tool for placement center", long_about = None)]
 #[command(next_line_help = true)]
@@ -183,7 +260,39 @@ async fn handle_mqtt(args: MqttArgs, cmd: MqttBrokerCommand) {
                     MatchOption::S => 2,
                 },
             }),
+            MQTTAction::ListRetainMessage(args) => {
+                MqttActionType::ListRetainMessage(ListRetainMessageRequest {
+                    topic_filter: args.topic_filter,
+                    limit: args.limit,
+                })
+            }
+            MQTTAction::DeleteRetainMessage(args) => {
+                MqttActionType::DeleteRetainMessage(DeleteRetainMessageRequest {
+                    topic_filter: args.topic_filter,
+                })
+            }
             MQTTAction::SlowSub(args) => process_slow_sub_args(args), // _ => unreachable!("UnSupport command"),
+            MQTTAction::ListAlarm(args) => MqttActionType::ListAlarm(ListAlarmRequest {
+                only_active: args.active,
+                limit: args.limit,
+            }),
+            MQTTAction::CreateTrace(args) => MqttActionType::CreateTrace(CreateTraceRequest {
+                name: args.name,
+                trace_type: match args.trace_type {
+                    TraceType::ClientId => "client_id".to_string(),
+                    TraceType::Topic => "topic".to_string(),
+                    TraceType::Ip => "ip".to_string(),
+                },
+                value: args.value,
+                duration_sec: args.duration_sec,
+            }),
+            MQTTAction::ListTrace => MqttActionType::ListTrace,
+            MQTTAction::StopTrace(args) => {
+                MqttActionType::StopTrace(StopTraceRequest { name: args.name })
+            }
+            MQTTAction::DeleteTrace(args) => {
+                MqttActionType::DeleteTrace(DeleteTraceRequest { name: args.name })
+            }
         },
     };
     cmd.start(params).await;
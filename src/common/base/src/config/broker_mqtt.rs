@@ -32,12 +32,12 @@ use std::sync::OnceLock;
 
 use serde::{Deserialize, Serialize};
 
-use super::common::{Auth, Log, Storage};
+use super::common::{Auth, Log, Prometheus, Storage, Telemetry};
 use super::default_mqtt::{
     default_auth, default_grpc_port, default_http_port, default_log, default_network,
     default_network_quic_port, default_network_tcp_port, default_network_tcps_port,
     default_network_websocket_port, default_network_websockets_port, default_placement_center,
-    default_storage, default_system, default_tcp_thread,
+    default_storage, default_system, default_tcp_thread, default_webhook,
 };
 use crate::tools::{read_file, try_create_fold};
 
@@ -63,6 +63,27 @@ pub struct BrokerMqttConfig {
     pub auth: Auth,
     #[serde(default = "default_log")]
     pub log: Log,
+    #[serde(default)]
+    pub telemetry: Telemetry,
+    #[serde(default)]
+    pub prometheus: Prometheus,
+    #[serde(default = "default_webhook")]
+    pub webhook: Webhook,
+    #[serde(default)]
+    pub metadata: MetadataStorage,
+}
+
+// Where the broker keeps users, sessions, subscriptions and the other metadata. An empty
+// or "placement" storage type goes through the placement center, "rocksdb" keeps them in
+// a RocksDB on the local disk for single node deployments.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct MetadataStorage {
+    #[serde(default)]
+    pub storage_type: String,
+    #[serde(default)]
+    pub rocksdb_data_path: String,
+    #[serde(default)]
+    pub rocksdb_max_open_files: Option<i32>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -77,6 +98,9 @@ pub struct Network {
     pub websockets_port: u32,
     #[serde(default = "default_network_quic_port")]
     pub quic_port: u32,
+    // The QUIC listener needs tls_cert and tls_key, so it only starts when enabled
+    #[serde(default)]
+    pub quic_enable: bool,
     #[serde(default)]
     pub tls_cert: String,
     #[serde(default)]
@@ -103,6 +127,35 @@ pub struct TcpThread {
     pub lock_try_mut_sleep_time_ms: u64,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Webhook {
+    #[serde(default)]
+    pub enable: bool,
+    #[serde(default)]
+    pub endpoints: Vec<WebhookEndpoint>,
+    // events waiting to be sent per endpoint, further events are dropped
+    #[serde(default)]
+    pub queue_size: usize,
+    #[serde(default)]
+    pub batch_size: usize,
+    #[serde(default)]
+    pub batch_interval_ms: u64,
+    #[serde(default)]
+    pub max_retries: u32,
+    #[serde(default)]
+    pub retry_interval_ms: u64,
+    #[serde(default)]
+    pub request_timeout_ms: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct WebhookEndpoint {
+    pub url: String,
+    // event names such as "client.connected" or "message.*", empty means all events
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct System {
     #[serde(default)]
@@ -187,6 +240,7 @@ mod tests {
         assert_eq!(config.network.websocket_port, 8093);
         assert_eq!(config.network.websockets_port, 8094);
         assert_eq!(config.network.quic_port, 9083);
+        assert!(config.network.quic_enable);
         assert!(!config.network.tls_cert.is_empty());
         assert!(!config.network.tls_key.is_empty());
 
@@ -217,6 +271,29 @@ mod tests {
             "./robust-data/mqtt-broker/logs".to_string()
         );
 
+        assert!(!config.telemetry.enable);
+        assert_eq!(
+            config.telemetry.exporter_endpoint,
+            "http://127.0.0.1:4317".to_string()
+        );
+
+        assert!(!config.prometheus.enable);
+        assert_eq!(config.prometheus.model, "push".to_string());
+        assert_eq!(
+            config.prometheus.push_gateway_server,
+            "127.0.0.1:8081".to_string()
+        );
+        assert_eq!(config.prometheus.interval, 10);
+
+        assert!(!config.webhook.enable);
+        assert!(config.webhook.endpoints.is_empty());
+        assert_eq!(config.webhook.queue_size, 10000);
+        assert_eq!(config.webhook.batch_size, 100);
+        assert_eq!(config.webhook.batch_interval_ms, 500);
+        assert_eq!(config.webhook.max_retries, 3);
+        assert_eq!(config.webhook.retry_interval_ms, 1000);
+        assert_eq!(config.webhook.request_timeout_ms, 5000);
+
         assert_eq!(config.auth.storage_type, "placement".to_string());
         assert_eq!(config.auth.journal_addr, "".to_string());
         assert_eq!(config.auth.mysql_addr, "".to_string());
@@ -242,6 +319,7 @@ mod tests {
         assert_eq!(config.network.websocket_port, 8093);
         assert_eq!(config.network.websockets_port, 8094);
         assert_eq!(config.network.quic_port, 9083);
+        assert!(config.network.quic_enable);
         assert!(!config.network.tls_cert.is_empty());
         assert!(!config.network.tls_key.is_empty());
 
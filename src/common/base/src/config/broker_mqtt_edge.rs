@@ -0,0 +1,174 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use super::common::Log;
+use super::default_mqtt_edge::{
+    default_edge_auth, default_edge_grpc_port, default_edge_http_port, default_edge_log,
+    default_edge_network, default_edge_network_tcp_port, default_edge_storage, default_edge_system,
+    default_max_connection_num,
+};
+use crate::tools::{read_file, try_create_fold};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct BrokerMqttEdgeConfig {
+    pub node_name: String,
+    #[serde(default = "default_edge_network")]
+    pub network: EdgeNetwork,
+    #[serde(default = "default_edge_system")]
+    pub system: EdgeSystem,
+    #[serde(default = "default_edge_storage")]
+    pub storage: EdgeStorage,
+    #[serde(default = "default_edge_auth")]
+    pub auth: EdgeAuth,
+    #[serde(default = "default_edge_log")]
+    pub log: Log,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct EdgeNetwork {
+    #[serde(default = "default_edge_network_tcp_port")]
+    pub tcp_port: u32,
+    #[serde(default = "default_max_connection_num")]
+    pub max_connection_num: usize,
+    #[serde(default = "default_edge_grpc_port")]
+    pub grpc_port: u32,
+    #[serde(default = "default_edge_http_port")]
+    pub http_port: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct EdgeSystem {
+    #[serde(default)]
+    pub runtime_worker_threads: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct EdgeStorage {
+    // Root of the edge data, the metadata and the messages each get a RocksDB instance below it
+    pub data_path: String,
+    #[serde(default)]
+    pub max_open_files: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct EdgeAuth {
+    // JSON file holding the users and ACL rules of the edge node
+    #[serde(default)]
+    pub auth_file: String,
+    #[serde(default)]
+    pub allow_anonymous: bool,
+}
+
+static BROKER_MQTT_EDGE_CONF: OnceLock<BrokerMqttEdgeConfig> = OnceLock::new();
+
+pub fn init_broker_mqtt_edge_conf_by_path(config_path: &str) -> &'static BrokerMqttEdgeConfig {
+    BROKER_MQTT_EDGE_CONF.get_or_init(|| {
+        let content = match read_file(config_path) {
+            Ok(data) => data,
+            Err(e) => {
+                panic!("{}", e.to_string())
+            }
+        };
+        let config: BrokerMqttEdgeConfig = match toml::from_str(&content) {
+            Ok(da) => da,
+            Err(e) => {
+                panic!("{}", e)
+            }
+        };
+        match try_create_fold(&config.log.log_path) {
+            Ok(()) => {}
+            Err(e) => {
+                panic!("{}", e);
+            }
+        }
+        config
+    })
+}
+
+pub fn init_broker_mqtt_edge_conf_by_config(
+    config: BrokerMqttEdgeConfig,
+) -> &'static BrokerMqttEdgeConfig {
+    BROKER_MQTT_EDGE_CONF.get_or_init(|| config)
+}
+
+pub fn broker_mqtt_edge_conf() -> &'static BrokerMqttEdgeConfig {
+    match BROKER_MQTT_EDGE_CONF.get() {
+        Some(config) => config,
+        None => {
+            panic!("MQTT Edge configuration is not initialized, check the configuration file.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BrokerMqttEdgeConfig;
+    use crate::tools::read_file;
+
+    #[test]
+    fn config_default_test() {
+        let path = format!(
+            "{}/../../../config/mqtt-edge.toml",
+            env!("CARGO_MANIFEST_DIR")
+        );
+
+        let content = read_file(&path).unwrap();
+        let config: BrokerMqttEdgeConfig = match toml::from_str(&content) {
+            Ok(da) => da,
+            Err(e) => {
+                panic!("{}", e)
+            }
+        };
+        assert_eq!(config.node_name, "edge-1".to_string());
+        assert_eq!(config.network.tcp_port, 1883);
+        assert_eq!(config.network.max_connection_num, 1000);
+        assert_eq!(config.network.grpc_port, 9981);
+        assert_eq!(config.network.http_port, 9982);
+        assert_eq!(config.system.runtime_worker_threads, 4);
+        assert_eq!(
+            config.storage.data_path,
+            "./robust-data/mqtt-edge/data".to_string()
+        );
+        assert_eq!(config.storage.max_open_files, Some(1000));
+        assert_eq!(
+            config.auth.auth_file,
+            "./config/example/mqtt-edge-auth.json".to_string()
+        );
+        assert!(!config.auth.allow_anonymous);
+        assert_eq!(
+            config.log.log_path,
+            "./robust-data/mqtt-edge/logs".to_string()
+        );
+    }
+
+    #[test]
+    fn config_fill_default_test() {
+        let config: BrokerMqttEdgeConfig = toml::from_str(
+            r#"
+            node_name = "edge-2"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.network.tcp_port, 1883);
+        assert_eq!(config.network.grpc_port, 9981);
+        assert_eq!(config.system.runtime_worker_threads, 4);
+        assert!(!config.storage.data_path.is_empty());
+        assert!(config.auth.auth_file.is_empty());
+        assert!(config.auth.allow_anonymous);
+    }
+}
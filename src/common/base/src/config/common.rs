@@ -40,3 +40,68 @@ pub struct Log {
     pub log_config: String,
     pub log_path: String,
 }
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Telemetry {
+    #[serde(default)]
+    pub enable: bool,
+    // OTLP gRPC endpoint of the collector
+    #[serde(default = "default_telemetry_exporter_endpoint")]
+    pub exporter_endpoint: String,
+}
+
+impl Default for Telemetry {
+    fn default() -> Self {
+        Telemetry {
+            enable: false,
+            exporter_endpoint: default_telemetry_exporter_endpoint(),
+        }
+    }
+}
+
+pub fn default_telemetry_exporter_endpoint() -> String {
+    "http://127.0.0.1:4317".to_string()
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Prometheus {
+    #[serde(default)]
+    pub enable: bool,
+    // "pull" exposes /metrics for scraping, "push" sends to the push gateway
+    #[serde(default = "default_prometheus_model")]
+    pub model: String,
+    #[serde(default = "default_prometheus_port")]
+    pub port: u32,
+    #[serde(default)]
+    pub push_gateway_server: String,
+    // push interval in seconds
+    #[serde(default = "default_prometheus_interval")]
+    pub interval: u32,
+    #[serde(default)]
+    pub header: String,
+}
+
+impl Default for Prometheus {
+    fn default() -> Self {
+        Prometheus {
+            enable: false,
+            model: default_prometheus_model(),
+            port: default_prometheus_port(),
+            push_gateway_server: "".to_string(),
+            interval: default_prometheus_interval(),
+            header: "".to_string(),
+        }
+    }
+}
+
+pub fn default_prometheus_model() -> String {
+    "pull".to_string()
+}
+
+pub fn default_prometheus_port() -> u32 {
+    9090
+}
+
+pub fn default_prometheus_interval() -> u32 {
+    10
+}
@@ -13,13 +13,15 @@
 // limitations under the License.
 
 use super::common::Log;
-use super::journal_server::{Network, Prometheus, Storage, System, TcpThread};
+use super::journal_server::{Network, Storage, System, TcpThread};
 
 pub fn default_network() -> Network {
     Network {
         grpc_port: default_grpc_port(),
         tcp_port: default_network_tcp_port(),
         tcps_port: default_network_tcps_port(),
+        quic_port: default_network_quic_port(),
+        quic_enable: false,
         tls_cert: "".to_string(),
         tls_key: "".to_string(),
     }
@@ -34,9 +36,8 @@ pub fn default_network_tcp_port() -> u32 {
 pub fn default_network_tcps_port() -> u32 {
     3111
 }
-
-pub fn default_prometheus_port() -> u32 {
-    9090
+pub fn default_network_quic_port() -> u32 {
+    3112
 }
 
 pub fn default_system() -> System {
@@ -63,17 +64,6 @@ pub fn default_tcp_thread() -> TcpThread {
     }
 }
 
-pub fn default_prometheus() -> Prometheus {
-    Prometheus {
-        enable: false,
-        model: "pull".to_string(),
-        port: default_prometheus_port(),
-        push_gateway_server: "".to_string(),
-        interval: 10,
-        header: "".to_string(),
-    }
-}
-
 pub fn default_log() -> Log {
     Log {
         log_path: "./logs".to_string(),
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::broker_mqtt::{Network, System, TcpThread};
+use super::broker_mqtt::{Network, System, TcpThread, Webhook};
 use super::common::{Auth, Log, Storage};
 
 pub fn default_grpc_port() -> u32 {
@@ -34,6 +34,7 @@ pub fn default_network() -> Network {
         websocket_port: default_network_websocket_port(),
         websockets_port: default_network_websockets_port(),
         quic_port: default_network_quic_port(),
+        quic_enable: false,
         tls_cert: "".to_string(),
         tls_key: "".to_string(),
     }
@@ -99,3 +100,16 @@ pub fn default_auth() -> Auth {
         mysql_addr: "".to_string(),
     }
 }
+
+pub fn default_webhook() -> Webhook {
+    Webhook {
+        enable: false,
+        endpoints: Vec::new(),
+        queue_size: 10000,
+        batch_size: 100,
+        batch_interval_ms: 500,
+        max_retries: 3,
+        retry_interval_ms: 1000,
+        request_timeout_ms: 5000,
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::broker_mqtt_edge::{EdgeAuth, EdgeNetwork, EdgeStorage, EdgeSystem};
+use super::common::Log;
+
+pub fn default_edge_network() -> EdgeNetwork {
+    EdgeNetwork {
+        tcp_port: default_edge_network_tcp_port(),
+        max_connection_num: default_max_connection_num(),
+        grpc_port: default_edge_grpc_port(),
+        http_port: default_edge_http_port(),
+    }
+}
+
+pub fn default_edge_network_tcp_port() -> u32 {
+    1883
+}
+
+pub fn default_max_connection_num() -> usize {
+    1000
+}
+
+pub fn default_edge_grpc_port() -> u32 {
+    9981
+}
+
+pub fn default_edge_http_port() -> usize {
+    9982
+}
+
+pub fn default_edge_system() -> EdgeSystem {
+    EdgeSystem {
+        runtime_worker_threads: 4,
+    }
+}
+
+pub fn default_edge_storage() -> EdgeStorage {
+    EdgeStorage {
+        data_path: "./robust-data/mqtt-edge/data".to_string(),
+        max_open_files: Some(1000),
+    }
+}
+
+pub fn default_edge_auth() -> EdgeAuth {
+    EdgeAuth {
+        auth_file: "".to_string(),
+        allow_anonymous: true,
+    }
+}
+
+pub fn default_edge_log() -> Log {
+    Log {
+        log_path: "./robust-data/mqtt-edge/logs".to_string(),
+        log_config: "./config/log4rs.yaml".to_string(),
+    }
+}
@@ -32,11 +32,11 @@ use std::sync::OnceLock;
 
 use serde::Deserialize;
 
-use super::common::Log;
+use super::common::{Log, Prometheus, Telemetry};
 use super::default_journal_server::{
-    default_grpc_port, default_log, default_network, default_network_tcp_port,
-    default_network_tcps_port, default_prometheus, default_prometheus_port, default_storage,
-    default_system, default_tcp_thread,
+    default_grpc_port, default_log, default_network, default_network_quic_port,
+    default_network_tcp_port, default_network_tcps_port, default_storage, default_system,
+    default_tcp_thread,
 };
 use crate::tools::{read_file, try_create_fold};
 
@@ -54,10 +54,12 @@ pub struct JournalServerConfig {
     pub storage: Storage,
     #[serde(default = "default_tcp_thread")]
     pub tcp_thread: TcpThread,
-    #[serde(default = "default_prometheus")]
+    #[serde(default)]
     pub prometheus: Prometheus,
     #[serde(default = "default_log")]
     pub log: Log,
+    #[serde(default)]
+    pub telemetry: Telemetry,
 }
 
 #[derive(Debug, Deserialize, Clone, Default)]
@@ -68,6 +70,11 @@ pub struct Network {
     pub tcp_port: u32,
     #[serde(default = "default_network_tcps_port")]
     pub tcps_port: u32,
+    #[serde(default = "default_network_quic_port")]
+    pub quic_port: u32,
+    // The QUIC listener needs tls_cert and tls_key, so it only starts when enabled
+    #[serde(default)]
+    pub quic_enable: bool,
     #[serde(default)]
     pub tls_cert: String,
     #[serde(default)]
@@ -103,22 +110,6 @@ pub struct TcpThread {
     pub response_queue_size: usize,
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
-pub struct Prometheus {
-    #[serde(default)]
-    pub enable: bool,
-    #[serde(default)]
-    pub model: String,
-    #[serde(default = "default_prometheus_port")]
-    pub port: u32,
-    #[serde(default)]
-    pub push_gateway_server: String,
-    #[serde(default)]
-    pub interval: u32,
-    #[serde(default)]
-    pub header: String,
-}
-
 static STORAGE_ENGINE_CONFIG: OnceLock<JournalServerConfig> = OnceLock::new();
 
 pub fn init_journal_server_conf_by_path(config_path: &str) -> &'static JournalServerConfig {
@@ -188,6 +179,8 @@ mod tests {
         assert_eq!(conf.network.grpc_port, 2228);
         assert_eq!(conf.network.tcp_port, 3110);
         assert_eq!(conf.network.tcps_port, 3111);
+        assert_eq!(conf.network.quic_port, 3112);
+        assert!(conf.network.quic_enable);
 
         assert_eq!(conf.system.runtime_work_threads, 100);
 
@@ -34,7 +34,7 @@ use serde::{Deserialize, Serialize};
 use toml::map::Map;
 use toml::{Table, Value};
 
-use super::common::Log;
+use super::common::{Log, Telemetry};
 use super::default_placement_center::{
     default_cluster_name, default_data_path, default_grpc_port, default_heartbeat,
     default_heartbeat_check_time_ms, default_heartbeat_timeout_ms, default_http_port, default_log,
@@ -59,6 +59,8 @@ pub struct PlacementCenterConfig {
     pub rocksdb: Rocksdb,
     #[serde(default = "default_log")]
     pub log: Log,
+    #[serde(default)]
+    pub telemetry: Telemetry,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
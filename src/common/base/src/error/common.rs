@@ -68,6 +68,12 @@ pub enum CommonError {
     #[error("{0}")]
     FromParseIntError(#[from] ParseIntError),
 
+    #[error("{0}")]
+    FromPrometheusError(#[from] prometheus::Error),
+
+    #[error("{0}")]
+    FromJoinError(#[from] tokio::task::JoinError),
+
     #[error("{0}")]
     CommonError(String),
 
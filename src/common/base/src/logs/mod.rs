@@ -12,7 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::config::broker_amqp::broker_amqp_conf;
 use crate::config::broker_mqtt::broker_mqtt_conf;
+use crate::config::broker_mqtt_edge::broker_mqtt_edge_conf;
 use crate::config::journal_server::journal_server_conf;
 use crate::config::placement_center::placement_center_conf;
 use crate::tools::{file_exists, read_file, try_create_fold};
@@ -27,6 +29,16 @@ pub fn init_broker_mqtt_log() {
     init_log(&conf.log.log_config, &conf.log.log_path);
 }
 
+pub fn init_broker_mqtt_edge_log() {
+    let conf = broker_mqtt_edge_conf();
+    init_log(&conf.log.log_config, &conf.log.log_path);
+}
+
+pub fn init_broker_amqp_log() {
+    let conf = broker_amqp_conf();
+    init_log(&conf.log.log_config, &conf.log.log_path);
+}
+
 pub fn init_journal_server_log() {
     let conf = journal_server_conf();
     init_log(&conf.log.log_config, &conf.log.log_path);
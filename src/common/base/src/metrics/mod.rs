@@ -13,6 +13,7 @@
 // limitations under the License.
 
 pub mod broker;
+pub mod push;
 use axum::routing::get;
 use axum::Router;
 use log::info;
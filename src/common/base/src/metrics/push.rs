@@ -0,0 +1,111 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use log::{debug, error, info};
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+
+use crate::config::common::Prometheus;
+use crate::error::common::CommonError;
+
+pub const PROMETHEUS_MODEL_PUSH: &str = "push";
+
+// Push the default registry to the push gateway every `interval` seconds until the
+// stop signal is received. Used by nodes that cannot be scraped, e.g. behind NAT.
+pub async fn start_prometheus_push(
+    conf: Prometheus,
+    job: String,
+    grouping: HashMap<String, String>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let interval = Duration::from_secs(conf.interval.max(1) as u64);
+    let mut stop_recv = stop_send.subscribe();
+    info!(
+        "Prometheus push thread started successfully, push gateway: {}, interval: {}s",
+        conf.push_gateway_server,
+        interval.as_secs()
+    );
+    loop {
+        select! {
+            val = stop_recv.recv() => {
+                if let Ok(flag) = val {
+                    if flag {
+                        debug!("{}", "Prometheus push thread exited successfully");
+                        break;
+                    }
+                }
+            }
+            _ = sleep(interval) => {
+                if let Err(e) = push_metrics(&conf.push_gateway_server, &job, &grouping).await {
+                    error!(
+                        "Failed to push metrics to the push gateway {}, error message: {}",
+                        conf.push_gateway_server, e
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub async fn push_metrics(
+    push_gateway_server: &str,
+    job: &str,
+    grouping: &HashMap<String, String>,
+) -> Result<(), CommonError> {
+    let url = push_gateway_server.to_string();
+    let job = job.to_string();
+    let grouping = grouping.clone();
+    // the push client is blocking, keep it off the async workers
+    tokio::task::spawn_blocking(move || {
+        prometheus::push_metrics(&job, grouping, &url, prometheus::gather(), None)
+    })
+    .await??;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::push_metrics;
+
+    #[tokio::test]
+    async fn push_metrics_test() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let gateway = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 4096];
+            let len = stream.read(&mut buf).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .unwrap();
+            String::from_utf8_lossy(&buf[..len]).to_string()
+        });
+
+        let mut grouping = HashMap::new();
+        grouping.insert("cluster".to_string(), "c1".to_string());
+        push_metrics(&addr, "mqtt-broker", &grouping).await.unwrap();
+
+        let request = gateway.join().unwrap();
+        assert!(request.starts_with("PUT /metrics/job/mqtt-broker/cluster/c1 "));
+    }
+}
@@ -0,0 +1,337 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use opentelemetry::propagation::{Extractor, Injector};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::{global, Context, KeyValue};
+use opentelemetry_otlp::{SpanExporterBuilder, WithExportConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{config, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
+use tonic::codegen::http;
+use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue, KeyAndValueRef, MetadataMap};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+
+use crate::config::common::Telemetry;
+use crate::error::common::CommonError;
+
+/// W3C trace context key, also accepted as an MQTT 5 user property
+pub const TRACE_PARENT: &str = "traceparent";
+
+pub const SERVICE_NAME_MQTT_BROKER: &str = "robustmq-mqtt-broker";
+pub const SERVICE_NAME_PLACEMENT_CENTER: &str = "robustmq-placement-center";
+pub const SERVICE_NAME_JOURNAL_SERVER: &str = "robustmq-journal-server";
+
+static TELEMETRY_INITIALIZED: Mutex<bool> = Mutex::new(false);
+
+/// Exports the `tracing` spans of this process to an OTLP collector.
+///
+/// Must be called inside a tokio runtime, the batch exporter runs on it. When telemetry is
+/// disabled no subscriber is installed, spans stay no-ops and no context is propagated.
+/// Calling it again once telemetry is initialized does nothing.
+pub fn init_telemetry(service_name: &str, conf: &Telemetry) -> Result<(), CommonError> {
+    if !conf.enable {
+        return Ok(());
+    }
+
+    let mut initialized = match TELEMETRY_INITIALIZED.lock() {
+        Ok(initialized) => initialized,
+        Err(e) => return Err(CommonError::CommonError(e.to_string())),
+    };
+    if *initialized {
+        return Ok(());
+    }
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let provider = build_tracer_provider(service_name, &conf.exporter_endpoint)?;
+    let tracer = provider.tracer(service_name.to_string());
+    global::set_tracer_provider(provider);
+
+    // Installed with set_global_default rather than try_init, which would also register a
+    // LogTracer as the `log` logger and make log4rs fail to initialize.
+    let subscriber =
+        tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|e| CommonError::CommonError(e.to_string()))?;
+    *initialized = true;
+    Ok(())
+}
+
+/// Flushes the spans that are still buffered, called when the server stops.
+pub fn shutdown_telemetry() {
+    global::shutdown_tracer_provider();
+}
+
+pub fn build_tracer_provider(
+    service_name: &str,
+    exporter_endpoint: &str,
+) -> Result<TracerProvider, CommonError> {
+    let exporter = SpanExporterBuilder::from(
+        opentelemetry_otlp::new_exporter()
+            .tonic()
+            .with_endpoint(exporter_endpoint),
+    )
+    .build_span_exporter()
+    .map_err(|e| CommonError::CommonError(e.to_string()))?;
+
+    Ok(TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_config(config().with_resource(Resource::new(vec![KeyValue::new(
+            "service.name",
+            service_name.to_string(),
+        )])))
+        .build())
+}
+
+/// Trace context of the current span, to be carried in a request header.
+pub fn current_trace_context() -> HashMap<String, String> {
+    let mut carrier = HashMap::new();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&Span::current().context(), &mut carrier)
+    });
+    carrier
+}
+
+pub fn extract_trace_context(carrier: &HashMap<String, String>) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(carrier))
+}
+
+pub fn set_span_parent(span: &Span, parent: Context) {
+    span.set_parent(parent);
+}
+
+/// Parent context sent by an MQTT 5 client in the `traceparent` user property, if any.
+pub fn extract_user_properties_context(user_properties: &[(String, String)]) -> Option<Context> {
+    let carrier: HashMap<String, String> = user_properties
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case(TRACE_PARENT))
+        .map(|(_, value)| (TRACE_PARENT.to_string(), value.clone()))
+        .collect();
+    if carrier.is_empty() {
+        return None;
+    }
+    Some(extract_trace_context(&carrier))
+}
+
+/// Wraps a gRPC message, carrying the trace context of the current span in its metadata.
+pub fn grpc_request<T>(message: T) -> tonic::Request<T> {
+    let mut request = tonic::Request::new(message);
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(
+            &Span::current().context(),
+            &mut MetadataInjector(request.metadata_mut()),
+        )
+    });
+    request
+}
+
+/// Span of an incoming gRPC request, a child of the caller's span when the request carries
+/// trace context. Used with `tonic::transport::Server::trace_fn`.
+pub fn grpc_server_span(request: &http::Request<()>) -> Span {
+    let span = tracing::info_span!("grpc_server", path = %request.uri().path());
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    span.set_parent(parent);
+    span
+}
+
+struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+impl Injector for MetadataInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(key), Ok(value)) = (
+            AsciiMetadataKey::from_bytes(key.as_bytes()),
+            AsciiMetadataValue::try_from(&value),
+        ) {
+            self.0.insert(key, value);
+        }
+    }
+}
+
+struct HeaderExtractor<'a>(&'a http::HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+pub struct MetadataExtractor<'a>(pub &'a MetadataMap);
+
+impl Extractor for MetadataExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0
+            .iter()
+            .filter_map(|entry| match entry {
+                KeyAndValueRef::Ascii(key, _) => Some(key.as_str()),
+                KeyAndValueRef::Binary(_, _) => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    use opentelemetry::trace::TraceContextExt;
+    use opentelemetry_proto::tonic::collector::trace::v1::trace_service_server::{
+        TraceService, TraceServiceServer,
+    };
+    use opentelemetry_proto::tonic::collector::trace::v1::{
+        ExportTraceServiceRequest, ExportTraceServiceResponse,
+    };
+    use tokio::sync::mpsc;
+    use tokio::time::{sleep, timeout};
+    use tonic::{Request, Response, Status};
+    use tracing::Instrument;
+
+    use super::*;
+
+    // Stands in for an OTLP collector, forwarding every exported span as (name, trace_id, parent_span_id)
+    struct TestCollector {
+        sender: mpsc::UnboundedSender<(String, Vec<u8>, Vec<u8>)>,
+    }
+
+    #[tonic::async_trait]
+    impl TraceService for TestCollector {
+        async fn export(
+            &self,
+            request: Request<ExportTraceServiceRequest>,
+        ) -> Result<Response<ExportTraceServiceResponse>, Status> {
+            for resource_spans in request.into_inner().resource_spans {
+                for scope_spans in resource_spans.scope_spans {
+                    for span in scope_spans.spans {
+                        let _ = self
+                            .sender
+                            .send((span.name, span.trace_id, span.parent_span_id));
+                    }
+                }
+            }
+            Ok(Response::new(ExportTraceServiceResponse::default()))
+        }
+    }
+
+    #[test]
+    fn user_properties_context_test() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let user_properties = vec![
+            ("k1".to_string(), "v1".to_string()),
+            (
+                TRACE_PARENT.to_string(),
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string(),
+            ),
+        ];
+        let context = extract_user_properties_context(&user_properties).unwrap();
+        assert_eq!(
+            context.span().span_context().trace_id().to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+        assert!(extract_user_properties_context(&user_properties[..1]).is_none());
+    }
+
+    struct NopLogger;
+
+    impl log::Log for NopLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            false
+        }
+
+        fn log(&self, _: &log::Record) {}
+
+        fn flush(&self) {}
+    }
+
+    #[tokio::test]
+    async fn init_telemetry_test() {
+        let conf = Telemetry {
+            enable: true,
+            exporter_endpoint: "http://127.0.0.1:4317".to_string(),
+        };
+        init_telemetry("robustmq-test", &conf).unwrap();
+        init_telemetry("robustmq-test", &conf).unwrap();
+
+        // The `log` logger is left for log4rs
+        assert!(log::set_boxed_logger(Box::new(NopLogger)).is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn otlp_export_test() {
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(TraceServiceServer::new(TestCollector { sender }))
+                .serve(addr),
+        );
+        sleep(Duration::from_millis(200)).await;
+
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = build_tracer_provider("robustmq-test", &format!("http://{}", addr)).unwrap();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("robustmq-test")));
+
+        {
+            let _default = tracing::subscriber::set_default(subscriber);
+
+            // The context crosses a process boundary as a header map, like the journal ReqHeader
+            let carrier = async { current_trace_context() }
+                .instrument(tracing::info_span!("mqtt_publish"))
+                .await;
+            assert!(carrier.contains_key(TRACE_PARENT));
+
+            let span = tracing::info_span!("journal_write");
+            span.set_parent(extract_trace_context(&carrier));
+            span.in_scope(|| {
+                let request = grpc_request(());
+                assert!(request.metadata().get(TRACE_PARENT).is_some());
+            });
+        }
+        for result in provider.force_flush() {
+            result.unwrap();
+        }
+
+        let mut spans = Vec::new();
+        while spans.len() < 2 {
+            let span = timeout(Duration::from_secs(10), receiver.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            spans.push(span);
+        }
+        let publish = spans.iter().find(|span| span.0 == "mqtt_publish").unwrap();
+        let write = spans.iter().find(|span| span.0 == "journal_write").unwrap();
+        assert!(publish.2.is_empty());
+        assert_eq!(publish.1, write.1);
+        assert!(!write.2.is_empty());
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::str::FromStr;
+
+use common_base::error::common::CommonError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum AmqpExchangeType {
+    Direct,
+    Fanout,
+    Topic,
+}
+
+impl FromStr for AmqpExchangeType {
+    type Err = CommonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "direct" => Ok(AmqpExchangeType::Direct),
+            "fanout" => Ok(AmqpExchangeType::Fanout),
+            "topic" => Ok(AmqpExchangeType::Topic),
+            _ => Err(CommonError::CommonError(format!(
+                "Exchange type {} is not supported",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for AmqpExchangeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                AmqpExchangeType::Direct => "direct",
+                AmqpExchangeType::Fanout => "fanout",
+                AmqpExchangeType::Topic => "topic",
+            }
+        )
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct AmqpExchange {
+    pub cluster_name: String,
+    pub exchange_name: String,
+    pub exchange_type: AmqpExchangeType,
+    pub durable: bool,
+    pub auto_delete: bool,
+    pub internal: bool,
+}
+
+impl AmqpExchange {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
@@ -0,0 +1,50 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::common::CommonError;
+use common_base::tools::now_second;
+use serde::{Deserialize, Serialize};
+
+use crate::adapter::record::Record;
+
+/// Message stored in the shard of an AMQP queue
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct AmqpMessage {
+    pub exchange: String,
+    pub routing_key: String,
+    // Encoded content header frame payload, kept as is to be sent back on delivery
+    pub header: Vec<u8>,
+    pub body: Vec<u8>,
+    pub create_time: u64,
+}
+
+impl AmqpMessage {
+    pub fn new(exchange: String, routing_key: String, header: Vec<u8>, body: Vec<u8>) -> Self {
+        AmqpMessage {
+            exchange,
+            routing_key,
+            header,
+            body,
+            create_time: now_second(),
+        }
+    }
+
+    pub fn build_record(&self) -> Record {
+        Record::build_byte(serde_json::to_vec(&self).unwrap())
+    }
+
+    pub fn decode_record(record: Record) -> Result<AmqpMessage, CommonError> {
+        Ok(serde_json::from_slice(&record.data)?)
+    }
+}
@@ -14,6 +14,7 @@
 
 pub mod acl;
 pub mod adapter;
+pub mod amqp;
 pub mod journal;
 pub mod mqtt;
 pub mod placement;
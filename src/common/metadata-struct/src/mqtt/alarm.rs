@@ -0,0 +1,95 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use common_base::error::common::CommonError;
+use common_base::tools::now_second;
+use serde::{Deserialize, Serialize};
+
+/// One activation of a named alarm on a broker node. It is active from `activate_time`
+/// until `deactivate_time` is set, and is kept in the placement center afterwards as
+/// alarm history.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct MqttAlarm {
+    pub name: String,
+    pub broker_id: u64,
+    pub message: String,
+    pub details: HashMap<String, String>,
+    pub activate_time: u64,
+    // 0 while the alarm is active
+    pub deactivate_time: u64,
+}
+
+impl MqttAlarm {
+    pub fn new(
+        name: String,
+        broker_id: u64,
+        message: String,
+        details: HashMap<String, String>,
+    ) -> Self {
+        MqttAlarm {
+            name,
+            broker_id,
+            message,
+            details,
+            activate_time: now_second(),
+            deactivate_time: 0,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.deactivate_time == 0
+    }
+
+    pub fn deactivate(&mut self, message: String, details: HashMap<String, String>) {
+        self.message = message;
+        self.details = details;
+        self.deactivate_time = now_second();
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
+        Ok(serde_json::to_vec(&self)?)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::MqttAlarm;
+
+    #[test]
+    fn alarm_lifecycle_test() {
+        let mut alarm = MqttAlarm::new(
+            "high_memory".to_string(),
+            1,
+            "memory usage 90% is above 80%".to_string(),
+            HashMap::from([("usage".to_string(), "90".to_string())]),
+        );
+        assert!(alarm.is_active());
+        assert_eq!(MqttAlarm::decode(&alarm.encode().unwrap()).unwrap(), alarm);
+
+        alarm.deactivate(
+            "memory usage 50% is back below 80%".to_string(),
+            HashMap::new(),
+        );
+        assert!(!alarm.is_active());
+        assert!(alarm.deactivate_time >= alarm.activate_time);
+    }
+}
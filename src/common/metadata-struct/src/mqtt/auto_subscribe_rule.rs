@@ -0,0 +1,61 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::common::CommonError;
+use common_base::tools::now_second;
+use protocol::mqtt::common::{QoS, RetainForwardRule};
+use serde::{Deserialize, Serialize};
+
+/// A subscription the broker creates for a client when it connects. `topic` is a
+/// template in which `${clientid}` and `${username}` are replaced by the values of
+/// the connecting client. A rule with an empty `username` applies to every user.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct MqttAutoSubscribeRule {
+    pub topic: String,
+    pub qos: QoS,
+    pub no_local: bool,
+    pub retain_as_published: bool,
+    pub retained_handling: RetainForwardRule,
+    pub username: String,
+    pub create_time: u64,
+}
+
+impl MqttAutoSubscribeRule {
+    pub fn new(
+        topic: String,
+        qos: QoS,
+        no_local: bool,
+        retain_as_published: bool,
+        retained_handling: RetainForwardRule,
+        username: String,
+    ) -> Self {
+        MqttAutoSubscribeRule {
+            topic,
+            qos,
+            no_local,
+            retain_as_published,
+            retained_handling,
+            username,
+            create_time: now_second(),
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
+        Ok(serde_json::to_vec(&self)?)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
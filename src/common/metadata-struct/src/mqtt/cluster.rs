@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+
 use protocol::mqtt::common::QoS;
 use serde::{Deserialize, Serialize};
 
@@ -23,6 +25,10 @@ pub struct MqttClusterDynamicConfig {
     pub security: MqttClusterDynamicConfigSecurity,
     pub network: MqttClusterDynamicConfigNetwork,
     pub slow: MqttClusterDynamicSlowSub,
+    #[serde(default)]
+    pub sysmon: MqttClusterDynamicSysmon,
+    #[serde(default)]
+    pub alarm: MqttClusterDynamicAlarm,
 }
 
 // MQTT cluster protocol related dynamic configuration
@@ -37,6 +43,66 @@ pub struct MqttClusterDynamicConfigProtocol {
     pub receive_max: u16,
     pub max_message_expiry_interval: u64,
     pub client_pkid_persistent: bool,
+    // Keep the unfinished QoS 2 exchanges of every client in the placement center, so a
+    // broker restart does not deliver a QoS 2 message twice
+    #[serde(default = "default_qos2_state_persistent")]
+    pub qos2_state_persistent: bool,
+    // Interval after which an unacknowledged QoS1/QoS2 message is retransmitted with DUP set
+    #[serde(default = "default_inflight_retry_interval_ms")]
+    pub inflight_retry_interval_ms: u64,
+    // Maximum number of messages buffered for a session while the inflight window is full
+    #[serde(default = "default_max_message_queue_len")]
+    pub max_message_queue_len: u64,
+    #[serde(default)]
+    pub message_queue_drop_policy: MessageQueueDropPolicy,
+    // Maximum number of retained messages in the cluster, 0 means unlimited
+    #[serde(default = "default_max_retain_message_num")]
+    pub max_retain_message_num: u64,
+    // Maximum payload size in bytes of a retained message, 0 means unlimited
+    #[serde(default = "default_max_retain_message_size")]
+    pub max_retain_message_size: u64,
+    // Expiry interval in seconds of retained messages published without a Message Expiry
+    // Interval, 0 means they never expire
+    #[serde(default)]
+    pub retain_message_expiry_interval: u64,
+    // Maximum delay in seconds of a message published to $delayed/{seconds}/{topic},
+    // 0 means unlimited
+    #[serde(default = "default_max_delay_message_interval")]
+    pub max_delay_message_interval: u64,
+}
+
+fn default_inflight_retry_interval_ms() -> u64 {
+    30000
+}
+
+fn default_qos2_state_persistent() -> bool {
+    true
+}
+
+fn default_max_message_queue_len() -> u64 {
+    1000
+}
+
+fn default_max_retain_message_num() -> u64 {
+    100000
+}
+
+fn default_max_retain_message_size() -> u64 {
+    1024 * 1024
+}
+
+fn default_max_delay_message_interval() -> u64 {
+    4294967
+}
+
+// Which message is discarded when the message queue of a session that ends with its connection
+// is full, persistent sessions stop reading from storage instead
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub enum MessageQueueDropPolicy {
+    #[default]
+    DropOldest,
+    DropNewest,
+    DropLowestQos,
 }
 
 // MQTT cluster security related dynamic configuration
@@ -65,14 +131,121 @@ pub struct MqttClusterDynamicConfigFeature {
     pub subscription_identifiers_available: AvailableFlag,
     pub shared_subscription_available: AvailableFlag,
     pub exclusive_subscription_available: AvailableFlag,
+    // Strategy used to pick the subscriber of a shared subscription group for each message
+    #[serde(default)]
+    pub shared_subscription_strategy: SharedSubscriptionStrategy,
+    // Per group overrides of shared_subscription_strategy, keyed by share group name
+    #[serde(default)]
+    pub shared_subscription_group_strategy: HashMap<String, SharedSubscriptionStrategy>,
+}
+
+impl MqttClusterDynamicConfigFeature {
+    pub fn get_shared_subscription_strategy(&self, group_name: &str) -> SharedSubscriptionStrategy {
+        if let Some(strategy) = self.shared_subscription_group_strategy.get(group_name) {
+            return strategy.clone();
+        }
+        self.shared_subscription_strategy.clone()
+    }
+}
+
+// How a message published to a shared subscription group is dispatched to its subscribers
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub enum SharedSubscriptionStrategy {
+    Random,
+    #[default]
+    RoundRobin,
+    // Keep sending to the same subscriber until it disconnects
+    Sticky,
+    // Messages from the same publisher always go to the same subscriber
+    HashClientId,
+    // Messages on the same topic always go to the same subscriber
+    HashTopic,
+    // Prefer subscribers connected to this broker over those forwarded from other brokers
+    LocalFirst,
 }
 
 #[derive(Serialize, Deserialize, Default, Clone)]
 pub struct MqttClusterDynamicSlowSub {
     pub enable: bool,
+    // A delivery is slow when the time from message creation to the packet being
+    // written exceeds whole_ms, or one of its stages exceeds its threshold (0 disables it):
+    // internal_ms from message creation until the broker starts sending it,
+    // response_ms for writing the packet to the client
     pub whole_ms: u64,
     pub internal_ms: u32,
     pub response_ms: u32,
+    // Number of slowest subscriptions kept in memory by each broker
+    #[serde(default = "default_slow_sub_top_k")]
+    pub top_k: u64,
+    // Records older than this are evicted, 0 keeps them until displaced by slower ones
+    #[serde(default = "default_slow_sub_expire_interval_sec")]
+    pub expire_interval_sec: u64,
+}
+
+fn default_slow_sub_top_k() -> u64 {
+    1000
+}
+
+fn default_slow_sub_expire_interval_sec() -> u64 {
+    300
+}
+
+// Thresholds of the system monitor that publishes alerts to $SYS/brokers/${node}/sysmon/*
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MqttClusterDynamicSysmon {
+    pub enable: bool,
+    // The runtime is considered stalled when a 1s tick is delayed by more than this
+    pub long_schedule_ms: u64,
+    // A network queue holding more packets than this is reported as busy
+    pub busy_queue_len: u64,
+    // Alert when the broker process uses more than this share of the system memory
+    pub high_memory_percent: u64,
+    // Minimum interval in seconds between two alerts of the same kind
+    pub alert_interval_sec: u64,
+}
+
+impl Default for MqttClusterDynamicSysmon {
+    fn default() -> Self {
+        MqttClusterDynamicSysmon {
+            enable: true,
+            long_schedule_ms: 240,
+            busy_queue_len: 80,
+            high_memory_percent: 70,
+            alert_interval_sec: 60,
+        }
+    }
+}
+
+// Thresholds of the alarms raised on $SYS/brokers/${node}/alarms/*, a threshold of 0
+// disables the corresponding alarm
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MqttClusterDynamicAlarm {
+    pub enable: bool,
+    pub check_interval_sec: u64,
+    // Share of network.tcp_max_connection_num in use
+    pub connection_high_percent: u64,
+    // Share of the available cores used by the broker process
+    pub cpu_high_percent: u64,
+    // Share of the system memory used by the broker process
+    pub memory_high_percent: u64,
+    // Failed message storage writes within one check interval
+    pub storage_write_failure_num: u64,
+    // Slow subscription deliveries within one check interval
+    pub slow_subscribe_num: u64,
+}
+
+impl Default for MqttClusterDynamicAlarm {
+    fn default() -> Self {
+        MqttClusterDynamicAlarm {
+            enable: true,
+            check_interval_sec: 10,
+            connection_high_percent: 90,
+            cpu_high_percent: 80,
+            memory_high_percent: 80,
+            storage_write_failure_num: 1,
+            slow_subscribe_num: 100,
+        }
+    }
 }
 
 impl MqttClusterDynamicConfig {
@@ -87,7 +260,15 @@ impl MqttClusterDynamicConfig {
                 default_server_keep_alive: 60,
                 receive_max: 65535,
                 client_pkid_persistent: false,
+                qos2_state_persistent: default_qos2_state_persistent(),
                 max_message_expiry_interval: 3600,
+                inflight_retry_interval_ms: default_inflight_retry_interval_ms(),
+                max_message_queue_len: default_max_message_queue_len(),
+                message_queue_drop_policy: MessageQueueDropPolicy::DropOldest,
+                max_retain_message_num: default_max_retain_message_num(),
+                max_retain_message_size: default_max_retain_message_size(),
+                retain_message_expiry_interval: 0,
+                max_delay_message_interval: default_max_delay_message_interval(),
             },
             feature: MqttClusterDynamicConfigFeature {
                 retain_available: AvailableFlag::Enable,
@@ -95,6 +276,8 @@ impl MqttClusterDynamicConfig {
                 subscription_identifiers_available: AvailableFlag::Enable,
                 shared_subscription_available: AvailableFlag::Enable,
                 exclusive_subscription_available: AvailableFlag::Enable,
+                shared_subscription_strategy: SharedSubscriptionStrategy::RoundRobin,
+                shared_subscription_group_strategy: HashMap::new(),
             },
             security: MqttClusterDynamicConfigSecurity {
                 secret_free_login: false,
@@ -113,7 +296,11 @@ impl MqttClusterDynamicConfig {
                 whole_ms: 0,
                 internal_ms: 0,
                 response_ms: 0,
+                top_k: default_slow_sub_top_k(),
+                expire_interval_sec: default_slow_sub_expire_interval_sec(),
             },
+            sysmon: MqttClusterDynamicSysmon::default(),
+            alarm: MqttClusterDynamicAlarm::default(),
         }
     }
 
@@ -131,11 +318,61 @@ pub enum AvailableFlag {
 
 #[cfg(test)]
 mod tests {
-    use crate::mqtt::cluster::AvailableFlag;
+    use crate::mqtt::cluster::{
+        AvailableFlag, MqttClusterDynamicConfig, MqttClusterDynamicConfigProtocol,
+        SharedSubscriptionStrategy,
+    };
 
     #[test]
     fn client34_connect_test() {
         assert_eq!(AvailableFlag::Disable as u8, 0);
         assert_eq!(AvailableFlag::Enable as u8, 1);
     }
+
+    #[test]
+    fn shared_subscription_strategy_test() {
+        let mut config = MqttClusterDynamicConfig::new();
+        config
+            .feature
+            .shared_subscription_group_strategy
+            .insert("g1".to_string(), SharedSubscriptionStrategy::HashTopic);
+        assert_eq!(
+            config.feature.get_shared_subscription_strategy("g1"),
+            SharedSubscriptionStrategy::HashTopic
+        );
+        assert_eq!(
+            config.feature.get_shared_subscription_strategy("g2"),
+            SharedSubscriptionStrategy::RoundRobin
+        );
+    }
+
+    #[test]
+    fn qos2_state_persistent_default_test() {
+        assert!(
+            MqttClusterDynamicConfig::new()
+                .protocol
+                .qos2_state_persistent
+        );
+
+        // Configs stored before the option existed persist the QoS 2 state as well
+        let mut protocol = serde_json::to_value(MqttClusterDynamicConfig::new().protocol).unwrap();
+        protocol
+            .as_object_mut()
+            .unwrap()
+            .remove("qos2_state_persistent");
+        let protocol: MqttClusterDynamicConfigProtocol = serde_json::from_value(protocol).unwrap();
+        assert!(protocol.qos2_state_persistent);
+        assert!(!protocol.client_pkid_persistent);
+    }
+
+    #[test]
+    fn max_delay_message_interval_default_test() {
+        let mut protocol = serde_json::to_value(MqttClusterDynamicConfig::new().protocol).unwrap();
+        protocol
+            .as_object_mut()
+            .unwrap()
+            .remove("max_delay_message_interval");
+        let protocol: MqttClusterDynamicConfigProtocol = serde_json::from_value(protocol).unwrap();
+        assert_eq!(protocol.max_delay_message_interval, 4294967);
+    }
 }
@@ -101,8 +101,17 @@ impl MQTTConnection {
         self.receive_qos_message.fetch_add(1, Ordering::Relaxed);
     }
 
+    // A QoS 2 exchange restored from a previous connection was never counted on this one
     pub fn recv_qos_message_decr(&self) {
-        self.receive_qos_message.fetch_add(-1, Ordering::Relaxed);
+        let _ =
+            self.receive_qos_message
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |num| {
+                    if num > 0 {
+                        Some(num - 1)
+                    } else {
+                        None
+                    }
+                });
     }
 
     pub fn get_send_qos_message(&self) -> isize {
@@ -12,11 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod alarm;
+pub mod auto_subscribe_rule;
 pub mod cluster;
 pub mod connection;
 pub mod lastwill;
 pub mod message;
 pub mod node_extend;
+pub mod qos2_state;
 pub mod session;
+pub mod subscribe_data;
 pub mod topic;
+pub mod topic_rewrite_rule;
 pub mod user;
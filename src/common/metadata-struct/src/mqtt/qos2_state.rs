@@ -0,0 +1,123 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::common::CommonError;
+use common_base::tools::now_second;
+use serde::{Deserialize, Serialize};
+
+pub const QOS2_STATE_DIRECTION_RECEIVE: &str = "receive";
+pub const QOS2_STATE_DIRECTION_SEND: &str = "send";
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub enum Qos2Stage {
+    // Received from the client, stored and acknowledged with PUBREC, waiting for PUBREL
+    #[default]
+    PubRecSent,
+    // Sent to the client, waiting for PUBREC
+    WaitPubRec,
+    // Released with PUBREL after the client sent PUBREC, waiting for PUBCOMP
+    WaitPubComp,
+}
+
+/// The state of a QoS 2 exchange between the broker and a client that has not completed
+/// yet. It is kept outside the broker so that a broker restarting between PUBREC and
+/// PUBREL neither stores a resent message twice nor delivers a message twice.
+/// `group_name` and `offset` locate the message of an outbound exchange.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct MqttQos2State {
+    pub client_id: String,
+    pub pkid: u16,
+    pub stage: Qos2Stage,
+    pub group_name: String,
+    pub offset: u64,
+    pub create_time: u64,
+}
+
+impl MqttQos2State {
+    pub fn new_receive(client_id: String, pkid: u16) -> Self {
+        MqttQos2State {
+            client_id,
+            pkid,
+            stage: Qos2Stage::PubRecSent,
+            create_time: now_second(),
+            ..Default::default()
+        }
+    }
+
+    pub fn new_send(
+        client_id: String,
+        pkid: u16,
+        stage: Qos2Stage,
+        group_name: String,
+        offset: u64,
+    ) -> Self {
+        MqttQos2State {
+            client_id,
+            pkid,
+            stage,
+            group_name,
+            offset,
+            create_time: now_second(),
+        }
+    }
+
+    pub fn is_receive(&self) -> bool {
+        self.stage == Qos2Stage::PubRecSent
+    }
+
+    // Packet identifiers of inbound and outbound exchanges are independent of each other
+    pub fn direction(&self) -> &'static str {
+        if self.is_receive() {
+            QOS2_STATE_DIRECTION_RECEIVE
+        } else {
+            QOS2_STATE_DIRECTION_SEND
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
+        Ok(serde_json::to_vec(&self)?)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        MqttQos2State, Qos2Stage, QOS2_STATE_DIRECTION_RECEIVE, QOS2_STATE_DIRECTION_SEND,
+    };
+
+    #[test]
+    fn qos2_state_direction_test() {
+        let state = MqttQos2State::new_receive("c1".to_string(), 1);
+        assert!(state.is_receive());
+        assert_eq!(state.direction(), QOS2_STATE_DIRECTION_RECEIVE);
+
+        let state = MqttQos2State::new_send(
+            "c1".to_string(),
+            1,
+            Qos2Stage::WaitPubComp,
+            "g1".to_string(),
+            10,
+        );
+        assert!(!state.is_receive());
+        assert_eq!(state.direction(), QOS2_STATE_DIRECTION_SEND);
+        assert_eq!(
+            MqttQos2State::decode(&state.encode().unwrap()).unwrap(),
+            state
+        );
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::tools::now_second;
+use protocol::mqtt::common::{Filter, MqttProtocol, SubscribeProperties};
+use serde::{Deserialize, Serialize};
+
+/// A subscription of a client as persisted in the placement center, so that it
+/// outlives the broker that received the SUBSCRIBE.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MqttSubscribe {
+    pub client_id: String,
+    pub path: String,
+    pub broker_id: u64,
+    pub protocol: MqttProtocol,
+    pub filter: Filter,
+    pub pkid: u16,
+    pub subscribe_properties: Option<SubscribeProperties>,
+    pub create_time: u64,
+}
+
+impl MqttSubscribe {
+    pub fn new(
+        client_id: String,
+        broker_id: u64,
+        protocol: MqttProtocol,
+        filter: Filter,
+        pkid: u16,
+        subscribe_properties: Option<SubscribeProperties>,
+    ) -> Self {
+        MqttSubscribe {
+            client_id,
+            path: filter.path.clone(),
+            broker_id,
+            protocol,
+            filter,
+            pkid,
+            subscribe_properties,
+            create_time: now_second(),
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use common_base::error::common::CommonError;
+use common_base::tools::now_second;
+use serde::{Deserialize, Serialize};
+
+/// Which packets a topic rewrite rule applies to.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub enum TopicRewriteAction {
+    #[default]
+    All,
+    Publish,
+    Subscribe,
+}
+
+impl fmt::Display for TopicRewriteAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                TopicRewriteAction::All => "All",
+                TopicRewriteAction::Publish => "Publish",
+                TopicRewriteAction::Subscribe => "Subscribe",
+            }
+        )
+    }
+}
+
+impl TryFrom<&str> for TopicRewriteAction {
+    type Error = CommonError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "All" => Ok(TopicRewriteAction::All),
+            "Publish" => Ok(TopicRewriteAction::Publish),
+            "Subscribe" => Ok(TopicRewriteAction::Subscribe),
+            _ => Err(CommonError::CommonError(format!(
+                "unknown topic rewrite action {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// Rewrites topics matching `source_topic`: `regex` is applied to the topic and
+/// `dest_topic` is the replacement, where `$1`, `$2`... refer to the capture groups.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct MqttTopicRewriteRule {
+    pub action: TopicRewriteAction,
+    pub source_topic: String,
+    pub dest_topic: String,
+    pub regex: String,
+    pub create_time: u64,
+}
+
+impl MqttTopicRewriteRule {
+    pub fn new(
+        action: TopicRewriteAction,
+        source_topic: String,
+        dest_topic: String,
+        regex: String,
+    ) -> Self {
+        MqttTopicRewriteRule {
+            action,
+            source_topic,
+            dest_topic,
+            regex,
+            create_time: now_second(),
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
+        Ok(serde_json::to_vec(&self)?)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
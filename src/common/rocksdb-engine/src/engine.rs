@@ -126,6 +126,49 @@ pub fn rocksdb_engine_prefix_list(
     Ok(results)
 }
 
+pub fn rocksdb_engine_prefix_list_rev(
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    comlumn_family: &str,
+    prefix_key_name: String,
+    limit: usize,
+) -> Result<Vec<StorageDataWrap>, CommonError> {
+    let cf = if let Some(cf) = rocksdb_engine_handler.cf_handle(comlumn_family) {
+        cf
+    } else {
+        return Err(CommonError::RocksDBFamilyNotAvailable(
+            comlumn_family.to_string(),
+        ));
+    };
+
+    let mut results = Vec::new();
+    for (_, v) in rocksdb_engine_handler.read_prefix_rev(cf, &prefix_key_name, limit)? {
+        match serde_json::from_slice::<StorageDataWrap>(v.as_ref()) {
+            Ok(v) => results.push(v),
+            Err(_) => {
+                continue;
+            }
+        }
+    }
+    Ok(results)
+}
+
+pub fn rocksdb_engine_delete_range(
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    comlumn_family: &str,
+    from_key_name: String,
+    to_key_name: String,
+) -> Result<(), CommonError> {
+    let cf = if let Some(cf) = rocksdb_engine_handler.cf_handle(comlumn_family) {
+        cf
+    } else {
+        return Err(CommonError::RocksDBFamilyNotAvailable(
+            comlumn_family.to_string(),
+        ));
+    };
+
+    rocksdb_engine_handler.delete_range(cf, &from_key_name, &to_key_name)
+}
+
 pub fn rocksdb_engine_prefix_map(
     rocksdb_engine_handler: Arc<RocksDBEngine>,
     comlumn_family: &str,
@@ -132,6 +132,37 @@ impl RocksDBEngine {
         Ok(result)
     }
 
+    // Search data by prefix from the last key backwards, at most limit entries when limit
+    // is greater than 0
+    pub fn read_prefix_rev(
+        &self,
+        cf: &ColumnFamily,
+        search_key: &str,
+        limit: usize,
+    ) -> Result<Vec<(String, Vec<u8>)>, CommonError> {
+        let mut iter = self.db.raw_iterator_cf(cf);
+        // Every key with the prefix sorts below the prefix followed by 0xff
+        let mut upper = search_key.as_bytes().to_vec();
+        upper.push(0xff);
+        iter.seek_for_prev(upper);
+
+        let mut result = Vec::new();
+        while iter.valid() && (limit == 0 || result.len() < limit) {
+            if let Some(key) = iter.key() {
+                if let Some(val) = iter.value() {
+                    let key = String::from_utf8(key.to_vec())?;
+                    if !key.starts_with(search_key) {
+                        break;
+                    }
+                    result.push((key, val.to_vec()));
+                }
+            }
+
+            iter.prev();
+        }
+        Ok(result)
+    }
+
     // Read all data in a ColumnFamily
     pub fn read_all_by_cf(&self, cf: &ColumnFamily) -> Result<Vec<(String, Vec<u8>)>, CommonError> {
         let mut iter = self.db.raw_iterator_cf(cf);
@@ -154,6 +185,11 @@ impl RocksDBEngine {
         Ok(self.db.delete_cf(cf, key)?)
     }
 
+    // Delete the keys in [from, to)
+    pub fn delete_range(&self, cf: &ColumnFamily, from: &str, to: &str) -> Result<(), CommonError> {
+        Ok(self.db.delete_range_cf(cf, from, to)?)
+    }
+
     pub fn delete_prefix(&self, cf: &ColumnFamily, search_key: &str) -> Result<(), CommonError> {
         let mut iter = self.db.raw_iterator_cf(cf);
         iter.seek(search_key);
@@ -47,7 +47,7 @@ macro_rules! impl_retriable_request {
                 request: Self,
             ) -> Result<Self::Response, Self::Error> {
                 client
-                    .$op(request)
+                    .$op(common_base::telemetry::grpc_request(request))
                     .await
                     .map(|reply| reply.into_inner())
                     .map_err(Into::into)
@@ -75,7 +75,7 @@ macro_rules! impl_retriable_request {
                 request: Self,
             ) -> Result<Self::Response, Self::Error> {
                 client
-                    .$op(request)
+                    .$op(common_base::telemetry::grpc_request(request))
                     .await
                     .map(|reply| reply.into_inner())
                     .map_err(Into::into)
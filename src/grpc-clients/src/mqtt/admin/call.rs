@@ -14,13 +14,21 @@
 
 use common_base::error::common::CommonError;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    ClusterStatusReply, ClusterStatusRequest, CreateAclReply, CreateAclRequest,
-    CreateBlacklistReply, CreateBlacklistRequest, CreateUserReply, CreateUserRequest,
-    DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply, DeleteBlacklistRequest,
-    DeleteUserReply, DeleteUserRequest, EnableSlowSubScribeReply, EnableSlowSubscribeRequest,
-    ListAclReply, ListAclRequest, ListBlacklistReply, ListBlacklistRequest, ListConnectionReply,
-    ListConnectionRequest, ListSlowSubscribeReply, ListSlowSubscribeRequest, ListTopicReply,
-    ListTopicRequest, ListUserReply, ListUserRequest,
+    CancelDelayMessageReply, CancelDelayMessageRequest, ClusterStatusReply, ClusterStatusRequest,
+    CreateAclReply, CreateAclRequest, CreateAutoSubscribeRuleReply, CreateAutoSubscribeRuleRequest,
+    CreateBlacklistReply, CreateBlacklistRequest, CreateTopicRewriteRuleReply,
+    CreateTopicRewriteRuleRequest, CreateTraceReply, CreateTraceRequest, CreateUserReply,
+    CreateUserRequest, DeleteAclReply, DeleteAclRequest, DeleteAutoSubscribeRuleReply,
+    DeleteAutoSubscribeRuleRequest, DeleteBlacklistReply, DeleteBlacklistRequest,
+    DeleteRetainMessageReply, DeleteRetainMessageRequest, DeleteTopicRewriteRuleReply,
+    DeleteTopicRewriteRuleRequest, DeleteTraceReply, DeleteTraceRequest, DeleteUserReply,
+    DeleteUserRequest, EnableSlowSubScribeReply, EnableSlowSubscribeRequest, ListAclReply,
+    ListAclRequest, ListAlarmReply, ListAlarmRequest, ListAutoSubscribeRuleReply,
+    ListAutoSubscribeRuleRequest, ListBlacklistReply, ListBlacklistRequest, ListConnectionReply,
+    ListConnectionRequest, ListDelayMessageReply, ListDelayMessageRequest, ListRetainMessageReply,
+    ListRetainMessageRequest, ListSlowSubscribeReply, ListSlowSubscribeRequest, ListTopicReply,
+    ListTopicRequest, ListTopicRewriteRuleReply, ListTopicRewriteRuleRequest, ListTraceReply,
+    ListTraceRequest, ListUserReply, ListUserRequest, StopTraceReply, StopTraceRequest,
 };
 
 use crate::pool::ClientPool;
@@ -143,3 +151,129 @@ pub async fn mqtt_broker_list_topic(
 ) -> Result<ListTopicReply, CommonError> {
     retry_call(client_pool, addrs, request).await
 }
+
+// --------- delay message --------
+pub async fn mqtt_broker_list_delay_message(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: ListDelayMessageRequest,
+) -> Result<ListDelayMessageReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_cancel_delay_message(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: CancelDelayMessageRequest,
+) -> Result<CancelDelayMessageReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+// --------- retain message --------
+pub async fn mqtt_broker_list_retain_message(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: ListRetainMessageRequest,
+) -> Result<ListRetainMessageReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_delete_retain_message(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: DeleteRetainMessageRequest,
+) -> Result<DeleteRetainMessageReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+// --------- topic rewrite rule --------
+pub async fn mqtt_broker_list_topic_rewrite_rule(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: ListTopicRewriteRuleRequest,
+) -> Result<ListTopicRewriteRuleReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_create_topic_rewrite_rule(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: CreateTopicRewriteRuleRequest,
+) -> Result<CreateTopicRewriteRuleReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_delete_topic_rewrite_rule(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: DeleteTopicRewriteRuleRequest,
+) -> Result<DeleteTopicRewriteRuleReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+// --------- auto subscribe rule --------
+pub async fn mqtt_broker_list_auto_subscribe_rule(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: ListAutoSubscribeRuleRequest,
+) -> Result<ListAutoSubscribeRuleReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_create_auto_subscribe_rule(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: CreateAutoSubscribeRuleRequest,
+) -> Result<CreateAutoSubscribeRuleReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_delete_auto_subscribe_rule(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: DeleteAutoSubscribeRuleRequest,
+) -> Result<DeleteAutoSubscribeRuleReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+// ---- alarm ------
+pub async fn mqtt_broker_list_alarm(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: ListAlarmRequest,
+) -> Result<ListAlarmReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+// ---- trace ------
+pub async fn mqtt_broker_create_trace(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: CreateTraceRequest,
+) -> Result<CreateTraceReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_list_trace(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: ListTraceRequest,
+) -> Result<ListTraceReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_stop_trace(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: StopTraceRequest,
+) -> Result<StopTraceReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn mqtt_broker_delete_trace(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: DeleteTraceRequest,
+) -> Result<DeleteTraceReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
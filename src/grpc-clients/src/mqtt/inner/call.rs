@@ -14,8 +14,9 @@
 
 use common_base::error::common::CommonError;
 use protocol::broker_mqtt::broker_mqtt_inner::{
-    DeleteSessionReply, DeleteSessionRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
-    UpdateCacheReply, UpdateCacheRequest,
+    DeleteSessionReply, DeleteSessionRequest, ListNodeSlowSubscribeReply,
+    ListNodeSlowSubscribeRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
+    TakeoverSessionReply, TakeoverSessionRequest, UpdateCacheReply, UpdateCacheRequest,
 };
 
 use crate::pool::ClientPool;
@@ -44,3 +45,19 @@ pub async fn send_last_will_message(
 ) -> Result<SendLastWillMessageReply, CommonError> {
     retry_call(client_pool, addrs, request).await
 }
+
+pub async fn broker_mqtt_takeover_session(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: TakeoverSessionRequest,
+) -> Result<TakeoverSessionReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
+
+pub async fn broker_mqtt_list_node_slow_subscribe(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    request: ListNodeSlowSubscribeRequest,
+) -> Result<ListNodeSlowSubscribeReply, CommonError> {
+    retry_call(client_pool, addrs, request).await
+}
@@ -14,18 +14,27 @@
 
 use protocol::broker_mqtt::broker_mqtt_admin::mqtt_broker_admin_service_client::MqttBrokerAdminServiceClient;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    ClusterStatusReply, ClusterStatusRequest, CreateAclReply, CreateAclRequest,
-    CreateBlacklistReply, CreateBlacklistRequest, CreateUserReply, CreateUserRequest,
-    DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply, DeleteBlacklistRequest,
-    DeleteUserReply, DeleteUserRequest, EnableSlowSubScribeReply, EnableSlowSubscribeRequest,
-    ListAclReply, ListAclRequest, ListBlacklistReply, ListBlacklistRequest, ListConnectionReply,
-    ListConnectionRequest, ListSlowSubscribeReply, ListSlowSubscribeRequest, ListTopicReply,
-    ListTopicRequest, ListUserReply, ListUserRequest,
+    CancelDelayMessageReply, CancelDelayMessageRequest, ClusterStatusReply, ClusterStatusRequest,
+    CreateAclReply, CreateAclRequest, CreateAutoSubscribeRuleReply, CreateAutoSubscribeRuleRequest,
+    CreateBlacklistReply, CreateBlacklistRequest, CreateTopicRewriteRuleReply,
+    CreateTopicRewriteRuleRequest, CreateTraceReply, CreateTraceRequest, CreateUserReply,
+    CreateUserRequest, DeleteAclReply, DeleteAclRequest, DeleteAutoSubscribeRuleReply,
+    DeleteAutoSubscribeRuleRequest, DeleteBlacklistReply, DeleteBlacklistRequest,
+    DeleteRetainMessageReply, DeleteRetainMessageRequest, DeleteTopicRewriteRuleReply,
+    DeleteTopicRewriteRuleRequest, DeleteTraceReply, DeleteTraceRequest, DeleteUserReply,
+    DeleteUserRequest, EnableSlowSubScribeReply, EnableSlowSubscribeRequest, ListAclReply,
+    ListAclRequest, ListAlarmReply, ListAlarmRequest, ListAutoSubscribeRuleReply,
+    ListAutoSubscribeRuleRequest, ListBlacklistReply, ListBlacklistRequest, ListConnectionReply,
+    ListConnectionRequest, ListDelayMessageReply, ListDelayMessageRequest, ListRetainMessageReply,
+    ListRetainMessageRequest, ListSlowSubscribeReply, ListSlowSubscribeRequest, ListTopicReply,
+    ListTopicRequest, ListTopicRewriteRuleReply, ListTopicRewriteRuleRequest, ListTraceReply,
+    ListTraceRequest, ListUserReply, ListUserRequest, StopTraceReply, StopTraceRequest,
 };
 use protocol::broker_mqtt::broker_mqtt_inner::mqtt_broker_inner_service_client::MqttBrokerInnerServiceClient;
 use protocol::broker_mqtt::broker_mqtt_inner::{
-    DeleteSessionReply, DeleteSessionRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
-    UpdateCacheReply, UpdateCacheRequest,
+    DeleteSessionReply, DeleteSessionRequest, ListNodeSlowSubscribeReply,
+    ListNodeSlowSubscribeRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
+    TakeoverSessionReply, TakeoverSessionRequest, UpdateCacheReply, UpdateCacheRequest,
 };
 use tonic::transport::Channel;
 
@@ -58,6 +67,22 @@ impl_retriable_request!(
     send_last_will_message
 );
 
+impl_retriable_request!(
+    TakeoverSessionRequest,
+    MqttBrokerInnerServiceClient<Channel>,
+    TakeoverSessionReply,
+    mqtt_broker_mqtt_services_client,
+    takeover_session
+);
+
+impl_retriable_request!(
+    ListNodeSlowSubscribeRequest,
+    MqttBrokerInnerServiceClient<Channel>,
+    ListNodeSlowSubscribeReply,
+    mqtt_broker_mqtt_services_client,
+    list_node_slow_subscribe
+);
+
 impl_retriable_request!(
     ClusterStatusRequest,
     MqttBrokerAdminServiceClient<Channel>,
@@ -170,5 +195,125 @@ impl_retriable_request!(
     mqtt_broker_list_topic
 );
 
+impl_retriable_request!(
+    ListDelayMessageRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListDelayMessageReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_delay_message
+);
+
+impl_retriable_request!(
+    CancelDelayMessageRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    CancelDelayMessageReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_cancel_delay_message
+);
+
+impl_retriable_request!(
+    ListRetainMessageRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListRetainMessageReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_retain_message
+);
+
+impl_retriable_request!(
+    DeleteRetainMessageRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    DeleteRetainMessageReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_delete_retain_message
+);
+
+impl_retriable_request!(
+    ListTopicRewriteRuleRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListTopicRewriteRuleReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_topic_rewrite_rule
+);
+
+impl_retriable_request!(
+    CreateTopicRewriteRuleRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    CreateTopicRewriteRuleReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_create_topic_rewrite_rule
+);
+
+impl_retriable_request!(
+    DeleteTopicRewriteRuleRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    DeleteTopicRewriteRuleReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_delete_topic_rewrite_rule
+);
+
+impl_retriable_request!(
+    ListAutoSubscribeRuleRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListAutoSubscribeRuleReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_auto_subscribe_rule
+);
+
+impl_retriable_request!(
+    CreateAutoSubscribeRuleRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    CreateAutoSubscribeRuleReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_create_auto_subscribe_rule
+);
+
+impl_retriable_request!(
+    DeleteAutoSubscribeRuleRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    DeleteAutoSubscribeRuleReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_delete_auto_subscribe_rule
+);
+
+impl_retriable_request!(
+    ListAlarmRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListAlarmReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_alarm
+);
+
+impl_retriable_request!(
+    CreateTraceRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    CreateTraceReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_create_trace
+);
+
+impl_retriable_request!(
+    ListTraceRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListTraceReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_trace
+);
+
+impl_retriable_request!(
+    StopTraceRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    StopTraceReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_stop_trace
+);
+
+impl_retriable_request!(
+    DeleteTraceRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    DeleteTraceReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_delete_trace
+);
+
 #[cfg(test)]
 mod tests {}
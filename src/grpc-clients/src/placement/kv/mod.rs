@@ -16,8 +16,8 @@ use common_base::error::common::CommonError;
 use mobc::Manager;
 use protocol::placement_center::placement_center_kv::kv_service_client::KvServiceClient;
 use protocol::placement_center::placement_center_kv::{
-    DeleteReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply, GetRequest, SetReply,
-    SetRequest,
+    DeleteReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply, GetRequest,
+    ListByPrefixReply, ListByPrefixRequest, SetReply, SetRequest,
 };
 use tonic::transport::Channel;
 
@@ -97,5 +97,14 @@ impl_retriable_request!(
     true
 );
 
+impl_retriable_request!(
+    ListByPrefixRequest,
+    KvServiceClient<Channel>,
+    ListByPrefixReply,
+    placement_center_kv_services_client,
+    list_by_prefix,
+    true
+);
+
 #[cfg(test)]
 mod tests {}
@@ -23,6 +23,7 @@ pub enum PlacementCenterInterface {
     Get,
     Delete,
     Exists,
+    ListByPrefix,
 
     // placement inner interface
     ClusterStatus,
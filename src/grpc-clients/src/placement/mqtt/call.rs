@@ -14,17 +14,25 @@
 
 use common_base::error::common::CommonError;
 use protocol::placement_center::placement_center_mqtt::{
-    CreateAclReply, CreateAclRequest, CreateBlacklistReply, CreateBlacklistRequest,
-    CreateSessionReply, CreateSessionRequest, CreateTopicReply, CreateTopicRequest,
-    CreateUserReply, CreateUserRequest, DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply,
-    DeleteBlacklistRequest, DeleteExclusiveTopicReply, DeleteExclusiveTopicRequest,
-    DeleteSessionReply, DeleteSessionRequest, DeleteTopicReply, DeleteTopicRequest,
+    CreateAclReply, CreateAclRequest, CreateAutoSubscribeRuleReply, CreateAutoSubscribeRuleRequest,
+    CreateBlacklistReply, CreateBlacklistRequest, CreateSessionReply, CreateSessionRequest,
+    CreateTopicReply, CreateTopicRequest, CreateTopicRewriteRuleReply,
+    CreateTopicRewriteRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclReply,
+    DeleteAclRequest, DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest,
+    DeleteBlacklistReply, DeleteBlacklistRequest, DeleteExclusiveTopicReply,
+    DeleteExclusiveTopicRequest, DeleteQos2StateReply, DeleteQos2StateRequest, DeleteSessionReply,
+    DeleteSessionRequest, DeleteSubscribeReply, DeleteSubscribeRequest, DeleteTopicReply,
+    DeleteTopicRequest, DeleteTopicRewriteRuleReply, DeleteTopicRewriteRuleRequest,
     DeleteUserReply, DeleteUserRequest, GetShareSubLeaderReply, GetShareSubLeaderRequest,
-    ListAclReply, ListAclRequest, ListBlacklistReply, ListBlacklistRequest, ListSessionReply,
-    ListSessionRequest, ListTopicReply, ListTopicRequest, ListUserReply, ListUserRequest,
-    SaveLastWillMessageReply, SaveLastWillMessageRequest, SetExclusiveTopicReply,
-    SetExclusiveTopicRequest, SetTopicRetainMessageReply, SetTopicRetainMessageRequest,
-    UpdateSessionReply, UpdateSessionRequest,
+    ListAclReply, ListAclRequest, ListAlarmReply, ListAlarmRequest, ListAutoSubscribeRuleReply,
+    ListAutoSubscribeRuleRequest, ListBlacklistReply, ListBlacklistRequest, ListQos2StateReply,
+    ListQos2StateRequest, ListSessionReply, ListSessionRequest, ListSubscribeReply,
+    ListSubscribeRequest, ListTopicReply, ListTopicRequest, ListTopicRewriteRuleReply,
+    ListTopicRewriteRuleRequest, ListUserReply, ListUserRequest, SaveAlarmReply, SaveAlarmRequest,
+    SaveLastWillMessageReply, SaveLastWillMessageRequest, SaveQos2StateReply, SaveQos2StateRequest,
+    SetExclusiveTopicReply, SetExclusiveTopicRequest, SetSubscribeReply, SetSubscribeRequest,
+    SetTopicRetainMessageReply, SetTopicRetainMessageRequest, UpdateSessionReply,
+    UpdateSessionRequest,
 };
 
 use crate::pool::ClientPool;
@@ -125,6 +133,90 @@ generate_mqtt_service_call!(
     UpdateSessionReply,
     UpdateSession
 );
+generate_mqtt_service_call!(
+    placement_list_subscribe,
+    ListSubscribeRequest,
+    ListSubscribeReply,
+    ListSubscribe
+);
+generate_mqtt_service_call!(
+    placement_set_subscribe,
+    SetSubscribeRequest,
+    SetSubscribeReply,
+    SetSubscribe
+);
+generate_mqtt_service_call!(
+    placement_delete_subscribe,
+    DeleteSubscribeRequest,
+    DeleteSubscribeReply,
+    DeleteSubscribe
+);
+generate_mqtt_service_call!(
+    placement_list_topic_rewrite_rule,
+    ListTopicRewriteRuleRequest,
+    ListTopicRewriteRuleReply,
+    ListTopicRewriteRule
+);
+generate_mqtt_service_call!(
+    placement_create_topic_rewrite_rule,
+    CreateTopicRewriteRuleRequest,
+    CreateTopicRewriteRuleReply,
+    CreateTopicRewriteRule
+);
+generate_mqtt_service_call!(
+    placement_delete_topic_rewrite_rule,
+    DeleteTopicRewriteRuleRequest,
+    DeleteTopicRewriteRuleReply,
+    DeleteTopicRewriteRule
+);
+generate_mqtt_service_call!(
+    placement_list_auto_subscribe_rule,
+    ListAutoSubscribeRuleRequest,
+    ListAutoSubscribeRuleReply,
+    ListAutoSubscribeRule
+);
+generate_mqtt_service_call!(
+    placement_create_auto_subscribe_rule,
+    CreateAutoSubscribeRuleRequest,
+    CreateAutoSubscribeRuleReply,
+    CreateAutoSubscribeRule
+);
+generate_mqtt_service_call!(
+    placement_delete_auto_subscribe_rule,
+    DeleteAutoSubscribeRuleRequest,
+    DeleteAutoSubscribeRuleReply,
+    DeleteAutoSubscribeRule
+);
+generate_mqtt_service_call!(
+    placement_list_qos2_state,
+    ListQos2StateRequest,
+    ListQos2StateReply,
+    ListQos2State
+);
+generate_mqtt_service_call!(
+    placement_save_qos2_state,
+    SaveQos2StateRequest,
+    SaveQos2StateReply,
+    SaveQos2State
+);
+generate_mqtt_service_call!(
+    placement_delete_qos2_state,
+    DeleteQos2StateRequest,
+    DeleteQos2StateReply,
+    DeleteQos2State
+);
+generate_mqtt_service_call!(
+    placement_list_alarm,
+    ListAlarmRequest,
+    ListAlarmReply,
+    ListAlarm
+);
+generate_mqtt_service_call!(
+    placement_save_alarm,
+    SaveAlarmRequest,
+    SaveAlarmReply,
+    SaveAlarm
+);
 generate_mqtt_service_call!(
     placement_save_last_will_message,
     SaveLastWillMessageRequest,
@@ -16,17 +16,25 @@ use common_base::error::common::CommonError;
 use mobc::Manager;
 use protocol::placement_center::placement_center_mqtt::mqtt_service_client::MqttServiceClient;
 use protocol::placement_center::placement_center_mqtt::{
-    CreateAclReply, CreateAclRequest, CreateBlacklistReply, CreateBlacklistRequest,
-    CreateSessionReply, CreateSessionRequest, CreateTopicReply, CreateTopicRequest,
-    CreateUserReply, CreateUserRequest, DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply,
-    DeleteBlacklistRequest, DeleteExclusiveTopicReply, DeleteExclusiveTopicRequest,
-    DeleteSessionReply, DeleteSessionRequest, DeleteTopicReply, DeleteTopicRequest,
+    CreateAclReply, CreateAclRequest, CreateAutoSubscribeRuleReply, CreateAutoSubscribeRuleRequest,
+    CreateBlacklistReply, CreateBlacklistRequest, CreateSessionReply, CreateSessionRequest,
+    CreateTopicReply, CreateTopicRequest, CreateTopicRewriteRuleReply,
+    CreateTopicRewriteRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclReply,
+    DeleteAclRequest, DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest,
+    DeleteBlacklistReply, DeleteBlacklistRequest, DeleteExclusiveTopicReply,
+    DeleteExclusiveTopicRequest, DeleteQos2StateReply, DeleteQos2StateRequest, DeleteSessionReply,
+    DeleteSessionRequest, DeleteSubscribeReply, DeleteSubscribeRequest, DeleteTopicReply,
+    DeleteTopicRequest, DeleteTopicRewriteRuleReply, DeleteTopicRewriteRuleRequest,
     DeleteUserReply, DeleteUserRequest, GetShareSubLeaderReply, GetShareSubLeaderRequest,
-    ListAclReply, ListAclRequest, ListBlacklistReply, ListBlacklistRequest, ListSessionReply,
-    ListSessionRequest, ListTopicReply, ListTopicRequest, ListUserReply, ListUserRequest,
-    SaveLastWillMessageReply, SaveLastWillMessageRequest, SetExclusiveTopicReply,
-    SetExclusiveTopicRequest, SetTopicRetainMessageReply, SetTopicRetainMessageRequest,
-    UpdateSessionReply, UpdateSessionRequest,
+    ListAclReply, ListAclRequest, ListAlarmReply, ListAlarmRequest, ListAutoSubscribeRuleReply,
+    ListAutoSubscribeRuleRequest, ListBlacklistReply, ListBlacklistRequest, ListQos2StateReply,
+    ListQos2StateRequest, ListSessionReply, ListSessionRequest, ListSubscribeReply,
+    ListSubscribeRequest, ListTopicReply, ListTopicRequest, ListTopicRewriteRuleReply,
+    ListTopicRewriteRuleRequest, ListUserReply, ListUserRequest, SaveAlarmReply, SaveAlarmRequest,
+    SaveLastWillMessageReply, SaveLastWillMessageRequest, SaveQos2StateReply, SaveQos2StateRequest,
+    SetExclusiveTopicReply, SetExclusiveTopicRequest, SetSubscribeReply, SetSubscribeRequest,
+    SetTopicRetainMessageReply, SetTopicRetainMessageRequest, UpdateSessionReply,
+    UpdateSessionRequest,
 };
 use tonic::transport::Channel;
 
@@ -196,6 +204,132 @@ impl_retriable_request!(
     true
 );
 
+impl_retriable_request!(
+    ListSubscribeRequest,
+    MqttServiceClient<Channel>,
+    ListSubscribeReply,
+    placement_center_mqtt_services_client,
+    list_subscribe,
+    true
+);
+
+impl_retriable_request!(
+    SetSubscribeRequest,
+    MqttServiceClient<Channel>,
+    SetSubscribeReply,
+    placement_center_mqtt_services_client,
+    set_subscribe,
+    true
+);
+
+impl_retriable_request!(
+    DeleteSubscribeRequest,
+    MqttServiceClient<Channel>,
+    DeleteSubscribeReply,
+    placement_center_mqtt_services_client,
+    delete_subscribe,
+    true
+);
+
+impl_retriable_request!(
+    ListTopicRewriteRuleRequest,
+    MqttServiceClient<Channel>,
+    ListTopicRewriteRuleReply,
+    placement_center_mqtt_services_client,
+    list_topic_rewrite_rule,
+    true
+);
+
+impl_retriable_request!(
+    CreateTopicRewriteRuleRequest,
+    MqttServiceClient<Channel>,
+    CreateTopicRewriteRuleReply,
+    placement_center_mqtt_services_client,
+    create_topic_rewrite_rule,
+    true
+);
+
+impl_retriable_request!(
+    DeleteTopicRewriteRuleRequest,
+    MqttServiceClient<Channel>,
+    DeleteTopicRewriteRuleReply,
+    placement_center_mqtt_services_client,
+    delete_topic_rewrite_rule,
+    true
+);
+
+impl_retriable_request!(
+    ListAutoSubscribeRuleRequest,
+    MqttServiceClient<Channel>,
+    ListAutoSubscribeRuleReply,
+    placement_center_mqtt_services_client,
+    list_auto_subscribe_rule,
+    true
+);
+
+impl_retriable_request!(
+    CreateAutoSubscribeRuleRequest,
+    MqttServiceClient<Channel>,
+    CreateAutoSubscribeRuleReply,
+    placement_center_mqtt_services_client,
+    create_auto_subscribe_rule,
+    true
+);
+
+impl_retriable_request!(
+    DeleteAutoSubscribeRuleRequest,
+    MqttServiceClient<Channel>,
+    DeleteAutoSubscribeRuleReply,
+    placement_center_mqtt_services_client,
+    delete_auto_subscribe_rule,
+    true
+);
+
+impl_retriable_request!(
+    ListQos2StateRequest,
+    MqttServiceClient<Channel>,
+    ListQos2StateReply,
+    placement_center_mqtt_services_client,
+    list_qos2_state,
+    true
+);
+
+impl_retriable_request!(
+    SaveQos2StateRequest,
+    MqttServiceClient<Channel>,
+    SaveQos2StateReply,
+    placement_center_mqtt_services_client,
+    save_qos2_state,
+    true
+);
+
+impl_retriable_request!(
+    DeleteQos2StateRequest,
+    MqttServiceClient<Channel>,
+    DeleteQos2StateReply,
+    placement_center_mqtt_services_client,
+    delete_qos2_state,
+    true
+);
+
+impl_retriable_request!(
+    ListAlarmRequest,
+    MqttServiceClient<Channel>,
+    ListAlarmReply,
+    placement_center_mqtt_services_client,
+    list_alarm,
+    true
+);
+
+impl_retriable_request!(
+    SaveAlarmRequest,
+    MqttServiceClient<Channel>,
+    SaveAlarmReply,
+    placement_center_mqtt_services_client,
+    save_alarm,
+    true
+);
+
 impl_retriable_request!(
     SaveLastWillMessageRequest,
     MqttServiceClient<Channel>,
@@ -40,6 +40,8 @@ pub(crate) trait RetriableRequest: Clone {
     ) -> Result<Self::Response, Self::Error>;
 }
 
+// Every call is a span, its trace context is sent to the server in the request metadata
+#[tracing::instrument(level = "info", skip_all, fields(request = std::any::type_name::<Req>()))]
 pub(crate) async fn retry_call<Req>(
     client_pool: &ClientPool,
     addrs: &[impl AsRef<str>],
@@ -39,6 +39,7 @@ mod tests {
         let extend = JournalNodeExtend {
             tcp_addr: "".to_string(),
             tcps_addr: "".to_string(),
+            quic_addr: "".to_string(),
             data_fold: vec!["/data".to_string()],
         };
         let request = RegisterNodeRequest {
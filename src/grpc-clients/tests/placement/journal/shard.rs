@@ -53,6 +53,7 @@ mod tests {
             data_fold: vec![node_fold.clone()],
             tcp_addr: "".to_string(),
             tcps_addr: "".to_string(),
+            quic_addr: "".to_string(),
         };
 
         let request = RegisterNodeRequest {
@@ -264,6 +265,7 @@ mod tests {
             data_fold: vec![node_fold.clone()],
             tcp_addr: "".to_string(),
             tcps_addr: "".to_string(),
+            quic_addr: "".to_string(),
         };
 
         let request = RegisterNodeRequest {
@@ -401,6 +403,7 @@ mod tests {
             data_fold: vec![node_fold.clone()],
             tcp_addr: "".to_string(),
             tcps_addr: "".to_string(),
+            quic_addr: "".to_string(),
         };
 
         let request = RegisterNodeRequest {
@@ -506,6 +509,7 @@ mod tests {
             data_fold: vec![node_fold.clone()],
             tcp_addr: "".to_string(),
             tcps_addr: "".to_string(),
+            quic_addr: "".to_string(),
         };
 
         let request = RegisterNodeRequest {
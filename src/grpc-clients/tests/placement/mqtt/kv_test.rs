@@ -17,11 +17,11 @@ mod tests {
     use std::sync::Arc;
 
     use grpc_clients::placement::kv::call::{
-        placement_delete, placement_exists, placement_get, placement_set,
+        placement_delete, placement_exists, placement_get, placement_list_by_prefix, placement_set,
     };
     use grpc_clients::pool::ClientPool;
     use protocol::placement_center::placement_center_kv::{
-        DeleteRequest, ExistsRequest, GetRequest, SetRequest,
+        DeleteRequest, ExistsRequest, GetRequest, ListByPrefixRequest, SetRequest,
     };
 
     use crate::common::get_placement_addr;
@@ -100,4 +100,50 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn kv_list_by_prefix_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(1));
+        let addrs = vec![get_placement_addr()];
+        let prefix = "/test-kv-prefix/".to_string();
+        for i in 0..3 {
+            let request = SetRequest {
+                key: format!("{}{}", prefix, i),
+                value: format!("value-{}", i),
+            };
+            placement_set(&client_pool, &addrs, request).await.unwrap();
+        }
+        let request = SetRequest {
+            key: "/test-kv-prefix-other/0".to_string(),
+            value: "other".to_string(),
+        };
+        placement_set(&client_pool, &addrs, request).await.unwrap();
+
+        let request = ListByPrefixRequest {
+            prefix: prefix.clone(),
+        };
+        let reply = placement_list_by_prefix(&client_pool, &addrs, request)
+            .await
+            .unwrap();
+        assert_eq!(reply.values, vec!["value-0", "value-1", "value-2"]);
+
+        let request = ListByPrefixRequest {
+            prefix: "".to_string(),
+        };
+        let err = placement_list_by_prefix(&client_pool, &addrs, request)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("prefix"));
+
+        for key in [
+            format!("{}0", prefix),
+            format!("{}1", prefix),
+            format!("{}2", prefix),
+            "/test-kv-prefix-other/0".to_string(),
+        ] {
+            placement_delete(&client_pool, &addrs, DeleteRequest { key })
+                .await
+                .unwrap();
+        }
+    }
 }
@@ -15,10 +15,15 @@
 mod cluster_test;
 mod kv_test;
 mod mqtt_acl_test;
+mod mqtt_alarm_test;
+mod mqtt_auto_subscribe_rule_test;
 mod mqtt_blacklist_test;
 mod mqtt_last_will_test;
+mod mqtt_qos2_state_test;
 mod mqtt_session_test;
 mod mqtt_share_sub_test;
+mod mqtt_subscribe_test;
+mod mqtt_topic_rewrite_rule_test;
 mod mqtt_topic_test;
 mod mqtt_user_test;
 mod openraft_test;
@@ -0,0 +1,91 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use common_base::tools::unique_id;
+    use grpc_clients::placement::mqtt::call::{placement_list_alarm, placement_save_alarm};
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::alarm::MqttAlarm;
+    use protocol::placement_center::placement_center_mqtt::{ListAlarmRequest, SaveAlarmRequest};
+
+    use crate::common::get_placement_addr;
+
+    async fn list_alarms(
+        client_pool: &ClientPool,
+        addrs: &[String],
+        cluster_name: &str,
+        only_active: bool,
+    ) -> Vec<MqttAlarm> {
+        let request = ListAlarmRequest {
+            cluster_name: cluster_name.to_string(),
+            only_active,
+            limit: 0,
+        };
+        match placement_list_alarm(client_pool, addrs, request).await {
+            Ok(data) => data
+                .alarms
+                .iter()
+                .map(|raw| MqttAlarm::decode(raw).unwrap())
+                .collect(),
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+    }
+
+    async fn save_alarm(
+        client_pool: &ClientPool,
+        addrs: &[String],
+        cluster_name: &str,
+        alarm: &MqttAlarm,
+    ) {
+        let request = SaveAlarmRequest {
+            cluster_name: cluster_name.to_string(),
+            alarm: alarm.encode().unwrap(),
+        };
+        if let Err(e) = placement_save_alarm(client_pool, addrs, request).await {
+            panic!("{:?}", e);
+        }
+    }
+
+    #[tokio::test]
+    async fn mqtt_alarm_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(3));
+        let addrs = vec![get_placement_addr()];
+        let cluster_name = unique_id();
+
+        let mut alarm = MqttAlarm::new(
+            "high_memory".to_string(),
+            1,
+            "memory usage is high".to_string(),
+            HashMap::from([("usage".to_string(), "95".to_string())]),
+        );
+        save_alarm(&client_pool, &addrs, &cluster_name, &alarm).await;
+
+        let alarms = list_alarms(&client_pool, &addrs, &cluster_name, true).await;
+        assert_eq!(alarms, vec![alarm.clone()]);
+
+        alarm.deactivate("memory usage is back to normal".to_string(), HashMap::new());
+        save_alarm(&client_pool, &addrs, &cluster_name, &alarm).await;
+
+        let alarms = list_alarms(&client_pool, &addrs, &cluster_name, true).await;
+        assert!(alarms.is_empty());
+        let alarms = list_alarms(&client_pool, &addrs, &cluster_name, false).await;
+        assert_eq!(alarms, vec![alarm]);
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use grpc_clients::placement::mqtt::call::{
+        placement_create_auto_subscribe_rule, placement_delete_auto_subscribe_rule,
+        placement_list_auto_subscribe_rule,
+    };
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
+    use protocol::mqtt::common::{QoS, RetainForwardRule};
+    use protocol::placement_center::placement_center_mqtt::{
+        CreateAutoSubscribeRuleRequest, DeleteAutoSubscribeRuleRequest,
+        ListAutoSubscribeRuleRequest,
+    };
+
+    use crate::common::get_placement_addr;
+
+    async fn list_rules(
+        client_pool: &ClientPool,
+        addrs: &[String],
+        cluster_name: &str,
+    ) -> Vec<MqttAutoSubscribeRule> {
+        let request = ListAutoSubscribeRuleRequest {
+            cluster_name: cluster_name.to_string(),
+        };
+        match placement_list_auto_subscribe_rule(client_pool, addrs, request).await {
+            Ok(data) => data
+                .auto_subscribe_rules
+                .iter()
+                .map(|raw| MqttAutoSubscribeRule::decode(raw).unwrap())
+                .collect(),
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn mqtt_auto_subscribe_rule_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(3));
+        let addrs = vec![get_placement_addr()];
+        let cluster_name: String = "test_cluster".to_string();
+
+        let rule = MqttAutoSubscribeRule::new(
+            "devices/${clientid}/cmd".to_string(),
+            QoS::AtLeastOnce,
+            false,
+            false,
+            RetainForwardRule::OnEverySubscribe,
+            "".to_string(),
+        );
+
+        let request = CreateAutoSubscribeRuleRequest {
+            cluster_name: cluster_name.clone(),
+            auto_subscribe_rule: rule.encode().unwrap(),
+        };
+        if let Err(e) = placement_create_auto_subscribe_rule(&client_pool, &addrs, request).await {
+            panic!("{:?}", e);
+        }
+
+        let rules = list_rules(&client_pool, &addrs, &cluster_name).await;
+        assert!(rules.contains(&rule));
+
+        let request = DeleteAutoSubscribeRuleRequest {
+            cluster_name: cluster_name.clone(),
+            username: rule.username.clone(),
+            topic: rule.topic.clone(),
+        };
+        if let Err(e) = placement_delete_auto_subscribe_rule(&client_pool, &addrs, request).await {
+            panic!("{:?}", e);
+        }
+
+        let rules = list_rules(&client_pool, &addrs, &cluster_name).await;
+        assert!(!rules.contains(&rule));
+    }
+}
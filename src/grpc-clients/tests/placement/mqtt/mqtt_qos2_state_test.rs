@@ -0,0 +1,112 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::tools::unique_id;
+    use grpc_clients::placement::mqtt::call::{
+        placement_delete_qos2_state, placement_list_qos2_state, placement_save_qos2_state,
+    };
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::qos2_state::{MqttQos2State, Qos2Stage};
+    use protocol::placement_center::placement_center_mqtt::{
+        DeleteQos2StateRequest, ListQos2StateRequest, SaveQos2StateRequest,
+    };
+
+    use crate::common::get_placement_addr;
+
+    async fn list_states(
+        client_pool: &ClientPool,
+        addrs: &[String],
+        cluster_name: &str,
+        client_id: &str,
+    ) -> Vec<MqttQos2State> {
+        let request = ListQos2StateRequest {
+            cluster_name: cluster_name.to_string(),
+            client_id: client_id.to_string(),
+        };
+        match placement_list_qos2_state(client_pool, addrs, request).await {
+            Ok(data) => data
+                .qos2_states
+                .iter()
+                .map(|raw| MqttQos2State::decode(raw).unwrap())
+                .collect(),
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+    }
+
+    async fn save_state(
+        client_pool: &ClientPool,
+        addrs: &[String],
+        cluster_name: &str,
+        state: &MqttQos2State,
+    ) {
+        let request = SaveQos2StateRequest {
+            cluster_name: cluster_name.to_string(),
+            qos2_state: state.encode().unwrap(),
+        };
+        if let Err(e) = placement_save_qos2_state(client_pool, addrs, request).await {
+            panic!("{:?}", e);
+        }
+    }
+
+    #[tokio::test]
+    async fn mqtt_qos2_state_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(3));
+        let addrs = vec![get_placement_addr()];
+        let cluster_name: String = "test_cluster".to_string();
+        let client_id = unique_id();
+
+        let receive = MqttQos2State::new_receive(client_id.clone(), 5);
+        let mut send = MqttQos2State::new_send(
+            client_id.clone(),
+            5,
+            Qos2Stage::WaitPubRec,
+            "group".to_string(),
+            100,
+        );
+        save_state(&client_pool, &addrs, &cluster_name, &receive).await;
+        save_state(&client_pool, &addrs, &cluster_name, &send).await;
+
+        let states = list_states(&client_pool, &addrs, &cluster_name, &client_id).await;
+        assert_eq!(states.len(), 2);
+        assert!(states.contains(&receive));
+        assert!(states.contains(&send));
+
+        send.stage = Qos2Stage::WaitPubComp;
+        save_state(&client_pool, &addrs, &cluster_name, &send).await;
+        let states = list_states(&client_pool, &addrs, &cluster_name, &client_id).await;
+        assert_eq!(states.len(), 2);
+        assert!(states.contains(&send));
+
+        for state in [receive, send] {
+            let request = DeleteQos2StateRequest {
+                cluster_name: cluster_name.clone(),
+                client_id: client_id.clone(),
+                direction: state.direction().to_string(),
+                pkid: state.pkid as u32,
+            };
+            if let Err(e) = placement_delete_qos2_state(&client_pool, &addrs, request).await {
+                panic!("{:?}", e);
+            }
+        }
+
+        let states = list_states(&client_pool, &addrs, &cluster_name, &client_id).await;
+        assert!(states.is_empty());
+    }
+}
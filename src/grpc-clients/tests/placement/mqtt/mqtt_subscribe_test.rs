@@ -0,0 +1,124 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use grpc_clients::placement::mqtt::call::{
+        placement_delete_subscribe, placement_list_subscribe, placement_set_subscribe,
+    };
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+    use protocol::mqtt::common::{Filter, MqttProtocol, QoS, RetainForwardRule};
+    use protocol::placement_center::placement_center_mqtt::{
+        DeleteSubscribeRequest, ListSubscribeRequest, SetSubscribeRequest,
+    };
+
+    use crate::common::get_placement_addr;
+
+    async fn list_subscribe(
+        client_pool: &Arc<ClientPool>,
+        addrs: &[String],
+        cluster_name: &str,
+        client_id: &str,
+    ) -> Vec<MqttSubscribe> {
+        let request = ListSubscribeRequest {
+            cluster_name: cluster_name.to_string(),
+            client_id: client_id.to_string(),
+        };
+        match placement_list_subscribe(client_pool, addrs, request).await {
+            Ok(data) => data
+                .subscribes
+                .iter()
+                .map(|raw| serde_json::from_slice::<MqttSubscribe>(raw).unwrap())
+                .collect(),
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn mqtt_subscribe_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(3));
+        let addrs = vec![get_placement_addr()];
+        let cluster_name: String = "test_cluster".to_string();
+        let client_id: String = "test_subscribe_client_id".to_string();
+
+        let mut subscribes = Vec::new();
+        for path in ["/test/subscribe/+", "/test/subscribe/#"] {
+            let filter = Filter {
+                path: path.to_string(),
+                qos: QoS::AtLeastOnce,
+                nolocal: false,
+                preserve_retain: false,
+                retain_forward_rule: RetainForwardRule::OnEverySubscribe,
+            };
+            let subscribe =
+                MqttSubscribe::new(client_id.clone(), 1, MqttProtocol::Mqtt5, filter, 1, None);
+
+            let request = SetSubscribeRequest {
+                cluster_name: cluster_name.clone(),
+                client_id: client_id.clone(),
+                path: subscribe.path.clone(),
+                subscribe: subscribe.encode(),
+            };
+            match placement_set_subscribe(&client_pool, &addrs, request).await {
+                Ok(_) => {}
+                Err(e) => {
+                    panic!("{:?}", e);
+                }
+            }
+            subscribes.push(subscribe);
+        }
+
+        let data = list_subscribe(&client_pool, &addrs, &cluster_name, &client_id).await;
+        assert_eq!(data.len(), 2);
+        for subscribe in subscribes.iter() {
+            assert!(data.contains(subscribe));
+        }
+
+        let request = DeleteSubscribeRequest {
+            cluster_name: cluster_name.clone(),
+            client_id: client_id.clone(),
+            path: "/test/subscribe/#".to_string(),
+        };
+        match placement_delete_subscribe(&client_pool, &addrs, request).await {
+            Ok(_) => {}
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+
+        let data = list_subscribe(&client_pool, &addrs, &cluster_name, &client_id).await;
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0], subscribes[0]);
+
+        let request = DeleteSubscribeRequest {
+            cluster_name: cluster_name.clone(),
+            client_id: client_id.clone(),
+            path: "".to_string(),
+        };
+        match placement_delete_subscribe(&client_pool, &addrs, request).await {
+            Ok(_) => {}
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+
+        let data = list_subscribe(&client_pool, &addrs, &cluster_name, &client_id).await;
+        assert!(data.is_empty());
+    }
+}
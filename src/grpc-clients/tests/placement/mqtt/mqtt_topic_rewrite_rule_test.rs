@@ -0,0 +1,87 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use grpc_clients::placement::mqtt::call::{
+        placement_create_topic_rewrite_rule, placement_delete_topic_rewrite_rule,
+        placement_list_topic_rewrite_rule,
+    };
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::topic_rewrite_rule::{MqttTopicRewriteRule, TopicRewriteAction};
+    use protocol::placement_center::placement_center_mqtt::{
+        CreateTopicRewriteRuleRequest, DeleteTopicRewriteRuleRequest, ListTopicRewriteRuleRequest,
+    };
+
+    use crate::common::get_placement_addr;
+
+    async fn list_rules(
+        client_pool: &ClientPool,
+        addrs: &[String],
+        cluster_name: &str,
+    ) -> Vec<MqttTopicRewriteRule> {
+        let request = ListTopicRewriteRuleRequest {
+            cluster_name: cluster_name.to_string(),
+        };
+        match placement_list_topic_rewrite_rule(client_pool, addrs, request).await {
+            Ok(data) => data
+                .topic_rewrite_rules
+                .iter()
+                .map(|raw| MqttTopicRewriteRule::decode(raw).unwrap())
+                .collect(),
+            Err(e) => {
+                panic!("{:?}", e);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn mqtt_topic_rewrite_rule_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(3));
+        let addrs = vec![get_placement_addr()];
+        let cluster_name: String = "test_cluster".to_string();
+
+        let rule = MqttTopicRewriteRule::new(
+            TopicRewriteAction::All,
+            "v1/dev/+/data".to_string(),
+            "devices/$1/telemetry".to_string(),
+            "^v1/dev/(.+)/data$".to_string(),
+        );
+
+        let request = CreateTopicRewriteRuleRequest {
+            cluster_name: cluster_name.clone(),
+            topic_rewrite_rule: rule.encode().unwrap(),
+        };
+        if let Err(e) = placement_create_topic_rewrite_rule(&client_pool, &addrs, request).await {
+            panic!("{:?}", e);
+        }
+
+        let rules = list_rules(&client_pool, &addrs, &cluster_name).await;
+        assert!(rules.contains(&rule));
+
+        let request = DeleteTopicRewriteRuleRequest {
+            cluster_name: cluster_name.clone(),
+            action: rule.action.to_string(),
+            source_topic: rule.source_topic.clone(),
+        };
+        if let Err(e) = placement_delete_topic_rewrite_rule(&client_pool, &addrs, request).await {
+            panic!("{:?}", e);
+        }
+
+        let rules = list_rules(&client_pool, &addrs, &cluster_name).await;
+        assert!(!rules.contains(&rule));
+    }
+}
@@ -27,6 +27,7 @@ use protocol::journal_server::journal_engine::{
 use tokio::select;
 use tokio::sync::mpsc::{self, Receiver, Sender};
 use tokio::time::{sleep, timeout};
+use tracing::{Instrument, Span};
 
 use crate::cache::{get_segment_leader, MetadataCache};
 use crate::client::JournalClientWriteData;
@@ -41,6 +42,8 @@ pub struct SenderMessage {
     shard_name: String,
     segment: u32,
     data: Vec<JournalClientWriteData>,
+    // span of the caller, the parent of the write request
+    span: Span,
 }
 
 impl SenderMessage {
@@ -55,6 +58,7 @@ impl SenderMessage {
             shard_name: shard_name.to_owned(),
             segment,
             data,
+            span: Span::current(),
         }
     }
 }
@@ -210,11 +214,26 @@ async fn batch_sender_message(
     pkid_generator: &AtomicU64,
     messages: Vec<DataSenderPkg>,
 ) {
+    // A write request carries the messages of several callers, it is traced as a child of
+    // the first one and follows from the others
+    let span = tracing::info_span!(
+        parent: &messages[0].message.span,
+        "journal_client_write",
+        node_id,
+        messages = messages.len()
+    );
+    for pkg in messages.iter().skip(1) {
+        span.follows_from(&pkg.message.span);
+    }
+
     let (segments, data_pkgs, callback_sx) = build_send_data(pkid_generator, messages);
 
     // send data
     let body = WriteReqBody { data: segments };
-    match batch_write(connection_manager, node_id, body).await {
+    match batch_write(connection_manager, node_id, body)
+        .instrument(span)
+        .await
+    {
         Ok(data) => {
             // callback resp
             let mut pkid_resp = HashMap::new();
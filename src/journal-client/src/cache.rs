@@ -58,6 +58,15 @@ impl MetadataCache {
         None
     }
 
+    pub fn get_quic_addr_by_node_id(&self, node_id: u64) -> Option<String> {
+        if let Some(node) = self.nodes.get(&node_id) {
+            if !node.quic_addr.is_empty() {
+                return Some(node.quic_addr.clone());
+            }
+        }
+        None
+    }
+
     pub fn add_shard(&self, shard: GetShardMetadataRespShard) {
         self.shards
             .insert(shard_name_iden(&shard.namespace, &shard.shard), shard);
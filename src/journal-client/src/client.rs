@@ -26,6 +26,7 @@ use super::error::JournalClientError;
 use crate::async_reader::AsyncReader;
 use crate::async_writer::{AsyncWriter, SenderMessage, SenderMessageResp};
 use crate::cache::get_active_segment;
+use crate::option::JournalClientOption;
 use crate::service::{create_shard, delete_shard};
 
 #[derive(Default, Clone)]
@@ -46,8 +47,14 @@ pub struct JournalClient {
 
 impl JournalClient {
     pub fn new(addrs: Vec<String>) -> Self {
-        let metadata_cache = Arc::new(MetadataCache::new(addrs));
-        let connection_manager = Arc::new(ConnectionManager::new(metadata_cache.clone()));
+        let mut option = JournalClientOption::build();
+        option.set_addrs(addrs);
+        Self::new_with_option(option)
+    }
+
+    pub fn new_with_option(option: JournalClientOption) -> Self {
+        let metadata_cache = Arc::new(MetadataCache::new(option.addrs.clone()));
+        let connection_manager = Arc::new(ConnectionManager::new(metadata_cache.clone(), option));
         let (stop_send, _) = broadcast::channel::<bool>(2);
         let writer = Arc::new(AsyncWriter::new(
             connection_manager.clone(),
@@ -113,6 +120,11 @@ impl JournalClient {
         Ok(())
     }
 
+    #[tracing::instrument(
+        level = "info",
+        skip_all,
+        fields(namespace = %namespace, shard_name = %shard_name)
+    )]
     pub async fn batch_write(
         &self,
         namespace: String,
@@ -21,32 +21,52 @@ use dashmap::DashMap;
 use futures::{SinkExt, StreamExt};
 use log::error;
 use protocol::journal_server::codec::{JournalEnginePacket, JournalServerCodec};
+use quinn::VarInt;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
 use tokio::select;
 use tokio::sync::broadcast::Receiver;
+use tokio::sync::{Mutex, OnceCell};
 use tokio::time::sleep;
 use tokio_util::codec::Framed;
 
 use crate::cache::MetadataCache;
 use crate::error::JournalClientError;
+use crate::option::JournalClientOption;
+use crate::quic::{QuicConnector, QuicStream};
+
+pub trait ClientStream: AsyncRead + AsyncWrite + Send + Sync + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Sync + Unpin> ClientStream for T {}
 
 pub struct ClientConnection {
-    pub stream: Framed<TcpStream, JournalServerCodec>,
+    pub stream: Framed<Box<dyn ClientStream>, JournalServerCodec>,
     pub last_active_time: u64,
 }
 
+#[derive(Clone)]
+pub enum NodeTransport {
+    Tcp,
+    Quic(Arc<QuicConnector>),
+}
+
 pub struct NodeConnection {
     node_id: u64,
     metadata_cache: Arc<MetadataCache>,
+    transport: NodeTransport,
+    // Over QUIC the admin, read and write modules are streams of this one connection
+    quic_connection: Mutex<Option<quinn::Connection>>,
     connection: DashMap<String, ClientConnection>,
 }
 
 impl NodeConnection {
-    pub fn new(node_id: u64, metadata_cache: Arc<MetadataCache>) -> Self {
+    pub fn new(node_id: u64, metadata_cache: Arc<MetadataCache>, transport: NodeTransport) -> Self {
         let connection = DashMap::with_capacity(2);
         NodeConnection {
             node_id,
             metadata_cache,
+            transport,
+            quic_connection: Mutex::new(None),
             connection,
         }
     }
@@ -142,15 +162,50 @@ impl NodeConnection {
         }
     }
 
-    async fn open(&self) -> Result<Framed<TcpStream, JournalServerCodec>, JournalClientError> {
-        let addr = if let Some(addr) = self.metadata_cache.get_tcp_addr_by_node_id(self.node_id) {
+    async fn open(
+        &self,
+    ) -> Result<Framed<Box<dyn ClientStream>, JournalServerCodec>, JournalClientError> {
+        let stream: Box<dyn ClientStream> = match &self.transport {
+            NodeTransport::Tcp => {
+                let addr =
+                    if let Some(addr) = self.metadata_cache.get_tcp_addr_by_node_id(self.node_id) {
+                        addr
+                    } else {
+                        return Err(JournalClientError::NodeNoAvailableAddr(self.node_id));
+                    };
+                Box::new(TcpStream::connect(&addr).await?)
+            }
+            NodeTransport::Quic(connector) => Box::new(self.open_quic_stream(connector).await?),
+        };
+        Ok(Framed::new(stream, JournalServerCodec::new()))
+    }
+
+    async fn open_quic_stream(
+        &self,
+        connector: &QuicConnector,
+    ) -> Result<QuicStream, JournalClientError> {
+        let mut quic_connection = self.quic_connection.lock().await;
+        if let Some(connection) = quic_connection.as_ref() {
+            if connection.close_reason().is_none() {
+                return QuicStream::open(connection).await;
+            }
+        }
+
+        let addr = if let Some(addr) = self.metadata_cache.get_quic_addr_by_node_id(self.node_id) {
             addr
         } else {
             return Err(JournalClientError::NodeNoAvailableAddr(self.node_id));
         };
+        let connection = connector.connect(&addr).await?;
+        let stream = QuicStream::open(&connection).await;
+        *quic_connection = Some(connection);
+        stream
+    }
 
-        let socket = TcpStream::connect(&addr).await?;
-        Ok(Framed::new(socket, JournalServerCodec::new()))
+    async fn close_quic_connection(&self) {
+        if let Some(connection) = self.quic_connection.lock().await.take() {
+            connection.close(VarInt::from_u32(0), b"");
+        }
     }
 
     pub async fn init_conn(&self) -> Result<(), JournalClientError> {
@@ -186,16 +241,20 @@ pub struct ConnectionManager {
     node_conns: DashMap<u64, NodeConnection>,
     metadata_cache: Arc<MetadataCache>,
     admin_conn_atom: AtomicU64,
+    option: JournalClientOption,
+    quic_connector: OnceCell<Arc<QuicConnector>>,
 }
 
 impl ConnectionManager {
-    pub fn new(metadata_cache: Arc<MetadataCache>) -> Self {
+    pub fn new(metadata_cache: Arc<MetadataCache>, option: JournalClientOption) -> Self {
         let node_conns = DashMap::with_capacity(2);
         let admin_conn_atom = AtomicU64::new(0);
         ConnectionManager {
             node_conns,
             metadata_cache,
             admin_conn_atom,
+            option,
+            quic_connector: OnceCell::new(),
         }
     }
 
@@ -206,7 +265,8 @@ impl ConnectionManager {
         let node_id = self.choose_admin_node();
 
         if !self.node_conns.contains_key(&node_id) {
-            let conn = NodeConnection::new(node_id, self.metadata_cache.clone());
+            let transport = self.node_transport(node_id).await?;
+            let conn = NodeConnection::new(node_id, self.metadata_cache.clone(), transport);
             conn.init_conn().await?;
             self.node_conns.insert(node_id, conn);
         }
@@ -221,7 +281,8 @@ impl ConnectionManager {
         req_packet: JournalEnginePacket,
     ) -> Result<JournalEnginePacket, JournalClientError> {
         if !self.node_conns.contains_key(&node_id) {
-            let conn = NodeConnection::new(node_id, self.metadata_cache.clone());
+            let transport = self.node_transport(node_id).await?;
+            let conn = NodeConnection::new(node_id, self.metadata_cache.clone(), transport);
             conn.init_conn().await?;
             self.node_conns.insert(node_id, conn);
         }
@@ -236,7 +297,8 @@ impl ConnectionManager {
         req_packet: JournalEnginePacket,
     ) -> Result<JournalEnginePacket, JournalClientError> {
         if !self.node_conns.contains_key(&node_id) {
-            let conn = NodeConnection::new(node_id, self.metadata_cache.clone());
+            let transport = self.node_transport(node_id).await?;
+            let conn = NodeConnection::new(node_id, self.metadata_cache.clone(), transport);
             conn.init_conn().await?;
             self.node_conns.insert(node_id, conn);
         }
@@ -245,6 +307,24 @@ impl ConnectionManager {
         conn.read_send(req_packet).await
     }
 
+    // QUIC is chosen per node, a node that does not advertise a QUIC address is still dialed over TCP
+    async fn node_transport(&self, node_id: u64) -> Result<NodeTransport, JournalClientError> {
+        if !self.option.enable_quic
+            || self
+                .metadata_cache
+                .get_quic_addr_by_node_id(node_id)
+                .is_none()
+        {
+            return Ok(NodeTransport::Tcp);
+        }
+
+        let connector = self
+            .quic_connector
+            .get_or_try_init(|| async { QuicConnector::new(&self.option).map(Arc::new) })
+            .await?;
+        Ok(NodeTransport::Quic(connector.clone()))
+    }
+
     fn choose_admin_node(&self) -> u64 {
         let node_ids = self.metadata_cache.all_node_ids();
         let posi = self
@@ -285,6 +365,7 @@ impl ConnectionManager {
                     error!("{}", e);
                 }
             }
+            node.close_quic_connection().await;
         }
     }
 }
@@ -31,6 +31,15 @@ pub enum JournalClientError {
     #[error("{0}")]
     MpscSendErrorBool(#[from] tokio::sync::mpsc::error::SendError<bool>),
 
+    #[error("{0}")]
+    QuicConnectError(#[from] quinn::ConnectError),
+
+    #[error("{0}")]
+    QuicConnectionError(#[from] quinn::ConnectionError),
+
+    #[error("QUIC client configuration is invalid, error message :{0}")]
+    QuicConfigError(String),
+
     #[error("Node {0} has no available access address, may be cache data inconsistency, ready to trigger update node cache.")]
     NodeNoAvailableAddr(u64),
 
@@ -20,5 +20,6 @@ pub mod client;
 mod connection;
 mod error;
 pub mod option;
+mod quic;
 mod service;
 pub mod tool;
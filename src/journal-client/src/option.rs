@@ -18,12 +18,19 @@ use common_base::error::common::CommonError;
 pub struct JournalClientOption {
     pub addrs: Vec<String>,
     pub line_ms: u64,
+    // Connect to the nodes that advertise a QUIC address over QUIC instead of TCP
+    pub enable_quic: bool,
+    // PEM file with the certificates trusted when connecting over QUIC
+    pub quic_ca_cert: String,
+    // Name verified against the certificate presented by the journal nodes
+    pub quic_server_name: String,
 }
 
 impl JournalClientOption {
     pub fn build() -> Self {
         JournalClientOption {
             line_ms: 10,
+            quic_server_name: "localhost".to_string(),
             ..Default::default()
         }
     }
@@ -31,6 +38,11 @@ impl JournalClientOption {
     pub fn set_addrs(&mut self, addrs: Vec<String>) {
         self.addrs = addrs;
     }
+
+    pub fn set_quic(&mut self, quic_ca_cert: String) {
+        self.enable_quic = true;
+        self.quic_ca_cert = quic_ca_cert;
+    }
 }
 
 pub fn options_validator(option: &JournalClientOption) -> Result<(), CommonError> {
@@ -39,5 +51,10 @@ pub fn options_validator(option: &JournalClientOption) -> Result<(), CommonError
             "option.addrs".to_string(),
         ));
     }
+    if option.enable_quic && option.quic_ca_cert.is_empty() {
+        return Err(CommonError::ParameterCannotBeNull(
+            "option.quic_ca_cert".to_string(),
+        ));
+    }
     Ok(())
 }
@@ -0,0 +1,114 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use quinn::crypto::rustls::QuicClientConfig;
+use quinn::{Connection, Endpoint, RecvStream, SendStream};
+use rustls_pemfile::certs;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_rustls::rustls::{self, ClientConfig, RootCertStore};
+
+use crate::error::JournalClientError;
+use crate::option::JournalClientOption;
+
+// Must match the ALPN protocol configured by the journal server QUIC listener
+const JOURNAL_ALPN: &[u8] = b"robust-journal";
+
+/// Shared client endpoint used to open QUIC connections to journal nodes.
+pub struct QuicConnector {
+    endpoint: Endpoint,
+    server_name: String,
+}
+
+impl QuicConnector {
+    pub fn new(option: &JournalClientOption) -> Result<Self, JournalClientError> {
+        let mut roots = RootCertStore::empty();
+        let mut reader = BufReader::new(File::open(&option.quic_ca_cert)?);
+        for cert in certs(&mut reader) {
+            roots
+                .add(cert?)
+                .map_err(|e| JournalClientError::QuicConfigError(e.to_string()))?;
+        }
+
+        let mut tls_config =
+            ClientConfig::builder_with_protocol_versions(&[&rustls::version::TLS13])
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        tls_config.alpn_protocols = vec![JOURNAL_ALPN.to_vec()];
+
+        let crypto = QuicClientConfig::try_from(tls_config)
+            .map_err(|e| JournalClientError::QuicConfigError(e.to_string()))?;
+        let mut endpoint = Endpoint::client("0.0.0.0:0".parse().unwrap())?;
+        endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(crypto)));
+
+        Ok(QuicConnector {
+            endpoint,
+            server_name: option.quic_server_name.clone(),
+        })
+    }
+
+    pub async fn connect(&self, addr: &str) -> Result<Connection, JournalClientError> {
+        let addr: SocketAddr = addr.parse().map_err(|e: std::net::AddrParseError| {
+            JournalClientError::QuicConfigError(e.to_string())
+        })?;
+        Ok(self.endpoint.connect(addr, &self.server_name)?.await?)
+    }
+}
+
+/// One bidirectional stream of a QUIC connection, usable wherever a `TcpStream` is.
+pub struct QuicStream {
+    send: SendStream,
+    recv: RecvStream,
+}
+
+impl QuicStream {
+    pub async fn open(connection: &Connection) -> Result<Self, JournalClientError> {
+        let (send, recv) = connection.open_bi().await?;
+        Ok(QuicStream { send, recv })
+    }
+}
+
+impl AsyncRead for QuicStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().recv).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for QuicStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        AsyncWrite::poll_write(Pin::new(&mut self.get_mut().send), cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        AsyncWrite::poll_flush(Pin::new(&mut self.get_mut().send), cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        AsyncWrite::poll_shutdown(Pin::new(&mut self.get_mut().send), cx)
+    }
+}
@@ -14,6 +14,7 @@
 
 use std::sync::Arc;
 
+use common_base::telemetry::current_trace_context;
 use protocol::journal_server::codec::JournalEnginePacket;
 use protocol::journal_server::journal_engine::{
     ApiKey, ApiVersion, CreateShardReq, CreateShardReqBody, CreateShardRespBody, DeleteShardReq,
@@ -34,6 +35,7 @@ pub(crate) async fn get_cluster_metadata(
         header: Some(ReqHeader {
             api_key: ApiKey::GetClusterMetadata.into(),
             api_version: ApiVersion::V0.into(),
+            trace_context: current_trace_context(),
         }),
     });
 
@@ -63,6 +65,7 @@ pub(crate) async fn get_shard_metadata(
         header: Some(ReqHeader {
             api_key: ApiKey::GetShardMetadata.into(),
             api_version: ApiVersion::V0.into(),
+            trace_context: current_trace_context(),
         }),
         body: Some(GetShardMetadataReqBody { shards }),
     });
@@ -93,6 +96,7 @@ pub(crate) async fn create_shard(
         header: Some(ReqHeader {
             api_key: ApiKey::CreateShard.into(),
             api_version: ApiVersion::V0.into(),
+            trace_context: current_trace_context(),
         }),
         body: Some(shard),
     });
@@ -123,6 +127,7 @@ pub(crate) async fn delete_shard(
         header: Some(ReqHeader {
             api_key: ApiKey::DeleteShard.into(),
             api_version: ApiVersion::V0.into(),
+            trace_context: current_trace_context(),
         }),
         body: Some(shard),
     });
@@ -154,6 +159,7 @@ pub(crate) async fn batch_write(
         header: Some(ReqHeader {
             api_key: ApiKey::Write.into(),
             api_version: ApiVersion::V0.into(),
+            trace_context: current_trace_context(),
         }),
         body: Some(body),
     });
@@ -187,6 +193,7 @@ pub(crate) async fn batch_read(
         header: Some(ReqHeader {
             api_key: ApiKey::Read.into(),
             api_version: ApiVersion::V0.into(),
+            trace_context: current_trace_context(),
         }),
         body: Some(body),
     });
@@ -220,6 +227,7 @@ pub(crate) async fn fetch_offset(
         header: Some(ReqHeader {
             api_key: ApiKey::FetchOffset.into(),
             api_version: ApiVersion::V0.into(),
+            trace_context: current_trace_context(),
         }),
         body: Some(body),
     });
@@ -40,10 +40,17 @@ pub async fn register_journal_node(
     config: JournalServerConfig,
 ) -> Result<(), CommonError> {
     let conf = journal_server_conf();
+    // Clients fall back to TCP for nodes that advertise no QUIC address
+    let quic_addr = if conf.network.quic_enable {
+        format!("{}:{}", get_local_ip(), conf.network.quic_port)
+    } else {
+        "".to_string()
+    };
     let extend = JournalNodeExtend {
         data_fold: conf.storage.data_path.clone(),
         tcp_addr: format!("{}:{}", get_local_ip(), conf.network.tcp_port),
         tcps_addr: format!("{}:{}", get_local_ip(), conf.network.tcps_port),
+        quic_addr,
     };
 
     let req = RegisterNodeRequest {
@@ -0,0 +1,195 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge_vec, HistogramVec,
+    IntCounterVec, IntGaugeVec,
+};
+
+const METRICS_LABEL_NAMESPACE: &str = "namespace";
+const METRICS_LABEL_SHARD: &str = "shard";
+const METRICS_LABEL_SEGMENT: &str = "segment";
+const METRICS_LABEL_READ_TYPE: &str = "read_type";
+const METRICS_LABEL_STAGE: &str = "stage";
+const METRICS_LABEL_NETWORK: &str = "network";
+
+// Latency buckets in milliseconds, from sub-millisecond disk writes up to multi-second stalls
+const LATENCY_MS_BUCKETS: &[f64] = &[
+    0.5, 1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0,
+];
+
+lazy_static! {
+    // Number of write requests received per shard
+    static ref WRITE_REQUESTS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "journal_write_requests_total",
+        "Number of write requests received per shard",
+        &[METRICS_LABEL_NAMESPACE, METRICS_LABEL_SHARD]
+    )
+    .unwrap();
+
+    // Number of records written per shard
+    static ref WRITE_RECORDS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "journal_write_records_total",
+        "Number of records written per shard",
+        &[METRICS_LABEL_NAMESPACE, METRICS_LABEL_SHARD]
+    )
+    .unwrap();
+
+    // Number of record bytes (key + value) written per shard
+    static ref WRITE_BYTES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "journal_write_bytes_total",
+        "Number of record bytes written per shard",
+        &[METRICS_LABEL_NAMESPACE, METRICS_LABEL_SHARD]
+    )
+    .unwrap();
+
+    // Time spent writing one batch of records into the segment file
+    static ref SEGMENT_WRITE_LATENCY: HistogramVec = register_histogram_vec!(
+        "journal_segment_write_latency_ms",
+        "Time spent writing one batch of records into the segment file, in milliseconds",
+        &[METRICS_LABEL_NAMESPACE, METRICS_LABEL_SHARD],
+        LATENCY_MS_BUCKETS.to_vec()
+    )
+    .unwrap();
+
+    // Time spent serving one read request, by read type
+    static ref READ_LATENCY: HistogramVec = register_histogram_vec!(
+        "journal_read_latency_ms",
+        "Time spent reading one segment, by read type, in milliseconds",
+        &[METRICS_LABEL_READ_TYPE],
+        LATENCY_MS_BUCKETS.to_vec()
+    )
+    .unwrap();
+
+    // Number of records written to the segment but not yet indexed
+    static ref INDEX_BUILD_LAG: IntGaugeVec = register_int_gauge_vec!(
+        "journal_index_build_lag",
+        "Number of records written to the segment but not yet indexed",
+        &[METRICS_LABEL_NAMESPACE, METRICS_LABEL_SHARD, METRICS_LABEL_SEGMENT]
+    )
+    .unwrap();
+
+    // Number of segment scroll events, by stage
+    static ref SEGMENT_SCROLL_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "journal_segment_scroll_total",
+        "Number of segment scroll events, by stage",
+        &[METRICS_LABEL_STAGE]
+    )
+    .unwrap();
+
+    // Number of active client connections, by network type
+    static ref ACTIVE_CONNECTIONS: IntGaugeVec = register_int_gauge_vec!(
+        "journal_active_connections",
+        "Number of active client connections",
+        &[METRICS_LABEL_NETWORK]
+    )
+    .unwrap();
+
+    // Size of the local segment file in bytes
+    static ref SEGMENT_FILE_SIZE: IntGaugeVec = register_int_gauge_vec!(
+        "journal_segment_file_size_bytes",
+        "Size of the local segment file in bytes",
+        &[METRICS_LABEL_NAMESPACE, METRICS_LABEL_SHARD, METRICS_LABEL_SEGMENT]
+    )
+    .unwrap();
+}
+
+pub fn metrics_write_request(namespace: &str, shard_name: &str, records: usize, bytes: usize) {
+    WRITE_REQUESTS_TOTAL
+        .with_label_values(&[namespace, shard_name])
+        .inc();
+    WRITE_RECORDS_TOTAL
+        .with_label_values(&[namespace, shard_name])
+        .inc_by(records as u64);
+    WRITE_BYTES_TOTAL
+        .with_label_values(&[namespace, shard_name])
+        .inc_by(bytes as u64);
+}
+
+pub fn metrics_segment_write_latency(namespace: &str, shard_name: &str, ms: f64) {
+    SEGMENT_WRITE_LATENCY
+        .with_label_values(&[namespace, shard_name])
+        .observe(ms);
+}
+
+pub fn metrics_read_latency(read_type: &str, ms: f64) {
+    READ_LATENCY.with_label_values(&[read_type]).observe(ms);
+}
+
+pub fn metrics_index_build_lag(namespace: &str, shard_name: &str, segment: u32, lag: i64) {
+    INDEX_BUILD_LAG
+        .with_label_values(&[namespace, shard_name, &segment.to_string()])
+        .set(lag);
+}
+
+pub fn metrics_segment_scroll(stage: &str) {
+    SEGMENT_SCROLL_TOTAL.with_label_values(&[stage]).inc();
+}
+
+pub fn metrics_connection_open(network: &str) {
+    ACTIVE_CONNECTIONS.with_label_values(&[network]).inc();
+}
+
+pub fn metrics_connection_close(network: &str) {
+    ACTIVE_CONNECTIONS.with_label_values(&[network]).dec();
+}
+
+pub fn metrics_segment_file_size(namespace: &str, shard_name: &str, segment: u32, size: u64) {
+    SEGMENT_FILE_SIZE
+        .with_label_values(&[namespace, shard_name, &segment.to_string()])
+        .set(size as i64);
+}
+
+// Drop the per-segment series once the segment file is gone, so that deleted
+// segments do not keep growing the label set.
+pub fn metrics_segment_remove(namespace: &str, shard_name: &str, segment: u32) {
+    let segment = segment.to_string();
+    let _ = SEGMENT_FILE_SIZE.remove_label_values(&[namespace, shard_name, &segment]);
+    let _ = INDEX_BUILD_LAG.remove_label_values(&[namespace, shard_name, &segment]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_request_test() {
+        metrics_write_request("ns-metrics", "shard-metrics", 3, 30);
+        metrics_write_request("ns-metrics", "shard-metrics", 2, 20);
+        let labels = ["ns-metrics", "shard-metrics"];
+        assert_eq!(WRITE_REQUESTS_TOTAL.with_label_values(&labels).get(), 2);
+        assert_eq!(WRITE_RECORDS_TOTAL.with_label_values(&labels).get(), 5);
+        assert_eq!(WRITE_BYTES_TOTAL.with_label_values(&labels).get(), 50);
+    }
+
+    #[test]
+    fn segment_remove_test() {
+        metrics_segment_file_size("ns-remove", "shard-remove", 1, 1024);
+        metrics_index_build_lag("ns-remove", "shard-remove", 1, 10);
+        let labels = ["ns-remove", "shard-remove", "1"];
+        assert_eq!(SEGMENT_FILE_SIZE.with_label_values(&labels).get(), 1024);
+
+        metrics_segment_remove("ns-remove", "shard-remove", 1);
+        assert_eq!(SEGMENT_FILE_SIZE.with_label_values(&labels).get(), 0);
+        assert_eq!(INDEX_BUILD_LAG.with_label_values(&labels).get(), 0);
+    }
+
+    #[test]
+    fn read_latency_test() {
+        metrics_read_latency("Offset", 1.5);
+        let histogram = READ_LATENCY.with_label_values(&["Offset"]);
+        assert!(histogram.get_sample_count() >= 1);
+    }
+}
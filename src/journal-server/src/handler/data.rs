@@ -15,12 +15,14 @@
 use std::sync::Arc;
 
 use common_base::config::journal_server::journal_server_conf;
+use common_base::telemetry::{extract_trace_context, set_span_parent};
 use grpc_clients::pool::ClientPool;
 use protocol::journal_server::journal_engine::{
     AutoOffsetStrategy, FetchOffsetReq, FetchOffsetRespBody, FetchOffsetShard,
     FetchOffsetShardMeta, ReadReq, ReadRespSegmentMessage, WriteReq, WriteRespMessage,
 };
 use rocksdb_engine::RocksDBEngine;
+use tracing::Instrument;
 
 use crate::core::cache::CacheManager;
 use crate::core::error::JournalServerError;
@@ -65,6 +67,12 @@ impl DataHandler {
             return Err(JournalServerError::RequestBodyNotEmpty("write".to_string()));
         }
 
+        // continue the trace of the client that sent the request
+        let span = tracing::info_span!("journal_server_write");
+        if let Some(header) = &request.header {
+            set_span_parent(&span, extract_trace_context(&header.trace_context));
+        }
+
         let req_body = request.body.unwrap();
         for message in req_body.data.iter() {
             let segment_identity = SegmentIdentity {
@@ -82,6 +90,7 @@ impl DataHandler {
             &self.client_pool,
             &req_body,
         )
+        .instrument(span)
         .await?;
         Ok(results)
     }
@@ -33,6 +33,7 @@ use super::time::TimestampIndexManager;
 use crate::core::cache::CacheManager;
 use crate::core::consts::{BUILD_INDE_PER_RECORD_NUM, DB_COLUMN_FAMILY_INDEX};
 use crate::core::error::JournalServerError;
+use crate::core::metrics::metrics_index_build_lag;
 use crate::index::IndexData;
 use crate::segment::file::{open_segment_write, SegmentFile};
 use crate::segment::manager::SegmentFileManager;
@@ -129,6 +130,7 @@ async fn build_thread(
 
     start_segment_build_index_thread(
         cache_manager.clone(),
+        segment_file_manager.clone(),
         rocksdb_engine_handler.clone(),
         segment_iden.clone(),
         segment_write,
@@ -141,6 +143,7 @@ async fn build_thread(
 
 async fn start_segment_build_index_thread(
     cache_manager: Arc<CacheManager>,
+    segment_file_manager: Arc<SegmentFileManager>,
     rocksdb_engine_handler: Arc<RocksDBEngine>,
     segment_iden: SegmentIdentity,
     segment_write: SegmentFile,
@@ -275,6 +278,14 @@ async fn start_segment_build_index_thread(
                                 ) {
                                     Ok(data) => {
                                         last_build_offset = last_read_data.record.offset;
+                                        if let Some(end_offset) = segment_file_manager.get_end_offset(&segment_iden) {
+                                            metrics_index_build_lag(
+                                                &segment_iden.namespace,
+                                                &segment_iden.shard_name,
+                                                segment_iden.segment_seq,
+                                                (end_offset - last_build_offset as i64).max(0),
+                                            );
+                                        }
                                     }
                                     Err(e) => {
                                         error!("Failure to save last_offset_build_index information with error message :{}",e);
@@ -17,13 +17,16 @@
 use core::cache::{load_metadata_cache, CacheManager};
 use core::cluster::{register_journal_node, report_heartbeat, unregister_journal_node};
 use core::offset::OffsetManager;
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 
 use common_base::config::journal_server::{journal_server_conf, JournalServerConfig};
+use common_base::metrics::push::{start_prometheus_push, PROMETHEUS_MODEL_PUSH};
 use common_base::metrics::register_prometheus_export;
 use common_base::runtime::create_runtime;
+use common_base::telemetry::{init_telemetry, shutdown_telemetry, SERVICE_NAME_JOURNAL_SERVER};
 use grpc_clients::pool::ClientPool;
 use index::engine::{column_family_list, storage_data_fold};
 use log::{error, info};
@@ -34,6 +37,7 @@ use segment::manager::{
 use segment::scroll::SegmentScrollManager;
 use server::connection_manager::ConnectionManager;
 use server::grpc::server::GrpcServer;
+use server::quic::server::start_quic_server;
 use server::tcp::server::start_tcp_server;
 use tokio::runtime::Runtime;
 use tokio::signal;
@@ -101,10 +105,16 @@ impl JournalServer {
     }
 
     pub fn start(&self) {
+        self.start_telemetry();
+
         self.start_grpc_server();
 
         self.start_tcp_server();
 
+        if self.config.network.quic_enable {
+            self.start_quic_server();
+        }
+
         self.start_prometheus();
 
         self.init_node();
@@ -114,6 +124,14 @@ impl JournalServer {
         self.waiting_stop();
     }
 
+    fn start_telemetry(&self) {
+        // the span exporter runs on the server runtime
+        let _guard = self.server_runtime.enter();
+        if let Err(e) = init_telemetry(SERVICE_NAME_JOURNAL_SERVER, &self.config.telemetry) {
+            error!("Failed to initialize telemetry, error message: {}", e);
+        }
+    }
+
     fn start_grpc_server(&self) {
         let server = GrpcServer::new(
             self.config.network.grpc_port,
@@ -154,8 +172,50 @@ impl JournalServer {
         });
     }
 
+    fn start_quic_server(&self) {
+        let client_pool = self.client_pool.clone();
+        let connection_manager = self.connection_manager.clone();
+        let cache_manager = self.cache_manager.clone();
+        let stop_sx = self.stop_send.clone();
+        let offset_manager = self.offset_manager.clone();
+        let segment_file_manager = self.segment_file_manager.clone();
+        let rocksdb_engine_handler = self.rocksdb_engine_handler.clone();
+        self.server_runtime.spawn(async {
+            if let Err(e) = start_quic_server(
+                client_pool,
+                connection_manager,
+                cache_manager,
+                offset_manager,
+                segment_file_manager,
+                rocksdb_engine_handler,
+                stop_sx,
+            )
+            .await
+            {
+                error!(
+                    "Failed to start the journal QUIC server, error message: {}",
+                    e
+                );
+            }
+        });
+    }
+
     fn start_prometheus(&self) {
-        if self.config.prometheus.enable {
+        if !self.config.prometheus.enable {
+            return;
+        }
+
+        if self.config.prometheus.model == PROMETHEUS_MODEL_PUSH {
+            let mut grouping = HashMap::new();
+            grouping.insert("cluster".to_string(), self.config.cluster_name.clone());
+            grouping.insert("node_id".to_string(), self.config.node_id.to_string());
+            let prometheus = self.config.prometheus.clone();
+            let stop_sx = self.stop_send.clone();
+            self.server_runtime.spawn(async move {
+                start_prometheus_push(prometheus, "journal-server".to_string(), grouping, stop_sx)
+                    .await;
+            });
+        } else {
             let prometheus_port = self.config.prometheus.port;
             self.server_runtime.spawn(async move {
                 register_prometheus_export(prometheus_port).await;
@@ -239,5 +299,6 @@ impl JournalServer {
                 error!("{}", e);
             }
         }
+        shutdown_telemetry();
     }
 }
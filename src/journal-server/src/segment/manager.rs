@@ -25,6 +25,7 @@ use rocksdb_engine::RocksDBEngine;
 use super::file::SegmentFile;
 use super::SegmentIdentity;
 use crate::core::error::JournalServerError;
+use crate::core::metrics::metrics_segment_remove;
 use crate::index::engine::storage_data_fold;
 use crate::index::offset::OffsetIndexManager;
 use crate::index::time::TimestampIndexManager;
@@ -71,6 +72,11 @@ impl SegmentFileManager {
 
     pub fn remove_segment_file(&self, segment_iden: &SegmentIdentity) {
         self.segment_files.remove(&segment_iden.name());
+        metrics_segment_remove(
+            &segment_iden.namespace,
+            &segment_iden.shard_name,
+            segment_iden.segment_seq,
+        );
     }
 
     pub fn get_end_offset(&self, segment_iden: &SegmentIdentity) -> Option<i64> {
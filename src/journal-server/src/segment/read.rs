@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use std::sync::Arc;
+use std::time::Instant;
 
 use protocol::journal_server::journal_engine::{
     ReadReqBody, ReadReqFilter, ReadReqOptions, ReadRespMessage, ReadRespSegmentMessage, ReadType,
@@ -23,6 +24,7 @@ use super::file::{ReadData, SegmentFile};
 use super::SegmentIdentity;
 use crate::core::cache::CacheManager;
 use crate::core::error::JournalServerError;
+use crate::core::metrics::metrics_read_latency;
 use crate::index::offset::OffsetIndexManager;
 use crate::index::tag::TagIndexManager;
 use crate::index::time::TimestampIndexManager;
@@ -88,6 +90,7 @@ pub async fn read_data_req(
             }
         };
 
+        let start = Instant::now();
         let read_data_list = match raw.ready_type() {
             ReadType::Offset => {
                 read_by_offset(
@@ -133,6 +136,10 @@ pub async fn read_data_req(
                 .await?
             }
         };
+        metrics_read_latency(
+            raw.ready_type().as_str_name(),
+            start.elapsed().as_secs_f64() * 1000.0,
+        );
 
         let mut record_message = Vec::new();
         for read_data in read_data_list {
@@ -29,6 +29,7 @@ use super::manager::SegmentFileManager;
 use super::SegmentIdentity;
 use crate::core::cache::CacheManager;
 use crate::core::error::JournalServerError;
+use crate::core::metrics::metrics_segment_scroll;
 use crate::core::segment_meta::{update_meta_end_offset, update_meta_start_offset};
 use crate::core::segment_status::pre_sealup_segment;
 
@@ -108,6 +109,7 @@ impl SegmentScrollManager {
                     {
                         Ok(_) => {
                             self.percentage50_cache.insert(key.clone(), now_second());
+                            metrics_segment_scroll("create_next_segment");
                         }
                         Err(e) => {
                             error!("{}", e);
@@ -151,6 +153,7 @@ impl SegmentScrollManager {
                         }
 
                         self.percentage90_cache.insert(key.clone(), now_second());
+                        metrics_segment_scroll("pre_sealup");
                     } else {
                         error!("When the file size is 90%, try adjusting the segment state. The segment file metadata does not exist, maybe a file is missing.")
                     }
@@ -14,7 +14,7 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use common_base::tools::now_second;
 use grpc_clients::pool::ClientPool;
@@ -29,9 +29,13 @@ use tokio::select;
 use tokio::sync::mpsc::{self, Receiver, Sender};
 use tokio::sync::{broadcast, oneshot};
 use tokio::time::timeout;
+use tracing::{Instrument, Span};
 
 use crate::core::cache::CacheManager;
 use crate::core::error::JournalServerError;
+use crate::core::metrics::{
+    metrics_segment_file_size, metrics_segment_write_latency, metrics_write_request,
+};
 use crate::core::segment_meta::{update_meta_end_timestamp, update_meta_start_timestamp};
 use crate::core::segment_status::sealup_segment;
 use crate::index::build::try_trigger_build_index;
@@ -49,6 +53,8 @@ pub struct SegmentWrite {
 pub struct SegmentWriteData {
     data: Vec<JournalRecord>,
     resp_sx: oneshot::Sender<SegmentWriteResp>,
+    // span of the write request, the parent of the segment write
+    span: Span,
 }
 
 #[derive(Default, Debug)]
@@ -58,6 +64,7 @@ pub struct SegmentWriteResp {
     error: Option<JournalServerError>,
 }
 
+#[tracing::instrument(level = "info", skip_all, fields(segments = req_body.data.len()))]
 pub async fn write_data_req(
     cache_manager: &Arc<CacheManager>,
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
@@ -81,7 +88,9 @@ pub async fn write_data_req(
         );
 
         let mut data_list = Vec::new();
+        let mut data_bytes = 0;
         for message in shard_data.messages.iter() {
+            data_bytes += message.key.len() + message.value.len();
             // todo data validator
             let record = JournalRecord {
                 content: message.value.clone(),
@@ -96,6 +105,12 @@ pub async fn write_data_req(
             };
             data_list.push(record);
         }
+        metrics_write_request(
+            &shard_data.namespace,
+            &shard_data.shard_name,
+            data_list.len(),
+            data_bytes,
+        );
 
         let resp = write(
             cache_manager,
@@ -162,6 +177,7 @@ async fn write(
     let data = SegmentWriteData {
         data: data_list,
         resp_sx: sx,
+        span: Span::current(),
     };
     write.data_sender.send(data).await?;
 
@@ -258,12 +274,18 @@ async fn start_segment_sync_write_thread(
                                     resp.error = Some(JournalServerError::SegmentAlreadySealUp(segment_iden.name()));
                                     is_break = true;
                                 }else{
+                                    let span = tracing::info_span!(
+                                        parent: &packet.span,
+                                        "journal_segment_write",
+                                        segment = %segment_iden.name(),
+                                        records = packet.data.len()
+                                    );
                                     match batch_write_segment(
                                         &packet,
                                         &segment_write,
                                         &segment_file_manager,
                                         &client_pool,
-                                        local_segment_end_offset as u64).await
+                                        local_segment_end_offset as u64).instrument(span).await
                                     {
                                         Ok((resp_data,last_offset)) =>{
                                             if let Some(end_offset) = last_offset {
@@ -330,8 +352,22 @@ async fn batch_write_segment(
     }
 
     // batch write data
+    let start = Instant::now();
     match segment_write.write(&records).await {
         Ok(positions) => {
+            metrics_segment_write_latency(
+                &segment_iden.namespace,
+                &segment_iden.shard_name,
+                start.elapsed().as_secs_f64() * 1000.0,
+            );
+            if let Ok(size) = segment_write.size().await {
+                metrics_segment_file_size(
+                    &segment_iden.namespace,
+                    &segment_iden.shard_name,
+                    segment_iden.segment_seq,
+                    size,
+                );
+            }
             resp.offsets = offsets.clone();
             resp.positions = positions;
         }
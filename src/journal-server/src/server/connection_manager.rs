@@ -23,6 +23,7 @@ use tokio::time::sleep;
 use tokio_util::codec::FramedWrite;
 
 use super::connection::{NetworkConnection, NetworkConnectionType};
+use crate::core::metrics::{metrics_connection_close, metrics_connection_open};
 
 pub struct ConnectionManager {
     connections: DashMap<u64, NetworkConnection>,
@@ -35,6 +36,8 @@ pub struct ConnectionManager {
             JournalServerCodec,
         >,
     >,
+    // Keyed by the connection id of each QUIC stream, see server::quic::server
+    quic_write_list: DashMap<u64, FramedWrite<quinn::SendStream, JournalServerCodec>>,
 }
 
 impl ConnectionManager {
@@ -45,15 +48,18 @@ impl ConnectionManager {
             FramedWrite<tokio::io::WriteHalf<tokio::net::TcpStream>, JournalServerCodec>,
         > = DashMap::with_capacity(64);
         let tcp_tls_write_list = DashMap::with_capacity(64);
+        let quic_write_list = DashMap::with_capacity(64);
         ConnectionManager {
             connections,
             tcp_write_list,
             tcp_tls_write_list,
+            quic_write_list,
         }
     }
 
     pub fn add_connection(&self, connection: NetworkConnection) -> u64 {
         let connection_id = connection.connection_id();
+        metrics_connection_open(&connection.connection_type.to_string());
         self.connections.insert(connection_id, connection);
         connection_id
     }
@@ -77,6 +83,14 @@ impl ConnectionManager {
         self.tcp_tls_write_list.insert(connection_id, write);
     }
 
+    pub fn add_quic_write(
+        &self,
+        connection_id: u64,
+        write: FramedWrite<quinn::SendStream, JournalServerCodec>,
+    ) {
+        self.quic_write_list.insert(connection_id, write);
+    }
+
     pub async fn _close_all_connect(&self) {
         for (connect_id, _) in self.connections.clone() {
             self.close_connect(connect_id).await;
@@ -85,6 +99,7 @@ impl ConnectionManager {
 
     pub async fn close_connect(&self, connection_id: u64) {
         if let Some((_, connection)) = self.connections.remove(&connection_id) {
+            metrics_connection_close(&connection.connection_type.to_string());
             connection.stop_connection().await;
         }
 
@@ -111,6 +126,17 @@ impl ConnectionManager {
                 Err(e) => error!("{}", e),
             }
         }
+        if let Some((id, mut stream)) = self.quic_write_list.remove(&connection_id) {
+            match stream.close().await {
+                Ok(_) => {
+                    info!(
+                        "server closes the quic stream actively, connection id [{}]",
+                        id
+                    );
+                }
+                Err(e) => error!("{}", e),
+            }
+        }
     }
 
     pub async fn write_tcp_frame(
@@ -125,6 +151,9 @@ impl ConnectionManager {
             if connection.connection_type == NetworkConnectionType::Tls {
                 return self.write_tcp_tls_frame(connection_id, resp).await;
             }
+            if connection.connection_type == NetworkConnectionType::Quic {
+                return self.write_quic_frame(connection_id, resp).await;
+            }
         }
 
         let mut times = 0;
@@ -224,6 +253,57 @@ impl ConnectionManager {
         Ok(())
     }
 
+    async fn write_quic_frame(
+        &self,
+        connection_id: u64,
+        resp: JournalEnginePacket,
+    ) -> Result<(), CommonError> {
+        let mut times = 0;
+        let response_max_try_mut_times = 5;
+        let response_try_mut_sleep_time_ms = 1000;
+        loop {
+            match self.quic_write_list.try_get_mut(&connection_id) {
+                dashmap::try_result::TryResult::Present(mut da) => {
+                    match da.send(resp.clone()).await {
+                        Ok(_) => {
+                            break;
+                        }
+                        Err(e) => {
+                            if times > response_max_try_mut_times {
+                                return Err(CommonError::CommonError(format!(
+                                    "Failed to write data to the journal engine client, error message: {e:?}"
+                                )));
+                            }
+                        }
+                    }
+                }
+                dashmap::try_result::TryResult::Absent => {
+                    if times > response_max_try_mut_times {
+                        return Err(CommonError::CommonError(
+                            format!(
+                                "[write_frame]Connection management could not obtain an available quic stream. Connection ID: {},len:{}",
+                                connection_id,
+                                self.quic_write_list.len()
+                            )
+                        ));
+                    }
+                }
+                dashmap::try_result::TryResult::Locked => {
+                    if times > response_max_try_mut_times {
+                        return Err(CommonError::CommonError(
+                            format!(
+                                "[write_frame]Connection management failed to get quic stream variable reference, connection ID: {}",connection_id
+                            )
+                        ));
+                    }
+                }
+            }
+            times += 1;
+            sleep(Duration::from_millis(response_try_mut_sleep_time_ms)).await
+        }
+        Ok(())
+    }
+
     pub fn _tcp_connect_num_check(&self) -> bool {
         if self.connections.len() >= 10000 {
             return true;
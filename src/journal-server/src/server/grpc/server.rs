@@ -15,6 +15,7 @@
 use std::sync::Arc;
 
 use common_base::error::common::CommonError;
+use common_base::telemetry::grpc_server_span;
 use grpc_clients::pool::ClientPool;
 use log::info;
 use protocol::journal_server::journal_admin::journal_server_admin_service_server::JournalServerAdminServiceServer;
@@ -65,6 +66,7 @@ impl GrpcServer {
         );
 
         Server::builder()
+            .trace_fn(grpc_server_span)
             .add_service(JournalServerAdminServiceServer::new(admin_handler))
             .add_service(JournalServerInnerServiceServer::new(inner_handler))
             .serve(addr)
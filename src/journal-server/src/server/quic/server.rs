@@ -0,0 +1,296 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use common_base::config::journal_server::journal_server_conf;
+use common_base::error::common::CommonError;
+use futures_util::StreamExt;
+use grpc_clients::pool::ClientPool;
+use log::{debug, error, info};
+use protocol::journal_server::codec::JournalServerCodec;
+use quinn::crypto::rustls::QuicServerConfig;
+use quinn::{Connection, Endpoint, Incoming, RecvStream, SendStream, VarInt};
+use rocksdb_engine::RocksDBEngine;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio_rustls::rustls::{self, ServerConfig};
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use crate::core::cache::CacheManager;
+use crate::core::offset::OffsetManager;
+use crate::handler::command::Command;
+use crate::segment::manager::SegmentFileManager;
+use crate::server::connection::{NetworkConnection, NetworkConnectionType};
+use crate::server::connection_manager::ConnectionManager;
+use crate::server::packet::{RequestPackage, ResponsePackage};
+use crate::server::tcp::handler::handler_process;
+use crate::server::tcp::response::response_process;
+use crate::server::tcp::tls_server::{load_certs, load_key};
+
+// ALPN protocol negotiated between the journal client and server
+pub const JOURNAL_ALPN: &[u8] = b"robust-journal";
+
+/// The journal data protocol over QUIC.
+///
+/// Every bidirectional stream opened by a client is registered as its own connection
+/// in the `ConnectionManager`, so responses are written back on the stream that carried
+/// the request. A client can keep writes and reads on separate streams of one QUIC
+/// connection, and a large read response then no longer blocks the small writes queued
+/// behind it the way it does on a single TCP connection.
+pub async fn start_quic_server(
+    client_pool: Arc<ClientPool>,
+    connection_manager: Arc<ConnectionManager>,
+    cache_manager: Arc<CacheManager>,
+    offset_manager: Arc<OffsetManager>,
+    segment_file_manager: Arc<SegmentFileManager>,
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    stop_sx: broadcast::Sender<bool>,
+) -> Result<(), CommonError> {
+    let conf = journal_server_conf();
+    let command = Command::new(
+        client_pool.clone(),
+        cache_manager.clone(),
+        offset_manager,
+        segment_file_manager,
+        rocksdb_engine_handler,
+    );
+
+    let endpoint = build_endpoint(conf.network.quic_port)?;
+
+    let (request_queue_sx, request_queue_rx) = mpsc::channel::<RequestPackage>(1000);
+    let (response_queue_sx, response_queue_rx) = mpsc::channel::<ResponsePackage>(1000);
+
+    acceptor_process(
+        conf.tcp_thread.accept_thread_num,
+        endpoint,
+        connection_manager.clone(),
+        stop_sx.clone(),
+        request_queue_sx,
+    );
+
+    handler_process(
+        conf.tcp_thread.handler_thread_num,
+        request_queue_rx,
+        connection_manager.clone(),
+        response_queue_sx,
+        stop_sx.clone(),
+        command,
+    )
+    .await;
+
+    response_process(
+        conf.tcp_thread.response_thread_num,
+        connection_manager,
+        cache_manager,
+        response_queue_rx,
+        client_pool,
+        stop_sx,
+    )
+    .await;
+
+    info!(
+        "Journal Engine QUIC Server started successfully, listening port: {}",
+        conf.network.quic_port
+    );
+    Ok(())
+}
+
+fn build_endpoint(port: u32) -> Result<Endpoint, CommonError> {
+    let conf = journal_server_conf();
+    if conf.network.tls_cert.is_empty() || conf.network.tls_key.is_empty() {
+        return Err(CommonError::CommonError(
+            "QUIC listener requires network.tls_cert and network.tls_key to be configured"
+                .to_string(),
+        ));
+    }
+    let certs = load_certs(Path::new(&conf.network.tls_cert))?;
+    let key = load_key(Path::new(&conf.network.tls_key))?;
+
+    let mut tls_config = ServerConfig::builder_with_protocol_versions(&[&rustls::version::TLS13])
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| CommonError::CommonError(e.to_string()))?;
+    tls_config.alpn_protocols = vec![JOURNAL_ALPN.to_vec()];
+
+    let crypto = QuicServerConfig::try_from(tls_config)
+        .map_err(|e| CommonError::CommonError(e.to_string()))?;
+    let server_config = quinn::ServerConfig::with_crypto(Arc::new(crypto));
+
+    let addr: SocketAddr = format!("0.0.0.0:{}", port).parse()?;
+    Ok(Endpoint::server(server_config, addr)?)
+}
+
+fn acceptor_process(
+    accept_thread_num: usize,
+    endpoint: Endpoint,
+    connection_manager: Arc<ConnectionManager>,
+    stop_sx: broadcast::Sender<bool>,
+    request_queue_sx: Sender<RequestPackage>,
+) {
+    for index in 1..=accept_thread_num {
+        let endpoint = endpoint.clone();
+        let connection_manager = connection_manager.clone();
+        let mut stop_rx = stop_sx.subscribe();
+        let request_queue_sx = request_queue_sx.clone();
+        tokio::spawn(async move {
+            debug!("QUIC Server acceptor thread {} start successfully.", index);
+            loop {
+                select! {
+                    val = stop_rx.recv() => {
+                        if let Ok(flag) = val {
+                            if flag {
+                                endpoint.close(VarInt::from_u32(0), b"server stopped");
+                                debug!("QUIC Server acceptor thread {} stopped successfully.", index);
+                                break;
+                            }
+                        }
+                    }
+                    val = endpoint.accept() => {
+                        match val {
+                            Some(incoming) => {
+                                tokio::spawn(process_connection(
+                                    incoming,
+                                    connection_manager.clone(),
+                                    request_queue_sx.clone(),
+                                ));
+                            }
+                            None => {
+                                debug!("QUIC endpoint closed, acceptor thread {} exits.", index);
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+async fn process_connection(
+    incoming: Incoming,
+    connection_manager: Arc<ConnectionManager>,
+    request_queue_sx: Sender<RequestPackage>,
+) {
+    let quic_connection = match incoming.await {
+        Ok(quic_connection) => quic_connection,
+        Err(e) => {
+            error!("QUIC handshake failed with error message :{:?}", e);
+            return;
+        }
+    };
+    info!(
+        "accept quic connection:{:?}",
+        quic_connection.remote_address()
+    );
+
+    loop {
+        match quic_connection.accept_bi().await {
+            Ok((send_stream, recv_stream)) => {
+                accept_stream(
+                    &quic_connection,
+                    send_stream,
+                    recv_stream,
+                    &connection_manager,
+                    &request_queue_sx,
+                );
+            }
+            Err(e) => {
+                debug!(
+                    "QUIC connection {} closed, error message :{:?}",
+                    quic_connection.remote_address(),
+                    e
+                );
+                break;
+            }
+        }
+    }
+}
+
+fn accept_stream(
+    quic_connection: &Connection,
+    send_stream: SendStream,
+    recv_stream: RecvStream,
+    connection_manager: &Arc<ConnectionManager>,
+    request_queue_sx: &Sender<RequestPackage>,
+) {
+    let codec = JournalServerCodec::new();
+    let read_frame_stream = FramedRead::new(recv_stream, codec.clone());
+    let write_frame_stream = FramedWrite::new(send_stream, codec);
+
+    let (connection_stop_sx, connection_stop_rx) = mpsc::channel::<bool>(1);
+    let connection = NetworkConnection::new(
+        NetworkConnectionType::Quic,
+        quic_connection.remote_address(),
+        Some(connection_stop_sx),
+    );
+    connection_manager.add_connection(connection.clone());
+    connection_manager.add_quic_write(connection.connection_id, write_frame_stream);
+
+    read_frame_process(
+        read_frame_stream,
+        connection,
+        connection_manager.clone(),
+        request_queue_sx.clone(),
+        connection_stop_rx,
+    );
+}
+
+fn read_frame_process(
+    mut read_frame_stream: FramedRead<RecvStream, JournalServerCodec>,
+    connection: NetworkConnection,
+    connection_manager: Arc<ConnectionManager>,
+    request_queue_sx: Sender<RequestPackage>,
+    mut connection_stop_rx: Receiver<bool>,
+) {
+    tokio::spawn(async move {
+        loop {
+            select! {
+                val = connection_stop_rx.recv() => {
+                    if let Some(flag) = val {
+                        if flag {
+                            debug!("QUIC stream 【{}】 read thread stopped successfully.", connection.connection_id);
+                            break;
+                        }
+                    }
+                }
+                val = read_frame_stream.next() => {
+                    match val {
+                        Some(Ok(pack)) => {
+                            debug!("revc quic packet:{:?}", pack);
+                            let package =
+                                RequestPackage::new(connection.connection_id, connection.addr, pack);
+                            if let Err(err) = request_queue_sx.send(package).await {
+                                error!("Failed to write data to the request queue, error message: {:?}", err);
+                            }
+                        }
+                        Some(Err(e)) => {
+                            debug!("QUIC stream parsing packet format error message :{:?}", e)
+                        }
+                        None => {
+                            // The client finished or reset the stream, or the whole
+                            // QUIC connection is gone
+                            debug!("QUIC stream 【{}】 closed by the client.", connection.connection_id);
+                            connection_manager.close_connect(connection.connection_id).await;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,459 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::error::common::CommonError;
+use grpc_clients::pool::ClientPool;
+use log::{error, info, warn};
+use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
+use metadata_struct::mqtt::connection::MQTTConnection;
+use protocol::mqtt::common::{ConnectReturnCode, Filter, MqttPacket, MqttProtocol, Subscribe};
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+
+use super::cache::CacheManager;
+use super::retain::try_send_retain_message;
+use super::subscribe::save_subscribe;
+use super::topic_rewrite::rewrite_subscribe_filter;
+use crate::security::AuthDriver;
+use crate::server::connection_manager::ConnectionManager;
+use crate::storage::subscribe::SubscribeStorage;
+use crate::subscribe::sub_common::sub_path_validator;
+use crate::subscribe::subscribe_manager::SubscribeManager;
+
+const CLIENT_ID_PLACEHOLDER: &str = "${clientid}";
+const USERNAME_PLACEHOLDER: &str = "${username}";
+
+pub fn auto_subscribe_rule_key(username: &str, topic: &str) -> String {
+    format!("{}/{}", username, topic)
+}
+
+// Topic filters have no escaping, so a value containing a wildcard or a level separator
+// would subscribe the client to topics outside of its own.
+fn is_safe_placeholder_value(value: &str) -> bool {
+    !value.contains(['+', '#', '/'])
+}
+
+// The topic template with the values of the client filled in, None when the template
+// references a username and the client connected without one, or when a filled in value
+// is not safe to use as a single topic level.
+fn build_auto_subscribe_path(template: &str, client_id: &str, username: &str) -> Option<String> {
+    if template.contains(USERNAME_PLACEHOLDER)
+        && (username.is_empty() || !is_safe_placeholder_value(username))
+    {
+        return None;
+    }
+    if template.contains(CLIENT_ID_PLACEHOLDER) && !is_safe_placeholder_value(client_id) {
+        return None;
+    }
+    Some(
+        template
+            .replace(CLIENT_ID_PLACEHOLDER, client_id)
+            .replace(USERNAME_PLACEHOLDER, username),
+    )
+}
+
+/// The filters the auto subscribe rules create for a client, skipping the ones the
+/// client is already subscribed to, e.g. when its session was resumed.
+pub fn build_auto_subscribe_filters(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+    username: &str,
+) -> Vec<Filter> {
+    if cache_manager.auto_subscribe_rule.is_empty() {
+        return Vec::new();
+    }
+
+    let mut rules: Vec<MqttAutoSubscribeRule> = cache_manager
+        .auto_subscribe_rule
+        .iter()
+        .filter(|rule| rule.username.is_empty() || rule.username == username)
+        .map(|rule| rule.value().clone())
+        .collect();
+    rules.sort_by(|a, b| {
+        a.create_time
+            .cmp(&b.create_time)
+            .then_with(|| a.topic.cmp(&b.topic))
+    });
+
+    let mut paths = HashSet::new();
+    let mut filters = Vec::new();
+    for rule in rules {
+        let path = if let Some(path) = build_auto_subscribe_path(&rule.topic, client_id, username) {
+            rewrite_subscribe_filter(cache_manager, path)
+        } else {
+            continue;
+        };
+
+        if !sub_path_validator(path.clone()) {
+            warn!(
+                "Auto subscribe rule [{}] produced an invalid topic filter [{}] for client [{}]",
+                rule.topic, path, client_id
+            );
+            continue;
+        }
+
+        if !paths.insert(path.clone()) {
+            continue;
+        }
+
+        if let Some(sub_list) = cache_manager.subscribe_filter.get(client_id) {
+            if sub_list.contains_key(&path) {
+                continue;
+            }
+        }
+
+        filters.push(Filter {
+            path,
+            qos: rule.qos,
+            nolocal: rule.no_local,
+            preserve_retain: rule.retain_as_published,
+            retain_forward_rule: rule.retained_handling,
+        });
+    }
+    filters
+}
+
+/// Runs the auto subscribe rules once a successful CONNACK has been written to the
+/// client, so the retained messages of the auto subscriptions never arrive before it.
+pub async fn auto_subscribe_after_connack(
+    connect_id: u64,
+    packet: &MqttPacket,
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    auth_driver: &Arc<AuthDriver>,
+) {
+    if let MqttPacket::ConnAck(conn_ack, _) = packet {
+        if conn_ack.code != ConnectReturnCode::Success {
+            return;
+        }
+    } else {
+        return;
+    }
+
+    let connection = if let Some(connection) = cache_manager.get_connection(connect_id) {
+        connection
+    } else {
+        return;
+    };
+
+    let protocol = if let Some(protocol) = connection_manager.get_connect_protocol(connect_id) {
+        protocol
+    } else {
+        return;
+    };
+
+    try_auto_subscribe(
+        &protocol,
+        &connection,
+        client_pool,
+        cache_manager,
+        subscribe_manager,
+        connection_manager,
+        auth_driver,
+    )
+    .await;
+}
+
+/// Subscribes a client that has just connected to the topics of the auto subscribe
+/// rules, as if the client had sent a SUBSCRIBE for each of them. Filters the ACL
+/// does not allow the client to subscribe to are skipped.
+pub async fn try_auto_subscribe(
+    protocol: &MqttProtocol,
+    connection: &MQTTConnection,
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    auth_driver: &Arc<AuthDriver>,
+) {
+    let client_id = connection.client_id.as_str();
+    for filter in build_auto_subscribe_filters(cache_manager, client_id, &connection.login_user) {
+        let subscribe = Subscribe {
+            packet_identifier: 0,
+            filters: vec![filter.clone()],
+        };
+
+        if !auth_driver.allow_subscribe(connection, &subscribe).await {
+            warn!(
+                "Client [{}] is not authorized to auto subscribe [{}]",
+                client_id, filter.path
+            );
+            continue;
+        }
+
+        match subscribe_manager
+            .save_exclusive_subscribe(subscribe.clone())
+            .await
+        {
+            Ok(None) => {}
+            Ok(Some(code)) => {
+                warn!(
+                    "Client [{}] failed to auto subscribe [{}], reason code: {:?}",
+                    client_id, filter.path, code
+                );
+                continue;
+            }
+            Err(e) => {
+                warn!(
+                    "Client [{}] failed to auto subscribe [{}], error: {}",
+                    client_id, filter.path, e
+                );
+                continue;
+            }
+        }
+
+        if let Err(e) = save_subscribe(client_pool, client_id, protocol, &subscribe, &None).await {
+            warn!(
+                "Client [{}] failed to auto subscribe [{}], error: {}",
+                client_id, filter.path, e
+            );
+            continue;
+        }
+
+        cache_manager.add_client_subscribe(
+            client_id.to_string(),
+            protocol.clone(),
+            subscribe.clone(),
+            None,
+        );
+
+        subscribe_manager
+            .add_subscribe(
+                client_id.to_string(),
+                protocol.clone(),
+                subscribe.clone(),
+                None,
+            )
+            .await;
+
+        try_send_retain_message(
+            protocol.clone(),
+            client_id.to_string(),
+            subscribe,
+            None,
+            cache_manager.clone(),
+            connection_manager.clone(),
+        )
+        .await;
+    }
+}
+
+pub async fn save_auto_subscribe_rule(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    rule: MqttAutoSubscribeRule,
+) -> Result<(), CommonError> {
+    if rule.topic.is_empty() {
+        return Err(CommonError::CommonError(
+            "auto subscribe topic cannot be empty".to_string(),
+        ));
+    }
+    let storage = SubscribeStorage::new(client_pool.clone());
+    storage.save_auto_subscribe_rule(&rule).await?;
+    cache_manager.add_auto_subscribe_rule(rule);
+    Ok(())
+}
+
+pub async fn delete_auto_subscribe_rule(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    username: String,
+    topic: String,
+) -> Result<(), CommonError> {
+    let storage = SubscribeStorage::new(client_pool.clone());
+    storage
+        .delete_auto_subscribe_rule(username.clone(), topic.clone())
+        .await?;
+    cache_manager.remove_auto_subscribe_rule(&username, &topic);
+    Ok(())
+}
+
+pub async fn load_auto_subscribe_rule(
+    cache_manager: &CacheManager,
+    client_pool: &Arc<ClientPool>,
+) -> Result<(), CommonError> {
+    let storage = SubscribeStorage::new(client_pool.clone());
+    let mut keys = HashSet::new();
+    for rule in storage.all_auto_subscribe_rule().await? {
+        keys.insert(auto_subscribe_rule_key(&rule.username, &rule.topic));
+        cache_manager.add_auto_subscribe_rule(rule);
+    }
+    cache_manager
+        .auto_subscribe_rule
+        .retain(|key, _| keys.contains(key));
+    Ok(())
+}
+
+// Rules changed through the admin api of another broker reach this broker by reloading them
+// from the placement center.
+pub struct UpdateAutoSubscribeRuleCache {
+    stop_send: broadcast::Sender<bool>,
+    cache_manager: Arc<CacheManager>,
+    client_pool: Arc<ClientPool>,
+}
+
+impl UpdateAutoSubscribeRuleCache {
+    pub fn new(
+        stop_send: broadcast::Sender<bool>,
+        cache_manager: Arc<CacheManager>,
+        client_pool: Arc<ClientPool>,
+    ) -> Self {
+        UpdateAutoSubscribeRuleCache {
+            stop_send,
+            cache_manager,
+            client_pool,
+        }
+    }
+
+    pub async fn start_update(&self) {
+        loop {
+            let mut stop_rx = self.stop_send.subscribe();
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}","Auto subscribe rule cache updating thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = self.update_auto_subscribe_rule_cache()=>{
+                }
+            }
+        }
+    }
+
+    async fn update_auto_subscribe_rule_cache(&self) {
+        if let Err(e) = load_auto_subscribe_rule(&self.cache_manager, &self.client_pool).await {
+            error!(
+                "Updating auto subscribe rule cache failed, error message: {}",
+                e
+            );
+        }
+        sleep(Duration::from_secs(5)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
+    use protocol::mqtt::common::{Filter, MqttProtocol, QoS, RetainForwardRule, Subscribe};
+
+    use super::{build_auto_subscribe_filters, build_auto_subscribe_path};
+    use crate::handler::cache::CacheManager;
+
+    fn build_rule(topic: &str, username: &str) -> MqttAutoSubscribeRule {
+        MqttAutoSubscribeRule::new(
+            topic.to_string(),
+            QoS::AtLeastOnce,
+            true,
+            false,
+            RetainForwardRule::OnNewSubscribe,
+            username.to_string(),
+        )
+    }
+
+    #[test]
+    fn build_auto_subscribe_path_test() {
+        assert_eq!(
+            build_auto_subscribe_path("devices/${clientid}/cmd", "c1", ""),
+            Some("devices/c1/cmd".to_string())
+        );
+        assert_eq!(
+            build_auto_subscribe_path("users/${username}/${clientid}", "c1", "u1"),
+            Some("users/u1/c1".to_string())
+        );
+        assert_eq!(
+            build_auto_subscribe_path("users/${username}", "c1", ""),
+            None
+        );
+
+        // Wildcards and level separators in the client's values are rejected
+        assert_eq!(
+            build_auto_subscribe_path("devices/${clientid}/cmd", "#", ""),
+            None
+        );
+        assert_eq!(
+            build_auto_subscribe_path("devices/${clientid}/cmd", "c+1", ""),
+            None
+        );
+        assert_eq!(
+            build_auto_subscribe_path("devices/${clientid}/cmd", "other/c1", ""),
+            None
+        );
+        assert_eq!(
+            build_auto_subscribe_path("users/${username}", "c1", "u/#"),
+            None
+        );
+        assert_eq!(
+            build_auto_subscribe_path("devices/fixed", "c/1", ""),
+            Some("devices/fixed".to_string())
+        );
+    }
+
+    #[test]
+    fn build_auto_subscribe_filters_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        assert!(build_auto_subscribe_filters(&cache_manager, "c1", "u1").is_empty());
+
+        cache_manager.add_auto_subscribe_rule(build_rule("devices/${clientid}/cmd", ""));
+        cache_manager.add_auto_subscribe_rule(build_rule("users/${username}/notice", "u1"));
+        cache_manager.add_auto_subscribe_rule(build_rule("admin/${clientid}", "admin"));
+
+        let filters = build_auto_subscribe_filters(&cache_manager, "c1", "u1");
+        let mut paths: Vec<String> = filters.iter().map(|f| f.path.clone()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["devices/c1/cmd", "users/u1/notice"]);
+        assert_eq!(filters[0].qos, QoS::AtLeastOnce);
+        assert!(filters[0].nolocal);
+        assert_eq!(
+            filters[0].retain_forward_rule,
+            RetainForwardRule::OnNewSubscribe
+        );
+
+        // Filters the client is already subscribed to are not created again
+        cache_manager.add_client_subscribe(
+            "c1".to_string(),
+            MqttProtocol::Mqtt5,
+            Subscribe {
+                packet_identifier: 1,
+                filters: vec![Filter {
+                    path: "devices/c1/cmd".to_string(),
+                    qos: QoS::AtMostOnce,
+                    nolocal: false,
+                    preserve_retain: false,
+                    retain_forward_rule: RetainForwardRule::OnEverySubscribe,
+                }],
+            },
+            None,
+        );
+        let filters = build_auto_subscribe_filters(&cache_manager, "c1", "u1");
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters[0].path, "users/u1/notice");
+
+        cache_manager.remove_auto_subscribe_rule("u1", "users/${username}/notice");
+        assert!(build_auto_subscribe_filters(&cache_manager, "c1", "u1").is_empty());
+    }
+}
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use std::collections::HashSet;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 use common_base::config::broker_mqtt::broker_mqtt_conf;
@@ -23,6 +23,7 @@ use grpc_clients::pool::ClientPool;
 use log::warn;
 use metadata_struct::acl::mqtt_acl::MqttAcl;
 use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
+use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
 use metadata_struct::mqtt::cluster::MqttClusterDynamicConfig;
 use metadata_struct::mqtt::connection::MQTTConnection;
 use metadata_struct::mqtt::session::MqttSession;
@@ -36,6 +37,16 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::broadcast::Sender;
 use tokio::time::sleep;
 
+use crate::handler::auto_subscribe::{auto_subscribe_rule_key, load_auto_subscribe_rule};
+use crate::handler::error::MqttBrokerError;
+use crate::handler::retain::RetainMessageCacheData;
+use crate::handler::retain_index::{RetainMessageEntry, RetainMessageIndex};
+use crate::handler::topic_rewrite::{
+    load_topic_rewrite_rule, sort_topic_rewrite_rule, topic_rewrite_rule_key, TopicRewriteRule,
+};
+use crate::observability::slow::sub::SlowSubStore;
+use crate::observability::trace::TraceManager;
+use crate::observability::webhook::WebhookManager;
 use crate::security::acl::metadata::AclMetadata;
 use crate::security::AuthDriver;
 use crate::storage::cluster::ClusterStorage;
@@ -141,6 +152,30 @@ pub struct CacheManager {
 
     // acl metadata
     pub acl_metadata: AclMetadata,
+
+    // retained messages indexed by topic level
+    pub retain_message_index: RetainMessageIndex,
+
+    // (action/source_topic, TopicRewriteRule)
+    pub topic_rewrite_rule: DashMap<String, TopicRewriteRule>,
+
+    // topic_rewrite_rule in evaluation order, rebuilt whenever the rules change
+    pub sorted_topic_rewrite_rule: Arc<RwLock<Vec<TopicRewriteRule>>>,
+
+    // (username/topic, MqttAutoSubscribeRule)
+    pub auto_subscribe_rule: DashMap<String, MqttAutoSubscribeRule>,
+
+    // (client_id, <response_topic, expire_at>)
+    pub response_topic_grant: DashMap<String, DashMap<String, u64>>,
+
+    // slowest subscriptions of this broker
+    pub slow_sub_store: SlowSubStore,
+
+    // admin-started message traces
+    pub trace_manager: TraceManager,
+
+    // outbound webhooks of client and message events
+    pub webhook_manager: WebhookManager,
 }
 
 impl CacheManager {
@@ -161,6 +196,14 @@ impl CacheManager {
             qos_ack_packet: DashMap::with_capacity(8),
             client_pkid_data: DashMap::with_capacity(8),
             acl_metadata: AclMetadata::new(),
+            retain_message_index: RetainMessageIndex::new(),
+            topic_rewrite_rule: DashMap::with_capacity(2),
+            sorted_topic_rewrite_rule: Arc::new(RwLock::new(Vec::new())),
+            auto_subscribe_rule: DashMap::with_capacity(2),
+            response_topic_grant: DashMap::with_capacity(8),
+            slow_sub_store: SlowSubStore::new(),
+            trace_manager: TraceManager::new(),
+            webhook_manager: WebhookManager::new(),
         }
     }
 
@@ -283,16 +326,78 @@ impl CacheManager {
 
     pub fn add_topic(&self, topic_name: &str, topic: &MqttTopic) {
         let t = topic.clone();
+        if let Some(retain_message) = &t.retain_message {
+            if let Some(entry) =
+                RetainMessageEntry::decode(topic_name, retain_message, t.retain_message_expired_at)
+            {
+                self.retain_message_index.insert(entry);
+            }
+        }
         self.topic_info.insert(topic_name.to_owned(), t.clone());
         self.topic_id_name.insert(t.topic_id, topic_name.to_owned());
     }
 
-    pub fn update_topic_retain_message(&self, topic_name: &str, retain_message: Option<Vec<u8>>) {
+    pub fn update_topic_retain_message(
+        &self,
+        topic_name: &str,
+        retain_message: Option<Vec<u8>>,
+        retain_message_expired_at: Option<u64>,
+    ) {
+        let entry = if let Some(message) = &retain_message {
+            RetainMessageEntry::decode(topic_name, message, retain_message_expired_at)
+        } else {
+            None
+        };
+
+        if let Some(entry) = entry {
+            self.retain_message_index.insert(entry);
+        } else {
+            self.retain_message_index.remove(topic_name);
+        }
+
         if let Some(mut topic) = self.topic_info.get_mut(topic_name) {
             topic.retain_message = retain_message;
+            topic.retain_message_expired_at = retain_message_expired_at;
         }
     }
 
+    pub fn add_topic_rewrite_rule(&self, rule: TopicRewriteRule) {
+        self.topic_rewrite_rule.insert(rule.key(), rule);
+        self.rebuild_sorted_topic_rewrite_rule();
+    }
+
+    pub fn remove_topic_rewrite_rule(&self, action: &str, source_topic: &str) {
+        self.topic_rewrite_rule
+            .remove(&topic_rewrite_rule_key(action, source_topic));
+        self.rebuild_sorted_topic_rewrite_rule();
+    }
+
+    // The rules are collected under the write lock, so concurrent rebuilds cannot leave
+    // an older snapshot behind
+    pub fn rebuild_sorted_topic_rewrite_rule(&self) {
+        let mut sorted = match self.sorted_topic_rewrite_rule.write() {
+            Ok(sorted) => sorted,
+            Err(e) => e.into_inner(),
+        };
+        let mut rules: Vec<TopicRewriteRule> = self
+            .topic_rewrite_rule
+            .iter()
+            .map(|rule| rule.value().clone())
+            .collect();
+        sort_topic_rewrite_rule(&mut rules);
+        *sorted = rules;
+    }
+
+    pub fn add_auto_subscribe_rule(&self, rule: MqttAutoSubscribeRule) {
+        self.auto_subscribe_rule
+            .insert(auto_subscribe_rule_key(&rule.username, &rule.topic), rule);
+    }
+
+    pub fn remove_auto_subscribe_rule(&self, username: &str, topic: &str) {
+        self.auto_subscribe_rule
+            .remove(&auto_subscribe_rule_key(username, topic));
+    }
+
     pub fn login_success(&self, connect_id: u64, user_name: String) {
         if let Some(mut conn) = self.connection_info.get_mut(&connect_id) {
             conn.login_success(user_name)
@@ -330,6 +435,7 @@ impl CacheManager {
         self.subscribe_is_new.remove(client_id);
         self.publish_pkid_info.remove(client_id);
         self.heartbeat_data.remove(client_id);
+        self.response_topic_grant.remove(client_id);
 
         for (key, _) in self.qos_ack_packet.clone() {
             if key.starts_with(client_id) {
@@ -339,11 +445,37 @@ impl CacheManager {
 
         for (key, _) in self.client_pkid_data.clone() {
             if key.starts_with(client_id) {
-                self.qos_ack_packet.remove(&key);
+                self.client_pkid_data.remove(&key);
             }
         }
     }
 
+    pub fn add_response_topic_grant(&self, client_id: &str, response_topic: &str, expire_at: u64) {
+        let now = now_second();
+        if let Some(grants) = self.response_topic_grant.get(client_id) {
+            grants.retain(|_, expire| *expire > now);
+            grants.insert(response_topic.to_string(), expire_at);
+            return;
+        }
+        let grants = DashMap::with_capacity(2);
+        grants.insert(response_topic.to_string(), expire_at);
+        self.response_topic_grant
+            .insert(client_id.to_string(), grants);
+    }
+
+    pub fn is_response_topic_granted(&self, client_id: &str, response_topic: &str) -> bool {
+        if let Some(grants) = self.response_topic_grant.get(client_id) {
+            let now = now_second();
+            if let Some(expire_at) = grants.get(response_topic).map(|expire| *expire) {
+                if expire_at > now {
+                    return true;
+                }
+                grants.remove(response_topic);
+            }
+        }
+        false
+    }
+
     pub fn remove_connection(&self, connect_id: u64) {
         self.connection_info.remove(&connect_id);
     }
@@ -426,9 +558,27 @@ impl CacheManager {
         }
     }
 
+    pub fn add_publish_pkid(&self, client_id: &str, pkid: u16) {
+        if let Some(mut pkid_list) = self.publish_pkid_info.get_mut(client_id) {
+            if !pkid_list.contains(&pkid) {
+                pkid_list.push(pkid);
+            }
+        } else {
+            self.publish_pkid_info
+                .insert(client_id.to_owned(), vec![pkid]);
+        }
+    }
+
+    pub fn list_publish_pkid(&self, client_id: &str) -> Vec<u16> {
+        if let Some(pkid_list) = self.publish_pkid_info.get(client_id) {
+            return pkid_list.clone();
+        }
+        Vec::new()
+    }
+
     pub fn remove_pkid_info(&self, client_id: &str, pkid: u16) {
         if let Some(mut pkid_list) = self.publish_pkid_info.get_mut(client_id) {
-            pkid_list.retain(|x| *x == pkid);
+            pkid_list.retain(|x| *x != pkid);
         }
     }
 
@@ -504,6 +654,22 @@ impl CacheManager {
         for blacklist in blacklist_list {
             self.add_blacklist(blacklist);
         }
+
+        // load all topic rewrite rule
+        if let Err(e) = load_topic_rewrite_rule(self, &self.client_pool).await {
+            panic!(
+                "Failed to load the topic rewrite rule list with error message:{}",
+                e
+            );
+        }
+
+        // load all auto subscribe rule
+        if let Err(e) = load_auto_subscribe_rule(self, &self.client_pool).await {
+            panic!(
+                "Failed to load the auto subscribe rule list with error message:{}",
+                e
+            );
+        }
     }
 
     pub async fn init_system_user(&self) {
@@ -600,12 +766,31 @@ impl CacheManager {
         None
     }
 
+    pub fn list_client_pkid(&self, client_id: &str) -> Vec<u16> {
+        let prefix = format!("{}_", client_id);
+        let mut results = Vec::new();
+        for raw in self.client_pkid_data.iter() {
+            if raw.value().client_id != client_id {
+                continue;
+            }
+            if let Some(pkid) = raw.key().strip_prefix(&prefix) {
+                if let Ok(pkid) = pkid.parse::<u16>() {
+                    results.push(pkid);
+                }
+            }
+        }
+        results
+    }
+
     fn key(&self, client_id: &str, pkid: u16) -> String {
         format!("{}_{}", client_id, pkid)
     }
 }
 
-pub fn update_cache_metadata(request: UpdateCacheRequest) {
+pub fn update_cache_metadata(
+    cache_manager: &Arc<CacheManager>,
+    request: UpdateCacheRequest,
+) -> Result<(), MqttBrokerError> {
     match request.resource_type() {
         MqttBrokerUpdateCacheResourceType::Session => match request.action_type() {
             MqttBrokerUpdateCacheActionType::Add => {}
@@ -615,5 +800,19 @@ pub fn update_cache_metadata(request: UpdateCacheRequest) {
             MqttBrokerUpdateCacheActionType::Add => {}
             MqttBrokerUpdateCacheActionType::Delete => {}
         },
+        MqttBrokerUpdateCacheResourceType::RetainMessage => {
+            let data = serde_json::from_slice::<RetainMessageCacheData>(&request.data)?;
+            match request.action_type() {
+                MqttBrokerUpdateCacheActionType::Add => cache_manager.update_topic_retain_message(
+                    &data.topic_name,
+                    Some(data.retain_message),
+                    Some(data.expiry_interval),
+                ),
+                MqttBrokerUpdateCacheActionType::Delete => {
+                    cache_manager.update_topic_retain_message(&data.topic_name, None, None)
+                }
+            }
+        }
     }
+    Ok(())
 }
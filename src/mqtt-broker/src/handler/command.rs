@@ -15,18 +15,25 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use bytes::Bytes;
 use grpc_clients::pool::ClientPool;
 use log::info;
 use protocol::mqtt::common::{
-    is_mqtt3, is_mqtt4, is_mqtt5, ConnectReturnCode, DisconnectReasonCode, MqttPacket, MqttProtocol,
+    is_mqtt3, is_mqtt4, is_mqtt5, ConnectReturnCode, DisconnectReasonCode, MqttPacket,
+    MqttProtocol, PubAckReason, PubRecReason,
 };
+use serde_json::json;
 use storage_adapter::storage::StorageAdapter;
 
 use super::mqtt::MqttService;
 use crate::handler::cache::CacheManager;
+use crate::handler::delay_message::DelayMessageManager;
 use crate::handler::response::{
     response_packet_mqtt_connect_fail, response_packet_mqtt_distinct_by_reason,
 };
+use crate::observability::metrics::messages::{metrics_message_dropped, MessageDropReason};
+use crate::observability::trace::{TraceEvent, TraceEventType};
+use crate::observability::webhook::{WebhookEvent, WebhookEventType};
 use crate::security::AuthDriver;
 use crate::server::connection::NetworkConnection;
 use crate::server::connection_manager::ConnectionManager;
@@ -52,6 +59,7 @@ where
         client_pool: Arc<ClientPool>,
         connection_manager: Arc<ConnectionManager>,
         auth_driver: Arc<AuthDriver>,
+        delay_message_manager: Arc<DelayMessageManager<S>>,
     ) -> Self {
         let mqtt3_service = MqttService::new(
             MqttProtocol::Mqtt3,
@@ -61,6 +69,7 @@ where
             subscribe_manager.clone(),
             client_pool.clone(),
             auth_driver.clone(),
+            delay_message_manager.clone(),
         );
         let mqtt4_service = MqttService::new(
             MqttProtocol::Mqtt4,
@@ -70,6 +79,7 @@ where
             subscribe_manager.clone(),
             client_pool.clone(),
             auth_driver.clone(),
+            delay_message_manager.clone(),
         );
         let mqtt5_service = MqttService::new(
             MqttProtocol::Mqtt5,
@@ -79,6 +89,7 @@ where
             subscribe_manager.clone(),
             client_pool.clone(),
             auth_driver.clone(),
+            delay_message_manager.clone(),
         );
         Command {
             mqtt3_service,
@@ -107,6 +118,9 @@ where
             ));
         }
 
+        self.trace_inbound_packet(tcp_connection.connection_id, &addr, &packet);
+        self.webhook_inbound_packet(tcp_connection.connection_id, &addr, &packet);
+
         match packet {
             MqttPacket::Connect(
                 protocol_version,
@@ -118,6 +132,7 @@ where
             ) => {
                 connect_manager
                     .set_connect_protocol(tcp_connection.connection_id, protocol_version);
+                let client_id = connect.client_id.clone();
 
                 let resp_pkg = if is_mqtt3(protocol_version) {
                     Some(
@@ -172,12 +187,38 @@ where
 
                 let ack_pkg = resp_pkg.unwrap();
                 if let MqttPacket::ConnAck(conn_ack, _) = ack_pkg.clone() {
+                    self.metadata_cache.trace_manager.record(|| {
+                        TraceEvent::new(TraceEventType::Connect, &client_id, "")
+                            .peer_addr(&addr.to_string())
+                            .detail(format!("code: {:?}", conn_ack.code))
+                    });
+                    let username = if let Some(user) = login {
+                        user.username
+                    } else {
+                        "".to_string()
+                    };
+                    self.metadata_cache.webhook_manager.emit(
+                        WebhookEventType::ClientConnack,
+                        || {
+                            WebhookEvent::new(WebhookEventType::ClientConnack, &client_id)
+                                .username(&username)
+                                .peer_addr(&addr.to_string())
+                                .data(json!({ "code": format!("{:?}", conn_ack.code) }))
+                        },
+                    );
                     if conn_ack.code == ConnectReturnCode::Success {
-                        let username = if let Some(user) = login {
-                            user.username
-                        } else {
-                            "".to_string()
-                        };
+                        self.metadata_cache.webhook_manager.emit(
+                            WebhookEventType::ClientConnected,
+                            || {
+                                WebhookEvent::new(
+                                    WebhookEventType::ClientConnected,
+                                    &self.trace_client_id(tcp_connection.connection_id),
+                                )
+                                .username(&username)
+                                .peer_addr(&addr.to_string())
+                                .data(json!({ "proto_ver": protocol_version }))
+                            },
+                        );
                         self.metadata_cache
                             .login_success(tcp_connection.connection_id, username);
                         info!("connect [{}] login success", tcp_connection.connection_id);
@@ -187,26 +228,29 @@ where
             }
 
             MqttPacket::Publish(publish, publish_properties) => {
-                if tcp_connection.is_mqtt3() {
-                    return self
-                        .mqtt3_service
+                let topic = publish.topic.clone();
+                let resp_pkg = if tcp_connection.is_mqtt3() {
+                    self.mqtt3_service
                         .publish(tcp_connection.connection_id, publish, publish_properties)
-                        .await;
-                }
-
-                if tcp_connection.is_mqtt4() {
-                    return self
-                        .mqtt4_service
+                        .await
+                } else if tcp_connection.is_mqtt4() {
+                    self.mqtt4_service
                         .publish(tcp_connection.connection_id, publish, publish_properties)
-                        .await;
-                }
-
-                if tcp_connection.is_mqtt5() {
-                    return self
-                        .mqtt5_service
+                        .await
+                } else if tcp_connection.is_mqtt5() {
+                    self.mqtt5_service
                         .publish(tcp_connection.connection_id, publish, publish_properties)
-                        .await;
-                }
+                        .await
+                } else {
+                    return Some(response_packet_mqtt_connect_fail(
+                        &MqttProtocol::Mqtt5,
+                        ConnectReturnCode::UnsupportedProtocolVersion,
+                        &None,
+                        None,
+                    ));
+                };
+                self.trace_publish_drop(tcp_connection.connection_id, &addr, &topic, &resp_pkg);
+                return resp_pkg;
             }
 
             MqttPacket::PubRec(pub_rec, pub_rec_properties) => {
@@ -458,4 +502,171 @@ where
     pub async fn check_login_status(&self, connection_id: u64) -> bool {
         self.metadata_cache.is_login(connection_id)
     }
+
+    fn trace_client_id(&self, connection_id: u64) -> String {
+        if let Some(conn) = self.metadata_cache.connection_info.get(&connection_id) {
+            return conn.client_id.clone();
+        }
+        "".to_string()
+    }
+
+    // CONNECT is recorded together with its result
+    fn trace_inbound_packet(&self, connection_id: u64, addr: &SocketAddr, packet: &MqttPacket) {
+        let (event, topic, pkid, detail) = match packet {
+            MqttPacket::Publish(publish, _) => (
+                TraceEventType::PublishIn,
+                String::from_utf8_lossy(&publish.topic).to_string(),
+                publish.pkid,
+                format!(
+                    "qos: {:?}, retain: {}, dup: {}, payload_size: {}",
+                    publish.qos,
+                    publish.retain,
+                    publish.dup,
+                    publish.payload.len()
+                ),
+            ),
+            MqttPacket::PubAck(pub_ack, _) => (
+                TraceEventType::Ack,
+                "".to_string(),
+                pub_ack.pkid,
+                format!("PUBACK {:?}", pub_ack.reason),
+            ),
+            MqttPacket::PubRec(pub_rec, _) => (
+                TraceEventType::Ack,
+                "".to_string(),
+                pub_rec.pkid,
+                format!("PUBREC {:?}", pub_rec.reason),
+            ),
+            MqttPacket::PubRel(pub_rel, _) => (
+                TraceEventType::Ack,
+                "".to_string(),
+                pub_rel.pkid,
+                format!("PUBREL {:?}", pub_rel.reason),
+            ),
+            MqttPacket::PubComp(pub_comp, _) => (
+                TraceEventType::Ack,
+                "".to_string(),
+                pub_comp.pkid,
+                format!("PUBCOMP {:?}", pub_comp.reason),
+            ),
+            _ => return,
+        };
+        self.metadata_cache.trace_manager.record(|| {
+            TraceEvent::new(event, &self.trace_client_id(connection_id), &topic)
+                .peer_addr(&addr.to_string())
+                .pkid(pkid)
+                .detail(detail)
+        });
+    }
+
+    // Client events that only depend on the inbound packet itself. Publish, subscribe and
+    // unsubscribe events are emitted by the handlers, once the topics have been rewritten
+    // and checked against the ACL.
+    fn webhook_inbound_packet(&self, connection_id: u64, addr: &SocketAddr, packet: &MqttPacket) {
+        let webhook_manager = &self.metadata_cache.webhook_manager;
+        let (client_id, username) =
+            if let Some(conn) = self.metadata_cache.connection_info.get(&connection_id) {
+                (conn.client_id.clone(), conn.login_user.clone())
+            } else {
+                ("".to_string(), "".to_string())
+            };
+        let peer_addr = addr.to_string();
+        match packet {
+            MqttPacket::Connect(protocol_version, connect, _, _, _, login) => {
+                webhook_manager.emit(WebhookEventType::ClientConnect, || {
+                    let username = if let Some(login) = login {
+                        login.username.as_str()
+                    } else {
+                        ""
+                    };
+                    WebhookEvent::new(WebhookEventType::ClientConnect, &connect.client_id)
+                        .username(username)
+                        .peer_addr(&peer_addr)
+                        .data(json!({
+                            "proto_ver": protocol_version,
+                            "keepalive": connect.keep_alive,
+                            "clean_start": connect.clean_session,
+                        }))
+                });
+            }
+            MqttPacket::PubAck(pub_ack, _) => {
+                webhook_manager.emit(WebhookEventType::MessageAcked, || {
+                    WebhookEvent::new(WebhookEventType::MessageAcked, &client_id)
+                        .username(&username)
+                        .peer_addr(&peer_addr)
+                        .data(json!({ "pkid": pub_ack.pkid, "packet": "PUBACK" }))
+                });
+            }
+            MqttPacket::PubComp(pub_comp, _) => {
+                webhook_manager.emit(WebhookEventType::MessageAcked, || {
+                    WebhookEvent::new(WebhookEventType::MessageAcked, &client_id)
+                        .username(&username)
+                        .peer_addr(&peer_addr)
+                        .data(json!({ "pkid": pub_comp.pkid, "packet": "PUBCOMP" }))
+                });
+            }
+            _ => {}
+        }
+    }
+
+    // A PUBLISH answered with a failure reason was not accepted by the broker
+    fn trace_publish_drop(
+        &self,
+        connection_id: u64,
+        addr: &SocketAddr,
+        topic: &Bytes,
+        resp_pkg: &Option<MqttPacket>,
+    ) {
+        let (pkid, reason, drop_reason) = match resp_pkg {
+            Some(MqttPacket::PubAck(pub_ack, _)) => match pub_ack.reason {
+                Some(PubAckReason::Success) | Some(PubAckReason::NoMatchingSubscribers) | None => {
+                    return
+                }
+                Some(reason) => {
+                    let drop_reason = match reason {
+                        PubAckReason::NotAuthorized => MessageDropReason::AclDenied,
+                        PubAckReason::QuotaExceeded => MessageDropReason::InflightFull,
+                        _ => MessageDropReason::Rejected,
+                    };
+                    (pub_ack.pkid, format!("{:?}", reason), drop_reason)
+                }
+            },
+            Some(MqttPacket::PubRec(pub_rec, _)) => match pub_rec.reason {
+                Some(PubRecReason::Success) | Some(PubRecReason::NoMatchingSubscribers) | None => {
+                    return
+                }
+                Some(reason) => {
+                    let drop_reason = match reason {
+                        PubRecReason::NotAuthorized => MessageDropReason::AclDenied,
+                        PubRecReason::QuotaExceeded => MessageDropReason::InflightFull,
+                        _ => MessageDropReason::Rejected,
+                    };
+                    (pub_rec.pkid, format!("{:?}", reason), drop_reason)
+                }
+            },
+            _ => return,
+        };
+        metrics_message_dropped(drop_reason, &String::from_utf8_lossy(topic));
+        self.metadata_cache.trace_manager.record(|| {
+            TraceEvent::new(
+                TraceEventType::Drop,
+                &self.trace_client_id(connection_id),
+                &String::from_utf8_lossy(topic),
+            )
+            .peer_addr(&addr.to_string())
+            .pkid(pkid)
+            .detail(format!("publish rejected: {}", reason))
+        });
+        self.metadata_cache
+            .webhook_manager
+            .emit(WebhookEventType::MessageDropped, || {
+                WebhookEvent::new(
+                    WebhookEventType::MessageDropped,
+                    &self.trace_client_id(connection_id),
+                )
+                .peer_addr(&addr.to_string())
+                .topic(&String::from_utf8_lossy(topic))
+                .data(json!({ "pkid": pkid, "reason": reason }))
+            });
+    }
 }
@@ -15,21 +15,27 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use axum::extract::ws::Message;
+use bytes::BytesMut;
 use common_base::error::common::CommonError;
 use common_base::tools::{now_second, unique_id};
 use grpc_clients::pool::ClientPool;
+use log::warn;
 use metadata_struct::mqtt::cluster::MqttClusterDynamicConfig;
 use metadata_struct::mqtt::connection::{ConnectionConfig, MQTTConnection};
-use protocol::mqtt::common::{Connect, ConnectProperties};
+use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
+use protocol::mqtt::common::{Connect, ConnectProperties, DisconnectReasonCode};
 
 use super::cache::CacheManager;
 use super::keep_alive::client_keep_live_time;
+use super::response::response_packet_mqtt_distinct_by_reason;
+use crate::observability::webhook::{WebhookEvent, WebhookEventType};
+use crate::security::acl::response_topic::build_response_information;
+use crate::server::connection::NetworkConnectionType;
 use crate::server::connection_manager::ConnectionManager;
 use crate::storage::session::SessionStorage;
 use crate::subscribe::subscribe_manager::SubscribeManager;
 
-pub const REQUEST_RESPONSE_PREFIX_NAME: &str = "/sys/request_response/";
-
 pub fn build_connection(
     connect_id: u64,
     client_id: String,
@@ -98,11 +104,14 @@ pub fn get_client_id(client_id: &str) -> (String, bool) {
     }
 }
 
-pub fn response_information(connect_properties: &Option<ConnectProperties>) -> Option<String> {
+pub fn response_information(
+    client_id: &str,
+    connect_properties: &Option<ConnectProperties>,
+) -> Option<String> {
     if let Some(properties) = connect_properties {
         if let Some(request_response_info) = properties.request_response_info {
             if request_response_info == 1 {
-                return Some(REQUEST_RESPONSE_PREFIX_NAME.to_string());
+                return Some(build_response_information(client_id));
             }
         }
     }
@@ -120,6 +129,22 @@ pub async fn disconnect_connection(
     subscribe_manager
         .remove_exclusive_subscribe_by_client_id(client_id)
         .await?;
+    cache_manager
+        .webhook_manager
+        .emit(WebhookEventType::ClientDisconnected, || {
+            let mut event = WebhookEvent::new(WebhookEventType::ClientDisconnected, client_id);
+            if let Some(conn) = cache_manager.get_connection(connect_id) {
+                event = event
+                    .username(&conn.login_user)
+                    .peer_addr(&conn.source_ip_addr)
+                    .data(serde_json::json!({
+                        "connected_at": conn.create_time,
+                        "disconnected_at": now_second(),
+                    }));
+            }
+            event
+        });
+
     // Remove the connection cache
     cache_manager.remove_connection(connect_id);
     // Remove the client id bound connection information
@@ -144,15 +169,59 @@ pub async fn disconnect_connection(
     Ok(())
 }
 
+/// Tell the client why the broker is about to close its connection. MQTT 3.1.1
+/// clients get a DISCONNECT without reason code. Failures are only logged, since
+/// the connection is closed either way.
+pub async fn send_disconnect_by_reason(
+    connection_manager: &Arc<ConnectionManager>,
+    connect_id: u64,
+    reason_code: DisconnectReasonCode,
+) {
+    let network = if let Some(network) = connection_manager.get_connect(connect_id) {
+        network
+    } else {
+        return;
+    };
+    let protocol = if let Some(protocol) = network.protocol.clone() {
+        protocol
+    } else {
+        return;
+    };
+
+    let wrap = MqttPacketWrapper {
+        protocol_version: protocol.clone().into(),
+        packet: response_packet_mqtt_distinct_by_reason(&protocol, Some(reason_code)),
+    };
+
+    let result = match network.connection_type {
+        NetworkConnectionType::WebSocket | NetworkConnectionType::WebSockets => {
+            let mut codec = MqttCodec::new(Some(protocol.into()));
+            let mut buff = BytesMut::new();
+            if let Err(e) = codec.encode_data(wrap.clone(), &mut buff) {
+                warn!("Websocket encode back packet failed with error message: {e:?}");
+                return;
+            }
+            connection_manager
+                .write_websocket_frame(connect_id, wrap, Message::Binary(buff.to_vec()))
+                .await
+        }
+        _ => connection_manager.write_tcp_frame(connect_id, wrap).await,
+    };
+
+    if let Err(e) = result {
+        warn!(
+            "Failed to send DISCONNECT ({:?}) to connection [{}], error: {}",
+            reason_code, connect_id, e
+        );
+    }
+}
+
 #[cfg(test)]
 mod test {
     use metadata_struct::mqtt::cluster::MqttClusterDynamicConfig;
     use protocol::mqtt::common::{Connect, ConnectProperties};
 
-    use super::{
-        build_connection, get_client_id, response_information, MQTTConnection,
-        REQUEST_RESPONSE_PREFIX_NAME,
-    };
+    use super::{build_connection, get_client_id, response_information, MQTTConnection};
 
     #[tokio::test]
     pub async fn build_connection_test() {
@@ -216,17 +285,17 @@ mod test {
             request_response_info: Some(1),
             ..Default::default()
         };
-        let res = response_information(&Some(connect_properties));
-        assert_eq!(res.unwrap(), REQUEST_RESPONSE_PREFIX_NAME.to_string());
+        let res = response_information("c1", &Some(connect_properties));
+        assert_eq!(res.unwrap(), "$response/c1/".to_string());
 
-        let res = response_information(&Some(ConnectProperties::default()));
+        let res = response_information("c1", &Some(ConnectProperties::default()));
         assert!(res.is_none());
 
         let connect_properties = ConnectProperties {
             request_response_info: Some(0),
             ..Default::default()
         };
-        let res = response_information(&Some(connect_properties));
+        let res = response_information("c1", &Some(connect_properties));
         assert!(res.is_none());
     }
 
@@ -238,6 +307,8 @@ mod test {
         assert_eq!(conn.get_recv_qos_message(), 1);
         conn.recv_qos_message_decr();
         assert_eq!(conn.get_recv_qos_message(), 0);
+        conn.recv_qos_message_decr();
+        assert_eq!(conn.get_recv_qos_message(), 0);
     }
 
     #[tokio::test]
@@ -22,3 +22,5 @@ pub const METRICS_KEY_LABEL_NAME: &str = "label";
 pub const METRICS_KEY_TYPE_NAME: &str = "type";
 pub const METRICS_KEY_QOS: &str = "qos";
 pub const METRICS_KEY_RETAIN: &str = "retain";
+pub const METRICS_KEY_REASON: &str = "reason";
+pub const METRICS_KEY_TOPIC: &str = "topic";
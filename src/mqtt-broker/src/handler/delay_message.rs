@@ -0,0 +1,736 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use common_base::error::common::CommonError;
+use common_base::tools::{now_second, unique_id};
+use dashmap::DashMap;
+use grpc_clients::pool::ClientPool;
+use log::{error, info, warn};
+use metadata_struct::adapter::read_config::ReadConfig;
+use metadata_struct::adapter::record::Record;
+use metadata_struct::mqtt::message::MqttMessage;
+use serde::{Deserialize, Serialize};
+use storage_adapter::storage::{ShardConfig, StorageAdapter};
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+
+use super::cache::CacheManager;
+use super::error::MqttBrokerError;
+use super::retain::store_retain_message;
+use crate::storage::message::{cluster_name, MessageStorage};
+
+pub const DELAY_TOPIC_PREFIX: &str = "$delayed/";
+
+// Shard holding the delayed messages of a broker, in the order they were received
+const DELAY_MESSAGE_SHARD_PREFIX: &str = "$delay-message";
+// Shard holding the ids of the delayed messages that were released or cancelled
+const DELAY_MESSAGE_DONE_SHARD_PREFIX: &str = "$delay-message-done";
+// Group whose committed offsets are where `load` starts reading each shard
+const DELAY_MESSAGE_GROUP_PREFIX: &str = "$delay-message-group";
+const DELAY_MESSAGE_READ_BATCH: u64 = 1000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DelayMessage {
+    pub delay_id: String,
+    pub client_id: String,
+    pub topic_name: String,
+    pub topic_id: String,
+    pub deliver_time: u64,
+    pub create_time: u64,
+    pub record: Record,
+    // Expiry interval of the retained message, None when the publish was not retained
+    #[serde(default)]
+    pub retain_expiry_interval: Option<u64>,
+    // Offset of the message in the delay shard
+    #[serde(skip)]
+    pub offset: u64,
+}
+
+// Content of a record in the done shard
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct DelayMessageDone {
+    delay_id: String,
+    offset: u64,
+}
+
+// Tracks which part of the two shards is still needed to rebuild the pending set
+#[derive(Default)]
+struct DelayShardCursor {
+    // Offsets in the delay shard of the pending messages
+    pending_offsets: BTreeSet<u64>,
+    // Next offset of the delay shard
+    message_end: u64,
+    // (offset in the done shard, offset in the delay shard) of the done records
+    // whose message is at or after the committed start of the delay shard
+    done_offsets: BTreeMap<u64, u64>,
+    // Next offset of the done shard
+    done_end: u64,
+    // Start offsets of the delay shard and the done shard last committed
+    committed: (u64, u64),
+}
+
+impl DelayShardCursor {
+    // New start offsets of the two shards, None when they did not move
+    fn compact(&mut self) -> Option<(u64, u64)> {
+        let message_start = if let Some(offset) = self.pending_offsets.first() {
+            *offset
+        } else {
+            self.message_end
+        };
+        self.done_offsets
+            .retain(|_, message_offset| *message_offset >= message_start);
+        let done_start = if let Some((offset, _)) = self.done_offsets.first_key_value() {
+            *offset
+        } else {
+            self.done_end
+        };
+
+        if (message_start, done_start) == self.committed {
+            return None;
+        }
+        Some((message_start, done_start))
+    }
+}
+
+pub fn is_delay_topic(topic_name: &str) -> bool {
+    topic_name.starts_with(DELAY_TOPIC_PREFIX)
+}
+
+/// Splits `$delayed/{seconds}/{topic}` into the delay in seconds and the real topic.
+/// Delays above `max_delay_secs`, when it is not 0, or whose delivery time does not fit
+/// in a u64 are rejected.
+pub fn decode_delay_topic(
+    topic_name: &str,
+    max_delay_secs: u64,
+) -> Result<(u64, String), MqttBrokerError> {
+    let raw = if let Some(raw) = topic_name.strip_prefix(DELAY_TOPIC_PREFIX) {
+        raw
+    } else {
+        return Err(MqttBrokerError::InvalidDelayTopic(topic_name.to_string()));
+    };
+
+    if let Some((secs, real_topic)) = raw.split_once('/') {
+        if let Ok(secs) = secs.parse::<u64>() {
+            let in_range = (max_delay_secs == 0 || secs <= max_delay_secs)
+                && now_second().checked_add(secs).is_some();
+            if in_range && !real_topic.is_empty() {
+                return Ok((secs, real_topic.to_string()));
+            }
+        }
+    }
+    Err(MqttBrokerError::InvalidDelayTopic(topic_name.to_string()))
+}
+
+/// Durable delay queue of the messages published to `$delayed/{seconds}/{topic}`.
+///
+/// Delayed messages are appended to a shard owned by this broker and indexed in
+/// memory by delivery time. Released or cancelled messages are recorded in a second
+/// shard, so that the pending set can be rebuilt from storage after a restart. The
+/// offsets before the oldest pending message, and the done records that only refer
+/// to them, are committed as consumed, so a restart reads only the live tail of
+/// both shards.
+///
+/// A message is marked done before it is appended to its topic, so a crash in
+/// between loses the message instead of releasing it twice.
+pub struct DelayMessageManager<S> {
+    cache_manager: Arc<CacheManager>,
+    client_pool: Arc<ClientPool>,
+    message_storage_adapter: Arc<S>,
+    // (delay_id, DelayMessage)
+    pending: DashMap<String, DelayMessage>,
+    // (deliver_time, delay_id)
+    time_index: Mutex<BTreeSet<(u64, String)>>,
+    cursor: Mutex<DelayShardCursor>,
+    // Writes to the delay shard that are not registered in the cursor yet
+    saving: AtomicUsize,
+}
+
+impl<S> DelayMessageManager<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        cache_manager: Arc<CacheManager>,
+        client_pool: Arc<ClientPool>,
+        message_storage_adapter: Arc<S>,
+    ) -> Self {
+        DelayMessageManager {
+            cache_manager,
+            client_pool,
+            message_storage_adapter,
+            pending: DashMap::with_capacity(8),
+            time_index: Mutex::new(BTreeSet::new()),
+            cursor: Mutex::new(DelayShardCursor::default()),
+            saving: AtomicUsize::new(0),
+        }
+    }
+
+    pub async fn start(&self, stop_send: broadcast::Sender<bool>) {
+        if let Err(e) = self.load().await {
+            panic!("Failed to load delayed messages, error message: {}", e);
+        }
+        info!(
+            "Delay message queue started, {} delayed messages pending",
+            self.pending.len()
+        );
+
+        let mut stop_rx = stop_send.subscribe();
+        loop {
+            // Not raced against the stop signal, a release that is cut short could
+            // leave a message marked done but never appended to its topic
+            self.release_due_message().await;
+            self.try_compact().await;
+
+            select! {
+                val = stop_rx.recv() => {
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}", "Delay message queue stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = sleep(Duration::from_millis(100)) => {}
+            }
+        }
+    }
+
+    pub async fn save(
+        &self,
+        client_id: &str,
+        topic_name: &str,
+        topic_id: &str,
+        delay_secs: u64,
+        record: Record,
+        retain_expiry_interval: Option<u64>,
+    ) -> Result<String, CommonError> {
+        let create_time = now_second();
+        let deliver_time = if let Some(deliver_time) = create_time.checked_add(delay_secs) {
+            deliver_time
+        } else {
+            return Err(CommonError::CommonError(format!(
+                "delay of {} seconds is out of range",
+                delay_secs
+            )));
+        };
+        let mut message = DelayMessage {
+            delay_id: unique_id(),
+            client_id: client_id.to_string(),
+            topic_name: topic_name.to_string(),
+            topic_id: topic_id.to_string(),
+            deliver_time,
+            create_time,
+            record,
+            retain_expiry_interval,
+            offset: 0,
+        };
+
+        let mut raw = Record::build_byte(serde_json::to_vec(&message)?);
+        raw.key = message.delay_id.clone();
+        raw.timestamp = message.deliver_time;
+
+        self.saving.fetch_add(1, Ordering::SeqCst);
+        let result = self
+            .message_storage_adapter
+            .write(cluster_name(), message_shard_name(), raw)
+            .await;
+        let offset = match result {
+            Ok(offset) => offset,
+            Err(e) => {
+                self.saving.fetch_sub(1, Ordering::SeqCst);
+                return Err(e);
+            }
+        };
+
+        message.offset = offset;
+        let delay_id = message.delay_id.clone();
+        {
+            let mut cursor = self.cursor.lock().unwrap();
+            cursor.pending_offsets.insert(offset);
+            cursor.message_end = cursor.message_end.max(offset + 1);
+        }
+        self.add_pending(message);
+        self.saving.fetch_sub(1, Ordering::SeqCst);
+        Ok(delay_id)
+    }
+
+    pub fn list(&self, topic_name: &str) -> Vec<DelayMessage> {
+        let index = self.time_index.lock().unwrap();
+        let mut results = Vec::new();
+        for (_, delay_id) in index.iter() {
+            if let Some(message) = self.pending.get(delay_id) {
+                if topic_name.is_empty() || message.topic_name == topic_name {
+                    results.push(message.clone());
+                }
+            }
+        }
+        results
+    }
+
+    pub async fn cancel(&self, delay_id: &str) -> Result<(), MqttBrokerError> {
+        let message = if let Some(message) = self.remove_pending(delay_id) {
+            message
+        } else {
+            return Err(MqttBrokerError::DelayMessageNotFound(delay_id.to_string()));
+        };
+        if let Err(e) = self.mark_done(&message).await {
+            self.add_pending(message);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    async fn release_due_message(&self) {
+        for delay_id in self.due_message_ids(now_second()) {
+            let message = if let Some(message) = self.remove_pending(&delay_id) {
+                message
+            } else {
+                continue;
+            };
+
+            if let Err(e) = self.mark_done(&message).await {
+                // Left in the queue, the release is retried on the next round
+                warn!(
+                    "Failed to mark delayed message [{}] as released, error message: {}",
+                    delay_id, e
+                );
+                self.add_pending(message);
+                continue;
+            }
+
+            let message_storage = MessageStorage::new(self.message_storage_adapter.clone());
+            if let Err(e) = message_storage
+                .append_topic_message(&message.topic_id, vec![message.record.clone()])
+                .await
+            {
+                // Already marked done, so the retry only survives while the broker is up
+                warn!(
+                    "Failed to release delayed message [{}] to topic [{}], error message: {}",
+                    delay_id, message.topic_name, e
+                );
+                self.add_pending(message);
+                continue;
+            }
+
+            self.try_retain(&message).await;
+        }
+    }
+
+    // A delayed publish with the retain flag replaces the retained message of its
+    // topic when it is released, not when it is received
+    async fn try_retain(&self, message: &DelayMessage) {
+        let expiry_interval = if let Some(expiry_interval) = message.retain_expiry_interval {
+            expiry_interval
+        } else {
+            return;
+        };
+
+        let result = match MqttMessage::decode_record(message.record.clone()) {
+            Ok(retain_message) => {
+                store_retain_message(
+                    &self.cache_manager,
+                    &self.client_pool,
+                    message.topic_name.clone(),
+                    retain_message,
+                    expiry_interval,
+                )
+                .await
+            }
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            error!(
+                "Failed to retain delayed message [{}] of topic [{}], error message: {}",
+                message.delay_id, message.topic_name, e
+            );
+        }
+    }
+
+    async fn load(&self) -> Result<(), CommonError> {
+        let message_storage = MessageStorage::new(self.message_storage_adapter.clone());
+        let message_start = message_storage
+            .get_group_offset(&message_group_name())
+            .await?;
+        let done_start = message_storage.get_group_offset(&done_group_name()).await?;
+
+        let messages = self.read_all(&message_shard_name(), message_start).await?;
+        if messages.is_empty() && message_start == 0 {
+            let namespace = cluster_name();
+            for shard_name in [message_shard_name(), done_shard_name()] {
+                self.message_storage_adapter
+                    .create_shard(namespace.clone(), shard_name, ShardConfig::default())
+                    .await?;
+            }
+            return Ok(());
+        }
+
+        let mut cursor = DelayShardCursor {
+            message_end: message_start,
+            done_end: done_start,
+            committed: (message_start, done_start),
+            ..Default::default()
+        };
+
+        let mut done = HashSet::new();
+        for record in self.read_all(&done_shard_name(), done_start).await? {
+            let done_offset = record.offset.unwrap_or(cursor.done_end);
+            cursor.done_end = done_offset + 1;
+            let entry = serde_json::from_slice::<DelayMessageDone>(&record.data)?;
+            cursor.done_offsets.insert(done_offset, entry.offset);
+            done.insert(entry.delay_id);
+        }
+
+        for record in messages {
+            let offset = record.offset.unwrap_or(cursor.message_end);
+            cursor.message_end = offset + 1;
+            if done.contains(&record.key) {
+                continue;
+            }
+            let mut message = serde_json::from_slice::<DelayMessage>(&record.data)?;
+            message.offset = offset;
+            cursor.pending_offsets.insert(offset);
+            self.add_pending(message);
+        }
+
+        *self.cursor.lock().unwrap() = cursor;
+        Ok(())
+    }
+
+    async fn read_all(&self, shard_name: &str, start: u64) -> Result<Vec<Record>, CommonError> {
+        let mut results = Vec::new();
+        let mut offset = start;
+        loop {
+            let mut read_config = ReadConfig::new();
+            read_config.max_record_num = DELAY_MESSAGE_READ_BATCH;
+            let records = self
+                .message_storage_adapter
+                .read_by_offset(cluster_name(), shard_name.to_string(), offset, read_config)
+                .await?;
+            if records.is_empty() {
+                break;
+            }
+            offset += records.len() as u64;
+            results.extend(records);
+        }
+        Ok(results)
+    }
+
+    async fn mark_done(&self, message: &DelayMessage) -> Result<(), CommonError> {
+        let done = DelayMessageDone {
+            delay_id: message.delay_id.clone(),
+            offset: message.offset,
+        };
+        let mut raw = Record::build_byte(serde_json::to_vec(&done)?);
+        raw.key = message.delay_id.clone();
+        let done_offset = self
+            .message_storage_adapter
+            .write(cluster_name(), done_shard_name(), raw)
+            .await?;
+
+        let mut cursor = self.cursor.lock().unwrap();
+        cursor.pending_offsets.remove(&message.offset);
+        cursor.done_offsets.insert(done_offset, message.offset);
+        cursor.done_end = cursor.done_end.max(done_offset + 1);
+        Ok(())
+    }
+
+    // Commit the offsets before which neither shard has to be read on the next load
+    async fn try_compact(&self) {
+        let starts = {
+            let mut cursor = self.cursor.lock().unwrap();
+            // A message being written may land before the end known to the cursor
+            if self.saving.load(Ordering::SeqCst) > 0 {
+                return;
+            }
+            cursor.compact()
+        };
+        let (message_start, done_start) = if let Some(starts) = starts {
+            starts
+        } else {
+            return;
+        };
+
+        // The delay shard goes first, a done start committed ahead of it would drop
+        // done records that a load from the older delay start still needs
+        let message_storage = MessageStorage::new(self.message_storage_adapter.clone());
+        let result = match message_storage
+            .commit_group_offset(&message_group_name(), &message_shard_name(), message_start)
+            .await
+        {
+            Ok(()) => {
+                message_storage
+                    .commit_group_offset(&done_group_name(), &done_shard_name(), done_start)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => {
+                self.cursor.lock().unwrap().committed = (message_start, done_start);
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to commit the delay message offsets, error message: {}",
+                    e
+                );
+            }
+        }
+    }
+
+    fn due_message_ids(&self, now: u64) -> Vec<String> {
+        let index = self.time_index.lock().unwrap();
+        index
+            .iter()
+            .take_while(|(deliver_time, _)| *deliver_time <= now)
+            .map(|(_, delay_id)| delay_id.clone())
+            .collect()
+    }
+
+    fn add_pending(&self, message: DelayMessage) {
+        let mut index = self.time_index.lock().unwrap();
+        index.insert((message.deliver_time, message.delay_id.clone()));
+        self.pending.insert(message.delay_id.clone(), message);
+    }
+
+    fn remove_pending(&self, delay_id: &str) -> Option<DelayMessage> {
+        let mut index = self.time_index.lock().unwrap();
+        if let Some((_, message)) = self.pending.remove(delay_id) {
+            index.remove(&(message.deliver_time, message.delay_id.clone()));
+            return Some(message);
+        }
+        None
+    }
+}
+
+// Each broker releases only the delayed messages it received, so the shards are per broker
+fn message_shard_name() -> String {
+    format!(
+        "{}-{}",
+        DELAY_MESSAGE_SHARD_PREFIX,
+        broker_mqtt_conf().broker_id
+    )
+}
+
+fn done_shard_name() -> String {
+    format!(
+        "{}-{}",
+        DELAY_MESSAGE_DONE_SHARD_PREFIX,
+        broker_mqtt_conf().broker_id
+    )
+}
+
+fn message_group_name() -> String {
+    format!("{}-{}", DELAY_MESSAGE_GROUP_PREFIX, message_shard_name())
+}
+
+fn done_group_name() -> String {
+    format!("{}-{}", DELAY_MESSAGE_GROUP_PREFIX, done_shard_name())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::config::broker_mqtt::init_broker_mqtt_conf_by_path;
+    use common_base::tools::now_second;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::adapter::record::Record;
+    use storage_adapter::memory::MemoryStorageAdapter;
+
+    use super::{
+        decode_delay_topic, done_group_name, is_delay_topic, message_group_name,
+        DelayMessageManager,
+    };
+    use crate::handler::cache::CacheManager;
+    use crate::handler::error::MqttBrokerError;
+    use crate::storage::message::MessageStorage;
+
+    fn build_manager(
+        storage_adapter: Arc<MemoryStorageAdapter>,
+    ) -> DelayMessageManager<MemoryStorageAdapter> {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        DelayMessageManager::new(cache_manager, client_pool, storage_adapter)
+    }
+
+    #[test]
+    fn decode_delay_topic_test() {
+        assert!(is_delay_topic("$delayed/10/a/b"));
+        assert!(!is_delay_topic("a/$delayed/10/b"));
+
+        let (secs, topic) = decode_delay_topic("$delayed/10/a/b", 3600).unwrap();
+        assert_eq!(secs, 10);
+        assert_eq!(topic, "a/b");
+
+        assert!(decode_delay_topic("$delayed/10/", 3600).is_err());
+        assert!(decode_delay_topic("$delayed/abc/a", 3600).is_err());
+        assert!(decode_delay_topic("$delayed/10", 3600).is_err());
+        assert!(decode_delay_topic("a/b", 3600).is_err());
+    }
+
+    #[test]
+    fn decode_delay_topic_range_test() {
+        assert!(decode_delay_topic("$delayed/3600/t", 3600).is_ok());
+        assert!(matches!(
+            decode_delay_topic("$delayed/3601/t", 3600),
+            Err(MqttBrokerError::InvalidDelayTopic(_))
+        ));
+
+        // Even without a limit the delivery time must not overflow
+        assert!(decode_delay_topic("$delayed/4294967/t", 0).is_ok());
+        assert!(matches!(
+            decode_delay_topic("$delayed/18446744073709551615/t", 0),
+            Err(MqttBrokerError::InvalidDelayTopic(_))
+        ));
+        assert!(matches!(
+            decode_delay_topic("$delayed/18446744073709551615/t", 4294967),
+            Err(MqttBrokerError::InvalidDelayTopic(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn delay_message_manager_test() {
+        let path = format!(
+            "{}/../../config/mqtt-server.toml",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        init_broker_mqtt_conf_by_path(&path);
+
+        let storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let manager = build_manager(storage_adapter.clone());
+        manager.load().await.unwrap();
+
+        let due_id = manager
+            .save(
+                "c1",
+                "t1",
+                "topic-1",
+                0,
+                Record::build_byte(b"now".to_vec()),
+                None,
+            )
+            .await
+            .unwrap();
+        let later_id = manager
+            .save(
+                "c1",
+                "t2",
+                "topic-2",
+                3600,
+                Record::build_byte(b"later".to_vec()),
+                None,
+            )
+            .await
+            .unwrap();
+        let cancel_id = manager
+            .save(
+                "c1",
+                "t2",
+                "topic-2",
+                7200,
+                Record::build_byte(b"cancel".to_vec()),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(manager
+            .save(
+                "c1",
+                "t3",
+                "topic-3",
+                u64::MAX,
+                Record::build_byte(b"overflow".to_vec()),
+                None,
+            )
+            .await
+            .is_err());
+
+        let list = manager.list("");
+        assert_eq!(list.len(), 3);
+        assert_eq!(list[0].delay_id, due_id);
+        assert!(list[1].deliver_time >= now_second() + 3600 - 1);
+        assert_eq!(manager.list("t2").len(), 2);
+
+        manager.cancel(&cancel_id).await.unwrap();
+        assert!(manager.cancel(&cancel_id).await.is_err());
+
+        manager.release_due_message().await;
+        let list = manager.list("");
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].delay_id, later_id);
+
+        let message_storage = MessageStorage::new(storage_adapter.clone());
+        let records = message_storage
+            .read_topic_message("topic-1", 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].data, b"now".to_vec());
+
+        // A restarted broker only recovers the message that is still pending
+        let manager = build_manager(storage_adapter.clone());
+        manager.load().await.unwrap();
+        let list = manager.list("");
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].delay_id, later_id);
+
+        // Everything before the pending message is committed as consumed, so the
+        // next load starts reading at its offset
+        manager.try_compact().await;
+        assert_eq!(
+            message_storage
+                .get_group_offset(&message_group_name())
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            message_storage
+                .get_group_offset(&done_group_name())
+                .await
+                .unwrap(),
+            0
+        );
+
+        manager.cancel(&later_id).await.unwrap();
+        manager.try_compact().await;
+        assert_eq!(
+            message_storage
+                .get_group_offset(&message_group_name())
+                .await
+                .unwrap(),
+            3
+        );
+        assert_eq!(
+            message_storage
+                .get_group_offset(&done_group_name())
+                .await
+                .unwrap(),
+            3
+        );
+
+        let manager = build_manager(storage_adapter);
+        manager.load().await.unwrap();
+        assert!(manager.list("").is_empty());
+        assert!(manager.cursor.lock().unwrap().done_offsets.is_empty());
+    }
+}
@@ -32,9 +32,6 @@ pub enum MqttBrokerError {
     #[error("{0}")]
     SerdeJsonError(#[from] serde_json::Error),
 
-    #[error("{0}")]
-    GrepError(#[from] grep::regex::Error),
-
     #[error("{0}")]
     FromMysqlError(#[from] mysql::Error),
 
@@ -90,6 +87,15 @@ pub enum MqttBrokerError {
 
     #[error("invalid acl permission")]
     InvalidAclPermission,
+
+    #[error("Delayed publish topic [{0}] must be in the format $delayed/{{seconds}}/{{topic}}")]
+    InvalidDelayTopic(String),
+
+    #[error("Delayed message [{0}] does not exist")]
+    DelayMessageNotFound(String),
+
+    #[error("Topic rewrite rule is invalid: {0}")]
+    InvalidTopicRewriteRule(String),
 }
 
 impl From<MqttBrokerError> for Status {
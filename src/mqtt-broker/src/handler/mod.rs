@@ -13,11 +13,13 @@
 // limitations under the License.
 
 pub mod acl;
+pub mod auto_subscribe;
 pub mod cache;
 pub mod cluster_config;
 pub mod command;
 pub mod connection;
 pub mod constant;
+pub mod delay_message;
 pub mod error;
 pub mod flow_control;
 pub mod heartbreat;
@@ -26,9 +28,14 @@ pub mod lastwill;
 pub mod message;
 pub mod mqtt;
 pub mod pkid;
+pub mod qos2_state;
 pub mod response;
 pub mod retain;
+pub mod retain_index;
 pub mod session;
+pub mod subscribe;
+pub mod takeover;
 pub mod topic;
+pub mod topic_rewrite;
 pub mod user;
 pub mod validator;
@@ -15,6 +15,7 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use common_base::telemetry::{extract_user_properties_context, set_span_parent};
 use common_base::tools::now_second;
 use grpc_clients::pool::ClientPool;
 use log::{error, warn};
@@ -27,7 +28,9 @@ use protocol::mqtt::common::{
     PublishProperties, QoS, Subscribe, SubscribeProperties, SubscribeReasonCode, UnsubAckReason,
     Unsubscribe, UnsubscribeProperties,
 };
+use serde_json::json;
 use storage_adapter::storage::StorageAdapter;
+use tracing::Span;
 
 use super::connection::disconnect_connection;
 use super::flow_control::is_flow_control;
@@ -37,8 +40,10 @@ use crate::handler::cache::{
     CacheManager, ConnectionLiveTime, QosAckPackageData, QosAckPackageType,
 };
 use crate::handler::connection::{build_connection, get_client_id};
+use crate::handler::delay_message::{decode_delay_topic, is_delay_topic, DelayMessageManager};
 use crate::handler::lastwill::save_last_will_message;
 use crate::handler::pkid::{pkid_delete, pkid_exists, pkid_save};
+use crate::handler::qos2_state::{clear_qos2_state, restore_qos2_state};
 use crate::handler::response::{
     response_packet_mqtt_connect_fail, response_packet_mqtt_connect_success,
     response_packet_mqtt_distinct_by_reason, response_packet_mqtt_ping_resp,
@@ -48,16 +53,25 @@ use crate::handler::response::{
     response_packet_mqtt_pubrel_success, response_packet_mqtt_suback,
     response_packet_mqtt_unsuback,
 };
-use crate::handler::retain::save_retain_message;
+use crate::handler::retain::{build_retain_message_expiry_interval, save_retain_message};
 use crate::handler::session::{build_session, save_session};
+use crate::handler::subscribe::{
+    clear_subscribe, delete_subscribe, restore_subscribe, save_subscribe,
+};
+use crate::handler::takeover::takeover_session;
 use crate::handler::topic::{get_topic_name, try_init_topic};
+use crate::handler::topic_rewrite::rewrite_subscribe_filter;
 use crate::handler::validator::{
     connect_validator, publish_validator, subscribe_validator, un_subscribe_validator,
 };
+use crate::observability::metrics::messages::metrics_message_no_subscribers;
+use crate::observability::metrics::publish::metrics_storage_write_failure_incr;
 use crate::observability::system_topic::event::{
     st_report_connected_event, st_report_disconnected_event, st_report_subscribed_event,
     st_report_unsubscribed_event,
 };
+use crate::observability::trace::{TraceEvent, TraceEventType};
+use crate::observability::webhook::{publish_event_payload, WebhookEvent, WebhookEventType};
 use crate::security::AuthDriver;
 use crate::server::connection_manager::ConnectionManager;
 use crate::storage::message::MessageStorage;
@@ -73,6 +87,7 @@ pub struct MqttService<S> {
     subscribe_manager: Arc<SubscribeManager>,
     client_pool: Arc<ClientPool>,
     auth_driver: Arc<AuthDriver>,
+    delay_message_manager: Arc<DelayMessageManager<S>>,
 }
 
 impl<S> MqttService<S>
@@ -87,6 +102,7 @@ where
         subscribe_manager: Arc<SubscribeManager>,
         client_pool: Arc<ClientPool>,
         auth_driver: Arc<AuthDriver>,
+        delay_message_manager: Arc<DelayMessageManager<S>>,
     ) -> Self {
         MqttService {
             protocol,
@@ -96,6 +112,7 @@ where
             subscribe_manager,
             client_pool,
             auth_driver,
+            delay_message_manager,
         }
     }
 
@@ -126,11 +143,28 @@ where
             return res;
         }
 
-        match self
+        let auth_result = self
             .auth_driver
             .check_login_auth(login, &connect_properties, &addr)
-            .await
-        {
+            .await;
+        self.cache_manager
+            .webhook_manager
+            .emit(WebhookEventType::ClientAuthenticate, || {
+                let username = login
+                    .as_ref()
+                    .map(|login| login.username.as_str())
+                    .unwrap_or_default();
+                let result = match &auth_result {
+                    Ok(true) => "success",
+                    Ok(false) => "not_authorized",
+                    Err(_) => "error",
+                };
+                WebhookEvent::new(WebhookEventType::ClientAuthenticate, &connect.client_id)
+                    .username(username)
+                    .peer_addr(&addr.to_string())
+                    .data(json!({ "result": result }))
+            });
+        match auth_result {
             Ok(flag) => {
                 if !flag {
                     return response_packet_mqtt_connect_fail(
@@ -162,7 +196,7 @@ where
             &addr,
         );
 
-        let (session, new_session) = match build_session(
+        let (session, new_session, online_broker_id) = match build_session(
             connect_id,
             client_id.clone(),
             &connect,
@@ -185,6 +219,27 @@ where
             }
         };
 
+        if let Some(broker_id) = online_broker_id {
+            if let Err(e) = takeover_session(
+                &self.client_pool,
+                &self.cache_manager,
+                &self.connection_manager,
+                &self.subscribe_manager,
+                &self.message_storage_adapter,
+                &session,
+                broker_id,
+            )
+            .await
+            {
+                return response_packet_mqtt_connect_fail(
+                    &self.protocol,
+                    ConnectReturnCode::UnspecifiedError,
+                    &connect_properties,
+                    Some(e.to_string()),
+                );
+            }
+        }
+
         match save_session(
             connect_id,
             session.clone(),
@@ -205,6 +260,56 @@ where
             }
         }
 
+        if new_session {
+            self.cache_manager
+                .webhook_manager
+                .emit(WebhookEventType::SessionCreated, || {
+                    WebhookEvent::new(WebhookEventType::SessionCreated, &client_id)
+                        .peer_addr(&addr.to_string())
+                        .data(json!({ "expiry_interval": session.session_expiry }))
+                });
+        }
+
+        let qos2_result = if new_session {
+            clear_qos2_state(&self.client_pool, &self.cache_manager, &client_id).await
+        } else {
+            restore_qos2_state(&self.client_pool, &self.cache_manager, &client_id).await
+        };
+        if let Err(e) = qos2_result {
+            return response_packet_mqtt_connect_fail(
+                &self.protocol,
+                ConnectReturnCode::UnspecifiedError,
+                &connect_properties,
+                Some(e.to_string()),
+            );
+        }
+
+        let subscribe_result = if new_session {
+            clear_subscribe(
+                &self.client_pool,
+                &self.cache_manager,
+                &self.subscribe_manager,
+                &client_id,
+            )
+            .await
+        } else {
+            restore_subscribe(
+                &self.client_pool,
+                &self.cache_manager,
+                &self.subscribe_manager,
+                &client_id,
+            )
+            .await
+        };
+        if let Err(e) = subscribe_result {
+            return response_packet_mqtt_connect_fail(
+                &self.protocol,
+                ConnectReturnCode::UnspecifiedError,
+                &connect_properties,
+                Some(e.to_string()),
+            );
+        }
+
         match save_last_will_message(
             client_id.clone(),
             &last_will,
@@ -254,18 +359,33 @@ where
             client_id,
             new_client_id,
             session.session_expiry as u32,
-            new_session,
+            !new_session,
             connection.keep_alive,
             &connect_properties,
         )
     }
 
+    #[tracing::instrument(
+        level = "info",
+        skip_all,
+        fields(
+            connect_id = connect_id,
+            topic = %String::from_utf8_lossy(&publish.topic),
+            qos = ?publish.qos
+        )
+    )]
     pub async fn publish(
         &self,
         connect_id: u64,
         publish: Publish,
         publish_properties: Option<PublishProperties>,
     ) -> Option<MqttPacket> {
+        if let Some(properties) = &publish_properties {
+            if let Some(parent) = extract_user_properties_context(&properties.user_properties) {
+                set_span_parent(&Span::current(), parent);
+            }
+        }
+
         let connection = if let Some(se) = self.cache_manager.connection_info.get(&connect_id) {
             se.clone()
         } else {
@@ -333,9 +453,53 @@ where
             }
         };
 
+        // Messages published to $delayed/{seconds}/{topic} are held back and
+        // delivered to {topic} once the delay has elapsed.
+        let (delay_secs, target_topic_name) = if is_delay_topic(&topic_name) {
+            let max_delay_secs = self
+                .cache_manager
+                .get_cluster_info()
+                .protocol
+                .max_delay_message_interval;
+            match decode_delay_topic(&topic_name, max_delay_secs) {
+                Ok((secs, real_topic_name)) => (Some(secs), real_topic_name),
+                Err(e) => {
+                    if is_flow_control(&self.protocol, publish.qos) {
+                        connection.recv_qos_message_decr();
+                    }
+
+                    if is_puback {
+                        return Some(response_packet_mqtt_puback_fail(
+                            &self.protocol,
+                            &connection,
+                            publish.pkid,
+                            PubAckReason::TopicNameInvalid,
+                            Some(e.to_string()),
+                        ));
+                    } else {
+                        return Some(response_packet_mqtt_pubrec_fail(
+                            &self.protocol,
+                            &connection,
+                            publish.pkid,
+                            PubRecReason::TopicNameInvalid,
+                            Some(e.to_string()),
+                        ));
+                    }
+                }
+            }
+        } else {
+            (None, topic_name.clone())
+        };
+
         if !self
             .auth_driver
-            .allow_publish(&connection, &topic_name, publish.retain, publish.qos)
+            .allow_publish(
+                &connection,
+                &target_topic_name,
+                publish.retain,
+                publish.qos,
+                &publish_properties,
+            )
             .await
         {
             if is_puback {
@@ -357,8 +521,28 @@ where
             }
         }
 
+        // Emitted once the topic is resolved and the publish is allowed, so the event
+        // carries the topic the message is actually published to
+        self.cache_manager
+            .webhook_manager
+            .emit(WebhookEventType::MessagePublish, || {
+                let payload = publish_event_payload(&publish.payload, &publish_properties);
+                WebhookEvent::new(WebhookEventType::MessagePublish, &connection.client_id)
+                    .username(&connection.login_user)
+                    .peer_addr(&connection.source_ip_addr)
+                    .topic(&target_topic_name)
+                    .data(json!({
+                        "pkid": publish.pkid,
+                        "qos": publish.qos as u8,
+                        "retain": publish.retain,
+                        "delay": delay_secs,
+                        "payload": payload,
+                        "payload_size": publish.payload.len(),
+                    }))
+            });
+
         let topic = match try_init_topic(
-            &topic_name,
+            &target_topic_name,
             &self.cache_manager,
             &self.message_storage_adapter,
             &self.client_pool,
@@ -393,17 +577,21 @@ where
 
         let client_id = connection.client_id.clone();
 
-        // Persisting retain message data
-        match save_retain_message(
-            &self.cache_manager,
-            &self.client_pool,
-            topic_name.clone(),
-            &client_id,
-            &publish,
-            &publish_properties,
-        )
-        .await
-        {
+        // Persisting retain message data, a delayed message is retained when it is released
+        let retain_result = if delay_secs.is_some() {
+            Ok(())
+        } else {
+            save_retain_message(
+                &self.cache_manager,
+                &self.client_pool,
+                topic_name.clone(),
+                &client_id,
+                &publish,
+                &publish_properties,
+            )
+            .await
+        };
+        match retain_result {
             Ok(()) => {}
             Err(e) => {
                 if is_flow_control(&self.protocol, publish.qos) {
@@ -437,14 +625,51 @@ where
         let offset = if let Some(record) =
             MqttMessage::build_record(&client_id, &publish, &publish_properties, message_expire)
         {
-            match message_storage
-                .append_topic_message(&topic.topic_id, vec![record])
-                .await
-            {
+            let result = if let Some(secs) = delay_secs {
+                let retain_expiry_interval = if publish.retain {
+                    Some(build_retain_message_expiry_interval(
+                        &self.cache_manager.get_cluster_info().protocol,
+                        &publish_properties,
+                    ))
+                } else {
+                    None
+                };
+                self.delay_message_manager
+                    .save(
+                        &client_id,
+                        &target_topic_name,
+                        &topic.topic_id,
+                        secs,
+                        record,
+                        retain_expiry_interval,
+                    )
+                    .await
+            } else {
+                message_storage
+                    .append_topic_message(&topic.topic_id, vec![record])
+                    .await
+                    .map(|da| format!("{:?}", da))
+            };
+            match result {
                 Ok(da) => {
-                    format!("{:?}", da)
+                    self.cache_manager.trace_manager.record(|| {
+                        TraceEvent::new(
+                            TraceEventType::StorageAppend,
+                            &client_id,
+                            &target_topic_name,
+                        )
+                        .peer_addr(&connection.source_ip_addr)
+                        .pkid(publish.pkid)
+                        .detail(if delay_secs.is_some() {
+                            format!("delayed: {}", da)
+                        } else {
+                            format!("offset: {}", da)
+                        })
+                    });
+                    da
                 }
                 Err(e) => {
+                    metrics_storage_write_failure_incr();
                     if is_flow_control(&self.protocol, publish.qos) {
                         connection.recv_qos_message_decr();
                     }
@@ -476,6 +701,12 @@ where
         self.cache_manager
             .add_topic_alias(connect_id, &topic_name, &publish_properties);
 
+        let has_subscriber = path_contain_sub(&target_topic_name);
+        if !has_subscriber {
+            // The message is stored all the same, so it is not counted as dropped
+            metrics_message_no_subscribers(&target_topic_name);
+        }
+
         match publish.qos {
             QoS::AtMostOnce => None,
             QoS::AtLeastOnce => {
@@ -483,7 +714,7 @@ where
                     connection.recv_qos_message_decr();
                 }
 
-                let reason_code = if path_contain_sub(&topic_name) {
+                let reason_code = if has_subscriber {
                     PubAckReason::Success
                 } else {
                     PubAckReason::NoMatchingSubscribers
@@ -529,7 +760,7 @@ where
                         }
                     }
                 }
-                let reason_code = if path_contain_sub(&topic_name) {
+                let reason_code = if has_subscriber {
                     PubRecReason::Success
                 } else {
                     PubRecReason::NoMatchingSubscribers
@@ -567,6 +798,9 @@ where
                         );
                     }
                 }
+            } else {
+                // Acknowledgement of a packet pushed before the session was taken over
+                self.cache_manager.remove_pkid_info(&client_id, pkid);
             }
         }
 
@@ -627,6 +861,9 @@ where
                         );
                     }
                 }
+            } else {
+                // Acknowledgement of a packet pushed before the session was taken over
+                self.cache_manager.remove_pkid_info(&client_id, pkid);
             }
         }
         None
@@ -706,7 +943,7 @@ where
     pub async fn subscribe(
         &self,
         connect_id: u64,
-        subscribe: Subscribe,
+        mut subscribe: Subscribe,
         subscribe_properties: Option<SubscribeProperties>,
     ) -> MqttPacket {
         let connection = if let Some(se) = self.cache_manager.connection_info.get(&connect_id) {
@@ -720,6 +957,11 @@ where
 
         let client_id = connection.client_id.clone();
 
+        // Filters are rewritten before they are validated and checked against the ACL
+        for filter in subscribe.filters.iter_mut() {
+            filter.path = rewrite_subscribe_filter(&self.cache_manager, filter.path.clone());
+        }
+
         if let Some(packet) = subscribe_validator(
             &self.protocol,
             &self.cache_manager,
@@ -808,6 +1050,24 @@ where
             }
         }
 
+        if let Err(e) = save_subscribe(
+            &self.client_pool,
+            &client_id,
+            &self.protocol,
+            &subscribe,
+            &subscribe_properties,
+        )
+        .await
+        {
+            return response_packet_mqtt_suback(
+                &self.protocol,
+                &connection,
+                subscribe.packet_identifier,
+                vec![SubscribeReasonCode::Unspecified],
+                Some(e.to_string()),
+            );
+        }
+
         self.cache_manager.add_client_subscribe(
             client_id.clone(),
             self.protocol.clone(),
@@ -826,6 +1086,22 @@ where
 
         let pkid = subscribe.packet_identifier;
 
+        for filter in subscribe.filters.iter() {
+            self.cache_manager
+                .webhook_manager
+                .emit(WebhookEventType::ClientSubscribe, || {
+                    WebhookEvent::new(WebhookEventType::ClientSubscribe, &client_id)
+                        .username(&connection.login_user)
+                        .peer_addr(&connection.source_ip_addr)
+                        .topic(&filter.path)
+                        .data(json!({
+                            "qos": filter.qos as u8,
+                            "nl": filter.nolocal,
+                            "rap": filter.preserve_retain,
+                        }))
+                });
+        }
+
         st_report_subscribed_event(
             &self.message_storage_adapter,
             &self.cache_manager,
@@ -842,7 +1118,6 @@ where
             client_id.clone(),
             subscribe.clone(),
             subscribe_properties.clone(),
-            self.client_pool.clone(),
             self.cache_manager.clone(),
             self.connection_manager.clone(),
         )
@@ -874,7 +1149,7 @@ where
     pub async fn un_subscribe(
         &self,
         connect_id: u64,
-        un_subscribe: Unsubscribe,
+        mut un_subscribe: Unsubscribe,
         _: Option<UnsubscribeProperties>,
     ) -> MqttPacket {
         let connection = if let Some(se) = self.cache_manager.connection_info.get(&connect_id) {
@@ -886,6 +1161,10 @@ where
             );
         };
 
+        for filter in un_subscribe.filters.iter_mut() {
+            *filter = rewrite_subscribe_filter(&self.cache_manager, filter.clone());
+        }
+
         if let Some(packet) = un_subscribe_validator(
             &connection.client_id,
             &self.cache_manager,
@@ -934,12 +1213,38 @@ where
             }
         }
 
+        if let Err(e) = delete_subscribe(
+            &self.client_pool,
+            &connection.client_id,
+            &un_subscribe.filters,
+        )
+        .await
+        {
+            return response_packet_mqtt_unsuback(
+                &connection,
+                un_subscribe.pkid,
+                vec![UnsubAckReason::UnspecifiedError],
+                Some(e.to_string()),
+            );
+        }
+
         self.subscribe_manager
             .remove_subscribe(&connection.client_id, &un_subscribe.filters);
 
         self.cache_manager
             .remove_filter_by_pkid(&connection.client_id, &un_subscribe.filters);
 
+        for path in un_subscribe.filters.iter() {
+            self.cache_manager
+                .webhook_manager
+                .emit(WebhookEventType::ClientUnsubscribe, || {
+                    WebhookEvent::new(WebhookEventType::ClientUnsubscribe, &connection.client_id)
+                        .username(&connection.login_user)
+                        .peer_addr(&connection.source_ip_addr)
+                        .topic(path)
+                });
+        }
+
         st_report_unsubscribed_event(
             &self.message_storage_adapter,
             &self.cache_manager,
@@ -14,17 +14,11 @@
 
 use std::sync::Arc;
 
-use common_base::config::broker_mqtt::broker_mqtt_conf;
 use common_base::error::common::CommonError;
-use grpc_clients::placement::inner::call::{
-    delete_idempotent_data, exists_idempotent_data, set_idempotent_data,
-};
 use grpc_clients::pool::ClientPool;
-use protocol::placement_center::placement_center_inner::{
-    DeleteIdempotentDataRequest, ExistsIdempotentDataRequest, SetIdempotentDataRequest,
-};
 
 use super::cache::CacheManager;
+use crate::storage::metadata::{build_metadata_storage, MetadataStorage};
 
 pub async fn pkid_save(
     cache_manager: &Arc<CacheManager>,
@@ -37,20 +31,9 @@ pub async fn pkid_save(
         .protocol
         .client_pkid_persistent
     {
-        let conf = broker_mqtt_conf();
-        let request = SetIdempotentDataRequest {
-            cluster_name: conf.cluster_name.clone(),
-            producer_id: client_id.to_owned(),
-            seq_num: pkid as u64,
-        };
-        match set_idempotent_data(client_pool, &conf.placement_center, request).await {
-            Ok(_) => {
-                return Ok(());
-            }
-            Err(e) => {
-                return Err(e);
-            }
-        }
+        return build_metadata_storage(client_pool.clone())
+            .set_idempotent_data(client_id, pkid as u64)
+            .await;
     } else {
         cache_manager.add_client_pkid(client_id, pkid);
     }
@@ -68,16 +51,9 @@ pub async fn pkid_exists(
         .protocol
         .client_pkid_persistent
     {
-        let conf = broker_mqtt_conf();
-        let request = ExistsIdempotentDataRequest {
-            cluster_name: conf.cluster_name.clone(),
-            producer_id: client_id.to_owned(),
-            seq_num: pkid as u64,
-        };
-        match exists_idempotent_data(client_pool, &conf.placement_center, request).await {
-            Ok(reply) => Ok(reply.exists),
-            Err(e) => Err(e),
-        }
+        build_metadata_storage(client_pool.clone())
+            .exists_idempotent_data(client_id, pkid as u64)
+            .await
     } else {
         Ok(cache_manager.get_client_pkid(client_id, pkid).is_some())
     }
@@ -94,20 +70,9 @@ pub async fn pkid_delete(
         .protocol
         .client_pkid_persistent
     {
-        let conf = broker_mqtt_conf();
-        let request = DeleteIdempotentDataRequest {
-            cluster_name: conf.cluster_name.clone(),
-            producer_id: client_id.to_owned(),
-            seq_num: pkid as u64,
-        };
-        match delete_idempotent_data(client_pool, &conf.placement_center, request).await {
-            Ok(_) => {
-                return Ok(());
-            }
-            Err(e) => {
-                return Err(e);
-            }
-        }
+        return build_metadata_storage(client_pool.clone())
+            .delete_idempotent_data(client_id, pkid as u64)
+            .await;
     } else {
         cache_manager.delete_client_pkid(client_id, pkid);
     }
@@ -0,0 +1,166 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::qos2_state::{MqttQos2State, QOS2_STATE_DIRECTION_SEND};
+
+use super::cache::CacheManager;
+use crate::storage::session::SessionStorage;
+
+// QoS 2 state is kept in the placement center unless the cluster turns it off, then it
+// lives in the memory of the broker alone.
+fn is_qos2_state_persistent(cache_manager: &Arc<CacheManager>) -> bool {
+    cache_manager
+        .get_cluster_info()
+        .protocol
+        .qos2_state_persistent
+}
+
+/// Restores the QoS 2 exchanges a client left unfinished when it reconnects, which matters
+/// when the broker restarted in the meantime and lost the state kept in memory.
+pub async fn restore_qos2_state(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+) -> Result<(), CommonError> {
+    if !is_qos2_state_persistent(cache_manager) {
+        return Ok(());
+    }
+    let storage = SessionStorage::new(client_pool.clone());
+    let states = storage.list_qos2_state(client_id).await?;
+    apply_qos2_state(cache_manager, client_id, &states);
+    Ok(())
+}
+
+// An inbound packet identifier stays known until PUBREL, so that a PUBLISH resent with DUP is
+// not stored twice. An outbound packet identifier stays reserved until PUBCOMP, so that it is
+// not reused for another message while the client still holds it.
+pub fn apply_qos2_state(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+    states: &[MqttQos2State],
+) {
+    for state in states {
+        if state.is_receive() {
+            cache_manager.add_client_pkid(client_id, state.pkid);
+        } else {
+            cache_manager.add_publish_pkid(client_id, state.pkid);
+        }
+    }
+}
+
+// A client that starts a new session discards the exchanges of the previous one
+pub async fn clear_qos2_state(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+) -> Result<(), CommonError> {
+    if !is_qos2_state_persistent(cache_manager) {
+        return Ok(());
+    }
+    let storage = SessionStorage::new(client_pool.clone());
+    for state in storage.list_qos2_state(client_id).await? {
+        storage
+            .delete_qos2_state(client_id, state.direction(), state.pkid)
+            .await?;
+    }
+    Ok(())
+}
+
+// The outbound exchanges of one subscription group, ordered by offset
+pub async fn list_send_qos2_state(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+    group_name: &str,
+) -> Result<Vec<MqttQos2State>, CommonError> {
+    if !is_qos2_state_persistent(cache_manager) {
+        return Ok(Vec::new());
+    }
+    let storage = SessionStorage::new(cache_manager.client_pool.clone());
+    let mut states: Vec<MqttQos2State> = storage
+        .list_qos2_state(client_id)
+        .await?
+        .into_iter()
+        .filter(|state| !state.is_receive() && state.group_name == group_name)
+        .collect();
+    states.sort_by_key(|state| state.offset);
+    Ok(states)
+}
+
+pub async fn save_send_qos2_state(
+    cache_manager: &Arc<CacheManager>,
+    state: &MqttQos2State,
+) -> Result<(), CommonError> {
+    if !is_qos2_state_persistent(cache_manager) {
+        return Ok(());
+    }
+    let storage = SessionStorage::new(cache_manager.client_pool.clone());
+    storage.save_qos2_state(state).await
+}
+
+pub async fn delete_send_qos2_state(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+    pkid: u16,
+) -> Result<(), CommonError> {
+    if !is_qos2_state_persistent(cache_manager) {
+        return Ok(());
+    }
+    let storage = SessionStorage::new(cache_manager.client_pool.clone());
+    storage
+        .delete_qos2_state(client_id, QOS2_STATE_DIRECTION_SEND, pkid)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::qos2_state::{MqttQos2State, Qos2Stage};
+
+    use super::apply_qos2_state;
+    use crate::handler::cache::CacheManager;
+
+    #[tokio::test]
+    async fn apply_qos2_state_test() {
+        let client_id = "c1";
+        let states = vec![
+            MqttQos2State::new_receive(client_id.to_string(), 7),
+            MqttQos2State::new_send(
+                client_id.to_string(),
+                1,
+                Qos2Stage::WaitPubComp,
+                "group".to_string(),
+                3,
+            ),
+        ];
+
+        // A broker restarted between PUBREC and PUBREL starts with an empty cache
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        assert!(cache_manager.get_client_pkid(client_id, 7).is_none());
+
+        apply_qos2_state(&cache_manager, client_id, &states);
+        assert!(cache_manager.get_client_pkid(client_id, 7).is_some());
+        assert!(cache_manager.get_client_pkid(client_id, 1).is_none());
+        assert_eq!(cache_manager.list_publish_pkid(client_id), vec![1]);
+
+        // The packet identifier the client still holds is not handed out again
+        assert_eq!(cache_manager.get_pkid(client_id).await, 2);
+    }
+}
@@ -48,6 +48,7 @@ pub fn response_packet_mqtt_connect_success(
         );
     }
 
+    let response_information = response_information(&client_id, connect_properties);
     let assigned_client_identifier = if auto_client_id {
         Some(client_id)
     } else {
@@ -74,7 +75,7 @@ pub fn response_packet_mqtt_connect_success(
             cluster.feature.shared_subscription_available.clone() as u8
         ),
         server_keep_alive: Some(keep_live_time(keep_alive)),
-        response_information: response_information(connect_properties),
+        response_information,
         server_reference: None,
         authentication_method: None,
         authentication_data: None,
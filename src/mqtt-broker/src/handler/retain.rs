@@ -13,34 +13,57 @@
 // limitations under the License.
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use bytes::Bytes;
+use common_base::config::broker_mqtt::broker_mqtt_conf;
 use common_base::tools::now_second;
+use grpc_clients::mqtt::inner::call::broker_mqtt_update_cache;
 use grpc_clients::pool::ClientPool;
-use log::info;
+use log::{error, info, warn};
+use metadata_struct::mqtt::cluster::MqttClusterDynamicConfigProtocol;
 use metadata_struct::mqtt::message::MqttMessage;
+use protocol::broker_mqtt::broker_mqtt_inner::{
+    MqttBrokerUpdateCacheActionType, MqttBrokerUpdateCacheResourceType, UpdateCacheRequest,
+};
 use protocol::mqtt::common::{
     MqttProtocol, Publish, PublishProperties, QoS, RetainForwardRule, Subscribe,
     SubscribeProperties,
 };
+use serde::{Deserialize, Serialize};
+use tokio::select;
 use tokio::sync::broadcast::{self};
+use tokio::time::sleep;
 
 use super::cache::{CacheManager, QosAckPacketInfo};
 use super::constant::{SUB_RETAIN_MESSAGE_PUSH_FLAG, SUB_RETAIN_MESSAGE_PUSH_FLAG_VALUE};
 use super::error::MqttBrokerError;
-use super::message::build_message_expire;
+use super::retain_index::RetainMessageEntry;
 use crate::observability::metrics::packets::{
     record_retain_recv_metrics, record_retain_sent_metrics,
 };
 use crate::server::connection_manager::ConnectionManager;
+use crate::storage::cluster::ClusterStorage;
 use crate::storage::topic::TopicStorage;
-use crate::subscribe::sub_common::{get_sub_topic_id_list, min_qos, publish_message_qos0};
+use crate::subscribe::sub_common::{is_queue_sub, is_share_sub, min_qos, publish_message_qos0};
 use crate::subscribe::sub_exclusive::{
     exclusive_publish_message_qos1, exclusive_publish_message_qos2,
 };
 use crate::subscribe::subscriber::Subscriber;
 use crate::subscribe::SubPublishParam;
 
+const RETAIN_MESSAGE_EXPIRE_CHECK_INTERVAL_SEC: u64 = 10;
+const RETAIN_MESSAGE_RELOAD_INTERVAL_SEC: u64 = 300;
+
+// Sent to the other brokers of the cluster when the retained message of a topic changes
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RetainMessageCacheData {
+    pub topic_name: String,
+    pub retain_message: Vec<u8>,
+    // Expiry interval in seconds, 0 means the message never expires
+    pub expiry_interval: u64,
+}
+
 pub async fn save_retain_message(
     cache_manager: &Arc<CacheManager>,
     client_pool: &Arc<ClientPool>,
@@ -53,34 +76,306 @@ pub async fn save_retain_message(
         return Ok(());
     }
 
+    let protocol = cache_manager.get_cluster_info().protocol;
+    let expiry_interval = build_retain_message_expiry_interval(&protocol, publish_properties);
+    let retain_message = MqttMessage::build_message(client_id, publish, publish_properties, 0);
+    store_retain_message(
+        cache_manager,
+        client_pool,
+        topic_name,
+        retain_message,
+        expiry_interval,
+    )
+    .await
+}
+
+/// Store `retain_message` as the retained message of `topic_name`, or delete the
+/// retained message when its payload is empty. Also used for delayed messages,
+/// which are retained only when they are released.
+pub async fn store_retain_message(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    topic_name: String,
+    mut retain_message: MqttMessage,
+    expiry_interval: u64,
+) -> Result<(), MqttBrokerError> {
     let topic_storage = TopicStorage::new(client_pool.clone());
 
-    if publish.payload.is_empty() {
+    if retain_message.payload.is_empty() {
         topic_storage
             .delete_retain_message(topic_name.clone())
             .await?;
-        cache_manager.update_topic_retain_message(&topic_name, Some(Vec::new()));
+        cache_manager.update_topic_retain_message(&topic_name, None, None);
+        notify_retain_message_change(
+            client_pool,
+            MqttBrokerUpdateCacheActionType::Delete,
+            RetainMessageCacheData {
+                topic_name,
+                retain_message: Vec::new(),
+                expiry_interval: 0,
+            },
+        );
+        return Ok(());
+    }
+
+    // A message over the retain limits is still delivered, it is only not retained
+    let protocol = cache_manager.get_cluster_info().protocol;
+    if protocol.max_retain_message_size > 0
+        && retain_message.payload.len() as u64 > protocol.max_retain_message_size
+    {
+        warn!(
+            "Retained message of topic [{}] exceeds the maximum size of {} bytes and is not retained",
+            topic_name, protocol.max_retain_message_size
+        );
+        return Ok(());
+    }
+
+    // Every broker indexes the retained messages of the whole cluster, so the limit applies to
+    // the cluster. Brokers storing new retained messages at the same time may overshoot it until
+    // they have received each other's notifications.
+    let index = &cache_manager.retain_message_index;
+    if protocol.max_retain_message_num > 0
+        && index.len() as u64 >= protocol.max_retain_message_num
+        && !index.contains(&topic_name)
+    {
+        warn!(
+            "The number of retained messages reached the limit of {}, the message of topic [{}] is not retained",
+            protocol.max_retain_message_num, topic_name
+        );
+        return Ok(());
+    }
+
+    record_retain_recv_metrics(retain_message.qos);
+    retain_message.expiry_interval = if expiry_interval > 0 {
+        now_second() + expiry_interval
     } else {
-        record_retain_recv_metrics(publish.qos);
-        let message_expire = build_message_expire(cache_manager, publish_properties);
-        let retain_message =
-            MqttMessage::build_message(client_id, publish, publish_properties, message_expire);
+        0
+    };
+    topic_storage
+        .set_retain_message(topic_name.clone(), &retain_message, expiry_interval)
+        .await?;
+
+    let encoded = retain_message.encode();
+    cache_manager.update_topic_retain_message(
+        &topic_name,
+        Some(encoded.clone()),
+        Some(expiry_interval),
+    );
+    notify_retain_message_change(
+        client_pool,
+        MqttBrokerUpdateCacheActionType::Add,
+        RetainMessageCacheData {
+            topic_name,
+            retain_message: encoded,
+            expiry_interval,
+        },
+    );
+
+    Ok(())
+}
+
+// The other brokers apply the change to their index when they receive it, so the publish does
+// not wait for them. A lost notification is repaired by the periodic reload of the index.
+fn notify_retain_message_change(
+    client_pool: &Arc<ClientPool>,
+    action_type: MqttBrokerUpdateCacheActionType,
+    data: RetainMessageCacheData,
+) {
+    let client_pool = client_pool.clone();
+    tokio::spawn(async move {
+        if let Err(e) = broadcast_retain_message_change(&client_pool, action_type, &data).await {
+            warn!(
+                "Failed to notify the brokers of the retained message change of topic [{}], error: {}",
+                data.topic_name, e
+            );
+        }
+    });
+}
+
+async fn broadcast_retain_message_change(
+    client_pool: &Arc<ClientPool>,
+    action_type: MqttBrokerUpdateCacheActionType,
+    data: &RetainMessageCacheData,
+) -> Result<(), MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let request = UpdateCacheRequest {
+        cluster_name: conf.cluster_name.clone(),
+        action_type: action_type.into(),
+        resource_type: MqttBrokerUpdateCacheResourceType::RetainMessage.into(),
+        data: serde_json::to_vec(data)?,
+    };
+
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    for node in cluster_storage.node_list().await? {
+        if node.node_id == conf.broker_id {
+            continue;
+        }
+        if let Err(e) =
+            broker_mqtt_update_cache(client_pool, &[node.node_inner_addr], request.clone()).await
+        {
+            warn!(
+                "Failed to notify broker [{}] of the retained message change of topic [{}], error: {}",
+                node.node_id, data.topic_name, e
+            );
+        }
+    }
+    Ok(())
+}
+
+// Expiry interval in seconds of a retained message, 0 means it never expires
+pub fn build_retain_message_expiry_interval(
+    protocol: &MqttClusterDynamicConfigProtocol,
+    publish_properties: &Option<PublishProperties>,
+) -> u64 {
+    if let Some(properties) = publish_properties {
+        if let Some(expire) = properties.message_expiry_interval {
+            if expire > 0 {
+                return expire as u64;
+            }
+        }
+    }
+    protocol.retain_message_expiry_interval
+}
+
+// Retained messages whose topic matches the filter, limited to `limit` messages when not 0
+pub fn list_retain_message(
+    cache_manager: &Arc<CacheManager>,
+    topic_filter: &str,
+    limit: usize,
+) -> Vec<RetainMessageEntry> {
+    let now = now_second();
+    let mut list: Vec<RetainMessageEntry> = cache_manager
+        .retain_message_index
+        .match_filter(topic_filter)
+        .into_iter()
+        .filter(|entry| !entry.is_expired(now))
+        .collect();
+    list.sort_by(|a, b| a.topic_name.cmp(&b.topic_name));
+    if limit > 0 {
+        list.truncate(limit);
+    }
+    list
+}
+
+// Deletes the retained messages whose topic matches the filter, returns how many were deleted
+pub async fn delete_retain_message_by_filter(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    topic_filter: &str,
+) -> Result<usize, MqttBrokerError> {
+    let topic_storage = TopicStorage::new(client_pool.clone());
+    let list = cache_manager
+        .retain_message_index
+        .match_filter(topic_filter);
+    for entry in list.iter() {
         topic_storage
-            .set_retain_message(topic_name.clone(), &retain_message, message_expire)
+            .delete_retain_message(entry.topic_name.clone())
             .await?;
+        cache_manager.update_topic_retain_message(&entry.topic_name, None, None);
+        notify_retain_message_change(
+            client_pool,
+            MqttBrokerUpdateCacheActionType::Delete,
+            RetainMessageCacheData {
+                topic_name: entry.topic_name.clone(),
+                retain_message: Vec::new(),
+                expiry_interval: 0,
+            },
+        );
+    }
+    Ok(list.len())
+}
+
+// Retained messages stored through other brokers reach the local index through their
+// notifications. This thread drops expired messages from the index, and reloads it from the
+// placement center at a much lower rate to repair notifications that were lost.
+pub struct UpdateRetainMessageCache {
+    stop_send: broadcast::Sender<bool>,
+    cache_manager: Arc<CacheManager>,
+    client_pool: Arc<ClientPool>,
+}
 
-        cache_manager.update_topic_retain_message(&topic_name, Some(retain_message.encode()));
+impl UpdateRetainMessageCache {
+    pub fn new(
+        stop_send: broadcast::Sender<bool>,
+        cache_manager: Arc<CacheManager>,
+        client_pool: Arc<ClientPool>,
+    ) -> Self {
+        UpdateRetainMessageCache {
+            stop_send,
+            cache_manager,
+            client_pool,
+        }
     }
 
+    pub async fn start_update(&self) {
+        // The index is loaded with the topics when the broker starts
+        let mut last_reload = now_second();
+        loop {
+            let mut stop_rx = self.stop_send.subscribe();
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}","Retain message cache updating thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = sleep(Duration::from_secs(RETAIN_MESSAGE_EXPIRE_CHECK_INTERVAL_SEC))=>{
+                    remove_expired_retain_message(&self.cache_manager);
+                    if now_second() - last_reload >= RETAIN_MESSAGE_RELOAD_INTERVAL_SEC {
+                        if let Err(e) = reload_retain_message(&self.cache_manager, &self.client_pool).await {
+                            error!("Updating retain message cache failed, error message: {}", e);
+                        }
+                        last_reload = now_second();
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn remove_expired_retain_message(cache_manager: &Arc<CacheManager>) {
+    for topic_name in cache_manager
+        .retain_message_index
+        .expired_topics(now_second())
+    {
+        cache_manager.update_topic_retain_message(&topic_name, None, None);
+    }
+}
+
+async fn reload_retain_message(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+) -> Result<(), MqttBrokerError> {
+    let topic_storage = TopicStorage::new(client_pool.clone());
+    let now = now_second();
+    let mut entries = Vec::new();
+    for (_, topic) in topic_storage.all().await? {
+        if let Some(retain_message) = &topic.retain_message {
+            if let Some(entry) = RetainMessageEntry::decode(
+                &topic.topic_name,
+                retain_message,
+                topic.retain_message_expired_at,
+            ) {
+                if !entry.is_expired(now) {
+                    entries.push(entry);
+                }
+            }
+        }
+        cache_manager.add_topic(&topic.topic_name, &topic);
+    }
+    cache_manager.retain_message_index.reset(entries);
     Ok(())
 }
 
+// Only called for clients that have been sent their CONNACK, SUBSCRIBE packets are
+// handled after the login and auto subscriptions are created once the CONNACK is written.
 pub async fn try_send_retain_message(
     protocol: MqttProtocol,
     client_id: String,
     subscribe: Subscribe,
     subscribe_properties: Option<SubscribeProperties>,
-    client_pool: Arc<ClientPool>,
     cache_manager: Arc<CacheManager>,
     connection_manager: Arc<ConnectionManager>,
 ) {
@@ -91,7 +386,6 @@ pub async fn try_send_retain_message(
             &client_id,
             &subscribe,
             &subscribe_properties,
-            &client_pool,
             &cache_manager,
             &connection_manager,
             &stop_sx,
@@ -103,13 +397,11 @@ pub async fn try_send_retain_message(
     });
 }
 
-#[allow(clippy::too_many_arguments)]
 async fn send_retain_message(
     protocol: &MqttProtocol,
     client_id: &String,
     subscribe: &Subscribe,
     subscribe_properties: &Option<SubscribeProperties>,
-    client_pool: &Arc<ClientPool>,
     cache_manager: &Arc<CacheManager>,
     connection_manager: &Arc<ConnectionManager>,
     stop_sx: &broadcast::Sender<bool>,
@@ -123,29 +415,31 @@ async fn send_retain_message(
 
     for filter in subscribe.filters.iter() {
         if filter.retain_forward_rule == RetainForwardRule::Never {
-            return Ok(());
+            continue;
         }
 
         let is_new_sub = cache_manager.is_new_sub(client_id, &filter.path);
         if filter.retain_forward_rule == RetainForwardRule::OnNewSubscribe && !is_new_sub {
-            return Ok(());
+            continue;
+        }
+
+        // Retained messages are not sent for shared subscriptions
+        if is_share_sub(filter.path.clone()) || is_queue_sub(filter.path.clone()) {
+            continue;
         }
 
-        let topic_id_list = get_sub_topic_id_list(cache_manager, &filter.path).await;
-        let topic_storage = TopicStorage::new(client_pool.clone());
         let cluster = cache_manager.get_cluster_info();
-        for topic_id in topic_id_list.iter() {
-            let topic_name = if let Some(topic_name) = cache_manager.topic_name_by_id(topic_id) {
-                topic_name
-            } else {
+        let now = now_second();
+        for entry in cache_manager
+            .retain_message_index
+            .match_filter(&filter.path)
+        {
+            if entry.is_expired(now) {
                 continue;
-            };
+            }
 
-            let msg = if let Some(message) = topic_storage.get_retain_message(&topic_name).await? {
-                message
-            } else {
-                continue;
-            };
+            let topic_name = entry.topic_name;
+            let msg = entry.message;
 
             if filter.nolocal && *client_id == msg.client_id {
                 continue;
@@ -167,7 +461,11 @@ async fn send_retain_message(
 
             let properties = PublishProperties {
                 payload_format_indicator: msg.format_indicator,
-                message_expiry_interval: Some(msg.expiry_interval as u32),
+                message_expiry_interval: if entry.expire_at > 0 {
+                    Some((entry.expire_at - now) as u32)
+                } else {
+                    None
+                },
                 topic_alias: None,
                 response_topic: msg.response_topic,
                 correlation_data: msg.correlation_data,
@@ -270,3 +568,79 @@ async fn send_retain_message(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::tools::now_second;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use protocol::broker_mqtt::broker_mqtt_inner::{
+        MqttBrokerUpdateCacheActionType, MqttBrokerUpdateCacheResourceType, UpdateCacheRequest,
+    };
+    use protocol::mqtt::common::Publish;
+
+    use super::{remove_expired_retain_message, RetainMessageCacheData};
+    use crate::handler::cache::{update_cache_metadata, CacheManager};
+
+    fn build_request(
+        action_type: MqttBrokerUpdateCacheActionType,
+        data: RetainMessageCacheData,
+    ) -> UpdateCacheRequest {
+        UpdateCacheRequest {
+            cluster_name: "test-cluster".to_string(),
+            action_type: action_type.into(),
+            resource_type: MqttBrokerUpdateCacheResourceType::RetainMessage.into(),
+            data: serde_json::to_vec(&data).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn update_retain_message_cache_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test-cluster".to_string()));
+        let message = MqttMessage::build_message("c1", &Publish::default(), &None, 0);
+
+        let request = build_request(
+            MqttBrokerUpdateCacheActionType::Add,
+            RetainMessageCacheData {
+                topic_name: "t/1".to_string(),
+                retain_message: message.encode(),
+                expiry_interval: 0,
+            },
+        );
+        update_cache_metadata(&cache_manager, request).unwrap();
+        assert!(cache_manager.retain_message_index.contains("t/1"));
+
+        let request = build_request(
+            MqttBrokerUpdateCacheActionType::Delete,
+            RetainMessageCacheData {
+                topic_name: "t/1".to_string(),
+                retain_message: Vec::new(),
+                expiry_interval: 0,
+            },
+        );
+        update_cache_metadata(&cache_manager, request).unwrap();
+        assert!(!cache_manager.retain_message_index.contains("t/1"));
+
+        let mut expired = message.clone();
+        expired.create_time = now_second() - 10;
+        for (topic_name, message) in [("t/2", expired), ("t/3", message)] {
+            let request = build_request(
+                MqttBrokerUpdateCacheActionType::Add,
+                RetainMessageCacheData {
+                    topic_name: topic_name.to_string(),
+                    retain_message: message.encode(),
+                    expiry_interval: 5,
+                },
+            );
+            update_cache_metadata(&cache_manager, request).unwrap();
+        }
+        assert_eq!(cache_manager.retain_message_index.len(), 2);
+
+        remove_expired_retain_message(&cache_manager);
+        assert!(!cache_manager.retain_message_index.contains("t/2"));
+        assert!(cache_manager.retain_message_index.contains("t/3"));
+    }
+}
@@ -0,0 +1,329 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use metadata_struct::mqtt::message::MqttMessage;
+
+#[derive(Clone, Debug)]
+pub struct RetainMessageEntry {
+    pub topic_name: String,
+    pub message: MqttMessage,
+    // Unix time in seconds at which the message expires, 0 means it never expires
+    pub expire_at: u64,
+}
+
+impl RetainMessageEntry {
+    /// Builds the entry of a retained message stored with a topic, where `expired_at` is
+    /// the expiry interval in seconds counted from the creation of the message.
+    pub fn decode(
+        topic_name: &str,
+        retain_message: &[u8],
+        expired_at: Option<u64>,
+    ) -> Option<RetainMessageEntry> {
+        if retain_message.is_empty() {
+            return None;
+        }
+
+        let message = serde_json::from_slice::<MqttMessage>(retain_message).ok()?;
+        let expire_at = match expired_at {
+            Some(interval) if interval > 0 => message.create_time + interval,
+            _ => 0,
+        };
+        Some(RetainMessageEntry {
+            topic_name: topic_name.to_owned(),
+            message,
+            expire_at,
+        })
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expire_at > 0 && now >= self.expire_at
+    }
+}
+
+#[derive(Default)]
+struct RetainNode {
+    children: HashMap<String, RetainNode>,
+    entry: Option<RetainMessageEntry>,
+}
+
+impl RetainNode {
+    fn collect_all(&self, skip_system_topic: bool, result: &mut Vec<RetainMessageEntry>) {
+        if let Some(entry) = &self.entry {
+            result.push(entry.clone());
+        }
+        for (level, child) in self.children.iter() {
+            if skip_system_topic && level.starts_with('$') {
+                continue;
+            }
+            child.collect_all(false, result);
+        }
+    }
+
+    fn collect_match(&self, levels: &[&str], depth: usize, result: &mut Vec<RetainMessageEntry>) {
+        let level = if let Some(level) = levels.first() {
+            level
+        } else {
+            if let Some(entry) = &self.entry {
+                result.push(entry.clone());
+            }
+            return;
+        };
+
+        // Topics beginning with $ are not matched by a filter beginning with a wildcard
+        match *level {
+            "#" => self.collect_all(depth == 0, result),
+            "+" => {
+                for (name, child) in self.children.iter() {
+                    if depth == 0 && name.starts_with('$') {
+                        continue;
+                    }
+                    child.collect_match(&levels[1..], depth + 1, result);
+                }
+            }
+            name => {
+                if let Some(child) = self.children.get(name) {
+                    child.collect_match(&levels[1..], depth + 1, result);
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, levels: &[&str]) -> Option<RetainMessageEntry> {
+        let level = if let Some(level) = levels.first() {
+            level
+        } else {
+            return self.entry.take();
+        };
+
+        let child = self.children.get_mut(*level)?;
+        let entry = child.remove(&levels[1..]);
+        if child.entry.is_none() && child.children.is_empty() {
+            self.children.remove(*level);
+        }
+        entry
+    }
+}
+
+#[derive(Default)]
+struct RetainTree {
+    root: RetainNode,
+    len: usize,
+}
+
+/// Retained messages indexed by topic level, so that the retained messages matching the
+/// filter of a new subscription are found by walking the filter instead of every topic.
+#[derive(Default, Clone)]
+pub struct RetainMessageIndex {
+    tree: Arc<RwLock<RetainTree>>,
+}
+
+impl RetainMessageIndex {
+    pub fn new() -> Self {
+        RetainMessageIndex {
+            tree: Arc::new(RwLock::new(RetainTree::default())),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.read().unwrap().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn insert(&self, entry: RetainMessageEntry) -> Option<RetainMessageEntry> {
+        let mut tree = self.tree.write().unwrap();
+        let mut node = &mut tree.root;
+        for level in entry.topic_name.split('/') {
+            node = node.children.entry(level.to_owned()).or_default();
+        }
+        let previous = node.entry.replace(entry);
+        if previous.is_none() {
+            tree.len += 1;
+        }
+        previous
+    }
+
+    pub fn remove(&self, topic_name: &str) -> Option<RetainMessageEntry> {
+        let mut tree = self.tree.write().unwrap();
+        let levels: Vec<&str> = topic_name.split('/').collect();
+        let entry = tree.root.remove(&levels);
+        if entry.is_some() {
+            tree.len -= 1;
+        }
+        entry
+    }
+
+    pub fn get(&self, topic_name: &str) -> Option<RetainMessageEntry> {
+        let tree = self.tree.read().unwrap();
+        let mut node = &tree.root;
+        for level in topic_name.split('/') {
+            node = node.children.get(level)?;
+        }
+        node.entry.clone()
+    }
+
+    pub fn contains(&self, topic_name: &str) -> bool {
+        self.get(topic_name).is_some()
+    }
+
+    /// Retained messages whose topic matches the topic filter, which may contain `+` and `#`
+    pub fn match_filter(&self, topic_filter: &str) -> Vec<RetainMessageEntry> {
+        let tree = self.tree.read().unwrap();
+        let levels: Vec<&str> = topic_filter.split('/').collect();
+        let mut result = Vec::new();
+        tree.root.collect_match(&levels, 0, &mut result);
+        result
+    }
+
+    pub fn expired_topics(&self, now: u64) -> Vec<String> {
+        let tree = self.tree.read().unwrap();
+        let mut entries = Vec::new();
+        tree.root.collect_all(false, &mut entries);
+        entries
+            .into_iter()
+            .filter(|entry| entry.is_expired(now))
+            .map(|entry| entry.topic_name)
+            .collect()
+    }
+
+    // Replaces the content of the index
+    pub fn reset(&self, entries: Vec<RetainMessageEntry>) {
+        let mut new_tree = RetainTree::default();
+        for entry in entries {
+            let mut node = &mut new_tree.root;
+            for level in entry.topic_name.split('/') {
+                node = node.children.entry(level.to_owned()).or_default();
+            }
+            if node.entry.replace(entry).is_none() {
+                new_tree.len += 1;
+            }
+        }
+        *self.tree.write().unwrap() = new_tree;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common_base::tools::now_second;
+    use metadata_struct::mqtt::message::MqttMessage;
+
+    use super::{RetainMessageEntry, RetainMessageIndex};
+
+    fn entry(topic_name: &str, expire_at: u64) -> RetainMessageEntry {
+        RetainMessageEntry {
+            topic_name: topic_name.to_string(),
+            message: MqttMessage::default(),
+            expire_at,
+        }
+    }
+
+    fn match_topics(index: &RetainMessageIndex, filter: &str) -> Vec<String> {
+        let mut topics: Vec<String> = index
+            .match_filter(filter)
+            .into_iter()
+            .map(|entry| entry.topic_name)
+            .collect();
+        topics.sort();
+        topics
+    }
+
+    #[test]
+    fn retain_message_index_match_test() {
+        let index = RetainMessageIndex::new();
+        for topic in [
+            "sport",
+            "sport/tennis/player1",
+            "sport/tennis/player2",
+            "sport/football/player1",
+            "/finance",
+            "$SYS/brokers",
+        ] {
+            index.insert(entry(topic, 0));
+        }
+        assert_eq!(index.len(), 6);
+
+        assert_eq!(
+            match_topics(&index, "sport/tennis/player1"),
+            vec!["sport/tennis/player1"]
+        );
+        assert_eq!(
+            match_topics(&index, "sport/+/player1"),
+            vec!["sport/football/player1", "sport/tennis/player1"]
+        );
+        assert_eq!(
+            match_topics(&index, "sport/#"),
+            vec![
+                "sport",
+                "sport/football/player1",
+                "sport/tennis/player1",
+                "sport/tennis/player2"
+            ]
+        );
+        assert_eq!(match_topics(&index, "+/+"), vec!["/finance"]);
+        assert_eq!(match_topics(&index, "sport/tennis/+/x").len(), 0);
+
+        // wildcards at the first level do not match $ topics
+        assert_eq!(match_topics(&index, "#").len(), 5);
+        assert_eq!(match_topics(&index, "+/brokers").len(), 0);
+        assert_eq!(match_topics(&index, "$SYS/#"), vec!["$SYS/brokers"]);
+    }
+
+    #[test]
+    fn retain_message_index_update_test() {
+        let index = RetainMessageIndex::new();
+        assert!(index.insert(entry("a/b", 0)).is_none());
+        assert!(index.insert(entry("a/b", 10)).is_some());
+        assert!(index.insert(entry("a/b/c", 0)).is_none());
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.get("a/b").unwrap().expire_at, 10);
+        assert!(index.get("a").is_none());
+
+        assert!(index.remove("a").is_none());
+        assert!(index.remove("a/b").is_some());
+        assert!(!index.contains("a/b"));
+        assert!(index.contains("a/b/c"));
+        assert_eq!(index.len(), 1);
+
+        assert!(index.remove("a/b/c").is_some());
+        assert!(index.is_empty());
+        assert_eq!(match_topics(&index, "#").len(), 0);
+
+        index.reset(vec![entry("x", 0), entry("y", 1)]);
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.expired_topics(now_second()), vec!["y".to_string()]);
+    }
+
+    #[test]
+    fn retain_message_entry_decode_test() {
+        let message = MqttMessage {
+            create_time: 100,
+            ..Default::default()
+        };
+        let entry = RetainMessageEntry::decode("t", &message.encode(), Some(30)).unwrap();
+        assert_eq!(entry.expire_at, 130);
+        assert!(!entry.is_expired(129));
+        assert!(entry.is_expired(130));
+
+        let entry = RetainMessageEntry::decode("t", &message.encode(), None).unwrap();
+        assert_eq!(entry.expire_at, 0);
+        assert!(!entry.is_expired(u64::MAX));
+
+        assert!(RetainMessageEntry::decode("t", &[], None).is_none());
+    }
+}
@@ -25,6 +25,9 @@ use super::cache::CacheManager;
 use super::lastwill::last_will_delay_interval;
 use crate::storage::session::SessionStorage;
 
+/// Builds the session of a connecting client. Besides the session and whether it is
+/// new, returns the id of the broker that still holds a live connection for a resumed
+/// session, which must be taken over before the client is accepted.
 #[allow(clippy::too_many_arguments)]
 pub async fn build_session(
     connect_id: u64,
@@ -35,15 +38,22 @@ pub async fn build_session(
     last_will_properties: &Option<LastWillProperties>,
     client_pool: &Arc<ClientPool>,
     cache_manager: &Arc<CacheManager>,
-) -> Result<(MqttSession, bool), CommonError> {
+) -> Result<(MqttSession, bool, Option<u64>), CommonError> {
     let session_expiry = session_expiry_interval(cache_manager, connect_properties);
     let is_contain_last_will = !last_will.is_none();
     let last_will_delay_interval = last_will_delay_interval(last_will_properties);
 
-    let (mut session, new_session) = if connect.clean_session {
+    let (mut session, new_session, online_broker_id) = if !connect.clean_session {
         let session_storage = SessionStorage::new(client_pool.clone());
         match session_storage.get_session(client_id.clone()).await {
-            Ok(Some(session)) => (session, false),
+            Ok(Some(session)) => {
+                let online_broker_id = if session.connection_id.is_some() {
+                    session.broker_id
+                } else {
+                    None
+                };
+                (session, false, online_broker_id)
+            }
             Ok(None) => (
                 MqttSession::new(
                     client_id,
@@ -52,6 +62,7 @@ pub async fn build_session(
                     last_will_delay_interval,
                 ),
                 true,
+                None,
             ),
             Err(e) => {
                 return Err(e);
@@ -66,6 +77,7 @@ pub async fn build_session(
                 last_will_delay_interval,
             ),
             true,
+            None,
         )
     };
 
@@ -73,7 +85,7 @@ pub async fn build_session(
     session.update_connnction_id(Some(connect_id));
     session.update_broker_id(Some(conf.broker_id));
     session.update_reconnect_time();
-    Ok((session, new_session))
+    Ok((session, new_session, online_broker_id))
 }
 
 pub async fn save_session(
@@ -135,12 +147,14 @@ fn session_expiry_interval(
 mod test {
     use std::sync::Arc;
 
-    use common_base::config::broker_mqtt::BrokerMqttConfig;
+    use common_base::config::broker_mqtt::{
+        broker_mqtt_conf, init_broker_mqtt_conf_by_path, BrokerMqttConfig,
+    };
     use grpc_clients::pool::ClientPool;
     use metadata_struct::mqtt::session::MqttSession;
-    use protocol::mqtt::common::ConnectProperties;
+    use protocol::mqtt::common::{Connect, ConnectProperties};
 
-    use super::session_expiry_interval;
+    use super::{build_session, session_expiry_interval};
     use crate::handler::cache::CacheManager;
 
     #[tokio::test]
@@ -158,6 +172,44 @@ mod test {
         assert!(session.distinct_time.is_none());
     }
 
+    #[tokio::test]
+    pub async fn build_clean_session_test() {
+        let path = format!(
+            "{}/../../config/mqtt-server.toml",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        init_broker_mqtt_conf_by_path(&path);
+
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(
+            client_pool.clone(),
+            broker_mqtt_conf().cluster_name.clone(),
+        ));
+        let connect = Connect {
+            keep_alive: 60,
+            client_id: "client_id_test-clean".to_string(),
+            clean_session: true,
+        };
+
+        // A clean session never resumes a stored session, so CONNACK carries session_present=false
+        let (session, new_session, online_broker_id) = build_session(
+            3,
+            connect.client_id.clone(),
+            &connect,
+            &None,
+            &None,
+            &None,
+            &client_pool,
+            &cache_manager,
+        )
+        .await
+        .unwrap();
+        assert!(new_session);
+        assert!(online_broker_id.is_none());
+        assert_eq!(session.connection_id, Some(3));
+        assert_eq!(session.broker_id, Some(broker_mqtt_conf().broker_id));
+    }
+
     #[test]
     pub fn session_expiry_interval_test() {
         let conf = BrokerMqttConfig {
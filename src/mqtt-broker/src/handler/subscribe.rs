@@ -0,0 +1,136 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use common_base::error::common::CommonError;
+use grpc_clients::pool::ClientPool;
+use log::warn;
+use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+use protocol::mqtt::common::{MqttProtocol, Subscribe, SubscribeProperties};
+
+use super::cache::CacheManager;
+use crate::storage::subscribe::SubscribeStorage;
+use crate::subscribe::subscribe_manager::SubscribeManager;
+
+pub async fn save_subscribe(
+    client_pool: &Arc<ClientPool>,
+    client_id: &str,
+    protocol: &MqttProtocol,
+    subscribe: &Subscribe,
+    subscribe_properties: &Option<SubscribeProperties>,
+) -> Result<(), CommonError> {
+    let conf = broker_mqtt_conf();
+    let storage = SubscribeStorage::new(client_pool.clone());
+    for filter in subscribe.filters.iter() {
+        let data = MqttSubscribe::new(
+            client_id.to_string(),
+            conf.broker_id,
+            protocol.clone(),
+            filter.clone(),
+            subscribe.packet_identifier,
+            subscribe_properties.clone(),
+        );
+        storage.save_subscribe(&data).await?;
+    }
+    Ok(())
+}
+
+pub async fn delete_subscribe(
+    client_pool: &Arc<ClientPool>,
+    client_id: &str,
+    filters: &[String],
+) -> Result<(), CommonError> {
+    let storage = SubscribeStorage::new(client_pool.clone());
+    for path in filters.iter() {
+        storage.delete_subscribe(client_id, path).await?;
+    }
+    Ok(())
+}
+
+/// Re-establish the subscriptions of a resumed session, which may have been
+/// created on another broker or before this broker restarted.
+pub async fn restore_subscribe(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_id: &str,
+) -> Result<(), CommonError> {
+    let storage = SubscribeStorage::new(client_pool.clone());
+    for data in storage.list_subscribe(client_id).await? {
+        let subscribe = Subscribe {
+            packet_identifier: data.pkid,
+            filters: vec![data.filter.clone()],
+        };
+
+        match subscribe_manager
+            .save_exclusive_subscribe(subscribe.clone())
+            .await
+        {
+            Ok(None) => {}
+            Ok(Some(code)) => {
+                warn!(
+                    "Client [{}] failed to restore subscription [{}], reason code: {:?}",
+                    client_id, data.path, code
+                );
+                continue;
+            }
+            Err(e) => {
+                warn!(
+                    "Client [{}] failed to restore subscription [{}], error: {}",
+                    client_id, data.path, e
+                );
+                continue;
+            }
+        }
+
+        cache_manager.add_client_subscribe(
+            client_id.to_string(),
+            data.protocol.clone(),
+            subscribe.clone(),
+            data.subscribe_properties.clone(),
+        );
+
+        subscribe_manager
+            .add_subscribe(
+                client_id.to_string(),
+                data.protocol,
+                subscribe,
+                data.subscribe_properties,
+            )
+            .await;
+    }
+    Ok(())
+}
+
+/// Drop every subscription left over from a previous session of the client:
+/// the persisted copy, the local cache and the push state of the subscribe manager.
+pub async fn clear_subscribe(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_id: &str,
+) -> Result<(), CommonError> {
+    let storage = SubscribeStorage::new(client_pool.clone());
+    storage.delete_subscribe_by_client_id(client_id).await?;
+    cache_manager.subscribe_filter.remove(client_id);
+    cache_manager.subscribe_is_new.remove(client_id);
+    // Releases the exclusive topics held by the old subscriptions before they are removed
+    subscribe_manager
+        .remove_exclusive_subscribe_by_client_id(client_id)
+        .await?;
+    subscribe_manager.remove_subscribe_by_client_id(client_id);
+    Ok(())
+}
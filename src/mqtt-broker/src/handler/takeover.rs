@@ -0,0 +1,414 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use common_base::error::common::CommonError;
+use grpc_clients::mqtt::inner::call::broker_mqtt_takeover_session;
+use grpc_clients::pool::ClientPool;
+use log::{info, warn};
+use metadata_struct::mqtt::session::MqttSession;
+use protocol::broker_mqtt::broker_mqtt_inner::{
+    TakeoverGroupOffset, TakeoverSessionReply, TakeoverSessionRequest,
+};
+use protocol::mqtt::common::DisconnectReasonCode;
+use storage_adapter::storage::StorageAdapter;
+use tokio::time::sleep;
+
+use super::cache::CacheManager;
+use super::connection::send_disconnect_by_reason;
+use super::pkid::pkid_save;
+use crate::server::connection_manager::ConnectionManager;
+use crate::storage::cluster::ClusterStorage;
+use crate::storage::message::MessageStorage;
+use crate::subscribe::sub_exclusive::build_group_name;
+use crate::subscribe::subscribe_manager::SubscribeManager;
+
+// How long to wait for a push thread to observe its stop signal
+const PUSH_THREAD_STOP_WAIT_TIMES: u32 = 100;
+
+/// Take over the session of a client that is still attached to a connection on
+/// `online_broker_id`. The old connection is kicked, and its committed offsets and
+/// in-flight packet ids are applied locally so that delivery resumes where it stopped.
+///
+/// Fails when the old broker is still registered but cannot be reached, since it
+/// would otherwise keep pushing the session's messages next to this broker. The
+/// client can connect again once that broker is back or has been deregistered.
+#[allow(clippy::too_many_arguments)]
+pub async fn takeover_session<S>(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    message_storage_adapter: &Arc<S>,
+    session: &MqttSession,
+    online_broker_id: u64,
+) -> Result<(), CommonError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let conf = broker_mqtt_conf();
+    let client_id = session.client_id.clone();
+
+    let reply = if online_broker_id == conf.broker_id {
+        release_session(
+            cache_manager,
+            connection_manager,
+            subscribe_manager,
+            message_storage_adapter,
+            &client_id,
+        )
+        .await?
+    } else {
+        let cluster_storage = ClusterStorage::new(client_pool.clone());
+        let node = cluster_storage
+            .node_list()
+            .await?
+            .into_iter()
+            .find(|node| node.node_id == online_broker_id);
+
+        let node = if let Some(node) = node {
+            node
+        } else {
+            warn!(
+                "Broker [{}] holding the session of client [{}] is no longer registered, skip session takeover",
+                online_broker_id, client_id
+            );
+            return Ok(());
+        };
+
+        let request = TakeoverSessionRequest {
+            cluster_name: conf.cluster_name.clone(),
+            client_id: client_id.clone(),
+            new_broker_id: conf.broker_id,
+        };
+        request_takeover(
+            client_pool,
+            &node.node_inner_addr,
+            online_broker_id,
+            request,
+        )
+        .await?
+    };
+
+    resume_session(
+        client_pool,
+        cache_manager,
+        message_storage_adapter,
+        &client_id,
+        reply,
+    )
+    .await?;
+
+    info!(
+        "Session of client [{}] was taken over from broker [{}]",
+        client_id, online_broker_id
+    );
+    Ok(())
+}
+
+async fn request_takeover(
+    client_pool: &Arc<ClientPool>,
+    addr: &str,
+    online_broker_id: u64,
+    request: TakeoverSessionRequest,
+) -> Result<TakeoverSessionReply, CommonError> {
+    let client_id = request.client_id.clone();
+    match broker_mqtt_takeover_session(client_pool, &[addr], request).await {
+        Ok(reply) => Ok(reply),
+        Err(e) => {
+            warn!(
+                "Failed to take over the session of client [{}] from broker [{}], error: {}",
+                client_id, online_broker_id, e
+            );
+            Err(CommonError::CommonError(format!(
+                "Broker [{}] holding the session of client [{}] is unreachable: {}",
+                online_broker_id, client_id, e
+            )))
+        }
+    }
+}
+
+/// Drop every local resource held for the session of a client that connected
+/// elsewhere, and collect the state the new owner needs to resume delivery.
+pub async fn release_session<S>(
+    cache_manager: &Arc<CacheManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    message_storage_adapter: &Arc<S>,
+    client_id: &str,
+) -> Result<TakeoverSessionReply, CommonError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    if let Some(connect_id) = cache_manager.get_connect_id(client_id) {
+        send_disconnect_by_reason(
+            connection_manager,
+            connect_id,
+            DisconnectReasonCode::SessionTakenOver,
+        )
+        .await;
+        cache_manager.remove_connection(connect_id);
+        connection_manager.close_connect(connect_id).await;
+    }
+
+    subscribe_manager
+        .remove_exclusive_subscribe_by_client_id(client_id)
+        .await?;
+
+    // Stop the push threads before reading the offsets, so that no commit lands
+    // after the offsets have been handed over.
+    let message_storage = MessageStorage::new(message_storage_adapter.clone());
+    let mut offsets = Vec::new();
+    for (exclusive_key, subscriber) in subscribe_manager.exclusive_subscribe.clone() {
+        if subscriber.client_id != client_id {
+            continue;
+        }
+        subscribe_manager.exclusive_subscribe.remove(&exclusive_key);
+        stop_push_thread(subscribe_manager, &exclusive_key).await;
+
+        let group_name = build_group_name(&subscriber);
+        let offset = message_storage.get_group_offset(&group_name).await?;
+        offsets.push(TakeoverGroupOffset {
+            group_name,
+            topic_id: subscriber.topic_id.clone(),
+            offset,
+        });
+    }
+    subscribe_manager.stop_push_by_client_id(client_id);
+
+    let reply = TakeoverSessionReply {
+        offsets,
+        publish_pkids: cache_manager
+            .list_publish_pkid(client_id)
+            .into_iter()
+            .map(|pkid| pkid as u32)
+            .collect(),
+        client_pkids: cache_manager
+            .list_client_pkid(client_id)
+            .into_iter()
+            .map(|pkid| pkid as u32)
+            .collect(),
+    };
+
+    cache_manager.remove_session(client_id);
+    Ok(reply)
+}
+
+async fn resume_session<S>(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    message_storage_adapter: &Arc<S>,
+    client_id: &str,
+    reply: TakeoverSessionReply,
+) -> Result<(), CommonError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let message_storage = MessageStorage::new(message_storage_adapter.clone());
+    for offset in reply.offsets {
+        message_storage
+            .commit_group_offset(&offset.group_name, &offset.topic_id, offset.offset)
+            .await?;
+    }
+
+    // The client may still acknowledge packets pushed by the old broker,
+    // so their ids stay reserved until it does.
+    for pkid in reply.publish_pkids {
+        cache_manager.add_publish_pkid(client_id, pkid as u16);
+    }
+
+    for pkid in reply.client_pkids {
+        pkid_save(cache_manager, client_pool, client_id, pkid as u16).await?;
+    }
+    Ok(())
+}
+
+async fn stop_push_thread(subscribe_manager: &Arc<SubscribeManager>, exclusive_key: &str) {
+    let sx = if let Some(sx) = subscribe_manager.exclusive_push_thread.get(exclusive_key) {
+        sx.clone()
+    } else {
+        return;
+    };
+
+    if sx.send(true).is_err() {
+        subscribe_manager
+            .exclusive_push_thread
+            .remove(exclusive_key);
+        return;
+    }
+
+    // The push thread removes itself from the list once it has stopped
+    for _ in 0..PUSH_THREAD_STOP_WAIT_TIMES {
+        if !subscribe_manager
+            .exclusive_push_thread
+            .contains_key(exclusive_key)
+        {
+            return;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+    subscribe_manager
+        .exclusive_push_thread
+        .remove(exclusive_key);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use common_base::config::broker_mqtt::init_broker_mqtt_conf_by_path;
+    use common_base::tools::unique_id;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::session::MqttSession;
+    use protocol::broker_mqtt::broker_mqtt_inner::{TakeoverSessionReply, TakeoverSessionRequest};
+    use storage_adapter::memory::MemoryStorageAdapter;
+    use storage_adapter::storage::StorageAdapter;
+    use tokio::sync::broadcast;
+
+    use super::{release_session, request_takeover, resume_session};
+    use crate::handler::cache::CacheManager;
+    use crate::server::connection_manager::ConnectionManager;
+    use crate::storage::message::MessageStorage;
+    use crate::subscribe::sub_exclusive::build_group_name;
+    use crate::subscribe::subscribe_manager::SubscribeManager;
+    use crate::subscribe::subscriber::Subscriber;
+
+    #[tokio::test]
+    async fn release_session_test() {
+        let path = format!(
+            "{}/../../config/mqtt-server.toml",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        init_broker_mqtt_conf_by_path(&path);
+
+        let client_pool = Arc::new(ClientPool::new(10));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        let subscribe_manager = Arc::new(SubscribeManager::new(
+            cache_manager.clone(),
+            client_pool.clone(),
+        ));
+        let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
+        let message_storage_adapter = Arc::new(MemoryStorageAdapter::new());
+
+        let client_id = unique_id();
+        let mut session = MqttSession::new(client_id.clone(), 60, false, None);
+        session.update_connnction_id(Some(1));
+        cache_manager.add_session(client_id.clone(), session);
+
+        let subscriber = Subscriber {
+            client_id: client_id.clone(),
+            sub_path: "/test/takeover".to_string(),
+            topic_name: "/test/takeover".to_string(),
+            topic_id: "topic-1".to_string(),
+            ..Default::default()
+        };
+        let group_name = build_group_name(&subscriber);
+        let mut offset = HashMap::new();
+        offset.insert(subscriber.topic_id.clone(), 12);
+        message_storage_adapter
+            .commit_offset(group_name.clone(), "test".to_string(), offset)
+            .await
+            .unwrap();
+
+        let exclusive_key = format!("{}_{}", client_id, subscriber.topic_id);
+        let (stop_sx, mut stop_rx) = broadcast::channel(1);
+        subscribe_manager
+            .exclusive_subscribe
+            .insert(exclusive_key.clone(), subscriber);
+        subscribe_manager
+            .exclusive_push_thread
+            .insert(exclusive_key.clone(), stop_sx);
+        let raw_subscribe_manager = subscribe_manager.clone();
+        let raw_exclusive_key = exclusive_key.clone();
+        tokio::spawn(async move {
+            if let Ok(true) = stop_rx.recv().await {
+                raw_subscribe_manager
+                    .exclusive_push_thread
+                    .remove(&raw_exclusive_key);
+            }
+        });
+
+        cache_manager.add_publish_pkid(&client_id, 3);
+        cache_manager.add_client_pkid(&client_id, 7);
+
+        let reply = release_session(
+            &cache_manager,
+            &connection_manager,
+            &subscribe_manager,
+            &message_storage_adapter,
+            &client_id,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(reply.offsets.len(), 1);
+        assert_eq!(reply.offsets[0].group_name, group_name);
+        assert_eq!(reply.offsets[0].topic_id, "topic-1");
+        assert_eq!(reply.offsets[0].offset, 12);
+        assert_eq!(reply.publish_pkids, vec![3]);
+        assert_eq!(reply.client_pkids, vec![7]);
+
+        assert!(cache_manager.get_session_info(&client_id).is_none());
+        assert!(!subscribe_manager
+            .exclusive_subscribe
+            .contains_key(&exclusive_key));
+        assert!(!subscribe_manager
+            .exclusive_push_thread
+            .contains_key(&exclusive_key));
+
+        // The new owner starts reading at the committed offset it was handed
+        let new_cache_manager =
+            Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        let new_storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let reply = TakeoverSessionReply {
+            client_pkids: Vec::new(),
+            ..reply
+        };
+        resume_session(
+            &client_pool,
+            &new_cache_manager,
+            &new_storage_adapter,
+            &client_id,
+            reply,
+        )
+        .await
+        .unwrap();
+
+        let message_storage = MessageStorage::new(new_storage_adapter);
+        assert_eq!(
+            message_storage.get_group_offset(&group_name).await.unwrap(),
+            12
+        );
+        assert_eq!(new_cache_manager.list_publish_pkid(&client_id), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn request_takeover_unreachable_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let request = TakeoverSessionRequest {
+            cluster_name: "test".to_string(),
+            client_id: unique_id(),
+            new_broker_id: 2,
+        };
+
+        // Nothing listens on port 1, the takeover fails instead of letting the
+        // old broker keep the session alive next to the new one
+        let result = request_takeover(&client_pool, "127.0.0.1:1", 1, request).await;
+        assert!(result.is_err());
+    }
+}
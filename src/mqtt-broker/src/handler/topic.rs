@@ -25,6 +25,7 @@ use storage_adapter::storage::{ShardConfig, StorageAdapter};
 
 use super::error::MqttBrokerError;
 use crate::handler::cache::CacheManager;
+use crate::handler::topic_rewrite::rewrite_publish_topic;
 use crate::storage::message::cluster_name;
 use crate::storage::topic::TopicStorage;
 
@@ -101,7 +102,9 @@ pub fn get_topic_name(
             return Err(MqttBrokerError::TopicNameInvalid());
         }
     } else {
-        topic
+        // Aliases are registered with the rewritten topic, so only a topic
+        // carried in the packet is rewritten.
+        rewrite_publish_topic(metadata_cache, topic)
     };
     topic_name_validator(&topic_name)?;
     Ok(topic_name)
@@ -0,0 +1,387 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use grpc_clients::pool::ClientPool;
+use log::{error, info};
+use metadata_struct::mqtt::topic_rewrite_rule::{MqttTopicRewriteRule, TopicRewriteAction};
+use regex::Regex;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+
+use super::cache::CacheManager;
+use super::error::MqttBrokerError;
+use crate::storage::topic::TopicStorage;
+use crate::subscribe::sub_common::{is_queue_sub, is_share_sub};
+
+/// A topic rewrite rule together with its compiled regex.
+#[derive(Clone, Debug)]
+pub struct TopicRewriteRule {
+    pub rule: MqttTopicRewriteRule,
+    regex: Regex,
+}
+
+impl TopicRewriteRule {
+    pub fn new(rule: MqttTopicRewriteRule) -> Result<Self, MqttBrokerError> {
+        if rule.source_topic.is_empty() || rule.dest_topic.is_empty() {
+            return Err(MqttBrokerError::InvalidTopicRewriteRule(
+                "source topic and destination topic cannot be empty".to_string(),
+            ));
+        }
+        let regex = match Regex::new(&rule.regex) {
+            Ok(regex) => regex,
+            Err(e) => return Err(MqttBrokerError::InvalidTopicRewriteRule(e.to_string())),
+        };
+        Ok(TopicRewriteRule { rule, regex })
+    }
+
+    pub fn key(&self) -> String {
+        topic_rewrite_rule_key(&self.rule.action.to_string(), &self.rule.source_topic)
+    }
+
+    fn applies_to(&self, action: &TopicRewriteAction) -> bool {
+        self.rule.action == TopicRewriteAction::All || self.rule.action == *action
+    }
+
+    // The destination topic if the topic matches both the source filter and the regex
+    fn rewrite(&self, topic: &str) -> Option<String> {
+        if !source_topic_match(&self.rule.source_topic, topic) {
+            return None;
+        }
+        let captures = self.regex.captures(topic)?;
+        let mut dest = String::new();
+        captures.expand(&self.rule.dest_topic, &mut dest);
+        Some(dest)
+    }
+}
+
+pub fn topic_rewrite_rule_key(action: &str, source_topic: &str) -> String {
+    format!("{}/{}", action, source_topic)
+}
+
+/// Rewrites the topic of a PUBLISH, returns the topic unchanged when no rule matches.
+pub fn rewrite_publish_topic(cache_manager: &Arc<CacheManager>, topic_name: String) -> String {
+    rewrite_topic(cache_manager, TopicRewriteAction::Publish, topic_name)
+}
+
+/// Rewrites a SUBSCRIBE or UNSUBSCRIBE filter. For shared and queue subscriptions
+/// only the filter after the `$share/{group}/` or `$queue/` prefix is rewritten.
+pub fn rewrite_subscribe_filter(cache_manager: &Arc<CacheManager>, filter: String) -> String {
+    if cache_manager.topic_rewrite_rule.is_empty() {
+        return filter;
+    }
+
+    let prefix_levels = if is_share_sub(filter.clone()) {
+        2
+    } else if is_queue_sub(filter.clone()) {
+        1
+    } else {
+        0
+    };
+    let parts: Vec<&str> = filter.splitn(prefix_levels + 1, '/').collect();
+    if parts.len() <= prefix_levels {
+        return filter;
+    }
+
+    let path = parts[prefix_levels];
+    let prefix = &filter[..filter.len() - path.len()];
+    let dest = rewrite_topic(
+        cache_manager,
+        TopicRewriteAction::Subscribe,
+        path.to_string(),
+    );
+    format!("{}{}", prefix, dest)
+}
+
+fn rewrite_topic(
+    cache_manager: &Arc<CacheManager>,
+    action: TopicRewriteAction,
+    topic: String,
+) -> String {
+    if cache_manager.topic_rewrite_rule.is_empty() {
+        return topic;
+    }
+
+    // The first matching rule wins
+    let rules = match cache_manager.sorted_topic_rewrite_rule.read() {
+        Ok(rules) => rules,
+        Err(e) => e.into_inner(),
+    };
+    for rule in rules.iter().filter(|rule| rule.applies_to(&action)) {
+        if let Some(dest) = rule.rewrite(&topic) {
+            return dest;
+        }
+    }
+    topic
+}
+
+// Rules are evaluated in creation order
+pub fn sort_topic_rewrite_rule(rules: &mut [TopicRewriteRule]) {
+    rules.sort_by(|a, b| {
+        a.rule
+            .create_time
+            .cmp(&b.rule.create_time)
+            .then_with(|| a.rule.source_topic.cmp(&b.rule.source_topic))
+    });
+}
+
+// Matches a topic, or a subscription filter whose wildcards are compared literally,
+// against the source filter of a rule.
+pub(crate) fn source_topic_match(source_topic: &str, topic: &str) -> bool {
+    let topic_levels: Vec<&str> = topic.split('/').collect();
+    let source_levels: Vec<&str> = source_topic.split('/').collect();
+    for (i, level) in source_levels.iter().enumerate() {
+        if *level == "#" {
+            return true;
+        }
+        match topic_levels.get(i) {
+            Some(topic_level) if *level == "+" || level == topic_level => {}
+            _ => return false,
+        }
+    }
+    source_levels.len() == topic_levels.len()
+}
+
+pub async fn save_topic_rewrite_rule(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    rule: MqttTopicRewriteRule,
+) -> Result<(), MqttBrokerError> {
+    let rule = TopicRewriteRule::new(rule)?;
+    let topic_storage = TopicStorage::new(client_pool.clone());
+    topic_storage.save_topic_rewrite_rule(&rule.rule).await?;
+    cache_manager.add_topic_rewrite_rule(rule);
+    Ok(())
+}
+
+pub async fn delete_topic_rewrite_rule(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    action: TopicRewriteAction,
+    source_topic: String,
+) -> Result<(), MqttBrokerError> {
+    let topic_storage = TopicStorage::new(client_pool.clone());
+    topic_storage
+        .delete_topic_rewrite_rule(action.to_string(), source_topic.clone())
+        .await?;
+    cache_manager.remove_topic_rewrite_rule(&action.to_string(), &source_topic);
+    Ok(())
+}
+
+pub async fn load_topic_rewrite_rule(
+    cache_manager: &CacheManager,
+    client_pool: &Arc<ClientPool>,
+) -> Result<(), MqttBrokerError> {
+    let topic_storage = TopicStorage::new(client_pool.clone());
+    let mut keys = HashSet::new();
+    for rule in topic_storage.all_topic_rewrite_rule().await? {
+        match TopicRewriteRule::new(rule) {
+            Ok(rule) => {
+                keys.insert(rule.key());
+                cache_manager.topic_rewrite_rule.insert(rule.key(), rule);
+            }
+            Err(e) => error!("{}", e),
+        }
+    }
+    cache_manager
+        .topic_rewrite_rule
+        .retain(|key, _| keys.contains(key));
+    cache_manager.rebuild_sorted_topic_rewrite_rule();
+    Ok(())
+}
+
+// Rules changed through the admin api of another broker reach this broker by reloading them
+// from the placement center.
+pub struct UpdateTopicRewriteRuleCache {
+    stop_send: broadcast::Sender<bool>,
+    cache_manager: Arc<CacheManager>,
+    client_pool: Arc<ClientPool>,
+}
+
+impl UpdateTopicRewriteRuleCache {
+    pub fn new(
+        stop_send: broadcast::Sender<bool>,
+        cache_manager: Arc<CacheManager>,
+        client_pool: Arc<ClientPool>,
+    ) -> Self {
+        UpdateTopicRewriteRuleCache {
+            stop_send,
+            cache_manager,
+            client_pool,
+        }
+    }
+
+    pub async fn start_update(&self) {
+        loop {
+            let mut stop_rx = self.stop_send.subscribe();
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}","Topic rewrite rule cache updating thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = self.update_topic_rewrite_rule_cache()=>{
+                }
+            }
+        }
+    }
+
+    async fn update_topic_rewrite_rule_cache(&self) {
+        if let Err(e) = load_topic_rewrite_rule(&self.cache_manager, &self.client_pool).await {
+            error!(
+                "Updating topic rewrite rule cache failed, error message: {}",
+                e
+            );
+        }
+        sleep(Duration::from_secs(5)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::topic_rewrite_rule::{MqttTopicRewriteRule, TopicRewriteAction};
+
+    use super::{
+        rewrite_publish_topic, rewrite_subscribe_filter, source_topic_match, TopicRewriteRule,
+    };
+    use crate::handler::cache::CacheManager;
+
+    fn build_rule(
+        action: TopicRewriteAction,
+        source: &str,
+        regex: &str,
+        dest: &str,
+    ) -> TopicRewriteRule {
+        TopicRewriteRule::new(MqttTopicRewriteRule::new(
+            action,
+            source.to_string(),
+            dest.to_string(),
+            regex.to_string(),
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn source_topic_match_test() {
+        assert!(source_topic_match("v1/dev/+/data", "v1/dev/1/data"));
+        assert!(!source_topic_match("v1/dev/+/data", "v1/dev/1/2/data"));
+        assert!(source_topic_match("v1/#", "v1/dev/1/data"));
+        assert!(source_topic_match("v1/#", "v1"));
+        assert!(source_topic_match("v1/dev/+/data", "v1/dev/+/data"));
+        assert!(!source_topic_match("v1/dev", "v1/dev/1"));
+    }
+
+    #[test]
+    fn invalid_rule_test() {
+        let rule = MqttTopicRewriteRule::new(
+            TopicRewriteAction::All,
+            "v1/#".to_string(),
+            "v2/$1".to_string(),
+            "^v1/(.+$".to_string(),
+        );
+        assert!(TopicRewriteRule::new(rule).is_err());
+    }
+
+    #[test]
+    fn rewrite_topic_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+
+        assert_eq!(
+            rewrite_publish_topic(&cache_manager, "v1/dev/1/data".to_string()),
+            "v1/dev/1/data"
+        );
+
+        cache_manager.add_topic_rewrite_rule(build_rule(
+            TopicRewriteAction::All,
+            "v1/dev/+/data",
+            "^v1/dev/(.+)/data$",
+            "devices/$1/telemetry",
+        ));
+        cache_manager.add_topic_rewrite_rule(build_rule(
+            TopicRewriteAction::Subscribe,
+            "v1/cmd/#",
+            "^v1/cmd/(.+)$",
+            "commands/$1",
+        ));
+
+        assert_eq!(
+            rewrite_publish_topic(&cache_manager, "v1/dev/10/data".to_string()),
+            "devices/10/telemetry"
+        );
+        assert_eq!(
+            rewrite_publish_topic(&cache_manager, "v1/cmd/10".to_string()),
+            "v1/cmd/10"
+        );
+        assert_eq!(
+            rewrite_subscribe_filter(&cache_manager, "v1/cmd/10".to_string()),
+            "commands/10"
+        );
+        assert_eq!(
+            rewrite_subscribe_filter(&cache_manager, "v1/dev/+/data".to_string()),
+            "devices/+/telemetry"
+        );
+        assert_eq!(
+            rewrite_subscribe_filter(&cache_manager, "$share/g1/v1/dev/+/data".to_string()),
+            "$share/g1/devices/+/telemetry"
+        );
+        assert_eq!(
+            rewrite_subscribe_filter(&cache_manager, "$queue/v1/cmd/1".to_string()),
+            "$queue/commands/1"
+        );
+
+        cache_manager.remove_topic_rewrite_rule("All", "v1/dev/+/data");
+        assert_eq!(
+            rewrite_publish_topic(&cache_manager, "v1/dev/10/data".to_string()),
+            "v1/dev/10/data"
+        );
+    }
+
+    #[test]
+    fn rewrite_topic_order_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+
+        let mut late = build_rule(TopicRewriteAction::Publish, "v1/#", "^v1/(.+)$", "late/$1");
+        late.rule.create_time = 200;
+        let mut early = build_rule(
+            TopicRewriteAction::Publish,
+            "v1/dev/#",
+            "^v1/dev/(.+)$",
+            "early/$1",
+        );
+        early.rule.create_time = 100;
+        cache_manager.add_topic_rewrite_rule(late);
+        cache_manager.add_topic_rewrite_rule(early);
+
+        assert_eq!(
+            rewrite_publish_topic(&cache_manager, "v1/dev/1".to_string()),
+            "early/1"
+        );
+        assert_eq!(
+            rewrite_publish_topic(&cache_manager, "v1/other".to_string()),
+            "late/other"
+        );
+    }
+}
@@ -27,6 +27,7 @@ use protocol::mqtt::common::{
     LastWillProperties, Login, MqttPacket, MqttProtocol, PubAckReason, PubRecReason, Publish,
     PublishProperties, QoS, Subscribe, SubscribeReasonCode, UnsubAckReason, Unsubscribe,
 };
+use tokio::io::AsyncWrite;
 use tokio_util::codec::FramedWrite;
 
 use super::cache::CacheManager;
@@ -38,7 +39,8 @@ use super::pkid::pkid_exists;
 use super::response::{
     response_packet_mqtt_connect_fail, response_packet_mqtt_distinct_by_reason,
     response_packet_mqtt_puback_fail, response_packet_mqtt_pubrec_fail,
-    response_packet_mqtt_suback, response_packet_mqtt_unsuback,
+    response_packet_mqtt_pubrec_success, response_packet_mqtt_suback,
+    response_packet_mqtt_unsuback,
 };
 use super::topic::topic_name_validator;
 use crate::security::authentication_acl;
@@ -46,11 +48,14 @@ use crate::security::login::is_ip_blacklist;
 use crate::server::connection_manager::ConnectionManager;
 use crate::subscribe::sub_common::sub_path_validator;
 
-pub async fn tcp_establish_connection_check(
+pub async fn establish_connection_check<T>(
     addr: &SocketAddr,
     connection_manager: &Arc<ConnectionManager>,
-    write_frame_stream: &mut FramedWrite<tokio::io::WriteHalf<tokio::net::TcpStream>, MqttCodec>,
-) -> bool {
+    write_frame_stream: &mut FramedWrite<T, MqttCodec>,
+) -> bool
+where
+    T: AsyncWrite + Unpin,
+{
     if connection_manager.tcp_connect_num_check() {
         let packet_wrapper = MqttPacketWrapper {
             protocol_version: MqttProtocol::Mqtt5.into(),
@@ -67,7 +72,7 @@ pub async fn tcp_establish_connection_check(
         match write_frame_stream.close().await {
             Ok(_) => {
                 error!(
-                    "tcp connection failed to establish from IP: {}",
+                    "connection failed to establish from IP: {}",
                     addr.to_string()
                 );
             }
@@ -92,67 +97,7 @@ pub async fn tcp_establish_connection_check(
         match write_frame_stream.close().await {
             Ok(_) => {
                 error!(
-                    "tcp connection failed to establish from IP: {}",
-                    addr.to_string()
-                );
-            }
-            Err(e) => error!("{}", e),
-        }
-        return false;
-    }
-    true
-}
-
-pub async fn tcp_tls_establish_connection_check(
-    addr: &SocketAddr,
-    connection_manager: &Arc<ConnectionManager>,
-    write_frame_stream: &mut FramedWrite<
-        tokio::io::WriteHalf<tokio_rustls::server::TlsStream<tokio::net::TcpStream>>,
-        MqttCodec,
-    >,
-) -> bool {
-    if connection_manager.tcp_connect_num_check() {
-        let packet_wrapper = MqttPacketWrapper {
-            protocol_version: MqttProtocol::Mqtt5.into(),
-            packet: response_packet_mqtt_distinct_by_reason(
-                &MqttProtocol::Mqtt5,
-                Some(DisconnectReasonCode::QuotaExceeded),
-            ),
-        };
-        match write_frame_stream.send(packet_wrapper).await {
-            Ok(_) => {}
-            Err(e) => error!("{}", e),
-        }
-
-        match write_frame_stream.close().await {
-            Ok(_) => {
-                error!(
-                    "tcp connection failed to establish from IP: {}",
-                    addr.to_string()
-                );
-            }
-            Err(e) => error!("{}", e),
-        }
-        return false;
-    }
-
-    if is_connection_rate_exceeded() {
-        let packet_wrapper = MqttPacketWrapper {
-            protocol_version: MqttProtocol::Mqtt5.into(),
-            packet: response_packet_mqtt_distinct_by_reason(
-                &MqttProtocol::Mqtt5,
-                Some(DisconnectReasonCode::ConnectionRateExceeded),
-            ),
-        };
-        match write_frame_stream.send(packet_wrapper).await {
-            Ok(_) => {}
-            Err(e) => error!("{}", e),
-        }
-
-        match write_frame_stream.close().await {
-            Ok(_) => {
-                error!(
-                    "tcp connection failed to establish from IP: {}",
+                    "connection failed to establish from IP: {}",
                     addr.to_string()
                 );
             }
@@ -304,6 +249,16 @@ pub async fn publish_validator(
         {
             Ok(res) => {
                 if res {
+                    // The message was already stored, the client resends it because the
+                    // PUBREC was lost, e.g. when the broker restarted
+                    if publish.dup {
+                        return Some(response_packet_mqtt_pubrec_success(
+                            protocol,
+                            PubRecReason::Success,
+                            publish.pkid,
+                            Vec::new(),
+                        ));
+                    }
                     return Some(response_packet_mqtt_pubrec_fail(
                         protocol,
                         connection,
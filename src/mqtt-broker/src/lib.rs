@@ -12,18 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
 use common_base::config::broker_mqtt::broker_mqtt_conf;
+use common_base::metrics::push::{start_prometheus_push, PROMETHEUS_MODEL_PUSH};
 use common_base::runtime::create_runtime;
+use common_base::telemetry::{init_telemetry, shutdown_telemetry, SERVICE_NAME_MQTT_BROKER};
 use common_base::tools::now_second;
 use grpc_clients::pool::ClientPool;
 use handler::acl::UpdateAclCache;
+use handler::auto_subscribe::UpdateAutoSubscribeRuleCache;
 use handler::cache::CacheManager;
+use handler::delay_message::DelayMessageManager;
 use handler::heartbreat::{register_node, report_heartbeat};
 use handler::keep_alive::ClientKeepAlive;
+use handler::retain::UpdateRetainMessageCache;
+use handler::topic_rewrite::UpdateTopicRewriteRuleCache;
 use handler::user::UpdateUserCache;
 use lazy_static::lazy_static;
 use log::{error, info};
@@ -32,9 +39,12 @@ use security::AuthDriver;
 use server::connection_manager::ConnectionManager;
 use server::grpc::server::GrpcServer;
 use server::http::server::{start_http_server, HttpServerState};
+use server::quic::server::start_quic_server;
 use server::tcp::server::start_tcp_server;
 use server::websocket::server::{websocket_server, websockets_server, WebSocketServerState};
 use storage::cluster::ClusterStorage;
+use storage::metadata::expire::LocalMetadataExpire;
+use storage::metadata::{is_local_metadata_storage, local_metadata_storage};
 use storage_adapter::memory::MemoryStorageAdapter;
 // use storage_adapter::mysql::MySQLStorageAdapter;
 // use storage_adapter::rocksdb::RocksDBStorageAdapter;
@@ -111,6 +121,7 @@ pub struct MqttBroker<S> {
     subscribe_manager: Arc<SubscribeManager>,
     connection_manager: Arc<ConnectionManager>,
     auth_driver: Arc<AuthDriver>,
+    delay_message_manager: Arc<DelayMessageManager<S>>,
 }
 
 impl<S> MqttBroker<S>
@@ -136,6 +147,12 @@ where
         let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
 
         let auth_driver = Arc::new(AuthDriver::new(cache_manager.clone(), client_pool.clone()));
+
+        let delay_message_manager = Arc::new(DelayMessageManager::new(
+            cache_manager.clone(),
+            client_pool.clone(),
+            message_storage_adapter.clone(),
+        ));
         MqttBroker {
             runtime,
             cache_manager,
@@ -144,24 +161,43 @@ where
             subscribe_manager,
             connection_manager,
             auth_driver,
+            delay_message_manager,
         }
     }
 
     pub fn start(&self, stop_send: broadcast::Sender<bool>) {
+        self.start_telemetry();
         self.register_node();
         self.start_grpc_server();
         self.start_mqtt_server(stop_send.clone());
         self.start_http_server();
         self.start_websocket_server(stop_send.clone());
+        if broker_mqtt_conf().network.quic_enable {
+            self.start_quic_server(stop_send.clone());
+        }
         self.start_keep_alive_thread(stop_send.clone());
         self.start_cluster_heartbeat_report(stop_send.clone());
         self.start_update_user_cache_thread(stop_send.clone());
         self.start_update_acl_cache_thread(stop_send.clone());
+        self.start_update_retain_message_cache_thread(stop_send.clone());
+        self.start_update_topic_rewrite_rule_cache_thread(stop_send.clone());
+        self.start_update_auto_subscribe_rule_cache_thread(stop_send.clone());
         self.start_push_server();
+        self.start_delay_message_thread(stop_send.clone());
         self.start_system_topic_thread(stop_send.clone());
+        self.start_prometheus_push(stop_send.clone());
+        self.start_local_metadata_expire_thread(stop_send.clone());
         self.awaiting_stop(stop_send);
     }
 
+    fn start_telemetry(&self) {
+        // the span exporter runs on the broker runtime
+        let _guard = self.runtime.enter();
+        if let Err(e) = init_telemetry(SERVICE_NAME_MQTT_BROKER, &broker_mqtt_conf().telemetry) {
+            error!("Failed to initialize telemetry, error message: {}", e);
+        }
+    }
+
     fn start_mqtt_server(&self, stop_send: broadcast::Sender<bool>) {
         let cache = self.cache_manager.clone();
         let message_storage_adapter = self.message_storage_adapter.clone();
@@ -169,6 +205,7 @@ where
         let client_pool = self.client_pool.clone();
         let connection_manager = self.connection_manager.clone();
         let auth_driver = self.auth_driver.clone();
+        let delay_message_manager = self.delay_message_manager.clone();
 
         self.runtime.spawn(async move {
             start_tcp_server(
@@ -179,6 +216,7 @@ where
                 client_pool,
                 stop_send,
                 auth_driver,
+                delay_message_manager,
             )
             .await
         });
@@ -193,6 +231,7 @@ where
             self.connection_manager.clone(),
             self.client_pool.clone(),
             self.message_storage_adapter.clone(),
+            self.delay_message_manager.clone(),
         );
         self.runtime.spawn(async move {
             match server.start().await {
@@ -205,7 +244,12 @@ where
     }
 
     fn start_http_server(&self) {
-        let http_state = HttpServerState::new();
+        let http_state = HttpServerState::new(
+            self.cache_manager.clone(),
+            self.connection_manager.clone(),
+            self.subscribe_manager.clone(),
+            self.client_pool.clone(),
+        );
         self.runtime.spawn(async move {
             match start_http_server(http_state).await {
                 Ok(_) => {}
@@ -224,6 +268,7 @@ where
             self.message_storage_adapter.clone(),
             self.client_pool.clone(),
             self.auth_driver.clone(),
+            self.delay_message_manager.clone(),
             stop_send.clone(),
         );
         self.runtime
@@ -236,6 +281,7 @@ where
             self.message_storage_adapter.clone(),
             self.client_pool.clone(),
             self.auth_driver.clone(),
+            self.delay_message_manager.clone(),
             stop_send.clone(),
         );
 
@@ -243,6 +289,33 @@ where
             .spawn(async move { websockets_server(ws_state).await });
     }
 
+    fn start_quic_server(&self, stop_send: broadcast::Sender<bool>) {
+        let cache = self.cache_manager.clone();
+        let message_storage_adapter = self.message_storage_adapter.clone();
+        let subscribe_manager = self.subscribe_manager.clone();
+        let client_pool = self.client_pool.clone();
+        let connection_manager = self.connection_manager.clone();
+        let auth_driver = self.auth_driver.clone();
+        let delay_message_manager = self.delay_message_manager.clone();
+
+        self.runtime.spawn(async move {
+            if let Err(e) = start_quic_server(
+                subscribe_manager,
+                cache,
+                connection_manager,
+                message_storage_adapter,
+                client_pool,
+                stop_send,
+                auth_driver,
+                delay_message_manager,
+            )
+            .await
+            {
+                error!("Failed to start the MQTT QUIC server, error message: {}", e);
+            }
+        });
+    }
+
     fn start_cluster_heartbeat_report(&self, stop_send: broadcast::Sender<bool>) {
         let client_pool = self.client_pool.clone();
         self.runtime.spawn(async move {
@@ -290,6 +363,28 @@ where
         });
     }
 
+    // Without a placement center the node expires its own sessions and messages
+    fn start_local_metadata_expire_thread(&self, stop_send: broadcast::Sender<bool>) {
+        if !is_local_metadata_storage() {
+            return;
+        }
+        let local_metadata_expire = LocalMetadataExpire::new(
+            local_metadata_storage(),
+            self.client_pool.clone(),
+            stop_send,
+        );
+        self.runtime.spawn(async move {
+            local_metadata_expire.start().await;
+        });
+    }
+
+    fn start_delay_message_thread(&self, stop_send: broadcast::Sender<bool>) {
+        let delay_message_manager = self.delay_message_manager.clone();
+        self.runtime.spawn(async move {
+            delay_message_manager.start(stop_send).await;
+        });
+    }
+
     fn start_keep_alive_thread(&self, stop_send: broadcast::Sender<bool>) {
         let mut keep_alive = ClientKeepAlive::new(
             self.client_pool.clone(),
@@ -319,6 +414,42 @@ where
         });
     }
 
+    fn start_update_retain_message_cache_thread(&self, stop_send: broadcast::Sender<bool>) {
+        let update_retain_message_cache = UpdateRetainMessageCache::new(
+            stop_send,
+            self.cache_manager.clone(),
+            self.client_pool.clone(),
+        );
+
+        self.runtime.spawn(async move {
+            update_retain_message_cache.start_update().await;
+        });
+    }
+
+    fn start_update_topic_rewrite_rule_cache_thread(&self, stop_send: broadcast::Sender<bool>) {
+        let update_topic_rewrite_rule_cache = UpdateTopicRewriteRuleCache::new(
+            stop_send,
+            self.cache_manager.clone(),
+            self.client_pool.clone(),
+        );
+
+        self.runtime.spawn(async move {
+            update_topic_rewrite_rule_cache.start_update().await;
+        });
+    }
+
+    fn start_update_auto_subscribe_rule_cache_thread(&self, stop_send: broadcast::Sender<bool>) {
+        let update_auto_subscribe_rule_cache = UpdateAutoSubscribeRuleCache::new(
+            stop_send,
+            self.cache_manager.clone(),
+            self.client_pool.clone(),
+        );
+
+        self.runtime.spawn(async move {
+            update_auto_subscribe_rule_cache.start_update().await;
+        });
+    }
+
     fn start_system_topic_thread(&self, stop_send: broadcast::Sender<bool>) {
         let cache_manager = self.cache_manager.clone();
         let message_storage_adapter = self.message_storage_adapter.clone();
@@ -334,6 +465,22 @@ where
         });
     }
 
+    fn start_prometheus_push(&self, stop_send: broadcast::Sender<bool>) {
+        // pull mode is served by the /metrics route of the http server
+        let conf = broker_mqtt_conf();
+        if !conf.prometheus.enable || conf.prometheus.model != PROMETHEUS_MODEL_PUSH {
+            return;
+        }
+
+        let mut grouping = HashMap::new();
+        grouping.insert("cluster".to_string(), conf.cluster_name.clone());
+        grouping.insert("broker_id".to_string(), conf.broker_id.to_string());
+        let prometheus = conf.prometheus.clone();
+        self.runtime.spawn(async move {
+            start_prometheus_push(prometheus, "mqtt-broker".to_string(), grouping, stop_send).await;
+        });
+    }
+
     pub fn awaiting_stop(&self, stop_send: broadcast::Sender<bool>) {
         self.runtime.spawn(async move {
             sleep(Duration::from_millis(5)).await;
@@ -392,5 +539,6 @@ where
             }
         }
         self.connection_manager.close_all_connect().await;
+        shutdown_telemetry();
     }
 }
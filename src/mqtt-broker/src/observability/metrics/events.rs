@@ -11,3 +11,41 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use lazy_static::lazy_static;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+
+const METRICS_LABEL_URL: &str = "url";
+const METRICS_LABEL_RESULT: &str = "result";
+
+lazy_static! {
+    // Number of webhook events per endpoint, by result: sent, dropped or failed
+    static ref WEBHOOK_EVENTS: IntCounterVec = register_int_counter_vec!(
+        "webhook_events",
+        "Number of webhook events per endpoint, by result",
+        &[METRICS_LABEL_URL, METRICS_LABEL_RESULT]
+    )
+    .unwrap();
+}
+
+pub fn metrics_webhook_sent(url: &str, num: usize) {
+    WEBHOOK_EVENTS
+        .with_label_values(&[url, "sent"])
+        .inc_by(num as u64);
+}
+
+// The endpoint queue was full, the event was discarded without being sent
+pub fn metrics_webhook_dropped(url: &str) {
+    WEBHOOK_EVENTS.with_label_values(&[url, "dropped"]).inc();
+}
+
+// The batch could not be delivered after all retries
+pub fn metrics_webhook_failed(url: &str, num: usize) {
+    WEBHOOK_EVENTS
+        .with_label_values(&[url, "failed"])
+        .inc_by(num as u64);
+}
+
+pub fn webhook_events_num(url: &str, result: &str) -> u64 {
+    WEBHOOK_EVENTS.with_label_values(&[url, result]).get()
+}
@@ -0,0 +1,248 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use dashmap::DashSet;
+use lazy_static::lazy_static;
+use prometheus::core::Collector;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+
+use crate::handler::constant::{METRICS_KEY_REASON, METRICS_KEY_TOPIC};
+
+// Topics are bucketed by their first levels, e.g. "factory/line1/#"
+pub const TOPIC_BUCKET_LEVELS: usize = 2;
+// Upper bound of distinct topic buckets, later topics share the "other" bucket
+pub const TOPIC_BUCKET_MAX_NUM: usize = 256;
+pub const TOPIC_BUCKET_OTHER: &str = "other";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageDropReason {
+    // The message expiry interval elapsed before delivery
+    Expired,
+    // No subscriber was available to deliver the message to, e.g. every member of a
+    // share group is gone
+    NoSubscribers,
+    // The subscription was made with no_local by the publishing client
+    NoLocal,
+    // The message queue of the subscriber was full
+    QueueFull,
+    // The client exceeded the receive maximum of its inflight window
+    InflightFull,
+    AclDenied,
+    // Rejected by the broker for any other reason, e.g. an invalid payload
+    Rejected,
+}
+
+impl MessageDropReason {
+    pub const ALL: [MessageDropReason; 7] = [
+        MessageDropReason::Expired,
+        MessageDropReason::NoSubscribers,
+        MessageDropReason::NoLocal,
+        MessageDropReason::QueueFull,
+        MessageDropReason::InflightFull,
+        MessageDropReason::AclDenied,
+        MessageDropReason::Rejected,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageDropReason::Expired => "expired",
+            MessageDropReason::NoSubscribers => "no_subscribers",
+            MessageDropReason::NoLocal => "no_local",
+            MessageDropReason::QueueFull => "queue_full",
+            MessageDropReason::InflightFull => "inflight_full",
+            MessageDropReason::AclDenied => "acl_denied",
+            MessageDropReason::Rejected => "rejected",
+        }
+    }
+}
+
+lazy_static! {
+    // Number of messages discarded by the broker, by reason and topic bucket
+    static ref MESSAGES_DROPPED: IntCounterVec = register_int_counter_vec!(
+        "messages_dropped",
+        "Number of messages discarded by the broker",
+        &[METRICS_KEY_REASON, METRICS_KEY_TOPIC]
+    )
+    .unwrap();
+
+    // Number of messages delivered with a lower QoS than they were published with
+    static ref MESSAGES_QOS_DOWNGRADED: IntCounterVec = register_int_counter_vec!(
+        "messages_qos_downgraded",
+        "Number of messages delivered with a lower QoS than they were published with",
+        &[METRICS_KEY_TOPIC]
+    )
+    .unwrap();
+
+    // Number of messages published to a topic no subscription matched. They are still
+    // stored, so this is not a drop reason.
+    static ref MESSAGES_NO_SUBSCRIBERS: IntCounterVec = register_int_counter_vec!(
+        "messages_no_subscribers",
+        "Number of messages published to a topic without matching subscriptions",
+        &[METRICS_KEY_TOPIC]
+    )
+    .unwrap();
+
+    static ref TOPIC_BUCKETS: DashSet<String> = DashSet::with_capacity(TOPIC_BUCKET_MAX_NUM);
+}
+
+// Map a topic to its metrics label, keeping the number of label values bounded
+pub fn topic_bucket(topic: &str) -> String {
+    let levels: Vec<&str> = topic.splitn(TOPIC_BUCKET_LEVELS + 1, '/').collect();
+    let bucket = if levels.len() > TOPIC_BUCKET_LEVELS {
+        format!("{}/#", levels[..TOPIC_BUCKET_LEVELS].join("/"))
+    } else {
+        topic.to_string()
+    };
+
+    if TOPIC_BUCKETS.contains(&bucket) {
+        return bucket;
+    }
+    // Concurrent callers may overshoot the limit by a few buckets, which is fine
+    if TOPIC_BUCKETS.len() >= TOPIC_BUCKET_MAX_NUM {
+        return TOPIC_BUCKET_OTHER.to_string();
+    }
+    TOPIC_BUCKETS.insert(bucket.clone());
+    bucket
+}
+
+pub fn metrics_message_dropped(reason: MessageDropReason, topic: &str) {
+    MESSAGES_DROPPED
+        .with_label_values(&[reason.as_str(), &topic_bucket(topic)])
+        .inc();
+}
+
+pub fn metrics_message_no_subscribers(topic: &str) {
+    MESSAGES_NO_SUBSCRIBERS
+        .with_label_values(&[&topic_bucket(topic)])
+        .inc();
+}
+
+pub fn metrics_message_qos_downgraded(topic: &str) {
+    MESSAGES_QOS_DOWNGRADED
+        .with_label_values(&[&topic_bucket(topic)])
+        .inc();
+}
+
+// Totals published under $SYS/brokers/${node}/metrics/messages
+pub fn messages_metrics_snapshot() -> Vec<(&'static str, i64)> {
+    let mut dropped = [0; MessageDropReason::ALL.len()];
+    for family in MESSAGES_DROPPED.collect() {
+        for metric in family.get_metric() {
+            let reason = metric
+                .get_label()
+                .iter()
+                .find(|label| label.get_name() == METRICS_KEY_REASON)
+                .map(|label| label.get_value());
+            if let Some(index) = MessageDropReason::ALL
+                .iter()
+                .position(|raw| Some(raw.as_str()) == reason)
+            {
+                dropped[index] += metric.get_counter().get_value() as i64;
+            }
+        }
+    }
+
+    let mut results = vec![("dropped", dropped.iter().sum::<i64>())];
+    for (reason, value) in MessageDropReason::ALL.iter().zip(dropped) {
+        let name = match reason {
+            MessageDropReason::Expired => "dropped/expired",
+            MessageDropReason::NoSubscribers => "dropped/no_subscribers",
+            MessageDropReason::NoLocal => "dropped/no_local",
+            MessageDropReason::QueueFull => "dropped/queue_full",
+            MessageDropReason::InflightFull => "dropped/inflight_full",
+            MessageDropReason::AclDenied => "dropped/acl_denied",
+            MessageDropReason::Rejected => "dropped/rejected",
+        };
+        results.push((name, value));
+    }
+    results.push((
+        "no_subscribers",
+        counter_vec_total(&MESSAGES_NO_SUBSCRIBERS),
+    ));
+    results.push((
+        "qos_downgraded",
+        counter_vec_total(&MESSAGES_QOS_DOWNGRADED),
+    ));
+    results
+}
+
+fn counter_vec_total(counter: &IntCounterVec) -> i64 {
+    let mut total = 0;
+    for family in counter.collect() {
+        for metric in family.get_metric() {
+            total += metric.get_counter().get_value() as i64;
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        messages_metrics_snapshot, metrics_message_dropped, metrics_message_no_subscribers,
+        metrics_message_qos_downgraded, topic_bucket, MessageDropReason, TOPIC_BUCKETS,
+        TOPIC_BUCKET_MAX_NUM, TOPIC_BUCKET_OTHER,
+    };
+
+    fn snapshot_value(name: &str) -> i64 {
+        messages_metrics_snapshot()
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| *value)
+            .unwrap()
+    }
+
+    #[test]
+    fn topic_bucket_test() {
+        assert_eq!(
+            topic_bucket("bucket_test/room1/temp"),
+            "bucket_test/room1/#"
+        );
+        assert_eq!(topic_bucket("bucket_test/room1"), "bucket_test/room1");
+        assert_eq!(topic_bucket("bucket_test"), "bucket_test");
+
+        for i in 0..TOPIC_BUCKET_MAX_NUM {
+            topic_bucket(&format!("bucket_fill/{}", i));
+        }
+        assert!(TOPIC_BUCKETS.len() >= TOPIC_BUCKET_MAX_NUM);
+        assert_eq!(topic_bucket("bucket_new/topic"), TOPIC_BUCKET_OTHER);
+        // known buckets keep their own label
+        assert_eq!(
+            topic_bucket("bucket_test/room1/humidity"),
+            "bucket_test/room1/#"
+        );
+    }
+
+    #[test]
+    fn messages_metrics_snapshot_test() {
+        let dropped = snapshot_value("dropped");
+        let expired = snapshot_value("dropped/expired");
+        let acl_denied = snapshot_value("dropped/acl_denied");
+        let downgraded = snapshot_value("qos_downgraded");
+        let no_subscribers = snapshot_value("no_subscribers");
+
+        metrics_message_dropped(MessageDropReason::Expired, "snapshot/a/b");
+        metrics_message_dropped(MessageDropReason::Expired, "snapshot/c");
+        metrics_message_dropped(MessageDropReason::AclDenied, "snapshot/a/b");
+        metrics_message_qos_downgraded("snapshot/a/b");
+        metrics_message_no_subscribers("snapshot/a/b");
+
+        assert_eq!(snapshot_value("dropped") - dropped, 3);
+        assert_eq!(snapshot_value("dropped/expired") - expired, 2);
+        assert_eq!(snapshot_value("dropped/acl_denied") - acl_denied, 1);
+        assert_eq!(snapshot_value("qos_downgraded") - downgraded, 1);
+        // Stored messages without subscribers are not drops
+        assert_eq!(snapshot_value("no_subscribers") - no_subscribers, 1);
+    }
+}
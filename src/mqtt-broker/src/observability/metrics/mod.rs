@@ -14,6 +14,7 @@
 
 pub mod auth;
 pub mod events;
+pub mod messages;
 pub mod packets;
 pub mod publish;
 pub mod server;
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use lazy_static::lazy_static;
+use prometheus::core::Collector;
 use prometheus::{register_int_gauge_vec, IntGaugeVec};
 use protocol::mqtt::codec::{calc_mqtt_packet_size, MqttPacketWrapper};
 use protocol::mqtt::common::{ConnectReturnCode, MqttPacket, QoS};
@@ -411,7 +412,7 @@ pub fn record_sent_metrics(packet_wrapper: &MqttPacketWrapper, network_type: Str
         MqttPacket::Disconnect(_, _) => PACKETS_DISCONNECT_SENT
             .with_label_values(&[&network_type, &qos_str])
             .inc(),
-        MqttPacket::Auth(_, _) => PACKETS_CONNACK_SENT
+        MqttPacket::Auth(_, _) => PACKETS_AUTH_SENT
             .with_label_values(&[&network_type, &qos_str])
             .inc(),
         _ => unreachable!("This branch only matches for packets could not be sent"),
@@ -428,10 +429,113 @@ pub fn record_retain_sent_metrics(qos: QoS) {
     RETAIN_PACKETS_SEND.with_label_values(&[&qos_str]).inc();
 }
 
+// Packet counters summed over all label values, keyed by the name used on
+// $SYS/brokers/${node}/metrics/packets/${name}
+pub fn packets_metrics_snapshot() -> Vec<(&'static str, i64)> {
+    vec![
+        ("received", gauge_vec_total(&PACKETS_RECEIVED)),
+        ("sent", gauge_vec_total(&PACKETS_SENT)),
+        ("received/error", gauge_vec_total(&PACKETS_RECEIVED_ERROR)),
+        (
+            "connect/received",
+            gauge_vec_total(&PACKETS_CONNECT_RECEIVED),
+        ),
+        ("connack/sent", gauge_vec_total(&PACKETS_CONNACK_SENT)),
+        (
+            "connack/auth_error",
+            gauge_vec_total(&PACKETS_CONNACK_AUTH_ERROR),
+        ),
+        (
+            "publish/received",
+            gauge_vec_total(&PACKETS_PUBLISH_RECEIVED),
+        ),
+        ("publish/sent", gauge_vec_total(&PACKETS_PUBLISH_SENT)),
+        ("puback/received", gauge_vec_total(&PACKETS_PUBACK_RECEIVED)),
+        ("puback/sent", gauge_vec_total(&PACKETS_PUBACK_SENT)),
+        ("pubrec/received", gauge_vec_total(&PACKETS_PUBREC_RECEIVED)),
+        ("pubrec/sent", gauge_vec_total(&PACKETS_PUBREC_SENT)),
+        ("pubrel/received", gauge_vec_total(&PACKETS_PUBREL_RECEIVED)),
+        ("pubrel/sent", gauge_vec_total(&PACKETS_PUBREL_SENT)),
+        (
+            "pubcomp/received",
+            gauge_vec_total(&PACKETS_PUBCOMP_RECEIVED),
+        ),
+        ("pubcomp/sent", gauge_vec_total(&PACKETS_PUBCOMP_SENT)),
+        (
+            "subscribe/received",
+            gauge_vec_total(&PACKETS_SUBSCRIBLE_RECEIVED),
+        ),
+        ("suback/sent", gauge_vec_total(&PACKETS_SUBACK_SENT)),
+        (
+            "unsubscribe/received",
+            gauge_vec_total(&PACKETS_UNSUBSCRIBLE_RECEIVED),
+        ),
+        ("unsuback/sent", gauge_vec_total(&PACKETS_UNSUBACK_SENT)),
+        (
+            "pingreq/received",
+            gauge_vec_total(&PACKETS_PINGREQ_RECEIVED),
+        ),
+        ("pingresp/sent", gauge_vec_total(&PACKETS_PINGRESP_SENT)),
+        (
+            "disconnect/received",
+            gauge_vec_total(&PACKETS_DISCONNECT_RECEIVED),
+        ),
+        ("disconnect/sent", gauge_vec_total(&PACKETS_DISCONNECT_SENT)),
+        ("auth/received", gauge_vec_total(&PACKETS_AUTH_RECEIVED)),
+        ("auth/sent", gauge_vec_total(&PACKETS_AUTH_SENT)),
+    ]
+}
+
+// Byte counters keyed by the name used on $SYS/brokers/${node}/metrics/bytes/${name}
+pub fn bytes_metrics_snapshot() -> Vec<(&'static str, i64)> {
+    vec![
+        ("received", gauge_vec_total(&BYTES_RECEIVED)),
+        ("sent", gauge_vec_total(&BYTES_SENT)),
+    ]
+}
+
+pub(crate) fn gauge_vec_total(gauge: &IntGaugeVec) -> i64 {
+    let mut total = 0;
+    for family in gauge.collect() {
+        for metric in family.get_metric() {
+            total += metric.get_gauge().get_value() as i64;
+        }
+    }
+    total
+}
+
 #[cfg(test)]
 mod tests {
     use protocol::mqtt::codec::{calc_mqtt_packet_size, MqttPacketWrapper};
-    use protocol::mqtt::common::{MqttPacket, UnsubAck};
+    use protocol::mqtt::common::{MqttPacket, PingResp, UnsubAck};
+
+    use super::{bytes_metrics_snapshot, packets_metrics_snapshot, record_sent_metrics};
+
+    fn snapshot_value(snapshot: &[(&'static str, i64)], name: &str) -> i64 {
+        snapshot
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| *value)
+            .unwrap()
+    }
+
+    #[test]
+    fn packets_metrics_snapshot_test() {
+        let pingresp = snapshot_value(&packets_metrics_snapshot(), "pingresp/sent");
+        let sent = snapshot_value(&packets_metrics_snapshot(), "sent");
+        let bytes = snapshot_value(&bytes_metrics_snapshot(), "sent");
+
+        let packet_wrapper = MqttPacketWrapper {
+            protocol_version: 4,
+            packet: MqttPacket::PingResp(PingResp),
+        };
+        record_sent_metrics(&packet_wrapper, "tcp".to_string());
+
+        let snapshot = packets_metrics_snapshot();
+        assert!(snapshot_value(&snapshot, "pingresp/sent") > pingresp);
+        assert!(snapshot_value(&snapshot, "sent") > sent);
+        assert!(snapshot_value(&bytes_metrics_snapshot(), "sent") > bytes);
+    }
 
     #[test]
     fn calc_mqtt_packet_size_test() {
@@ -11,3 +11,38 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use lazy_static::lazy_static;
+use prometheus::{register_int_counter, IntCounter};
+
+lazy_static! {
+    // Number of messages that could not be written to the message storage
+    static ref PUBLISH_STORAGE_WRITE_FAILURE: IntCounter = register_int_counter!(
+        "publish_storage_write_failure",
+        "Number of messages that could not be written to the message storage"
+    )
+    .unwrap();
+
+    // Number of deliveries slower than the slow subscription threshold
+    static ref PUBLISH_SLOW_SUBSCRIBE: IntCounter = register_int_counter!(
+        "publish_slow_subscribe",
+        "Number of deliveries slower than the slow subscription threshold"
+    )
+    .unwrap();
+}
+
+pub fn metrics_storage_write_failure_incr() {
+    PUBLISH_STORAGE_WRITE_FAILURE.inc();
+}
+
+pub fn storage_write_failure_num() -> u64 {
+    PUBLISH_STORAGE_WRITE_FAILURE.get()
+}
+
+pub fn metrics_slow_subscribe_incr() {
+    PUBLISH_SLOW_SUBSCRIBE.inc();
+}
+
+pub fn slow_subscribe_num() -> u64 {
+    PUBLISH_SLOW_SUBSCRIBE.get()
+}
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use lazy_static::lazy_static;
+use prometheus::core::Collector;
 use prometheus::{register_int_gauge_vec, IntGaugeVec};
 
 use crate::handler::constant::{METRICS_KEY_LABEL_NAME, METRICS_KEY_TYPE_NAME};
@@ -37,3 +38,32 @@ pub fn metrics_response_queue(label: &str, len: usize) {
         .with_label_values(&[label, "response"])
         .set(len as i64);
 }
+
+// Current length of every network queue, keyed by "${label}/${type}"
+pub fn network_queue_snapshot() -> Vec<(String, i64)> {
+    let mut results = Vec::new();
+    for family in BROKER_NETWORK_QUEUE_NUM.collect() {
+        for metric in family.get_metric() {
+            let name = metric
+                .get_label()
+                .iter()
+                .map(|label| label.get_value())
+                .collect::<Vec<&str>>()
+                .join("/");
+            results.push((name, metric.get_gauge().get_value() as i64));
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{metrics_response_queue, network_queue_snapshot};
+
+    #[test]
+    fn network_queue_snapshot_test() {
+        metrics_response_queue("handler-test", 3);
+        let snapshot = network_queue_snapshot();
+        assert!(snapshot.contains(&("handler-test/response".to_string(), 3)));
+    }
+}
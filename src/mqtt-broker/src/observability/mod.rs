@@ -14,17 +14,22 @@
 
 use std::sync::Arc;
 
+use common_base::config::broker_mqtt::broker_mqtt_conf;
 use grpc_clients::pool::ClientPool;
 use storage_adapter::storage::StorageAdapter;
+use system_topic::sysmon::SystemMonitor;
 use system_topic::SystemTopic;
 use tokio::sync::broadcast;
+use warn::AlarmManager;
 
 use crate::handler::cache::CacheManager;
 
 pub mod metrics;
 pub mod slow;
 pub mod system_topic;
+pub mod trace;
 pub mod warn;
+pub mod webhook;
 
 pub async fn start_opservability<S>(
     cache_manager: Arc<CacheManager>,
@@ -40,7 +45,43 @@ pub async fn start_opservability<S>(
         client_pool.clone(),
     );
 
+    let system_monitor = SystemMonitor::new(
+        cache_manager.clone(),
+        message_storage_adapter.clone(),
+        client_pool.clone(),
+    );
+
+    let alarm_manager = AlarmManager::new(
+        cache_manager.clone(),
+        message_storage_adapter.clone(),
+        client_pool.clone(),
+    );
+
+    let conf = broker_mqtt_conf();
+    cache_manager
+        .webhook_manager
+        .start(conf.broker_id, &conf.webhook, stop_send.clone());
+
+    let raw_stop_send = stop_send.clone();
+    tokio::spawn(async move {
+        system_topic.start_thread(raw_stop_send).await;
+    });
+
+    let raw_stop_send = stop_send.clone();
+    tokio::spawn(async move {
+        alarm_manager.start_thread(raw_stop_send).await;
+    });
+
+    let raw_stop_send = stop_send.clone();
+    let raw_cache_manager = cache_manager.clone();
+    tokio::spawn(async move {
+        raw_cache_manager
+            .trace_manager
+            .start_sweep_thread(raw_stop_send)
+            .await;
+    });
+
     tokio::spawn(async move {
-        system_topic.start_thread(stop_send).await;
+        system_monitor.start_thread(stop_send).await;
     });
 }
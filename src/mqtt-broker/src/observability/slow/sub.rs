@@ -12,156 +12,453 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::VecDeque;
-use std::fs::File;
-use std::path::PathBuf;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
 
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use common_base::enum_type::common_enum::SortType;
 use common_base::tools::{get_local_ip, now_second};
-use grep::matcher::Matcher;
-use grep::regex::RegexMatcher;
-use grep::searcher::sinks::UTF8;
-use grep::searcher::Searcher;
+use futures::future::join_all;
+use grpc_clients::mqtt::inner::call::broker_mqtt_list_node_slow_subscribe;
+use grpc_clients::pool::ClientPool;
 use log::info;
+use metadata_struct::mqtt::cluster::MqttClusterDynamicSlowSub;
 use protocol::broker_mqtt::broker_mqtt_admin::ListSlowSubscribeRequest;
+use protocol::broker_mqtt::broker_mqtt_inner::ListNodeSlowSubscribeRequest;
 use serde::{Deserialize, Serialize};
 
+use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
+use crate::observability::metrics::publish::metrics_slow_subscribe_incr;
+use crate::storage::cluster::ClusterStorage;
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Default, Clone)]
 pub struct SlowSubData {
     pub(crate) sub_name: String,
     pub(crate) client_id: String,
     pub(crate) topic: String,
+    // Whole latency, internal_ms + response_ms
     pub(crate) time_ms: u64,
+    #[serde(default)]
+    pub(crate) internal_ms: u64,
+    #[serde(default)]
+    pub(crate) response_ms: u64,
     pub(crate) node_info: String,
     pub(crate) create_time: u64,
 }
 
 impl SlowSubData {
-    pub fn build(sub_name: String, client_id: String, topic_name: String, time_ms: u64) -> Self {
+    pub fn build(
+        sub_name: String,
+        client_id: String,
+        topic_name: String,
+        internal_ms: u64,
+        response_ms: u64,
+    ) -> Self {
         let ip = get_local_ip();
         let node_info = format!("RobustMQ-MQTT@{}", ip);
         SlowSubData {
             sub_name,
             client_id,
             topic: topic_name,
-            time_ms,
+            time_ms: internal_ms + response_ms,
+            internal_ms,
+            response_ms,
             node_info,
             create_time: now_second(),
         }
     }
+
+    fn key(&self) -> String {
+        format!("{}/{}/{}", self.client_id, self.sub_name, self.topic)
+    }
+
+    fn is_match(&self, sub_name: &str, client_id: &str, topic: &str) -> bool {
+        self.sub_name.contains(sub_name)
+            && self.client_id.contains(client_id)
+            && self.topic.contains(topic)
+    }
 }
 
-pub fn record_slow_sub_data(slow_data: SlowSubData, whole_ms: u64) -> Result<(), MqttBrokerError> {
-    let data = serde_json::to_string(&slow_data)?;
+pub fn is_slow_sub(config: &MqttClusterDynamicSlowSub, data: &SlowSubData) -> bool {
+    data.time_ms > config.whole_ms
+        || (config.internal_ms > 0 && data.internal_ms > config.internal_ms as u64)
+        || (config.response_ms > 0 && data.response_ms > config.response_ms as u64)
+}
 
-    if slow_data.time_ms > whole_ms {
-        info!("{}", data);
+pub fn record_slow_sub_data(
+    cache_manager: &Arc<CacheManager>,
+    slow_data: SlowSubData,
+) -> Result<(), MqttBrokerError> {
+    let config = cache_manager.get_slow_sub_config();
+    if !is_slow_sub(&config, &slow_data) {
+        return Ok(());
     }
 
+    metrics_slow_subscribe_incr();
+    info!("{}", serde_json::to_string(&slow_data)?);
+    cache_manager.slow_sub_store.record(
+        slow_data,
+        config.top_k as usize,
+        config.expire_interval_sec,
+    );
     Ok(())
 }
 
-pub fn connect_regex_pattern(sub_name: String, client_id: String, topic: String) -> String {
-    let mut pattern: String = String::new();
-    pattern += "\\{";
-    if !sub_name.is_empty() {
-        pattern += ".*";
-        pattern += sub_name.as_str();
+// The records plus two min-heaps over them, one by latency to find the record a slower
+// delivery displaces and one by create time to find the expired ones. A replaced or
+// removed record leaves its heap entries behind, they are skipped when they reach the
+// top and dropped when the heaps are rebuilt.
+#[derive(Default)]
+struct SlowSubTopK {
+    // (client_id/sub_name/topic, SlowSubData)
+    records: HashMap<String, SlowSubData>,
+    // (time_ms, create_time, key)
+    by_latency: BinaryHeap<Reverse<(u64, u64, String)>>,
+    // (create_time, time_ms, key)
+    by_create_time: BinaryHeap<Reverse<(u64, u64, String)>>,
+}
+
+impl SlowSubTopK {
+    fn is_current(&self, key: &str, time_ms: u64, create_time: u64) -> bool {
+        self.records
+            .get(key)
+            .map(|data| data.time_ms == time_ms && data.create_time == create_time)
+            .unwrap_or(false)
+    }
+
+    fn insert(&mut self, key: String, data: SlowSubData) {
+        self.by_latency
+            .push(Reverse((data.time_ms, data.create_time, key.clone())));
+        self.by_create_time
+            .push(Reverse((data.create_time, data.time_ms, key.clone())));
+        self.records.insert(key, data);
+        if self.by_latency.len() > 2 * self.records.len() + 16 {
+            self.rebuild();
+        }
     }
-    if !client_id.is_empty() {
-        pattern += ".*";
-        pattern += client_id.as_str();
+
+    fn rebuild(&mut self) {
+        self.by_latency = self
+            .records
+            .iter()
+            .map(|(key, data)| Reverse((data.time_ms, data.create_time, key.clone())))
+            .collect();
+        self.by_create_time = self
+            .records
+            .iter()
+            .map(|(key, data)| Reverse((data.create_time, data.time_ms, key.clone())))
+            .collect();
     }
-    if !topic.is_empty() {
-        pattern += ".*";
-        pattern += topic.as_str();
+
+    // The fastest record, stale heap entries on top are dropped on the way
+    fn fastest(&mut self) -> Option<(u64, String)> {
+        while let Some(Reverse((time_ms, create_time, key))) = self.by_latency.peek() {
+            if self.is_current(key, *time_ms, *create_time) {
+                return Some((*time_ms, key.clone()));
+            }
+            self.by_latency.pop();
+        }
+        None
+    }
+
+    fn expire(&mut self, now: u64, expire_interval_sec: u64) {
+        if expire_interval_sec == 0 {
+            return;
+        }
+        while let Some(Reverse((create_time, time_ms, key))) = self.by_create_time.peek() {
+            if create_time + expire_interval_sec > now {
+                break;
+            }
+            if self.is_current(key, *time_ms, *create_time) {
+                self.records.remove(key);
+            }
+            self.by_create_time.pop();
+        }
     }
-    pattern += ".*";
-    pattern += "\\}";
-    pattern
 }
 
-pub fn read_slow_sub_record(
-    search_options: ListSlowSubscribeRequest,
-    path: PathBuf,
-) -> Result<VecDeque<String>, MqttBrokerError> {
-    let regex_pattern = connect_regex_pattern(
-        search_options.sub_name,
-        search_options.client_id,
-        search_options.topic,
-    );
-    let file = File::open(path);
-    let mut matches_queue: VecDeque<String> = VecDeque::new();
-    if file.is_ok() {
-        let matcher = RegexMatcher::new(regex_pattern.as_str())?;
-        Searcher::new().search_file(
-            &matcher,
-            &file.unwrap(),
-            UTF8(|_lnum, line| {
-                let match_byte = matcher.find(line.as_bytes())?.unwrap();
-                if matches_queue.len() == search_options.list as usize {
-                    matches_queue.pop_front();
-                    matches_queue.push_back(line[match_byte].to_string())
+/// The slowest deliveries seen by this broker, at most one record per subscription,
+/// bounded to the top K by latency and evicted once older than the expire interval.
+#[derive(Default, Clone)]
+pub struct SlowSubStore {
+    top_k: Arc<Mutex<SlowSubTopK>>,
+}
+
+impl SlowSubStore {
+    pub fn new() -> Self {
+        SlowSubStore::default()
+    }
+
+    fn top_k(&self) -> MutexGuard<'_, SlowSubTopK> {
+        match self.top_k.lock() {
+            Ok(top_k) => top_k,
+            Err(e) => e.into_inner(),
+        }
+    }
+
+    pub fn record(&self, data: SlowSubData, top_k: usize, expire_interval_sec: u64) {
+        if top_k == 0 {
+            return;
+        }
+        let mut store = self.top_k();
+        store.expire(now_second(), expire_interval_sec);
+
+        let key = data.key();
+        if let Some(current) = store.records.get(&key) {
+            if current.time_ms >= data.time_ms {
+                return;
+            }
+        } else {
+            // top_k may have been lowered since the last record
+            while store.records.len() >= top_k {
+                if let Some((fastest_ms, fastest_key)) = store.fastest() {
+                    if fastest_ms >= data.time_ms {
+                        return;
+                    }
+                    store.records.remove(&fastest_key);
                 } else {
-                    matches_queue.push_back(line[match_byte].to_string());
+                    break;
+                }
+            }
+        }
+        store.insert(key, data);
+    }
+
+    pub fn list(
+        &self,
+        sub_name: &str,
+        client_id: &str,
+        topic: &str,
+        limit: usize,
+        expire_interval_sec: u64,
+    ) -> Vec<SlowSubData> {
+        let mut results: Vec<SlowSubData> = {
+            let mut store = self.top_k();
+            store.expire(now_second(), expire_interval_sec);
+            store
+                .records
+                .values()
+                .filter(|data| data.is_match(sub_name, client_id, topic))
+                .cloned()
+                .collect()
+        };
+        sort_and_limit(&mut results, limit);
+        results
+    }
+}
+
+// Slowest first
+fn sort_and_limit(list: &mut Vec<SlowSubData>, limit: usize) {
+    list.sort_by(|a, b| b.time_ms.cmp(&a.time_ms));
+    if limit > 0 {
+        list.truncate(limit);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ClusterSlowSubList {
+    pub list: Vec<SlowSubData>,
+    // (node_id, error) of the brokers whose records are missing from the list
+    pub node_errors: Vec<(u64, String)>,
+}
+
+/// Collect the slow subscription records of every broker in the cluster and merge them
+/// into one ranking. The brokers are queried concurrently, the ones that fail are
+/// reported in `node_errors` next to the records of the others.
+pub async fn list_cluster_slow_sub(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    request: ListSlowSubscribeRequest,
+) -> Result<ClusterSlowSubList, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let expire_interval_sec = cache_manager.get_slow_sub_config().expire_interval_sec;
+    let limit = request.list as usize;
+
+    let mut results = cache_manager.slow_sub_store.list(
+        &request.sub_name,
+        &request.client_id,
+        &request.topic,
+        limit,
+        expire_interval_sec,
+    );
+
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let nodes = cluster_storage
+        .node_list()
+        .await?
+        .into_iter()
+        .filter(|node| node.node_id != conf.broker_id);
+    let replies = join_all(nodes.map(|node| {
+        let node_request = ListNodeSlowSubscribeRequest {
+            cluster_name: conf.cluster_name.clone(),
+            sub_name: request.sub_name.clone(),
+            client_id: request.client_id.clone(),
+            topic: request.topic.clone(),
+            limit: request.list,
+        };
+        async move {
+            let reply = broker_mqtt_list_node_slow_subscribe(
+                client_pool,
+                &[node.node_inner_addr],
+                node_request,
+            )
+            .await;
+            (node.node_id, reply)
+        }
+    }))
+    .await;
+
+    let mut node_errors = Vec::new();
+    for (node_id, reply) in replies {
+        let raws = match reply {
+            Ok(reply) => reply.slow_subscribes,
+            Err(e) => {
+                node_errors.push((node_id, e.to_string()));
+                continue;
+            }
+        };
+        for raw in raws {
+            match serde_json::from_slice::<SlowSubData>(&raw) {
+                Ok(data) => results.push(data),
+                Err(e) => {
+                    node_errors.push((node_id, e.to_string()));
+                    break;
                 }
-                Ok(true)
-            }),
-        )?;
+            }
+        }
     }
 
-    Ok(matches_queue)
+    sort_and_limit(&mut results, limit);
+    if let Ok(SortType::ASC) = SortType::from_str(&request.sort) {
+        results.reverse();
+    }
+    Ok(ClusterSlowSubList {
+        list: results,
+        node_errors,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn build_data(client_id: &str, time_ms: u64) -> SlowSubData {
+        SlowSubData::build(
+            "sub".to_string(),
+            client_id.to_string(),
+            "t1".to_string(),
+            time_ms,
+            0,
+        )
+    }
+
     #[test]
-    fn test_regex_pattern_param_is_empty() {
-        let sub_name = "".to_string();
-        let client_id = "".to_string();
-        let topic_id = "".to_string();
+    fn is_slow_sub_test() {
+        let config = MqttClusterDynamicSlowSub {
+            enable: true,
+            whole_ms: 500,
+            internal_ms: 100,
+            response_ms: 0,
+            ..Default::default()
+        };
+        let data = SlowSubData::build("s".to_string(), "c".to_string(), "t".to_string(), 50, 40);
+        assert!(!is_slow_sub(&config, &data));
 
-        let regex_pattern = connect_regex_pattern(sub_name, client_id, topic_id);
+        let data = SlowSubData::build("s".to_string(), "c".to_string(), "t".to_string(), 150, 0);
+        assert!(is_slow_sub(&config, &data));
 
-        assert_eq!(regex_pattern, "\\{.*\\}");
+        // response_ms is disabled, only the whole latency counts
+        let data = SlowSubData::build("s".to_string(), "c".to_string(), "t".to_string(), 0, 400);
+        assert!(!is_slow_sub(&config, &data));
+        let data = SlowSubData::build("s".to_string(), "c".to_string(), "t".to_string(), 0, 600);
+        assert!(is_slow_sub(&config, &data));
     }
 
     #[test]
-    fn test_regex_pattern_param_is_one() {
-        let sub_name = "sub_name_test".to_string();
-        let client_id = "".to_string();
-        let topic_id = "".to_string();
+    fn slow_sub_store_top_k_test() {
+        let store = SlowSubStore::new();
+        store.record(build_data("c1", 100), 2, 0);
+        store.record(build_data("c2", 300), 2, 0);
 
-        let regex_pattern = connect_regex_pattern(sub_name, client_id, topic_id);
+        // Faster than every record of a full store
+        store.record(build_data("c3", 50), 2, 0);
+        let list = store.list("", "", "", 0, 0);
+        assert_eq!(
+            list.iter()
+                .map(|d| d.client_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["c2", "c1"]
+        );
 
-        assert_eq!(regex_pattern, "\\{.*sub_name_test.*\\}");
+        // Displaces the fastest record
+        store.record(build_data("c3", 200), 2, 0);
+        let list = store.list("", "", "", 0, 0);
+        assert_eq!(
+            list.iter()
+                .map(|d| d.client_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["c2", "c3"]
+        );
+
+        // One record per subscription, keeping the slowest delivery
+        store.record(build_data("c2", 100), 2, 0);
+        store.record(build_data("c3", 400), 2, 0);
+        let list = store.list("", "", "", 0, 0);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].client_id, "c3");
+        assert_eq!(list[0].time_ms, 400);
+        assert_eq!(list[1].time_ms, 300);
+
+        assert_eq!(store.list("", "c2", "", 0, 0).len(), 1);
+        assert_eq!(store.list("", "", "", 1, 0).len(), 1);
+        assert!(store.list("other", "", "", 0, 0).is_empty());
     }
 
     #[test]
-    fn test_regex_pattern_param_is_two() {
-        let sub_name = "sub_name_test".to_string();
-        let client_id = "client_id_test".to_string();
-        let topic_id = "".to_string();
+    fn slow_sub_store_expire_test() {
+        let store = SlowSubStore::new();
+        let mut data = build_data("c1", 100);
+        data.create_time = now_second() - 100;
+        store.record(data, 10, 0);
+        store.record(build_data("c2", 50), 10, 0);
+        assert_eq!(store.list("", "", "", 0, 0).len(), 2);
 
-        let regex_pattern = connect_regex_pattern(sub_name, client_id, topic_id);
-
-        assert_eq!(regex_pattern, "\\{.*sub_name_test.*client_id_test.*\\}");
+        let list = store.list("", "", "", 0, 60);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].client_id, "c2");
     }
 
     #[test]
-    fn test_regex_pattern_param_is_three() {
-        let sub_name = "sub_name_test".to_string();
-        let client_id = "client_id_test".to_string();
-        let topic_id = "topic_id_test".to_string();
-
-        let regex_pattern = connect_regex_pattern(sub_name, client_id, topic_id);
+    fn slow_sub_store_replace_test() {
+        let store = SlowSubStore::new();
+        store.record(build_data("c1", 100), 2, 0);
+        store.record(build_data("c1", 500), 2, 0);
+        store.record(build_data("c2", 200), 2, 0);
 
+        // The stale latency entry of c1 must not make c1 the record to displace
+        store.record(build_data("c3", 300), 2, 0);
+        let list = store.list("", "", "", 0, 0);
         assert_eq!(
-            regex_pattern,
-            "\\{.*sub_name_test.*client_id_test.*topic_id_test.*\\}"
+            list.iter()
+                .map(|d| d.client_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["c1", "c3"]
         );
+
+        // Lowering top_k evicts down to the new bound
+        store.record(build_data("c4", 400), 1, 0);
+        let list = store.list("", "", "", 0, 0);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].client_id, "c1");
+
+        for i in 0..100 {
+            store.record(build_data("c1", 500 + i), 1, 0);
+        }
+        let store = store.top_k();
+        assert_eq!(store.records.len(), 1);
+        assert!(store.by_latency.len() <= 2 * store.records.len() + 16);
     }
 }
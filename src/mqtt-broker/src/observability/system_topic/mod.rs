@@ -17,9 +17,12 @@ use std::time::Duration;
 
 use broker::report_broker_info;
 use common_base::tools::get_local_ip;
+use dashmap::DashMap;
 use grpc_clients::pool::ClientPool;
 use log::{debug, error};
 use metadata_struct::adapter::record::Record;
+use packet::report_packet_info;
+use stats::report_stats_info;
 use storage_adapter::storage::StorageAdapter;
 use tokio::select;
 use tokio::sync::broadcast;
@@ -36,6 +39,24 @@ pub const SYSTEM_TOPIC_BROKERS_UPTIME: &str = "$SYS/brokers/${node}/uptime";
 pub const SYSTEM_TOPIC_BROKERS_DATETIME: &str = "$SYS/brokers/${node}/datetime";
 pub const SYSTEM_TOPIC_BROKERS_SYSDESCR: &str = "$SYS/brokers/${node}/sysdescr";
 
+// Statistics, published as ${prefix}/${name}/count and ${prefix}/${name}/max
+pub const SYSTEM_TOPIC_BROKERS_STATS: &str = "$SYS/brokers/${node}/stats";
+
+// Metrics, published as ${prefix}/${name}
+pub const SYSTEM_TOPIC_BROKERS_METRICS_PACKETS: &str = "$SYS/brokers/${node}/metrics/packets";
+pub const SYSTEM_TOPIC_BROKERS_METRICS_BYTES: &str = "$SYS/brokers/${node}/metrics/bytes";
+pub const SYSTEM_TOPIC_BROKERS_METRICS_MESSAGES: &str = "$SYS/brokers/${node}/metrics/messages";
+
+// System monitor alerts
+pub const SYSTEM_TOPIC_BROKERS_SYSMON_LONG_SCHEDULE: &str =
+    "$SYS/brokers/${node}/sysmon/long_schedule";
+pub const SYSTEM_TOPIC_BROKERS_SYSMON_BUSY_PORT: &str = "$SYS/brokers/${node}/sysmon/busy_port";
+pub const SYSTEM_TOPIC_BROKERS_SYSMON_HIGH_MEMORY: &str = "$SYS/brokers/${node}/sysmon/high_memory";
+
+// Alarms
+pub const SYSTEM_TOPIC_BROKERS_ALARMS_ACTIVATE: &str = "$SYS/brokers/${node}/alarms/activate";
+pub const SYSTEM_TOPIC_BROKERS_ALARMS_DEACTIVATE: &str = "$SYS/brokers/${node}/alarms/deactivate";
+
 // Event
 pub const SYSTEM_TOPIC_BROKERS_CONNECTED: &str =
     "$SYS/brokers/${node}/clients/${clientid}/connected";
@@ -57,6 +78,8 @@ pub struct SystemTopic<S> {
     pub metadata_cache: Arc<CacheManager>,
     pub message_storage_adapter: Arc<S>,
     pub client_pool: Arc<ClientPool>,
+    // (stats name, max value since the broker started)
+    stats_max: DashMap<String, u64>,
 }
 
 impl<S> SystemTopic<S>
@@ -72,6 +95,7 @@ where
             metadata_cache,
             message_storage_adapter,
             client_pool,
+            stats_max: DashMap::with_capacity(8),
         }
     }
 
@@ -101,6 +125,19 @@ where
             &self.message_storage_adapter,
         )
         .await;
+        report_stats_info(
+            &self.client_pool,
+            &self.metadata_cache,
+            &self.message_storage_adapter,
+            &self.stats_max,
+        )
+        .await;
+        report_packet_info(
+            &self.client_pool,
+            &self.metadata_cache,
+            &self.message_storage_adapter,
+        )
+        .await;
     }
 
     pub async fn try_init_system_topic(&self) {
@@ -137,7 +174,7 @@ where
     }
 }
 
-fn replace_topic_name(mut topic_name: String) -> String {
+pub(crate) fn replace_topic_name(mut topic_name: String) -> String {
     if topic_name.contains("${node}") {
         let local_ip = get_local_ip();
         topic_name = topic_name.replace("${node}", &local_ip)
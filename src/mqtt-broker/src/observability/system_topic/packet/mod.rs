@@ -11,3 +11,55 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::sync::Arc;
+
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::message::MqttMessage;
+use storage_adapter::storage::StorageAdapter;
+
+use super::{
+    replace_topic_name, write_topic_data, SYSTEM_TOPIC_BROKERS_METRICS_BYTES,
+    SYSTEM_TOPIC_BROKERS_METRICS_MESSAGES, SYSTEM_TOPIC_BROKERS_METRICS_PACKETS,
+};
+use crate::handler::cache::CacheManager;
+use crate::observability::metrics::messages::messages_metrics_snapshot;
+use crate::observability::metrics::packets::{bytes_metrics_snapshot, packets_metrics_snapshot};
+
+pub(crate) async fn report_packet_info<S>(
+    client_pool: &Arc<ClientPool>,
+    metadata_cache: &Arc<CacheManager>,
+    message_storage_adapter: &Arc<S>,
+) where
+    S: StorageAdapter + Clone + Send + Sync + 'static,
+{
+    let metrics = [
+        (
+            SYSTEM_TOPIC_BROKERS_METRICS_PACKETS,
+            packets_metrics_snapshot(),
+        ),
+        (SYSTEM_TOPIC_BROKERS_METRICS_BYTES, bytes_metrics_snapshot()),
+        (
+            SYSTEM_TOPIC_BROKERS_METRICS_MESSAGES,
+            messages_metrics_snapshot(),
+        ),
+    ];
+
+    for (prefix, snapshot) in metrics {
+        for (name, value) in snapshot {
+            let topic_name = replace_topic_name(format!("{}/{}", prefix, name));
+            if let Some(record) =
+                MqttMessage::build_system_topic_message(topic_name.clone(), value.to_string())
+            {
+                write_topic_data(
+                    message_storage_adapter,
+                    metadata_cache,
+                    client_pool,
+                    topic_name,
+                    record,
+                )
+                .await;
+            }
+        }
+    }
+}
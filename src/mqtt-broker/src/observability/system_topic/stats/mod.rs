@@ -11,3 +11,148 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::message::MqttMessage;
+use storage_adapter::storage::StorageAdapter;
+
+use super::{replace_topic_name, write_topic_data, SYSTEM_TOPIC_BROKERS_STATS};
+use crate::handler::cache::CacheManager;
+use crate::subscribe::sub_common::{is_queue_sub, is_share_sub};
+
+pub(crate) async fn report_stats_info<S>(
+    client_pool: &Arc<ClientPool>,
+    metadata_cache: &Arc<CacheManager>,
+    message_storage_adapter: &Arc<S>,
+    stats_max: &DashMap<String, u64>,
+) where
+    S: StorageAdapter + Clone + Send + Sync + 'static,
+{
+    for (name, count) in collect_stats(metadata_cache) {
+        let max = update_stats_max(stats_max, name, count);
+        for (suffix, value) in [("count", count), ("max", max)] {
+            let topic_name = replace_topic_name(format!(
+                "{}/{}/{}",
+                SYSTEM_TOPIC_BROKERS_STATS, name, suffix
+            ));
+            if let Some(record) =
+                MqttMessage::build_system_topic_message(topic_name.clone(), value.to_string())
+            {
+                write_topic_data(
+                    message_storage_adapter,
+                    metadata_cache,
+                    client_pool,
+                    topic_name,
+                    record,
+                )
+                .await;
+            }
+        }
+    }
+}
+
+// Current value of every statistic published under $SYS/brokers/${node}/stats
+pub(crate) fn collect_stats(metadata_cache: &Arc<CacheManager>) -> Vec<(&'static str, u64)> {
+    let mut subscriptions = 0;
+    let mut shared_subscriptions = 0;
+    for sub_list in metadata_cache.subscribe_filter.iter() {
+        for path in sub_list.iter() {
+            subscriptions += 1;
+            if is_share_sub(path.key().clone()) || is_queue_sub(path.key().clone()) {
+                shared_subscriptions += 1;
+            }
+        }
+    }
+
+    vec![
+        ("connections", metadata_cache.connection_info.len() as u64),
+        ("sessions", metadata_cache.session_info.len() as u64),
+        ("topics", metadata_cache.topic_info.len() as u64),
+        ("subscriptions", subscriptions),
+        ("subscriptions/shared", shared_subscriptions),
+        ("retained", metadata_cache.retain_message_index.len() as u64),
+    ]
+}
+
+// Records the peak value of a statistic since the broker started and returns it
+fn update_stats_max(stats_max: &DashMap<String, u64>, name: &str, value: u64) -> u64 {
+    let mut max = stats_max.entry(name.to_string()).or_insert(value);
+    if value > *max {
+        *max = value;
+    }
+    *max
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use dashmap::DashMap;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::connection::MQTTConnection;
+    use metadata_struct::mqtt::session::MqttSession;
+    use protocol::mqtt::common::{Filter, MqttProtocol, QoS, RetainForwardRule, Subscribe};
+
+    use super::{collect_stats, update_stats_max};
+    use crate::handler::cache::CacheManager;
+
+    fn stats_value(stats: &[(&'static str, u64)], name: &str) -> u64 {
+        stats
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| *value)
+            .unwrap()
+    }
+
+    fn build_filter(path: &str) -> Filter {
+        Filter {
+            path: path.to_string(),
+            qos: QoS::AtMostOnce,
+            nolocal: false,
+            preserve_retain: false,
+            retain_forward_rule: RetainForwardRule::OnEverySubscribe,
+        }
+    }
+
+    #[test]
+    fn collect_stats_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.add_session("c1".to_string(), MqttSession::default());
+        cache_manager.add_connection(
+            1,
+            MQTTConnection {
+                client_id: "c1".to_string(),
+                ..Default::default()
+            },
+        );
+        cache_manager.add_client_subscribe(
+            "c1".to_string(),
+            MqttProtocol::Mqtt5,
+            Subscribe {
+                packet_identifier: 1,
+                filters: vec![build_filter("/a/b"), build_filter("$share/g1/a/b")],
+            },
+            None,
+        );
+
+        let stats = collect_stats(&cache_manager);
+        assert_eq!(stats_value(&stats, "connections"), 1);
+        assert_eq!(stats_value(&stats, "sessions"), 1);
+        assert_eq!(stats_value(&stats, "topics"), 0);
+        assert_eq!(stats_value(&stats, "subscriptions"), 2);
+        assert_eq!(stats_value(&stats, "subscriptions/shared"), 1);
+        assert_eq!(stats_value(&stats, "retained"), 0);
+    }
+
+    #[test]
+    fn update_stats_max_test() {
+        let stats_max = DashMap::new();
+        assert_eq!(update_stats_max(&stats_max, "connections", 3), 3);
+        assert_eq!(update_stats_max(&stats_max, "connections", 1), 3);
+        assert_eq!(update_stats_max(&stats_max, "connections", 5), 5);
+    }
+}
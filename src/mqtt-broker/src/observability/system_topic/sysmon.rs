@@ -11,3 +11,272 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::fs;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use common_base::tools::{get_local_ip, now_second};
+use dashmap::DashMap;
+use grpc_clients::pool::ClientPool;
+use log::{debug, error, warn};
+use metadata_struct::mqtt::cluster::MqttClusterDynamicSysmon;
+use metadata_struct::mqtt::message::MqttMessage;
+use serde::{Deserialize, Serialize};
+use storage_adapter::storage::StorageAdapter;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+
+use super::{
+    replace_topic_name, write_topic_data, SYSTEM_TOPIC_BROKERS_SYSMON_BUSY_PORT,
+    SYSTEM_TOPIC_BROKERS_SYSMON_HIGH_MEMORY, SYSTEM_TOPIC_BROKERS_SYSMON_LONG_SCHEDULE,
+};
+use crate::handler::cache::CacheManager;
+use crate::observability::metrics::server::network_queue_snapshot;
+
+const SYSMON_TICK: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SysmonAlert {
+    pub node: String,
+    pub kind: String,
+    pub resource: String,
+    pub value: u64,
+    pub threshold: u64,
+    pub create_time: u64,
+}
+
+// Watches the broker process and publishes an alert on $SYS/brokers/${node}/sysmon/*
+// when the runtime stalls, a network queue backs up or memory usage is high.
+pub struct SystemMonitor<S> {
+    metadata_cache: Arc<CacheManager>,
+    message_storage_adapter: Arc<S>,
+    client_pool: Arc<ClientPool>,
+    // (topic/resource, last alert time)
+    last_alert_time: DashMap<String, u64>,
+}
+
+impl<S> SystemMonitor<S>
+where
+    S: StorageAdapter + Clone + Send + Sync + 'static,
+{
+    pub fn new(
+        metadata_cache: Arc<CacheManager>,
+        message_storage_adapter: Arc<S>,
+        client_pool: Arc<ClientPool>,
+    ) -> Self {
+        SystemMonitor {
+            metadata_cache,
+            message_storage_adapter,
+            client_pool,
+            last_alert_time: DashMap::with_capacity(8),
+        }
+    }
+
+    pub async fn start_thread(&self, stop_send: broadcast::Sender<bool>) {
+        let mut stop_rx = stop_send.subscribe();
+        let mut last_tick = Instant::now();
+        loop {
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            debug!("System monitor thread stopped successfully");
+                            break;
+                        }
+                    }
+                }
+                _ = sleep(SYSMON_TICK) => {
+                    let elapsed = last_tick.elapsed();
+                    last_tick = Instant::now();
+                    self.check(elapsed).await;
+                }
+            }
+        }
+    }
+
+    async fn check(&self, elapsed: Duration) {
+        let config = self.metadata_cache.get_cluster_info().sysmon;
+        if !config.enable {
+            return;
+        }
+
+        if let Some(delay_ms) = long_schedule_delay(elapsed, SYSMON_TICK, config.long_schedule_ms) {
+            self.alert(
+                &config,
+                SYSTEM_TOPIC_BROKERS_SYSMON_LONG_SCHEDULE,
+                "runtime",
+                delay_ms,
+                config.long_schedule_ms,
+            )
+            .await;
+        }
+
+        for (queue, len) in busy_queues(network_queue_snapshot(), config.busy_queue_len) {
+            self.alert(
+                &config,
+                SYSTEM_TOPIC_BROKERS_SYSMON_BUSY_PORT,
+                &queue,
+                len,
+                config.busy_queue_len,
+            )
+            .await;
+        }
+
+        if let Some(percent) = memory_usage_percent() {
+            if percent >= config.high_memory_percent {
+                self.alert(
+                    &config,
+                    SYSTEM_TOPIC_BROKERS_SYSMON_HIGH_MEMORY,
+                    "process",
+                    percent,
+                    config.high_memory_percent,
+                )
+                .await;
+            }
+        }
+    }
+
+    async fn alert(
+        &self,
+        config: &MqttClusterDynamicSysmon,
+        topic: &str,
+        resource: &str,
+        value: u64,
+        threshold: u64,
+    ) {
+        let now = now_second();
+        let alert_key = format!("{}/{}", topic, resource);
+        if let Some(last_time) = self.last_alert_time.get(&alert_key) {
+            if now - *last_time < config.alert_interval_sec {
+                return;
+            }
+        }
+        self.last_alert_time.insert(alert_key, now);
+
+        let topic_name = replace_topic_name(topic.to_string());
+        let alert = SysmonAlert {
+            node: get_local_ip(),
+            kind: topic_name
+                .rsplit('/')
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            resource: resource.to_string(),
+            value,
+            threshold,
+            create_time: now,
+        };
+        warn!("System monitor alert: {:?}", alert);
+
+        let content = match serde_json::to_string(&alert) {
+            Ok(content) => content,
+            Err(e) => {
+                error!("Failed to serialize sysmon alert, failure message :{}", e);
+                return;
+            }
+        };
+
+        if let Some(record) = MqttMessage::build_system_topic_message(topic_name.clone(), content) {
+            write_topic_data(
+                &self.message_storage_adapter,
+                &self.metadata_cache,
+                &self.client_pool,
+                topic_name,
+                record,
+            )
+            .await;
+        }
+    }
+}
+
+// How many milliseconds a tick was late by, if that exceeds the threshold
+fn long_schedule_delay(elapsed: Duration, tick: Duration, threshold_ms: u64) -> Option<u64> {
+    let delay_ms = elapsed.saturating_sub(tick).as_millis() as u64;
+    if threshold_ms > 0 && delay_ms > threshold_ms {
+        return Some(delay_ms);
+    }
+    None
+}
+
+fn busy_queues(queues: Vec<(String, i64)>, threshold: u64) -> Vec<(String, u64)> {
+    if threshold == 0 {
+        return Vec::new();
+    }
+    queues
+        .into_iter()
+        .filter(|(_, len)| *len > 0 && *len as u64 > threshold)
+        .map(|(name, len)| (name, len as u64))
+        .collect()
+}
+
+// Resident memory of the broker process as a percentage of the system memory.
+// Only available where /proc is mounted.
+pub(crate) fn memory_usage_percent() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let rss_kb = parse_kb_field(&status, "VmRSS:")?;
+    let total_kb = parse_kb_field(&meminfo, "MemTotal:")?;
+    if total_kb == 0 {
+        return None;
+    }
+    Some(rss_kb * 100 / total_kb)
+}
+
+fn parse_kb_field(content: &str, key: &str) -> Option<u64> {
+    for line in content.lines() {
+        if let Some(value) = line.strip_prefix(key) {
+            return value.split_whitespace().next()?.parse().ok();
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{busy_queues, long_schedule_delay, parse_kb_field};
+
+    #[test]
+    fn long_schedule_delay_test() {
+        let tick = Duration::from_secs(1);
+        assert_eq!(
+            long_schedule_delay(Duration::from_millis(1100), tick, 240),
+            None
+        );
+        assert_eq!(
+            long_schedule_delay(Duration::from_millis(900), tick, 240),
+            None
+        );
+        assert_eq!(
+            long_schedule_delay(Duration::from_millis(1500), tick, 240),
+            Some(500)
+        );
+        assert_eq!(
+            long_schedule_delay(Duration::from_millis(1500), tick, 0),
+            None
+        );
+    }
+
+    #[test]
+    fn busy_queues_test() {
+        let queues = vec![
+            ("handler-1/response".to_string(), 10),
+            ("handler-2/response".to_string(), 90),
+        ];
+        assert_eq!(
+            busy_queues(queues.clone(), 80),
+            vec![("handler-2/response".to_string(), 90)]
+        );
+        assert!(busy_queues(queues, 0).is_empty());
+    }
+
+    #[test]
+    fn parse_kb_field_test() {
+        let content = "Name:\trobustmq\nVmRSS:\t  20480 kB\nThreads:\t8\n";
+        assert_eq!(parse_kb_field(content, "VmRSS:"), Some(20480));
+        assert_eq!(parse_kb_field(content, "MemTotal:"), None);
+    }
+}
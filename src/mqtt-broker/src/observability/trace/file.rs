@@ -0,0 +1,139 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+// Appends lines to ${dir}/${name}.log. Once the file would grow beyond max_size it is
+// renamed to ${name}.log.1, older files are shifted up and the oldest beyond max_files
+// is removed.
+pub struct RotatingFile {
+    dir: PathBuf,
+    name: String,
+    max_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(dir: &Path, name: &str, max_size: u64, max_files: usize) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = open_append(&trace_file_path(dir, name, 0))?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            dir: dir.to_path_buf(),
+            name: name.to_string(),
+            max_size,
+            max_files: max_files.max(1),
+            file,
+            size,
+        })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_size {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.file.write_all(b"\n")?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let oldest = trace_file_path(&self.dir, &self.name, self.max_files - 1);
+        if oldest.exists() {
+            fs::remove_file(oldest)?;
+        }
+        for index in (0..self.max_files - 1).rev() {
+            let from = trace_file_path(&self.dir, &self.name, index);
+            if from.exists() {
+                fs::rename(from, trace_file_path(&self.dir, &self.name, index + 1))?;
+            }
+        }
+        self.file = open_append(&trace_file_path(&self.dir, &self.name, 0))?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+pub fn trace_file_path(dir: &Path, name: &str, index: usize) -> PathBuf {
+    if index == 0 {
+        dir.join(format!("{}.log", name))
+    } else {
+        dir.join(format!("{}.log.{}", name, index))
+    }
+}
+
+// Content of every file of a trace, oldest first
+pub fn read_trace_files(dir: &Path, name: &str, max_files: usize) -> io::Result<Vec<u8>> {
+    let mut content = Vec::new();
+    for index in (0..max_files.max(1)).rev() {
+        let path = trace_file_path(dir, name, index);
+        if path.exists() {
+            content.extend(fs::read(path)?);
+        }
+    }
+    Ok(content)
+}
+
+pub fn remove_trace_files(dir: &Path, name: &str, max_files: usize) -> io::Result<()> {
+    for index in 0..max_files.max(1) {
+        let path = trace_file_path(dir, name, index);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+
+    use common_base::tools::unique_id;
+
+    use super::*;
+
+    #[test]
+    fn rotating_file_test() {
+        let dir = std::env::temp_dir().join(format!("trace-{}", unique_id()));
+        // Two lines fit into one file
+        let mut file = RotatingFile::open(&dir, "t1", 10, 2).unwrap();
+        for line in ["aaaa", "bbbb", "cccc", "dddd", "eeee", "ffff"] {
+            file.write_line(line).unwrap();
+        }
+        assert!(trace_file_path(&dir, "t1", 1).exists());
+        assert!(!trace_file_path(&dir, "t1", 2).exists());
+
+        // The first file was rotated out
+        let content = read_trace_files(&dir, "t1", 2).unwrap();
+        assert_eq!(
+            String::from_utf8(content).unwrap(),
+            "cccc\ndddd\neeee\nffff\n"
+        );
+
+        remove_trace_files(&dir, "t1", 2).unwrap();
+        assert!(read_trace_files(&dir, "t1", 2).unwrap().is_empty());
+        remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,604 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use common_base::tools::{now_mills, now_second};
+use dashmap::DashMap;
+use file::{read_trace_files, remove_trace_files, RotatingFile};
+use ipnet::IpNet;
+use log::{debug, error, warn};
+use serde::Serialize;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::task::spawn_blocking;
+use tokio::time::sleep;
+
+use crate::handler::error::MqttBrokerError;
+use crate::handler::topic_rewrite::source_topic_match;
+
+pub mod file;
+
+pub const TRACE_FILE_MAX_SIZE: u64 = 10 * 1024 * 1024;
+pub const TRACE_FILE_MAX_NUM: usize = 5;
+// Lines a trace buffers for its writer thread, events beyond it are dropped
+pub const TRACE_WRITE_QUEUE_SIZE: usize = 4096;
+// How long the files of an expired trace are kept before the sweeper deletes the trace
+pub const TRACE_RETENTION_SEC: u64 = 24 * 3600;
+pub const TRACE_SWEEP_INTERVAL_SEC: u64 = 10;
+pub const TRACE_MAX_DURATION_SEC: u64 = 7 * 24 * 3600;
+
+pub const TRACE_TYPE_CLIENT_ID: &str = "client_id";
+pub const TRACE_TYPE_TOPIC: &str = "topic";
+pub const TRACE_TYPE_IP: &str = "ip";
+
+pub fn trace_dir() -> PathBuf {
+    Path::new(&broker_mqtt_conf().log.log_path).join("trace")
+}
+
+// Which traffic a trace records
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceFilter {
+    ClientId(String),
+    // A topic filter, wildcards are allowed
+    Topic(String),
+    // A peer ip address or network, such as 192.168.1.10 or 192.168.1.0/24
+    Ip(String),
+}
+
+impl TraceFilter {
+    pub fn new(trace_type: &str, value: String) -> Result<Self, MqttBrokerError> {
+        if value.is_empty() {
+            return Err(MqttBrokerError::CommonError(
+                "trace value cannot be empty".to_string(),
+            ));
+        }
+        match trace_type {
+            TRACE_TYPE_CLIENT_ID => Ok(TraceFilter::ClientId(value)),
+            TRACE_TYPE_TOPIC => Ok(TraceFilter::Topic(value)),
+            TRACE_TYPE_IP => Ok(TraceFilter::Ip(value)),
+            _ => Err(MqttBrokerError::CommonError(format!(
+                "unsupported trace type {}, expected one of client_id, topic, ip",
+                trace_type
+            ))),
+        }
+    }
+
+    pub fn trace_type(&self) -> &str {
+        match self {
+            TraceFilter::ClientId(_) => TRACE_TYPE_CLIENT_ID,
+            TraceFilter::Topic(_) => TRACE_TYPE_TOPIC,
+            TraceFilter::Ip(_) => TRACE_TYPE_IP,
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        match self {
+            TraceFilter::ClientId(value) | TraceFilter::Topic(value) | TraceFilter::Ip(value) => {
+                value
+            }
+        }
+    }
+
+    fn is_match(&self, event: &TraceEvent) -> bool {
+        match self {
+            TraceFilter::ClientId(client_id) => event.client_id == *client_id,
+            TraceFilter::Topic(filter) => {
+                !event.topic.is_empty() && source_topic_match(filter, &event.topic)
+            }
+            TraceFilter::Ip(ip) => peer_ip_match(&event.peer_addr, ip),
+        }
+    }
+}
+
+fn peer_ip_match(peer_addr: &str, ip: &str) -> bool {
+    let peer_ip = if let Ok(addr) = SocketAddr::from_str(peer_addr) {
+        addr.ip()
+    } else if let Ok(addr) = IpAddr::from_str(peer_addr) {
+        addr
+    } else {
+        return false;
+    };
+    if let Ok(network) = IpNet::from_str(ip) {
+        return network.contains(&peer_ip);
+    }
+    IpAddr::from_str(ip)
+        .map(|ip| ip == peer_ip)
+        .unwrap_or(false)
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TraceEventType {
+    Connect,
+    PublishIn,
+    StorageAppend,
+    Dispatch,
+    Ack,
+    Drop,
+}
+
+// One line of a trace file
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TraceEvent {
+    pub time: u128,
+    pub event: TraceEventType,
+    pub client_id: String,
+    pub topic: String,
+    pub peer_addr: String,
+    pub pkid: u16,
+    pub detail: String,
+}
+
+impl TraceEvent {
+    pub fn new(event: TraceEventType, client_id: &str, topic: &str) -> Self {
+        TraceEvent {
+            time: now_mills(),
+            event,
+            client_id: client_id.to_string(),
+            topic: topic.to_string(),
+            peer_addr: String::new(),
+            pkid: 0,
+            detail: String::new(),
+        }
+    }
+
+    pub fn peer_addr(mut self, peer_addr: &str) -> Self {
+        self.peer_addr = peer_addr.to_string();
+        self
+    }
+
+    pub fn pkid(mut self, pkid: u16) -> Self {
+        self.pkid = pkid;
+        self
+    }
+
+    pub fn detail(mut self, detail: String) -> Self {
+        self.detail = detail;
+        self
+    }
+}
+
+enum TraceCommand {
+    Line(String),
+    // Acknowledged once every line queued before it has been written
+    Flush(SyncSender<()>),
+}
+
+// The sending side of the writer thread that owns the trace file. Dropping the sender
+// lets the thread write what is still queued and close the file.
+struct TraceWriter {
+    sender: Option<SyncSender<TraceCommand>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+fn spawn_trace_writer(name: &str, mut file: RotatingFile) -> Result<TraceWriter, MqttBrokerError> {
+    let (sender, receiver) = sync_channel(TRACE_WRITE_QUEUE_SIZE);
+    let trace_name = name.to_string();
+    let handle = thread::Builder::new()
+        .name(format!("trace-{}", name))
+        .spawn(move || {
+            for command in receiver {
+                match command {
+                    TraceCommand::Line(line) => {
+                        if let Err(e) = file.write_line(&line) {
+                            error!("Failed to write trace {}, error message :{}", trace_name, e);
+                        }
+                    }
+                    TraceCommand::Flush(ack) => {
+                        let _ = ack.send(());
+                    }
+                }
+            }
+        })?;
+    Ok(TraceWriter {
+        sender: Some(sender),
+        handle: Some(handle),
+    })
+}
+
+pub struct Trace {
+    pub name: String,
+    pub filter: TraceFilter,
+    pub start_time: u64,
+    pub end_time: u64,
+    dir: PathBuf,
+    stopped: AtomicBool,
+    // Events dropped because the write queue was full, reported by the sweeper
+    dropped: AtomicU64,
+    writer: Mutex<TraceWriter>,
+}
+
+impl Trace {
+    pub fn is_running(&self, now: u64) -> bool {
+        !self.stopped.load(Ordering::Relaxed) && now < self.end_time
+    }
+
+    fn writer(&self) -> MutexGuard<'_, TraceWriter> {
+        match self.writer.lock() {
+            Ok(writer) => writer,
+            Err(e) => e.into_inner(),
+        }
+    }
+
+    // Called on the packet path, so it only queues the line and never touches the file
+    fn write(&self, event: &TraceEvent) {
+        let line = match serde_json::to_string(event) {
+            Ok(line) => line,
+            Err(e) => {
+                error!("Failed to serialize trace event, error message :{}", e);
+                return;
+            }
+        };
+        if let Some(sender) = &self.writer().sender {
+            if let Err(TrySendError::Full(_)) = sender.try_send(TraceCommand::Line(line)) {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    // Stops accepting events, the writer thread drains its queue and closes the file
+    fn close(&self) {
+        self.writer().sender.take();
+    }
+
+    // Waits until everything recorded so far is in the files
+    fn flush(&self) {
+        let sender = self.writer().sender.clone();
+        if let Some(sender) = sender {
+            let (ack_sender, ack_receiver) = sync_channel(1);
+            if sender.send(TraceCommand::Flush(ack_sender)).is_ok() {
+                let _ = ack_receiver.recv();
+            }
+            return;
+        }
+        let handle = self.writer().handle.take();
+        if let Some(handle) = handle {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Admin-started traces of this broker. Every packet event matching a running trace is
+/// handed to the writer thread of the trace, which appends it to a rotating file under
+/// `${log_path}/trace` until the trace expires or is stopped. The files stay available for
+/// download until the trace is deleted, the sweeper deletes expired traces after
+/// [`TRACE_RETENTION_SEC`].
+#[derive(Default, Clone)]
+pub struct TraceManager {
+    // (trace name, Trace)
+    traces: DashMap<String, Arc<Trace>>,
+}
+
+impl TraceManager {
+    pub fn new() -> Self {
+        TraceManager {
+            traces: DashMap::with_capacity(2),
+        }
+    }
+
+    pub fn start(
+        &self,
+        dir: &Path,
+        name: &str,
+        filter: TraceFilter,
+        duration_sec: u64,
+    ) -> Result<(), MqttBrokerError> {
+        if name.is_empty() || name.contains(['/', '\\', '.']) {
+            return Err(MqttBrokerError::CommonError(format!(
+                "invalid trace name {}",
+                name
+            )));
+        }
+        if duration_sec == 0 || duration_sec > TRACE_MAX_DURATION_SEC {
+            return Err(MqttBrokerError::CommonError(format!(
+                "trace duration must be between 1 and {} seconds",
+                TRACE_MAX_DURATION_SEC
+            )));
+        }
+        if self.traces.contains_key(name) {
+            return Err(MqttBrokerError::CommonError(format!(
+                "trace {} already exists",
+                name
+            )));
+        }
+
+        let file = RotatingFile::open(dir, name, TRACE_FILE_MAX_SIZE, TRACE_FILE_MAX_NUM)?;
+        let writer = spawn_trace_writer(name, file)?;
+        let start_time = now_second();
+        self.traces.insert(
+            name.to_string(),
+            Arc::new(Trace {
+                name: name.to_string(),
+                filter,
+                start_time,
+                end_time: start_time.saturating_add(duration_sec),
+                dir: dir.to_path_buf(),
+                stopped: AtomicBool::new(false),
+                dropped: AtomicU64::new(0),
+                writer: Mutex::new(writer),
+            }),
+        );
+        Ok(())
+    }
+
+    pub fn stop(&self, name: &str) -> Result<(), MqttBrokerError> {
+        if let Some(trace) = self.traces.get(name) {
+            trace.stopped.store(true, Ordering::Relaxed);
+            trace.close();
+            return Ok(());
+        }
+        Err(MqttBrokerError::CommonError(format!(
+            "trace {} does not exist",
+            name
+        )))
+    }
+
+    pub async fn delete(&self, name: &str) -> Result<(), MqttBrokerError> {
+        if let Some((_, trace)) = self.traces.remove(name) {
+            trace.stopped.store(true, Ordering::Relaxed);
+            trace.close();
+            // Waiting for the writer thread and removing the files both block
+            return run_blocking(move || {
+                trace.flush();
+                Ok(remove_trace_files(
+                    &trace.dir,
+                    &trace.name,
+                    TRACE_FILE_MAX_NUM,
+                )?)
+            })
+            .await;
+        }
+        Err(MqttBrokerError::CommonError(format!(
+            "trace {} does not exist",
+            name
+        )))
+    }
+
+    pub fn list(&self) -> Vec<Arc<Trace>> {
+        let mut traces: Vec<Arc<Trace>> = self
+            .traces
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        traces.sort_by_key(|trace| trace.start_time);
+        traces
+    }
+
+    // Content of the trace files, oldest events first
+    pub async fn read(&self, name: &str) -> Result<Vec<u8>, MqttBrokerError> {
+        let trace = self.traces.get(name).map(|entry| entry.value().clone());
+        if let Some(trace) = trace {
+            return run_blocking(move || {
+                trace.flush();
+                Ok(read_trace_files(
+                    &trace.dir,
+                    &trace.name,
+                    TRACE_FILE_MAX_NUM,
+                )?)
+            })
+            .await;
+        }
+        Err(MqttBrokerError::CommonError(format!(
+            "trace {} does not exist",
+            name
+        )))
+    }
+
+    // The event is only built when a trace is running
+    pub fn record<F>(&self, build: F)
+    where
+        F: FnOnce() -> TraceEvent,
+    {
+        if self.traces.is_empty() {
+            return;
+        }
+        let now = now_second();
+        let traces: Vec<Arc<Trace>> = self
+            .traces
+            .iter()
+            .filter(|entry| entry.is_running(now))
+            .map(|entry| entry.value().clone())
+            .collect();
+        if traces.is_empty() {
+            return;
+        }
+
+        let event = build();
+        for trace in traces {
+            if trace.filter.is_match(&event) {
+                trace.write(&event);
+            }
+        }
+    }
+
+    // Closes the files of traces that are no longer running and deletes the traces whose
+    // retention has passed
+    pub async fn sweep(&self, now: u64) {
+        let mut expired = Vec::new();
+        for entry in self.traces.iter() {
+            let dropped = entry.dropped.swap(0, Ordering::Relaxed);
+            if dropped > 0 {
+                warn!(
+                    "Trace {} dropped {} events because its write queue was full",
+                    entry.name, dropped
+                );
+            }
+            if !entry.is_running(now) {
+                entry.close();
+            }
+            if now >= entry.end_time.saturating_add(TRACE_RETENTION_SEC) {
+                expired.push(entry.key().clone());
+            }
+        }
+        for name in expired {
+            if let Err(e) = self.delete(&name).await {
+                error!(
+                    "Failed to delete expired trace {}, error message :{}",
+                    name, e
+                );
+            }
+        }
+    }
+
+    pub async fn start_sweep_thread(&self, stop_send: broadcast::Sender<bool>) {
+        let mut stop_rx = stop_send.subscribe();
+        loop {
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            debug!("Trace sweep thread stopped successfully");
+                            break;
+                        }
+                    }
+                }
+                _ = sleep(Duration::from_secs(TRACE_SWEEP_INTERVAL_SEC)) => {
+                    self.sweep(now_second()).await;
+                }
+            }
+        }
+    }
+}
+
+async fn run_blocking<T, F>(f: F) -> Result<T, MqttBrokerError>
+where
+    F: FnOnce() -> Result<T, MqttBrokerError> + Send + 'static,
+    T: Send + 'static,
+{
+    match spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(e) => Err(MqttBrokerError::CommonError(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+
+    use common_base::tools::unique_id;
+
+    use super::*;
+
+    #[test]
+    fn trace_filter_test() {
+        let event = TraceEvent::new(TraceEventType::PublishIn, "c1", "sensor/1/temp")
+            .peer_addr("192.168.1.10:52011");
+
+        assert!(TraceFilter::ClientId("c1".to_string()).is_match(&event));
+        assert!(!TraceFilter::ClientId("c2".to_string()).is_match(&event));
+        assert!(TraceFilter::Topic("sensor/+/temp".to_string()).is_match(&event));
+        assert!(TraceFilter::Topic("sensor/#".to_string()).is_match(&event));
+        assert!(!TraceFilter::Topic("sensor/2/temp".to_string()).is_match(&event));
+        assert!(TraceFilter::Ip("192.168.1.10".to_string()).is_match(&event));
+        assert!(TraceFilter::Ip("192.168.1.0/24".to_string()).is_match(&event));
+        assert!(!TraceFilter::Ip("10.0.0.0/8".to_string()).is_match(&event));
+
+        assert!(TraceFilter::new("topic", "a/#".to_string()).is_ok());
+        assert!(TraceFilter::new("user", "a".to_string()).is_err());
+        assert!(TraceFilter::new("ip", "".to_string()).is_err());
+    }
+
+    #[tokio::test]
+    async fn trace_manager_test() {
+        let dir = std::env::temp_dir().join(format!("trace-{}", unique_id()));
+        let manager = TraceManager::new();
+        manager
+            .start(&dir, "t1", TraceFilter::ClientId("c1".to_string()), 60)
+            .unwrap();
+        assert!(manager
+            .start(&dir, "t1", TraceFilter::ClientId("c1".to_string()), 60)
+            .is_err());
+        assert!(manager
+            .start(&dir, "../t2", TraceFilter::ClientId("c1".to_string()), 60)
+            .is_err());
+        assert!(manager
+            .start(
+                &dir,
+                "t3",
+                TraceFilter::ClientId("c1".to_string()),
+                u64::MAX
+            )
+            .is_err());
+
+        manager.record(|| TraceEvent::new(TraceEventType::Connect, "c1", ""));
+        manager.record(|| TraceEvent::new(TraceEventType::Connect, "c2", ""));
+        manager.record(|| {
+            TraceEvent::new(TraceEventType::Dispatch, "c1", "t/1")
+                .pkid(3)
+                .detail("qos: 1".to_string())
+        });
+
+        let content = String::from_utf8(manager.read("t1").await.unwrap()).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("\"event\":\"CONNECT\""));
+        assert!(lines[1].contains("\"event\":\"DISPATCH\""));
+        assert!(lines[1].contains("\"pkid\":3"));
+
+        // A stopped trace keeps its file but records nothing more
+        manager.stop("t1").unwrap();
+        manager.record(|| TraceEvent::new(TraceEventType::Connect, "c1", ""));
+        assert_eq!(
+            String::from_utf8(manager.read("t1").await.unwrap())
+                .unwrap()
+                .lines()
+                .count(),
+            2
+        );
+        assert_eq!(manager.list().len(), 1);
+
+        manager.delete("t1").await.unwrap();
+        assert!(manager.read("t1").await.is_err());
+        assert!(manager.list().is_empty());
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn trace_sweep_test() {
+        let dir = std::env::temp_dir().join(format!("trace-{}", unique_id()));
+        let manager = TraceManager::new();
+        manager
+            .start(&dir, "t1", TraceFilter::ClientId("c1".to_string()), 60)
+            .unwrap();
+        manager.record(|| TraceEvent::new(TraceEventType::Connect, "c1", ""));
+        let end_time = manager.list()[0].end_time;
+
+        // Expired, the file is closed but still readable
+        manager.sweep(end_time).await;
+        assert_eq!(manager.list().len(), 1);
+        assert!(manager.list()[0].writer().sender.is_none());
+        assert_eq!(
+            String::from_utf8(manager.read("t1").await.unwrap())
+                .unwrap()
+                .lines()
+                .count(),
+            1
+        );
+
+        // Retention passed, the trace and its files are gone
+        manager.sweep(end_time + TRACE_RETENTION_SEC).await;
+        assert!(manager.list().is_empty());
+        assert!(!file::trace_file_path(&dir, "t1", 0).exists());
+        remove_dir_all(dir).unwrap();
+    }
+}
@@ -11,3 +11,398 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use dashmap::DashMap;
+use grpc_clients::pool::ClientPool;
+use log::{debug, error, info, warn};
+use metadata_struct::mqtt::alarm::MqttAlarm;
+use metadata_struct::mqtt::message::MqttMessage;
+use storage_adapter::storage::StorageAdapter;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+
+use crate::handler::cache::CacheManager;
+use crate::observability::metrics::publish::{slow_subscribe_num, storage_write_failure_num};
+use crate::observability::system_topic::sysmon::memory_usage_percent;
+use crate::observability::system_topic::{
+    replace_topic_name, write_topic_data, SYSTEM_TOPIC_BROKERS_ALARMS_ACTIVATE,
+    SYSTEM_TOPIC_BROKERS_ALARMS_DEACTIVATE,
+};
+use crate::storage::alarm::AlarmStorage;
+use crate::storage::cluster::ClusterStorage;
+
+pub const ALARM_CONNECTION_HIGH: &str = "connection_high";
+pub const ALARM_HIGH_CPU: &str = "high_cpu";
+pub const ALARM_HIGH_MEMORY: &str = "high_memory";
+pub const ALARM_PLACEMENT_CENTER_UNREACHABLE: &str = "placement_center_unreachable";
+pub const ALARM_STORAGE_WRITE_FAILURE: &str = "storage_write_failure";
+pub const ALARM_SLOW_SUBSCRIBE_SPIKE: &str = "slow_subscribe_spike";
+
+// An active alarm is only cleared once the value is this far below its threshold, so a
+// value hovering around the threshold does not flap the alarm
+const ALARM_CLEAR_MARGIN_PERCENT: u64 = 10;
+
+// Linux reports process cpu time in clock ticks of 1/100 second
+const CLOCK_TICKS_PER_SECOND: u64 = 100;
+
+// Readings of the previous check, alarms on counters compare against them
+#[derive(Default)]
+struct AlarmCheckState {
+    cpu_ticks: Option<(Instant, u64)>,
+    storage_write_failure: u64,
+    slow_subscribe: u64,
+}
+
+// Raises named alarms when a resource of this broker crosses its threshold and clears
+// them once it is back to normal. Every transition is published to
+// $SYS/brokers/${node}/alarms/activate|deactivate and recorded in the placement center.
+pub struct AlarmManager<S> {
+    metadata_cache: Arc<CacheManager>,
+    message_storage_adapter: Arc<S>,
+    client_pool: Arc<ClientPool>,
+    // (alarm name, active alarm)
+    active_alarms: DashMap<String, MqttAlarm>,
+}
+
+impl<S> AlarmManager<S>
+where
+    S: StorageAdapter + Clone + Send + Sync + 'static,
+{
+    pub fn new(
+        metadata_cache: Arc<CacheManager>,
+        message_storage_adapter: Arc<S>,
+        client_pool: Arc<ClientPool>,
+    ) -> Self {
+        AlarmManager {
+            metadata_cache,
+            message_storage_adapter,
+            client_pool,
+            active_alarms: DashMap::with_capacity(8),
+        }
+    }
+
+    pub async fn start_thread(&self, stop_send: broadcast::Sender<bool>) {
+        self.load_active_alarms().await;
+
+        let mut state = AlarmCheckState {
+            storage_write_failure: storage_write_failure_num(),
+            slow_subscribe: slow_subscribe_num(),
+            ..Default::default()
+        };
+        let mut stop_rx = stop_send.subscribe();
+        loop {
+            let interval = self
+                .metadata_cache
+                .get_cluster_info()
+                .alarm
+                .check_interval_sec
+                .max(1);
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            debug!("Alarm manager thread stopped successfully");
+                            break;
+                        }
+                    }
+                }
+                _ = sleep(Duration::from_secs(interval)) => {
+                    self.check(&mut state).await;
+                }
+            }
+        }
+    }
+
+    pub async fn activate(&self, name: &str, message: String, details: HashMap<String, String>) {
+        if self.active_alarms.contains_key(name) {
+            return;
+        }
+        let alarm = MqttAlarm::new(
+            name.to_string(),
+            broker_mqtt_conf().broker_id,
+            message,
+            details,
+        );
+        self.active_alarms.insert(name.to_string(), alarm.clone());
+        warn!("Alarm {} activated, {}", name, alarm.message);
+        self.publish(SYSTEM_TOPIC_BROKERS_ALARMS_ACTIVATE, &alarm)
+            .await;
+    }
+
+    pub async fn deactivate(&self, name: &str, message: String, details: HashMap<String, String>) {
+        if let Some((_, mut alarm)) = self.active_alarms.remove(name) {
+            alarm.deactivate(message, details);
+            info!("Alarm {} deactivated, {}", name, alarm.message);
+            self.publish(SYSTEM_TOPIC_BROKERS_ALARMS_DEACTIVATE, &alarm)
+                .await;
+        }
+    }
+
+    // Alarms left active by a previous run of this broker are taken over, so that they
+    // get deactivated once the condition has cleared.
+    async fn load_active_alarms(&self) {
+        let broker_id = broker_mqtt_conf().broker_id;
+        let storage = AlarmStorage::new(self.client_pool.clone());
+        match storage.list(true, 0).await {
+            Ok(alarms) => {
+                for alarm in alarms {
+                    if alarm.broker_id == broker_id {
+                        self.active_alarms.insert(alarm.name.clone(), alarm);
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Failed to load active alarms, error message :{}", e);
+            }
+        }
+    }
+
+    async fn check(&self, state: &mut AlarmCheckState) {
+        let cluster = self.metadata_cache.get_cluster_info();
+        let config = cluster.alarm;
+        if !config.enable {
+            return;
+        }
+
+        let connections = self.metadata_cache.connection_info.len() as u64;
+        let max_connections = cluster.network.tcp_max_connection_num as u64;
+        self.evaluate(
+            ALARM_CONNECTION_HIGH,
+            usage_percent(connections, max_connections),
+            config.connection_high_percent,
+            HashMap::from([
+                ("connections".to_string(), connections.to_string()),
+                ("max_connections".to_string(), max_connections.to_string()),
+            ]),
+        )
+        .await;
+
+        if let Some(ticks) = process_cpu_ticks() {
+            let now = Instant::now();
+            if let Some((last_time, last_ticks)) = state.cpu_ticks {
+                let percent = cpu_usage_percent(
+                    ticks.saturating_sub(last_ticks),
+                    now.duration_since(last_time),
+                    available_cores(),
+                );
+                self.evaluate(
+                    ALARM_HIGH_CPU,
+                    percent,
+                    config.cpu_high_percent,
+                    HashMap::from([("usage_percent".to_string(), percent.to_string())]),
+                )
+                .await;
+            }
+            state.cpu_ticks = Some((now, ticks));
+        }
+
+        if let Some(percent) = memory_usage_percent() {
+            self.evaluate(
+                ALARM_HIGH_MEMORY,
+                percent,
+                config.memory_high_percent,
+                HashMap::from([("usage_percent".to_string(), percent.to_string())]),
+            )
+            .await;
+        }
+
+        let failures = storage_write_failure_num();
+        let new_failures = failures.saturating_sub(state.storage_write_failure);
+        state.storage_write_failure = failures;
+        self.evaluate(
+            ALARM_STORAGE_WRITE_FAILURE,
+            new_failures,
+            config.storage_write_failure_num,
+            HashMap::from([("failures".to_string(), new_failures.to_string())]),
+        )
+        .await;
+
+        let slow = slow_subscribe_num();
+        let new_slow = slow.saturating_sub(state.slow_subscribe);
+        state.slow_subscribe = slow;
+        self.evaluate(
+            ALARM_SLOW_SUBSCRIBE_SPIKE,
+            new_slow,
+            config.slow_subscribe_num,
+            HashMap::from([("slow_subscribes".to_string(), new_slow.to_string())]),
+        )
+        .await;
+
+        let cluster_storage = ClusterStorage::new(self.client_pool.clone());
+        match cluster_storage.node_list().await {
+            Ok(_) => {
+                self.deactivate(
+                    ALARM_PLACEMENT_CENTER_UNREACHABLE,
+                    "placement center is reachable again".to_string(),
+                    HashMap::new(),
+                )
+                .await;
+            }
+            Err(e) => {
+                self.activate(
+                    ALARM_PLACEMENT_CENTER_UNREACHABLE,
+                    "placement center is unreachable".to_string(),
+                    HashMap::from([("error".to_string(), e.to_string())]),
+                )
+                .await;
+            }
+        }
+    }
+
+    async fn evaluate(
+        &self,
+        name: &str,
+        value: u64,
+        threshold: u64,
+        details: HashMap<String, String>,
+    ) {
+        if is_alarm_triggered(value, threshold) {
+            self.activate(
+                name,
+                format!(
+                    "{} is {}, reaching the threshold {}",
+                    name, value, threshold
+                ),
+                details,
+            )
+            .await;
+        } else if is_alarm_cleared(value, threshold) {
+            self.deactivate(
+                name,
+                format!("{} is {}, below the threshold {}", name, value, threshold),
+                details,
+            )
+            .await;
+        }
+    }
+
+    async fn publish(&self, topic: &str, alarm: &MqttAlarm) {
+        let storage = AlarmStorage::new(self.client_pool.clone());
+        if let Err(e) = storage.save(alarm).await {
+            error!(
+                "Failed to save alarm {} to the placement center, error message :{}",
+                alarm.name, e
+            );
+        }
+
+        let content = match serde_json::to_string(alarm) {
+            Ok(content) => content,
+            Err(e) => {
+                error!("Failed to serialize alarm, failure message :{}", e);
+                return;
+            }
+        };
+
+        let topic_name = replace_topic_name(topic.to_string());
+        if let Some(record) = MqttMessage::build_system_topic_message(topic_name.clone(), content) {
+            write_topic_data(
+                &self.message_storage_adapter,
+                &self.metadata_cache,
+                &self.client_pool,
+                topic_name,
+                record,
+            )
+            .await;
+        }
+    }
+}
+
+// A threshold of 0 disables the alarm
+fn is_alarm_triggered(value: u64, threshold: u64) -> bool {
+    threshold > 0 && value >= threshold
+}
+
+fn is_alarm_cleared(value: u64, threshold: u64) -> bool {
+    threshold == 0 || value * 100 < threshold * (100 - ALARM_CLEAR_MARGIN_PERCENT)
+}
+
+fn usage_percent(value: u64, total: u64) -> u64 {
+    if total == 0 {
+        return 0;
+    }
+    value * 100 / total
+}
+
+// user + system cpu time of the broker process. Only available where /proc is mounted.
+fn process_cpu_ticks() -> Option<u64> {
+    parse_process_cpu_ticks(&fs::read_to_string("/proc/self/stat").ok()?)
+}
+
+fn parse_process_cpu_ticks(stat: &str) -> Option<u64> {
+    // The process name may contain spaces, the fields are counted from its closing ')'.
+    // utime and stime are the 14th and 15th fields of the line.
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+fn cpu_usage_percent(ticks: u64, elapsed: Duration, cores: u64) -> u64 {
+    let available_ms = elapsed.as_millis() as u64 * cores.max(1);
+    if available_ms == 0 {
+        return 0;
+    }
+    ticks * (1000 / CLOCK_TICKS_PER_SECOND) * 100 / available_ms
+}
+
+fn available_cores() -> u64 {
+    std::thread::available_parallelism()
+        .map(|num| num.get() as u64)
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{
+        cpu_usage_percent, is_alarm_cleared, is_alarm_triggered, parse_process_cpu_ticks,
+        usage_percent,
+    };
+
+    #[test]
+    fn is_alarm_triggered_test() {
+        assert!(is_alarm_triggered(90, 90));
+        assert!(is_alarm_triggered(95, 90));
+        assert!(!is_alarm_triggered(89, 90));
+        assert!(!is_alarm_triggered(100, 0));
+    }
+
+    #[test]
+    fn is_alarm_cleared_test() {
+        // Between the clear level and the threshold the alarm keeps its state
+        assert!(!is_alarm_cleared(85, 90));
+        assert!(!is_alarm_cleared(81, 90));
+        assert!(is_alarm_cleared(80, 90));
+        assert!(is_alarm_cleared(0, 1));
+        assert!(is_alarm_cleared(100, 0));
+    }
+
+    #[test]
+    fn usage_percent_test() {
+        assert_eq!(usage_percent(950, 1000), 95);
+        assert_eq!(usage_percent(10, 0), 0);
+    }
+
+    #[test]
+    fn parse_process_cpu_ticks_test() {
+        let stat = "1234 (robust mqtt) S 1 1234 1234 0 -1 4194560 2000 0 0 0 150 50 0 0 20 0 8 0";
+        assert_eq!(parse_process_cpu_ticks(stat), Some(200));
+        assert_eq!(parse_process_cpu_ticks("1234 (robust"), None);
+    }
+
+    #[test]
+    fn cpu_usage_percent_test() {
+        // 5 seconds of cpu time within 10 seconds on one core
+        assert_eq!(cpu_usage_percent(500, Duration::from_secs(10), 1), 50);
+        assert_eq!(cpu_usage_percent(500, Duration::from_secs(10), 2), 25);
+        assert_eq!(cpu_usage_percent(500, Duration::ZERO, 2), 0);
+    }
+}
@@ -0,0 +1,357 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use common_base::config::broker_mqtt::{Webhook, WebhookEndpoint};
+use common_base::tools::now_mills;
+use dashmap::DashMap;
+use log::{debug, warn};
+use protocol::mqtt::common::PublishProperties;
+use sender::WebhookSender;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+
+use crate::observability::metrics::events::metrics_webhook_dropped;
+
+pub mod sender;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEventType {
+    #[serde(rename = "client.connect")]
+    ClientConnect,
+    #[serde(rename = "client.connack")]
+    ClientConnack,
+    #[serde(rename = "client.connected")]
+    ClientConnected,
+    #[serde(rename = "client.disconnected")]
+    ClientDisconnected,
+    #[serde(rename = "client.authenticate")]
+    ClientAuthenticate,
+    #[serde(rename = "client.subscribe")]
+    ClientSubscribe,
+    #[serde(rename = "client.unsubscribe")]
+    ClientUnsubscribe,
+    #[serde(rename = "session.created")]
+    SessionCreated,
+    #[serde(rename = "session.terminated")]
+    SessionTerminated,
+    #[serde(rename = "message.publish")]
+    MessagePublish,
+    #[serde(rename = "message.delivered")]
+    MessageDelivered,
+    #[serde(rename = "message.acked")]
+    MessageAcked,
+    #[serde(rename = "message.dropped")]
+    MessageDropped,
+}
+
+impl WebhookEventType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEventType::ClientConnect => "client.connect",
+            WebhookEventType::ClientConnack => "client.connack",
+            WebhookEventType::ClientConnected => "client.connected",
+            WebhookEventType::ClientDisconnected => "client.disconnected",
+            WebhookEventType::ClientAuthenticate => "client.authenticate",
+            WebhookEventType::ClientSubscribe => "client.subscribe",
+            WebhookEventType::ClientUnsubscribe => "client.unsubscribe",
+            WebhookEventType::SessionCreated => "session.created",
+            WebhookEventType::SessionTerminated => "session.terminated",
+            WebhookEventType::MessagePublish => "message.publish",
+            WebhookEventType::MessageDelivered => "message.delivered",
+            WebhookEventType::MessageAcked => "message.acked",
+            WebhookEventType::MessageDropped => "message.dropped",
+        }
+    }
+}
+
+// One event sent to the webhook endpoints, events are posted as a JSON array
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WebhookEvent {
+    pub event: WebhookEventType,
+    pub timestamp: u128,
+    pub node: u64,
+    pub client_id: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub username: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub peer_addr: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub topic: String,
+    #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+    pub data: serde_json::Value,
+}
+
+impl WebhookEvent {
+    pub fn new(event: WebhookEventType, client_id: &str) -> Self {
+        WebhookEvent {
+            event,
+            timestamp: now_mills(),
+            node: 0,
+            client_id: client_id.to_string(),
+            username: "".to_string(),
+            peer_addr: "".to_string(),
+            topic: "".to_string(),
+            data: serde_json::Value::Null,
+        }
+    }
+
+    pub fn username(mut self, username: &str) -> Self {
+        self.username = username.to_string();
+        self
+    }
+
+    pub fn peer_addr(mut self, peer_addr: &str) -> Self {
+        self.peer_addr = peer_addr.to_string();
+        self
+    }
+
+    pub fn topic(mut self, topic: &str) -> Self {
+        self.topic = topic.to_string();
+        self
+    }
+
+    pub fn data(mut self, data: serde_json::Value) -> Self {
+        self.data = data;
+        self
+    }
+}
+
+// Payload carried by a message.publish event. Only payloads the publisher marked as UTF-8
+// text are sent, binary payloads would not survive the JSON encoding of the event.
+pub fn publish_event_payload(
+    payload: &[u8],
+    publish_properties: &Option<PublishProperties>,
+) -> Option<String> {
+    if let Some(properties) = publish_properties {
+        if properties.payload_format_indicator == Some(1) {
+            return String::from_utf8(payload.to_vec()).ok();
+        }
+    }
+    None
+}
+
+// Event names an endpoint subscribes to, "client.*" matches every client event
+#[derive(Debug, Clone, Default)]
+pub struct WebhookEventFilter {
+    events: Vec<String>,
+}
+
+impl WebhookEventFilter {
+    pub fn new(events: Vec<String>) -> Self {
+        WebhookEventFilter { events }
+    }
+
+    pub fn is_match(&self, event: WebhookEventType) -> bool {
+        if self.events.is_empty() {
+            return true;
+        }
+        let name = event.name();
+        self.events.iter().any(|filter| {
+            if let Some(prefix) = filter.strip_suffix('*') {
+                name.starts_with(prefix)
+            } else {
+                filter == name
+            }
+        })
+    }
+}
+
+#[derive(Clone)]
+struct WebhookEndpointQueue {
+    filter: WebhookEventFilter,
+    sender: Sender<WebhookEvent>,
+}
+
+#[derive(Default, Clone)]
+pub struct WebhookManager {
+    // broker id stamped on every event
+    node: Arc<AtomicU64>,
+    // url -> bounded queue of the endpoint
+    endpoints: DashMap<String, WebhookEndpointQueue>,
+}
+
+impl WebhookManager {
+    pub fn new() -> Self {
+        WebhookManager {
+            node: Arc::new(AtomicU64::new(0)),
+            endpoints: DashMap::with_capacity(2),
+        }
+    }
+
+    // Start one sender per configured endpoint, so that a slow endpoint only delays its own events
+    pub fn start(&self, broker_id: u64, conf: &Webhook, stop_send: broadcast::Sender<bool>) {
+        if !conf.enable {
+            return;
+        }
+        self.node.store(broker_id, Ordering::Relaxed);
+        for endpoint in conf.endpoints.iter() {
+            let recv = self.register(endpoint, conf.queue_size);
+            let sender = WebhookSender::new(endpoint.url.clone(), conf.clone());
+            let raw_stop_send = stop_send.clone();
+            tokio::spawn(async move {
+                sender.start(recv, raw_stop_send).await;
+            });
+        }
+    }
+
+    pub fn register(
+        &self,
+        endpoint: &WebhookEndpoint,
+        queue_size: usize,
+    ) -> Receiver<WebhookEvent> {
+        let (sender, recv) = mpsc::channel(queue_size.max(1));
+        self.endpoints.insert(
+            endpoint.url.clone(),
+            WebhookEndpointQueue {
+                filter: WebhookEventFilter::new(endpoint.events.clone()),
+                sender,
+            },
+        );
+        recv
+    }
+
+    // The event is only built when at least one endpoint wants it. Never waits: when an
+    // endpoint queue is full the event is dropped for that endpoint.
+    pub fn emit<F>(&self, event_type: WebhookEventType, build: F)
+    where
+        F: FnOnce() -> WebhookEvent,
+    {
+        if self.endpoints.is_empty() {
+            return;
+        }
+        let senders: Vec<(String, Sender<WebhookEvent>)> = self
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint.filter.is_match(event_type))
+            .map(|endpoint| (endpoint.key().clone(), endpoint.sender.clone()))
+            .collect();
+        if senders.is_empty() {
+            return;
+        }
+
+        let mut event = build();
+        event.node = self.node.load(Ordering::Relaxed);
+        for (url, sender) in senders {
+            if let Err(e) = sender.try_send(event.clone()) {
+                metrics_webhook_dropped(&url);
+                match e {
+                    mpsc::error::TrySendError::Full(_) => {
+                        warn!(
+                            "Webhook queue of {} is full, event {} dropped",
+                            url,
+                            event_type.name()
+                        );
+                    }
+                    mpsc::error::TrySendError::Closed(_) => {
+                        debug!("Webhook sender of {} has exited", url);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common_base::config::broker_mqtt::WebhookEndpoint;
+    use protocol::mqtt::common::PublishProperties;
+
+    use super::{
+        publish_event_payload, WebhookEvent, WebhookEventFilter, WebhookEventType, WebhookManager,
+    };
+    use crate::observability::metrics::events::webhook_events_num;
+
+    #[test]
+    fn event_filter_test() {
+        let filter = WebhookEventFilter::default();
+        assert!(filter.is_match(WebhookEventType::MessageDropped));
+
+        let filter = WebhookEventFilter::new(vec![
+            "client.connected".to_string(),
+            "message.*".to_string(),
+        ]);
+        assert!(filter.is_match(WebhookEventType::ClientConnected));
+        assert!(filter.is_match(WebhookEventType::MessagePublish));
+        assert!(filter.is_match(WebhookEventType::MessageAcked));
+        assert!(!filter.is_match(WebhookEventType::ClientConnect));
+        assert!(!filter.is_match(WebhookEventType::SessionCreated));
+    }
+
+    #[test]
+    fn event_serialize_test() {
+        let mut event = WebhookEvent::new(WebhookEventType::ClientConnected, "c1").username("u1");
+        event.timestamp = 1;
+        let data = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            data,
+            r#"{"event":"client.connected","timestamp":1,"node":0,"client_id":"c1","username":"u1"}"#
+        );
+    }
+
+    #[test]
+    fn publish_event_payload_test() {
+        let utf8 = Some(PublishProperties {
+            payload_format_indicator: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(
+            publish_event_payload(b"hello", &utf8),
+            Some("hello".to_string())
+        );
+        assert_eq!(publish_event_payload(&[0xff, 0xfe], &utf8), None);
+
+        // Unspecified bytes are never sent, even when they happen to be valid text
+        assert_eq!(publish_event_payload(b"hello", &None), None);
+        let binary = Some(PublishProperties {
+            payload_format_indicator: Some(0),
+            ..Default::default()
+        });
+        assert_eq!(publish_event_payload(b"hello", &binary), None);
+    }
+
+    #[tokio::test]
+    async fn emit_test() {
+        let manager = WebhookManager::new();
+        let url = "http://127.0.0.1:1/emit_test";
+        let mut recv = manager.register(
+            &WebhookEndpoint {
+                url: url.to_string(),
+                events: vec!["client.*".to_string()],
+            },
+            1,
+        );
+
+        // filtered out, never built
+        manager.emit(WebhookEventType::MessagePublish, || {
+            panic!("event should not be built")
+        });
+
+        manager.emit(WebhookEventType::ClientConnected, || {
+            WebhookEvent::new(WebhookEventType::ClientConnected, "c1")
+        });
+        // the queue holds one event, the second one is dropped
+        manager.emit(WebhookEventType::ClientDisconnected, || {
+            WebhookEvent::new(WebhookEventType::ClientDisconnected, "c1")
+        });
+
+        let event = recv.recv().await.unwrap();
+        assert_eq!(event.event, WebhookEventType::ClientConnected);
+        assert!(recv.try_recv().is_err());
+        assert_eq!(webhook_events_num(url, "dropped"), 1);
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use common_base::config::broker_mqtt::Webhook;
+use log::{debug, error, warn};
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc::Receiver;
+use tokio::time::{sleep, timeout_at, Instant};
+
+use super::WebhookEvent;
+use crate::observability::metrics::events::{metrics_webhook_failed, metrics_webhook_sent};
+
+// Posts the events of one endpoint in batches, retrying a failed batch before giving up on it
+pub struct WebhookSender {
+    url: String,
+    conf: Webhook,
+    client: reqwest::Client,
+}
+
+impl WebhookSender {
+    pub fn new(url: String, conf: Webhook) -> Self {
+        let client = match reqwest::Client::builder()
+            .timeout(Duration::from_millis(conf.request_timeout_ms.max(1)))
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => {
+                warn!(
+                    "Failed to build the webhook client of {} with the configured timeout, error message: {}",
+                    url, e
+                );
+                reqwest::Client::new()
+            }
+        };
+        WebhookSender { url, conf, client }
+    }
+
+    pub async fn start(
+        &self,
+        mut recv: Receiver<WebhookEvent>,
+        stop_send: broadcast::Sender<bool>,
+    ) {
+        let mut stop_recv = stop_send.subscribe();
+        let batch_size = self.conf.batch_size.max(1);
+        let batch_interval = Duration::from_millis(self.conf.batch_interval_ms);
+        loop {
+            let mut batch = Vec::with_capacity(batch_size);
+            select! {
+                val = stop_recv.recv() => {
+                    if let Ok(flag) = val {
+                        if flag {
+                            debug!("Webhook sender of {} exited successfully", self.url);
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                val = recv.recv() => {
+                    if let Some(event) = val {
+                        batch.push(event);
+                    } else {
+                        break;
+                    }
+                }
+            }
+
+            // wait a little for more events, so that a burst is sent in one request
+            let deadline = Instant::now() + batch_interval;
+            while batch.len() < batch_size {
+                match timeout_at(deadline, recv.recv()).await {
+                    Ok(Some(event)) => batch.push(event),
+                    _ => break,
+                }
+            }
+            self.send_batch(&batch).await;
+        }
+    }
+
+    pub async fn send_batch(&self, batch: &[WebhookEvent]) -> bool {
+        let mut attempt = 0;
+        loop {
+            let err = match self.client.post(&self.url).json(batch).send().await {
+                Ok(resp) => {
+                    if resp.status().is_success() {
+                        metrics_webhook_sent(&self.url, batch.len());
+                        return true;
+                    }
+                    format!("response status {}", resp.status())
+                }
+                Err(e) => e.to_string(),
+            };
+
+            if attempt >= self.conf.max_retries {
+                error!(
+                    "Failed to send {} webhook events to {} after {} retries, error message: {}",
+                    batch.len(),
+                    self.url,
+                    attempt,
+                    err
+                );
+                metrics_webhook_failed(&self.url, batch.len());
+                return false;
+            }
+            attempt += 1;
+            sleep(Duration::from_millis(
+                self.conf.retry_interval_ms * attempt as u64,
+            ))
+            .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use axum::extract::State;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use common_base::config::broker_mqtt::Webhook;
+
+    use super::WebhookSender;
+    use crate::observability::webhook::{WebhookEvent, WebhookEventType};
+
+    #[derive(Clone, Default)]
+    struct Endpoint {
+        // the first `fail_times` requests are answered with an error
+        fail_times: usize,
+        requests: Arc<AtomicUsize>,
+        received: Arc<Mutex<Vec<serde_json::Value>>>,
+    }
+
+    async fn receive(
+        State(endpoint): State<Endpoint>,
+        Json(body): Json<serde_json::Value>,
+    ) -> StatusCode {
+        if endpoint.requests.fetch_add(1, Ordering::SeqCst) < endpoint.fail_times {
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+        endpoint.received.lock().unwrap().push(body);
+        StatusCode::OK
+    }
+
+    async fn start_endpoint(endpoint: Endpoint) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new()
+            .route("/webhook", post(receive))
+            .with_state(endpoint);
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}/webhook", addr)
+    }
+
+    fn conf(max_retries: u32) -> Webhook {
+        Webhook {
+            enable: true,
+            max_retries,
+            retry_interval_ms: 10,
+            request_timeout_ms: 1000,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn send_batch_retry_test() {
+        let endpoint = Endpoint {
+            fail_times: 2,
+            ..Default::default()
+        };
+        let url = start_endpoint(endpoint.clone()).await;
+        let sender = WebhookSender::new(url, conf(3));
+
+        let batch = vec![
+            WebhookEvent::new(WebhookEventType::ClientConnected, "c1"),
+            WebhookEvent::new(WebhookEventType::ClientDisconnected, "c1"),
+        ];
+        assert!(sender.send_batch(&batch).await);
+        assert_eq!(endpoint.requests.load(Ordering::SeqCst), 3);
+
+        let received = endpoint.received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].as_array().unwrap().len(), 2);
+        assert_eq!(received[0][1]["event"], "client.disconnected");
+    }
+
+    #[tokio::test]
+    async fn send_batch_give_up_test() {
+        let endpoint = Endpoint {
+            fail_times: usize::MAX,
+            ..Default::default()
+        };
+        let url = start_endpoint(endpoint.clone()).await;
+        let sender = WebhookSender::new(url, conf(1));
+
+        let batch = vec![WebhookEvent::new(WebhookEventType::ClientConnected, "c1")];
+        assert!(!sender.send_batch(&batch).await);
+        assert_eq!(endpoint.requests.load(Ordering::SeqCst), 2);
+    }
+}
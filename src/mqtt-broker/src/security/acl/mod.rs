@@ -27,6 +27,7 @@ use crate::handler::cache::CacheManager;
 use crate::handler::constant::WILDCARD_RESOURCE;
 
 pub mod metadata;
+pub mod response_topic;
 
 pub fn is_allow_acl(
     cache_mamanger: &Arc<CacheManager>,
@@ -0,0 +1,262 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::tools::now_second;
+use protocol::mqtt::common::PublishProperties;
+
+use crate::handler::cache::CacheManager;
+use crate::subscribe::sub_common::{is_queue_sub, is_share_sub};
+
+pub const RESPONSE_TOPIC_PREFIX: &str = "$response/";
+
+// How long a responder may publish to a response topic when the request
+// carried no Message Expiry Interval.
+pub const RESPONSE_TOPIC_GRANT_DEFAULT_EXPIRE_SEC: u64 = 60;
+
+pub fn build_response_information(client_id: &str) -> String {
+    format!(
+        "{}{}/",
+        RESPONSE_TOPIC_PREFIX,
+        encode_response_topic_owner(client_id)
+    )
+}
+
+// The client id as a single topic level. '/' would split it over several levels and
+// '+' or '#' would be wildcards, they are percent-encoded together with '%' itself so
+// that two client ids never share a level.
+pub fn encode_response_topic_owner(client_id: &str) -> String {
+    let mut owner = String::with_capacity(client_id.len());
+    for c in client_id.chars() {
+        match c {
+            '%' => owner.push_str("%25"),
+            '/' => owner.push_str("%2F"),
+            '+' => owner.push_str("%2B"),
+            '#' => owner.push_str("%23"),
+            _ => owner.push(c),
+        }
+    }
+    owner
+}
+
+// The filter a subscription matches topics with, without the $share/{group}/ or
+// $queue/ prefix.
+pub fn subscribe_filter_path(path: &str) -> &str {
+    if is_share_sub(path.to_string()) {
+        if let Some((_, rest)) = path.split_once('/') {
+            if let Some((_, filter)) = rest.split_once('/') {
+                return filter;
+            }
+        }
+        return "";
+    }
+    if is_queue_sub(path.to_string()) {
+        if let Some((_, filter)) = path.split_once('/') {
+            return filter;
+        }
+        return "";
+    }
+    path
+}
+
+pub fn is_response_topic(topic_name: &str) -> bool {
+    topic_name.starts_with(RESPONSE_TOPIC_PREFIX)
+}
+
+// The encoded client id that owns a response topic, i.e. the level right after the prefix.
+pub fn response_topic_owner(topic_name: &str) -> Option<&str> {
+    if let Some(rest) = topic_name.strip_prefix(RESPONSE_TOPIC_PREFIX) {
+        if let Some((owner, _)) = rest.split_once('/') {
+            if !owner.is_empty() {
+                return Some(owner);
+            }
+        }
+    }
+    None
+}
+
+fn is_owner(client_id: &str, topic_name: &str) -> bool {
+    if let Some(owner) = response_topic_owner(topic_name) {
+        return owner == encode_response_topic_owner(client_id);
+    }
+    false
+}
+
+pub fn allow_response_topic_subscribe(client_id: &str, path: &str) -> bool {
+    is_owner(client_id, path)
+}
+
+pub fn allow_response_topic_publish(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+    topic_name: &str,
+) -> bool {
+    if is_owner(client_id, topic_name) {
+        return true;
+    }
+    cache_manager.is_response_topic_granted(client_id, topic_name)
+}
+
+// A request may only ask for replies on a response topic owned by the requester,
+// otherwise it could be used to open another client's private prefix.
+pub fn allow_request_response_topic(
+    client_id: &str,
+    publish_properties: &Option<PublishProperties>,
+) -> bool {
+    if let Some(properties) = publish_properties {
+        if let Some(response_topic) = &properties.response_topic {
+            if is_response_topic(response_topic) {
+                return is_owner(client_id, response_topic);
+            }
+        }
+    }
+    true
+}
+
+// Called when a request is delivered: the receiving client may then reply on the
+// request's response topic until the grant expires.
+pub fn grant_response_topic(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+    publish_properties: &PublishProperties,
+) {
+    if let Some(response_topic) = &publish_properties.response_topic {
+        if !is_response_topic(response_topic) || is_owner(client_id, response_topic) {
+            return;
+        }
+
+        let expire_sec = if let Some(interval) = publish_properties.message_expiry_interval {
+            if interval > 0 {
+                interval as u64
+            } else {
+                RESPONSE_TOPIC_GRANT_DEFAULT_EXPIRE_SEC
+            }
+        } else {
+            RESPONSE_TOPIC_GRANT_DEFAULT_EXPIRE_SEC
+        };
+        cache_manager.add_response_topic_grant(
+            client_id,
+            response_topic,
+            now_second() + expire_sec,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use grpc_clients::pool::ClientPool;
+
+    use super::*;
+
+    #[test]
+    fn response_topic_owner_test() {
+        let info = build_response_information("c1");
+        assert_eq!(info, "$response/c1/");
+        assert!(is_response_topic(&info));
+        assert_eq!(response_topic_owner("$response/c1/a/b"), Some("c1"));
+        assert_eq!(response_topic_owner("$response/c1"), None);
+        assert_eq!(response_topic_owner("$response//a"), None);
+        assert_eq!(response_topic_owner("/response/c1/a"), None);
+    }
+
+    #[test]
+    fn response_topic_special_client_id_test() {
+        let info = build_response_information("a/b");
+        assert_eq!(info, "$response/a%2Fb/");
+        assert!(allow_response_topic_subscribe("a/b", &format!("{}#", info)));
+        assert!(!allow_response_topic_subscribe("a", &format!("{}#", info)));
+        assert!(!allow_response_topic_subscribe("a/b", "$response/a/b/#"));
+
+        let info = build_response_information("c+1#");
+        assert_eq!(info, "$response/c%2B1%23/");
+        assert!(allow_response_topic_subscribe(
+            "c+1#",
+            &format!("{}x", info)
+        ));
+        assert!(!allow_response_topic_subscribe("c", &format!("{}x", info)));
+
+        // An id that looks encoded does not collide with the id it encodes
+        assert!(!allow_response_topic_subscribe(
+            "a%2Fb",
+            "$response/a%2Fb/#"
+        ));
+        assert!(allow_response_topic_subscribe(
+            "a%2Fb",
+            "$response/a%252Fb/#"
+        ));
+    }
+
+    #[test]
+    fn subscribe_filter_path_test() {
+        assert_eq!(
+            subscribe_filter_path("$share/g1/$response/c2/#"),
+            "$response/c2/#"
+        );
+        assert_eq!(
+            subscribe_filter_path("$queue/$response/c2/#"),
+            "$response/c2/#"
+        );
+        assert_eq!(subscribe_filter_path("$response/c2/#"), "$response/c2/#");
+        assert_eq!(subscribe_filter_path("$share/g1"), "");
+    }
+
+    #[test]
+    fn response_topic_subscribe_test() {
+        assert!(allow_response_topic_subscribe("c1", "$response/c1/#"));
+        assert!(allow_response_topic_subscribe("c1", "$response/c1/a"));
+        assert!(!allow_response_topic_subscribe("c1", "$response/c2/#"));
+        assert!(!allow_response_topic_subscribe("c1", "$response/+/#"));
+        assert!(!allow_response_topic_subscribe("c1", "$response/#"));
+    }
+
+    #[test]
+    fn response_topic_publish_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let topic = "$response/c1/a";
+
+        assert!(allow_response_topic_publish(&cache_manager, "c1", topic));
+        assert!(!allow_response_topic_publish(&cache_manager, "c2", topic));
+
+        let properties = PublishProperties {
+            response_topic: Some(topic.to_string()),
+            ..Default::default()
+        };
+        assert!(allow_request_response_topic(
+            "c1",
+            &Some(properties.clone())
+        ));
+        assert!(!allow_request_response_topic(
+            "c3",
+            &Some(properties.clone())
+        ));
+        assert!(allow_request_response_topic("c3", &None));
+
+        grant_response_topic(&cache_manager, "c2", &properties);
+        assert!(allow_response_topic_publish(&cache_manager, "c2", topic));
+        assert!(!allow_response_topic_publish(
+            &cache_manager,
+            "c2",
+            "$response/c1/b"
+        ));
+        assert!(!allow_response_topic_publish(&cache_manager, "c3", topic));
+
+        cache_manager.add_response_topic_grant("c3", topic, now_second() - 1);
+        assert!(!allow_response_topic_publish(&cache_manager, "c3", topic));
+
+        cache_manager.remove_session("c2");
+        assert!(!allow_response_topic_publish(&cache_manager, "c2", topic));
+    }
+}
@@ -18,6 +18,10 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use acl::is_allow_acl;
+use acl::response_topic::{
+    allow_request_response_topic, allow_response_topic_publish, allow_response_topic_subscribe,
+    is_response_topic, subscribe_filter_path,
+};
 use axum::async_trait;
 use common_base::config::broker_mqtt::broker_mqtt_conf;
 use common_base::config::common::Auth;
@@ -31,7 +35,7 @@ use metadata_struct::mqtt::connection::MQTTConnection;
 use metadata_struct::mqtt::user::MqttUser;
 use mysql::MySQLAuthStorageAdapter;
 use placement::PlacementAuthStorageAdapter;
-use protocol::mqtt::common::{ConnectProperties, Login, QoS, Subscribe};
+use protocol::mqtt::common::{ConnectProperties, Login, PublishProperties, QoS, Subscribe};
 use storage_adapter::StorageType;
 
 use crate::handler::cache::CacheManager;
@@ -213,7 +217,19 @@ impl AuthDriver {
         topic_name: &str,
         retain: bool,
         qos: QoS,
+        publish_properties: &Option<PublishProperties>,
     ) -> bool {
+        if !allow_request_response_topic(&connection.client_id, publish_properties) {
+            return false;
+        }
+
+        // Owning or being granted a response topic does not bypass blacklist and deny rules
+        if is_response_topic(topic_name)
+            && !allow_response_topic_publish(&self.cache_manager, &connection.client_id, topic_name)
+        {
+            return false;
+        }
+
         is_allow_acl(
             &self.cache_manager,
             connection,
@@ -230,6 +246,14 @@ impl AuthDriver {
         subscribe: &Subscribe,
     ) -> bool {
         for filter in subscribe.filters.clone() {
+            // Shared and queue subscriptions on a response topic are checked like plain ones
+            let path = subscribe_filter_path(&filter.path);
+            if is_response_topic(path)
+                && !allow_response_topic_subscribe(&connection.client_id, path)
+            {
+                return false;
+            }
+
             let topic_list = get_sub_topic_id_list(&self.cache_manager, &filter.path).await;
             for topic in topic_list {
                 if !is_allow_acl(
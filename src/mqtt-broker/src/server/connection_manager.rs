@@ -22,6 +22,7 @@ use futures::SinkExt;
 use log::{error, info};
 use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
 use protocol::mqtt::common::MqttProtocol;
+use tokio::io::AsyncWrite;
 use tokio::time::sleep;
 use tokio_util::codec::FramedWrite;
 
@@ -42,6 +43,7 @@ pub struct ConnectionManager {
         >,
     >,
     websocket_write_list: DashMap<u64, SplitSink<WebSocket, Message>>,
+    quic_write_list: DashMap<u64, FramedWrite<quinn::SendStream, MqttCodec>>,
     cache_manager: Arc<CacheManager>,
 }
 
@@ -51,12 +53,14 @@ impl ConnectionManager {
         let tcp_write_list = DashMap::with_capacity(64);
         let tcp_tls_write_list = DashMap::with_capacity(64);
         let websocket_write_list = DashMap::with_capacity(64);
+        let quic_write_list = DashMap::with_capacity(64);
         ConnectionManager {
             connections,
             tcp_write_list,
             tcp_tls_write_list,
             cache_manager,
             websocket_write_list,
+            quic_write_list,
         }
     }
 
@@ -92,6 +96,14 @@ impl ConnectionManager {
         self.websocket_write_list.insert(connection_id, write);
     }
 
+    pub fn add_quic_write(
+        &self,
+        connection_id: u64,
+        write: FramedWrite<quinn::SendStream, MqttCodec>,
+    ) {
+        self.quic_write_list.insert(connection_id, write);
+    }
+
     pub async fn close_all_connect(&self) {
         for (connect_id, _) in self.connections.clone() {
             self.close_connect(connect_id).await;
@@ -138,6 +150,18 @@ impl ConnectionManager {
                 Err(e) => error!("{}", e),
             }
         }
+
+        if let Some((id, mut stream)) = self.quic_write_list.remove(&connection_id) {
+            match stream.close().await {
+                Ok(_) => {
+                    info!(
+                        "server closes the quic connection actively, connection id [{}]",
+                        id
+                    );
+                }
+                Err(e) => error!("{}", e),
+            }
+        }
     }
 
     pub async fn write_websocket_frame(
@@ -211,63 +235,18 @@ impl ConnectionManager {
             if connection.connection_type == NetworkConnectionType::Tls {
                 return self.write_tcp_tls_frame(connection_id, resp).await;
             }
-        }
-
-        let mut times = 0;
-        let cluster = self.cache_manager.get_cluster_info();
-        loop {
-            match self.tcp_write_list.try_get_mut(&connection_id) {
-                dashmap::try_result::TryResult::Present(mut da) => {
-                    match da.send(resp.clone()).await {
-                        Ok(_) => {
-                            // write tls stream
-                            let network_type =
-                                if let Some(connection) = self.get_connect(connection_id) {
-                                    connection.connection_type.to_string()
-                                } else {
-                                    "".to_string()
-                                };
-
-                            record_sent_metrics(&resp, network_type);
-                            break;
-                        }
-                        Err(e) => {
-                            if times > cluster.network.response_max_try_mut_times {
-                                return Err(MqttBrokerError::CommonError(format!(
-                                    "Failed to write data to the mqtt tcp client, error message: {e:?}"
-                                )));
-                            }
-                        }
-                    }
-                }
-                dashmap::try_result::TryResult::Absent => {
-                    if times > cluster.network.response_max_try_mut_times {
-                        return Err(MqttBrokerError::CommonError(
-                            format!(
-                                "[write_frame]Connection management could not obtain an available tcp connection. Connection ID: {},len:{}",
-                                connection_id,
-                                self.tcp_write_list.len()
-                            )
-                        ));
-                    }
-                }
-                dashmap::try_result::TryResult::Locked => {
-                    if times > cluster.network.response_max_try_mut_times {
-                        return Err(MqttBrokerError::CommonError(
-                            format!(
-                                "[write_frame]Connection management failed to get tcp connection variable reference, connection ID: {}",connection_id
-                            )
-                        ));
-                    }
-                }
+            if connection.connection_type == NetworkConnectionType::Quic {
+                return self.write_quic_frame(connection_id, resp).await;
             }
-            times += 1;
-            sleep(Duration::from_millis(
-                cluster.network.response_try_mut_sleep_time_ms,
-            ))
-            .await
         }
-        Ok(())
+
+        self.write_frame(
+            &self.tcp_write_list,
+            connection_id,
+            resp,
+            NetworkConnectionType::Tcp,
+        )
+        .await
     }
 
     async fn write_tcp_tls_frame(
@@ -275,27 +254,53 @@ impl ConnectionManager {
         connection_id: u64,
         resp: MqttPacketWrapper,
     ) -> Result<(), MqttBrokerError> {
+        self.write_frame(
+            &self.tcp_tls_write_list,
+            connection_id,
+            resp,
+            NetworkConnectionType::Tls,
+        )
+        .await
+    }
+
+    async fn write_quic_frame(
+        &self,
+        connection_id: u64,
+        resp: MqttPacketWrapper,
+    ) -> Result<(), MqttBrokerError> {
+        self.write_frame(
+            &self.quic_write_list,
+            connection_id,
+            resp,
+            NetworkConnectionType::Quic,
+        )
+        .await
+    }
+
+    async fn write_frame<T>(
+        &self,
+        write_list: &DashMap<u64, FramedWrite<T, MqttCodec>>,
+        connection_id: u64,
+        resp: MqttPacketWrapper,
+        network_type: NetworkConnectionType,
+    ) -> Result<(), MqttBrokerError>
+    where
+        T: AsyncWrite + Unpin,
+    {
         let mut times = 0;
         let cluster = self.cache_manager.get_cluster_info();
         loop {
-            match self.tcp_tls_write_list.try_get_mut(&connection_id) {
+            match write_list.try_get_mut(&connection_id) {
                 dashmap::try_result::TryResult::Present(mut da) => {
                     match da.send(resp.clone()).await {
                         Ok(_) => {
-                            let network_type =
-                                if let Some(connection) = self.get_connect(connection_id) {
-                                    connection.connection_type.to_string()
-                                } else {
-                                    "".to_string()
-                                };
-
-                            record_sent_metrics(&resp, network_type);
+                            record_sent_metrics(&resp, network_type.to_string());
                             break;
                         }
                         Err(e) => {
                             if times > cluster.network.response_max_try_mut_times {
                                 return Err(MqttBrokerError::CommonError(format!(
-                                    "Failed to write data to the mqtt tcp client, error message: {e:?}"
+                                    "Failed to write data to the mqtt {network_type} client, error message: {e:?}"
                                 )));
                             }
                         }
@@ -305,9 +310,10 @@ impl ConnectionManager {
                     if times > cluster.network.response_max_try_mut_times {
                         return Err(MqttBrokerError::CommonError(
                             format!(
-                                "[write_frame]Connection management could not obtain an available tcp connection. Connection ID: {},len:{}",
+                                "[write_frame]Connection management could not obtain an available {} connection. Connection ID: {},len:{}",
+                                network_type,
                                 connection_id,
-                                self.tcp_write_list.len()
+                                write_list.len()
                             )
                         ));
                     }
@@ -316,7 +322,7 @@ impl ConnectionManager {
                     if times > cluster.network.response_max_try_mut_times {
                         return Err(MqttBrokerError::CommonError(
                             format!(
-                                "[write_frame]Connection management failed to get tcp connection variable reference, connection ID: {}",connection_id
+                                "[write_frame]Connection management failed to get {} connection variable reference, connection ID: {}",network_type,connection_id
                             )
                         ));
                     }
@@ -15,53 +15,78 @@
 use std::sync::Arc;
 
 use common_base::config::broker_mqtt::broker_mqtt_conf;
-use common_base::tools::serialize_value;
-use common_base::utils::file_utils::get_project_root;
+use common_base::tools::{now_second, serialize_value};
 use grpc_clients::pool::ClientPool;
 use metadata_struct::acl::mqtt_acl::MqttAcl;
 use metadata_struct::acl::mqtt_blacklist::{MqttAclBlackList, MqttAclBlackListType};
+use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
+use metadata_struct::mqtt::topic_rewrite_rule::{MqttTopicRewriteRule, TopicRewriteAction};
 use metadata_struct::mqtt::user::MqttUser;
 use protocol::broker_mqtt::broker_mqtt_admin::mqtt_broker_admin_service_server::MqttBrokerAdminService;
 use protocol::broker_mqtt::broker_mqtt_admin::{
+    AlarmRaw, AutoSubscribeRuleRaw, CancelDelayMessageReply, CancelDelayMessageRequest,
     ClusterStatusReply, ClusterStatusRequest, CreateAclReply, CreateAclRequest,
-    CreateBlacklistReply, CreateBlacklistRequest, CreateUserReply, CreateUserRequest,
-    DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply, DeleteBlacklistRequest,
-    DeleteUserReply, DeleteUserRequest, EnableSlowSubScribeReply, EnableSlowSubscribeRequest,
-    ListAclReply, ListAclRequest, ListBlacklistReply, ListBlacklistRequest, ListConnectionRaw,
-    ListConnectionReply, ListConnectionRequest, ListSlowSubScribeRaw, ListSlowSubscribeReply,
-    ListSlowSubscribeRequest, ListTopicReply, ListTopicRequest, ListUserReply, ListUserRequest,
-    MqttTopic,
+    CreateAutoSubscribeRuleReply, CreateAutoSubscribeRuleRequest, CreateBlacklistReply,
+    CreateBlacklistRequest, CreateTopicRewriteRuleReply, CreateTopicRewriteRuleRequest,
+    CreateTraceReply, CreateTraceRequest, CreateUserReply, CreateUserRequest, DelayMessageRaw,
+    DeleteAclReply, DeleteAclRequest, DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest,
+    DeleteBlacklistReply, DeleteBlacklistRequest, DeleteRetainMessageReply,
+    DeleteRetainMessageRequest, DeleteTopicRewriteRuleReply, DeleteTopicRewriteRuleRequest,
+    DeleteTraceReply, DeleteTraceRequest, DeleteUserReply, DeleteUserRequest,
+    EnableSlowSubScribeReply, EnableSlowSubscribeRequest, ListAclReply, ListAclRequest,
+    ListAlarmReply, ListAlarmRequest, ListAutoSubscribeRuleReply, ListAutoSubscribeRuleRequest,
+    ListBlacklistReply, ListBlacklistRequest, ListConnectionRaw, ListConnectionReply,
+    ListConnectionRequest, ListDelayMessageReply, ListDelayMessageRequest, ListRetainMessageReply,
+    ListRetainMessageRequest, ListSlowSubScribeRaw, ListSlowSubscribeNodeError,
+    ListSlowSubscribeReply, ListSlowSubscribeRequest, ListTopicReply, ListTopicRequest,
+    ListTopicRewriteRuleReply, ListTopicRewriteRuleRequest, ListTraceReply, ListTraceRequest,
+    ListUserReply, ListUserRequest, MqttTopic, RetainMessageRaw, StopTraceReply, StopTraceRequest,
+    TopicRewriteRuleRaw, TraceRaw,
 };
+use protocol::mqtt::common::{qos, RetainForwardRule};
+use storage_adapter::storage::StorageAdapter;
 use tonic::{Request, Response, Status};
 
+use crate::handler::auto_subscribe::{delete_auto_subscribe_rule, save_auto_subscribe_rule};
 use crate::handler::cache::CacheManager;
-use crate::observability::slow::sub::{read_slow_sub_record, SlowSubData};
+use crate::handler::delay_message::DelayMessageManager;
+use crate::handler::retain::{delete_retain_message_by_filter, list_retain_message};
+use crate::handler::topic_rewrite::{delete_topic_rewrite_rule, save_topic_rewrite_rule};
+use crate::observability::slow::sub::list_cluster_slow_sub;
+use crate::observability::trace::{trace_dir, TraceFilter};
 use crate::security::AuthDriver;
 use crate::server::connection_manager::ConnectionManager;
+use crate::storage::alarm::AlarmStorage;
 use crate::storage::cluster::ClusterStorage;
 
-pub struct GrpcAdminServices {
+pub struct GrpcAdminServices<S> {
     client_pool: Arc<ClientPool>,
     cache_manager: Arc<CacheManager>,
     connection_manager: Arc<ConnectionManager>,
+    delay_message_manager: Arc<DelayMessageManager<S>>,
 }
 
-impl GrpcAdminServices {
+impl<S> GrpcAdminServices<S> {
     pub fn new(
         client_pool: Arc<ClientPool>,
         cache_manager: Arc<CacheManager>,
         connection_manager: Arc<ConnectionManager>,
+        delay_message_manager: Arc<DelayMessageManager<S>>,
     ) -> Self {
         GrpcAdminServices {
             client_pool,
             cache_manager,
             connection_manager,
+            delay_message_manager,
         }
     }
 }
 
 #[tonic::async_trait]
-impl MqttBrokerAdminService for GrpcAdminServices {
+impl<S> MqttBrokerAdminService for GrpcAdminServices<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
     // --- cluster ---
     async fn cluster_status(
         &self,
@@ -303,33 +328,38 @@ impl MqttBrokerAdminService for GrpcAdminServices {
         request: Request<ListSlowSubscribeRequest>,
     ) -> Result<Response<ListSlowSubscribeReply>, Status> {
         let list_slow_subscribe_request = request.into_inner();
-        let mut list_slow_subscribe_raw: Vec<ListSlowSubScribeRaw> = Vec::new();
-        let mqtt_config = broker_mqtt_conf();
-        if self.cache_manager.get_slow_sub_config().enable {
-            let path = mqtt_config.log.log_path.clone();
-            let path_buf = get_project_root()?.join(path.replace("./", "") + "/slow_sub.log");
-            let deque = read_slow_sub_record(list_slow_subscribe_request, path_buf)?;
-            for slow_sub_data in deque {
-                match serde_json::from_str::<SlowSubData>(slow_sub_data.as_str()) {
-                    Ok(data) => {
-                        let raw = ListSlowSubScribeRaw {
-                            client_id: data.client_id,
-                            topic: data.topic,
-                            time_ms: data.time_ms,
-                            node_info: data.node_info,
-                            create_time: data.create_time,
-                            sub_name: data.sub_name,
-                        };
-                        list_slow_subscribe_raw.push(raw);
-                    }
-                    Err(e) => {
-                        return Err(Status::cancelled(e.to_string()));
-                    }
-                }
-            }
-        }
+        let list = match list_cluster_slow_sub(
+            &self.client_pool,
+            &self.cache_manager,
+            list_slow_subscribe_request,
+        )
+        .await
+        {
+            Ok(list) => list,
+            Err(e) => return Err(Status::cancelled(e.to_string())),
+        };
+        let list_slow_subscribe_raw = list
+            .list
+            .into_iter()
+            .map(|data| ListSlowSubScribeRaw {
+                client_id: data.client_id,
+                topic: data.topic,
+                time_ms: data.time_ms,
+                node_info: data.node_info,
+                create_time: data.create_time,
+                sub_name: data.sub_name,
+                internal_ms: data.internal_ms,
+                response_ms: data.response_ms,
+            })
+            .collect();
+        let node_errors = list
+            .node_errors
+            .into_iter()
+            .map(|(node_id, error)| ListSlowSubscribeNodeError { node_id, error })
+            .collect();
         Ok(Response::new(ListSlowSubscribeReply {
             list_slow_subscribe_raw,
+            node_errors,
         }))
     }
 
@@ -376,4 +406,307 @@ impl MqttBrokerAdminService for GrpcAdminServices {
 
         Ok(Response::new(reply))
     }
+
+    // --- delay message ---
+    async fn mqtt_broker_list_delay_message(
+        &self,
+        request: Request<ListDelayMessageRequest>,
+    ) -> Result<Response<ListDelayMessageReply>, Status> {
+        let req = request.into_inner();
+        let delay_messages = self
+            .delay_message_manager
+            .list(&req.topic_name)
+            .into_iter()
+            .map(|message| DelayMessageRaw {
+                delay_id: message.delay_id,
+                client_id: message.client_id,
+                topic_name: message.topic_name,
+                deliver_time: message.deliver_time,
+                create_time: message.create_time,
+            })
+            .collect();
+        Ok(Response::new(ListDelayMessageReply { delay_messages }))
+    }
+
+    async fn mqtt_broker_cancel_delay_message(
+        &self,
+        request: Request<CancelDelayMessageRequest>,
+    ) -> Result<Response<CancelDelayMessageReply>, Status> {
+        let req = request.into_inner();
+        match self.delay_message_manager.cancel(&req.delay_id).await {
+            Ok(()) => Ok(Response::new(CancelDelayMessageReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    // --- retain message ---
+    async fn mqtt_broker_list_retain_message(
+        &self,
+        request: Request<ListRetainMessageRequest>,
+    ) -> Result<Response<ListRetainMessageReply>, Status> {
+        let req = request.into_inner();
+        let retain_messages =
+            list_retain_message(&self.cache_manager, &req.topic_filter, req.limit as usize)
+                .into_iter()
+                .map(|entry| RetainMessageRaw {
+                    topic_name: entry.topic_name,
+                    client_id: entry.message.client_id,
+                    qos: entry.message.qos as u32,
+                    payload: entry.message.payload.to_vec(),
+                    create_time: entry.message.create_time,
+                    expire_at: entry.expire_at,
+                })
+                .collect();
+        Ok(Response::new(ListRetainMessageReply { retain_messages }))
+    }
+
+    async fn mqtt_broker_delete_retain_message(
+        &self,
+        request: Request<DeleteRetainMessageRequest>,
+    ) -> Result<Response<DeleteRetainMessageReply>, Status> {
+        let req = request.into_inner();
+        match delete_retain_message_by_filter(
+            &self.cache_manager,
+            &self.client_pool,
+            &req.topic_filter,
+        )
+        .await
+        {
+            Ok(deleted_num) => Ok(Response::new(DeleteRetainMessageReply {
+                deleted_num: deleted_num as u64,
+            })),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    // --- topic rewrite rule ---
+    async fn mqtt_broker_list_topic_rewrite_rule(
+        &self,
+        _: Request<ListTopicRewriteRuleRequest>,
+    ) -> Result<Response<ListTopicRewriteRuleReply>, Status> {
+        let mut topic_rewrite_rules: Vec<TopicRewriteRuleRaw> = self
+            .cache_manager
+            .topic_rewrite_rule
+            .iter()
+            .map(|entry| TopicRewriteRuleRaw {
+                action: entry.rule.action.to_string(),
+                source_topic: entry.rule.source_topic.clone(),
+                dest_topic: entry.rule.dest_topic.clone(),
+                regex: entry.rule.regex.clone(),
+                create_time: entry.rule.create_time,
+            })
+            .collect();
+        topic_rewrite_rules.sort_by_key(|rule| rule.create_time);
+        Ok(Response::new(ListTopicRewriteRuleReply {
+            topic_rewrite_rules,
+        }))
+    }
+
+    async fn mqtt_broker_create_topic_rewrite_rule(
+        &self,
+        request: Request<CreateTopicRewriteRuleRequest>,
+    ) -> Result<Response<CreateTopicRewriteRuleReply>, Status> {
+        let req = request.into_inner();
+        let action = match TopicRewriteAction::try_from(req.action.as_str()) {
+            Ok(action) => action,
+            Err(e) => return Err(Status::cancelled(e.to_string())),
+        };
+        let rule = MqttTopicRewriteRule::new(action, req.source_topic, req.dest_topic, req.regex);
+        match save_topic_rewrite_rule(&self.cache_manager, &self.client_pool, rule).await {
+            Ok(()) => Ok(Response::new(CreateTopicRewriteRuleReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn mqtt_broker_delete_topic_rewrite_rule(
+        &self,
+        request: Request<DeleteTopicRewriteRuleRequest>,
+    ) -> Result<Response<DeleteTopicRewriteRuleReply>, Status> {
+        let req = request.into_inner();
+        let action = match TopicRewriteAction::try_from(req.action.as_str()) {
+            Ok(action) => action,
+            Err(e) => return Err(Status::cancelled(e.to_string())),
+        };
+        match delete_topic_rewrite_rule(
+            &self.cache_manager,
+            &self.client_pool,
+            action,
+            req.source_topic,
+        )
+        .await
+        {
+            Ok(()) => Ok(Response::new(DeleteTopicRewriteRuleReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    // --- auto subscribe rule ---
+    async fn mqtt_broker_list_auto_subscribe_rule(
+        &self,
+        _: Request<ListAutoSubscribeRuleRequest>,
+    ) -> Result<Response<ListAutoSubscribeRuleReply>, Status> {
+        let mut auto_subscribe_rules: Vec<AutoSubscribeRuleRaw> = self
+            .cache_manager
+            .auto_subscribe_rule
+            .iter()
+            .map(|entry| AutoSubscribeRuleRaw {
+                topic: entry.topic.clone(),
+                qos: entry.qos as u32,
+                no_local: entry.no_local,
+                retain_as_published: entry.retain_as_published,
+                retained_handling: u8::from(entry.retained_handling.clone()) as u32,
+                username: entry.username.clone(),
+                create_time: entry.create_time,
+            })
+            .collect();
+        auto_subscribe_rules.sort_by_key(|rule| rule.create_time);
+        Ok(Response::new(ListAutoSubscribeRuleReply {
+            auto_subscribe_rules,
+        }))
+    }
+
+    async fn mqtt_broker_create_auto_subscribe_rule(
+        &self,
+        request: Request<CreateAutoSubscribeRuleRequest>,
+    ) -> Result<Response<CreateAutoSubscribeRuleReply>, Status> {
+        let req = request.into_inner();
+        let sub_qos = if let Some(sub_qos) = qos(req.qos as u8) {
+            sub_qos
+        } else {
+            return Err(Status::cancelled(format!("invalid qos {}", req.qos)));
+        };
+        let retained_handling = match req.retained_handling {
+            0 => RetainForwardRule::OnEverySubscribe,
+            1 => RetainForwardRule::OnNewSubscribe,
+            2 => RetainForwardRule::Never,
+            _ => {
+                return Err(Status::cancelled(format!(
+                    "invalid retained handling {}",
+                    req.retained_handling
+                )))
+            }
+        };
+        let rule = MqttAutoSubscribeRule::new(
+            req.topic,
+            sub_qos,
+            req.no_local,
+            req.retain_as_published,
+            retained_handling,
+            req.username,
+        );
+        match save_auto_subscribe_rule(&self.cache_manager, &self.client_pool, rule).await {
+            Ok(()) => Ok(Response::new(CreateAutoSubscribeRuleReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn mqtt_broker_delete_auto_subscribe_rule(
+        &self,
+        request: Request<DeleteAutoSubscribeRuleRequest>,
+    ) -> Result<Response<DeleteAutoSubscribeRuleReply>, Status> {
+        let req = request.into_inner();
+        match delete_auto_subscribe_rule(
+            &self.cache_manager,
+            &self.client_pool,
+            req.username,
+            req.topic,
+        )
+        .await
+        {
+            Ok(()) => Ok(Response::new(DeleteAutoSubscribeRuleReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+    async fn mqtt_broker_list_alarm(
+        &self,
+        request: Request<ListAlarmRequest>,
+    ) -> Result<Response<ListAlarmReply>, Status> {
+        let req = request.into_inner();
+        let storage = AlarmStorage::new(self.client_pool.clone());
+        match storage.list(req.only_active, req.limit).await {
+            Ok(list) => {
+                let alarms = list
+                    .into_iter()
+                    .map(|alarm| AlarmRaw {
+                        name: alarm.name,
+                        broker_id: alarm.broker_id,
+                        message: alarm.message,
+                        details: alarm.details,
+                        activate_time: alarm.activate_time,
+                        deactivate_time: alarm.deactivate_time,
+                    })
+                    .collect();
+                Ok(Response::new(ListAlarmReply { alarms }))
+            }
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn mqtt_broker_create_trace(
+        &self,
+        request: Request<CreateTraceRequest>,
+    ) -> Result<Response<CreateTraceReply>, Status> {
+        let req = request.into_inner();
+        let filter = match TraceFilter::new(&req.trace_type, req.value) {
+            Ok(filter) => filter,
+            Err(e) => return Err(Status::cancelled(e.to_string())),
+        };
+        match self.cache_manager.trace_manager.start(
+            &trace_dir(),
+            &req.name,
+            filter,
+            req.duration_sec,
+        ) {
+            Ok(()) => Ok(Response::new(CreateTraceReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn mqtt_broker_list_trace(
+        &self,
+        _: Request<ListTraceRequest>,
+    ) -> Result<Response<ListTraceReply>, Status> {
+        let now = now_second();
+        let traces = self
+            .cache_manager
+            .trace_manager
+            .list()
+            .into_iter()
+            .map(|trace| TraceRaw {
+                name: trace.name.clone(),
+                trace_type: trace.filter.trace_type().to_string(),
+                value: trace.filter.value().to_string(),
+                start_time: trace.start_time,
+                end_time: trace.end_time,
+                status: if trace.is_running(now) {
+                    "running".to_string()
+                } else {
+                    "stopped".to_string()
+                },
+            })
+            .collect();
+        Ok(Response::new(ListTraceReply { traces }))
+    }
+
+    async fn mqtt_broker_stop_trace(
+        &self,
+        request: Request<StopTraceRequest>,
+    ) -> Result<Response<StopTraceReply>, Status> {
+        let req = request.into_inner();
+        match self.cache_manager.trace_manager.stop(&req.name) {
+            Ok(()) => Ok(Response::new(StopTraceReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn mqtt_broker_delete_trace(
+        &self,
+        request: Request<DeleteTraceRequest>,
+    ) -> Result<Response<DeleteTraceReply>, Status> {
+        let req = request.into_inner();
+        match self.cache_manager.trace_manager.delete(&req.name).await {
+            Ok(()) => Ok(Response::new(DeleteTraceReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
 }
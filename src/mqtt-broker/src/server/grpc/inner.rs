@@ -19,18 +19,23 @@ use log::debug;
 use metadata_struct::mqtt::lastwill::LastWillData;
 use protocol::broker_mqtt::broker_mqtt_inner::mqtt_broker_inner_service_server::MqttBrokerInnerService;
 use protocol::broker_mqtt::broker_mqtt_inner::{
-    DeleteSessionReply, DeleteSessionRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
-    UpdateCacheReply, UpdateCacheRequest,
+    DeleteSessionReply, DeleteSessionRequest, ListNodeSlowSubscribeReply,
+    ListNodeSlowSubscribeRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
+    TakeoverSessionReply, TakeoverSessionRequest, UpdateCacheReply, UpdateCacheRequest,
 };
 use storage_adapter::storage::StorageAdapter;
 use tonic::{Request, Response, Status};
 
 use crate::handler::cache::{update_cache_metadata, CacheManager};
 use crate::handler::lastwill::send_last_will_message;
+use crate::handler::takeover::release_session;
+use crate::observability::webhook::{WebhookEvent, WebhookEventType};
+use crate::server::connection_manager::ConnectionManager;
 use crate::subscribe::subscribe_manager::SubscribeManager;
 
 pub struct GrpcInnerServices<S> {
     cache_manager: Arc<CacheManager>,
+    connection_manager: Arc<ConnectionManager>,
     subscribe_manager: Arc<SubscribeManager>,
     client_pool: Arc<ClientPool>,
     message_storage_adapter: Arc<S>,
@@ -39,12 +44,14 @@ pub struct GrpcInnerServices<S> {
 impl<S> GrpcInnerServices<S> {
     pub fn new(
         metadata_cache: Arc<CacheManager>,
+        connection_manager: Arc<ConnectionManager>,
         subscribe_manager: Arc<SubscribeManager>,
         client_pool: Arc<ClientPool>,
         message_storage_adapter: Arc<S>,
     ) -> Self {
         GrpcInnerServices {
             cache_manager: metadata_cache,
+            connection_manager,
             subscribe_manager,
             client_pool,
             message_storage_adapter,
@@ -62,7 +69,12 @@ where
         request: Request<UpdateCacheRequest>,
     ) -> Result<Response<UpdateCacheReply>, Status> {
         let req = request.into_inner();
-        update_cache_metadata(req);
+        if self.cache_manager.cluster_name != req.cluster_name {
+            return Err(Status::cancelled("Cluster name does not match".to_string()));
+        }
+        if let Err(e) = update_cache_metadata(&self.cache_manager, req) {
+            return Err(Status::cancelled(e.to_string()));
+        }
         return Ok(Response::new(UpdateCacheReply::default()));
     }
 
@@ -85,6 +97,12 @@ where
                 .await?;
             self.cache_manager.remove_session(&client_id);
             self.subscribe_manager.stop_push_by_client_id(&client_id);
+            self.cache_manager
+                .webhook_manager
+                .emit(WebhookEventType::SessionTerminated, || {
+                    WebhookEvent::new(WebhookEventType::SessionTerminated, &client_id)
+                        .data(serde_json::json!({ "reason": "expired" }))
+                });
         }
 
         return Ok(Response::new(DeleteSessionReply::default()));
@@ -124,4 +142,60 @@ where
             }
         }
     }
+
+    async fn takeover_session(
+        &self,
+        request: Request<TakeoverSessionRequest>,
+    ) -> Result<Response<TakeoverSessionReply>, Status> {
+        let req = request.into_inner();
+        debug!(
+            "Received request from broker {} to take over the session of client {}",
+            req.new_broker_id, req.client_id
+        );
+        if self.cache_manager.cluster_name != req.cluster_name {
+            return Err(Status::cancelled("Cluster name does not match".to_string()));
+        }
+
+        match release_session(
+            &self.cache_manager,
+            &self.connection_manager,
+            &self.subscribe_manager,
+            &self.message_storage_adapter,
+            &req.client_id,
+        )
+        .await
+        {
+            Ok(reply) => Ok(Response::new(reply)),
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
+
+    async fn list_node_slow_subscribe(
+        &self,
+        request: Request<ListNodeSlowSubscribeRequest>,
+    ) -> Result<Response<ListNodeSlowSubscribeReply>, Status> {
+        let req = request.into_inner();
+        if self.cache_manager.cluster_name != req.cluster_name {
+            return Err(Status::cancelled("Cluster name does not match".to_string()));
+        }
+
+        let expire_interval_sec = self.cache_manager.get_slow_sub_config().expire_interval_sec;
+        let list = self.cache_manager.slow_sub_store.list(
+            &req.sub_name,
+            &req.client_id,
+            &req.topic,
+            req.limit as usize,
+            expire_interval_sec,
+        );
+        let mut slow_subscribes = Vec::new();
+        for data in list {
+            match serde_json::to_vec(&data) {
+                Ok(raw) => slow_subscribes.push(raw),
+                Err(e) => return Err(Status::internal(e.to_string())),
+            }
+        }
+        Ok(Response::new(ListNodeSlowSubscribeReply {
+            slow_subscribes,
+        }))
+    }
 }
@@ -15,6 +15,7 @@
 use std::sync::Arc;
 
 use common_base::error::common::CommonError;
+use common_base::telemetry::grpc_server_span;
 use grpc_clients::pool::ClientPool;
 use log::info;
 use protocol::broker_mqtt::broker_mqtt_admin::mqtt_broker_admin_service_server::MqttBrokerAdminServiceServer;
@@ -24,6 +25,7 @@ use tonic::transport::Server;
 
 use super::inner::GrpcInnerServices;
 use crate::handler::cache::CacheManager;
+use crate::handler::delay_message::DelayMessageManager;
 use crate::server::connection_manager::ConnectionManager;
 use crate::server::grpc::admin::services::GrpcAdminServices;
 use crate::subscribe::subscribe_manager::SubscribeManager;
@@ -35,6 +37,7 @@ pub struct GrpcServer<S> {
     subscribe_manager: Arc<SubscribeManager>,
     client_pool: Arc<ClientPool>,
     message_storage_adapter: Arc<S>,
+    delay_message_manager: Arc<DelayMessageManager<S>>,
 }
 
 impl<S> GrpcServer<S>
@@ -48,6 +51,7 @@ where
         connection_manager: Arc<ConnectionManager>,
         client_pool: Arc<ClientPool>,
         message_storage_adapter: Arc<S>,
+        delay_message_manager: Arc<DelayMessageManager<S>>,
     ) -> Self {
         Self {
             port,
@@ -56,6 +60,7 @@ where
             subscribe_manager,
             client_pool,
             message_storage_adapter,
+            delay_message_manager,
         }
     }
     pub async fn start(&self) -> Result<(), CommonError> {
@@ -63,6 +68,7 @@ where
         info!("Broker Grpc Server start success. port:{}", self.port);
         let inner_handler = GrpcInnerServices::new(
             self.metadata_cache.clone(),
+            self.connection_manager.clone(),
             self.subscribe_manager.clone(),
             self.client_pool.clone(),
             self.message_storage_adapter.clone(),
@@ -71,8 +77,10 @@ where
             self.client_pool.clone(),
             self.metadata_cache.clone(),
             self.connection_manager.clone(),
+            self.delay_message_manager.clone(),
         );
         Server::builder()
+            .trace_fn(grpc_server_span)
             .add_service(MqttBrokerInnerServiceServer::new(inner_handler))
             .add_service(MqttBrokerAdminServiceServer::new(admin_handler))
             .serve(addr)
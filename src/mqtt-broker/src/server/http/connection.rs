@@ -12,11 +12,227 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use axum::extract::State;
-use common_base::http_response::success_response;
+use std::net::SocketAddr;
 
+use axum::extract::{Query, State};
+use common_base::http_response::{error_response, success_response};
+use metadata_struct::mqtt::connection::MQTTConnection;
+use protocol::mqtt::common::DisconnectReasonCode;
+use serde::{Deserialize, Serialize};
+
+use super::page::paginate;
 use super::server::HttpServerState;
+use crate::handler::cache::CacheManager;
+use crate::handler::connection::{disconnect_connection, send_disconnect_by_reason};
+use crate::server::connection::NetworkConnection;
+
+#[derive(Deserialize, Default)]
+pub struct ConnectionListParams {
+    pub page: Option<usize>,
+    pub limit: Option<usize>,
+    pub username: Option<String>,
+    pub ip: Option<String>,
+    pub client_id_prefix: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ConnectionKickParams {
+    pub client_id: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ConnectionRaw {
+    pub connect_id: u64,
+    pub client_id: String,
+    pub username: String,
+    pub peer_addr: String,
+    pub protocol: String,
+    pub listener: String,
+    pub keep_alive: u16,
+    pub connected_at: u64,
+    // QoS 1/2 messages sent to the client and not yet acknowledged
+    pub send_inflight: isize,
+    // QoS 1/2 messages received from the client and not yet completed
+    pub receive_inflight: isize,
+    pub subscriptions: Vec<String>,
+}
+
+pub async fn connection_list(
+    State(state): State<HttpServerState>,
+    Query(params): Query<ConnectionListParams>,
+) -> String {
+    let mut results = Vec::new();
+    for conn in state.cache_manager.connection_info.iter() {
+        if !connection_match(&params, &conn) {
+            continue;
+        }
+        let network = state.connection_manager.get_connect(conn.connect_id);
+        results.push(build_connection_raw(&state.cache_manager, &conn, network));
+    }
+    results.sort_by_key(|raw| raw.connect_id);
+    success_response(paginate(results, params.page, params.limit))
+}
+
+/// Kick the client off this broker. The client is sent a DISCONNECT with reason
+/// Administrative action, and its network connection is closed even when cleaning
+/// up the session state fails.
+pub async fn connection_kick(
+    State(state): State<HttpServerState>,
+    Query(params): Query<ConnectionKickParams>,
+) -> String {
+    let connect_id = if let Some(connect_id) = state.cache_manager.get_connect_id(&params.client_id)
+    {
+        connect_id
+    } else {
+        return error_response(format!(
+            "Client {} has no active connection on this broker",
+            params.client_id
+        ));
+    };
+
+    send_disconnect_by_reason(
+        &state.connection_manager,
+        connect_id,
+        DisconnectReasonCode::AdministrativeAction,
+    )
+    .await;
+
+    match disconnect_connection(
+        &params.client_id,
+        connect_id,
+        &state.cache_manager,
+        &state.client_pool,
+        &state.connection_manager,
+        &state.subscribe_manager,
+    )
+    .await
+    {
+        Ok(()) => success_response(connect_id),
+        Err(e) => {
+            // disconnect_connection returns before closing the socket when the
+            // session update fails, the client must not stay connected
+            state.connection_manager.close_connect(connect_id).await;
+            error_response(e.to_string())
+        }
+    }
+}
+
+fn connection_match(params: &ConnectionListParams, conn: &MQTTConnection) -> bool {
+    if let Some(username) = &params.username {
+        if conn.login_user != *username {
+            return false;
+        }
+    }
+
+    if let Some(prefix) = &params.client_id_prefix {
+        if !conn.client_id.starts_with(prefix) {
+            return false;
+        }
+    }
+
+    if let Some(ip) = &params.ip {
+        let peer_ip = match conn.source_ip_addr.parse::<SocketAddr>() {
+            Ok(addr) => addr.ip().to_string(),
+            Err(_) => conn.source_ip_addr.clone(),
+        };
+        if peer_ip != *ip {
+            return false;
+        }
+    }
+    true
+}
+
+fn build_connection_raw(
+    cache_manager: &CacheManager,
+    conn: &MQTTConnection,
+    network: Option<NetworkConnection>,
+) -> ConnectionRaw {
+    let (peer_addr, protocol, listener) = if let Some(network) = network {
+        (
+            network.addr.to_string(),
+            network.protocol.map(String::from).unwrap_or_default(),
+            network.connection_type.to_string(),
+        )
+    } else {
+        (conn.source_ip_addr.clone(), "".to_string(), "".to_string())
+    };
+
+    ConnectionRaw {
+        connect_id: conn.connect_id,
+        client_id: conn.client_id.clone(),
+        username: conn.login_user.clone(),
+        peer_addr,
+        protocol,
+        listener,
+        keep_alive: conn.keep_alive,
+        connected_at: conn.create_time,
+        send_inflight: conn.get_send_qos_message(),
+        receive_inflight: conn.get_recv_qos_message(),
+        subscriptions: client_subscriptions(cache_manager, &conn.client_id),
+    }
+}
+
+pub(crate) fn client_subscriptions(cache_manager: &CacheManager, client_id: &str) -> Vec<String> {
+    let mut paths: Vec<String> =
+        if let Some(filters) = cache_manager.subscribe_filter.get(client_id) {
+            filters.iter().map(|raw| raw.key().clone()).collect()
+        } else {
+            Vec::new()
+        };
+    paths.sort();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use metadata_struct::mqtt::connection::{ConnectionConfig, MQTTConnection};
+
+    use super::{connection_match, ConnectionListParams};
+
+    fn build_conn(client_id: &str, user: &str, addr: &str) -> MQTTConnection {
+        let mut conn = MQTTConnection::new(ConnectionConfig {
+            connect_id: 1,
+            client_id: client_id.to_string(),
+            receive_maximum: 10,
+            max_packet_size: 1024,
+            topic_alias_max: 10,
+            request_problem_info: 0,
+            keep_alive: 60,
+            source_ip_addr: addr.to_string(),
+        });
+        conn.login_success(user.to_string());
+        conn
+    }
+
+    #[test]
+    fn connection_match_test() {
+        let conn = build_conn("sensor-01", "admin", "10.0.0.5:51234");
+        assert!(connection_match(&ConnectionListParams::default(), &conn));
+
+        let params = ConnectionListParams {
+            username: Some("admin".to_string()),
+            ip: Some("10.0.0.5".to_string()),
+            client_id_prefix: Some("sensor-".to_string()),
+            ..Default::default()
+        };
+        assert!(connection_match(&params, &conn));
+
+        let params = ConnectionListParams {
+            username: Some("guest".to_string()),
+            ..Default::default()
+        };
+        assert!(!connection_match(&params, &conn));
+
+        let params = ConnectionListParams {
+            ip: Some("10.0.0.6".to_string()),
+            ..Default::default()
+        };
+        assert!(!connection_match(&params, &conn));
 
-pub async fn connection_list(State(_): State<HttpServerState>) -> String {
-    success_response("data")
+        let params = ConnectionListParams {
+            client_id_prefix: Some("camera-".to_string()),
+            ..Default::default()
+        };
+        assert!(!connection_match(&params, &conn));
+    }
 }
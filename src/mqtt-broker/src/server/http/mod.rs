@@ -13,6 +13,9 @@
 // limitations under the License.
 
 mod connection;
+mod page;
 mod prometheus;
 mod publish;
 pub mod server;
+mod session;
+mod trace;
@@ -0,0 +1,70 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::Serialize;
+
+const DEFAULT_PAGE_LIMIT: usize = 100;
+const MAX_PAGE_LIMIT: usize = 1000;
+
+#[derive(Serialize, Debug)]
+pub struct PageReply<T> {
+    pub total: usize,
+    pub page: usize,
+    pub limit: usize,
+    pub data: Vec<T>,
+}
+
+// Cut one page out of `list`, pages start at 1 and the limit is capped so that a
+// single request cannot dump the whole connection table.
+pub fn paginate<T>(list: Vec<T>, page: Option<usize>, limit: Option<usize>) -> PageReply<T> {
+    let page = page.unwrap_or(1).max(1);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT);
+    let total = list.len();
+    let data = list
+        .into_iter()
+        .skip((page - 1).saturating_mul(limit))
+        .take(limit)
+        .collect();
+    PageReply {
+        total,
+        page,
+        limit,
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::paginate;
+
+    #[test]
+    fn paginate_test() {
+        let list: Vec<u32> = (0..25).collect();
+
+        let reply = paginate(list.clone(), Some(2), Some(10));
+        assert_eq!(reply.total, 25);
+        assert_eq!(reply.data, (10..20).collect::<Vec<u32>>());
+
+        let reply = paginate(list.clone(), Some(3), Some(10));
+        assert_eq!(reply.data, (20..25).collect::<Vec<u32>>());
+
+        let reply = paginate(list.clone(), Some(4), Some(10));
+        assert!(reply.data.is_empty());
+
+        let reply = paginate(list, None, Some(0));
+        assert_eq!(reply.page, 1);
+        assert_eq!(reply.limit, 1);
+        assert_eq!(reply.data, vec![0]);
+    }
+}
@@ -13,27 +13,52 @@
 // limitations under the License.
 
 use std::net::SocketAddr;
+use std::sync::Arc;
 
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::Router;
 use common_base::config::broker_mqtt::broker_mqtt_conf;
 use common_base::error::common::CommonError;
+use grpc_clients::pool::ClientPool;
 use log::info;
 
-use super::connection::connection_list;
+use super::connection::{connection_kick, connection_list};
 use super::prometheus::metrics;
 use super::publish::http_publish;
+use super::session::session_list;
+use super::trace::trace_download;
+use crate::handler::cache::CacheManager;
+use crate::server::connection_manager::ConnectionManager;
+use crate::subscribe::subscribe_manager::SubscribeManager;
 
 pub const ROUTE_PUBLISTH: &str = "/publish";
 pub const ROUTE_CONNECTION: &str = "/connection";
+pub const ROUTE_CONNECTION_KICK: &str = "/connection/kick";
+pub const ROUTE_SESSION: &str = "/session";
 pub const ROUTE_METRICS: &str = "/metrics";
+pub const ROUTE_TRACE_DOWNLOAD: &str = "/trace/download";
 
 #[derive(Clone)]
-pub struct HttpServerState {}
+pub struct HttpServerState {
+    pub cache_manager: Arc<CacheManager>,
+    pub connection_manager: Arc<ConnectionManager>,
+    pub subscribe_manager: Arc<SubscribeManager>,
+    pub client_pool: Arc<ClientPool>,
+}
 
 impl HttpServerState {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(
+        cache_manager: Arc<CacheManager>,
+        connection_manager: Arc<ConnectionManager>,
+        subscribe_manager: Arc<SubscribeManager>,
+        client_pool: Arc<ClientPool>,
+    ) -> Self {
+        Self {
+            cache_manager,
+            connection_manager,
+            subscribe_manager,
+            client_pool,
+        }
     }
 }
 
@@ -50,11 +75,17 @@ pub async fn start_http_server(state: HttpServerState) -> Result<(), CommonError
     Ok(())
 }
 
+/// The HTTP API has no authentication of its own. `/connection/kick` and the
+/// listing routes expose and act on client state, so `http_port` must only be
+/// reachable from the admin network.
 fn routes_v1(state: HttpServerState) -> Router {
     let meta = Router::new()
         .route(ROUTE_PUBLISTH, get(http_publish))
         .route(ROUTE_CONNECTION, get(connection_list))
-        .route(ROUTE_METRICS, get(metrics));
+        .route(ROUTE_CONNECTION_KICK, post(connection_kick))
+        .route(ROUTE_SESSION, get(session_list))
+        .route(ROUTE_METRICS, get(metrics))
+        .route(ROUTE_TRACE_DOWNLOAD, get(trace_download));
 
     let app = Router::new().merge(meta);
     app.with_state(state)
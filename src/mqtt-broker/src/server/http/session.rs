@@ -0,0 +1,70 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::extract::{Query, State};
+use common_base::http_response::success_response;
+use serde::{Deserialize, Serialize};
+
+use super::connection::client_subscriptions;
+use super::page::paginate;
+use super::server::HttpServerState;
+
+#[derive(Deserialize, Default)]
+pub struct SessionListParams {
+    pub page: Option<usize>,
+    pub limit: Option<usize>,
+    pub client_id_prefix: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SessionRaw {
+    pub client_id: String,
+    pub connected: bool,
+    pub connect_id: Option<u64>,
+    pub broker_id: Option<u64>,
+    pub session_expiry: u64,
+    pub is_contain_last_will: bool,
+    pub create_time: u64,
+    pub reconnect_time: Option<u64>,
+    pub distinct_time: Option<u64>,
+    pub subscriptions: Vec<String>,
+}
+
+pub async fn session_list(
+    State(state): State<HttpServerState>,
+    Query(params): Query<SessionListParams>,
+) -> String {
+    let mut results = Vec::new();
+    for session in state.cache_manager.session_info.iter() {
+        if let Some(prefix) = &params.client_id_prefix {
+            if !session.client_id.starts_with(prefix) {
+                continue;
+            }
+        }
+        results.push(SessionRaw {
+            client_id: session.client_id.clone(),
+            connected: session.connection_id.is_some(),
+            connect_id: session.connection_id,
+            broker_id: session.broker_id,
+            session_expiry: session.session_expiry,
+            is_contain_last_will: session.is_contain_last_will,
+            create_time: session.create_time,
+            reconnect_time: session.reconnect_time,
+            distinct_time: session.distinct_time,
+            subscriptions: client_subscriptions(&state.cache_manager, &session.client_id),
+        });
+    }
+    results.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+    success_response(paginate(results, params.page, params.limit))
+}
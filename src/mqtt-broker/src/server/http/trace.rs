@@ -0,0 +1,47 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::extract::{Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use common_base::http_response::error_response;
+use serde::Deserialize;
+
+use super::server::HttpServerState;
+
+#[derive(Deserialize)]
+pub struct TraceDownloadParams {
+    pub name: String,
+}
+
+// Downloads the events recorded by a trace of this broker as JSON lines
+pub async fn trace_download(
+    State(state): State<HttpServerState>,
+    Query(params): Query<TraceDownloadParams>,
+) -> Response {
+    match state.cache_manager.trace_manager.read(&params.name).await {
+        Ok(data) => (
+            [
+                (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}.log\"", params.name),
+                ),
+            ],
+            data,
+        )
+            .into_response(),
+        Err(e) => (StatusCode::NOT_FOUND, error_response(e.to_string())).into_response(),
+    }
+}
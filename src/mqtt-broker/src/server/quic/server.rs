@@ -0,0 +1,357 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use futures_util::StreamExt;
+use grpc_clients::pool::ClientPool;
+use log::{debug, error, info};
+use protocol::mqtt::codec::MqttCodec;
+use protocol::mqtt::common::MqttPacket;
+use quinn::crypto::rustls::QuicServerConfig;
+use quinn::{Connection, Endpoint, Incoming, RecvStream, VarInt, ZeroRttAccepted};
+use storage_adapter::storage::StorageAdapter;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio_rustls::rustls::{self, ServerConfig};
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use crate::handler::cache::CacheManager;
+use crate::handler::command::Command;
+use crate::handler::delay_message::DelayMessageManager;
+use crate::handler::error::MqttBrokerError;
+use crate::handler::validator::establish_connection_check;
+use crate::observability::metrics::packets::{
+    record_received_error_metrics, record_received_metrics,
+};
+use crate::observability::slow::request::try_record_total_request_ms;
+use crate::security::AuthDriver;
+use crate::server::connection::{NetworkConnection, NetworkConnectionType};
+use crate::server::connection_manager::ConnectionManager;
+use crate::server::packet::{RequestPackage, ResponsePackage};
+use crate::server::tcp::handler::handler_process;
+use crate::server::tcp::response::response_process;
+use crate::server::tcp::tls_server::{load_certs, load_key};
+use crate::subscribe::subscribe_manager::SubscribeManager;
+
+// ALPN protocol negotiated by MQTT over QUIC clients
+const MQTT_ALPN: &[u8] = b"mqtt";
+
+/// MQTT over QUIC. Each QUIC connection carries one MQTT connection on its first
+/// bidirectional stream, and packets then go through the same handler and response
+/// queues as the TCP listener. A resumed client may send its CONNECT as 0-RTT early data,
+/// but nothing after it is handled before the handshake completes, since replayed early
+/// data could carry a PUBLISH that the broker would deliver twice.
+pub async fn start_quic_server<S>(
+    subscribe_manager: Arc<SubscribeManager>,
+    cache_manager: Arc<CacheManager>,
+    connection_manager: Arc<ConnectionManager>,
+    message_storage_adapter: Arc<S>,
+    client_pool: Arc<ClientPool>,
+    stop_sx: broadcast::Sender<bool>,
+    auth_driver: Arc<AuthDriver>,
+    delay_message_manager: Arc<DelayMessageManager<S>>,
+) -> Result<(), MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let conf = broker_mqtt_conf();
+    let command = Command::new(
+        cache_manager.clone(),
+        message_storage_adapter,
+        subscribe_manager.clone(),
+        client_pool.clone(),
+        connection_manager.clone(),
+        auth_driver.clone(),
+        delay_message_manager,
+    );
+
+    let endpoint = build_endpoint(conf.network.quic_port)?;
+
+    let (request_queue_sx, request_queue_rx) = mpsc::channel::<RequestPackage>(1000);
+    let (response_queue_sx, response_queue_rx) = mpsc::channel::<ResponsePackage>(1000);
+
+    acceptor_process(
+        conf.tcp_thread.accept_thread_num,
+        endpoint,
+        connection_manager.clone(),
+        stop_sx.clone(),
+        request_queue_sx,
+        cache_manager.clone(),
+    );
+
+    handler_process(
+        conf.tcp_thread.handler_thread_num,
+        request_queue_rx,
+        connection_manager.clone(),
+        response_queue_sx,
+        stop_sx.clone(),
+        command,
+    )
+    .await;
+
+    response_process(
+        conf.tcp_thread.response_thread_num,
+        connection_manager,
+        cache_manager,
+        subscribe_manager,
+        response_queue_rx,
+        client_pool,
+        auth_driver,
+        stop_sx,
+    )
+    .await;
+
+    info!(
+        "MQTT QUIC Server started successfully, listening port: {}",
+        conf.network.quic_port
+    );
+    Ok(())
+}
+
+fn build_endpoint(port: u32) -> Result<Endpoint, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    if conf.network.tls_cert.is_empty() || conf.network.tls_key.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "QUIC listener requires network.tls_cert and network.tls_key to be configured"
+                .to_string(),
+        ));
+    }
+    let certs = load_certs(Path::new(&conf.network.tls_cert))?;
+    let key = load_key(Path::new(&conf.network.tls_key))?;
+
+    let mut tls_config = ServerConfig::builder_with_protocol_versions(&[&rustls::version::TLS13])
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+    tls_config.alpn_protocols = vec![MQTT_ALPN.to_vec()];
+    // rustls only accepts early data for sessions resumed from its stateful session cache,
+    // which hands out each ticket once, so a replayed ClientHello falls back to a full
+    // handshake. The stateless ticketer must stay disabled for this to hold.
+    tls_config.max_early_data_size = u32::MAX;
+
+    let crypto = QuicServerConfig::try_from(tls_config)
+        .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+    let server_config = quinn::ServerConfig::with_crypto(Arc::new(crypto));
+
+    let addr: SocketAddr = format!("0.0.0.0:{}", port)
+        .parse()
+        .map_err(|e: std::net::AddrParseError| MqttBrokerError::CommonError(e.to_string()))?;
+    Ok(Endpoint::server(server_config, addr)?)
+}
+
+fn acceptor_process(
+    accept_thread_num: usize,
+    endpoint: Endpoint,
+    connection_manager: Arc<ConnectionManager>,
+    stop_sx: broadcast::Sender<bool>,
+    request_queue_sx: Sender<RequestPackage>,
+    cache_manager: Arc<CacheManager>,
+) {
+    for index in 1..=accept_thread_num {
+        let endpoint = endpoint.clone();
+        let connection_manager = connection_manager.clone();
+        let mut stop_rx = stop_sx.subscribe();
+        let request_queue_sx = request_queue_sx.clone();
+        let cache_manager = cache_manager.clone();
+        tokio::spawn(async move {
+            debug!("QUIC Server acceptor thread {} start successfully.", index);
+            loop {
+                select! {
+                    val = stop_rx.recv() => {
+                        if let Ok(flag) = val {
+                            if flag {
+                                endpoint.close(VarInt::from_u32(0), b"server stopped");
+                                debug!("QUIC Server acceptor thread {} stopped successfully.", index);
+                                break;
+                            }
+                        }
+                    }
+                    val = endpoint.accept() => {
+                        match val {
+                            Some(incoming) => {
+                                // The handshake runs in its own task so that a slow client
+                                // does not hold up the accept loop
+                                tokio::spawn(process_connection(
+                                    incoming,
+                                    connection_manager.clone(),
+                                    request_queue_sx.clone(),
+                                    cache_manager.clone(),
+                                ));
+                            }
+                            None => {
+                                debug!("QUIC endpoint closed, acceptor thread {} exits.", index);
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+async fn process_connection(
+    incoming: Incoming,
+    connection_manager: Arc<ConnectionManager>,
+    request_queue_sx: Sender<RequestPackage>,
+    cache_manager: Arc<CacheManager>,
+) {
+    let connecting = match incoming.accept() {
+        Ok(connecting) => connecting,
+        Err(e) => {
+            error!(
+                "QUIC accept failed to create connection with error message :{:?}",
+                e
+            );
+            return;
+        }
+    };
+
+    let (quic_connection, handshake) = match connecting.into_0rtt() {
+        Ok((quic_connection, handshake)) => (quic_connection, Some(handshake)),
+        Err(connecting) => match connecting.await {
+            Ok(quic_connection) => (quic_connection, None),
+            Err(e) => {
+                error!("QUIC handshake failed with error message :{:?}", e);
+                return;
+            }
+        },
+    };
+    let addr = quic_connection.remote_address();
+    info!("accept quic connection:{:?}", addr);
+
+    let (send_stream, recv_stream) = match quic_connection.accept_bi().await {
+        Ok(streams) => streams,
+        Err(e) => {
+            debug!(
+                "QUIC connection {} opened no stream, error message :{:?}",
+                addr, e
+            );
+            return;
+        }
+    };
+
+    let codec = MqttCodec::new(None);
+    let read_frame_stream = FramedRead::new(recv_stream, codec.clone());
+    let mut write_frame_stream = FramedWrite::new(send_stream, codec);
+
+    if !establish_connection_check(&addr, &connection_manager, &mut write_frame_stream).await {
+        quic_connection.close(VarInt::from_u32(0), b"connection refused");
+        return;
+    }
+
+    let (connection_stop_sx, connection_stop_rx) = mpsc::channel::<bool>(1);
+    let connection =
+        NetworkConnection::new(NetworkConnectionType::Quic, addr, Some(connection_stop_sx));
+    connection_manager.add_connection(connection.clone());
+    connection_manager.add_quic_write(connection.connection_id, write_frame_stream);
+
+    read_frame_process(
+        read_frame_stream,
+        quic_connection.clone(),
+        handshake,
+        connection,
+        request_queue_sx,
+        connection_stop_rx,
+        connection_manager,
+        cache_manager,
+    )
+    .await;
+    quic_connection.close(VarInt::from_u32(0), b"");
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn read_frame_process(
+    mut read_frame_stream: FramedRead<RecvStream, MqttCodec>,
+    quic_connection: Connection,
+    mut handshake: Option<ZeroRttAccepted>,
+    connection: NetworkConnection,
+    request_queue_sx: Sender<RequestPackage>,
+    mut connection_stop_rx: Receiver<bool>,
+    connection_manager: Arc<ConnectionManager>,
+    cache_manager: Arc<CacheManager>,
+) {
+    let network_type = NetworkConnectionType::Quic;
+    let mut early_connect = false;
+    loop {
+        select! {
+            val = connection_stop_rx.recv() => {
+                if let Some(flag) = val {
+                    if flag {
+                        debug!("QUIC connection 【{}】 acceptor thread stopped successfully.", connection.connection_id);
+                        break;
+                    }
+                }
+            }
+            val = read_frame_stream.next() => {
+                match val {
+                    Some(Ok(pack)) => {
+                        // Until the handshake completes the packets may come from early
+                        // data. The first CONNECT is handled right away, anything else
+                        // waits for the handshake to confirm the client.
+                        if let Some(zero_rtt_accepted) = handshake.take() {
+                            if !early_connect && matches!(pack, MqttPacket::Connect(..)) {
+                                early_connect = true;
+                                handshake = Some(zero_rtt_accepted);
+                            } else if !handshake_confirmed(zero_rtt_accepted, &quic_connection).await {
+                                debug!("QUIC connection 【{}】 lost before the handshake completed.", connection.connection_id);
+                                connection_manager.close_connect(connection.connection_id).await;
+                                break;
+                            }
+                        }
+
+                        record_received_metrics(&connection, &pack, &network_type);
+                        info!("revc quic packet:{:?}", pack);
+                        let package =
+                            RequestPackage::new(connection.connection_id, connection.addr, pack);
+
+                        match request_queue_sx.send(package.clone()).await {
+                            Ok(_) => {
+                                try_record_total_request_ms(cache_manager.clone(), package);
+                            }
+                            Err(err) => error!("Failed to write data to the request queue, error message: {:?}", err),
+                        }
+                    }
+                    Some(Err(e)) => {
+                        record_received_error_metrics(network_type.clone());
+                        debug!("QUIC connection parsing packet format error message :{:?}", e)
+                    }
+                    None => {
+                        // Unlike a TCP socket, a finished or reset QUIC stream is not
+                        // going to yield more data, so the connection and its write
+                        // stream are released here
+                        debug!("QUIC connection 【{}】 stream closed by the client.", connection.connection_id);
+                        connection_manager.close_connect(connection.connection_id).await;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The server side of ZeroRttAccepted resolves once the handshake is done, or when the
+// connection is lost before that, which only the close reason tells apart.
+async fn handshake_confirmed(
+    zero_rtt_accepted: ZeroRttAccepted,
+    quic_connection: &Connection,
+) -> bool {
+    zero_rtt_accepted.await;
+    quic_connection.close_reason().is_none()
+}
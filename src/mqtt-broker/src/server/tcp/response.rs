@@ -23,13 +23,16 @@ use tokio::select;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc::{self, Receiver, Sender};
 
+use crate::handler::auto_subscribe::auto_subscribe_after_connack;
 use crate::handler::cache::CacheManager;
 use crate::handler::connection::disconnect_connection;
 use crate::observability::metrics::server::{metrics_request_queue, metrics_response_queue};
+use crate::security::AuthDriver;
 use crate::server::connection_manager::ConnectionManager;
 use crate::server::packet::ResponsePackage;
 use crate::subscribe::subscribe_manager::SubscribeManager;
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn response_process(
     response_process_num: usize,
     connection_manager: Arc<ConnectionManager>,
@@ -37,6 +40,7 @@ pub(crate) async fn response_process(
     subscribe_manager: Arc<SubscribeManager>,
     mut response_queue_rx: Receiver<ResponsePackage>,
     client_pool: Arc<ClientPool>,
+    auth_driver: Arc<AuthDriver>,
     stop_sx: broadcast::Sender<bool>,
 ) {
     let mut stop_rx = stop_sx.subscribe();
@@ -50,6 +54,7 @@ pub(crate) async fn response_process(
             cache_manager,
             subscribe_manager,
             client_pool,
+            auth_driver,
         );
 
         let mut response_process_seq = 1;
@@ -104,6 +109,7 @@ pub(crate) fn response_child_process(
     cache_manager: Arc<CacheManager>,
     subscribe_manager: Arc<SubscribeManager>,
     client_pool: Arc<ClientPool>,
+    auth_driver: Arc<AuthDriver>,
 ) {
     for index in 1..=response_process_num {
         let (response_process_sx, mut response_process_rx) = mpsc::channel::<ResponsePackage>(100);
@@ -114,6 +120,7 @@ pub(crate) fn response_child_process(
         let raw_cache_manager = cache_manager.clone();
         let raw_client_pool = client_pool.clone();
         let raw_subscribe_manager = subscribe_manager.clone();
+        let raw_auth_driver = auth_driver.clone();
         tokio::spawn(async move {
             debug!("TCP Server response process thread {index} start successfully.");
 
@@ -142,7 +149,17 @@ pub(crate) fn response_child_process(
                                 match raw_connect_manager
                                     .write_tcp_frame(response_package.connection_id, packet_wrapper)
                                     .await{
-                                        Ok(()) => {},
+                                        Ok(()) => {
+                                            auto_subscribe_after_connack(
+                                                response_package.connection_id,
+                                                &response_package.packet,
+                                                &raw_client_pool,
+                                                &raw_cache_manager,
+                                                &raw_subscribe_manager,
+                                                &raw_connect_manager,
+                                                &raw_auth_driver,
+                                            ).await;
+                                        },
                                         Err(e) => {
                                             error!("{}",e);
                                             raw_connect_manager.close_connect(response_package.connection_id).await;
@@ -23,6 +23,7 @@ use tokio::sync::{broadcast, mpsc};
 
 use crate::handler::cache::CacheManager;
 use crate::handler::command::Command;
+use crate::handler::delay_message::DelayMessageManager;
 use crate::security::AuthDriver;
 use crate::server::connection::NetworkConnectionType;
 use crate::server::connection_manager::ConnectionManager;
@@ -41,6 +42,7 @@ pub async fn start_tcp_server<S>(
     client_pool: Arc<ClientPool>,
     stop_sx: broadcast::Sender<bool>,
     auth_driver: Arc<AuthDriver>,
+    delay_message_manager: Arc<DelayMessageManager<S>>,
 ) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
@@ -52,6 +54,7 @@ pub async fn start_tcp_server<S>(
         client_pool.clone(),
         connection_manager.clone(),
         auth_driver.clone(),
+        delay_message_manager,
     );
 
     let proc_config = ProcessorConfig {
@@ -68,6 +71,7 @@ pub async fn start_tcp_server<S>(
         subscribe_manager.clone(),
         cache_manager.clone(),
         client_pool.clone(),
+        auth_driver.clone(),
     );
     server.start(conf.network.tcp_port).await;
 
@@ -79,6 +83,7 @@ pub async fn start_tcp_server<S>(
         subscribe_manager.clone(),
         cache_manager,
         client_pool,
+        auth_driver,
     );
     server.start_tls(conf.network.tcps_port).await;
 }
@@ -91,6 +96,7 @@ struct TcpServer<S> {
     cache_manager: Arc<CacheManager>,
     subscribe_manager: Arc<SubscribeManager>,
     client_pool: Arc<ClientPool>,
+    auth_driver: Arc<AuthDriver>,
     accept_thread_num: usize,
     handler_process_num: usize,
     response_process_num: usize,
@@ -109,6 +115,7 @@ impl<S> TcpServer<S>
 where
     S: StorageAdapter + Clone + Send + Sync + 'static,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         command: Command<S>,
         proc_config: ProcessorConfig,
@@ -117,12 +124,14 @@ where
         subscribe_manager: Arc<SubscribeManager>,
         cache_manager: Arc<CacheManager>,
         client_pool: Arc<ClientPool>,
+        auth_driver: Arc<AuthDriver>,
     ) -> Self {
         Self {
             command,
             subscribe_manager,
             cache_manager,
             client_pool,
+            auth_driver,
             connection_manager,
             accept_thread_num: proc_config.accept_thread_num,
             handler_process_num: proc_config.handler_process_num,
@@ -172,6 +181,7 @@ where
             self.subscribe_manager.clone(),
             response_queue_rx,
             self.client_pool.clone(),
+            self.auth_driver.clone(),
             self.stop_sx.clone(),
         )
         .await;
@@ -219,6 +229,7 @@ where
             self.subscribe_manager.clone(),
             response_queue_rx,
             self.client_pool.clone(),
+            self.auth_driver.clone(),
             self.stop_sx.clone(),
         )
         .await;
@@ -33,7 +33,7 @@ use tokio_rustls::rustls::ServerConfig;
 use tokio_rustls::TlsAcceptor;
 use tokio_util::codec::{FramedRead, FramedWrite};
 
-use crate::handler::validator::tcp_tls_establish_connection_check;
+use crate::handler::validator::establish_connection_check;
 use crate::observability::metrics::packets::{
     record_received_error_metrics, record_received_metrics,
 };
@@ -124,7 +124,7 @@ pub(crate) async fn acceptor_tls_process(
                                 let read_frame_stream = FramedRead::new(r_stream, codec.clone());
                                 let mut  write_frame_stream = FramedWrite::new(w_stream, codec.clone());
 
-                                if !tcp_tls_establish_connection_check(&addr,&connection_manager,&mut write_frame_stream).await{
+                                if !establish_connection_check(&addr,&connection_manager,&mut write_frame_stream).await{
                                     continue;
                                 }
 
@@ -35,8 +35,10 @@ use storage_adapter::storage::StorageAdapter;
 use tokio::select;
 use tokio::sync::broadcast::{self};
 
+use crate::handler::auto_subscribe::auto_subscribe_after_connack;
 use crate::handler::cache::CacheManager;
 use crate::handler::command::Command;
+use crate::handler::delay_message::DelayMessageManager;
 use crate::security::AuthDriver;
 use crate::server::connection::NetworkConnection;
 use crate::server::connection_manager::ConnectionManager;
@@ -53,6 +55,7 @@ pub struct WebSocketServerState<S> {
     stop_sx: broadcast::Sender<bool>,
     connection_manager: Arc<ConnectionManager>,
     auth_driver: Arc<AuthDriver>,
+    delay_message_manager: Arc<DelayMessageManager<S>>,
 }
 
 impl<S> WebSocketServerState<S>
@@ -66,6 +69,7 @@ where
         message_storage_adapter: Arc<S>,
         client_pool: Arc<ClientPool>,
         auth_driver: Arc<AuthDriver>,
+        delay_message_manager: Arc<DelayMessageManager<S>>,
         stop_sx: broadcast::Sender<bool>,
     ) -> Self {
         Self {
@@ -75,6 +79,7 @@ where
             message_storage_adapter,
             client_pool,
             auth_driver,
+            delay_message_manager,
             stop_sx,
         }
     }
@@ -169,19 +174,11 @@ where
         state.client_pool.clone(),
         state.connection_manager.clone(),
         state.auth_driver.clone(),
+        state.delay_message_manager.clone(),
     );
     let codec = MqttCodec::new(None);
     ws.protocols(["mqtt", "mqttv3.1"])
-        .on_upgrade(move |socket| {
-            handle_socket(
-                socket,
-                addr,
-                command,
-                codec,
-                state.connection_manager.clone(),
-                state.stop_sx.clone(),
-            )
-        })
+        .on_upgrade(move |socket| handle_socket(socket, addr, command, codec, state))
 }
 
 async fn handle_socket<S>(
@@ -189,11 +186,12 @@ async fn handle_socket<S>(
     addr: SocketAddr,
     mut command: Command<S>,
     mut codec: MqttCodec,
-    connection_manager: Arc<ConnectionManager>,
-    stop_sx: broadcast::Sender<bool>,
+    state: WebSocketServerState<S>,
 ) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
+    let connection_manager = state.connection_manager.clone();
+    let stop_sx = state.stop_sx.clone();
     let (sender, mut receiver) = socket.split();
     let mut tcp_connection = NetworkConnection::new(
         crate::server::connection::NetworkConnectionType::WebSocket,
@@ -252,8 +250,18 @@ async fn handle_socket<S>(
                                                 error!("Websocket encode back packet failed with error message: {e:?}");
                                             }
                                         }
-                                        match connection_manager.write_websocket_frame(tcp_connection.connection_id, packet_wrapper, Message::Binary(response_buff.to_vec())).await{
-                                            Ok(()) => {},
+                                        match connection_manager.write_websocket_frame(tcp_connection.connection_id, packet_wrapper.clone(), Message::Binary(response_buff.to_vec())).await{
+                                            Ok(()) => {
+                                                auto_subscribe_after_connack(
+                                                    tcp_connection.connection_id,
+                                                    &packet_wrapper.packet,
+                                                    &state.client_pool,
+                                                    &state.cache_manager,
+                                                    &state.sucscribe_manager,
+                                                    &connection_manager,
+                                                    &state.auth_driver,
+                                                ).await;
+                                            },
                                             Err(e) => {
                                                 error!("websocket returns failure to write the packet to the client with error message {e:?}");
                                                 connection_manager.close_connect(tcp_connection.connection_id).await;
@@ -14,58 +14,34 @@
 
 use std::sync::Arc;
 
-use common_base::config::broker_mqtt::broker_mqtt_conf;
-use grpc_clients::placement::mqtt::call::{create_acl, delete_acl, list_acl};
 use grpc_clients::pool::ClientPool;
 use metadata_struct::acl::mqtt_acl::MqttAcl;
-use protocol::placement_center::placement_center_mqtt::{
-    CreateAclRequest, DeleteAclRequest, ListAclRequest,
-};
 
+use super::metadata::{build_metadata_storage, MetadataStorage};
 use crate::handler::error::MqttBrokerError;
 
 pub struct AclStorage {
-    client_pool: Arc<ClientPool>,
+    metadata_storage: Arc<dyn MetadataStorage>,
 }
 
 impl AclStorage {
     pub fn new(client_pool: Arc<ClientPool>) -> Self {
-        AclStorage { client_pool }
+        AclStorage {
+            metadata_storage: build_metadata_storage(client_pool),
+        }
     }
 
     pub async fn list_acl(&self) -> Result<Vec<MqttAcl>, MqttBrokerError> {
-        let config = broker_mqtt_conf();
-        let request = ListAclRequest {
-            cluster_name: config.cluster_name.clone(),
-        };
-        let reply = list_acl(&self.client_pool, &config.placement_center, request).await?;
-        let mut list = Vec::new();
-        for raw in reply.acls {
-            list.push(serde_json::from_slice::<MqttAcl>(raw.as_slice())?);
-        }
-        Ok(list)
+        Ok(self.metadata_storage.list_acl().await?)
     }
 
     pub async fn save_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError> {
-        let config = broker_mqtt_conf();
-
-        let value = acl.encode()?;
-        let request = CreateAclRequest {
-            cluster_name: config.cluster_name.clone(),
-            acl: value,
-        };
-        create_acl(&self.client_pool, &config.placement_center, request).await?;
+        self.metadata_storage.save_acl(acl).await?;
         Ok(())
     }
 
     pub async fn delete_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError> {
-        let config = broker_mqtt_conf();
-        let value = acl.encode()?;
-        let request = DeleteAclRequest {
-            cluster_name: config.cluster_name.clone(),
-            acl: value,
-        };
-        delete_acl(&self.client_pool, &config.placement_center, request).await?;
+        self.metadata_storage.delete_acl(acl).await?;
         Ok(())
     }
 }
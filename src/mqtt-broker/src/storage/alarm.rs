@@ -0,0 +1,41 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::alarm::MqttAlarm;
+
+use super::metadata::{build_metadata_storage, MetadataStorage};
+
+pub struct AlarmStorage {
+    metadata_storage: Arc<dyn MetadataStorage>,
+}
+
+impl AlarmStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        AlarmStorage {
+            metadata_storage: build_metadata_storage(client_pool),
+        }
+    }
+
+    pub async fn save(&self, alarm: &MqttAlarm) -> Result<(), CommonError> {
+        self.metadata_storage.save_alarm(alarm).await
+    }
+
+    pub async fn list(&self, only_active: bool, limit: u32) -> Result<Vec<MqttAlarm>, CommonError> {
+        self.metadata_storage.list_alarm(only_active, limit).await
+    }
+}
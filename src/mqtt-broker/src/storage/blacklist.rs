@@ -14,45 +14,29 @@
 
 use std::sync::Arc;
 
-use common_base::config::broker_mqtt::broker_mqtt_conf;
-use grpc_clients::placement::mqtt::call::{create_blacklist, delete_blacklist, list_blacklist};
 use grpc_clients::pool::ClientPool;
 use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
-use protocol::placement_center::placement_center_mqtt::{
-    CreateBlacklistRequest, DeleteBlacklistRequest, ListBlacklistRequest,
-};
 
+use super::metadata::{build_metadata_storage, MetadataStorage};
 use crate::handler::error::MqttBrokerError;
 
 pub struct BlackListStorage {
-    client_pool: Arc<ClientPool>,
+    metadata_storage: Arc<dyn MetadataStorage>,
 }
 
 impl BlackListStorage {
     pub fn new(client_pool: Arc<ClientPool>) -> Self {
-        BlackListStorage { client_pool }
+        BlackListStorage {
+            metadata_storage: build_metadata_storage(client_pool),
+        }
     }
 
     pub async fn list_blacklist(&self) -> Result<Vec<MqttAclBlackList>, MqttBrokerError> {
-        let config = broker_mqtt_conf();
-        let request = ListBlacklistRequest {
-            cluster_name: config.cluster_name.clone(),
-        };
-        let reply = list_blacklist(&self.client_pool, &config.placement_center, request).await?;
-        let mut list = Vec::new();
-        for raw in reply.blacklists {
-            list.push(serde_json::from_slice::<MqttAclBlackList>(raw.as_slice())?);
-        }
-        Ok(list)
+        Ok(self.metadata_storage.list_blacklist().await?)
     }
 
     pub async fn save_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), MqttBrokerError> {
-        let config = broker_mqtt_conf();
-        let request = CreateBlacklistRequest {
-            cluster_name: config.cluster_name.clone(),
-            blacklist: blacklist.encode()?,
-        };
-        create_blacklist(&self.client_pool, &config.placement_center, request).await?;
+        self.metadata_storage.save_blacklist(blacklist).await?;
         Ok(())
     }
 
@@ -60,13 +44,7 @@ impl BlackListStorage {
         &self,
         blacklist: MqttAclBlackList,
     ) -> Result<(), MqttBrokerError> {
-        let config = broker_mqtt_conf();
-        let request = DeleteBlacklistRequest {
-            cluster_name: config.cluster_name.clone(),
-            blacklist_type: blacklist.blacklist_type.to_string(),
-            resource_name: blacklist.resource_name,
-        };
-        delete_blacklist(&self.client_pool, &config.placement_center, request).await?;
+        self.metadata_storage.delete_blacklist(blacklist).await?;
         Ok(())
     }
 }
@@ -79,6 +79,8 @@ where
         Ok(0)
     }
 
+    /// The committed offset of a group is the next offset it reads, every message
+    /// before it has been handled.
     pub async fn commit_group_offset(
         &self,
         group_id: &str,
@@ -0,0 +1,137 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use grpc_clients::mqtt::inner::call::{broker_mqtt_delete_session, send_last_will_message};
+use grpc_clients::pool::ClientPool;
+use log::{error, info, warn};
+use protocol::broker_mqtt::broker_mqtt_inner::{DeleteSessionRequest, SendLastWillMessageRequest};
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+
+use super::local::LocalMetadataStorage;
+
+/// The session and message expiry the placement center runs for a cluster, for a broker
+/// whose metadata is local. Expired sessions and due last will messages are handed to the
+/// node through its own inner gRPC service, the same calls the placement center makes.
+pub struct LocalMetadataExpire {
+    local_storage: Arc<LocalMetadataStorage>,
+    client_pool: Arc<ClientPool>,
+    stop_send: broadcast::Sender<bool>,
+}
+
+impl LocalMetadataExpire {
+    pub fn new(
+        local_storage: Arc<LocalMetadataStorage>,
+        client_pool: Arc<ClientPool>,
+        stop_send: broadcast::Sender<bool>,
+    ) -> Self {
+        LocalMetadataExpire {
+            local_storage,
+            client_pool,
+            stop_send,
+        }
+    }
+
+    pub async fn start(&self) {
+        loop {
+            let mut stop_rx = self.stop_send.subscribe();
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}","Local metadata expiry thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = self.expire()=>{
+                }
+            }
+        }
+    }
+
+    async fn expire(&self) {
+        self.session_expire().await;
+        self.last_will_expire_send().await;
+        if let Err(e) = self.local_storage.expire_message() {
+            error!("{}", e);
+        }
+        sleep(Duration::from_secs(1)).await;
+    }
+
+    async fn session_expire(&self) {
+        let sessions = match self.local_storage.list_expire_session() {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                error!("{}", e);
+                return;
+            }
+        };
+        if sessions.is_empty() {
+            return;
+        }
+
+        let conf = broker_mqtt_conf();
+        let request = DeleteSessionRequest {
+            client_id: sessions.iter().map(|raw| raw.client_id.clone()).collect(),
+            cluster_name: conf.cluster_name.clone(),
+        };
+        if let Err(e) =
+            broker_mqtt_delete_session(&self.client_pool, &[self.node_addr()], request).await
+        {
+            // The sessions stay expired, the next round tries again
+            warn!("{}", e);
+            return;
+        }
+
+        for session in sessions {
+            if let Err(e) = self.local_storage.expire_session(&session) {
+                error!("{}", e);
+            }
+        }
+    }
+
+    async fn last_will_expire_send(&self) {
+        for client_id in self.local_storage.list_due_last_will() {
+            let data = match self.local_storage.get_last_will(&client_id) {
+                Ok(Some(data)) => data,
+                Ok(None) => {
+                    self.local_storage.remove_expire_last_will(&client_id);
+                    continue;
+                }
+                Err(e) => {
+                    error!("{}", e);
+                    continue;
+                }
+            };
+            let request = SendLastWillMessageRequest {
+                client_id: client_id.clone(),
+                last_will_message: data.encode(),
+            };
+            match send_last_will_message(&self.client_pool, &[self.node_addr()], request).await {
+                Ok(_) => self.local_storage.remove_expire_last_will(&client_id),
+                Err(e) => error!("{}", e),
+            }
+        }
+    }
+
+    fn node_addr(&self) -> String {
+        format!("127.0.0.1:{}", broker_mqtt_conf().grpc_port)
+    }
+}
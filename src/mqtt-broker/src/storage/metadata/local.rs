@@ -0,0 +1,910 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use axum::async_trait;
+use common_base::config::broker_mqtt::{broker_mqtt_conf, BrokerMqttConfig};
+use common_base::error::common::CommonError;
+use common_base::tools::{get_local_ip, now_mills, now_second, try_create_fold};
+use common_base::utils::topic_util::topic_name_regex_match;
+use dashmap::DashMap;
+use metadata_struct::acl::mqtt_acl::MqttAcl;
+use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
+use metadata_struct::mqtt::alarm::MqttAlarm;
+use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
+use metadata_struct::mqtt::cluster::MqttClusterDynamicConfig;
+use metadata_struct::mqtt::lastwill::LastWillData;
+use metadata_struct::mqtt::qos2_state::MqttQos2State;
+use metadata_struct::mqtt::session::MqttSession;
+use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+use metadata_struct::mqtt::topic::MqttTopic;
+use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
+use metadata_struct::mqtt::user::MqttUser;
+use metadata_struct::placement::node::BrokerNode;
+use protocol::placement_center::placement_center_inner::ClusterType;
+use protocol::placement_center::placement_center_mqtt::GetShareSubLeaderReply;
+use rocksdb_engine::engine::{
+    rocksdb_engine_delete, rocksdb_engine_delete_range, rocksdb_engine_exists, rocksdb_engine_get,
+    rocksdb_engine_prefix_list, rocksdb_engine_prefix_list_rev, rocksdb_engine_save,
+};
+use rocksdb_engine::warp::StorageDataWrap;
+use rocksdb_engine::RocksDBEngine;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::{build_node_extend, MetadataStorage};
+
+const DB_COLUMN_FAMILY_METADATA: &str = "metadata";
+
+// Alarms activated longer ago than this are dropped from the history
+const ALARM_HISTORY_RETENTION_SEC: u64 = 7 * 24 * 3600;
+
+// Last will messages without a message expiry interval are kept this long
+const LAST_WILL_DEFAULT_EXPIRY_SEC: u64 = 30 * 24 * 3600;
+
+/// Metadata kept in a RocksDB on the local disk, for a broker that runs without a placement
+/// center. The node is the whole cluster: it is the only registered node and the leader of
+/// every shared subscription group. The session and message expiry the placement center
+/// runs for a cluster is driven by [`super::expire::LocalMetadataExpire`].
+pub struct LocalMetadataStorage {
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    // Client id of the expired sessions whose last will message is waiting for its delay,
+    // with the time it is due
+    expire_last_wills: DashMap<String, u64>,
+}
+
+impl LocalMetadataStorage {
+    pub fn new(data_path: &str, max_open_files: i32) -> Self {
+        if let Err(e) = try_create_fold(data_path) {
+            panic!("{}", e);
+        }
+        LocalMetadataStorage {
+            rocksdb_engine_handler: Arc::new(RocksDBEngine::new(
+                data_path,
+                max_open_files,
+                vec![DB_COLUMN_FAMILY_METADATA.to_string()],
+            )),
+            expire_last_wills: DashMap::with_capacity(2),
+        }
+    }
+
+    // Sessions whose client has been gone longer than the session expiry interval
+    pub fn list_expire_session(&self) -> Result<Vec<MqttSession>, CommonError> {
+        let now = now_second();
+        let mut results = Vec::new();
+        for session in self.list::<MqttSession>(key_session_prefix())? {
+            if session.connection_id.is_some() || session.broker_id.is_some() {
+                continue;
+            }
+            if let Some(distinct_time) = session.distinct_time {
+                if now >= session.session_expiry.saturating_add(distinct_time) {
+                    results.push(session);
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    // Removes the expired session with its subscriptions and schedules its last will
+    pub fn expire_session(&self, session: &MqttSession) -> Result<(), CommonError> {
+        for subscribe in self.list_client_subscribe(&session.client_id)? {
+            self.delete(key_subscribe(&subscribe.client_id, &subscribe.path))?;
+        }
+        self.delete(key_session(&session.client_id))?;
+        let delay = session.last_will_delay_interval.unwrap_or_default();
+        self.expire_last_wills.insert(
+            session.client_id.clone(),
+            now_second().saturating_add(delay),
+        );
+        Ok(())
+    }
+
+    pub fn list_due_last_will(&self) -> Vec<String> {
+        let now = now_second();
+        self.expire_last_wills
+            .iter()
+            .filter(|raw| *raw.value() <= now)
+            .map(|raw| raw.key().clone())
+            .collect()
+    }
+
+    pub fn get_last_will(&self, client_id: &str) -> Result<Option<LastWillData>, CommonError> {
+        self.get::<LastWillData>(key_last_will(client_id))
+    }
+
+    pub fn remove_expire_last_will(&self, client_id: &str) {
+        self.expire_last_wills.remove(client_id);
+    }
+
+    // Drops the retained messages and the last will messages past their expiry interval
+    pub fn expire_message(&self) -> Result<(), CommonError> {
+        let now = now_second();
+        for wrap in self.list_wrap(key_topic_prefix())? {
+            let mut topic = serde_json::from_slice::<MqttTopic>(&wrap.data)?;
+            if topic.retain_message.is_none() {
+                continue;
+            }
+            // An expiry interval of 0 means the retained message never expires
+            let expired_at = topic.retain_message_expired_at.unwrap_or_default();
+            if expired_at > 0 && now >= wrap.create_time.saturating_add(expired_at) {
+                topic.retain_message = None;
+                topic.retain_message_expired_at = None;
+                self.save(key_topic(&topic.topic_name), topic)?;
+            }
+        }
+
+        for wrap in self.list_wrap(key_last_will_prefix())? {
+            let data = serde_json::from_slice::<LastWillData>(&wrap.data)?;
+            let expiry = data
+                .last_will_properties
+                .as_ref()
+                .and_then(|properties| properties.message_expiry_interval)
+                .map(|interval| interval as u64)
+                .unwrap_or(LAST_WILL_DEFAULT_EXPIRY_SEC);
+            if now >= wrap.create_time.saturating_add(expiry) {
+                self.delete(key_last_will(&data.client_id))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn list_client_subscribe(&self, client_id: &str) -> Result<Vec<MqttSubscribe>, CommonError> {
+        // The prefix of client "a" also covers the keys of client "a/b"
+        Ok(self
+            .list::<MqttSubscribe>(key_subscribe_prefix(client_id))?
+            .into_iter()
+            .filter(|subscribe| subscribe.client_id == client_id)
+            .collect())
+    }
+
+    fn save<T: Serialize>(&self, key: String, value: T) -> Result<(), CommonError> {
+        rocksdb_engine_save(
+            self.rocksdb_engine_handler.clone(),
+            DB_COLUMN_FAMILY_METADATA,
+            key,
+            value,
+        )
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: String) -> Result<Option<T>, CommonError> {
+        match rocksdb_engine_get(
+            self.rocksdb_engine_handler.clone(),
+            DB_COLUMN_FAMILY_METADATA,
+            key,
+        )? {
+            Some(wrap) => Ok(Some(serde_json::from_slice::<T>(&wrap.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn exists(&self, key: String) -> Result<bool, CommonError> {
+        rocksdb_engine_exists(
+            self.rocksdb_engine_handler.clone(),
+            DB_COLUMN_FAMILY_METADATA,
+            key,
+        )
+    }
+
+    fn delete(&self, key: String) -> Result<(), CommonError> {
+        rocksdb_engine_delete(
+            self.rocksdb_engine_handler.clone(),
+            DB_COLUMN_FAMILY_METADATA,
+            key,
+        )
+    }
+
+    fn list_wrap(&self, prefix: String) -> Result<Vec<StorageDataWrap>, CommonError> {
+        rocksdb_engine_prefix_list(
+            self.rocksdb_engine_handler.clone(),
+            DB_COLUMN_FAMILY_METADATA,
+            prefix,
+        )
+    }
+
+    fn list<T: DeserializeOwned>(&self, prefix: String) -> Result<Vec<T>, CommonError> {
+        let mut results = Vec::new();
+        for wrap in self.list_wrap(prefix)? {
+            results.push(serde_json::from_slice::<T>(&wrap.data)?);
+        }
+        Ok(results)
+    }
+
+    fn local_node(&self, config: &BrokerMqttConfig) -> Result<BrokerNode, CommonError> {
+        let local_ip = get_local_ip();
+        Ok(BrokerNode {
+            cluster_name: config.cluster_name.clone(),
+            cluster_type: ClusterType::MqttBrokerServer.as_str_name().to_string(),
+            create_time: now_mills(),
+            extend: serde_json::to_string(&build_node_extend(config))?,
+            node_id: config.broker_id,
+            node_inner_addr: format!("{}:{}", local_ip, config.grpc_port),
+            node_ip: local_ip,
+        })
+    }
+}
+
+#[async_trait]
+impl MetadataStorage for LocalMetadataStorage {
+    async fn node_list(&self) -> Result<Vec<BrokerNode>, CommonError> {
+        self.list::<BrokerNode>(key_node_prefix())
+    }
+
+    async fn register_node(&self, config: &BrokerMqttConfig) -> Result<(), CommonError> {
+        let node = self.local_node(config)?;
+        self.save(key_node(config.broker_id), node)
+    }
+
+    async fn unregister_node(&self, config: &BrokerMqttConfig) -> Result<(), CommonError> {
+        self.delete(key_node(config.broker_id))
+    }
+
+    // There is no placement center to tell the node is alive
+    async fn heartbeat(&self) -> Result<(), CommonError> {
+        Ok(())
+    }
+
+    async fn set_cluster_config(
+        &self,
+        cluster_name: &str,
+        cluster: MqttClusterDynamicConfig,
+    ) -> Result<(), CommonError> {
+        self.save(key_cluster_config(cluster_name), cluster)
+    }
+
+    async fn get_cluster_config(
+        &self,
+        cluster_name: &str,
+    ) -> Result<Option<MqttClusterDynamicConfig>, CommonError> {
+        self.get::<MqttClusterDynamicConfig>(key_cluster_config(cluster_name))
+    }
+
+    async fn delete_cluster_config(&self, cluster_name: &str) -> Result<(), CommonError> {
+        self.delete(key_cluster_config(cluster_name))
+    }
+
+    async fn save_user(&self, user: MqttUser) -> Result<(), CommonError> {
+        self.save(key_user(&user.username), user)
+    }
+
+    async fn delete_user(&self, username: String) -> Result<(), CommonError> {
+        self.delete(key_user(&username))
+    }
+
+    async fn list_user(&self, username: String) -> Result<Vec<MqttUser>, CommonError> {
+        if username.is_empty() {
+            return self.list::<MqttUser>(key_user_prefix());
+        }
+        Ok(self
+            .get::<MqttUser>(key_user(&username))?
+            .into_iter()
+            .collect())
+    }
+
+    async fn list_acl(&self) -> Result<Vec<MqttAcl>, CommonError> {
+        let mut results = Vec::new();
+        for acl_list in self.list::<Vec<MqttAcl>>(key_acl_prefix())? {
+            results.extend(acl_list);
+        }
+        Ok(results)
+    }
+
+    // The ACLs of a user or client id are kept together under one key
+    async fn save_acl(&self, acl: MqttAcl) -> Result<(), CommonError> {
+        let key = key_acl(&acl.resource_type.to_string(), &acl.resource_name);
+        let mut acl_list = self.get::<Vec<MqttAcl>>(key.clone())?.unwrap_or_default();
+        if acl_list.contains(&acl) {
+            return Ok(());
+        }
+        acl_list.push(acl);
+        self.save(key, acl_list)
+    }
+
+    async fn delete_acl(&self, acl: MqttAcl) -> Result<(), CommonError> {
+        let key = key_acl(&acl.resource_type.to_string(), &acl.resource_name);
+        let acl_list = if let Some(list) = self.get::<Vec<MqttAcl>>(key.clone())? {
+            list
+        } else {
+            return Ok(());
+        };
+        let acl_list: Vec<MqttAcl> = acl_list.into_iter().filter(|raw| *raw != acl).collect();
+        if acl_list.is_empty() {
+            return self.delete(key);
+        }
+        self.save(key, acl_list)
+    }
+
+    async fn list_blacklist(&self) -> Result<Vec<MqttAclBlackList>, CommonError> {
+        self.list::<MqttAclBlackList>(key_blacklist_prefix())
+    }
+
+    async fn save_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), CommonError> {
+        let key = key_blacklist(
+            &blacklist.blacklist_type.to_string(),
+            &blacklist.resource_name,
+        );
+        self.save(key, blacklist)
+    }
+
+    async fn delete_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), CommonError> {
+        self.delete(key_blacklist(
+            &blacklist.blacklist_type.to_string(),
+            &blacklist.resource_name,
+        ))
+    }
+
+    // Same layout as the alarms of the placement center: the history is ordered by
+    // activation time and the active alarms are also kept under a key of their own
+    async fn save_alarm(&self, alarm: &MqttAlarm) -> Result<(), CommonError> {
+        let key = key_alarm(&alarm.name, alarm.broker_id, alarm.activate_time);
+        let active_key = key_alarm_active(&alarm.name, alarm.broker_id);
+        self.save(key, alarm)?;
+        if !alarm.is_active() {
+            return self.delete(active_key);
+        }
+        self.save(active_key, alarm)?;
+        let cutoff = alarm
+            .activate_time
+            .saturating_sub(ALARM_HISTORY_RETENTION_SEC);
+        if cutoff == 0 {
+            return Ok(());
+        }
+        rocksdb_engine_delete_range(
+            self.rocksdb_engine_handler.clone(),
+            DB_COLUMN_FAMILY_METADATA,
+            key_alarm_prefix(),
+            key_alarm_time_bound(cutoff),
+        )
+    }
+
+    async fn list_alarm(
+        &self,
+        only_active: bool,
+        limit: u32,
+    ) -> Result<Vec<MqttAlarm>, CommonError> {
+        if !only_active {
+            // Newest activations first, read from the end of the history
+            let mut results = Vec::new();
+            for wrap in rocksdb_engine_prefix_list_rev(
+                self.rocksdb_engine_handler.clone(),
+                DB_COLUMN_FAMILY_METADATA,
+                key_alarm_prefix(),
+                limit as usize,
+            )? {
+                results.push(serde_json::from_slice::<MqttAlarm>(&wrap.data)?);
+            }
+            return Ok(results);
+        }
+
+        let mut results = self.list::<MqttAlarm>(key_alarm_active_prefix())?;
+        results.sort_by(|a, b| b.activate_time.cmp(&a.activate_time));
+        if limit > 0 {
+            results.truncate(limit as usize);
+        }
+        Ok(results)
+    }
+
+    async fn save_topic(&self, topic: MqttTopic) -> Result<(), CommonError> {
+        let key = key_topic(&topic.topic_name);
+        if self.exists(key.clone())? {
+            return Err(CommonError::CommonError(format!(
+                "Topic [{}] already exist",
+                topic.topic_name
+            )));
+        }
+        self.save(key, topic)
+    }
+
+    async fn delete_topic(&self, topic_name: String) -> Result<(), CommonError> {
+        self.delete(key_topic(&topic_name))
+    }
+
+    async fn list_topic(&self, topic_name: String) -> Result<Vec<MqttTopic>, CommonError> {
+        if topic_name.is_empty() {
+            return self.list::<MqttTopic>(key_topic_prefix());
+        }
+        Ok(self
+            .get::<MqttTopic>(key_topic(&topic_name))?
+            .into_iter()
+            .collect())
+    }
+
+    async fn set_retain_message(
+        &self,
+        topic_name: String,
+        retain_message: Vec<u8>,
+        retain_message_expired_at: u64,
+    ) -> Result<(), CommonError> {
+        let mut topic = if let Some(topic) = self.get::<MqttTopic>(key_topic(&topic_name))? {
+            topic
+        } else {
+            return Err(CommonError::CommonError(format!(
+                "Topic [{}] does not exist",
+                topic_name
+            )));
+        };
+        if retain_message.is_empty() {
+            topic.retain_message = None;
+            topic.retain_message_expired_at = None;
+        } else {
+            topic.retain_message = Some(retain_message);
+            topic.retain_message_expired_at = Some(retain_message_expired_at);
+        }
+        self.save(key_topic(&topic_name), topic)
+    }
+
+    async fn list_topic_rewrite_rule(&self) -> Result<Vec<MqttTopicRewriteRule>, CommonError> {
+        self.list::<MqttTopicRewriteRule>(key_topic_rewrite_rule_prefix())
+    }
+
+    async fn save_topic_rewrite_rule(
+        &self,
+        rule: &MqttTopicRewriteRule,
+    ) -> Result<(), CommonError> {
+        let key = key_topic_rewrite_rule(&rule.action.to_string(), &rule.source_topic);
+        self.save(key, rule)
+    }
+
+    async fn delete_topic_rewrite_rule(
+        &self,
+        action: String,
+        source_topic: String,
+    ) -> Result<(), CommonError> {
+        self.delete(key_topic_rewrite_rule(&action, &source_topic))
+    }
+
+    async fn set_nx_exclusive_topic(&self, topic_name: String) -> Result<bool, CommonError> {
+        for exclusive_topic in self.list::<String>(key_exclusive_topic_prefix())? {
+            if topic_name_regex_match(&exclusive_topic, &topic_name) {
+                return Ok(false);
+            }
+        }
+        self.save(key_exclusive_topic(&topic_name), topic_name.clone())?;
+        Ok(true)
+    }
+
+    async fn delete_exclusive_topic(&self, topic_name: String) -> Result<(), CommonError> {
+        self.delete(key_exclusive_topic(&topic_name))
+    }
+
+    async fn set_session(
+        &self,
+        client_id: String,
+        session: &MqttSession,
+    ) -> Result<(), CommonError> {
+        self.save(key_session(&client_id), session)
+    }
+
+    async fn update_session(
+        &self,
+        client_id: String,
+        connection_id: u64,
+        broker_id: u64,
+        reconnect_time: u64,
+        distinct_time: u64,
+    ) -> Result<(), CommonError> {
+        let mut session = if let Some(session) = self.get::<MqttSession>(key_session(&client_id))? {
+            session
+        } else {
+            return Ok(());
+        };
+        session.update_connnction_id(if connection_id > 0 {
+            Some(connection_id)
+        } else {
+            None
+        });
+        session.update_broker_id(if broker_id > 0 { Some(broker_id) } else { None });
+        if reconnect_time > 0 {
+            session.reconnect_time = Some(reconnect_time);
+        }
+        session.distinct_time = if distinct_time > 0 {
+            Some(distinct_time)
+        } else {
+            None
+        };
+        self.save(key_session(&client_id), session)
+    }
+
+    async fn delete_session(&self, client_id: String) -> Result<(), CommonError> {
+        self.delete(key_session(&client_id))
+    }
+
+    async fn list_session(&self, client_id: String) -> Result<Vec<MqttSession>, CommonError> {
+        if client_id.is_empty() {
+            return self.list::<MqttSession>(key_session_prefix());
+        }
+        Ok(self
+            .get::<MqttSession>(key_session(&client_id))?
+            .into_iter()
+            .collect())
+    }
+
+    async fn save_last_will_message(
+        &self,
+        client_id: String,
+        last_will_message: Vec<u8>,
+    ) -> Result<(), CommonError> {
+        let data = serde_json::from_slice::<LastWillData>(&last_will_message)?;
+        self.save(key_last_will(&client_id), data)
+    }
+
+    async fn list_qos2_state(&self, client_id: &str) -> Result<Vec<MqttQos2State>, CommonError> {
+        // The prefix of client "a" also covers the keys of client "a/b"
+        Ok(self
+            .list::<MqttQos2State>(key_qos2_state_prefix(client_id))?
+            .into_iter()
+            .filter(|state| state.client_id == client_id)
+            .collect())
+    }
+
+    async fn save_qos2_state(&self, state: &MqttQos2State) -> Result<(), CommonError> {
+        let key = key_qos2_state(&state.client_id, state.direction(), state.pkid);
+        self.save(key, state)
+    }
+
+    async fn delete_qos2_state(
+        &self,
+        client_id: &str,
+        direction: &str,
+        pkid: u16,
+    ) -> Result<(), CommonError> {
+        self.delete(key_qos2_state(client_id, direction, pkid))
+    }
+
+    async fn save_subscribe(&self, subscribe: &MqttSubscribe) -> Result<(), CommonError> {
+        self.save(
+            key_subscribe(&subscribe.client_id, &subscribe.path),
+            subscribe,
+        )
+    }
+
+    async fn delete_subscribe(&self, client_id: &str, path: &str) -> Result<(), CommonError> {
+        if !path.is_empty() {
+            return self.delete(key_subscribe(client_id, path));
+        }
+        for subscribe in self.list_client_subscribe(client_id)? {
+            self.delete(key_subscribe(&subscribe.client_id, &subscribe.path))?;
+        }
+        Ok(())
+    }
+
+    async fn list_subscribe(&self, client_id: &str) -> Result<Vec<MqttSubscribe>, CommonError> {
+        if client_id.is_empty() {
+            return self.list::<MqttSubscribe>(key_subscribe_all_prefix());
+        }
+        self.list_client_subscribe(client_id)
+    }
+
+    async fn get_share_sub_leader(
+        &self,
+        _group_name: String,
+    ) -> Result<GetShareSubLeaderReply, CommonError> {
+        let config = broker_mqtt_conf();
+        let node = self.local_node(config)?;
+        Ok(GetShareSubLeaderReply {
+            broker_id: node.node_id,
+            broker_addr: node.node_inner_addr,
+            extend_info: node.extend,
+        })
+    }
+
+    async fn list_auto_subscribe_rule(&self) -> Result<Vec<MqttAutoSubscribeRule>, CommonError> {
+        self.list::<MqttAutoSubscribeRule>(key_auto_subscribe_rule_prefix())
+    }
+
+    async fn save_auto_subscribe_rule(
+        &self,
+        rule: &MqttAutoSubscribeRule,
+    ) -> Result<(), CommonError> {
+        self.save(key_auto_subscribe_rule(&rule.username, &rule.topic), rule)
+    }
+
+    async fn delete_auto_subscribe_rule(
+        &self,
+        username: String,
+        topic: String,
+    ) -> Result<(), CommonError> {
+        self.delete(key_auto_subscribe_rule(&username, &topic))
+    }
+
+    async fn set_idempotent_data(
+        &self,
+        producer_id: &str,
+        seq_num: u64,
+    ) -> Result<(), CommonError> {
+        self.save(key_idempotent(producer_id, seq_num), now_second())
+    }
+
+    async fn exists_idempotent_data(
+        &self,
+        producer_id: &str,
+        seq_num: u64,
+    ) -> Result<bool, CommonError> {
+        self.exists(key_idempotent(producer_id, seq_num))
+    }
+
+    async fn delete_idempotent_data(
+        &self,
+        producer_id: &str,
+        seq_num: u64,
+    ) -> Result<(), CommonError> {
+        self.delete(key_idempotent(producer_id, seq_num))
+    }
+}
+
+fn key_node(broker_id: u64) -> String {
+    format!("/node/{}", broker_id)
+}
+
+fn key_node_prefix() -> String {
+    "/node/".to_string()
+}
+
+fn key_cluster_config(cluster_name: &str) -> String {
+    format!("/cluster_config/{}", cluster_name)
+}
+
+fn key_user(username: &str) -> String {
+    format!("/user/{}", username)
+}
+
+fn key_user_prefix() -> String {
+    "/user/".to_string()
+}
+
+fn key_acl(resource_type: &str, resource_name: &str) -> String {
+    format!("/acl/{}/{}", resource_type, resource_name)
+}
+
+fn key_acl_prefix() -> String {
+    "/acl/".to_string()
+}
+
+fn key_blacklist(blacklist_type: &str, resource_name: &str) -> String {
+    format!("/blacklist/{}/{}", blacklist_type, resource_name)
+}
+
+fn key_blacklist_prefix() -> String {
+    "/blacklist/".to_string()
+}
+
+fn key_alarm(name: &str, broker_id: u64, activate_time: u64) -> String {
+    format!("/alarm/{:020}/{}/{}", activate_time, broker_id, name)
+}
+
+fn key_alarm_prefix() -> String {
+    "/alarm/".to_string()
+}
+
+// History keys of the alarms activated before activate_time sort below it
+fn key_alarm_time_bound(activate_time: u64) -> String {
+    format!("/alarm/{:020}", activate_time)
+}
+
+fn key_alarm_active(name: &str, broker_id: u64) -> String {
+    format!("/alarm_active/{}/{}", broker_id, name)
+}
+
+fn key_alarm_active_prefix() -> String {
+    "/alarm_active/".to_string()
+}
+
+fn key_topic(topic_name: &str) -> String {
+    format!("/topic/{}", topic_name)
+}
+
+fn key_topic_prefix() -> String {
+    "/topic/".to_string()
+}
+
+fn key_topic_rewrite_rule(action: &str, source_topic: &str) -> String {
+    format!("/topic_rewrite_rule/{}/{}", action, source_topic)
+}
+
+fn key_topic_rewrite_rule_prefix() -> String {
+    "/topic_rewrite_rule/".to_string()
+}
+
+fn key_exclusive_topic(topic_name: &str) -> String {
+    format!("/exclusive_topic/{}", topic_name)
+}
+
+fn key_exclusive_topic_prefix() -> String {
+    "/exclusive_topic/".to_string()
+}
+
+fn key_session(client_id: &str) -> String {
+    format!("/session/{}", client_id)
+}
+
+fn key_session_prefix() -> String {
+    "/session/".to_string()
+}
+
+fn key_last_will(client_id: &str) -> String {
+    format!("/lastwill/{}", client_id)
+}
+
+fn key_last_will_prefix() -> String {
+    "/lastwill/".to_string()
+}
+
+fn key_qos2_state(client_id: &str, direction: &str, pkid: u16) -> String {
+    format!("/qos2_state/{}/{}/{}", client_id, direction, pkid)
+}
+
+fn key_qos2_state_prefix(client_id: &str) -> String {
+    format!("/qos2_state/{}/", client_id)
+}
+
+fn key_subscribe(client_id: &str, path: &str) -> String {
+    format!("/subscribe/{}/{}", client_id, path)
+}
+
+fn key_subscribe_prefix(client_id: &str) -> String {
+    format!("/subscribe/{}/", client_id)
+}
+
+fn key_subscribe_all_prefix() -> String {
+    "/subscribe/".to_string()
+}
+
+fn key_auto_subscribe_rule(username: &str, topic: &str) -> String {
+    format!("/auto_subscribe_rule/{}/{}", username, topic)
+}
+
+fn key_auto_subscribe_rule_prefix() -> String {
+    "/auto_subscribe_rule/".to_string()
+}
+
+fn key_idempotent(producer_id: &str, seq_num: u64) -> String {
+    format!("/idempotent/{}/{}", producer_id, seq_num)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+
+    use common_base::tools::{now_second, unique_id};
+    use metadata_struct::mqtt::session::MqttSession;
+    use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+    use metadata_struct::mqtt::user::MqttUser;
+    use protocol::mqtt::common::{Filter, MqttProtocol, QoS, RetainForwardRule};
+
+    use super::LocalMetadataStorage;
+    use crate::storage::metadata::MetadataStorage;
+
+    fn subscribe(client_id: &str, path: &str) -> MqttSubscribe {
+        let filter = Filter {
+            path: path.to_string(),
+            qos: QoS::AtLeastOnce,
+            nolocal: false,
+            preserve_retain: false,
+            retain_forward_rule: RetainForwardRule::OnEverySubscribe,
+        };
+        MqttSubscribe::new(
+            client_id.to_string(),
+            1,
+            MqttProtocol::Mqtt5,
+            filter,
+            1,
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn local_metadata_storage_test() {
+        let dir = std::env::temp_dir().join(format!("metadata-{}", unique_id()));
+        let storage = LocalMetadataStorage::new(dir.to_str().unwrap(), 100);
+
+        let user = MqttUser {
+            username: "u1".to_string(),
+            password: "p1".to_string(),
+            is_superuser: false,
+        };
+        storage.save_user(user).await.unwrap();
+        assert_eq!(storage.list_user("u1".to_string()).await.unwrap().len(), 1);
+        assert!(storage
+            .list_user("u2".to_string())
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(storage.list_user("".to_string()).await.unwrap().len(), 1);
+        storage.delete_user("u1".to_string()).await.unwrap();
+        assert!(storage.list_user("".to_string()).await.unwrap().is_empty());
+
+        // The subscriptions of client "a" share a key prefix with the ones of client "a/b"
+        storage
+            .save_subscribe(&subscribe("a", "t/1"))
+            .await
+            .unwrap();
+        storage
+            .save_subscribe(&subscribe("a", "t/2"))
+            .await
+            .unwrap();
+        storage
+            .save_subscribe(&subscribe("a/b", "t/1"))
+            .await
+            .unwrap();
+        assert_eq!(storage.list_subscribe("a").await.unwrap().len(), 2);
+        storage.delete_subscribe("a", "").await.unwrap();
+        assert!(storage.list_subscribe("a").await.unwrap().is_empty());
+        assert_eq!(storage.list_subscribe("a/b").await.unwrap().len(), 1);
+
+        assert!(storage
+            .set_nx_exclusive_topic("t/1".to_string())
+            .await
+            .unwrap());
+        assert!(!storage
+            .set_nx_exclusive_topic("t/1".to_string())
+            .await
+            .unwrap());
+        storage
+            .delete_exclusive_topic("t/1".to_string())
+            .await
+            .unwrap();
+        assert!(storage
+            .set_nx_exclusive_topic("t/1".to_string())
+            .await
+            .unwrap());
+
+        assert!(!storage.exists_idempotent_data("c1", 7).await.unwrap());
+        storage.set_idempotent_data("c1", 7).await.unwrap();
+        assert!(storage.exists_idempotent_data("c1", 7).await.unwrap());
+        storage.delete_idempotent_data("c1", 7).await.unwrap();
+        assert!(!storage.exists_idempotent_data("c1", 7).await.unwrap());
+
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn local_session_expire_test() {
+        let dir = std::env::temp_dir().join(format!("metadata-{}", unique_id()));
+        let storage = LocalMetadataStorage::new(dir.to_str().unwrap(), 100);
+
+        let session = MqttSession::new("c1".to_string(), 10, true, Some(0));
+        storage
+            .set_session("c1".to_string(), &session)
+            .await
+            .unwrap();
+        storage
+            .update_session("c1".to_string(), 1, 1, now_second(), 0)
+            .await
+            .unwrap();
+        storage
+            .save_subscribe(&subscribe("c1", "t/1"))
+            .await
+            .unwrap();
+        assert!(storage.list_expire_session().unwrap().is_empty());
+
+        // The client went away long enough ago for the session to expire
+        storage
+            .update_session("c1".to_string(), 0, 0, 0, now_second() - 100)
+            .await
+            .unwrap();
+        let sessions = storage.list_expire_session().unwrap();
+        assert_eq!(sessions.len(), 1);
+
+        storage.expire_session(&sessions[0]).unwrap();
+        assert!(storage
+            .list_session("c1".to_string())
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(storage.list_subscribe("c1").await.unwrap().is_empty());
+        assert_eq!(storage.list_due_last_will(), vec!["c1".to_string()]);
+
+        remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::{Arc, OnceLock};
+
+use axum::async_trait;
+use common_base::config::broker_mqtt::{broker_mqtt_conf, BrokerMqttConfig};
+use common_base::error::common::CommonError;
+use common_base::tools::get_local_ip;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::acl::mqtt_acl::MqttAcl;
+use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
+use metadata_struct::mqtt::alarm::MqttAlarm;
+use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
+use metadata_struct::mqtt::cluster::MqttClusterDynamicConfig;
+use metadata_struct::mqtt::node_extend::MqttNodeExtend;
+use metadata_struct::mqtt::qos2_state::MqttQos2State;
+use metadata_struct::mqtt::session::MqttSession;
+use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+use metadata_struct::mqtt::topic::MqttTopic;
+use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
+use metadata_struct::mqtt::user::MqttUser;
+use metadata_struct::placement::node::BrokerNode;
+use protocol::placement_center::placement_center_mqtt::GetShareSubLeaderReply;
+
+use self::local::LocalMetadataStorage;
+use self::placement::PlacementMetadataStorage;
+
+pub mod expire;
+pub mod local;
+pub mod placement;
+
+pub const METADATA_STORAGE_TYPE_ROCKSDB: &str = "rocksdb";
+
+/// The metadata of the broker: nodes, cluster config, users, ACLs, topics, sessions,
+/// subscriptions and the idempotent data of the clients. The facades in `storage/*` go
+/// through it, so the broker runs the same against the placement center of a cluster and
+/// against the local RocksDB of a single node.
+#[async_trait]
+pub trait MetadataStorage: Send + Sync {
+    async fn node_list(&self) -> Result<Vec<BrokerNode>, CommonError>;
+
+    async fn register_node(&self, config: &BrokerMqttConfig) -> Result<(), CommonError>;
+
+    async fn unregister_node(&self, config: &BrokerMqttConfig) -> Result<(), CommonError>;
+
+    async fn heartbeat(&self) -> Result<(), CommonError>;
+
+    async fn set_cluster_config(
+        &self,
+        cluster_name: &str,
+        cluster: MqttClusterDynamicConfig,
+    ) -> Result<(), CommonError>;
+
+    async fn get_cluster_config(
+        &self,
+        cluster_name: &str,
+    ) -> Result<Option<MqttClusterDynamicConfig>, CommonError>;
+
+    async fn delete_cluster_config(&self, cluster_name: &str) -> Result<(), CommonError>;
+
+    async fn save_user(&self, user: MqttUser) -> Result<(), CommonError>;
+
+    async fn delete_user(&self, username: String) -> Result<(), CommonError>;
+
+    // An empty username lists every user
+    async fn list_user(&self, username: String) -> Result<Vec<MqttUser>, CommonError>;
+
+    async fn list_acl(&self) -> Result<Vec<MqttAcl>, CommonError>;
+
+    async fn save_acl(&self, acl: MqttAcl) -> Result<(), CommonError>;
+
+    async fn delete_acl(&self, acl: MqttAcl) -> Result<(), CommonError>;
+
+    async fn list_blacklist(&self) -> Result<Vec<MqttAclBlackList>, CommonError>;
+
+    async fn save_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), CommonError>;
+
+    async fn delete_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), CommonError>;
+
+    async fn save_alarm(&self, alarm: &MqttAlarm) -> Result<(), CommonError>;
+
+    async fn list_alarm(
+        &self,
+        only_active: bool,
+        limit: u32,
+    ) -> Result<Vec<MqttAlarm>, CommonError>;
+
+    async fn save_topic(&self, topic: MqttTopic) -> Result<(), CommonError>;
+
+    async fn delete_topic(&self, topic_name: String) -> Result<(), CommonError>;
+
+    // An empty topic name lists every topic
+    async fn list_topic(&self, topic_name: String) -> Result<Vec<MqttTopic>, CommonError>;
+
+    // An empty retain message removes the retained message of the topic
+    async fn set_retain_message(
+        &self,
+        topic_name: String,
+        retain_message: Vec<u8>,
+        retain_message_expired_at: u64,
+    ) -> Result<(), CommonError>;
+
+    async fn list_topic_rewrite_rule(&self) -> Result<Vec<MqttTopicRewriteRule>, CommonError>;
+
+    async fn save_topic_rewrite_rule(&self, rule: &MqttTopicRewriteRule)
+        -> Result<(), CommonError>;
+
+    async fn delete_topic_rewrite_rule(
+        &self,
+        action: String,
+        source_topic: String,
+    ) -> Result<(), CommonError>;
+
+    // Returns false when an exclusive subscription already holds a matching topic
+    async fn set_nx_exclusive_topic(&self, topic_name: String) -> Result<bool, CommonError>;
+
+    async fn delete_exclusive_topic(&self, topic_name: String) -> Result<(), CommonError>;
+
+    async fn set_session(
+        &self,
+        client_id: String,
+        session: &MqttSession,
+    ) -> Result<(), CommonError>;
+
+    async fn update_session(
+        &self,
+        client_id: String,
+        connection_id: u64,
+        broker_id: u64,
+        reconnect_time: u64,
+        distinct_time: u64,
+    ) -> Result<(), CommonError>;
+
+    async fn delete_session(&self, client_id: String) -> Result<(), CommonError>;
+
+    // An empty client id lists every session
+    async fn list_session(&self, client_id: String) -> Result<Vec<MqttSession>, CommonError>;
+
+    async fn save_last_will_message(
+        &self,
+        client_id: String,
+        last_will_message: Vec<u8>,
+    ) -> Result<(), CommonError>;
+
+    async fn list_qos2_state(&self, client_id: &str) -> Result<Vec<MqttQos2State>, CommonError>;
+
+    async fn save_qos2_state(&self, state: &MqttQos2State) -> Result<(), CommonError>;
+
+    async fn delete_qos2_state(
+        &self,
+        client_id: &str,
+        direction: &str,
+        pkid: u16,
+    ) -> Result<(), CommonError>;
+
+    async fn save_subscribe(&self, subscribe: &MqttSubscribe) -> Result<(), CommonError>;
+
+    // An empty path removes every subscription of the client
+    async fn delete_subscribe(&self, client_id: &str, path: &str) -> Result<(), CommonError>;
+
+    async fn list_subscribe(&self, client_id: &str) -> Result<Vec<MqttSubscribe>, CommonError>;
+
+    async fn get_share_sub_leader(
+        &self,
+        group_name: String,
+    ) -> Result<GetShareSubLeaderReply, CommonError>;
+
+    async fn list_auto_subscribe_rule(&self) -> Result<Vec<MqttAutoSubscribeRule>, CommonError>;
+
+    async fn save_auto_subscribe_rule(
+        &self,
+        rule: &MqttAutoSubscribeRule,
+    ) -> Result<(), CommonError>;
+
+    async fn delete_auto_subscribe_rule(
+        &self,
+        username: String,
+        topic: String,
+    ) -> Result<(), CommonError>;
+
+    async fn set_idempotent_data(&self, producer_id: &str, seq_num: u64)
+        -> Result<(), CommonError>;
+
+    async fn exists_idempotent_data(
+        &self,
+        producer_id: &str,
+        seq_num: u64,
+    ) -> Result<bool, CommonError>;
+
+    async fn delete_idempotent_data(
+        &self,
+        producer_id: &str,
+        seq_num: u64,
+    ) -> Result<(), CommonError>;
+}
+
+// RocksDB takes a lock on its data directory, so the process opens the local metadata
+// storage once and every facade shares it
+static LOCAL_METADATA_STORAGE: OnceLock<Arc<LocalMetadataStorage>> = OnceLock::new();
+
+pub fn is_local_metadata_storage() -> bool {
+    broker_mqtt_conf().metadata.storage_type == METADATA_STORAGE_TYPE_ROCKSDB
+}
+
+pub fn local_metadata_storage() -> Arc<LocalMetadataStorage> {
+    LOCAL_METADATA_STORAGE
+        .get_or_init(|| {
+            let conf = &broker_mqtt_conf().metadata;
+            if conf.rocksdb_data_path.is_empty() {
+                panic!("metadata storage type is [rocksdb],[metadata.rocksdb_data_path] cannot be empty");
+            }
+            Arc::new(LocalMetadataStorage::new(
+                &conf.rocksdb_data_path,
+                conf.rocksdb_max_open_files.unwrap_or(1000),
+            ))
+        })
+        .clone()
+}
+
+pub fn build_metadata_storage(client_pool: Arc<ClientPool>) -> Arc<dyn MetadataStorage> {
+    if is_local_metadata_storage() {
+        return local_metadata_storage();
+    }
+    Arc::new(PlacementMetadataStorage::new(client_pool))
+}
+
+pub(crate) fn build_node_extend(config: &BrokerMqttConfig) -> MqttNodeExtend {
+    let local_ip = get_local_ip();
+    MqttNodeExtend {
+        grpc_addr: format!("{}:{}", local_ip, config.grpc_port),
+        http_addr: format!("{}:{}", local_ip, config.http_port),
+        mqtt_addr: format!("{}:{}", local_ip, config.network.tcp_port),
+        mqtts_addr: format!("{}:{}", local_ip, config.network.tcps_port),
+        websocket_addr: format!("{}:{}", local_ip, config.network.websocket_port),
+        websockets_addr: format!("{}:{}", local_ip, config.network.websockets_port),
+        quic_addr: format!("{}:{}", local_ip, config.network.quic_port),
+    }
+}
@@ -0,0 +1,726 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use axum::async_trait;
+use common_base::config::broker_mqtt::{broker_mqtt_conf, BrokerMqttConfig};
+use common_base::error::common::CommonError;
+use common_base::tools::get_local_ip;
+use grpc_clients::placement::inner::call::{
+    delete_idempotent_data, delete_resource_config, exists_idempotent_data, get_resource_config,
+    heartbeat, node_list, register_node, set_idempotent_data, set_resource_config, unregister_node,
+};
+use grpc_clients::placement::mqtt::call::{
+    create_acl, create_blacklist, delete_acl, delete_blacklist, list_acl, list_blacklist,
+    placement_create_auto_subscribe_rule, placement_create_session, placement_create_topic,
+    placement_create_topic_rewrite_rule, placement_create_user,
+    placement_delete_auto_subscribe_rule, placement_delete_exclusive_topic,
+    placement_delete_qos2_state, placement_delete_session, placement_delete_subscribe,
+    placement_delete_topic, placement_delete_topic_rewrite_rule, placement_delete_user,
+    placement_get_share_sub_leader, placement_list_alarm, placement_list_auto_subscribe_rule,
+    placement_list_qos2_state, placement_list_session, placement_list_subscribe,
+    placement_list_topic, placement_list_topic_rewrite_rule, placement_list_user,
+    placement_save_alarm, placement_save_last_will_message, placement_save_qos2_state,
+    placement_set_nx_exclusive_topic, placement_set_subscribe, placement_set_topic_retain_message,
+    placement_update_session,
+};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::acl::mqtt_acl::MqttAcl;
+use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
+use metadata_struct::mqtt::alarm::MqttAlarm;
+use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
+use metadata_struct::mqtt::cluster::MqttClusterDynamicConfig;
+use metadata_struct::mqtt::qos2_state::MqttQos2State;
+use metadata_struct::mqtt::session::MqttSession;
+use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+use metadata_struct::mqtt::topic::MqttTopic;
+use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
+use metadata_struct::mqtt::user::MqttUser;
+use metadata_struct::placement::node::BrokerNode;
+use protocol::placement_center::placement_center_inner::{
+    ClusterType, DeleteIdempotentDataRequest, DeleteResourceConfigRequest,
+    ExistsIdempotentDataRequest, GetResourceConfigRequest, HeartbeatRequest, NodeListRequest,
+    RegisterNodeRequest, SetIdempotentDataRequest, SetResourceConfigRequest, UnRegisterNodeRequest,
+};
+use protocol::placement_center::placement_center_mqtt::{
+    CreateAclRequest, CreateAutoSubscribeRuleRequest, CreateBlacklistRequest, CreateSessionRequest,
+    CreateTopicRequest, CreateTopicRewriteRuleRequest, CreateUserRequest, DeleteAclRequest,
+    DeleteAutoSubscribeRuleRequest, DeleteBlacklistRequest, DeleteExclusiveTopicRequest,
+    DeleteQos2StateRequest, DeleteSessionRequest, DeleteSubscribeRequest, DeleteTopicRequest,
+    DeleteTopicRewriteRuleRequest, DeleteUserRequest, GetShareSubLeaderReply,
+    GetShareSubLeaderRequest, ListAclRequest, ListAlarmRequest, ListAutoSubscribeRuleRequest,
+    ListBlacklistRequest, ListQos2StateRequest, ListSessionRequest, ListSubscribeRequest,
+    ListTopicRequest, ListTopicRewriteRuleRequest, ListUserRequest, SaveAlarmRequest,
+    SaveLastWillMessageRequest, SaveQos2StateRequest, SetExclusiveTopicRequest,
+    SetSubscribeRequest, SetTopicRetainMessageRequest, UpdateSessionRequest,
+};
+
+use super::{build_node_extend, MetadataStorage};
+
+/// Metadata kept by the placement center of the cluster, reached over gRPC
+pub struct PlacementMetadataStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl PlacementMetadataStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        PlacementMetadataStorage { client_pool }
+    }
+
+    fn cluster_config_resources(&self, cluster_name: String) -> Vec<String> {
+        vec!["cluster".to_string(), cluster_name]
+    }
+}
+
+#[async_trait]
+impl MetadataStorage for PlacementMetadataStorage {
+    async fn node_list(&self) -> Result<Vec<BrokerNode>, CommonError> {
+        let conf = broker_mqtt_conf();
+        let request = NodeListRequest {
+            cluster_name: conf.cluster_name.clone(),
+        };
+
+        let reply = node_list(&self.client_pool, &conf.placement_center, request).await?;
+
+        let mut node_list: Vec<BrokerNode> = Vec::new();
+        for node in reply.nodes {
+            match serde_json::from_slice::<BrokerNode>(&node) {
+                Ok(data) => node_list.push(data),
+                Err(e) => {
+                    return Err(CommonError::CommonError(format!("Retrieving cluster Node list, parsing Node information failed, error message :{}", e)));
+                }
+            }
+        }
+        Ok(node_list)
+    }
+
+    async fn register_node(&self, config: &BrokerMqttConfig) -> Result<(), CommonError> {
+        let local_ip = get_local_ip();
+
+        let node = build_node_extend(config);
+        let req = RegisterNodeRequest {
+            cluster_type: ClusterType::MqttBrokerServer.into(),
+            cluster_name: config.cluster_name.clone(),
+            node_ip: local_ip.clone(),
+            node_id: config.broker_id,
+            node_inner_addr: format!("{}:{}", local_ip, config.grpc_port),
+            extend_info: serde_json::to_string(&node).unwrap(),
+        };
+
+        register_node(&self.client_pool, &config.placement_center, req.clone()).await?;
+
+        Ok(())
+    }
+
+    async fn unregister_node(&self, config: &BrokerMqttConfig) -> Result<(), CommonError> {
+        let req = UnRegisterNodeRequest {
+            cluster_type: ClusterType::MqttBrokerServer.into(),
+            cluster_name: config.cluster_name.clone(),
+            node_id: config.broker_id,
+        };
+
+        unregister_node(&self.client_pool, &config.placement_center, req.clone()).await?;
+        Ok(())
+    }
+
+    async fn heartbeat(&self) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let req = HeartbeatRequest {
+            cluster_name: config.cluster_name.clone(),
+            cluster_type: ClusterType::MqttBrokerServer.into(),
+            node_id: config.broker_id,
+        };
+
+        heartbeat(&self.client_pool, &config.placement_center, req.clone()).await?;
+
+        Ok(())
+    }
+
+    async fn set_cluster_config(
+        &self,
+        cluster_name: &str,
+        cluster: MqttClusterDynamicConfig,
+    ) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let resources = self.cluster_config_resources(cluster_name.to_string());
+        let request = SetResourceConfigRequest {
+            cluster_name: cluster_name.to_string(),
+            resources,
+            config: cluster.encode(),
+        };
+
+        set_resource_config(&self.client_pool, &config.placement_center, request).await?;
+
+        Ok(())
+    }
+
+    async fn get_cluster_config(
+        &self,
+        cluster_name: &str,
+    ) -> Result<Option<MqttClusterDynamicConfig>, CommonError> {
+        let config = broker_mqtt_conf();
+        let resources = self.cluster_config_resources(cluster_name.to_string());
+        let request = GetResourceConfigRequest {
+            cluster_name: cluster_name.to_string(),
+            resources,
+        };
+
+        match get_resource_config(&self.client_pool, &config.placement_center, request).await {
+            Ok(data) => {
+                if data.config.is_empty() {
+                    Ok(None)
+                } else {
+                    match serde_json::from_slice::<MqttClusterDynamicConfig>(&data.config) {
+                        Ok(data) => Ok(Some(data)),
+                        Err(e) => Err(CommonError::CommonError(e.to_string())),
+                    }
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn delete_cluster_config(&self, cluster_name: &str) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let resources = self.cluster_config_resources(cluster_name.to_string());
+        let request = DeleteResourceConfigRequest {
+            cluster_name: cluster_name.to_string(),
+            resources,
+        };
+
+        delete_resource_config(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn save_user(&self, user: MqttUser) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = CreateUserRequest {
+            cluster_name: config.cluster_name.clone(),
+            user_name: user.username.clone(),
+            content: user.encode(),
+        };
+        placement_create_user(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn delete_user(&self, username: String) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteUserRequest {
+            cluster_name: config.cluster_name.clone(),
+            user_name: username,
+        };
+        placement_delete_user(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn list_user(&self, username: String) -> Result<Vec<MqttUser>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = ListUserRequest {
+            cluster_name: config.cluster_name.clone(),
+            user_name: username,
+        };
+        let reply =
+            placement_list_user(&self.client_pool, &config.placement_center, request).await?;
+        let mut results = Vec::new();
+        for raw in reply.users {
+            results.push(serde_json::from_slice::<MqttUser>(&raw)?);
+        }
+        Ok(results)
+    }
+
+    async fn list_acl(&self) -> Result<Vec<MqttAcl>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = ListAclRequest {
+            cluster_name: config.cluster_name.clone(),
+        };
+        let reply = list_acl(&self.client_pool, &config.placement_center, request).await?;
+        let mut list = Vec::new();
+        for raw in reply.acls {
+            list.push(serde_json::from_slice::<MqttAcl>(raw.as_slice())?);
+        }
+        Ok(list)
+    }
+
+    async fn save_acl(&self, acl: MqttAcl) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = CreateAclRequest {
+            cluster_name: config.cluster_name.clone(),
+            acl: acl.encode()?,
+        };
+        create_acl(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn delete_acl(&self, acl: MqttAcl) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteAclRequest {
+            cluster_name: config.cluster_name.clone(),
+            acl: acl.encode()?,
+        };
+        delete_acl(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn list_blacklist(&self) -> Result<Vec<MqttAclBlackList>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = ListBlacklistRequest {
+            cluster_name: config.cluster_name.clone(),
+        };
+        let reply = list_blacklist(&self.client_pool, &config.placement_center, request).await?;
+        let mut list = Vec::new();
+        for raw in reply.blacklists {
+            list.push(serde_json::from_slice::<MqttAclBlackList>(raw.as_slice())?);
+        }
+        Ok(list)
+    }
+
+    async fn save_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = CreateBlacklistRequest {
+            cluster_name: config.cluster_name.clone(),
+            blacklist: blacklist.encode()?,
+        };
+        create_blacklist(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn delete_blacklist(&self, blacklist: MqttAclBlackList) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteBlacklistRequest {
+            cluster_name: config.cluster_name.clone(),
+            blacklist_type: blacklist.blacklist_type.to_string(),
+            resource_name: blacklist.resource_name,
+        };
+        delete_blacklist(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn save_alarm(&self, alarm: &MqttAlarm) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = SaveAlarmRequest {
+            cluster_name: config.cluster_name.clone(),
+            alarm: alarm.encode()?,
+        };
+        placement_save_alarm(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn list_alarm(
+        &self,
+        only_active: bool,
+        limit: u32,
+    ) -> Result<Vec<MqttAlarm>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = ListAlarmRequest {
+            cluster_name: config.cluster_name.clone(),
+            only_active,
+            limit,
+        };
+        let reply =
+            placement_list_alarm(&self.client_pool, &config.placement_center, request).await?;
+        let mut results = Vec::new();
+        for raw in reply.alarms {
+            results.push(MqttAlarm::decode(&raw)?);
+        }
+        Ok(results)
+    }
+
+    async fn save_topic(&self, topic: MqttTopic) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = CreateTopicRequest {
+            cluster_name: config.cluster_name.clone(),
+            topic_name: topic.topic_name.clone(),
+            content: topic.encode(),
+        };
+        placement_create_topic(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn delete_topic(&self, topic_name: String) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteTopicRequest {
+            cluster_name: config.cluster_name.clone(),
+            topic_name,
+        };
+        placement_delete_topic(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn list_topic(&self, topic_name: String) -> Result<Vec<MqttTopic>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = ListTopicRequest {
+            cluster_name: config.cluster_name.clone(),
+            topic_name,
+        };
+        let reply =
+            placement_list_topic(&self.client_pool, &config.placement_center, request).await?;
+        let mut results = Vec::new();
+        for raw in reply.topics {
+            results.push(serde_json::from_slice::<MqttTopic>(&raw)?);
+        }
+        Ok(results)
+    }
+
+    async fn set_retain_message(
+        &self,
+        topic_name: String,
+        retain_message: Vec<u8>,
+        retain_message_expired_at: u64,
+    ) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = SetTopicRetainMessageRequest {
+            cluster_name: config.cluster_name.clone(),
+            topic_name,
+            retain_message,
+            retain_message_expired_at,
+        };
+        placement_set_topic_retain_message(&self.client_pool, &config.placement_center, request)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_topic_rewrite_rule(&self) -> Result<Vec<MqttTopicRewriteRule>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = ListTopicRewriteRuleRequest {
+            cluster_name: config.cluster_name.clone(),
+        };
+        let reply =
+            placement_list_topic_rewrite_rule(&self.client_pool, &config.placement_center, request)
+                .await?;
+        let mut results = Vec::new();
+        for raw in reply.topic_rewrite_rules {
+            results.push(MqttTopicRewriteRule::decode(&raw)?);
+        }
+        Ok(results)
+    }
+
+    async fn save_topic_rewrite_rule(
+        &self,
+        rule: &MqttTopicRewriteRule,
+    ) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = CreateTopicRewriteRuleRequest {
+            cluster_name: config.cluster_name.clone(),
+            topic_rewrite_rule: rule.encode()?,
+        };
+        placement_create_topic_rewrite_rule(&self.client_pool, &config.placement_center, request)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_topic_rewrite_rule(
+        &self,
+        action: String,
+        source_topic: String,
+    ) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteTopicRewriteRuleRequest {
+            cluster_name: config.cluster_name.clone(),
+            action,
+            source_topic,
+        };
+        placement_delete_topic_rewrite_rule(&self.client_pool, &config.placement_center, request)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_nx_exclusive_topic(&self, topic_name: String) -> Result<bool, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = SetExclusiveTopicRequest {
+            cluster_name: config.cluster_name.clone(),
+            topic_name,
+        };
+        let reply =
+            placement_set_nx_exclusive_topic(&self.client_pool, &config.placement_center, request)
+                .await?;
+        Ok(reply.success)
+    }
+
+    async fn delete_exclusive_topic(&self, topic_name: String) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteExclusiveTopicRequest {
+            cluster_name: config.cluster_name.clone(),
+            topic_name,
+        };
+        placement_delete_exclusive_topic(&self.client_pool, &config.placement_center, request)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_session(
+        &self,
+        client_id: String,
+        session: &MqttSession,
+    ) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = CreateSessionRequest {
+            cluster_name: config.cluster_name.clone(),
+            client_id,
+            session: session.encode(),
+        };
+        placement_create_session(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn update_session(
+        &self,
+        client_id: String,
+        connection_id: u64,
+        broker_id: u64,
+        reconnect_time: u64,
+        distinct_time: u64,
+    ) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = UpdateSessionRequest {
+            cluster_name: config.cluster_name.clone(),
+            client_id,
+            connection_id,
+            broker_id,
+            reconnect_time,
+            distinct_time,
+        };
+        placement_update_session(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn delete_session(&self, client_id: String) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteSessionRequest {
+            cluster_name: config.cluster_name.clone(),
+            client_id,
+        };
+        placement_delete_session(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn list_session(&self, client_id: String) -> Result<Vec<MqttSession>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = ListSessionRequest {
+            cluster_name: config.cluster_name.clone(),
+            client_id,
+        };
+        let reply =
+            placement_list_session(&self.client_pool, &config.placement_center, request).await?;
+        let mut results = Vec::new();
+        for raw in reply.sessions {
+            if let Ok(data) = serde_json::from_slice::<MqttSession>(&raw) {
+                results.push(data);
+            }
+        }
+        Ok(results)
+    }
+
+    async fn save_last_will_message(
+        &self,
+        client_id: String,
+        last_will_message: Vec<u8>,
+    ) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = SaveLastWillMessageRequest {
+            cluster_name: config.cluster_name.clone(),
+            client_id,
+            last_will_message,
+        };
+        placement_save_last_will_message(&self.client_pool, &config.placement_center, request)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_qos2_state(&self, client_id: &str) -> Result<Vec<MqttQos2State>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = ListQos2StateRequest {
+            cluster_name: config.cluster_name.clone(),
+            client_id: client_id.to_string(),
+        };
+        let reply =
+            placement_list_qos2_state(&self.client_pool, &config.placement_center, request).await?;
+        let mut results = Vec::new();
+        for raw in reply.qos2_states {
+            results.push(MqttQos2State::decode(&raw)?);
+        }
+        Ok(results)
+    }
+
+    async fn save_qos2_state(&self, state: &MqttQos2State) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = SaveQos2StateRequest {
+            cluster_name: config.cluster_name.clone(),
+            qos2_state: state.encode()?,
+        };
+        placement_save_qos2_state(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn delete_qos2_state(
+        &self,
+        client_id: &str,
+        direction: &str,
+        pkid: u16,
+    ) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteQos2StateRequest {
+            cluster_name: config.cluster_name.clone(),
+            client_id: client_id.to_string(),
+            direction: direction.to_string(),
+            pkid: pkid as u32,
+        };
+        placement_delete_qos2_state(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn save_subscribe(&self, subscribe: &MqttSubscribe) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = SetSubscribeRequest {
+            cluster_name: config.cluster_name.clone(),
+            client_id: subscribe.client_id.clone(),
+            path: subscribe.path.clone(),
+            subscribe: subscribe.encode(),
+        };
+        placement_set_subscribe(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn delete_subscribe(&self, client_id: &str, path: &str) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteSubscribeRequest {
+            cluster_name: config.cluster_name.clone(),
+            client_id: client_id.to_string(),
+            path: path.to_string(),
+        };
+        placement_delete_subscribe(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn list_subscribe(&self, client_id: &str) -> Result<Vec<MqttSubscribe>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = ListSubscribeRequest {
+            cluster_name: config.cluster_name.clone(),
+            client_id: client_id.to_string(),
+        };
+        let reply =
+            placement_list_subscribe(&self.client_pool, &config.placement_center, request).await?;
+        let mut results = Vec::new();
+        for raw in reply.subscribes {
+            results.push(serde_json::from_slice::<MqttSubscribe>(&raw)?);
+        }
+        Ok(results)
+    }
+
+    async fn get_share_sub_leader(
+        &self,
+        group_name: String,
+    ) -> Result<GetShareSubLeaderReply, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = GetShareSubLeaderRequest {
+            cluster_name: config.cluster_name.clone(),
+            group_name,
+        };
+        placement_get_share_sub_leader(&self.client_pool, &config.placement_center, request).await
+    }
+
+    async fn list_auto_subscribe_rule(&self) -> Result<Vec<MqttAutoSubscribeRule>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = ListAutoSubscribeRuleRequest {
+            cluster_name: config.cluster_name.clone(),
+        };
+        let reply = placement_list_auto_subscribe_rule(
+            &self.client_pool,
+            &config.placement_center,
+            request,
+        )
+        .await?;
+        let mut results = Vec::new();
+        for raw in reply.auto_subscribe_rules {
+            results.push(MqttAutoSubscribeRule::decode(&raw)?);
+        }
+        Ok(results)
+    }
+
+    async fn save_auto_subscribe_rule(
+        &self,
+        rule: &MqttAutoSubscribeRule,
+    ) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = CreateAutoSubscribeRuleRequest {
+            cluster_name: config.cluster_name.clone(),
+            auto_subscribe_rule: rule.encode()?,
+        };
+        placement_create_auto_subscribe_rule(&self.client_pool, &config.placement_center, request)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_auto_subscribe_rule(
+        &self,
+        username: String,
+        topic: String,
+    ) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteAutoSubscribeRuleRequest {
+            cluster_name: config.cluster_name.clone(),
+            username,
+            topic,
+        };
+        placement_delete_auto_subscribe_rule(&self.client_pool, &config.placement_center, request)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_idempotent_data(
+        &self,
+        producer_id: &str,
+        seq_num: u64,
+    ) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = SetIdempotentDataRequest {
+            cluster_name: config.cluster_name.clone(),
+            producer_id: producer_id.to_owned(),
+            seq_num,
+        };
+        set_idempotent_data(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    async fn exists_idempotent_data(
+        &self,
+        producer_id: &str,
+        seq_num: u64,
+    ) -> Result<bool, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = ExistsIdempotentDataRequest {
+            cluster_name: config.cluster_name.clone(),
+            producer_id: producer_id.to_owned(),
+            seq_num,
+        };
+        let reply =
+            exists_idempotent_data(&self.client_pool, &config.placement_center, request).await?;
+        Ok(reply.exists)
+    }
+
+    async fn delete_idempotent_data(
+        &self,
+        producer_id: &str,
+        seq_num: u64,
+    ) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteIdempotentDataRequest {
+            cluster_name: config.cluster_name.clone(),
+            producer_id: producer_id.to_owned(),
+            seq_num,
+        };
+        delete_idempotent_data(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+}
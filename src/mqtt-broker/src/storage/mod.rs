@@ -13,9 +13,12 @@
 // limitations under the License.
 
 pub mod acl;
+pub mod alarm;
 pub mod blacklist;
 pub mod cluster;
 pub mod message;
+pub mod metadata;
 pub mod session;
+pub mod subscribe;
 pub mod topic;
 pub mod user;
@@ -14,27 +14,23 @@
 
 use std::sync::Arc;
 
-use common_base::config::broker_mqtt::broker_mqtt_conf;
 use common_base::error::common::CommonError;
 use dashmap::DashMap;
-use grpc_clients::placement::mqtt::call::{
-    placement_create_session, placement_delete_session, placement_list_session,
-    placement_save_last_will_message, placement_update_session,
-};
 use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::qos2_state::MqttQos2State;
 use metadata_struct::mqtt::session::MqttSession;
-use protocol::placement_center::placement_center_mqtt::{
-    CreateSessionRequest, DeleteSessionRequest, ListSessionRequest, SaveLastWillMessageRequest,
-    UpdateSessionRequest,
-};
+
+use super::metadata::{build_metadata_storage, MetadataStorage};
 
 pub struct SessionStorage {
-    client_pool: Arc<ClientPool>,
+    metadata_storage: Arc<dyn MetadataStorage>,
 }
 
 impl SessionStorage {
     pub fn new(client_pool: Arc<ClientPool>) -> Self {
-        SessionStorage { client_pool }
+        SessionStorage {
+            metadata_storage: build_metadata_storage(client_pool),
+        }
     }
 
     pub async fn set_session(
@@ -42,16 +38,7 @@ impl SessionStorage {
         client_id: String,
         session: &MqttSession,
     ) -> Result<(), CommonError> {
-        let config = broker_mqtt_conf();
-        let request = CreateSessionRequest {
-            cluster_name: config.cluster_name.clone(),
-            client_id,
-            session: session.encode(),
-        };
-        match placement_create_session(&self.client_pool, &config.placement_center, request).await {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e),
-        }
+        self.metadata_storage.set_session(client_id, session).await
     }
 
     pub async fn update_session(
@@ -62,77 +49,32 @@ impl SessionStorage {
         reconnect_time: u64,
         distinct_time: u64,
     ) -> Result<(), CommonError> {
-        let config = broker_mqtt_conf();
-        let request = UpdateSessionRequest {
-            cluster_name: config.cluster_name.clone(),
-            client_id,
-            connection_id,
-            broker_id,
-            reconnect_time,
-            distinct_time,
-        };
-        match placement_update_session(&self.client_pool, &config.placement_center, request).await {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e),
-        }
+        self.metadata_storage
+            .update_session(
+                client_id,
+                connection_id,
+                broker_id,
+                reconnect_time,
+                distinct_time,
+            )
+            .await
     }
 
     pub async fn delete_session(&self, client_id: String) -> Result<(), CommonError> {
-        let config = broker_mqtt_conf();
-        let request = DeleteSessionRequest {
-            cluster_name: config.cluster_name.clone(),
-            client_id,
-        };
-        match placement_delete_session(&self.client_pool, &config.placement_center, request).await {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e),
-        }
+        self.metadata_storage.delete_session(client_id).await
     }
 
     pub async fn get_session(&self, client_id: String) -> Result<Option<MqttSession>, CommonError> {
-        let config = broker_mqtt_conf();
-        let request = ListSessionRequest {
-            cluster_name: config.cluster_name.clone(),
-            client_id,
-        };
-        match placement_list_session(&self.client_pool, &config.placement_center, request).await {
-            Ok(reply) => {
-                if reply.sessions.is_empty() {
-                    return Ok(None);
-                }
-                let raw = reply.sessions.first().unwrap();
-                match serde_json::from_slice::<MqttSession>(raw) {
-                    Ok(data) => Ok(Some(data)),
-                    Err(e) => Err(CommonError::CommonError(e.to_string())),
-                }
-            }
-            Err(e) => Err(e),
-        }
+        let list = self.metadata_storage.list_session(client_id).await?;
+        Ok(list.into_iter().next())
     }
 
     pub async fn list_session(&self) -> Result<DashMap<String, MqttSession>, CommonError> {
-        let config = broker_mqtt_conf();
-        let request = ListSessionRequest {
-            cluster_name: config.cluster_name.clone(),
-            client_id: "".to_string(),
-        };
-        match placement_list_session(&self.client_pool, &config.placement_center, request).await {
-            Ok(reply) => {
-                let results = DashMap::with_capacity(2);
-                for raw in reply.sessions {
-                    match serde_json::from_slice::<MqttSession>(&raw) {
-                        Ok(data) => {
-                            results.insert(data.client_id.clone(), data);
-                        }
-                        Err(_) => {
-                            continue;
-                        }
-                    }
-                }
-                Ok(results)
-            }
-            Err(e) => Err(e),
+        let results = DashMap::with_capacity(2);
+        for data in self.metadata_storage.list_session("".to_string()).await? {
+            results.insert(data.client_id.clone(), data);
         }
+        Ok(results)
     }
 
     pub async fn save_last_will_message(
@@ -140,17 +82,30 @@ impl SessionStorage {
         client_id: String,
         last_will_message: Vec<u8>,
     ) -> Result<(), CommonError> {
-        let config = broker_mqtt_conf();
-        let request = SaveLastWillMessageRequest {
-            cluster_name: config.cluster_name.clone(),
-            client_id,
-            last_will_message,
-        };
-        match placement_save_last_will_message(&self.client_pool, &config.placement_center, request)
+        self.metadata_storage
+            .save_last_will_message(client_id, last_will_message)
+            .await
+    }
+
+    pub async fn list_qos2_state(
+        &self,
+        client_id: &str,
+    ) -> Result<Vec<MqttQos2State>, CommonError> {
+        self.metadata_storage.list_qos2_state(client_id).await
+    }
+
+    pub async fn save_qos2_state(&self, state: &MqttQos2State) -> Result<(), CommonError> {
+        self.metadata_storage.save_qos2_state(state).await
+    }
+
+    pub async fn delete_qos2_state(
+        &self,
+        client_id: &str,
+        direction: &str,
+        pkid: u16,
+    ) -> Result<(), CommonError> {
+        self.metadata_storage
+            .delete_qos2_state(client_id, direction, pkid)
             .await
-        {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e),
-        }
     }
 }
@@ -0,0 +1,74 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
+use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+
+use super::metadata::{build_metadata_storage, MetadataStorage};
+
+pub struct SubscribeStorage {
+    metadata_storage: Arc<dyn MetadataStorage>,
+}
+
+impl SubscribeStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        SubscribeStorage {
+            metadata_storage: build_metadata_storage(client_pool),
+        }
+    }
+
+    pub async fn save_subscribe(&self, subscribe: &MqttSubscribe) -> Result<(), CommonError> {
+        self.metadata_storage.save_subscribe(subscribe).await
+    }
+
+    pub async fn delete_subscribe(&self, client_id: &str, path: &str) -> Result<(), CommonError> {
+        self.metadata_storage
+            .delete_subscribe(client_id, path)
+            .await
+    }
+
+    pub async fn delete_subscribe_by_client_id(&self, client_id: &str) -> Result<(), CommonError> {
+        // An empty path removes every subscription of the client
+        self.delete_subscribe(client_id, "").await
+    }
+
+    pub async fn list_subscribe(&self, client_id: &str) -> Result<Vec<MqttSubscribe>, CommonError> {
+        self.metadata_storage.list_subscribe(client_id).await
+    }
+
+    pub async fn all_auto_subscribe_rule(&self) -> Result<Vec<MqttAutoSubscribeRule>, CommonError> {
+        self.metadata_storage.list_auto_subscribe_rule().await
+    }
+
+    pub async fn save_auto_subscribe_rule(
+        &self,
+        rule: &MqttAutoSubscribeRule,
+    ) -> Result<(), CommonError> {
+        self.metadata_storage.save_auto_subscribe_rule(rule).await
+    }
+
+    pub async fn delete_auto_subscribe_rule(
+        &self,
+        username: String,
+        topic: String,
+    ) -> Result<(), CommonError> {
+        self.metadata_storage
+            .delete_auto_subscribe_rule(username, topic)
+            .await
+    }
+}
@@ -14,82 +14,50 @@
 
 use std::sync::Arc;
 
-use common_base::config::broker_mqtt::broker_mqtt_conf;
 use dashmap::DashMap;
-use grpc_clients::placement::mqtt::call::{
-    placement_create_topic, placement_delete_topic, placement_list_topic,
-    placement_set_topic_retain_message,
-};
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::message::MqttMessage;
 use metadata_struct::mqtt::topic::MqttTopic;
-use protocol::placement_center::placement_center_mqtt::{
-    CreateTopicRequest, DeleteTopicRequest, ListTopicRequest, SetTopicRetainMessageRequest,
-};
+use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
 
+use super::metadata::{build_metadata_storage, MetadataStorage};
 use crate::handler::error::MqttBrokerError;
 
 pub struct TopicStorage {
-    client_pool: Arc<ClientPool>,
+    metadata_storage: Arc<dyn MetadataStorage>,
 }
 
 impl TopicStorage {
     pub fn new(client_pool: Arc<ClientPool>) -> Self {
-        TopicStorage { client_pool }
+        TopicStorage {
+            metadata_storage: build_metadata_storage(client_pool),
+        }
     }
 
     pub async fn save_topic(&self, topic: MqttTopic) -> Result<(), MqttBrokerError> {
-        let config = broker_mqtt_conf();
-        let request = CreateTopicRequest {
-            cluster_name: config.cluster_name.clone(),
-            topic_name: topic.topic_name.clone(),
-            content: topic.encode(),
-        };
-        placement_create_topic(&self.client_pool, &config.placement_center, request).await?;
+        self.metadata_storage.save_topic(topic).await?;
         Ok(())
     }
 
     pub async fn delete_topic(&self, topic_name: String) -> Result<(), MqttBrokerError> {
-        let config = broker_mqtt_conf();
-        let request = DeleteTopicRequest {
-            cluster_name: config.cluster_name.clone(),
-            topic_name,
-        };
-        placement_delete_topic(&self.client_pool, &config.placement_center, request).await?;
+        self.metadata_storage.delete_topic(topic_name).await?;
         Ok(())
     }
 
     pub async fn all(&self) -> Result<DashMap<String, MqttTopic>, MqttBrokerError> {
-        let config = broker_mqtt_conf();
-        let request = ListTopicRequest {
-            cluster_name: config.cluster_name.clone(),
-            topic_name: "".to_string(),
-        };
-        let reply =
-            placement_list_topic(&self.client_pool, &config.placement_center, request).await?;
         let results = DashMap::with_capacity(2);
-        for raw in reply.topics {
-            let data = serde_json::from_slice::<MqttTopic>(&raw)?;
+        for data in self.metadata_storage.list_topic("".to_string()).await? {
             results.insert(data.topic_name.clone(), data);
         }
         Ok(results)
     }
 
     pub async fn get_topic(&self, topic_name: &str) -> Result<Option<MqttTopic>, MqttBrokerError> {
-        let config = broker_mqtt_conf();
-        let request = ListTopicRequest {
-            cluster_name: config.cluster_name.clone(),
-            topic_name: topic_name.to_owned(),
-        };
-
-        let reply =
-            placement_list_topic(&self.client_pool, &config.placement_center, request).await?;
-
-        if let Some(raw) = reply.topics.first() {
-            return Ok(Some(serde_json::from_slice::<MqttTopic>(raw)?));
-        }
-
-        Ok(None)
+        let list = self
+            .metadata_storage
+            .list_topic(topic_name.to_owned())
+            .await?;
+        Ok(list.into_iter().next())
     }
 
     pub async fn set_retain_message(
@@ -98,27 +66,19 @@ impl TopicStorage {
         retain_message: &MqttMessage,
         retain_message_expired_at: u64,
     ) -> Result<(), MqttBrokerError> {
-        let config = broker_mqtt_conf();
-        let request = SetTopicRetainMessageRequest {
-            cluster_name: config.cluster_name.clone(),
-            topic_name,
-            retain_message: retain_message.encode(),
-            retain_message_expired_at,
-        };
-        placement_set_topic_retain_message(&self.client_pool, &config.placement_center, request)
+        self.metadata_storage
+            .set_retain_message(
+                topic_name,
+                retain_message.encode(),
+                retain_message_expired_at,
+            )
             .await?;
         Ok(())
     }
 
     pub async fn delete_retain_message(&self, topic_name: String) -> Result<(), MqttBrokerError> {
-        let config = broker_mqtt_conf();
-        let request = SetTopicRetainMessageRequest {
-            cluster_name: config.cluster_name.clone(),
-            topic_name,
-            retain_message: Vec::new(),
-            retain_message_expired_at: 0,
-        };
-        placement_set_topic_retain_message(&self.client_pool, &config.placement_center, request)
+        self.metadata_storage
+            .set_retain_message(topic_name, Vec::new(), 0)
             .await?;
         Ok(())
     }
@@ -141,4 +101,29 @@ impl TopicStorage {
         }
         Err(MqttBrokerError::TopicDoesNotExist(topic_name.to_owned()))
     }
+
+    pub async fn all_topic_rewrite_rule(
+        &self,
+    ) -> Result<Vec<MqttTopicRewriteRule>, MqttBrokerError> {
+        Ok(self.metadata_storage.list_topic_rewrite_rule().await?)
+    }
+
+    pub async fn save_topic_rewrite_rule(
+        &self,
+        rule: &MqttTopicRewriteRule,
+    ) -> Result<(), MqttBrokerError> {
+        self.metadata_storage.save_topic_rewrite_rule(rule).await?;
+        Ok(())
+    }
+
+    pub async fn delete_topic_rewrite_rule(
+        &self,
+        action: String,
+        source_topic: String,
+    ) -> Result<(), MqttBrokerError> {
+        self.metadata_storage
+            .delete_topic_rewrite_rule(action, source_topic)
+            .await?;
+        Ok(())
+    }
 }
@@ -0,0 +1,418 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use metadata_struct::mqtt::cluster::MessageQueueDropPolicy;
+use protocol::mqtt::common::QoS;
+
+use super::SubPublishParam;
+use crate::handler::cache::{QosAckPackageData, QosAckPackageType};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InflightStage {
+    WaitPubAck,
+    WaitPubRec,
+    WaitPubComp,
+}
+
+#[derive(Clone, Debug)]
+pub struct InflightMessage {
+    // Offset of the record in the topic shard
+    pub offset: u64,
+    pub stage: InflightStage,
+    // The connection the message was last sent on, and counted against
+    pub connect_id: u64,
+    // Time in milliseconds when the message was last sent
+    pub send_time: u128,
+    pub param: SubPublishParam,
+}
+
+pub enum InflightAckAction {
+    // The message is fully acknowledged and leaves the window
+    Complete(InflightMessage),
+    // PubRec was received, PubRel must be sent for the message
+    SendPubRel(u16),
+    Ignore,
+}
+
+/// QoS1/QoS2 messages that have been sent to the client and are waiting for acknowledgement,
+/// kept in the order in which they were first sent.
+#[derive(Default)]
+pub struct InflightWindow {
+    messages: VecDeque<InflightMessage>,
+}
+
+impl InflightWindow {
+    pub fn new() -> Self {
+        InflightWindow {
+            messages: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, message: InflightMessage) {
+        self.messages.push_back(message);
+    }
+
+    pub fn get_mut(&mut self, pkid: u16) -> Option<&mut InflightMessage> {
+        self.messages.iter_mut().find(|msg| msg.param.pkid == pkid)
+    }
+
+    pub fn first_offset(&self) -> Option<u64> {
+        self.messages.front().map(|msg| msg.offset)
+    }
+
+    pub fn ack(&mut self, data: &QosAckPackageData, now: u128) -> InflightAckAction {
+        let index = if let Some(index) = self
+            .messages
+            .iter()
+            .position(|msg| msg.param.pkid == data.pkid)
+        {
+            index
+        } else {
+            return InflightAckAction::Ignore;
+        };
+
+        let stage = self.messages[index].stage.clone();
+        match (stage, &data.ack_type) {
+            (InflightStage::WaitPubAck, QosAckPackageType::PubAck)
+            | (InflightStage::WaitPubComp, QosAckPackageType::PubComp) => {
+                InflightAckAction::Complete(self.messages.remove(index).unwrap())
+            }
+            (InflightStage::WaitPubRec, QosAckPackageType::PubRec) => {
+                let message = &mut self.messages[index];
+                message.stage = InflightStage::WaitPubComp;
+                message.send_time = now;
+                InflightAckAction::SendPubRel(data.pkid)
+            }
+            _ => InflightAckAction::Ignore,
+        }
+    }
+
+    // Packet identifiers of the messages to retransmit, in sending order: the acknowledgement
+    // timed out, or the messages were sent on a connection that is no longer the current one
+    pub fn expired(&self, now: u128, retry_interval_ms: u64, connect_id: u64) -> Vec<u16> {
+        self.messages
+            .iter()
+            .filter(|msg| {
+                msg.connect_id != connect_id || now >= msg.send_time + retry_interval_ms as u128
+            })
+            .map(|msg| msg.param.pkid)
+            .collect()
+    }
+
+    pub fn drain(&mut self) -> Vec<InflightMessage> {
+        self.messages.drain(..).collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct QueuedMessage {
+    pub offset: u64,
+    // QoS the message was published with
+    pub qos: QoS,
+    pub param: SubPublishParam,
+}
+
+/// Messages of one subscription read from storage that have not been sent to the client yet.
+///
+/// The length limit applies to the session: all the queues of the subscriptions of a client
+/// share one counter. When the session is full, a message is discarded according to the
+/// drop policy.
+pub struct MessageQueue {
+    max_len: usize,
+    drop_policy: MessageQueueDropPolicy,
+    // Number of messages queued for all the subscriptions of the session
+    session_len: Arc<AtomicUsize>,
+    messages: VecDeque<QueuedMessage>,
+}
+
+impl MessageQueue {
+    pub fn new(
+        max_len: usize,
+        drop_policy: MessageQueueDropPolicy,
+        session_len: Arc<AtomicUsize>,
+    ) -> Self {
+        MessageQueue {
+            max_len,
+            drop_policy,
+            session_len,
+            messages: VecDeque::new(),
+        }
+    }
+
+    pub fn front(&self) -> Option<&QueuedMessage> {
+        self.messages.front()
+    }
+
+    pub fn pop_front(&mut self) -> Option<QueuedMessage> {
+        let message = self.messages.pop_front();
+        if message.is_some() {
+            self.session_len.fetch_sub(1, Ordering::Relaxed);
+        }
+        message
+    }
+
+    // Puts back a message taken from the queue that could not be sent, it stays the next one
+    pub fn push_front(&mut self, message: QueuedMessage) {
+        self.messages.push_front(message);
+        self.session_len.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn first_offset(&self) -> Option<u64> {
+        self.messages.front().map(|msg| msg.offset)
+    }
+
+    // Number of messages the session can still queue
+    pub fn free_len(&self) -> usize {
+        if self.max_len == 0 {
+            return usize::MAX;
+        }
+        self.max_len
+            .saturating_sub(self.session_len.load(Ordering::Relaxed))
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.free_len() == 0
+    }
+
+    // Returns the message that was discarded, if any
+    pub fn push(&mut self, message: QueuedMessage) -> Option<QueuedMessage> {
+        if !self.is_full() {
+            self.messages.push_back(message);
+            self.session_len.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        match self.drop_policy {
+            MessageQueueDropPolicy::DropOldest => {
+                // The session may be filled by the queues of its other subscriptions
+                if let Some(dropped) = self.messages.pop_front() {
+                    self.messages.push_back(message);
+                    Some(dropped)
+                } else {
+                    Some(message)
+                }
+            }
+            MessageQueueDropPolicy::DropNewest => Some(message),
+            MessageQueueDropPolicy::DropLowestQos => {
+                // The oldest of the messages with the lowest QoS is dropped,
+                // unless the new message has an equal or lower QoS itself
+                let mut lowest: Option<(usize, QoS)> = None;
+                for (index, msg) in self.messages.iter().enumerate() {
+                    let is_lower = match lowest {
+                        Some((_, qos)) => msg.qos < qos,
+                        None => true,
+                    };
+                    if is_lower {
+                        lowest = Some((index, msg.qos));
+                    }
+                }
+                match lowest {
+                    Some((index, qos)) if qos < message.qos => {
+                        let dropped = self.messages.remove(index);
+                        self.messages.push_back(message);
+                        dropped
+                    }
+                    _ => Some(message),
+                }
+            }
+        }
+    }
+
+    pub fn set_limit(&mut self, max_len: usize, drop_policy: MessageQueueDropPolicy) {
+        self.max_len = max_len;
+        self.drop_policy = drop_policy;
+    }
+}
+
+impl Drop for MessageQueue {
+    fn drop(&mut self) {
+        self.session_len
+            .fetch_sub(self.messages.len(), Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use metadata_struct::mqtt::cluster::MessageQueueDropPolicy;
+    use protocol::mqtt::common::QoS;
+
+    use super::{
+        InflightAckAction, InflightMessage, InflightStage, InflightWindow, MessageQueue,
+        QueuedMessage,
+    };
+    use crate::handler::cache::{QosAckPackageData, QosAckPackageType};
+    use crate::subscribe::SubPublishParam;
+
+    fn inflight_message(offset: u64, pkid: u16, stage: InflightStage) -> InflightMessage {
+        InflightMessage {
+            offset,
+            stage,
+            connect_id: 1,
+            send_time: 1000,
+            param: SubPublishParam {
+                pkid,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn message_queue(max_len: usize, drop_policy: MessageQueueDropPolicy) -> MessageQueue {
+        MessageQueue::new(max_len, drop_policy, Arc::new(AtomicUsize::new(0)))
+    }
+
+    fn queued_message(offset: u64, qos: QoS) -> QueuedMessage {
+        QueuedMessage {
+            offset,
+            qos,
+            param: SubPublishParam::default(),
+        }
+    }
+
+    #[test]
+    fn inflight_window_ack_test() {
+        let mut window = InflightWindow::new();
+        window.push(inflight_message(10, 1, InflightStage::WaitPubAck));
+        window.push(inflight_message(11, 2, InflightStage::WaitPubRec));
+        assert_eq!(window.first_offset(), Some(10));
+
+        // a PubRec does not acknowledge a QoS1 message
+        let ack = QosAckPackageData {
+            ack_type: QosAckPackageType::PubRec,
+            pkid: 1,
+        };
+        assert!(matches!(window.ack(&ack, 2000), InflightAckAction::Ignore));
+
+        let ack = QosAckPackageData {
+            ack_type: QosAckPackageType::PubRec,
+            pkid: 2,
+        };
+        assert!(matches!(
+            window.ack(&ack, 2000),
+            InflightAckAction::SendPubRel(2)
+        ));
+        assert_eq!(window.get_mut(2).unwrap().stage, InflightStage::WaitPubComp);
+
+        let ack = QosAckPackageData {
+            ack_type: QosAckPackageType::PubComp,
+            pkid: 2,
+        };
+        assert!(matches!(
+            window.ack(&ack, 2000),
+            InflightAckAction::Complete(_)
+        ));
+        // the oldest message is still unacknowledged
+        assert_eq!(window.first_offset(), Some(10));
+
+        let ack = QosAckPackageData {
+            ack_type: QosAckPackageType::PubAck,
+            pkid: 1,
+        };
+        assert!(matches!(
+            window.ack(&ack, 2000),
+            InflightAckAction::Complete(_)
+        ));
+        assert!(window.first_offset().is_none());
+    }
+
+    #[test]
+    fn inflight_window_expired_test() {
+        let mut window = InflightWindow::new();
+        window.push(inflight_message(10, 3, InflightStage::WaitPubAck));
+        window.push(inflight_message(11, 1, InflightStage::WaitPubAck));
+        window.get_mut(1).unwrap().send_time = 1500;
+
+        assert!(window.expired(1999, 1000, 1).is_empty());
+        assert_eq!(window.expired(2000, 1000, 1), vec![3]);
+        assert_eq!(window.expired(2500, 1000, 1), vec![3, 1]);
+
+        // everything is retransmitted once the client reconnects
+        assert_eq!(window.expired(1000, 1000, 2), vec![3, 1]);
+
+        assert_eq!(window.drain().len(), 2);
+        assert!(window.first_offset().is_none());
+    }
+
+    #[test]
+    fn message_queue_drop_policy_test() {
+        let mut queue = message_queue(2, MessageQueueDropPolicy::DropOldest);
+        assert!(queue.push(queued_message(1, QoS::AtLeastOnce)).is_none());
+        assert!(queue.push(queued_message(2, QoS::AtLeastOnce)).is_none());
+        let dropped = queue.push(queued_message(3, QoS::AtLeastOnce)).unwrap();
+        assert_eq!(dropped.offset, 1);
+        assert_eq!(queue.first_offset(), Some(2));
+
+        let mut queue = message_queue(2, MessageQueueDropPolicy::DropNewest);
+        queue.push(queued_message(1, QoS::AtLeastOnce));
+        queue.push(queued_message(2, QoS::AtLeastOnce));
+        let dropped = queue.push(queued_message(3, QoS::AtLeastOnce)).unwrap();
+        assert_eq!(dropped.offset, 3);
+        assert_eq!(queue.pop_front().unwrap().offset, 1);
+        assert_eq!(queue.pop_front().unwrap().offset, 2);
+
+        let mut queue = message_queue(3, MessageQueueDropPolicy::DropLowestQos);
+        queue.push(queued_message(1, QoS::ExactlyOnce));
+        queue.push(queued_message(2, QoS::AtMostOnce));
+        queue.push(queued_message(3, QoS::AtLeastOnce));
+        let dropped = queue.push(queued_message(4, QoS::AtLeastOnce)).unwrap();
+        assert_eq!(dropped.offset, 2);
+        let dropped = queue.push(queued_message(5, QoS::AtMostOnce)).unwrap();
+        assert_eq!(dropped.offset, 5);
+        let dropped = queue.push(queued_message(6, QoS::ExactlyOnce)).unwrap();
+        assert_eq!(dropped.offset, 3);
+        assert_eq!(queue.pop_front().unwrap().offset, 1);
+        assert_eq!(queue.pop_front().unwrap().offset, 4);
+        assert_eq!(queue.pop_front().unwrap().offset, 6);
+        assert!(queue.front().is_none());
+
+        let mut queue = message_queue(0, MessageQueueDropPolicy::DropNewest);
+        for i in 0..10 {
+            assert!(queue.push(queued_message(i, QoS::AtMostOnce)).is_none());
+        }
+    }
+
+    #[test]
+    fn message_queue_session_len_test() {
+        let session_len = Arc::new(AtomicUsize::new(0));
+        let mut first =
+            MessageQueue::new(3, MessageQueueDropPolicy::DropNewest, session_len.clone());
+        let mut second =
+            MessageQueue::new(3, MessageQueueDropPolicy::DropNewest, session_len.clone());
+
+        first.push(queued_message(1, QoS::AtLeastOnce));
+        first.push(queued_message(2, QoS::AtLeastOnce));
+        assert_eq!(second.free_len(), 1);
+        assert!(second.push(queued_message(10, QoS::AtLeastOnce)).is_none());
+
+        // the session is full, whichever subscription the message belongs to
+        assert!(first.is_full());
+        assert!(second.is_full());
+        assert!(first.push(queued_message(3, QoS::AtLeastOnce)).is_some());
+
+        assert_eq!(first.pop_front().unwrap().offset, 1);
+        assert_eq!(second.free_len(), 1);
+        assert_eq!(session_len.load(Ordering::Relaxed), 2);
+
+        // messages still queued when a subscription stops no longer count
+        drop(first);
+        assert_eq!(session_len.load(Ordering::Relaxed), 1);
+        assert_eq!(second.free_len(), 2);
+    }
+}
@@ -15,6 +15,8 @@
 use protocol::mqtt::common::{Publish, PublishProperties};
 use subscriber::Subscriber;
 
+pub mod inflight;
+pub mod share_strategy;
 pub mod sub_common;
 pub mod sub_exclusive;
 pub mod sub_share_follower;
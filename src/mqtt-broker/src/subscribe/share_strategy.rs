@@ -0,0 +1,306 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use metadata_struct::mqtt::cluster::SharedSubscriptionStrategy;
+use metadata_struct::mqtt::message::MqttMessage;
+use rand::Rng;
+
+use super::sub_share_follower::is_share_follower_client;
+use crate::handler::cache::CacheManager;
+use crate::subscribe::subscriber::Subscriber;
+
+// The group name the strategy is configured under, e.g. "g1" for "$share/g1/sport/#"
+// and "$queue" for queue subscriptions.
+pub fn share_strategy_group_name(group_name: &str) -> &str {
+    if let Some((name, _)) = group_name.split_once('/') {
+        return name;
+    }
+    group_name
+}
+
+// Picks the subscriber of a shared subscription group that receives each message.
+// The sub list is expected to be sorted so that hash strategies stay stable between refreshes.
+pub struct ShareSubscriberSelector {
+    strategy: SharedSubscriptionStrategy,
+    cursor: usize,
+    sticky_client_id: Option<String>,
+}
+
+impl ShareSubscriberSelector {
+    pub fn new(strategy: SharedSubscriptionStrategy) -> Self {
+        ShareSubscriberSelector {
+            strategy,
+            cursor: 0,
+            sticky_client_id: None,
+        }
+    }
+
+    pub fn set_strategy(&mut self, strategy: SharedSubscriptionStrategy) {
+        if self.strategy != strategy {
+            self.strategy = strategy;
+            self.sticky_client_id = None;
+        }
+    }
+
+    // Returns the index of the subscriber to deliver to. `attempt` counts the failed deliveries
+    // of the current message so that each retry moves on to another subscriber.
+    pub fn choose(
+        &mut self,
+        cache_manager: &Arc<CacheManager>,
+        sub_list: &[Subscriber],
+        msg: &MqttMessage,
+        attempt: usize,
+    ) -> Option<usize> {
+        if sub_list.is_empty() {
+            return None;
+        }
+
+        let index = match self.strategy {
+            SharedSubscriptionStrategy::Random => rand::thread_rng().gen_range(0..sub_list.len()),
+            SharedSubscriptionStrategy::RoundRobin => self.next_cursor(sub_list.len()),
+            SharedSubscriptionStrategy::Sticky => {
+                self.choose_sticky(cache_manager, sub_list, attempt)
+            }
+            SharedSubscriptionStrategy::HashClientId => {
+                hash_index(&msg.client_id, attempt, sub_list.len())
+            }
+            SharedSubscriptionStrategy::HashTopic => {
+                hash_index(&msg.topic, attempt, sub_list.len())
+            }
+            SharedSubscriptionStrategy::LocalFirst => self.choose_local_first(sub_list, attempt),
+        };
+        Some(index)
+    }
+
+    fn next_cursor(&mut self, len: usize) -> usize {
+        self.cursor = (self.cursor + 1) % len;
+        self.cursor
+    }
+
+    fn choose_sticky(
+        &mut self,
+        cache_manager: &Arc<CacheManager>,
+        sub_list: &[Subscriber],
+        attempt: usize,
+    ) -> usize {
+        if attempt == 0 {
+            if let Some(client_id) = &self.sticky_client_id {
+                if cache_manager.get_connect_id(client_id).is_some() {
+                    if let Some(index) = sub_list.iter().position(|sub| sub.client_id == *client_id)
+                    {
+                        return index;
+                    }
+                }
+            }
+        }
+
+        let index = self.next_cursor(sub_list.len());
+        self.sticky_client_id = Some(sub_list[index].client_id.clone());
+        index
+    }
+
+    fn choose_local_first(&mut self, sub_list: &[Subscriber], attempt: usize) -> usize {
+        let local_list: Vec<usize> = sub_list
+            .iter()
+            .enumerate()
+            .filter(|(_, sub)| !is_share_follower_client(&sub.client_id))
+            .map(|(index, _)| index)
+            .collect();
+
+        // Fall back to every subscriber when there is no local one or all of them have failed
+        if local_list.is_empty() || attempt >= local_list.len() {
+            return self.next_cursor(sub_list.len());
+        }
+        local_list[self.next_cursor(local_list.len())]
+    }
+}
+
+// Plain modulo hashing: the same key goes to the same subscriber as long as the group
+// does not change, but a subscriber joining or leaving remaps most keys, not only the
+// ones of that subscriber. Retries move on to the next subscribers of the list.
+fn hash_index<T: Hash + ?Sized>(key: &T, attempt: usize, len: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hash_slot(hasher.finish(), attempt, len)
+}
+
+// Reduced before adding the attempt, so neither step can overflow
+fn hash_slot(hash: u64, attempt: usize, len: usize) -> usize {
+    ((hash % len as u64) as usize).wrapping_add(attempt % len) % len
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::session::MqttSession;
+
+    use super::*;
+    use crate::subscribe::sub_share_follower::SHARE_SUB_FOLLOWER_CLIENT_ID_PREFIX;
+
+    fn build_sub_list(client_ids: &[&str]) -> Vec<Subscriber> {
+        client_ids
+            .iter()
+            .map(|client_id| Subscriber {
+                client_id: client_id.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn build_message(client_id: &str, topic: &str) -> MqttMessage {
+        MqttMessage {
+            client_id: client_id.to_string(),
+            topic: Bytes::from(topic.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn build_cache_manager() -> Arc<CacheManager> {
+        let client_pool = Arc::new(ClientPool::new(1));
+        Arc::new(CacheManager::new(client_pool, "test".to_string()))
+    }
+
+    #[test]
+    fn share_strategy_group_name_test() {
+        assert_eq!(share_strategy_group_name("g1/sport/#"), "g1");
+        assert_eq!(share_strategy_group_name("$queue/sport/#"), "$queue");
+        assert_eq!(share_strategy_group_name("g1"), "g1");
+    }
+
+    #[test]
+    fn round_robin_test() {
+        let cache_manager = build_cache_manager();
+        let sub_list = build_sub_list(&["c1", "c2", "c3"]);
+        let msg = build_message("p1", "t1");
+        let mut selector = ShareSubscriberSelector::new(SharedSubscriptionStrategy::RoundRobin);
+
+        let res: Vec<usize> = (0..4)
+            .map(|_| selector.choose(&cache_manager, &sub_list, &msg, 0).unwrap())
+            .collect();
+        assert_eq!(res, vec![1, 2, 0, 1]);
+        assert!(selector.choose(&cache_manager, &[], &msg, 0).is_none());
+    }
+
+    #[test]
+    fn random_test() {
+        let cache_manager = build_cache_manager();
+        let sub_list = build_sub_list(&["c1", "c2", "c3"]);
+        let msg = build_message("p1", "t1");
+        let mut selector = ShareSubscriberSelector::new(SharedSubscriptionStrategy::Random);
+        for _ in 0..10 {
+            let index = selector.choose(&cache_manager, &sub_list, &msg, 0).unwrap();
+            assert!(index < sub_list.len());
+        }
+    }
+
+    #[test]
+    fn hash_test() {
+        let cache_manager = build_cache_manager();
+        let sub_list = build_sub_list(&["c1", "c2", "c3"]);
+        let mut selector = ShareSubscriberSelector::new(SharedSubscriptionStrategy::HashTopic);
+
+        let msg = build_message("p1", "device/1");
+        let first = selector.choose(&cache_manager, &sub_list, &msg, 0).unwrap();
+        for publisher in ["p2", "p3", "p4"] {
+            let msg = build_message(publisher, "device/1");
+            assert_eq!(
+                selector.choose(&cache_manager, &sub_list, &msg, 0).unwrap(),
+                first
+            );
+        }
+        let retry = selector.choose(&cache_manager, &sub_list, &msg, 1).unwrap();
+        assert_eq!(retry, (first + 1) % sub_list.len());
+
+        selector.set_strategy(SharedSubscriptionStrategy::HashClientId);
+        let msg = build_message("p1", "device/1");
+        let first = selector.choose(&cache_manager, &sub_list, &msg, 0).unwrap();
+        for topic in ["device/2", "device/3"] {
+            let msg = build_message("p1", topic);
+            assert_eq!(
+                selector.choose(&cache_manager, &sub_list, &msg, 0).unwrap(),
+                first
+            );
+        }
+    }
+
+    #[test]
+    fn hash_slot_test() {
+        assert_eq!(hash_slot(7, 0, 3), 1);
+        assert_eq!(hash_slot(7, 1, 3), 2);
+        assert_eq!(hash_slot(7, 5, 3), 0);
+        assert_eq!(hash_slot(u64::MAX, usize::MAX, 3), 0);
+        assert_eq!(hash_slot(u64::MAX, 0, 1), 0);
+    }
+
+    #[test]
+    fn sticky_test() {
+        let cache_manager = build_cache_manager();
+        let sub_list = build_sub_list(&["c1", "c2", "c3"]);
+        let msg = build_message("p1", "t1");
+        for (connect_id, client_id) in ["c1", "c2", "c3"].iter().enumerate() {
+            cache_manager.add_session(
+                client_id.to_string(),
+                MqttSession {
+                    client_id: client_id.to_string(),
+                    connection_id: Some(connect_id as u64),
+                    ..Default::default()
+                },
+            );
+        }
+        let mut selector = ShareSubscriberSelector::new(SharedSubscriptionStrategy::Sticky);
+
+        let first = selector.choose(&cache_manager, &sub_list, &msg, 0).unwrap();
+        for _ in 0..3 {
+            assert_eq!(
+                selector.choose(&cache_manager, &sub_list, &msg, 0).unwrap(),
+                first
+            );
+        }
+
+        // a failed delivery moves the stickiness to another subscriber
+        let second = selector.choose(&cache_manager, &sub_list, &msg, 1).unwrap();
+        assert_ne!(second, first);
+        assert_eq!(
+            selector.choose(&cache_manager, &sub_list, &msg, 0).unwrap(),
+            second
+        );
+
+        // so does a disconnect
+        cache_manager.update_session_connect_id(&sub_list[second].client_id, None);
+        let third = selector.choose(&cache_manager, &sub_list, &msg, 0).unwrap();
+        assert_ne!(third, second);
+    }
+
+    #[test]
+    fn local_first_test() {
+        let cache_manager = build_cache_manager();
+        let follower = format!("{}f1", SHARE_SUB_FOLLOWER_CLIENT_ID_PREFIX);
+        let sub_list = build_sub_list(&[&follower, "c1", "c2"]);
+        let msg = build_message("p1", "t1");
+        let mut selector = ShareSubscriberSelector::new(SharedSubscriptionStrategy::LocalFirst);
+
+        for _ in 0..4 {
+            let index = selector.choose(&cache_manager, &sub_list, &msg, 0).unwrap();
+            assert_ne!(index, 0);
+        }
+
+        let sub_list = build_sub_list(&[&follower]);
+        assert_eq!(selector.choose(&cache_manager, &sub_list, &msg, 0), Some(0));
+    }
+}
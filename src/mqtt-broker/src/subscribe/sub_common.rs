@@ -17,20 +17,14 @@ use std::time::Duration;
 
 use axum::extract::ws::Message;
 use bytes::BytesMut;
-use common_base::config::broker_mqtt::broker_mqtt_conf;
 use common_base::error::common::CommonError;
 use common_base::tools::now_mills;
-use grpc_clients::placement::mqtt::call::{
-    placement_delete_exclusive_topic, placement_get_share_sub_leader,
-    placement_set_nx_exclusive_topic,
-};
 use grpc_clients::pool::ClientPool;
 use log::error;
 use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
 use protocol::mqtt::common::{MqttPacket, MqttProtocol, PubRel, QoS};
 use protocol::placement_center::placement_center_mqtt::{
-    DeleteExclusiveTopicReply, DeleteExclusiveTopicRequest, GetShareSubLeaderReply,
-    GetShareSubLeaderRequest, SetExclusiveTopicReply, SetExclusiveTopicRequest,
+    DeleteExclusiveTopicReply, GetShareSubLeaderReply, SetExclusiveTopicReply,
 };
 use regex::Regex;
 use storage_adapter::storage::StorageAdapter;
@@ -42,9 +36,13 @@ use super::SubPublishParam;
 use crate::handler::cache::{CacheManager, QosAckPackageData};
 use crate::handler::error::MqttBrokerError;
 use crate::observability::slow::sub::{record_slow_sub_data, SlowSubData};
+use crate::observability::trace::{TraceEvent, TraceEventType};
+use crate::observability::webhook::{WebhookEvent, WebhookEventType};
+use crate::security::acl::response_topic::grant_response_topic;
 use crate::server::connection_manager::ConnectionManager;
 use crate::server::packet::ResponsePackage;
 use crate::storage::message::MessageStorage;
+use crate::storage::metadata::{build_metadata_storage, MetadataStorage};
 
 const SHARE_SUB_PREFIX: &str = "$share";
 
@@ -150,44 +148,28 @@ pub async fn get_share_sub_leader(
     client_pool: Arc<ClientPool>,
     group_name: String,
 ) -> Result<GetShareSubLeaderReply, CommonError> {
-    let conf = broker_mqtt_conf();
-    let req = GetShareSubLeaderRequest {
-        cluster_name: conf.cluster_name.clone(),
-        group_name,
-    };
-    match placement_get_share_sub_leader(&client_pool, &conf.placement_center, req).await {
-        Ok(reply) => Ok(reply),
-        Err(e) => Err(e),
-    }
+    build_metadata_storage(client_pool)
+        .get_share_sub_leader(group_name)
+        .await
 }
 pub async fn set_nx_exclusive_topic(
     client_pool: Arc<ClientPool>,
     topic_name: String,
 ) -> Result<SetExclusiveTopicReply, CommonError> {
-    let conf = broker_mqtt_conf();
-    let req = SetExclusiveTopicRequest {
-        cluster_name: conf.cluster_name.clone(),
-        topic_name,
-    };
-    match placement_set_nx_exclusive_topic(&client_pool, &conf.placement_center, req).await {
-        Ok(reply) => Ok(reply),
-        Err(e) => Err(e),
-    }
+    let success = build_metadata_storage(client_pool)
+        .set_nx_exclusive_topic(topic_name)
+        .await?;
+    Ok(SetExclusiveTopicReply { success })
 }
 
 pub async fn delete_exclusive_topic(
     client_pool: Arc<ClientPool>,
     topic_name: String,
 ) -> Result<DeleteExclusiveTopicReply, CommonError> {
-    let conf = broker_mqtt_conf();
-    let req = DeleteExclusiveTopicRequest {
-        cluster_name: conf.cluster_name.clone(),
-        topic_name,
-    };
-    match placement_delete_exclusive_topic(&client_pool, &conf.placement_center, req).await {
-        Ok(reply) => Ok(reply),
-        Err(e) => Err(e),
-    }
+    build_metadata_storage(client_pool)
+        .delete_exclusive_topic(topic_name)
+        .await?;
+    Ok(DeleteExclusiveTopicReply::default())
 }
 
 pub async fn wait_packet_ack(sx: &Sender<QosAckPackageData>) -> Option<QosAckPackageData> {
@@ -207,7 +189,22 @@ pub async fn publish_message_to_client(
     connection_manager: &Arc<ConnectionManager>,
     metadata_cache: &Arc<CacheManager>,
 ) -> Result<(), MqttBrokerError> {
+    let send_start_time = now_mills();
     if let Some(protocol) = connection_manager.get_connect_protocol(resp.connection_id) {
+        if let MqttPacket::Publish(_, Some(properties)) = &resp.packet {
+            grant_response_topic(
+                metadata_cache,
+                &sub_pub_param.subscribe.client_id,
+                properties,
+            );
+        }
+
+        let (pkid, qos) = if let MqttPacket::Publish(publish, _) = &resp.packet {
+            (publish.pkid, Some(publish.qos))
+        } else {
+            (0, None)
+        };
+
         let response: MqttPacketWrapper = MqttPacketWrapper {
             protocol_version: protocol.clone().into(),
             packet: resp.packet,
@@ -230,21 +227,82 @@ pub async fn publish_message_to_client(
                 .write_tcp_frame(resp.connection_id, response)
                 .await?
         }
+
+        metadata_cache.trace_manager.record(|| {
+            let peer_addr =
+                if let Some(conn) = metadata_cache.connection_info.get(&resp.connection_id) {
+                    conn.source_ip_addr.clone()
+                } else {
+                    "".to_string()
+                };
+            TraceEvent::new(
+                TraceEventType::Dispatch,
+                &sub_pub_param.subscribe.client_id,
+                &sub_pub_param.subscribe.topic_name,
+            )
+            .peer_addr(&peer_addr)
+            .pkid(pkid)
+            .detail(format!(
+                "sub_path: {}, qos: {:?}",
+                sub_pub_param.subscribe.sub_path, qos
+            ))
+        });
+
+        metadata_cache
+            .webhook_manager
+            .emit(WebhookEventType::MessageDelivered, || {
+                WebhookEvent::new(
+                    WebhookEventType::MessageDelivered,
+                    &sub_pub_param.subscribe.client_id,
+                )
+                .topic(&sub_pub_param.subscribe.topic_name)
+                .data(serde_json::json!({
+                    "pkid": pkid,
+                    "qos": qos.map(|qos| qos as u8),
+                    "sub_path": sub_pub_param.subscribe.sub_path,
+                }))
+            });
+
         // record slow sub data
         if metadata_cache.get_slow_sub_config().enable && sub_pub_param.create_time > 0 {
             let slow_data = SlowSubData::build(
                 sub_pub_param.subscribe.sub_path.clone(),
                 sub_pub_param.subscribe.client_id.clone(),
                 sub_pub_param.subscribe.topic_name.clone(),
-                (now_mills() - sub_pub_param.create_time) as u64,
+                send_start_time.saturating_sub(sub_pub_param.create_time) as u64,
+                (now_mills() - send_start_time) as u64,
             );
-            record_slow_sub_data(slow_data, metadata_cache.get_slow_sub_config().whole_ms)?;
+            record_slow_sub_data(metadata_cache, slow_data)?;
         }
     }
 
     Ok(())
 }
 
+pub fn trace_message_drop(
+    metadata_cache: &Arc<CacheManager>,
+    client_id: &str,
+    topic_name: &str,
+    offset: Option<u64>,
+    reason: &str,
+) {
+    metadata_cache.trace_manager.record(|| {
+        let detail = if let Some(offset) = offset {
+            format!("offset: {}, reason: {}", offset, reason)
+        } else {
+            format!("reason: {}", reason)
+        };
+        TraceEvent::new(TraceEventType::Drop, client_id, topic_name).detail(detail)
+    });
+    metadata_cache
+        .webhook_manager
+        .emit(WebhookEventType::MessageDropped, || {
+            WebhookEvent::new(WebhookEventType::MessageDropped, client_id)
+                .topic(topic_name)
+                .data(serde_json::json!({ "offset": offset, "reason": reason }))
+        });
+}
+
 pub async fn qos2_send_publish(
     connection_manager: &Arc<ConnectionManager>,
     metadata_cache: &Arc<CacheManager>,
@@ -383,6 +441,52 @@ pub async fn qos2_send_pubrel(
     }
 }
 
+// Writes a single Publish packet to the connection, without waiting for the acknowledgement
+pub async fn send_publish_packet(
+    connection_manager: &Arc<ConnectionManager>,
+    metadata_cache: &Arc<CacheManager>,
+    connect_id: u64,
+    sub_pub_param: &SubPublishParam,
+) -> Result<(), MqttBrokerError> {
+    let mut contain_properties = false;
+    if let Some(protocol) = connection_manager.get_connect_protocol(connect_id) {
+        if MqttProtocol::is_mqtt5(&protocol) {
+            contain_properties = true;
+        }
+    }
+
+    let properties = if contain_properties {
+        sub_pub_param.properties.clone()
+    } else {
+        None
+    };
+
+    let resp = ResponsePackage {
+        connection_id: connect_id,
+        packet: MqttPacket::Publish(sub_pub_param.publish.clone(), properties),
+    };
+    publish_message_to_client(resp, sub_pub_param, connection_manager, metadata_cache).await
+}
+
+// Writes a single PubRel packet to the connection, without waiting for the acknowledgement
+pub async fn send_pubrel_packet(
+    connection_manager: &Arc<ConnectionManager>,
+    metadata_cache: &Arc<CacheManager>,
+    connect_id: u64,
+    sub_pub_param: &SubPublishParam,
+) -> Result<(), MqttBrokerError> {
+    let pubrel = PubRel {
+        pkid: sub_pub_param.pkid,
+        reason: Some(protocol::mqtt::common::PubRelReason::Success),
+    };
+
+    let resp = ResponsePackage {
+        connection_id: connect_id,
+        packet: MqttPacket::PubRel(pubrel, None),
+    };
+    publish_message_to_client(resp, sub_pub_param, connection_manager, metadata_cache).await
+}
+
 pub async fn loop_commit_offset<S>(
     message_storage: &MessageStorage<S>,
     topic_id: &str,
@@ -12,34 +12,53 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use std::time::Duration;
 
 use bytes::Bytes;
-use common_base::tools::now_second;
+use common_base::error::common::CommonError;
+use common_base::tools::{now_mills, now_second};
 use log::{debug, error, info};
 use metadata_struct::adapter::record::Record;
+use metadata_struct::mqtt::connection::MQTTConnection;
 use metadata_struct::mqtt::message::MqttMessage;
+use metadata_struct::mqtt::qos2_state::{MqttQos2State, Qos2Stage};
 use protocol::mqtt::common::{MqttPacket, MqttProtocol, Publish, PublishProperties, QoS};
 use storage_adapter::storage::StorageAdapter;
 use tokio::select;
+use tokio::sync::broadcast::error::TryRecvError;
 use tokio::sync::broadcast::{self};
 use tokio::time::sleep;
 
+use super::inflight::{
+    InflightAckAction, InflightMessage, InflightStage, InflightWindow, MessageQueue, QueuedMessage,
+};
 use super::sub_common::{
     loop_commit_offset, min_qos, publish_message_qos0, publish_message_to_client,
-    qos2_send_publish, qos2_send_pubrel, wait_packet_ack,
+    qos2_send_publish, qos2_send_pubrel, send_publish_packet, send_pubrel_packet,
+    trace_message_drop, wait_packet_ack,
 };
 use super::subscribe_manager::SubscribeManager;
 use super::subscriber::Subscriber;
 use crate::handler::cache::{CacheManager, QosAckPackageData, QosAckPackageType, QosAckPacketInfo};
 use crate::handler::error::MqttBrokerError;
 use crate::handler::message::is_message_expire;
+use crate::handler::qos2_state::{
+    delete_send_qos2_state, list_send_qos2_state, save_send_qos2_state,
+};
+use crate::observability::metrics::messages::{
+    metrics_message_dropped, metrics_message_qos_downgraded, MessageDropReason,
+};
 use crate::server::connection_manager::ConnectionManager;
 use crate::server::packet::ResponsePackage;
 use crate::storage::message::MessageStorage;
 use crate::subscribe::SubPublishParam;
 
+const EXCLUSIVE_READ_BATCH: u64 = 100;
+const INFLIGHT_ACK_CHANNEL_SIZE: usize = 1000;
+
 pub struct SubscribeExclusive<S> {
     cache_manager: Arc<CacheManager>,
     subscribe_manager: Arc<SubscribeManager>,
@@ -88,6 +107,11 @@ where
                     .remove(&exclusive_key);
             }
         }
+
+        // Queue counters no longer shared with any push thread
+        self.subscribe_manager
+            .exclusive_session_queue_len
+            .retain(|_, len| Arc::strong_count(len) > 1);
     }
 
     // Handles exclusive subscription push tasks
@@ -117,12 +141,8 @@ where
             tokio::spawn(async move {
                 info!("Exclusive push thread for client_id [{}], sub_path: [{}], topic_id [{}] was started successfully",
                         subscriber.client_id, subscriber.sub_path, subscriber.topic_id);
-
                 let group_id = build_group_name(&subscriber);
-                let qos = build_pub_qos(&cache_manager, &subscriber);
-                let sub_ids = build_sub_ids(&subscriber);
-
-                let mut offset = match message_storage.get_group_offset(&group_id).await {
+                let offset = match message_storage.get_group_offset(&group_id).await {
                     Ok(offset) => offset,
                     Err(e) => {
                         error!("{}", e);
@@ -133,53 +153,76 @@ where
                     }
                 };
 
+                let session_queue_len = subscribe_manager.session_queue_len(&subscriber.client_id);
+                let mut push = InflightPush::new(
+                    connection_manager,
+                    cache_manager,
+                    message_storage,
+                    subscriber,
+                    group_id,
+                    offset,
+                    session_queue_len,
+                    sub_thread_stop_sx,
+                );
+                if let Err(e) = push.restore().await {
+                    error!(
+                        "Failed to restore the QoS 2 state of client [{}], group [{}], error: {}",
+                        push.subscriber.client_id, push.group_id, e
+                    );
+                }
+                let mut ack_rx = push.ack_sx.subscribe();
+
                 loop {
-                    select! {
-                        val = sub_thread_stop_rx.recv() =>{
-                            if let Ok(flag) = val {
-                                if flag {
-                                    info!(
-                                        "Exclusive Push thread for client_id [{}], sub_path: [{}], topic_id [{}] was stopped successfully",
-                                        subscriber.client_id,
-                                        subscriber.sub_path,
-                                        subscriber.topic_id
-                                    );
-
-                                    subscribe_manager.exclusive_push_thread.remove(&exclusive_key);
-                                    break;
-                                }
-                            }
-                        },
-                        val = pub_message(
-                                &connection_manager,
-                                &message_storage,
-                                &cache_manager,
-                                &subscriber,
-                                &group_id,
-                                &qos,
-                                &sub_ids,
-                                offset,
-                                &sub_thread_stop_sx
-                            ) => {
-                                match val{
-                                    Ok(offset_op) => {
-                                        if let Some(off) = offset_op{
-                                            offset = off + 1;
-                                        }else{
-                                            sleep(Duration::from_millis(100)).await;
-                                        }
-                                    }
-                                    Err(e) => {
-                                        error!(
-                                            "Push message to client failed, failure message: {},topic:{},group{}",
-                                            e.to_string(),
-                                            subscriber.topic_id.clone(),
-                                            group_id.clone()
-                                        );
-                                        sleep(Duration::from_millis(100)).await;
-                                    }
+                    // Acknowledgements that arrived while pushing
+                    loop {
+                        match ack_rx.try_recv() {
+                            Ok(data) => push.handle_ack(data).await,
+                            Err(TryRecvError::Lagged(_)) => continue,
+                            Err(_) => break,
+                        }
+                    }
+
+                    let busy = match push.push().await {
+                        Ok(busy) => busy,
+                        Err(e) => {
+                            error!(
+                                "Push message to client failed, failure message: {},topic:{},group{}",
+                                e.to_string(),
+                                push.subscriber.topic_id,
+                                push.group_id
+                            );
+                            false
+                        }
+                    };
+                    push.commit_offset().await;
+
+                    let stop = if busy {
+                        matches!(sub_thread_stop_rx.try_recv(), Ok(true))
+                    } else {
+                        select! {
+                            val = sub_thread_stop_rx.recv() => matches!(val, Ok(true)),
+                            val = ack_rx.recv() => {
+                                if let Ok(data) = val {
+                                    push.handle_ack(data).await;
                                 }
-                            }
+                                false
+                            },
+                            _ = sleep(Duration::from_millis(100)) => false,
+                        }
+                    };
+
+                    if stop {
+                        push.release();
+                        info!(
+                            "Exclusive Push thread for client_id [{}], sub_path: [{}], topic_id [{}] was stopped successfully",
+                            push.subscriber.client_id,
+                            push.subscriber.sub_path,
+                            push.subscriber.topic_id
+                        );
+                        subscribe_manager
+                            .exclusive_push_thread
+                            .remove(&exclusive_key);
+                        break;
                     }
                 }
             });
@@ -187,151 +230,577 @@ where
     }
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn pub_message<S>(
-    connection_manager: &Arc<ConnectionManager>,
-    message_storage: &MessageStorage<S>,
-    cache_manager: &Arc<CacheManager>,
-    subscriber: &Subscriber,
-    group_id: &str,
-    qos: &QoS,
-    sub_ids: &[usize],
+/// Pushes the messages of one exclusive subscription to the client.
+///
+/// Records are read from storage into a message queue bounded per session. QoS1/QoS2 messages are
+/// pipelined to the client as long as the number of unacknowledged messages on the connection
+/// stays below the Receive Maximum the client sent in CONNECT. Unacknowledged messages are
+/// retransmitted in order, with the DUP flag, after the retry interval or when the client
+/// reconnects. The committed group offset is the oldest message that is not yet delivered.
+///
+/// When the queue of a persistent session is full, reading from storage stops until the client
+/// catches up, so no stored message is lost. Only a session that ends with the connection drops
+/// messages according to the drop policy.
+///
+/// The state of QoS2 messages is persisted until PUBCOMP. A push thread started for the group
+/// later, e.g. after a broker restart, resends PUBREL for messages the client already received,
+/// and resends the other ones with their original packet identifier and the DUP flag.
+struct InflightPush<S> {
+    connection_manager: Arc<ConnectionManager>,
+    cache_manager: Arc<CacheManager>,
+    message_storage: MessageStorage<S>,
+    subscriber: Subscriber,
+    group_id: String,
+    qos: QoS,
+    sub_ids: Vec<usize>,
+    stop_sx: broadcast::Sender<bool>,
+    ack_sx: broadcast::Sender<QosAckPackageData>,
+    window: InflightWindow,
+    queue: MessageQueue,
+    // Offset of the next record to read from storage
     offset: u64,
-    sub_thread_stop_sx: &broadcast::Sender<bool>,
-) -> Result<Option<u64>, MqttBrokerError>
+    committed_offset: u64,
+    // Offsets of restored QoS2 messages still waiting for PUBREC, and their packet identifier
+    restored_pkids: HashMap<u64, u16>,
+    // Offsets of restored QoS2 messages the client has received, they are not sent again
+    released_offsets: HashSet<u64>,
+}
+
+impl<S> InflightPush<S>
 where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
-    let record_num = 5;
-    let client_id = subscriber.client_id.clone();
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        connection_manager: Arc<ConnectionManager>,
+        cache_manager: Arc<CacheManager>,
+        message_storage: MessageStorage<S>,
+        subscriber: Subscriber,
+        group_id: String,
+        offset: u64,
+        session_queue_len: Arc<AtomicUsize>,
+        stop_sx: broadcast::Sender<bool>,
+    ) -> Self {
+        let qos = build_pub_qos(&cache_manager, &subscriber);
+        let sub_ids = build_sub_ids(&subscriber);
+        let protocol = cache_manager.get_cluster_info().protocol;
+        let (ack_sx, _) = broadcast::channel(INFLIGHT_ACK_CHANNEL_SIZE);
+        InflightPush {
+            connection_manager,
+            cache_manager,
+            message_storage,
+            subscriber,
+            group_id,
+            qos,
+            sub_ids,
+            stop_sx,
+            ack_sx,
+            window: InflightWindow::new(),
+            queue: MessageQueue::new(
+                protocol.max_message_queue_len as usize,
+                protocol.message_queue_drop_policy,
+                session_queue_len,
+            ),
+            offset,
+            committed_offset: offset,
+            restored_pkids: HashMap::new(),
+            released_offsets: HashSet::new(),
+        }
+    }
 
-    let results = message_storage
-        .read_topic_message(&subscriber.topic_id, offset, record_num)
+    async fn restore(&mut self) -> Result<(), CommonError> {
+        let states = list_send_qos2_state(
+            &self.cache_manager,
+            &self.subscriber.client_id,
+            &self.group_id,
+        )
         .await?;
+        self.apply_restored_qos2_state(states);
+        Ok(())
+    }
 
-    if results.is_empty() {
-        return Ok(None);
+    fn apply_restored_qos2_state(&mut self, states: Vec<MqttQos2State>) {
+        let client_id = self.subscriber.client_id.clone();
+        for state in states {
+            self.cache_manager.add_publish_pkid(&client_id, state.pkid);
+            if state.stage != Qos2Stage::WaitPubComp {
+                self.restored_pkids.insert(state.offset, state.pkid);
+                continue;
+            }
+
+            self.cache_manager.add_ack_packet(
+                &client_id,
+                state.pkid,
+                QosAckPacketInfo {
+                    sx: self.ack_sx.clone(),
+                    create_time: now_second(),
+                },
+            );
+            self.released_offsets.insert(state.offset);
+
+            // Not sent on any connection yet, PUBREL goes out as soon as the client is connected
+            self.window.push(InflightMessage {
+                offset: state.offset,
+                stage: InflightStage::WaitPubComp,
+                connect_id: 0,
+                send_time: 0,
+                param: SubPublishParam {
+                    subscribe: self.subscriber.clone(),
+                    pkid: state.pkid,
+                    group_id: self.group_id.clone(),
+                    ..Default::default()
+                },
+            });
+        }
     }
 
-    for record in results.iter() {
-        let record_offset = record.offset.unwrap();
+    // Returns whether any message was read or sent
+    async fn push(&mut self) -> Result<bool, MqttBrokerError> {
+        let protocol = self.cache_manager.get_cluster_info().protocol;
+        self.queue.set_limit(
+            protocol.max_message_queue_len as usize,
+            protocol.message_queue_drop_policy,
+        );
 
-        // build publish params
-        let sub_pub_param = if let Some(params) = buile_pub_message(
-            record.to_owned(),
-            group_id,
-            qos,
-            subscriber,
-            cache_manager,
-            sub_ids,
-        )
-        .await?
+        let mut busy = self.read_messages().await?;
+
+        let connect_id = if let Some(id) = self
+            .cache_manager
+            .get_connect_id(&self.subscriber.client_id)
         {
-            params
+            id
         } else {
-            continue;
+            return Ok(busy);
         };
 
-        let pkid = sub_pub_param.pkid;
-        match qos {
-            QoS::AtMostOnce => {
-                publish_message_qos0(
-                    cache_manager,
-                    connection_manager,
-                    &sub_pub_param,
-                    sub_thread_stop_sx,
-                )
-                .await;
+        let conn = if let Some(conn) = self.cache_manager.get_connection(connect_id) {
+            conn
+        } else {
+            return Ok(busy);
+        };
+
+        self.retry_inflight(connect_id, &conn, protocol.inflight_retry_interval_ms)
+            .await;
+
+        if self.send_queued(connect_id, &conn).await {
+            busy = true;
+        }
+        Ok(busy)
+    }
+
+    async fn read_messages(&mut self) -> Result<bool, MqttBrokerError> {
+        let persistent = self.is_persistent_session();
+        let free_len = self.queue.free_len();
+        if persistent && free_len == 0 {
+            return Ok(false);
+        }
+
+        let batch = if persistent {
+            EXCLUSIVE_READ_BATCH.min(free_len as u64)
+        } else {
+            EXCLUSIVE_READ_BATCH
+        };
+        let results = self
+            .message_storage
+            .read_topic_message(&self.subscriber.topic_id, self.offset, batch)
+            .await?;
+
+        if results.is_empty() {
+            return Ok(false);
+        }
+
+        for record in results {
+            let record_offset = record.offset.unwrap();
+            // The other subscriptions of the session may have filled the queue meanwhile,
+            // the record is read again once there is room for it
+            if persistent && self.queue.is_full() {
+                break;
+            }
+            self.offset = record_offset + 1;
+
+            if self.released_offsets.remove(&record_offset) {
+                continue;
             }
 
-            QoS::AtLeastOnce => {
-                let (wait_puback_sx, _) = broadcast::channel(1);
-                cache_manager.add_ack_packet(
-                    &client_id,
-                    pkid,
-                    QosAckPacketInfo {
-                        sx: wait_puback_sx.clone(),
-                        create_time: now_second(),
-                    },
+            let message = if let Some(message) = build_queued_message(
+                &self.cache_manager,
+                record,
+                &self.group_id,
+                &self.qos,
+                &self.subscriber,
+                &self.sub_ids,
+            )? {
+                message
+            } else {
+                if let Some(pkid) = self.restored_pkids.remove(&record_offset) {
+                    self.release_restored_pkid(pkid).await;
+                }
+                continue;
+            };
+
+            if let Some(dropped) = self.queue.push(message) {
+                debug!(
+                    "Message queue of client_id [{}], sub_path: [{}] is full, the message at offset {} was dropped",
+                    self.subscriber.client_id, self.subscriber.sub_path, dropped.offset
+                );
+                metrics_message_dropped(MessageDropReason::QueueFull, &self.subscriber.topic_name);
+                trace_message_drop(
+                    &self.cache_manager,
+                    &self.subscriber.client_id,
+                    &self.subscriber.topic_name,
+                    Some(dropped.offset),
+                    "message queue is full",
                 );
+            }
+        }
+        Ok(true)
+    }
 
-                exclusive_publish_message_qos1(
-                    cache_manager,
-                    connection_manager,
-                    &sub_pub_param,
-                    sub_thread_stop_sx,
-                    &wait_puback_sx,
+    // A session that outlives the connection keeps its messages in storage until they are
+    // delivered, a session that ends with the connection does not need them afterwards
+    fn is_persistent_session(&self) -> bool {
+        if let Some(session) = self
+            .cache_manager
+            .get_session_info(&self.subscriber.client_id)
+        {
+            session.session_expiry > 0
+        } else {
+            true
+        }
+    }
+
+    async fn retry_inflight(
+        &mut self,
+        connect_id: u64,
+        conn: &MQTTConnection,
+        retry_interval_ms: u64,
+    ) {
+        let now = now_mills();
+        for pkid in self.window.expired(now, retry_interval_ms, connect_id) {
+            let message = if let Some(message) = self.window.get_mut(pkid) {
+                message
+            } else {
+                continue;
+            };
+
+            if message.connect_id != connect_id {
+                // The client reconnected, the message now counts against the new connection
+                conn.send_qos_message_incr();
+                message.connect_id = connect_id;
+            }
+            message.send_time = now;
+
+            let result = if message.stage == InflightStage::WaitPubComp {
+                send_pubrel_packet(
+                    &self.connection_manager,
+                    &self.cache_manager,
+                    connect_id,
+                    &message.param,
+                )
+                .await
+            } else {
+                message.param.publish.dup = true;
+                send_publish_packet(
+                    &self.connection_manager,
+                    &self.cache_manager,
+                    connect_id,
+                    &message.param,
                 )
-                .await?;
+                .await
+            };
 
-                cache_manager.remove_pkid_info(&client_id, pkid);
-                cache_manager.remove_ack_packet(&client_id, pkid);
+            if let Err(e) = result {
+                error!(
+                    "Failed to retransmit message to client [{}], failure message: {}",
+                    self.subscriber.client_id, e
+                );
+            }
+        }
+    }
+
+    // Returns whether any message was taken from the queue
+    async fn send_queued(&mut self, connect_id: u64, conn: &MQTTConnection) -> bool {
+        let receive_maximum = conn.client_max_receive_maximum.max(1) as isize;
+        let client_id = self.subscriber.client_id.clone();
+        let mut sent = false;
+
+        while let Some(front) = self.queue.front() {
+            if front.param.publish.qos != QoS::AtMostOnce
+                && conn.get_send_qos_message() >= receive_maximum
+            {
+                break;
             }
 
-            QoS::ExactlyOnce => {
-                let (wait_ack_sx, _) = broadcast::channel(1);
-                cache_manager.add_ack_packet(
-                    &client_id,
-                    pkid,
-                    QosAckPacketInfo {
-                        sx: wait_ack_sx.clone(),
-                        create_time: now_second(),
-                    },
+            let mut message = if let Some(message) = self.queue.pop_front() {
+                message
+            } else {
+                break;
+            };
+            sent = true;
+
+            if message.param.publish.payload.len() > (conn.max_packet_size as usize) {
+                debug!(
+                    "Message at offset {} exceeds the maximum packet size of client [{}] and is discarded",
+                    message.offset, client_id
                 );
+                continue;
+            }
 
-                exclusive_publish_message_qos2(
-                    cache_manager,
-                    connection_manager,
-                    &sub_pub_param,
-                    sub_thread_stop_sx,
-                    &wait_ack_sx,
+            if message.param.publish.qos == QoS::AtMostOnce {
+                publish_message_qos0(
+                    &self.cache_manager,
+                    &self.connection_manager,
+                    &message.param,
+                    &self.stop_sx,
                 )
-                .await?;
+                .await;
+                continue;
+            }
 
-                cache_manager.remove_pkid_info(&client_id, pkid);
-                cache_manager.remove_ack_packet(&client_id, pkid);
+            let restored_pkid = self.restored_pkids.remove(&message.offset);
+            let pkid = match restored_pkid {
+                Some(pkid) if message.param.publish.qos == QoS::ExactlyOnce => {
+                    message.param.publish.dup = true;
+                    pkid
+                }
+                _ => {
+                    if let Some(pkid) = restored_pkid {
+                        self.release_restored_pkid(pkid).await;
+                    }
+                    let pkid = self.cache_manager.get_pkid(&client_id).await;
+                    if message.param.publish.qos == QoS::ExactlyOnce {
+                        // Persisted before sending, the client may receive the message even
+                        // if the broker stops right after
+                        if let Err(e) = save_send_qos2_state(
+                            &self.cache_manager,
+                            &MqttQos2State::new_send(
+                                client_id.clone(),
+                                pkid,
+                                Qos2Stage::WaitPubRec,
+                                self.group_id.clone(),
+                                message.offset,
+                            ),
+                        )
+                        .await
+                        {
+                            error!(
+                                "Failed to save the QoS 2 state of packet [{}] for client [{}], the message is sent later, error: {}",
+                                pkid, client_id, e
+                            );
+                            self.cache_manager.remove_pkid_info(&client_id, pkid);
+                            self.queue.push_front(message);
+                            break;
+                        }
+                    }
+                    pkid
+                }
+            };
+            message.param.pkid = pkid;
+            message.param.publish.pkid = pkid;
+
+            // Register before sending, so that an early acknowledgement is not missed
+            self.cache_manager.add_ack_packet(
+                &client_id,
+                pkid,
+                QosAckPacketInfo {
+                    sx: self.ack_sx.clone(),
+                    create_time: now_second(),
+                },
+            );
+            conn.send_qos_message_incr();
+
+            let stage = if message.param.publish.qos == QoS::AtLeastOnce {
+                InflightStage::WaitPubAck
+            } else {
+                InflightStage::WaitPubRec
+            };
+
+            if let Err(e) = send_publish_packet(
+                &self.connection_manager,
+                &self.cache_manager,
+                connect_id,
+                &message.param,
+            )
+            .await
+            {
+                error!(
+                    "Failed to write Publish message to response queue, failure message: {}",
+                    e
+                );
             }
+
+            self.window.push(InflightMessage {
+                offset: message.offset,
+                stage,
+                connect_id,
+                send_time: now_mills(),
+                param: message.param,
+            });
         }
+        sent
+    }
 
-        // commit offset
-        loop_commit_offset(
-            message_storage,
-            &subscriber.topic_id,
-            group_id,
-            record_offset,
-        )
-        .await;
+    async fn handle_ack(&mut self, data: QosAckPackageData) {
+        let client_id = &self.subscriber.client_id;
+        match self.window.ack(&data, now_mills()) {
+            InflightAckAction::Complete(message) => {
+                if message.stage == InflightStage::WaitPubComp {
+                    if let Err(e) =
+                        delete_send_qos2_state(&self.cache_manager, client_id, message.param.pkid)
+                            .await
+                    {
+                        error!(
+                            "Failed to delete the QoS 2 state of packet [{}] for client [{}], error: {}",
+                            message.param.pkid, client_id, e
+                        );
+                    }
+                }
+                self.cache_manager
+                    .remove_ack_packet(client_id, message.param.pkid);
+                self.cache_manager
+                    .remove_pkid_info(client_id, message.param.pkid);
+                if let Some(conn) = self.cache_manager.get_connection(message.connect_id) {
+                    conn.send_qos_message_decr();
+                }
+            }
+            InflightAckAction::SendPubRel(pkid) => {
+                if let Some(message) = self.window.get_mut(pkid) {
+                    // Without the saved stage a broker restarting now resends the PUBLISH with
+                    // DUP instead of the PUBREL, which the client answers with PUBREC again
+                    if let Err(e) = save_send_qos2_state(
+                        &self.cache_manager,
+                        &MqttQos2State::new_send(
+                            client_id.clone(),
+                            pkid,
+                            Qos2Stage::WaitPubComp,
+                            self.group_id.clone(),
+                            message.offset,
+                        ),
+                    )
+                    .await
+                    {
+                        error!(
+                            "Failed to save the QoS 2 state of packet [{}] for client [{}], error: {}",
+                            pkid, client_id, e
+                        );
+                    }
+                    if let Err(e) = send_pubrel_packet(
+                        &self.connection_manager,
+                        &self.cache_manager,
+                        message.connect_id,
+                        &message.param,
+                    )
+                    .await
+                    {
+                        error!(
+                            "Failed to write PubRel message to response queue, failure message: {}",
+                            e
+                        );
+                    }
+                }
+            }
+            InflightAckAction::Ignore => {}
+        }
+    }
+
+    // Commit the offset of the oldest message that has not been delivered yet, so that a push
+    // thread restarted for this group (e.g. after a session takeover) resumes from it
+    async fn commit_offset(&mut self) {
+        let offset = [
+            self.window.first_offset(),
+            self.queue.first_offset(),
+            Some(self.offset),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(self.offset);
+
+        if offset != self.committed_offset {
+            loop_commit_offset(
+                &self.message_storage,
+                &self.subscriber.topic_id,
+                &self.group_id,
+                offset,
+            )
+            .await;
+            self.committed_offset = offset;
+        }
+    }
+
+    // A restored message that is no longer delivered as QoS2, e.g. because it expired
+    async fn release_restored_pkid(&self, pkid: u16) {
+        let client_id = &self.subscriber.client_id;
+        if let Err(e) = delete_send_qos2_state(&self.cache_manager, client_id, pkid).await {
+            error!(
+                "Failed to delete the QoS 2 state of packet [{}] for client [{}], error: {}",
+                pkid, client_id, e
+            );
+        }
+        self.cache_manager.remove_pkid_info(client_id, pkid);
     }
 
-    Ok(Some(results.last().unwrap().offset.unwrap()))
+    // The packet identifiers of unacknowledged messages stay reserved, the client may still
+    // acknowledge them, or they are handed over to the broker taking over the session.
+    fn release(&mut self) {
+        for message in self.window.drain() {
+            self.cache_manager
+                .remove_ack_packet(&self.subscriber.client_id, message.param.pkid);
+            if let Some(conn) = self.cache_manager.get_connection(message.connect_id) {
+                conn.send_qos_message_decr();
+            }
+        }
+    }
 }
 
-async fn buile_pub_message(
+fn build_queued_message(
+    cache_manager: &Arc<CacheManager>,
     record: Record,
     group_id: &str,
     qos: &QoS,
     subscriber: &Subscriber,
-    cache_manager: &Arc<CacheManager>,
     sub_ids: &[usize],
-) -> Result<Option<SubPublishParam>, MqttBrokerError> {
+) -> Result<Option<QueuedMessage>, MqttBrokerError> {
+    let offset = record.offset.unwrap();
     let msg = MqttMessage::decode_record(record.clone())?;
 
     if is_message_expire(&msg) {
         debug!("message expires, is not pushed to the client, and is discarded");
+        metrics_message_dropped(MessageDropReason::Expired, &subscriber.topic_name);
+        trace_message_drop(
+            cache_manager,
+            &subscriber.client_id,
+            &subscriber.topic_name,
+            Some(offset),
+            "message expired",
+        );
         return Ok(None);
     }
 
     if subscriber.nolocal && (subscriber.client_id == msg.client_id) {
+        metrics_message_dropped(MessageDropReason::NoLocal, &subscriber.topic_name);
+        trace_message_drop(
+            cache_manager,
+            &subscriber.client_id,
+            &subscriber.topic_name,
+            Some(offset),
+            "no_local subscription",
+        );
         return Ok(None);
     }
 
+    if msg.qos > *qos {
+        metrics_message_qos_downgraded(&subscriber.topic_name);
+    }
+
     let retain = if subscriber.preserve_retain {
         msg.retain
     } else {
         false
     };
 
-    let mut publish = Publish {
+    // The packet identifier is allocated when the message enters the inflight window
+    let publish = Publish {
         dup: false,
         qos: qos.to_owned(),
         pkid: 0,
@@ -351,22 +820,19 @@ async fn buile_pub_message(
         content_type: msg.content_type,
     };
 
-    let pkid = if *qos != QoS::AtMostOnce {
-        cache_manager.get_pkid(&subscriber.client_id).await
-    } else {
-        0
-    };
-    publish.pkid = pkid;
-
     let sub_pub_param = SubPublishParam::new(
         subscriber.clone(),
         publish,
         Some(properties),
         record.timestamp as u128,
         group_id.to_string(),
-        pkid,
+        0,
     );
-    Ok(Some(sub_pub_param))
+    Ok(Some(QueuedMessage {
+        offset,
+        qos: msg.qos,
+        param: sub_pub_param,
+    }))
 }
 
 // When the subscribed QOS is 1, we need to keep retrying to send the message to the client.
@@ -499,7 +965,7 @@ pub async fn exclusive_publish_message_qos2(
     Ok(())
 }
 
-fn build_group_name(subscriber: &Subscriber) -> String {
+pub fn build_group_name(subscriber: &Subscriber) -> String {
     format!(
         "system_sub_{}_{}_{}",
         subscriber.client_id, subscriber.sub_path, subscriber.topic_id
@@ -520,4 +986,154 @@ fn build_sub_ids(subscriber: &Subscriber) -> Vec<usize> {
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use common_base::config::broker_mqtt::init_broker_mqtt_conf_by_path;
+    use common_base::tools::now_second;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::connection::{ConnectionConfig, MQTTConnection};
+    use metadata_struct::mqtt::message::MqttMessage;
+    use metadata_struct::mqtt::qos2_state::{MqttQos2State, Qos2Stage};
+    use metadata_struct::mqtt::session::MqttSession;
+    use protocol::mqtt::common::{Publish, QoS};
+    use storage_adapter::memory::MemoryStorageAdapter;
+    use tokio::sync::broadcast;
+
+    use super::{build_group_name, InflightPush};
+    use crate::handler::cache::{CacheManager, QosAckPackageData, QosAckPackageType};
+    use crate::server::connection_manager::ConnectionManager;
+    use crate::storage::message::MessageStorage;
+    use crate::subscribe::inflight::InflightStage;
+    use crate::subscribe::subscriber::Subscriber;
+
+    #[tokio::test]
+    async fn restart_between_pubrec_and_pubrel_test() {
+        let path = format!(
+            "{}/../../config/mqtt-server.toml",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        init_broker_mqtt_conf_by_path(&path);
+
+        let client_id = "qos2-restart".to_string();
+        let subscriber = Subscriber {
+            client_id: client_id.clone(),
+            sub_path: "/test/qos2".to_string(),
+            topic_name: "/test/qos2".to_string(),
+            topic_id: "topic-qos2".to_string(),
+            qos: QoS::ExactlyOnce,
+            ..Default::default()
+        };
+
+        let message_storage = MessageStorage::new(Arc::new(MemoryStorageAdapter::new()));
+        let mut records = Vec::new();
+        for i in 0..3 {
+            let publish = Publish {
+                dup: false,
+                qos: QoS::ExactlyOnce,
+                pkid: 0,
+                retain: false,
+                topic: Bytes::from(subscriber.topic_name.clone()),
+                payload: Bytes::from(format!("message-{}", i)),
+            };
+            records.push(
+                MqttMessage::build_record("publisher", &publish, &None, now_second() + 3600)
+                    .unwrap(),
+            );
+        }
+        message_storage
+            .append_topic_message(&subscriber.topic_id, records)
+            .await
+            .unwrap();
+
+        // The broker was stopped after the message at offset 0 was answered with PUBREC,
+        // and before the client received the message at offset 1. It restarts with an
+        // empty cache and the QoS 2 state that was persisted.
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
+        let group_id = build_group_name(&subscriber);
+        let states = vec![
+            MqttQos2State::new_send(
+                client_id.clone(),
+                3,
+                Qos2Stage::WaitPubComp,
+                group_id.clone(),
+                0,
+            ),
+            MqttQos2State::new_send(
+                client_id.clone(),
+                4,
+                Qos2Stage::WaitPubRec,
+                group_id.clone(),
+                1,
+            ),
+        ];
+
+        let connect_id = 1;
+        let mut session = MqttSession::new(client_id.clone(), 60, false, None);
+        session.update_connnction_id(Some(connect_id));
+        cache_manager.add_session(client_id.clone(), session);
+        cache_manager.add_connection(
+            connect_id,
+            MQTTConnection::new(ConnectionConfig {
+                connect_id,
+                client_id: client_id.clone(),
+                receive_maximum: 10,
+                max_packet_size: 1024 * 1024,
+                topic_alias_max: 0,
+                request_problem_info: 0,
+                keep_alive: 60,
+                source_ip_addr: "127.0.0.1".to_string(),
+            }),
+        );
+
+        let (stop_sx, _) = broadcast::channel(1);
+        let mut push = InflightPush::new(
+            connection_manager,
+            cache_manager.clone(),
+            message_storage,
+            subscriber,
+            group_id,
+            0,
+            Arc::new(AtomicUsize::new(0)),
+            stop_sx,
+        );
+        push.apply_restored_qos2_state(states);
+        assert_eq!(cache_manager.list_publish_pkid(&client_id), vec![3, 4]);
+
+        assert!(push.push().await.unwrap());
+
+        // The message the client already holds is released, not delivered again
+        let released = push.window.get_mut(3).unwrap();
+        assert_eq!(released.offset, 0);
+        assert_eq!(released.stage, InflightStage::WaitPubComp);
+        assert_eq!(released.connect_id, connect_id);
+
+        // The message the client may hold is resent with its packet identifier and DUP
+        let resent = push.window.get_mut(4).unwrap();
+        assert_eq!(resent.offset, 1);
+        assert_eq!(resent.stage, InflightStage::WaitPubRec);
+        assert!(resent.param.publish.dup);
+
+        // The next message gets a packet identifier the client does not hold
+        let next = push.window.get_mut(1).unwrap();
+        assert_eq!(next.offset, 2);
+        assert!(!next.param.publish.dup);
+        assert!(push.queue.front().is_none());
+
+        push.commit_offset().await;
+        assert_eq!(push.committed_offset, 0);
+
+        push.handle_ack(QosAckPackageData {
+            ack_type: QosAckPackageType::PubComp,
+            pkid: 3,
+        })
+        .await;
+        assert!(push.window.get_mut(3).is_none());
+        push.commit_offset().await;
+        assert_eq!(push.committed_offset, 1);
+    }
+}
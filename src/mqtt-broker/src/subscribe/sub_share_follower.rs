@@ -48,6 +48,13 @@ use crate::server::packet::ResponsePackage;
 use crate::subscribe::subscribe_manager::ShareSubShareSub;
 use crate::subscribe::subscriber::Subscriber;
 
+// Client ID prefix of the connection a follower broker opens to the share leader
+pub const SHARE_SUB_FOLLOWER_CLIENT_ID_PREFIX: &str = "share_follower_";
+
+pub fn is_share_follower_client(client_id: &str) -> bool {
+    client_id.starts_with(SHARE_SUB_FOLLOWER_CLIENT_ID_PREFIX)
+}
+
 #[derive(Clone)]
 pub struct SubscribeShareFollower {
     pub subscribe_manager: Arc<SubscribeManager>,
@@ -218,7 +225,8 @@ async fn resub_sub_mqtt5(
     ws.add_write(write_frame_stream);
     let write_stream = Arc::new(ws);
 
-    let follower_sub_leader_client_id = unique_id();
+    let follower_sub_leader_client_id =
+        format!("{}{}", SHARE_SUB_FOLLOWER_CLIENT_ID_PREFIX, unique_id());
     let follower_sub_leader_pkid: u16 = 1;
 
     // Create a connection to GroupName
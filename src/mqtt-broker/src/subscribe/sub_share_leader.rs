@@ -18,6 +18,7 @@ use std::time::Duration;
 use bytes::Bytes;
 use common_base::tools::now_second;
 use log::{error, info};
+use metadata_struct::mqtt::cluster::SharedSubscriptionStrategy;
 use metadata_struct::mqtt::message::MqttMessage;
 use protocol::mqtt::common::{MqttPacket, MqttProtocol, Publish, PublishProperties, QoS};
 use storage_adapter::storage::StorageAdapter;
@@ -25,14 +26,18 @@ use tokio::select;
 use tokio::sync::broadcast::{self, Sender};
 use tokio::time::sleep;
 
+use super::share_strategy::{share_strategy_group_name, ShareSubscriberSelector};
 use super::sub_common::{
     loop_commit_offset, min_qos, publish_message_qos0, publish_message_to_client,
-    qos2_send_publish, qos2_send_pubrel, wait_packet_ack,
+    qos2_send_publish, qos2_send_pubrel, trace_message_drop, wait_packet_ack,
 };
 use super::subscribe_manager::{ShareLeaderSubscribeData, SubscribeManager};
 use crate::handler::cache::{CacheManager, QosAckPackageData, QosAckPackageType, QosAckPacketInfo};
 use crate::handler::error::MqttBrokerError;
 use crate::handler::message::is_message_expire;
+use crate::observability::metrics::messages::{
+    metrics_message_dropped, metrics_message_qos_downgraded, MessageDropReason,
+};
 use crate::server::connection_manager::ConnectionManager;
 use crate::server::packet::ResponsePackage;
 use crate::storage::message::MessageStorage;
@@ -119,17 +124,13 @@ where
                 .share_leader_push_thread
                 .contains_key(&share_leader_key)
             {
-                self.push_by_round_robin(
-                    share_leader_key,
-                    sub_data,
-                    self.subscribe_manager.clone(),
-                )
-                .await;
+                self.push_by_strategy(share_leader_key, sub_data, self.subscribe_manager.clone())
+                    .await;
             }
         }
     }
 
-    async fn push_by_round_robin(
+    async fn push_by_strategy(
         &self,
         share_leader_key: String,
         sub_data: ShareLeaderSubscribeData,
@@ -141,8 +142,6 @@ where
             "system_sub_{}_{}_{}",
             sub_data.group_name, sub_data.sub_name, sub_data.topic_id
         );
-        let cursor_point = 0;
-
         let message_storage = MessageStorage::new(self.message_storage.clone());
 
         // get current offset by group
@@ -170,6 +169,8 @@ where
 
             let mut sub_list: Vec<Subscriber> =
                 build_share_leader_sub_list(&subscribe_manager, &share_leader_key);
+            let mut selector =
+                ShareSubscriberSelector::new(get_share_strategy(&cache_manager, &sub_data));
             let mut pre_times = now_second();
             loop {
                 select! {
@@ -191,7 +192,7 @@ where
                         &sub_data,
                         &sub_list,
                         &group_id,
-                        cursor_point,
+                        &mut selector,
                         offset,
                         &sub_thread_stop_sx
                     ) =>{
@@ -216,6 +217,7 @@ where
                         // Refresh the subscriber list of shared subscriptions every second to ensure that new subscribers can get messages in time.
                         if now_second() - pre_times >= 1{
                             sub_list = build_share_leader_sub_list(&subscribe_manager, &share_leader_key);
+                            selector.set_strategy(get_share_strategy(&cache_manager, &sub_data));
                             pre_times = now_second();
                         }
                     }
@@ -237,7 +239,7 @@ async fn read_message_process<S>(
     sub_data: &ShareLeaderSubscribeData,
     sub_list: &[Subscriber],
     group_id: &str,
-    mut cursor_point: usize,
+    selector: &mut ShareSubscriberSelector,
     offset: u64,
     stop_sx: &Sender<bool>,
 ) -> Result<Option<u64>, MqttBrokerError>
@@ -258,6 +260,14 @@ where
         let msg = MqttMessage::decode_record(record.clone())?;
 
         if is_message_expire(&msg) {
+            metrics_message_dropped(MessageDropReason::Expired, &sub_data.topic_name);
+            trace_message_drop(
+                cache_manager,
+                "",
+                &sub_data.topic_name,
+                record.offset,
+                "message expired",
+            );
             continue;
         }
 
@@ -265,11 +275,33 @@ where
         loop {
             if loop_times > try_loop_times(sub_list.len()) {
                 error!("Share subscription push message fails, dropping the message, possibly because no subscriber is available");
+                metrics_message_dropped(MessageDropReason::NoSubscribers, &sub_data.topic_name);
+                trace_message_drop(
+                    cache_manager,
+                    "",
+                    &sub_data.topic_name,
+                    record.offset,
+                    &format!("no subscriber of share group {} is available", group_id),
+                );
                 break;
             }
 
-            cursor_point = choose_available_sub(cursor_point, sub_list);
-            let subscribe = sub_list.get(cursor_point).unwrap();
+            let subscribe = if let Some(index) =
+                selector.choose(cache_manager, sub_list, &msg, loop_times)
+            {
+                &sub_list[index]
+            } else {
+                error!("Share subscription push message fails, dropping the message, because the group has no subscriber");
+                metrics_message_dropped(MessageDropReason::NoSubscribers, &sub_data.topic_name);
+                trace_message_drop(
+                    cache_manager,
+                    "",
+                    &sub_data.topic_name,
+                    record.offset,
+                    &format!("share group {} has no subscriber", group_id),
+                );
+                break;
+            };
 
             if let Some((mut publish, properties)) =
                 build_publish(cache_manager, subscribe, &sub_data.topic_name, &msg)
@@ -307,12 +339,12 @@ where
             loop_times += 1;
         }
 
-        // commit offset
+        // commit offset, the committed offset is the next one to read
         loop_commit_offset(
             message_storage,
             &sub_data.topic_id,
             group_id,
-            record.offset.unwrap(),
+            record.offset.unwrap() + 1,
         )
         .await;
     }
@@ -323,13 +355,14 @@ fn try_loop_times(sub_len: usize) -> usize {
     sub_len * 2
 }
 
-fn choose_available_sub(cursor_point: usize, sub_list: &[Subscriber]) -> usize {
-    let current_point = cursor_point + 1;
-    if current_point < sub_list.len() {
-        current_point
-    } else {
-        0
-    }
+fn get_share_strategy(
+    cache_manager: &Arc<CacheManager>,
+    sub_data: &ShareLeaderSubscribeData,
+) -> SharedSubscriptionStrategy {
+    cache_manager
+        .get_cluster_info()
+        .feature
+        .get_shared_subscription_strategy(share_strategy_group_name(&sub_data.group_name))
 }
 
 async fn qos_publish<S>(
@@ -439,6 +472,10 @@ fn build_publish(
         return None;
     }
 
+    if msg.qos > qos {
+        metrics_message_qos_downgraded(topic_name);
+    }
+
     let publish = Publish {
         dup: false,
         qos,
@@ -575,7 +612,7 @@ where
                     message_storage,
                     &sub_pub_param.subscribe.topic_id,
                     &sub_pub_param.group_id,
-                    offset,
+                    offset + 1,
                 )
                 .await;
                 break;
@@ -628,6 +665,12 @@ fn build_share_leader_sub_list(
     for (_, sub) in sub_list {
         result.push(sub);
     }
+    // Keep a stable order so that hash based strategies map keys to the same subscriber
+    result.sort_by(|a, b| {
+        a.client_id
+            .cmp(&b.client_id)
+            .then_with(|| a.sub_path.cmp(&b.sub_path))
+    });
     result
 }
 
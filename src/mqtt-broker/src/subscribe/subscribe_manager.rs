@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -81,6 +82,9 @@ pub struct SubscribeManager {
     // (client_id_sub_name_topic_id, Sender<bool>)
     pub exclusive_push_thread: DashMap<String, Sender<bool>>,
 
+    // (client_id, number of messages queued for the exclusive subscriptions of the session)
+    pub exclusive_session_queue_len: DashMap<String, Arc<AtomicUsize>>,
+
     // (group_name_sub_name_topic_id, ShareLeaderSubscribeData)
     pub share_leader_subscribe: DashMap<String, ShareLeaderSubscribeData>,
 
@@ -107,11 +111,19 @@ impl SubscribeManager {
             share_follower_subscribe: DashMap::with_capacity(8),
             share_follower_identifier_id: DashMap::with_capacity(8),
             exclusive_push_thread: DashMap::with_capacity(8),
+            exclusive_session_queue_len: DashMap::with_capacity(8),
             share_leader_push_thread: DashMap::with_capacity(8),
             share_follower_resub_thread: DashMap::with_capacity(8),
         }
     }
 
+    pub fn session_queue_len(&self, client_id: &str) -> Arc<AtomicUsize> {
+        self.exclusive_session_queue_len
+            .entry(client_id.to_string())
+            .or_insert_with(|| Arc::new(AtomicUsize::new(0)))
+            .clone()
+    }
+
     pub async fn start(&self) {
         info!("Subscribe manager thread started successfully.");
         loop {
@@ -186,6 +198,51 @@ impl SubscribeManager {
         }
     }
 
+    // Removes every subscription of the client and notifies the push threads serving them
+    pub fn remove_subscribe_by_client_id(&self, client_id: &str) {
+        for (key, subscriber) in self.exclusive_subscribe.clone() {
+            if subscriber.client_id == *client_id {
+                if let Some(sx) = self.exclusive_push_thread.get(&key) {
+                    if let Err(e) = sx.send(true) {
+                        error!("{}", e);
+                    }
+                }
+                self.exclusive_subscribe.remove(&key);
+            }
+        }
+
+        for (key, data) in self.share_leader_subscribe.clone() {
+            let mut flag = false;
+            for (sub_key, share_sub) in data.sub_list {
+                if share_sub.client_id == *client_id {
+                    if let Some(mut_data) = self.share_leader_subscribe.get_mut(&key) {
+                        mut_data.sub_list.remove(&sub_key);
+                    }
+                    flag = true;
+                }
+            }
+
+            if flag {
+                if let Some(sx) = self.share_leader_push_thread.get(&key) {
+                    if let Err(e) = sx.send(true) {
+                        error!("{}", e);
+                    }
+                }
+            }
+        }
+
+        for (key, data) in self.share_follower_subscribe.clone() {
+            if data.client_id == *client_id {
+                self.share_follower_subscribe.remove(&key);
+                if let Some(sx) = self.share_follower_resub_thread.get(&key) {
+                    if let Err(e) = sx.send(true) {
+                        error!("{}", e);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn remove_subscribe(&self, client_id: &str, filter_path: &[String]) {
         for (topic_name, _) in self.metadata_cache.topic_info.clone() {
             for path in filter_path {
@@ -557,3 +614,75 @@ impl SubscribeManager {
         format!("{}_{}_{}", client_id, group_name, topic_id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use dashmap::DashMap;
+    use grpc_clients::pool::ClientPool;
+    use tokio::sync::broadcast;
+
+    use super::{ShareLeaderSubscribeData, SubscribeManager};
+    use crate::handler::cache::CacheManager;
+    use crate::subscribe::subscriber::Subscriber;
+
+    fn build_subscriber(client_id: &str) -> Subscriber {
+        Subscriber {
+            client_id: client_id.to_string(),
+            sub_path: "t/1".to_string(),
+            topic_name: "t/1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn remove_subscribe_by_client_id_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        let subscribe_manager = SubscribeManager::new(cache_manager, client_pool);
+
+        let (exclusive_sx, mut exclusive_rx) = broadcast::channel(1);
+        subscribe_manager
+            .exclusive_subscribe
+            .insert("c1_t/1".to_string(), build_subscriber("c1"));
+        subscribe_manager
+            .exclusive_push_thread
+            .insert("c1_t/1".to_string(), exclusive_sx);
+        subscribe_manager
+            .exclusive_subscribe
+            .insert("c2_t/1".to_string(), build_subscriber("c2"));
+
+        let (leader_sx, mut leader_rx) = broadcast::channel(1);
+        let sub_list = DashMap::new();
+        sub_list.insert("c1_t/1".to_string(), build_subscriber("c1"));
+        sub_list.insert("c2_t/1".to_string(), build_subscriber("c2"));
+        subscribe_manager.share_leader_subscribe.insert(
+            "g1_t/1".to_string(),
+            ShareLeaderSubscribeData {
+                group_name: "g1".to_string(),
+                topic_id: "t1".to_string(),
+                topic_name: "t/1".to_string(),
+                sub_name: "t/1".to_string(),
+                sub_list,
+            },
+        );
+        subscribe_manager
+            .share_leader_push_thread
+            .insert("g1_t/1".to_string(), leader_sx);
+
+        subscribe_manager.remove_subscribe_by_client_id("c1");
+
+        assert!(!subscribe_manager.exclusive_subscribe.contains_key("c1_t/1"));
+        assert!(subscribe_manager.exclusive_subscribe.contains_key("c2_t/1"));
+        assert!(exclusive_rx.recv().await.unwrap());
+
+        let leader = subscribe_manager
+            .share_leader_subscribe
+            .get("g1_t/1")
+            .unwrap();
+        assert!(!leader.sub_list.contains_key("c1_t/1"));
+        assert!(leader.sub_list.contains_key("c2_t/1"));
+        assert!(leader_rx.recv().await.unwrap());
+    }
+}
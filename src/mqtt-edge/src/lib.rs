@@ -11,3 +11,181 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::config::broker_mqtt::{
+    broker_mqtt_conf, init_broker_mqtt_conf_by_config, BrokerMqttConfig, MetadataStorage, Network,
+    System, TcpThread,
+};
+use common_base::config::broker_mqtt_edge::{broker_mqtt_edge_conf, BrokerMqttEdgeConfig};
+use common_base::config::common::Storage;
+use common_base::config::default_mqtt::{
+    default_auth, default_network, default_system, default_tcp_thread, default_webhook,
+};
+use common_base::error::common::CommonError;
+use common_base::runtime::create_runtime;
+use common_base::tools::try_create_fold;
+use error::MqttEdgeError;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::cluster::MqttClusterDynamicConfig;
+use mqtt_broker::handler::cache::CacheManager;
+use mqtt_broker::storage::cluster::ClusterStorage;
+use mqtt_broker::storage::metadata::METADATA_STORAGE_TYPE_ROCKSDB;
+use mqtt_broker::MqttBroker;
+use security::{import_auth_file, read_auth_file, AuthFileContent};
+use storage_adapter::rocksdb::RocksDBStorageAdapter;
+use tokio::sync::broadcast;
+
+pub mod error;
+pub mod security;
+
+const EDGE_BROKER_ID: u64 = 1;
+
+/// Starts the edge profile of the MQTT broker. It is the regular `MqttBroker` without a
+/// placement center: the messages go to a local RocksDB storage adapter and the metadata to
+/// a local RocksDB metadata storage, both below the data path of the node.
+pub fn start_mqtt_edge_server(stop_send: broadcast::Sender<bool>) {
+    let conf = broker_mqtt_edge_conf();
+    init_broker_mqtt_conf_by_config(build_broker_mqtt_conf(conf));
+
+    let auth_content = match read_auth_file(&conf.auth.auth_file) {
+        Ok(content) => content,
+        Err(e) => {
+            panic!("{}", e);
+        }
+    };
+
+    let storage = &broker_mqtt_conf().storage;
+    if let Err(e) = try_create_fold(&storage.rocksdb_data_path) {
+        panic!("{}", e);
+    }
+    let message_storage_adapter = Arc::new(RocksDBStorageAdapter::new(
+        &storage.rocksdb_data_path,
+        storage.rocksdb_max_open_files.unwrap_or(1000),
+    ));
+
+    let client_pool = Arc::new(ClientPool::new(5));
+
+    // The broker loads its caches from the metadata when it starts, so the cluster config
+    // and the auth file are written first
+    let runtime = create_runtime("mqtt-edge-bootstrap-runtime", 1);
+    if let Err(e) = runtime.block_on(bootstrap(&client_pool, conf, auth_content)) {
+        panic!(
+            "Failed to bootstrap the MQTT Edge node, error message: {}",
+            e
+        );
+    }
+    drop(runtime);
+
+    let cache_manager = Arc::new(CacheManager::new(
+        client_pool.clone(),
+        broker_mqtt_conf().cluster_name.clone(),
+    ));
+    let server = MqttBroker::new(client_pool, message_storage_adapter, cache_manager);
+    server.start(stop_send);
+}
+
+fn build_broker_mqtt_conf(conf: &BrokerMqttEdgeConfig) -> BrokerMqttConfig {
+    BrokerMqttConfig {
+        cluster_name: conf.node_name.clone(),
+        broker_id: EDGE_BROKER_ID,
+        grpc_port: conf.network.grpc_port,
+        http_port: conf.network.http_port,
+        placement_center: Vec::new(),
+        network: Network {
+            tcp_port: conf.network.tcp_port,
+            ..default_network()
+        },
+        tcp_thread: TcpThread {
+            max_connection_num: conf.network.max_connection_num,
+            ..default_tcp_thread()
+        },
+        system: System {
+            runtime_worker_threads: conf.system.runtime_worker_threads,
+            ..default_system()
+        },
+        storage: Storage {
+            storage_type: "rocksdb".to_string(),
+            journal_addr: "".to_string(),
+            mysql_addr: "".to_string(),
+            rocksdb_data_path: format!("{}/message", conf.storage.data_path),
+            rocksdb_max_open_files: conf.storage.max_open_files,
+        },
+        metadata: MetadataStorage {
+            storage_type: METADATA_STORAGE_TYPE_ROCKSDB.to_string(),
+            rocksdb_data_path: format!("{}/metadata", conf.storage.data_path),
+            rocksdb_max_open_files: conf.storage.max_open_files,
+        },
+        auth: default_auth(),
+        log: conf.log.clone(),
+        webhook: default_webhook(),
+        ..Default::default()
+    }
+}
+
+async fn bootstrap(
+    client_pool: &Arc<ClientPool>,
+    conf: &BrokerMqttEdgeConfig,
+    auth_content: AuthFileContent,
+) -> Result<(), MqttEdgeError> {
+    save_cluster_config(client_pool, conf.auth.allow_anonymous).await?;
+    import_auth_file(client_pool, auth_content).await
+}
+
+async fn save_cluster_config(
+    client_pool: &Arc<ClientPool>,
+    allow_anonymous: bool,
+) -> Result<(), CommonError> {
+    let cluster_name = &broker_mqtt_conf().cluster_name;
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let mut cluster = match cluster_storage.get_cluster_config(cluster_name).await? {
+        Some(cluster) => cluster,
+        None => MqttClusterDynamicConfig::new(),
+    };
+    cluster.security.secret_free_login = allow_anonymous;
+    cluster_storage
+        .set_cluster_config(cluster_name, cluster)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use common_base::config::broker_mqtt_edge::BrokerMqttEdgeConfig;
+
+    use super::build_broker_mqtt_conf;
+
+    #[test]
+    fn build_conf_test() {
+        let conf: BrokerMqttEdgeConfig = toml::from_str(
+            r#"
+            node_name = "edge-1"
+
+            [network]
+            tcp_port = 1885
+
+            [storage]
+            data_path = "/tmp/mqtt-edge"
+            "#,
+        )
+        .unwrap();
+
+        let broker_conf = build_broker_mqtt_conf(&conf);
+        assert_eq!(broker_conf.cluster_name, "edge-1");
+        assert_eq!(broker_conf.network.tcp_port, 1885);
+        assert!(broker_conf.placement_center.is_empty());
+        assert_eq!(broker_conf.storage.storage_type, "rocksdb");
+        assert_eq!(
+            broker_conf.storage.rocksdb_data_path,
+            "/tmp/mqtt-edge/message"
+        );
+        assert_eq!(broker_conf.metadata.storage_type, "rocksdb");
+        assert_eq!(
+            broker_conf.metadata.rocksdb_data_path,
+            "/tmp/mqtt-edge/metadata"
+        );
+        assert_eq!(broker_conf.system.runtime_worker_threads, 4);
+        assert_eq!(broker_conf.tcp_thread.max_connection_num, 1000);
+        assert!(!broker_conf.network.quic_enable);
+    }
+}
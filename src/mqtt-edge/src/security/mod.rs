@@ -0,0 +1,170 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::config::broker_mqtt::broker_mqtt_conf;
+use common_base::tools::{file_exists, read_file};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::acl::mqtt_acl::MqttAcl;
+use metadata_struct::mqtt::user::MqttUser;
+use mqtt_broker::storage::acl::AclStorage;
+use mqtt_broker::storage::user::UserStorage;
+use serde::{Deserialize, Serialize};
+
+use crate::error::MqttEdgeError;
+
+/// Content of the local auth file. The users and ACLs are imported into the local metadata
+/// storage at startup, the broker then loads them like on a regular cluster.
+#[derive(Clone, Serialize, Deserialize, Default, Debug)]
+pub struct AuthFileContent {
+    #[serde(default)]
+    pub users: Vec<MqttUser>,
+    #[serde(default)]
+    pub acls: Vec<MqttAcl>,
+}
+
+pub fn read_auth_file(path: &str) -> Result<AuthFileContent, MqttEdgeError> {
+    if path.is_empty() {
+        return Ok(AuthFileContent::default());
+    }
+    if !file_exists(path) {
+        return Err(MqttEdgeError::AuthFileNotExists(path.to_string()));
+    }
+    Ok(serde_json::from_str::<AuthFileContent>(&read_file(path)?)?)
+}
+
+// The auth file is the whole truth about the users and ACLs of the node: the ones removed
+// from the file since the last start are deleted from the metadata as well. The system user
+// of the broker is not part of the file and is kept.
+pub async fn import_auth_file(
+    client_pool: &Arc<ClientPool>,
+    content: AuthFileContent,
+) -> Result<(), MqttEdgeError> {
+    let system_user = &broker_mqtt_conf().system.default_user;
+    let user_storage = UserStorage::new(client_pool.clone());
+    for (username, _) in user_storage.user_list().await? {
+        if username != *system_user && !content.users.iter().any(|u| u.username == username) {
+            user_storage.delete_user(username).await?;
+        }
+    }
+    for user in content.users {
+        user_storage.save_user(user).await?;
+    }
+
+    let acl_storage = AclStorage::new(client_pool.clone());
+    for acl in acl_storage.list_acl().await? {
+        if !content.acls.contains(&acl) {
+            acl_storage.delete_acl(acl).await?;
+        }
+    }
+    for acl in content.acls {
+        acl_storage.save_acl(acl).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+    use std::sync::Arc;
+
+    use common_base::config::broker_mqtt::{
+        init_broker_mqtt_conf_by_config, BrokerMqttConfig, MetadataStorage,
+    };
+    use common_base::tools::unique_id;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::acl::mqtt_acl::{
+        MqttAcl, MqttAclAction, MqttAclPermission, MqttAclResourceType,
+    };
+    use metadata_struct::mqtt::user::MqttUser;
+    use mqtt_broker::storage::acl::AclStorage;
+    use mqtt_broker::storage::user::UserStorage;
+
+    use super::{import_auth_file, read_auth_file, AuthFileContent};
+
+    #[test]
+    fn read_auth_file_test() {
+        let path = format!(
+            "{}/../../config/example/mqtt-edge-auth.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let content = read_auth_file(&path).unwrap();
+        assert_eq!(content.users.len(), 2);
+        assert!(content.users[0].is_superuser);
+        assert_eq!(content.users[1].username, "device".to_string());
+        assert_eq!(content.acls.len(), 1);
+        assert_eq!(content.acls[0].resource_type, MqttAclResourceType::User);
+        assert_eq!(content.acls[0].permission, MqttAclPermission::Deny);
+
+        assert!(read_auth_file("").unwrap().users.is_empty());
+        assert!(read_auth_file("/tmp/not-exists-auth.json").is_err());
+    }
+
+    fn user(username: &str) -> MqttUser {
+        MqttUser {
+            username: username.to_string(),
+            password: "pwd".to_string(),
+            is_superuser: false,
+        }
+    }
+
+    fn acl(resource_name: &str) -> MqttAcl {
+        MqttAcl {
+            resource_type: MqttAclResourceType::User,
+            resource_name: resource_name.to_string(),
+            topic: "t/#".to_string(),
+            ip: "*".to_string(),
+            action: MqttAclAction::All,
+            permission: MqttAclPermission::Deny,
+        }
+    }
+
+    #[tokio::test]
+    async fn import_auth_file_test() {
+        let dir = std::env::temp_dir().join(format!("mqtt-edge-{}", unique_id()));
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            metadata: MetadataStorage {
+                storage_type: "rocksdb".to_string(),
+                rocksdb_data_path: dir.to_str().unwrap().to_string(),
+                rocksdb_max_open_files: Some(100),
+            },
+            ..Default::default()
+        });
+        let client_pool = Arc::new(ClientPool::new(1));
+        let user_storage = UserStorage::new(client_pool.clone());
+        let acl_storage = AclStorage::new(client_pool.clone());
+
+        let content = AuthFileContent {
+            users: vec![user("u1"), user("u2")],
+            acls: vec![acl("u1"), acl("u2")],
+        };
+        import_auth_file(&client_pool, content).await.unwrap();
+        assert_eq!(user_storage.user_list().await.unwrap().len(), 2);
+        assert_eq!(acl_storage.list_acl().await.unwrap().len(), 2);
+
+        // u2 and its ACL were removed from the file
+        let content = AuthFileContent {
+            users: vec![user("u1")],
+            acls: vec![acl("u1")],
+        };
+        import_auth_file(&client_pool, content).await.unwrap();
+        let users = user_storage.user_list().await.unwrap();
+        assert_eq!(users.len(), 1);
+        assert!(users.contains_key("u1"));
+        assert_eq!(acl_storage.list_acl().await.unwrap(), vec![acl("u1")]);
+
+        remove_dir_all(dir).unwrap();
+    }
+}
@@ -16,8 +16,11 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use common_base::config::placement_center::placement_center_conf;
+use common_base::telemetry::{
+    grpc_server_span, init_telemetry, shutdown_telemetry, SERVICE_NAME_PLACEMENT_CENTER,
+};
 use grpc_clients::pool::ClientPool;
-use log::info;
+use log::{error, info};
 use openraft::Raft;
 use protocol::placement_center::placement_center_inner::placement_center_service_server::PlacementCenterServiceServer;
 use protocol::placement_center::placement_center_journal::engine_service_server::EngineServiceServer;
@@ -107,6 +110,13 @@ impl PlacementCenter {
     }
 
     pub async fn start(&mut self, stop_send: broadcast::Sender<bool>) {
+        if let Err(e) = init_telemetry(
+            SERVICE_NAME_PLACEMENT_CENTER,
+            &placement_center_conf().telemetry,
+        ) {
+            error!("Failed to initialize telemetry, error message: {}", e);
+        }
+
         self.init_cache();
 
         let data_route = Arc::new(DataRoute::new(
@@ -214,6 +224,7 @@ impl PlacementCenter {
         tokio::spawn(async move {
             info!("RobustMQ Meta Grpc Server start success. bind addr:{}", ip);
             Server::builder()
+                .trace_fn(grpc_server_span)
                 .add_service(PlacementCenterServiceServer::new(placement_handler))
                 .add_service(KvServiceServer::new(kv_handler))
                 .add_service(MqttServiceServer::new(mqtt_handler))
@@ -290,6 +301,7 @@ impl PlacementCenter {
         if stop_send.send(true).is_ok() {
             info!("When ctrl + c is received, the service starts to stop");
         }
+        shutdown_telemetry();
     }
 
     pub fn init_cache(&self) {
@@ -26,6 +26,7 @@ use super::session_expire::ExpireLastWill;
 use crate::core::cache::PlacementCacheManager;
 use crate::mqtt::cache::MqttCacheManager;
 use crate::storage::mqtt::session::MqttSessionStorage;
+use crate::storage::mqtt::subscribe::MqttSubscribeStorage;
 use crate::storage::rocksdb::RocksDBEngine;
 
 pub struct MqttBrokerCall {
@@ -86,7 +87,14 @@ impl MqttBrokerCall {
             debug!("Session expired call Broker status: {}", success);
             if success {
                 let session_storage = MqttSessionStorage::new(self.rocksdb_engine_handler.clone());
+                let subscribe_storage =
+                    MqttSubscribeStorage::new(self.rocksdb_engine_handler.clone());
                 for ms in raw {
+                    if let Err(e) =
+                        subscribe_storage.delete_by_client_id(&self.cluster_name, &ms.client_id)
+                    {
+                        error!("{}", e);
+                    }
                     match session_storage.delete(&self.cluster_name, &ms.client_id) {
                         Ok(()) => {
                             let delay = ms.last_will_delay_interval.unwrap_or_default();
@@ -87,8 +87,9 @@ impl MessageExpire {
             let mut value = serde_json::from_slice::<MqttTopic>(data.data.as_slice()).unwrap();
 
             if value.retain_message.is_some() {
+                // An expiry interval of 0 means the retained message never expires
                 let delete = if let Some(expired_at) = value.retain_message_expired_at {
-                    now_second() >= (data.create_time + expired_at)
+                    expired_at > 0 && now_second() >= (data.create_time + expired_at)
                 } else {
                     false
                 };
@@ -76,4 +76,13 @@ pub enum StorageDataType {
     MqttDeleteBlacklist,
     MqttSetNxExclusiveTopic,
     MqttDeleteExclusiveTopic,
+    MqttSetSubscribe,
+    MqttDeleteSubscribe,
+    MqttSetTopicRewriteRule,
+    MqttDeleteTopicRewriteRule,
+    MqttSetAutoSubscribeRule,
+    MqttDeleteAutoSubscribeRule,
+    MqttSaveQos2State,
+    MqttDeleteQos2State,
+    MqttSaveAlarm,
 }
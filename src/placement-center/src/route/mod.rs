@@ -210,6 +210,46 @@ impl DataRoute {
                 self.route_mqtt.delete_exclusive_topic(storage_data.value)?;
                 Ok(None)
             }
+            StorageDataType::MqttSetSubscribe => {
+                self.route_mqtt.create_subscribe(storage_data.value)?;
+                Ok(None)
+            }
+            StorageDataType::MqttDeleteSubscribe => {
+                self.route_mqtt.delete_subscribe(storage_data.value)?;
+                Ok(None)
+            }
+            StorageDataType::MqttSetTopicRewriteRule => {
+                self.route_mqtt
+                    .create_topic_rewrite_rule(storage_data.value)?;
+                Ok(None)
+            }
+            StorageDataType::MqttDeleteTopicRewriteRule => {
+                self.route_mqtt
+                    .delete_topic_rewrite_rule(storage_data.value)?;
+                Ok(None)
+            }
+            StorageDataType::MqttSetAutoSubscribeRule => {
+                self.route_mqtt
+                    .create_auto_subscribe_rule(storage_data.value)?;
+                Ok(None)
+            }
+            StorageDataType::MqttDeleteAutoSubscribeRule => {
+                self.route_mqtt
+                    .delete_auto_subscribe_rule(storage_data.value)?;
+                Ok(None)
+            }
+            StorageDataType::MqttSaveQos2State => {
+                self.route_mqtt.save_qos2_state(storage_data.value)?;
+                Ok(None)
+            }
+            StorageDataType::MqttDeleteQos2State => {
+                self.route_mqtt.delete_qos2_state(storage_data.value)?;
+                Ok(None)
+            }
+            StorageDataType::MqttSaveAlarm => {
+                self.route_mqtt.save_alarm(storage_data.value)?;
+                Ok(None)
+            }
         }
     }
 
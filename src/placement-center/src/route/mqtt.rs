@@ -14,19 +14,31 @@
 
 use std::sync::Arc;
 
+use metadata_struct::mqtt::alarm::MqttAlarm;
+use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
+use metadata_struct::mqtt::qos2_state::MqttQos2State;
 use metadata_struct::mqtt::session::MqttSession;
+use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
 use metadata_struct::mqtt::topic::MqttTopic;
+use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
 use prost::Message as _;
 use protocol::placement_center::placement_center_mqtt::{
-    CreateSessionRequest, CreateUserRequest, DeleteExclusiveTopicRequest, DeleteSessionRequest,
-    DeleteTopicRequest, DeleteUserRequest, SaveLastWillMessageRequest, SetExclusiveTopicRequest,
-    UpdateSessionRequest,
+    CreateAutoSubscribeRuleRequest, CreateSessionRequest, CreateTopicRewriteRuleRequest,
+    CreateUserRequest, DeleteAutoSubscribeRuleRequest, DeleteExclusiveTopicRequest,
+    DeleteQos2StateRequest, DeleteSessionRequest, DeleteSubscribeRequest, DeleteTopicRequest,
+    DeleteTopicRewriteRuleRequest, DeleteUserRequest, SaveAlarmRequest, SaveLastWillMessageRequest,
+    SaveQos2StateRequest, SetExclusiveTopicRequest, SetSubscribeRequest, UpdateSessionRequest,
 };
 
 use crate::core::error::PlacementCenterError;
+use crate::storage::mqtt::alarm::MqttAlarmStorage;
+use crate::storage::mqtt::auto_subscribe_rule::MqttAutoSubscribeRuleStorage;
 use crate::storage::mqtt::lastwill::MqttLastWillStorage;
+use crate::storage::mqtt::qos2_state::MqttQos2StateStorage;
 use crate::storage::mqtt::session::MqttSessionStorage;
+use crate::storage::mqtt::subscribe::MqttSubscribeStorage;
 use crate::storage::mqtt::topic::MqttTopicStorage;
+use crate::storage::mqtt::topic_rewrite_rule::MqttTopicRewriteRuleStorage;
 use crate::storage::mqtt::user::MqttUserStorage;
 use crate::storage::rocksdb::RocksDBEngine;
 
@@ -126,6 +138,83 @@ impl DataRouteMqtt {
         Ok(())
     }
 
+    pub fn create_subscribe(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = SetSubscribeRequest::decode(value.as_ref())?;
+        let storage = MqttSubscribeStorage::new(self.rocksdb_engine_handler.clone());
+        let subscribe = serde_json::from_slice::<MqttSubscribe>(&req.subscribe)?;
+        storage.save(&req.cluster_name, &req.client_id, &req.path, subscribe)?;
+        Ok(())
+    }
+
+    pub fn delete_subscribe(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = DeleteSubscribeRequest::decode(value.as_ref())?;
+        let storage = MqttSubscribeStorage::new(self.rocksdb_engine_handler.clone());
+        if req.path.is_empty() {
+            storage.delete_by_client_id(&req.cluster_name, &req.client_id)?;
+        } else {
+            storage.delete(&req.cluster_name, &req.client_id, &req.path)?;
+        }
+        Ok(())
+    }
+
+    pub fn create_topic_rewrite_rule(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = CreateTopicRewriteRuleRequest::decode(value.as_ref())?;
+        let storage = MqttTopicRewriteRuleStorage::new(self.rocksdb_engine_handler.clone());
+        let rule = serde_json::from_slice::<MqttTopicRewriteRule>(&req.topic_rewrite_rule)?;
+        storage.save(&req.cluster_name, rule)?;
+        Ok(())
+    }
+
+    pub fn delete_topic_rewrite_rule(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = DeleteTopicRewriteRuleRequest::decode(value.as_ref())?;
+        let storage = MqttTopicRewriteRuleStorage::new(self.rocksdb_engine_handler.clone());
+        storage.delete(&req.cluster_name, &req.action, &req.source_topic)?;
+        Ok(())
+    }
+
+    pub fn create_auto_subscribe_rule(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = CreateAutoSubscribeRuleRequest::decode(value.as_ref())?;
+        let storage = MqttAutoSubscribeRuleStorage::new(self.rocksdb_engine_handler.clone());
+        let rule = serde_json::from_slice::<MqttAutoSubscribeRule>(&req.auto_subscribe_rule)?;
+        storage.save(&req.cluster_name, rule)?;
+        Ok(())
+    }
+
+    pub fn delete_auto_subscribe_rule(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = DeleteAutoSubscribeRuleRequest::decode(value.as_ref())?;
+        let storage = MqttAutoSubscribeRuleStorage::new(self.rocksdb_engine_handler.clone());
+        storage.delete(&req.cluster_name, &req.username, &req.topic)?;
+        Ok(())
+    }
+
+    pub fn save_qos2_state(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = SaveQos2StateRequest::decode(value.as_ref())?;
+        let storage = MqttQos2StateStorage::new(self.rocksdb_engine_handler.clone());
+        let state = serde_json::from_slice::<MqttQos2State>(&req.qos2_state)?;
+        storage.save(&req.cluster_name, state)?;
+        Ok(())
+    }
+
+    pub fn delete_qos2_state(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = DeleteQos2StateRequest::decode(value.as_ref())?;
+        let storage = MqttQos2StateStorage::new(self.rocksdb_engine_handler.clone());
+        storage.delete(
+            &req.cluster_name,
+            &req.client_id,
+            &req.direction,
+            req.pkid as u16,
+        )?;
+        Ok(())
+    }
+
+    pub fn save_alarm(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = SaveAlarmRequest::decode(value.as_ref())?;
+        let storage = MqttAlarmStorage::new(self.rocksdb_engine_handler.clone());
+        let alarm = MqttAlarm::decode(&req.alarm)?;
+        storage.save(&req.cluster_name, alarm)?;
+        Ok(())
+    }
+
     pub fn set_nx_exclusive_topic(&self, value: Vec<u8>) -> Result<bool, PlacementCenterError> {
         let req = SetExclusiveTopicRequest::decode(value.as_ref())?;
         let storage = MqttTopicStorage::new(self.rocksdb_engine_handler.clone());
@@ -18,8 +18,8 @@ use common_base::error::common::CommonError;
 use prost::Message;
 use protocol::placement_center::placement_center_kv::kv_service_server::KvService;
 use protocol::placement_center::placement_center_kv::{
-    DeleteReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply, GetRequest, SetReply,
-    SetRequest,
+    DeleteReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply, GetRequest,
+    ListByPrefixReply, ListByPrefixRequest, SetReply, SetRequest,
 };
 use tonic::{Request, Response, Status};
 
@@ -136,4 +136,27 @@ impl KvService for GrpcKvService {
             }
         }
     }
+
+    async fn list_by_prefix(
+        &self,
+        request: Request<ListByPrefixRequest>,
+    ) -> Result<Response<ListByPrefixReply>, Status> {
+        let req = request.into_inner();
+
+        if req.prefix.is_empty() {
+            return Err(Status::cancelled(
+                CommonError::ParameterCannotBeNull("prefix".to_string()).to_string(),
+            ));
+        }
+
+        let kv_storage = KvStorage::new(self.rocksdb_engine_handler.clone());
+        match kv_storage.list_by_prefix(req.prefix) {
+            Ok(values) => {
+                return Ok(Response::new(ListByPrefixReply { values }));
+            }
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
 }
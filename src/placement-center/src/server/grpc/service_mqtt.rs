@@ -18,17 +18,25 @@ use common_base::utils::vec_util;
 use prost::Message;
 use protocol::placement_center::placement_center_mqtt::mqtt_service_server::MqttService;
 use protocol::placement_center::placement_center_mqtt::{
-    CreateAclReply, CreateAclRequest, CreateBlacklistReply, CreateBlacklistRequest,
-    CreateSessionReply, CreateSessionRequest, CreateTopicReply, CreateTopicRequest,
-    CreateUserReply, CreateUserRequest, DeleteAclReply, DeleteAclRequest, DeleteBlacklistReply,
-    DeleteBlacklistRequest, DeleteExclusiveTopicReply, DeleteExclusiveTopicRequest,
-    DeleteSessionReply, DeleteSessionRequest, DeleteTopicReply, DeleteTopicRequest,
+    CreateAclReply, CreateAclRequest, CreateAutoSubscribeRuleReply, CreateAutoSubscribeRuleRequest,
+    CreateBlacklistReply, CreateBlacklistRequest, CreateSessionReply, CreateSessionRequest,
+    CreateTopicReply, CreateTopicRequest, CreateTopicRewriteRuleReply,
+    CreateTopicRewriteRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclReply,
+    DeleteAclRequest, DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest,
+    DeleteBlacklistReply, DeleteBlacklistRequest, DeleteExclusiveTopicReply,
+    DeleteExclusiveTopicRequest, DeleteQos2StateReply, DeleteQos2StateRequest, DeleteSessionReply,
+    DeleteSessionRequest, DeleteSubscribeReply, DeleteSubscribeRequest, DeleteTopicReply,
+    DeleteTopicRequest, DeleteTopicRewriteRuleReply, DeleteTopicRewriteRuleRequest,
     DeleteUserReply, DeleteUserRequest, GetShareSubLeaderReply, GetShareSubLeaderRequest,
-    ListAclReply, ListAclRequest, ListBlacklistReply, ListBlacklistRequest, ListSessionReply,
-    ListSessionRequest, ListTopicReply, ListTopicRequest, ListUserReply, ListUserRequest,
-    SaveLastWillMessageReply, SaveLastWillMessageRequest, SetExclusiveTopicReply,
-    SetExclusiveTopicRequest, SetTopicRetainMessageReply, SetTopicRetainMessageRequest,
-    UpdateSessionReply, UpdateSessionRequest,
+    ListAclReply, ListAclRequest, ListAlarmReply, ListAlarmRequest, ListAutoSubscribeRuleReply,
+    ListAutoSubscribeRuleRequest, ListBlacklistReply, ListBlacklistRequest, ListQos2StateReply,
+    ListQos2StateRequest, ListSessionReply, ListSessionRequest, ListSubscribeReply,
+    ListSubscribeRequest, ListTopicReply, ListTopicRequest, ListTopicRewriteRuleReply,
+    ListTopicRewriteRuleRequest, ListUserReply, ListUserRequest, SaveAlarmReply, SaveAlarmRequest,
+    SaveLastWillMessageReply, SaveLastWillMessageRequest, SaveQos2StateReply, SaveQos2StateRequest,
+    SetExclusiveTopicReply, SetExclusiveTopicRequest, SetSubscribeReply, SetSubscribeRequest,
+    SetTopicRetainMessageReply, SetTopicRetainMessageRequest, UpdateSessionReply,
+    UpdateSessionRequest,
 };
 use tonic::{Request, Response, Status};
 
@@ -40,9 +48,14 @@ use crate::route::apply::RaftMachineApply;
 use crate::route::data::{StorageData, StorageDataType};
 use crate::server::grpc::validate::ValidateExt;
 use crate::storage::mqtt::acl::AclStorage;
+use crate::storage::mqtt::alarm::MqttAlarmStorage;
+use crate::storage::mqtt::auto_subscribe_rule::MqttAutoSubscribeRuleStorage;
 use crate::storage::mqtt::blacklist::MqttBlackListStorage;
+use crate::storage::mqtt::qos2_state::MqttQos2StateStorage;
 use crate::storage::mqtt::session::MqttSessionStorage;
+use crate::storage::mqtt::subscribe::MqttSubscribeStorage;
 use crate::storage::mqtt::topic::MqttTopicStorage;
+use crate::storage::mqtt::topic_rewrite_rule::MqttTopicRewriteRuleStorage;
 use crate::storage::mqtt::user::MqttUserStorage;
 use crate::storage::rocksdb::RocksDBEngine;
 
@@ -411,6 +424,254 @@ impl MqttService for GrpcMqttService {
         }
     }
 
+    async fn list_subscribe(
+        &self,
+        request: Request<ListSubscribeRequest>,
+    ) -> Result<Response<ListSubscribeReply>, Status> {
+        let req = request.into_inner();
+        let storage = MqttSubscribeStorage::new(self.rocksdb_engine_handler.clone());
+
+        match storage.list_by_client_id(&req.cluster_name, &req.client_id) {
+            Ok(data) => {
+                let subscribes = data.into_iter().map(|raw| raw.encode()).collect();
+                Ok(Response::new(ListSubscribeReply { subscribes }))
+            }
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn set_subscribe(
+        &self,
+        request: Request<SetSubscribeRequest>,
+    ) -> Result<Response<SetSubscribeReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::MqttSetSubscribe,
+            SetSubscribeRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => Ok(Response::new(SetSubscribeReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn delete_subscribe(
+        &self,
+        request: Request<DeleteSubscribeRequest>,
+    ) -> Result<Response<DeleteSubscribeReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::MqttDeleteSubscribe,
+            DeleteSubscribeRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => Ok(Response::new(DeleteSubscribeReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn list_topic_rewrite_rule(
+        &self,
+        request: Request<ListTopicRewriteRuleRequest>,
+    ) -> Result<Response<ListTopicRewriteRuleReply>, Status> {
+        let req = request.into_inner();
+        let storage = MqttTopicRewriteRuleStorage::new(self.rocksdb_engine_handler.clone());
+        match storage.list(&req.cluster_name) {
+            Ok(list) => {
+                let mut topic_rewrite_rules = Vec::new();
+                for rule in list {
+                    match rule.encode() {
+                        Ok(data) => topic_rewrite_rules.push(data),
+                        Err(e) => return Err(Status::cancelled(e.to_string())),
+                    }
+                }
+                Ok(Response::new(ListTopicRewriteRuleReply {
+                    topic_rewrite_rules,
+                }))
+            }
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
+
+    async fn create_topic_rewrite_rule(
+        &self,
+        request: Request<CreateTopicRewriteRuleRequest>,
+    ) -> Result<Response<CreateTopicRewriteRuleReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::MqttSetTopicRewriteRule,
+            CreateTopicRewriteRuleRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => Ok(Response::new(CreateTopicRewriteRuleReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn delete_topic_rewrite_rule(
+        &self,
+        request: Request<DeleteTopicRewriteRuleRequest>,
+    ) -> Result<Response<DeleteTopicRewriteRuleReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::MqttDeleteTopicRewriteRule,
+            DeleteTopicRewriteRuleRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => Ok(Response::new(DeleteTopicRewriteRuleReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn list_auto_subscribe_rule(
+        &self,
+        request: Request<ListAutoSubscribeRuleRequest>,
+    ) -> Result<Response<ListAutoSubscribeRuleReply>, Status> {
+        let req = request.into_inner();
+        let storage = MqttAutoSubscribeRuleStorage::new(self.rocksdb_engine_handler.clone());
+        match storage.list(&req.cluster_name) {
+            Ok(list) => {
+                let mut auto_subscribe_rules = Vec::new();
+                for rule in list {
+                    match rule.encode() {
+                        Ok(data) => auto_subscribe_rules.push(data),
+                        Err(e) => return Err(Status::cancelled(e.to_string())),
+                    }
+                }
+                Ok(Response::new(ListAutoSubscribeRuleReply {
+                    auto_subscribe_rules,
+                }))
+            }
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
+
+    async fn create_auto_subscribe_rule(
+        &self,
+        request: Request<CreateAutoSubscribeRuleRequest>,
+    ) -> Result<Response<CreateAutoSubscribeRuleReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::MqttSetAutoSubscribeRule,
+            CreateAutoSubscribeRuleRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => Ok(Response::new(CreateAutoSubscribeRuleReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn delete_auto_subscribe_rule(
+        &self,
+        request: Request<DeleteAutoSubscribeRuleRequest>,
+    ) -> Result<Response<DeleteAutoSubscribeRuleReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::MqttDeleteAutoSubscribeRule,
+            DeleteAutoSubscribeRuleRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => Ok(Response::new(DeleteAutoSubscribeRuleReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn list_qos2_state(
+        &self,
+        request: Request<ListQos2StateRequest>,
+    ) -> Result<Response<ListQos2StateReply>, Status> {
+        let req = request.into_inner();
+        let storage = MqttQos2StateStorage::new(self.rocksdb_engine_handler.clone());
+        match storage.list(&req.cluster_name, &req.client_id) {
+            Ok(list) => {
+                let mut qos2_states = Vec::new();
+                for state in list {
+                    match state.encode() {
+                        Ok(data) => qos2_states.push(data),
+                        Err(e) => return Err(Status::cancelled(e.to_string())),
+                    }
+                }
+                Ok(Response::new(ListQos2StateReply { qos2_states }))
+            }
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
+
+    async fn save_qos2_state(
+        &self,
+        request: Request<SaveQos2StateRequest>,
+    ) -> Result<Response<SaveQos2StateReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::MqttSaveQos2State,
+            SaveQos2StateRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => Ok(Response::new(SaveQos2StateReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn delete_qos2_state(
+        &self,
+        request: Request<DeleteQos2StateRequest>,
+    ) -> Result<Response<DeleteQos2StateReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::MqttDeleteQos2State,
+            DeleteQos2StateRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => Ok(Response::new(DeleteQos2StateReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
+    async fn list_alarm(
+        &self,
+        request: Request<ListAlarmRequest>,
+    ) -> Result<Response<ListAlarmReply>, Status> {
+        let req = request.into_inner();
+        let storage = MqttAlarmStorage::new(self.rocksdb_engine_handler.clone());
+        match storage.list(&req.cluster_name, req.only_active, req.limit as usize) {
+            Ok(list) => {
+                let mut alarms = Vec::new();
+                for alarm in list {
+                    match alarm.encode() {
+                        Ok(data) => alarms.push(data),
+                        Err(e) => return Err(Status::cancelled(e.to_string())),
+                    }
+                }
+                Ok(Response::new(ListAlarmReply { alarms }))
+            }
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
+
+    async fn save_alarm(
+        &self,
+        request: Request<SaveAlarmRequest>,
+    ) -> Result<Response<SaveAlarmReply>, Status> {
+        let req = request.into_inner();
+        let data = StorageData::new(
+            StorageDataType::MqttSaveAlarm,
+            SaveAlarmRequest::encode_to_vec(&req),
+        );
+
+        match self.raft_machine_apply.client_write(data).await {
+            Ok(_) => Ok(Response::new(SaveAlarmReply::default())),
+            Err(e) => Err(Status::cancelled(e.to_string())),
+        }
+    }
+
     async fn save_last_will_message(
         &self,
         request: Request<SaveLastWillMessageRequest>,
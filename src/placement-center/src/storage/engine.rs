@@ -16,8 +16,8 @@ use std::sync::Arc;
 
 use common_base::error::common::CommonError;
 use rocksdb_engine::engine::{
-    rocksdb_engine_delete, rocksdb_engine_exists, rocksdb_engine_get, rocksdb_engine_prefix_list,
-    rocksdb_engine_save,
+    rocksdb_engine_delete, rocksdb_engine_delete_range, rocksdb_engine_exists, rocksdb_engine_get,
+    rocksdb_engine_prefix_list, rocksdb_engine_prefix_list_rev, rocksdb_engine_save,
 };
 use rocksdb_engine::warp::StorageDataWrap;
 use serde::Serialize;
@@ -60,6 +60,19 @@ pub fn engine_delete_by_cluster(
 ) -> Result<(), CommonError> {
     rocksdb_engine_delete(rocksdb_engine_handler, DB_COLUMN_FAMILY_CLUSTER, key_name)
 }
+pub fn engine_delete_range_by_cluster(
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    from_key_name: String,
+    to_key_name: String,
+) -> Result<(), CommonError> {
+    rocksdb_engine_delete_range(
+        rocksdb_engine_handler,
+        DB_COLUMN_FAMILY_CLUSTER,
+        from_key_name,
+        to_key_name,
+    )
+}
+
 pub fn engine_prefix_list_by_cluster(
     rocksdb_engine_handler: Arc<RocksDBEngine>,
     prefix_key_name: String,
@@ -70,3 +83,17 @@ pub fn engine_prefix_list_by_cluster(
         prefix_key_name,
     )
 }
+
+// Newest keys first, at most limit entries when limit is greater than 0
+pub fn engine_prefix_list_rev_by_cluster(
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    prefix_key_name: String,
+    limit: usize,
+) -> Result<Vec<StorageDataWrap>, CommonError> {
+    rocksdb_engine_prefix_list_rev(
+        rocksdb_engine_handler,
+        DB_COLUMN_FAMILY_CLUSTER,
+        prefix_key_name,
+        limit,
+    )
+}
@@ -167,6 +167,90 @@ pub fn storage_key_mqtt_session_cluster_prefix(cluster_name: &str) -> String {
     format!("/mqtt/session/{}/", cluster_name)
 }
 
+pub fn storage_key_mqtt_subscribe(cluster_name: &str, client_id: &str, path: &str) -> String {
+    format!("/mqtt/subscribe/{}/{}/{}", cluster_name, client_id, path)
+}
+
+pub fn storage_key_mqtt_subscribe_client_prefix(cluster_name: &str, client_id: &str) -> String {
+    format!("/mqtt/subscribe/{}/{}/", cluster_name, client_id)
+}
+
+pub fn storage_key_mqtt_topic_rewrite_rule(
+    cluster_name: &str,
+    action: &str,
+    source_topic: &str,
+) -> String {
+    format!(
+        "/mqtt/topic_rewrite_rule/{}/{}/{}",
+        cluster_name, action, source_topic
+    )
+}
+
+pub fn storage_key_mqtt_topic_rewrite_rule_prefix(cluster_name: &str) -> String {
+    format!("/mqtt/topic_rewrite_rule/{}/", cluster_name)
+}
+
+pub fn storage_key_mqtt_auto_subscribe_rule(
+    cluster_name: &str,
+    username: &str,
+    topic: &str,
+) -> String {
+    format!(
+        "/mqtt/auto_subscribe_rule/{}/{}/{}",
+        cluster_name, username, topic
+    )
+}
+
+pub fn storage_key_mqtt_auto_subscribe_rule_prefix(cluster_name: &str) -> String {
+    format!("/mqtt/auto_subscribe_rule/{}/", cluster_name)
+}
+
+pub fn storage_key_mqtt_qos2_state(
+    cluster_name: &str,
+    client_id: &str,
+    direction: &str,
+    pkid: u16,
+) -> String {
+    format!(
+        "/mqtt/qos2_state/{}/{}/{}/{}",
+        cluster_name, client_id, direction, pkid
+    )
+}
+
+pub fn storage_key_mqtt_qos2_state_prefix(cluster_name: &str, client_id: &str) -> String {
+    format!("/mqtt/qos2_state/{}/{}/", cluster_name, client_id)
+}
+
+// Alarm history, ordered by activation time
+pub fn storage_key_mqtt_alarm(
+    cluster_name: &str,
+    name: &str,
+    broker_id: u64,
+    activate_time: u64,
+) -> String {
+    format!(
+        "/mqtt/alarm/{}/{:020}/{}/{}",
+        cluster_name, activate_time, broker_id, name
+    )
+}
+
+pub fn storage_key_mqtt_alarm_prefix(cluster_name: &str) -> String {
+    format!("/mqtt/alarm/{}/", cluster_name)
+}
+
+// History keys of the alarms activated before activate_time sort below it
+pub fn storage_key_mqtt_alarm_time_bound(cluster_name: &str, activate_time: u64) -> String {
+    format!("/mqtt/alarm/{}/{:020}", cluster_name, activate_time)
+}
+
+pub fn storage_key_mqtt_alarm_active(cluster_name: &str, name: &str, broker_id: u64) -> String {
+    format!("/mqtt/alarm_active/{}/{}/{}", cluster_name, broker_id, name)
+}
+
+pub fn storage_key_mqtt_alarm_active_prefix(cluster_name: &str) -> String {
+    format!("/mqtt/alarm_active/{}/", cluster_name)
+}
+
 pub fn storage_key_mqtt_last_will(cluster_name: &str, client_id: &str) -> String {
     format!("/mqtt/lastwill/{}/{}", cluster_name, client_id)
 }
@@ -0,0 +1,212 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use metadata_struct::mqtt::alarm::MqttAlarm;
+
+use crate::storage::engine::{
+    engine_delete_by_cluster, engine_delete_range_by_cluster, engine_prefix_list_by_cluster,
+    engine_prefix_list_rev_by_cluster, engine_save_by_cluster,
+};
+use crate::storage::keys::{
+    storage_key_mqtt_alarm, storage_key_mqtt_alarm_active, storage_key_mqtt_alarm_active_prefix,
+    storage_key_mqtt_alarm_prefix, storage_key_mqtt_alarm_time_bound,
+};
+use crate::storage::rocksdb::RocksDBEngine;
+
+// Alarms activated longer ago than this are dropped from the history
+pub const MQTT_ALARM_HISTORY_RETENTION_SEC: u64 = 7 * 24 * 3600;
+
+pub struct MqttAlarmStorage {
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+}
+
+impl MqttAlarmStorage {
+    pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
+        MqttAlarmStorage {
+            rocksdb_engine_handler,
+        }
+    }
+
+    // An activation and its later deactivation share the same history key, so saving the
+    // deactivated alarm closes the history entry instead of adding a new one. Active
+    // alarms are also kept under a key of their own, so listing them does not scan the
+    // history.
+    //
+    // Runs in the apply of the Raft log, so the history cutoff is taken from the saved
+    // alarm instead of the local clock and every replica drops the same entries.
+    pub fn save(&self, cluster_name: &str, alarm: MqttAlarm) -> Result<(), CommonError> {
+        let key = storage_key_mqtt_alarm(
+            cluster_name,
+            &alarm.name,
+            alarm.broker_id,
+            alarm.activate_time,
+        );
+        let active_key = storage_key_mqtt_alarm_active(cluster_name, &alarm.name, alarm.broker_id);
+        let alarm_activate_time = alarm.activate_time;
+        if alarm.is_active() {
+            engine_save_by_cluster(self.rocksdb_engine_handler.clone(), key, alarm.clone())?;
+            engine_save_by_cluster(self.rocksdb_engine_handler.clone(), active_key, alarm)?;
+            let cutoff = alarm_activate_time.saturating_sub(MQTT_ALARM_HISTORY_RETENTION_SEC);
+            return self.expire_history(cluster_name, cutoff);
+        }
+        engine_save_by_cluster(self.rocksdb_engine_handler.clone(), key, alarm)?;
+        engine_delete_by_cluster(self.rocksdb_engine_handler.clone(), active_key)
+    }
+
+    // History keys are ordered by activation time, the entries activated before the
+    // cutoff are a single range
+    fn expire_history(&self, cluster_name: &str, cutoff: u64) -> Result<(), CommonError> {
+        if cutoff == 0 {
+            return Ok(());
+        }
+        engine_delete_range_by_cluster(
+            self.rocksdb_engine_handler.clone(),
+            storage_key_mqtt_alarm_prefix(cluster_name),
+            storage_key_mqtt_alarm_time_bound(cluster_name, cutoff),
+        )
+    }
+
+    pub fn list(
+        &self,
+        cluster_name: &str,
+        only_active: bool,
+        limit: usize,
+    ) -> Result<Vec<MqttAlarm>, CommonError> {
+        if !only_active {
+            // Newest activations first, read from the end of the history
+            let prefix_key = storage_key_mqtt_alarm_prefix(cluster_name);
+            let data = engine_prefix_list_rev_by_cluster(
+                self.rocksdb_engine_handler.clone(),
+                prefix_key,
+                limit,
+            )?;
+            let mut results = Vec::new();
+            for raw in data {
+                results.push(serde_json::from_slice::<MqttAlarm>(&raw.data)?);
+            }
+            return Ok(results);
+        }
+
+        let prefix_key = storage_key_mqtt_alarm_active_prefix(cluster_name);
+        let data = engine_prefix_list_by_cluster(self.rocksdb_engine_handler.clone(), prefix_key)?;
+        let mut results = Vec::new();
+        for raw in data {
+            results.push(serde_json::from_slice::<MqttAlarm>(&raw.data)?);
+        }
+        results.sort_by(|a, b| b.activate_time.cmp(&a.activate_time));
+        if limit > 0 {
+            results.truncate(limit);
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs::remove_dir_all;
+    use std::sync::Arc;
+
+    use common_base::config::placement_center::placement_center_test_conf;
+    use common_base::tools::now_second;
+    use metadata_struct::mqtt::alarm::MqttAlarm;
+
+    use crate::storage::mqtt::alarm::{MqttAlarmStorage, MQTT_ALARM_HISTORY_RETENTION_SEC};
+    use crate::storage::rocksdb::{column_family_list, RocksDBEngine};
+
+    #[tokio::test]
+    async fn alarm_storage_test() {
+        let config = placement_center_test_conf();
+        let rs = Arc::new(RocksDBEngine::new(
+            &config.rocksdb.data_path,
+            config.rocksdb.max_open_files.unwrap(),
+            column_family_list(),
+        ));
+        let storage = MqttAlarmStorage::new(rs);
+        let cluster_name = "test_cluster".to_string();
+
+        let mut memory = MqttAlarm::new(
+            "high_memory".to_string(),
+            1,
+            "memory usage is high".to_string(),
+            HashMap::new(),
+        );
+        memory.activate_time = now_second() - 200;
+        let mut cpu = MqttAlarm::new(
+            "high_cpu".to_string(),
+            1,
+            "cpu usage is high".to_string(),
+            HashMap::new(),
+        );
+        cpu.activate_time = now_second() - 100;
+        storage.save(&cluster_name, memory.clone()).unwrap();
+        storage.save(&cluster_name, cpu.clone()).unwrap();
+
+        let res = storage.list(&cluster_name, false, 0).unwrap();
+        assert_eq!(res, vec![cpu.clone(), memory.clone()]);
+        assert_eq!(storage.list(&cluster_name, false, 1).unwrap(), vec![cpu]);
+
+        memory.deactivate("memory usage is back to normal".to_string(), HashMap::new());
+        storage.save(&cluster_name, memory.clone()).unwrap();
+        let res = storage.list(&cluster_name, false, 0).unwrap();
+        assert_eq!(res.len(), 2);
+        assert!(res.contains(&memory));
+
+        let res = storage.list(&cluster_name, true, 0).unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].name, "high_cpu");
+
+        // A new activation drops the history beyond the retention
+        let mut old = MqttAlarm::new(
+            "high_memory".to_string(),
+            2,
+            "memory usage is high".to_string(),
+            HashMap::new(),
+        );
+        old.activate_time = now_second() - MQTT_ALARM_HISTORY_RETENTION_SEC - 10;
+        old.deactivate("memory usage is back to normal".to_string(), HashMap::new());
+        storage.save(&cluster_name, old.clone()).unwrap();
+        assert_eq!(storage.list(&cluster_name, false, 0).unwrap().len(), 3);
+
+        let connection = MqttAlarm::new(
+            "connection_high".to_string(),
+            1,
+            "connections are high".to_string(),
+            HashMap::new(),
+        );
+        storage.save(&cluster_name, connection.clone()).unwrap();
+        let res = storage.list(&cluster_name, false, 0).unwrap();
+        assert_eq!(res.len(), 3);
+        assert!(!res.contains(&old));
+        assert_eq!(res[0], connection);
+        assert_eq!(storage.list(&cluster_name, true, 0).unwrap().len(), 2);
+
+        // The cutoff follows the activation time of the saved alarm, not the clock of
+        // the replica applying it, so an alarm replayed later expires nothing newer
+        let mut replayed = MqttAlarm::new(
+            "high_cpu".to_string(),
+            2,
+            "cpu usage is high".to_string(),
+            HashMap::new(),
+        );
+        replayed.activate_time = old.activate_time + 1;
+        storage.save(&cluster_name, replayed.clone()).unwrap();
+        assert_eq!(storage.list(&cluster_name, false, 0).unwrap().len(), 4);
+
+        remove_dir_all(config.rocksdb.data_path).unwrap();
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
+
+use crate::storage::engine::{
+    engine_delete_by_cluster, engine_prefix_list_by_cluster, engine_save_by_cluster,
+};
+use crate::storage::keys::{
+    storage_key_mqtt_auto_subscribe_rule, storage_key_mqtt_auto_subscribe_rule_prefix,
+};
+use crate::storage::rocksdb::RocksDBEngine;
+
+pub struct MqttAutoSubscribeRuleStorage {
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+}
+
+impl MqttAutoSubscribeRuleStorage {
+    pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
+        MqttAutoSubscribeRuleStorage {
+            rocksdb_engine_handler,
+        }
+    }
+
+    pub fn save(&self, cluster_name: &str, rule: MqttAutoSubscribeRule) -> Result<(), CommonError> {
+        let key = storage_key_mqtt_auto_subscribe_rule(cluster_name, &rule.username, &rule.topic);
+        engine_save_by_cluster(self.rocksdb_engine_handler.clone(), key, rule)
+    }
+
+    pub fn list(&self, cluster_name: &str) -> Result<Vec<MqttAutoSubscribeRule>, CommonError> {
+        let prefix_key = storage_key_mqtt_auto_subscribe_rule_prefix(cluster_name);
+        let data = engine_prefix_list_by_cluster(self.rocksdb_engine_handler.clone(), prefix_key)?;
+        let mut results = Vec::new();
+        for raw in data {
+            results.push(serde_json::from_slice::<MqttAutoSubscribeRule>(&raw.data)?);
+        }
+        Ok(results)
+    }
+
+    pub fn delete(
+        &self,
+        cluster_name: &str,
+        username: &str,
+        topic: &str,
+    ) -> Result<(), CommonError> {
+        let key = storage_key_mqtt_auto_subscribe_rule(cluster_name, username, topic);
+        engine_delete_by_cluster(self.rocksdb_engine_handler.clone(), key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+    use std::sync::Arc;
+
+    use common_base::config::placement_center::placement_center_test_conf;
+    use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
+    use protocol::mqtt::common::{QoS, RetainForwardRule};
+
+    use crate::storage::mqtt::auto_subscribe_rule::MqttAutoSubscribeRuleStorage;
+    use crate::storage::rocksdb::{column_family_list, RocksDBEngine};
+
+    #[tokio::test]
+    async fn auto_subscribe_rule_storage_test() {
+        let config = placement_center_test_conf();
+        let rs = Arc::new(RocksDBEngine::new(
+            &config.rocksdb.data_path,
+            config.rocksdb.max_open_files.unwrap(),
+            column_family_list(),
+        ));
+        let storage = MqttAutoSubscribeRuleStorage::new(rs);
+        let cluster_name = "test_cluster".to_string();
+
+        let rule = MqttAutoSubscribeRule::new(
+            "devices/${clientid}/cmd".to_string(),
+            QoS::AtLeastOnce,
+            false,
+            false,
+            RetainForwardRule::OnEverySubscribe,
+            "".to_string(),
+        );
+        storage.save(&cluster_name, rule.clone()).unwrap();
+        storage
+            .save(
+                &cluster_name,
+                MqttAutoSubscribeRule::new(
+                    "users/${username}/notice".to_string(),
+                    QoS::AtMostOnce,
+                    true,
+                    false,
+                    RetainForwardRule::Never,
+                    "loboxu".to_string(),
+                ),
+            )
+            .unwrap();
+
+        let res = storage.list(&cluster_name).unwrap();
+        assert_eq!(res.len(), 2);
+        assert!(res.contains(&rule));
+
+        storage
+            .delete(&cluster_name, "", "devices/${clientid}/cmd")
+            .unwrap();
+        let res = storage.list(&cluster_name).unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].username, "loboxu");
+
+        remove_dir_all(config.rocksdb.data_path).unwrap();
+    }
+}
@@ -25,8 +25,13 @@
 // limitations under the License.
 
 pub mod acl;
+pub mod alarm;
+pub mod auto_subscribe_rule;
 pub mod blacklist;
 pub mod lastwill;
+pub mod qos2_state;
 pub mod session;
+pub mod subscribe;
 pub mod topic;
+pub mod topic_rewrite_rule;
 pub mod user;
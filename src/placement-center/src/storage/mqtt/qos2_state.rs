@@ -0,0 +1,152 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use metadata_struct::mqtt::qos2_state::MqttQos2State;
+
+use crate::storage::engine::{
+    engine_delete_by_cluster, engine_prefix_list_by_cluster, engine_save_by_cluster,
+};
+use crate::storage::keys::{storage_key_mqtt_qos2_state, storage_key_mqtt_qos2_state_prefix};
+use crate::storage::rocksdb::RocksDBEngine;
+
+pub struct MqttQos2StateStorage {
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+}
+
+impl MqttQos2StateStorage {
+    pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
+        MqttQos2StateStorage {
+            rocksdb_engine_handler,
+        }
+    }
+
+    pub fn save(&self, cluster_name: &str, state: MqttQos2State) -> Result<(), CommonError> {
+        let key = storage_key_mqtt_qos2_state(
+            cluster_name,
+            &state.client_id,
+            state.direction(),
+            state.pkid,
+        );
+        engine_save_by_cluster(self.rocksdb_engine_handler.clone(), key, state)
+    }
+
+    pub fn list(
+        &self,
+        cluster_name: &str,
+        client_id: &str,
+    ) -> Result<Vec<MqttQos2State>, CommonError> {
+        let prefix_key = storage_key_mqtt_qos2_state_prefix(cluster_name, client_id);
+        let data = engine_prefix_list_by_cluster(self.rocksdb_engine_handler.clone(), prefix_key)?;
+        let mut results = Vec::new();
+        for raw in data {
+            let state = serde_json::from_slice::<MqttQos2State>(&raw.data)?;
+            // The prefix of client "a" also covers the keys of client "a/b"
+            if state.client_id == client_id {
+                results.push(state);
+            }
+        }
+        Ok(results)
+    }
+
+    pub fn delete(
+        &self,
+        cluster_name: &str,
+        client_id: &str,
+        direction: &str,
+        pkid: u16,
+    ) -> Result<(), CommonError> {
+        let key = storage_key_mqtt_qos2_state(cluster_name, client_id, direction, pkid);
+        engine_delete_by_cluster(self.rocksdb_engine_handler.clone(), key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+    use std::sync::Arc;
+
+    use common_base::config::placement_center::placement_center_test_conf;
+    use metadata_struct::mqtt::qos2_state::{
+        MqttQos2State, Qos2Stage, QOS2_STATE_DIRECTION_RECEIVE, QOS2_STATE_DIRECTION_SEND,
+    };
+
+    use crate::storage::mqtt::qos2_state::MqttQos2StateStorage;
+    use crate::storage::rocksdb::{column_family_list, RocksDBEngine};
+
+    #[tokio::test]
+    async fn qos2_state_storage_test() {
+        let config = placement_center_test_conf();
+        let rs = Arc::new(RocksDBEngine::new(
+            &config.rocksdb.data_path,
+            config.rocksdb.max_open_files.unwrap(),
+            column_family_list(),
+        ));
+        let storage = MqttQos2StateStorage::new(rs);
+        let cluster_name = "test_cluster".to_string();
+
+        // The same packet identifier is used in both directions
+        let receive = MqttQos2State::new_receive("c1".to_string(), 1);
+        let send = MqttQos2State::new_send(
+            "c1".to_string(),
+            1,
+            Qos2Stage::WaitPubRec,
+            "g1".to_string(),
+            10,
+        );
+        storage.save(&cluster_name, receive.clone()).unwrap();
+        storage.save(&cluster_name, send.clone()).unwrap();
+        storage
+            .save(
+                &cluster_name,
+                MqttQos2State::new_receive("c12".to_string(), 1),
+            )
+            .unwrap();
+        storage
+            .save(
+                &cluster_name,
+                MqttQos2State::new_receive("c1/x".to_string(), 2),
+            )
+            .unwrap();
+
+        let res = storage.list(&cluster_name, "c1").unwrap();
+        assert_eq!(res.len(), 2);
+        assert!(res.contains(&receive));
+        assert!(res.contains(&send));
+
+        let mut send = send;
+        send.stage = Qos2Stage::WaitPubComp;
+        storage.save(&cluster_name, send.clone()).unwrap();
+        let res = storage.list(&cluster_name, "c1").unwrap();
+        assert_eq!(res.len(), 2);
+        assert!(res.contains(&send));
+
+        storage
+            .delete(&cluster_name, "c1", QOS2_STATE_DIRECTION_RECEIVE, 1)
+            .unwrap();
+        let res = storage.list(&cluster_name, "c1").unwrap();
+        assert_eq!(res, vec![send]);
+
+        storage
+            .delete(&cluster_name, "c1", QOS2_STATE_DIRECTION_SEND, 1)
+            .unwrap();
+        assert!(storage.list(&cluster_name, "c1").unwrap().is_empty());
+        assert_eq!(storage.list(&cluster_name, "c12").unwrap().len(), 1);
+        assert_eq!(storage.list(&cluster_name, "c1/x").unwrap().len(), 1);
+
+        remove_dir_all(config.rocksdb.data_path).unwrap();
+    }
+}
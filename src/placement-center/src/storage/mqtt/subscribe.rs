@@ -0,0 +1,168 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+
+use crate::storage::engine::{
+    engine_delete_by_cluster, engine_prefix_list_by_cluster, engine_save_by_cluster,
+};
+use crate::storage::keys::{storage_key_mqtt_subscribe, storage_key_mqtt_subscribe_client_prefix};
+use crate::storage::rocksdb::RocksDBEngine;
+
+pub struct MqttSubscribeStorage {
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+}
+
+impl MqttSubscribeStorage {
+    pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
+        MqttSubscribeStorage {
+            rocksdb_engine_handler,
+        }
+    }
+
+    pub fn save(
+        &self,
+        cluster_name: &str,
+        client_id: &str,
+        path: &str,
+        subscribe: MqttSubscribe,
+    ) -> Result<(), CommonError> {
+        let key = storage_key_mqtt_subscribe(cluster_name, client_id, path);
+        engine_save_by_cluster(self.rocksdb_engine_handler.clone(), key, subscribe)
+    }
+
+    pub fn list_by_client_id(
+        &self,
+        cluster_name: &str,
+        client_id: &str,
+    ) -> Result<Vec<MqttSubscribe>, CommonError> {
+        let prefix_key = storage_key_mqtt_subscribe_client_prefix(cluster_name, client_id);
+        let data = engine_prefix_list_by_cluster(self.rocksdb_engine_handler.clone(), prefix_key)?;
+        let mut results = Vec::new();
+        for raw in data {
+            let subscribe = serde_json::from_slice::<MqttSubscribe>(&raw.data)?;
+            // The prefix of client "a" also covers the keys of client "a/b"
+            if subscribe.client_id == client_id {
+                results.push(subscribe);
+            }
+        }
+        Ok(results)
+    }
+
+    pub fn delete(
+        &self,
+        cluster_name: &str,
+        client_id: &str,
+        path: &str,
+    ) -> Result<(), CommonError> {
+        let key = storage_key_mqtt_subscribe(cluster_name, client_id, path);
+        engine_delete_by_cluster(self.rocksdb_engine_handler.clone(), key)
+    }
+
+    pub fn delete_by_client_id(
+        &self,
+        cluster_name: &str,
+        client_id: &str,
+    ) -> Result<(), CommonError> {
+        for subscribe in self.list_by_client_id(cluster_name, client_id)? {
+            self.delete(cluster_name, client_id, &subscribe.path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+    use std::sync::Arc;
+
+    use common_base::config::placement_center::placement_center_test_conf;
+    use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+    use protocol::mqtt::common::{Filter, MqttProtocol, QoS, RetainForwardRule};
+
+    use crate::storage::mqtt::subscribe::MqttSubscribeStorage;
+    use crate::storage::rocksdb::{column_family_list, RocksDBEngine};
+
+    fn build_subscribe(client_id: &str, path: &str) -> MqttSubscribe {
+        let filter = Filter {
+            path: path.to_string(),
+            qos: QoS::AtLeastOnce,
+            nolocal: false,
+            preserve_retain: false,
+            retain_forward_rule: RetainForwardRule::OnEverySubscribe,
+        };
+        MqttSubscribe::new(
+            client_id.to_string(),
+            1,
+            MqttProtocol::Mqtt5,
+            filter,
+            1,
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn subscribe_storage_test() {
+        let config = placement_center_test_conf();
+        let rs = Arc::new(RocksDBEngine::new(
+            &config.rocksdb.data_path,
+            config.rocksdb.max_open_files.unwrap(),
+            column_family_list(),
+        ));
+        let storage = MqttSubscribeStorage::new(rs);
+        let cluster_name = "test_cluster".to_string();
+
+        for path in ["/sensor/+/temp", "/sensor/#"] {
+            storage
+                .save(&cluster_name, "c1", path, build_subscribe("c1", path))
+                .unwrap();
+        }
+        storage
+            .save(&cluster_name, "c2", "/a", build_subscribe("c2", "/a"))
+            .unwrap();
+        storage
+            .save(&cluster_name, "c1/x", "/b", build_subscribe("c1/x", "/b"))
+            .unwrap();
+
+        let res = storage.list_by_client_id(&cluster_name, "c1").unwrap();
+        assert_eq!(res.len(), 2);
+        assert!(res.iter().any(|sub| sub.path == "/sensor/#"));
+
+        storage.delete(&cluster_name, "c1", "/sensor/#").unwrap();
+        let res = storage.list_by_client_id(&cluster_name, "c1").unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].filter.path, "/sensor/+/temp");
+
+        storage.delete_by_client_id(&cluster_name, "c1").unwrap();
+        assert!(storage
+            .list_by_client_id(&cluster_name, "c1")
+            .unwrap()
+            .is_empty());
+        let res = storage.list_by_client_id(&cluster_name, "c1/x").unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].path, "/b");
+        assert_eq!(
+            storage
+                .list_by_client_id(&cluster_name, "c2")
+                .unwrap()
+                .len(),
+            1
+        );
+
+        remove_dir_all(config.rocksdb.data_path).unwrap();
+    }
+}
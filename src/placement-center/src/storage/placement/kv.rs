@@ -18,7 +18,7 @@ use common_base::error::common::CommonError;
 
 use crate::storage::engine::{
     engine_delete_by_cluster, engine_exists_by_cluster, engine_get_by_cluster,
-    engine_save_by_cluster,
+    engine_prefix_list_by_cluster, engine_save_by_cluster,
 };
 use crate::storage::rocksdb::RocksDBEngine;
 
@@ -52,4 +52,14 @@ impl KvStorage {
     pub fn exists(&self, key: String) -> Result<bool, CommonError> {
         engine_exists_by_cluster(self.rocksdb_engine_handler.clone(), key)
     }
+
+    // Values of every kv entry under the prefix, in key order. Entries of the same column
+    // family that were not written through the kv service are skipped.
+    pub fn list_by_prefix(&self, prefix: String) -> Result<Vec<String>, CommonError> {
+        let data = engine_prefix_list_by_cluster(self.rocksdb_engine_handler.clone(), prefix)?;
+        Ok(data
+            .into_iter()
+            .filter_map(|raw| serde_json::from_slice::<String>(&raw.data).ok())
+            .collect())
+    }
 }
@@ -11,3 +11,490 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec;
+
+use super::common::{
+    read_protocol_header, AmqpFrame, ContentHeader, Error, FRAME_BODY, FRAME_DEFAULT_MAX_SIZE,
+    FRAME_END, FRAME_HEADER, FRAME_HEADER_LEN, FRAME_HEARTBEAT, FRAME_METHOD, PROTOCOL_HEADER,
+    PROTOCOL_HEADER_LEN,
+};
+use super::method::AmqpMethod;
+
+#[derive(Clone, Debug)]
+pub struct AmqpCodec {
+    /// Largest frame accepted or produced, including the frame header and frame end.
+    /// Zero means no limit.
+    pub frame_max: u32,
+}
+
+impl Default for AmqpCodec {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl AmqpCodec {
+    pub fn new(frame_max: Option<u32>) -> AmqpCodec {
+        AmqpCodec {
+            frame_max: frame_max.unwrap_or(FRAME_DEFAULT_MAX_SIZE),
+        }
+    }
+
+    /// Called once connection.tune-ok has been negotiated
+    pub fn set_frame_max(&mut self, frame_max: u32) {
+        self.frame_max = frame_max;
+    }
+
+    fn check_frame_size(&self, frame_len: usize) -> Result<(), Error> {
+        if self.frame_max > 0 && frame_len > self.frame_max as usize {
+            return Err(Error::FrameTooLarge(frame_len, self.frame_max));
+        }
+        Ok(())
+    }
+
+    pub fn decode_data(&mut self, stream: &mut BytesMut) -> Result<Option<AmqpFrame>, Error> {
+        if stream.is_empty() {
+            return Ok(None);
+        }
+
+        // Frame types are 1, 2, 3 and 8, so a leading 'A' can only be the protocol header
+        if stream[0] == b'A' {
+            if stream.len() < PROTOCOL_HEADER_LEN {
+                return Ok(None);
+            }
+            let header = stream.split_to(PROTOCOL_HEADER_LEN);
+            return Ok(Some(AmqpFrame::ProtocolHeader(read_protocol_header(
+                &header,
+            )?)));
+        }
+
+        if stream.len() < FRAME_HEADER_LEN {
+            return Ok(None);
+        }
+
+        let frame_type = stream[0];
+        let channel = u16::from_be_bytes([stream[1], stream[2]]);
+        let size = u32::from_be_bytes([stream[3], stream[4], stream[5], stream[6]]) as usize;
+        let frame_len = FRAME_HEADER_LEN + size + 1;
+        self.check_frame_size(frame_len)?;
+
+        if stream.len() < frame_len {
+            stream.reserve(frame_len - stream.len());
+            return Ok(None);
+        }
+
+        let mut frame = stream.split_to(frame_len);
+        frame.advance(FRAME_HEADER_LEN);
+        let frame_end = frame[size];
+        if frame_end != FRAME_END {
+            return Err(Error::InvalidFrameEnd(frame_end));
+        }
+        frame.truncate(size);
+        let payload = frame.freeze();
+
+        let frame = match frame_type {
+            FRAME_METHOD => AmqpFrame::Method(channel, AmqpMethod::read(payload)?),
+            FRAME_HEADER => AmqpFrame::Header(channel, ContentHeader::read(payload)?),
+            FRAME_BODY => AmqpFrame::Body(channel, payload),
+            FRAME_HEARTBEAT => {
+                if channel != 0 {
+                    return Err(Error::InvalidHeartbeatChannel(channel));
+                }
+                AmqpFrame::Heartbeat
+            }
+            _ => return Err(Error::InvalidFrameType(frame_type)),
+        };
+        Ok(Some(frame))
+    }
+
+    pub fn encode_data(&mut self, frame: AmqpFrame, buffer: &mut BytesMut) -> Result<(), Error> {
+        let mut payload = BytesMut::new();
+        let (frame_type, channel) = match &frame {
+            AmqpFrame::ProtocolHeader(version) => {
+                buffer.put_slice(&PROTOCOL_HEADER[0..5]);
+                buffer.put_u8(version.major);
+                buffer.put_u8(version.minor);
+                buffer.put_u8(version.revision);
+                return Ok(());
+            }
+            AmqpFrame::Method(channel, method) => {
+                method.write(&mut payload)?;
+                (FRAME_METHOD, *channel)
+            }
+            AmqpFrame::Header(channel, header) => {
+                header.write(&mut payload)?;
+                (FRAME_HEADER, *channel)
+            }
+            AmqpFrame::Body(channel, body) => {
+                payload.put_slice(body);
+                (FRAME_BODY, *channel)
+            }
+            AmqpFrame::Heartbeat => (FRAME_HEARTBEAT, 0),
+        };
+
+        self.check_frame_size(FRAME_HEADER_LEN + payload.len() + 1)?;
+        buffer.reserve(FRAME_HEADER_LEN + payload.len() + 1);
+        buffer.put_u8(frame_type);
+        buffer.put_u16(channel);
+        buffer.put_u32(payload.len() as u32);
+        buffer.put_slice(&payload);
+        buffer.put_u8(FRAME_END);
+        Ok(())
+    }
+}
+
+impl codec::Encoder<AmqpFrame> for AmqpCodec {
+    type Error = Error;
+    fn encode(&mut self, frame: AmqpFrame, buffer: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode_data(frame, buffer)
+    }
+}
+
+impl codec::Decoder for AmqpCodec {
+    type Item = AmqpFrame;
+    type Error = Error;
+    fn decode(&mut self, stream: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.decode_data(stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use super::AmqpCodec;
+    use crate::amqp::common::{
+        AmqpFrame, BasicProperties, ContentHeader, Error, ProtocolVersion, FRAME_END,
+    };
+    use crate::amqp::field::{FieldTable, FieldValue};
+    use crate::amqp::method::basic::{
+        BasicAck, BasicConsume, BasicDeliver, BasicNack, BasicPublish, BasicQos,
+    };
+    use crate::amqp::method::channel::ChannelClose;
+    use crate::amqp::method::connection::{
+        ConnectionClose, ConnectionOpen, ConnectionStart, ConnectionStartOk, ConnectionTune,
+    };
+    use crate::amqp::method::exchange::{ExchangeBind, ExchangeDeclare, ExchangeDelete};
+    use crate::amqp::method::queue::{QueueBind, QueueDeclare, QueueDeclareOk, QueueDelete};
+    use crate::amqp::method::{
+        AmqpMethod, BasicMethod, ChannelMethod, ConfirmMethod, ConnectionMethod, ExchangeMethod,
+        QueueMethod, TxMethod,
+    };
+
+    fn round_trip(frame: AmqpFrame) {
+        let mut codec = AmqpCodec::new(None);
+        let mut buffer = BytesMut::new();
+        codec.encode(frame.clone(), &mut buffer).unwrap();
+        let frame_return = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(frame_return, frame);
+        assert!(buffer.is_empty());
+    }
+
+    fn method(channel: u16, method: AmqpMethod) {
+        round_trip(AmqpFrame::Method(channel, method));
+    }
+
+    #[test]
+    fn test_protocol_header() {
+        let mut codec = AmqpCodec::default();
+        let mut buffer = BytesMut::from(&b"AMQP\x00\x00\x09"[..]);
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+
+        buffer.extend_from_slice(&[1]);
+        let frame = codec.decode(&mut buffer).unwrap().unwrap();
+        match frame {
+            AmqpFrame::ProtocolHeader(version) => assert!(version.is_supported()),
+            _ => panic!("unexpected frame {:?}", frame),
+        }
+
+        round_trip(AmqpFrame::ProtocolHeader(ProtocolVersion::amqp_0_9_1()));
+
+        let mut buffer = BytesMut::from(&b"AMQX\x00\x00\x09\x01"[..]);
+        assert!(codec.decode(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn test_connection_methods() {
+        let mut server_properties = FieldTable::new();
+        server_properties.insert("product".to_string(), FieldValue::from("RobustMQ"));
+        method(
+            0,
+            AmqpMethod::Connection(ConnectionMethod::Start(ConnectionStart {
+                version_major: 0,
+                version_minor: 9,
+                server_properties,
+                mechanisms: "PLAIN AMQPLAIN".to_string(),
+                locales: "en_US".to_string(),
+            })),
+        );
+        method(
+            0,
+            AmqpMethod::Connection(ConnectionMethod::StartOk(ConnectionStartOk {
+                client_properties: FieldTable::new(),
+                mechanism: "PLAIN".to_string(),
+                response: Bytes::from_static(b"\x00guest\x00guest"),
+                locale: "en_US".to_string(),
+            })),
+        );
+        let tune = ConnectionTune {
+            channel_max: 2047,
+            frame_max: 131072,
+            heartbeat: 60,
+        };
+        method(
+            0,
+            AmqpMethod::Connection(ConnectionMethod::Tune(tune.clone())),
+        );
+        method(0, AmqpMethod::Connection(ConnectionMethod::TuneOk(tune)));
+        method(
+            0,
+            AmqpMethod::Connection(ConnectionMethod::Open(ConnectionOpen {
+                virtual_host: "/".to_string(),
+            })),
+        );
+        method(0, AmqpMethod::Connection(ConnectionMethod::OpenOk));
+        method(
+            0,
+            AmqpMethod::Connection(ConnectionMethod::Close(ConnectionClose {
+                reply_code: 320,
+                reply_text: "CONNECTION_FORCED".to_string(),
+                class_id: 0,
+                method_id: 0,
+            })),
+        );
+        method(0, AmqpMethod::Connection(ConnectionMethod::CloseOk));
+    }
+
+    #[test]
+    fn test_channel_methods() {
+        method(1, AmqpMethod::Channel(ChannelMethod::Open));
+        method(1, AmqpMethod::Channel(ChannelMethod::OpenOk));
+        method(
+            1,
+            AmqpMethod::Channel(ChannelMethod::Close(ChannelClose {
+                reply_code: 404,
+                reply_text: "NOT_FOUND".to_string(),
+                class_id: 50,
+                method_id: 10,
+            })),
+        );
+        method(1, AmqpMethod::Channel(ChannelMethod::CloseOk));
+    }
+
+    #[test]
+    fn test_exchange_and_queue_methods() {
+        method(
+            1,
+            AmqpMethod::Exchange(ExchangeMethod::Declare(ExchangeDeclare {
+                exchange: "logs".to_string(),
+                kind: "topic".to_string(),
+                durable: true,
+                no_wait: true,
+                ..Default::default()
+            })),
+        );
+        method(
+            1,
+            AmqpMethod::Exchange(ExchangeMethod::Delete(ExchangeDelete {
+                exchange: "logs".to_string(),
+                if_unused: true,
+                no_wait: false,
+            })),
+        );
+        method(
+            1,
+            AmqpMethod::Exchange(ExchangeMethod::Bind(ExchangeBind {
+                destination: "d".to_string(),
+                source: "s".to_string(),
+                routing_key: "a.*".to_string(),
+                ..Default::default()
+            })),
+        );
+        method(1, AmqpMethod::Exchange(ExchangeMethod::UnbindOk));
+
+        let mut arguments = FieldTable::new();
+        arguments.insert("x-max-length".to_string(), FieldValue::LongInt(1000));
+        method(
+            1,
+            AmqpMethod::Queue(QueueMethod::Declare(QueueDeclare {
+                queue: "q1".to_string(),
+                durable: true,
+                exclusive: false,
+                auto_delete: true,
+                arguments,
+                ..Default::default()
+            })),
+        );
+        method(
+            1,
+            AmqpMethod::Queue(QueueMethod::DeclareOk(QueueDeclareOk {
+                queue: "q1".to_string(),
+                message_count: 10,
+                consumer_count: 1,
+            })),
+        );
+        method(
+            1,
+            AmqpMethod::Queue(QueueMethod::Bind(QueueBind {
+                queue: "q1".to_string(),
+                exchange: "logs".to_string(),
+                routing_key: "#".to_string(),
+                ..Default::default()
+            })),
+        );
+        method(
+            1,
+            AmqpMethod::Queue(QueueMethod::Delete(QueueDelete {
+                queue: "q1".to_string(),
+                if_empty: true,
+                ..Default::default()
+            })),
+        );
+        method(1, AmqpMethod::Queue(QueueMethod::DeleteOk(3)));
+    }
+
+    #[test]
+    fn test_basic_methods() {
+        method(
+            1,
+            AmqpMethod::Basic(BasicMethod::Qos(BasicQos {
+                prefetch_size: 0,
+                prefetch_count: 10,
+                global: false,
+            })),
+        );
+        method(
+            1,
+            AmqpMethod::Basic(BasicMethod::Consume(BasicConsume {
+                queue: "q1".to_string(),
+                consumer_tag: "ctag-1".to_string(),
+                no_ack: true,
+                ..Default::default()
+            })),
+        );
+        method(
+            1,
+            AmqpMethod::Basic(BasicMethod::ConsumeOk("ctag-1".to_string())),
+        );
+        method(
+            1,
+            AmqpMethod::Basic(BasicMethod::Publish(BasicPublish {
+                exchange: "logs".to_string(),
+                routing_key: "a.b".to_string(),
+                mandatory: true,
+                immediate: false,
+            })),
+        );
+        method(
+            1,
+            AmqpMethod::Basic(BasicMethod::Deliver(BasicDeliver {
+                consumer_tag: "ctag-1".to_string(),
+                delivery_tag: u64::MAX,
+                redelivered: true,
+                exchange: "logs".to_string(),
+                routing_key: "a.b".to_string(),
+            })),
+        );
+        method(
+            1,
+            AmqpMethod::Basic(BasicMethod::Ack(BasicAck {
+                delivery_tag: 5,
+                multiple: true,
+            })),
+        );
+        method(
+            1,
+            AmqpMethod::Basic(BasicMethod::Nack(BasicNack {
+                delivery_tag: 6,
+                multiple: false,
+                requeue: true,
+            })),
+        );
+        method(1, AmqpMethod::Basic(BasicMethod::GetEmpty));
+        method(1, AmqpMethod::Basic(BasicMethod::Recover(true)));
+    }
+
+    #[test]
+    fn test_confirm_and_tx_methods() {
+        method(
+            1,
+            AmqpMethod::Confirm(ConfirmMethod::Select { no_wait: false }),
+        );
+        method(1, AmqpMethod::Confirm(ConfirmMethod::SelectOk));
+        method(1, AmqpMethod::Tx(TxMethod::Commit));
+    }
+
+    #[test]
+    fn test_content_header_body_heartbeat() {
+        let mut headers = FieldTable::new();
+        headers.insert("trace".to_string(), FieldValue::Boolean(true));
+        round_trip(AmqpFrame::Header(
+            1,
+            ContentHeader {
+                class_id: 60,
+                body_size: 5,
+                properties: BasicProperties {
+                    content_type: Some("text/plain".to_string()),
+                    headers: Some(headers),
+                    delivery_mode: Some(2),
+                    timestamp: Some(1700000000),
+                    app_id: Some("robustmq".to_string()),
+                    ..Default::default()
+                },
+            },
+        ));
+        round_trip(AmqpFrame::Header(1, ContentHeader::default()));
+        round_trip(AmqpFrame::Body(1, Bytes::from_static(b"hello")));
+        round_trip(AmqpFrame::Heartbeat);
+    }
+
+    #[test]
+    fn test_partial_and_invalid_frames() {
+        let mut codec = AmqpCodec::new(None);
+        let mut buffer = BytesMut::new();
+        codec
+            .encode(
+                AmqpFrame::Method(1, AmqpMethod::Channel(ChannelMethod::Open)),
+                &mut buffer,
+            )
+            .unwrap();
+        codec.encode(AmqpFrame::Heartbeat, &mut buffer).unwrap();
+
+        // Feed the bytes one by one, the frames only come out once complete
+        let data = buffer.split();
+        let mut frames = Vec::new();
+        for byte in data.iter() {
+            buffer.extend_from_slice(&[*byte]);
+            if let Some(frame) = codec.decode(&mut buffer).unwrap() {
+                frames.push(frame);
+            }
+        }
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1], AmqpFrame::Heartbeat);
+
+        // Wrong frame end
+        let mut buffer = BytesMut::from(&[8u8, 0, 0, 0, 0, 0, 0, 0x00][..]);
+        assert!(matches!(
+            codec.decode(&mut buffer),
+            Err(Error::InvalidFrameEnd(0))
+        ));
+
+        // Frame larger than frame max
+        let mut codec = AmqpCodec::new(Some(4096));
+        let mut buffer = BytesMut::from(&[3u8, 0, 1, 0, 0, 0x20, 0][..]);
+        assert!(matches!(
+            codec.decode(&mut buffer),
+            Err(Error::FrameTooLarge(_, 4096))
+        ));
+
+        // Unknown method
+        let mut buffer = BytesMut::from(&[1u8, 0, 1, 0, 0, 0, 4, 0, 99, 0, 1, FRAME_END][..]);
+        assert!(matches!(
+            codec.decode(&mut buffer),
+            Err(Error::UnknownMethod(99, 1))
+        ));
+    }
+}
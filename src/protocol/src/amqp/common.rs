@@ -0,0 +1,323 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+use std::string::FromUtf8Error;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use super::field::{
+    read_field_table, read_short_string, read_u16, read_u64, read_u8, write_field_table,
+    write_short_string, FieldTable,
+};
+use super::method::AmqpMethod;
+
+/// Protocol header sent by the client before anything else: "AMQP" 0 0 9 1
+pub const PROTOCOL_HEADER: [u8; 8] = [b'A', b'M', b'Q', b'P', 0, 0, 9, 1];
+pub const PROTOCOL_HEADER_LEN: usize = 8;
+
+pub const FRAME_METHOD: u8 = 1;
+pub const FRAME_HEADER: u8 = 2;
+pub const FRAME_BODY: u8 = 3;
+pub const FRAME_HEARTBEAT: u8 = 8;
+pub const FRAME_END: u8 = 0xCE;
+
+/// type(1) + channel(2) + size(4)
+pub const FRAME_HEADER_LEN: usize = 7;
+pub const FRAME_MIN_SIZE: u32 = 4096;
+pub const FRAME_DEFAULT_MAX_SIZE: u32 = 131072;
+
+pub const REPLY_SUCCESS: u16 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolVersion {
+    pub major: u8,
+    pub minor: u8,
+    pub revision: u8,
+}
+
+impl ProtocolVersion {
+    pub fn amqp_0_9_1() -> Self {
+        ProtocolVersion {
+            major: 0,
+            minor: 9,
+            revision: 1,
+        }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        *self == ProtocolVersion::amqp_0_9_1()
+    }
+}
+
+/// A single AMQP 0-9-1 frame.
+///
+/// ```ignore
+/// +------+---------+-------------+  +------------+  +-----------+
+/// | type | channel |    size     |  |  payload   |  | frame-end |
+/// +------+---------+-------------+  +------------+  +-----------+
+///  octet   short       long          size octets       0xCE
+/// ```
+///
+/// <https://www.rabbitmq.com/resources/specs/amqp0-9-1.pdf>
+#[derive(Debug, Clone, PartialEq)]
+pub enum AmqpFrame {
+    ProtocolHeader(ProtocolVersion),
+    Method(u16, AmqpMethod),
+    Header(u16, ContentHeader),
+    Body(u16, Bytes),
+    Heartbeat,
+}
+
+impl AmqpFrame {
+    pub fn channel(&self) -> u16 {
+        match self {
+            AmqpFrame::ProtocolHeader(_) | AmqpFrame::Heartbeat => 0,
+            AmqpFrame::Method(channel, _)
+            | AmqpFrame::Header(channel, _)
+            | AmqpFrame::Body(channel, _) => *channel,
+        }
+    }
+}
+
+/// Content header frame, sent after a content-carrying method such as basic.publish
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ContentHeader {
+    pub class_id: u16,
+    pub body_size: u64,
+    pub properties: BasicProperties,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicProperties {
+    pub content_type: Option<String>,
+    pub content_encoding: Option<String>,
+    pub headers: Option<FieldTable>,
+    /// 1 for non-persistent, 2 for persistent
+    pub delivery_mode: Option<u8>,
+    pub priority: Option<u8>,
+    pub correlation_id: Option<String>,
+    pub reply_to: Option<String>,
+    pub expiration: Option<String>,
+    pub message_id: Option<String>,
+    pub timestamp: Option<u64>,
+    pub kind: Option<String>,
+    pub user_id: Option<String>,
+    pub app_id: Option<String>,
+    pub cluster_id: Option<String>,
+}
+
+const FLAG_CONTENT_TYPE: u16 = 1 << 15;
+const FLAG_CONTENT_ENCODING: u16 = 1 << 14;
+const FLAG_HEADERS: u16 = 1 << 13;
+const FLAG_DELIVERY_MODE: u16 = 1 << 12;
+const FLAG_PRIORITY: u16 = 1 << 11;
+const FLAG_CORRELATION_ID: u16 = 1 << 10;
+const FLAG_REPLY_TO: u16 = 1 << 9;
+const FLAG_EXPIRATION: u16 = 1 << 8;
+const FLAG_MESSAGE_ID: u16 = 1 << 7;
+const FLAG_TIMESTAMP: u16 = 1 << 6;
+const FLAG_TYPE: u16 = 1 << 5;
+const FLAG_USER_ID: u16 = 1 << 4;
+const FLAG_APP_ID: u16 = 1 << 3;
+const FLAG_CLUSTER_ID: u16 = 1 << 2;
+
+impl BasicProperties {
+    pub fn is_persistent(&self) -> bool {
+        self.delivery_mode == Some(2)
+    }
+
+    fn flags(&self) -> u16 {
+        let mut flags = 0;
+        let fields = [
+            (self.content_type.is_some(), FLAG_CONTENT_TYPE),
+            (self.content_encoding.is_some(), FLAG_CONTENT_ENCODING),
+            (self.headers.is_some(), FLAG_HEADERS),
+            (self.delivery_mode.is_some(), FLAG_DELIVERY_MODE),
+            (self.priority.is_some(), FLAG_PRIORITY),
+            (self.correlation_id.is_some(), FLAG_CORRELATION_ID),
+            (self.reply_to.is_some(), FLAG_REPLY_TO),
+            (self.expiration.is_some(), FLAG_EXPIRATION),
+            (self.message_id.is_some(), FLAG_MESSAGE_ID),
+            (self.timestamp.is_some(), FLAG_TIMESTAMP),
+            (self.kind.is_some(), FLAG_TYPE),
+            (self.user_id.is_some(), FLAG_USER_ID),
+            (self.app_id.is_some(), FLAG_APP_ID),
+            (self.cluster_id.is_some(), FLAG_CLUSTER_ID),
+        ];
+        for (present, flag) in fields {
+            if present {
+                flags |= flag;
+            }
+        }
+        flags
+    }
+}
+
+impl ContentHeader {
+    pub fn read(mut bytes: Bytes) -> Result<ContentHeader, Error> {
+        let class_id = read_u16(&mut bytes)?;
+        // weight, unused and must be zero
+        read_u16(&mut bytes)?;
+        let body_size = read_u64(&mut bytes)?;
+
+        let flags = read_u16(&mut bytes)?;
+        if flags & 1 != 0 {
+            // Property flags continuation is not used by any 0-9-1 class
+            return Err(Error::MalformedFrame);
+        }
+
+        let read_str = |bytes: &mut Bytes, flag: u16| -> Result<Option<String>, Error> {
+            if flags & flag == 0 {
+                return Ok(None);
+            }
+            Ok(Some(read_short_string(bytes)?))
+        };
+
+        let content_type = read_str(&mut bytes, FLAG_CONTENT_TYPE)?;
+        let content_encoding = read_str(&mut bytes, FLAG_CONTENT_ENCODING)?;
+        let headers = if flags & FLAG_HEADERS != 0 {
+            Some(read_field_table(&mut bytes)?)
+        } else {
+            None
+        };
+        let delivery_mode = if flags & FLAG_DELIVERY_MODE != 0 {
+            Some(read_u8(&mut bytes)?)
+        } else {
+            None
+        };
+        let priority = if flags & FLAG_PRIORITY != 0 {
+            Some(read_u8(&mut bytes)?)
+        } else {
+            None
+        };
+        let correlation_id = read_str(&mut bytes, FLAG_CORRELATION_ID)?;
+        let reply_to = read_str(&mut bytes, FLAG_REPLY_TO)?;
+        let expiration = read_str(&mut bytes, FLAG_EXPIRATION)?;
+        let message_id = read_str(&mut bytes, FLAG_MESSAGE_ID)?;
+        let timestamp = if flags & FLAG_TIMESTAMP != 0 {
+            Some(read_u64(&mut bytes)?)
+        } else {
+            None
+        };
+        let kind = read_str(&mut bytes, FLAG_TYPE)?;
+        let user_id = read_str(&mut bytes, FLAG_USER_ID)?;
+        let app_id = read_str(&mut bytes, FLAG_APP_ID)?;
+        let cluster_id = read_str(&mut bytes, FLAG_CLUSTER_ID)?;
+
+        Ok(ContentHeader {
+            class_id,
+            body_size,
+            properties: BasicProperties {
+                content_type,
+                content_encoding,
+                headers,
+                delivery_mode,
+                priority,
+                correlation_id,
+                reply_to,
+                expiration,
+                message_id,
+                timestamp,
+                kind,
+                user_id,
+                app_id,
+                cluster_id,
+            },
+        })
+    }
+
+    pub fn write(&self, buffer: &mut BytesMut) -> Result<(), Error> {
+        let properties = &self.properties;
+        buffer.put_u16(self.class_id);
+        buffer.put_u16(0);
+        buffer.put_u64(self.body_size);
+        buffer.put_u16(properties.flags());
+
+        let strings = |buffer: &mut BytesMut, value: &Option<String>| -> Result<(), Error> {
+            if let Some(value) = value {
+                write_short_string(buffer, value)?;
+            }
+            Ok(())
+        };
+
+        strings(buffer, &properties.content_type)?;
+        strings(buffer, &properties.content_encoding)?;
+        if let Some(headers) = &properties.headers {
+            write_field_table(buffer, headers)?;
+        }
+        if let Some(delivery_mode) = properties.delivery_mode {
+            buffer.put_u8(delivery_mode);
+        }
+        if let Some(priority) = properties.priority {
+            buffer.put_u8(priority);
+        }
+        strings(buffer, &properties.correlation_id)?;
+        strings(buffer, &properties.reply_to)?;
+        strings(buffer, &properties.expiration)?;
+        strings(buffer, &properties.message_id)?;
+        if let Some(timestamp) = properties.timestamp {
+            buffer.put_u64(timestamp);
+        }
+        strings(buffer, &properties.kind)?;
+        strings(buffer, &properties.user_id)?;
+        strings(buffer, &properties.app_id)?;
+        strings(buffer, &properties.cluster_id)?;
+        Ok(())
+    }
+}
+
+pub(crate) fn read_protocol_header(bytes: &[u8]) -> Result<ProtocolVersion, Error> {
+    if bytes.len() < PROTOCOL_HEADER_LEN || &bytes[0..4] != b"AMQP" || bytes[4] != 0 {
+        return Err(Error::InvalidProtocolHeader);
+    }
+    let mut bytes = &bytes[5..8];
+    Ok(ProtocolVersion {
+        major: bytes.get_u8(),
+        minor: bytes.get_u8(),
+        revision: bytes.get_u8(),
+    })
+}
+
+/// Error during serialization and deserialization
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Invalid protocol header")]
+    InvalidProtocolHeader,
+    #[error("Invalid frame type = {0}")]
+    InvalidFrameType(u8),
+    #[error("Invalid frame end = {0}")]
+    InvalidFrameEnd(u8),
+    #[error("Frame size {0} exceeds the negotiated frame max {1}")]
+    FrameTooLarge(usize, u32),
+    #[error("Heartbeat frame must be sent on channel 0, got channel {0}")]
+    InvalidHeartbeatChannel(u16),
+    #[error("Unknown method, class id = {0}, method id = {1}")]
+    UnknownMethod(u16, u16),
+    #[error("Invalid field value type = {0}")]
+    InvalidFieldType(u8),
+    #[error("Field table or array is nested deeper than {0} levels")]
+    FieldNestingTooDeep(usize),
+    #[error("Short string is too long, length = {0}")]
+    ShortStringTooLong(usize),
+    #[error("Insufficient number of bytes to read frame, {0} more bytes required")]
+    InsufficientBytes(usize),
+    #[error("Frame is malformed")]
+    MalformedFrame,
+    #[error("String is not utf-8")]
+    StringNotUtf8(#[from] FromUtf8Error),
+
+    #[error(transparent)]
+    IoError(#[from] io::Error),
+}
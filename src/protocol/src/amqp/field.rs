@@ -0,0 +1,383 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use super::common::Error;
+
+/// Field table, the keys are short strings. A `BTreeMap` keeps the encoding deterministic.
+pub type FieldTable = BTreeMap<String, FieldValue>;
+
+/// Field value types, using the type tags accepted by RabbitMQ and most clients
+/// (see the errata of the 0-9-1 specification).
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Boolean(bool),
+    ShortShortInt(i8),
+    ShortShortUint(u8),
+    ShortInt(i16),
+    ShortUint(u16),
+    LongInt(i32),
+    LongUint(u32),
+    LongLongInt(i64),
+    Float(f32),
+    Double(f64),
+    Decimal(u8, u32),
+    LongString(Bytes),
+    FieldArray(Vec<FieldValue>),
+    Timestamp(u64),
+    FieldTable(FieldTable),
+    ByteArray(Bytes),
+    Void,
+}
+
+impl FieldValue {
+    fn tag(&self) -> u8 {
+        match self {
+            FieldValue::Boolean(_) => b't',
+            FieldValue::ShortShortInt(_) => b'b',
+            FieldValue::ShortShortUint(_) => b'B',
+            FieldValue::ShortInt(_) => b's',
+            FieldValue::ShortUint(_) => b'u',
+            FieldValue::LongInt(_) => b'I',
+            FieldValue::LongUint(_) => b'i',
+            FieldValue::LongLongInt(_) => b'l',
+            FieldValue::Float(_) => b'f',
+            FieldValue::Double(_) => b'd',
+            FieldValue::Decimal(_, _) => b'D',
+            FieldValue::LongString(_) => b'S',
+            FieldValue::FieldArray(_) => b'A',
+            FieldValue::Timestamp(_) => b'T',
+            FieldValue::FieldTable(_) => b'F',
+            FieldValue::ByteArray(_) => b'x',
+            FieldValue::Void => b'V',
+        }
+    }
+
+    pub fn as_str(&self) -> Option<String> {
+        match self {
+            FieldValue::LongString(value) => String::from_utf8(value.to_vec()).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(value: &str) -> Self {
+        FieldValue::LongString(Bytes::copy_from_slice(value.as_bytes()))
+    }
+}
+
+impl From<bool> for FieldValue {
+    fn from(value: bool) -> Self {
+        FieldValue::Boolean(value)
+    }
+}
+
+fn ensure(bytes: &Bytes, len: usize) -> Result<(), Error> {
+    if bytes.len() < len {
+        return Err(Error::InsufficientBytes(len - bytes.len()));
+    }
+    Ok(())
+}
+
+pub fn read_u8(bytes: &mut Bytes) -> Result<u8, Error> {
+    ensure(bytes, 1)?;
+    Ok(bytes.get_u8())
+}
+
+pub fn read_u16(bytes: &mut Bytes) -> Result<u16, Error> {
+    ensure(bytes, 2)?;
+    Ok(bytes.get_u16())
+}
+
+pub fn read_u32(bytes: &mut Bytes) -> Result<u32, Error> {
+    ensure(bytes, 4)?;
+    Ok(bytes.get_u32())
+}
+
+pub fn read_u64(bytes: &mut Bytes) -> Result<u64, Error> {
+    ensure(bytes, 8)?;
+    Ok(bytes.get_u64())
+}
+
+/// Consecutive bit fields of a method are packed into octets, low bit first
+pub fn read_bits(bytes: &mut Bytes, count: usize) -> Result<Vec<bool>, Error> {
+    let mut bits = Vec::with_capacity(count);
+    let mut octet = 0;
+    for index in 0..count {
+        if index % 8 == 0 {
+            octet = read_u8(bytes)?;
+        }
+        bits.push(octet & (1 << (index % 8)) != 0);
+    }
+    Ok(bits)
+}
+
+pub fn write_bits(buffer: &mut BytesMut, bits: &[bool]) {
+    for chunk in bits.chunks(8) {
+        let mut octet = 0u8;
+        for (index, bit) in chunk.iter().enumerate() {
+            if *bit {
+                octet |= 1 << index;
+            }
+        }
+        buffer.put_u8(octet);
+    }
+}
+
+pub fn read_short_string(bytes: &mut Bytes) -> Result<String, Error> {
+    let len = read_u8(bytes)? as usize;
+    ensure(bytes, len)?;
+    Ok(String::from_utf8(bytes.split_to(len).to_vec())?)
+}
+
+pub fn write_short_string(buffer: &mut BytesMut, value: &str) -> Result<(), Error> {
+    if value.len() > u8::MAX as usize {
+        return Err(Error::ShortStringTooLong(value.len()));
+    }
+    buffer.put_u8(value.len() as u8);
+    buffer.put_slice(value.as_bytes());
+    Ok(())
+}
+
+pub fn read_long_bytes(bytes: &mut Bytes) -> Result<Bytes, Error> {
+    let len = read_u32(bytes)? as usize;
+    ensure(bytes, len)?;
+    Ok(bytes.split_to(len))
+}
+
+pub fn write_long_bytes(buffer: &mut BytesMut, value: &[u8]) {
+    buffer.put_u32(value.len() as u32);
+    buffer.put_slice(value);
+}
+
+pub fn read_long_string(bytes: &mut Bytes) -> Result<String, Error> {
+    Ok(String::from_utf8(read_long_bytes(bytes)?.to_vec())?)
+}
+
+pub fn write_long_string(buffer: &mut BytesMut, value: &str) {
+    write_long_bytes(buffer, value.as_bytes());
+}
+
+// Tables and arrays nested deeper than this are rejected, so that a crafted frame cannot
+// exhaust the stack of the decoder
+pub const MAX_FIELD_NESTING_DEPTH: usize = 32;
+
+pub fn read_field_table(bytes: &mut Bytes) -> Result<FieldTable, Error> {
+    read_nested_field_table(bytes, 0)
+}
+
+fn read_nested_field_table(bytes: &mut Bytes, depth: usize) -> Result<FieldTable, Error> {
+    if depth > MAX_FIELD_NESTING_DEPTH {
+        return Err(Error::FieldNestingTooDeep(MAX_FIELD_NESTING_DEPTH));
+    }
+    let mut data = read_long_bytes(bytes)?;
+    let mut table = FieldTable::new();
+    while data.has_remaining() {
+        let name = read_short_string(&mut data)?;
+        let value = read_field_value(&mut data, depth)?;
+        table.insert(name, value);
+    }
+    Ok(table)
+}
+
+pub fn write_field_table(buffer: &mut BytesMut, table: &FieldTable) -> Result<(), Error> {
+    let mut data = BytesMut::new();
+    for (name, value) in table {
+        write_short_string(&mut data, name)?;
+        write_field_value(&mut data, value)?;
+    }
+    write_long_bytes(buffer, &data);
+    Ok(())
+}
+
+fn read_field_value(bytes: &mut Bytes, depth: usize) -> Result<FieldValue, Error> {
+    let tag = read_u8(bytes)?;
+    let value = match tag {
+        b't' => FieldValue::Boolean(read_u8(bytes)? != 0),
+        b'b' => FieldValue::ShortShortInt(read_u8(bytes)? as i8),
+        b'B' => FieldValue::ShortShortUint(read_u8(bytes)?),
+        b's' => FieldValue::ShortInt(read_u16(bytes)? as i16),
+        b'u' => FieldValue::ShortUint(read_u16(bytes)?),
+        b'I' => FieldValue::LongInt(read_u32(bytes)? as i32),
+        b'i' => FieldValue::LongUint(read_u32(bytes)?),
+        b'l' => FieldValue::LongLongInt(read_u64(bytes)? as i64),
+        b'f' => FieldValue::Float(f32::from_bits(read_u32(bytes)?)),
+        b'd' => FieldValue::Double(f64::from_bits(read_u64(bytes)?)),
+        b'D' => {
+            let scale = read_u8(bytes)?;
+            FieldValue::Decimal(scale, read_u32(bytes)?)
+        }
+        b'S' => FieldValue::LongString(read_long_bytes(bytes)?),
+        b'A' => {
+            if depth >= MAX_FIELD_NESTING_DEPTH {
+                return Err(Error::FieldNestingTooDeep(MAX_FIELD_NESTING_DEPTH));
+            }
+            let mut data = read_long_bytes(bytes)?;
+            let mut values = Vec::new();
+            while data.has_remaining() {
+                values.push(read_field_value(&mut data, depth + 1)?);
+            }
+            FieldValue::FieldArray(values)
+        }
+        b'T' => FieldValue::Timestamp(read_u64(bytes)?),
+        b'F' => FieldValue::FieldTable(read_nested_field_table(bytes, depth + 1)?),
+        b'x' => FieldValue::ByteArray(read_long_bytes(bytes)?),
+        b'V' => FieldValue::Void,
+        _ => return Err(Error::InvalidFieldType(tag)),
+    };
+    Ok(value)
+}
+
+fn write_field_value(buffer: &mut BytesMut, value: &FieldValue) -> Result<(), Error> {
+    buffer.put_u8(value.tag());
+    match value {
+        FieldValue::Boolean(v) => buffer.put_u8(*v as u8),
+        FieldValue::ShortShortInt(v) => buffer.put_i8(*v),
+        FieldValue::ShortShortUint(v) => buffer.put_u8(*v),
+        FieldValue::ShortInt(v) => buffer.put_i16(*v),
+        FieldValue::ShortUint(v) => buffer.put_u16(*v),
+        FieldValue::LongInt(v) => buffer.put_i32(*v),
+        FieldValue::LongUint(v) => buffer.put_u32(*v),
+        FieldValue::LongLongInt(v) => buffer.put_i64(*v),
+        FieldValue::Float(v) => buffer.put_f32(*v),
+        FieldValue::Double(v) => buffer.put_f64(*v),
+        FieldValue::Decimal(scale, v) => {
+            buffer.put_u8(*scale);
+            buffer.put_u32(*v);
+        }
+        FieldValue::LongString(v) | FieldValue::ByteArray(v) => write_long_bytes(buffer, v),
+        FieldValue::FieldArray(values) => {
+            let mut data = BytesMut::new();
+            for v in values {
+                write_field_value(&mut data, v)?;
+            }
+            write_long_bytes(buffer, &data);
+        }
+        FieldValue::Timestamp(v) => buffer.put_u64(*v),
+        FieldValue::FieldTable(table) => write_field_table(buffer, table)?,
+        FieldValue::Void => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+
+    use super::*;
+
+    #[test]
+    fn test_field_table() {
+        let mut nested = FieldTable::new();
+        nested.insert("publisher_confirms".to_string(), FieldValue::Boolean(true));
+
+        let mut table = FieldTable::new();
+        table.insert("capabilities".to_string(), FieldValue::FieldTable(nested));
+        table.insert("product".to_string(), FieldValue::from("RobustMQ"));
+        table.insert("b".to_string(), FieldValue::ShortShortInt(-1));
+        table.insert("B".to_string(), FieldValue::ShortShortUint(255));
+        table.insert("s".to_string(), FieldValue::ShortInt(-300));
+        table.insert("u".to_string(), FieldValue::ShortUint(300));
+        table.insert("I".to_string(), FieldValue::LongInt(-70000));
+        table.insert("i".to_string(), FieldValue::LongUint(70000));
+        table.insert("l".to_string(), FieldValue::LongLongInt(-5_000_000_000));
+        table.insert("f".to_string(), FieldValue::Float(1.5));
+        table.insert("d".to_string(), FieldValue::Double(2.25));
+        table.insert("D".to_string(), FieldValue::Decimal(2, 12345));
+        table.insert("T".to_string(), FieldValue::Timestamp(1700000000));
+        table.insert(
+            "A".to_string(),
+            FieldValue::FieldArray(vec![FieldValue::Void, FieldValue::from("x")]),
+        );
+        table.insert(
+            "x".to_string(),
+            FieldValue::ByteArray(Bytes::from_static(&[0, 1, 2])),
+        );
+
+        let mut buffer = BytesMut::new();
+        write_field_table(&mut buffer, &table).unwrap();
+        let mut bytes = buffer.freeze();
+        let table_return = read_field_table(&mut bytes).unwrap();
+        assert_eq!(table_return, table);
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn test_field_nesting_depth() {
+        fn nested_table(depth: usize) -> FieldTable {
+            let mut table = FieldTable::new();
+            if depth > 0 {
+                table.insert(
+                    "t".to_string(),
+                    FieldValue::FieldTable(nested_table(depth - 1)),
+                );
+            }
+            table
+        }
+        fn nested_array(depth: usize) -> FieldTable {
+            let mut value = FieldValue::Void;
+            for _ in 0..depth {
+                value = FieldValue::FieldArray(vec![value]);
+            }
+            let mut table = FieldTable::new();
+            table.insert("a".to_string(), value);
+            table
+        }
+
+        for (table, ok) in [
+            (nested_table(MAX_FIELD_NESTING_DEPTH), true),
+            (nested_table(MAX_FIELD_NESTING_DEPTH + 1), false),
+            (nested_array(MAX_FIELD_NESTING_DEPTH), true),
+            (nested_array(MAX_FIELD_NESTING_DEPTH + 1), false),
+        ] {
+            let mut buffer = BytesMut::new();
+            write_field_table(&mut buffer, &table).unwrap();
+            let result = read_field_table(&mut buffer.freeze());
+            if ok {
+                assert_eq!(result.unwrap(), table);
+            } else {
+                assert!(matches!(result, Err(Error::FieldNestingTooDeep(_))));
+            }
+        }
+    }
+
+    #[test]
+    fn test_bits() {
+        let bits = vec![true, false, true, true, false, false, false, false, true];
+        let mut buffer = BytesMut::new();
+        write_bits(&mut buffer, &bits);
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer[0], 0b0000_1101);
+
+        let mut bytes = buffer.freeze();
+        assert_eq!(read_bits(&mut bytes, bits.len()).unwrap(), bits);
+    }
+
+    #[test]
+    fn test_short_string() {
+        let mut buffer = BytesMut::new();
+        assert!(write_short_string(&mut buffer, &"a".repeat(256)).is_err());
+
+        write_short_string(&mut buffer, "amq.direct").unwrap();
+        let mut bytes = buffer.freeze();
+        assert_eq!(read_short_string(&mut bytes).unwrap(), "amq.direct");
+
+        let mut truncated = Bytes::from_static(&[5, b'a']);
+        assert!(read_short_string(&mut truncated).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use super::super::common::Error;
+use super::super::field::{
+    read_bits, read_field_table, read_short_string, read_u16, read_u32, read_u64, write_bits,
+    write_field_table, write_short_string, FieldTable,
+};
+use super::CLASS_BASIC;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicQos {
+    pub prefetch_size: u32,
+    pub prefetch_count: u16,
+    pub global: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicConsume {
+    pub queue: String,
+    pub consumer_tag: String,
+    pub no_local: bool,
+    pub no_ack: bool,
+    pub exclusive: bool,
+    pub no_wait: bool,
+    pub arguments: FieldTable,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicCancel {
+    pub consumer_tag: String,
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicPublish {
+    pub exchange: String,
+    pub routing_key: String,
+    pub mandatory: bool,
+    pub immediate: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicReturn {
+    pub reply_code: u16,
+    pub reply_text: String,
+    pub exchange: String,
+    pub routing_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicDeliver {
+    pub consumer_tag: String,
+    pub delivery_tag: u64,
+    pub redelivered: bool,
+    pub exchange: String,
+    pub routing_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicGet {
+    pub queue: String,
+    pub no_ack: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicGetOk {
+    pub delivery_tag: u64,
+    pub redelivered: bool,
+    pub exchange: String,
+    pub routing_key: String,
+    pub message_count: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicAck {
+    pub delivery_tag: u64,
+    pub multiple: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicReject {
+    pub delivery_tag: u64,
+    pub requeue: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BasicNack {
+    pub delivery_tag: u64,
+    pub multiple: bool,
+    pub requeue: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BasicMethod {
+    Qos(BasicQos),
+    QosOk,
+    Consume(BasicConsume),
+    ConsumeOk(String),
+    Cancel(BasicCancel),
+    CancelOk(String),
+    Publish(BasicPublish),
+    Return(BasicReturn),
+    Deliver(BasicDeliver),
+    Get(BasicGet),
+    GetOk(BasicGetOk),
+    GetEmpty,
+    Ack(BasicAck),
+    Reject(BasicReject),
+    RecoverAsync(bool),
+    Recover(bool),
+    RecoverOk,
+    Nack(BasicNack),
+}
+
+impl BasicMethod {
+    pub fn method_id(&self) -> u16 {
+        match self {
+            BasicMethod::Qos(_) => 10,
+            BasicMethod::QosOk => 11,
+            BasicMethod::Consume(_) => 20,
+            BasicMethod::ConsumeOk(_) => 21,
+            BasicMethod::Cancel(_) => 30,
+            BasicMethod::CancelOk(_) => 31,
+            BasicMethod::Publish(_) => 40,
+            BasicMethod::Return(_) => 50,
+            BasicMethod::Deliver(_) => 60,
+            BasicMethod::Get(_) => 70,
+            BasicMethod::GetOk(_) => 71,
+            BasicMethod::GetEmpty => 72,
+            BasicMethod::Ack(_) => 80,
+            BasicMethod::Reject(_) => 90,
+            BasicMethod::RecoverAsync(_) => 100,
+            BasicMethod::Recover(_) => 110,
+            BasicMethod::RecoverOk => 111,
+            BasicMethod::Nack(_) => 120,
+        }
+    }
+
+    pub fn read(method_id: u16, bytes: &mut Bytes) -> Result<Self, Error> {
+        let method = match method_id {
+            10 => BasicMethod::Qos(BasicQos {
+                prefetch_size: read_u32(bytes)?,
+                prefetch_count: read_u16(bytes)?,
+                global: read_bits(bytes, 1)?[0],
+            }),
+            11 => BasicMethod::QosOk,
+            20 => {
+                // reserved-1 (ticket)
+                read_u16(bytes)?;
+                let queue = read_short_string(bytes)?;
+                let consumer_tag = read_short_string(bytes)?;
+                let bits = read_bits(bytes, 4)?;
+                BasicMethod::Consume(BasicConsume {
+                    queue,
+                    consumer_tag,
+                    no_local: bits[0],
+                    no_ack: bits[1],
+                    exclusive: bits[2],
+                    no_wait: bits[3],
+                    arguments: read_field_table(bytes)?,
+                })
+            }
+            21 => BasicMethod::ConsumeOk(read_short_string(bytes)?),
+            30 => BasicMethod::Cancel(BasicCancel {
+                consumer_tag: read_short_string(bytes)?,
+                no_wait: read_bits(bytes, 1)?[0],
+            }),
+            31 => BasicMethod::CancelOk(read_short_string(bytes)?),
+            40 => {
+                read_u16(bytes)?;
+                let exchange = read_short_string(bytes)?;
+                let routing_key = read_short_string(bytes)?;
+                let bits = read_bits(bytes, 2)?;
+                BasicMethod::Publish(BasicPublish {
+                    exchange,
+                    routing_key,
+                    mandatory: bits[0],
+                    immediate: bits[1],
+                })
+            }
+            50 => BasicMethod::Return(BasicReturn {
+                reply_code: read_u16(bytes)?,
+                reply_text: read_short_string(bytes)?,
+                exchange: read_short_string(bytes)?,
+                routing_key: read_short_string(bytes)?,
+            }),
+            60 => BasicMethod::Deliver(BasicDeliver {
+                consumer_tag: read_short_string(bytes)?,
+                delivery_tag: read_u64(bytes)?,
+                redelivered: read_bits(bytes, 1)?[0],
+                exchange: read_short_string(bytes)?,
+                routing_key: read_short_string(bytes)?,
+            }),
+            70 => {
+                read_u16(bytes)?;
+                BasicMethod::Get(BasicGet {
+                    queue: read_short_string(bytes)?,
+                    no_ack: read_bits(bytes, 1)?[0],
+                })
+            }
+            71 => BasicMethod::GetOk(BasicGetOk {
+                delivery_tag: read_u64(bytes)?,
+                redelivered: read_bits(bytes, 1)?[0],
+                exchange: read_short_string(bytes)?,
+                routing_key: read_short_string(bytes)?,
+                message_count: read_u32(bytes)?,
+            }),
+            72 => {
+                // reserved-1 (cluster-id)
+                read_short_string(bytes)?;
+                BasicMethod::GetEmpty
+            }
+            80 => BasicMethod::Ack(BasicAck {
+                delivery_tag: read_u64(bytes)?,
+                multiple: read_bits(bytes, 1)?[0],
+            }),
+            90 => BasicMethod::Reject(BasicReject {
+                delivery_tag: read_u64(bytes)?,
+                requeue: read_bits(bytes, 1)?[0],
+            }),
+            100 => BasicMethod::RecoverAsync(read_bits(bytes, 1)?[0]),
+            110 => BasicMethod::Recover(read_bits(bytes, 1)?[0]),
+            111 => BasicMethod::RecoverOk,
+            120 => {
+                let delivery_tag = read_u64(bytes)?;
+                let bits = read_bits(bytes, 2)?;
+                BasicMethod::Nack(BasicNack {
+                    delivery_tag,
+                    multiple: bits[0],
+                    requeue: bits[1],
+                })
+            }
+            _ => return Err(Error::UnknownMethod(CLASS_BASIC, method_id)),
+        };
+        Ok(method)
+    }
+
+    pub fn write(&self, buffer: &mut BytesMut) -> Result<(), Error> {
+        match self {
+            BasicMethod::Qos(qos) => {
+                buffer.put_u32(qos.prefetch_size);
+                buffer.put_u16(qos.prefetch_count);
+                write_bits(buffer, &[qos.global]);
+            }
+            BasicMethod::Consume(consume) => {
+                buffer.put_u16(0);
+                write_short_string(buffer, &consume.queue)?;
+                write_short_string(buffer, &consume.consumer_tag)?;
+                write_bits(
+                    buffer,
+                    &[
+                        consume.no_local,
+                        consume.no_ack,
+                        consume.exclusive,
+                        consume.no_wait,
+                    ],
+                );
+                write_field_table(buffer, &consume.arguments)?;
+            }
+            BasicMethod::ConsumeOk(consumer_tag) | BasicMethod::CancelOk(consumer_tag) => {
+                write_short_string(buffer, consumer_tag)?
+            }
+            BasicMethod::Cancel(cancel) => {
+                write_short_string(buffer, &cancel.consumer_tag)?;
+                write_bits(buffer, &[cancel.no_wait]);
+            }
+            BasicMethod::Publish(publish) => {
+                buffer.put_u16(0);
+                write_short_string(buffer, &publish.exchange)?;
+                write_short_string(buffer, &publish.routing_key)?;
+                write_bits(buffer, &[publish.mandatory, publish.immediate]);
+            }
+            BasicMethod::Return(ret) => {
+                buffer.put_u16(ret.reply_code);
+                write_short_string(buffer, &ret.reply_text)?;
+                write_short_string(buffer, &ret.exchange)?;
+                write_short_string(buffer, &ret.routing_key)?;
+            }
+            BasicMethod::Deliver(deliver) => {
+                write_short_string(buffer, &deliver.consumer_tag)?;
+                buffer.put_u64(deliver.delivery_tag);
+                write_bits(buffer, &[deliver.redelivered]);
+                write_short_string(buffer, &deliver.exchange)?;
+                write_short_string(buffer, &deliver.routing_key)?;
+            }
+            BasicMethod::Get(get) => {
+                buffer.put_u16(0);
+                write_short_string(buffer, &get.queue)?;
+                write_bits(buffer, &[get.no_ack]);
+            }
+            BasicMethod::GetOk(get_ok) => {
+                buffer.put_u64(get_ok.delivery_tag);
+                write_bits(buffer, &[get_ok.redelivered]);
+                write_short_string(buffer, &get_ok.exchange)?;
+                write_short_string(buffer, &get_ok.routing_key)?;
+                buffer.put_u32(get_ok.message_count);
+            }
+            BasicMethod::GetEmpty => write_short_string(buffer, "")?,
+            BasicMethod::Ack(ack) => {
+                buffer.put_u64(ack.delivery_tag);
+                write_bits(buffer, &[ack.multiple]);
+            }
+            BasicMethod::Reject(reject) => {
+                buffer.put_u64(reject.delivery_tag);
+                write_bits(buffer, &[reject.requeue]);
+            }
+            BasicMethod::RecoverAsync(requeue) | BasicMethod::Recover(requeue) => {
+                write_bits(buffer, &[*requeue])
+            }
+            BasicMethod::Nack(nack) => {
+                buffer.put_u64(nack.delivery_tag);
+                write_bits(buffer, &[nack.multiple, nack.requeue]);
+            }
+            BasicMethod::QosOk | BasicMethod::RecoverOk => {}
+        }
+        Ok(())
+    }
+}
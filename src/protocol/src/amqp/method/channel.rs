@@ -0,0 +1,106 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use super::super::common::Error;
+use super::super::field::{
+    read_bits, read_long_bytes, read_short_string, read_u16, write_bits, write_long_bytes,
+    write_short_string,
+};
+use super::CLASS_CHANNEL;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChannelFlow {
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChannelClose {
+    pub reply_code: u16,
+    pub reply_text: String,
+    pub class_id: u16,
+    pub method_id: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelMethod {
+    Open,
+    OpenOk,
+    Flow(ChannelFlow),
+    FlowOk(ChannelFlow),
+    Close(ChannelClose),
+    CloseOk,
+}
+
+impl ChannelMethod {
+    pub fn method_id(&self) -> u16 {
+        match self {
+            ChannelMethod::Open => 10,
+            ChannelMethod::OpenOk => 11,
+            ChannelMethod::Flow(_) => 20,
+            ChannelMethod::FlowOk(_) => 21,
+            ChannelMethod::Close(_) => 40,
+            ChannelMethod::CloseOk => 41,
+        }
+    }
+
+    pub fn read(method_id: u16, bytes: &mut Bytes) -> Result<Self, Error> {
+        let method = match method_id {
+            10 => {
+                // reserved-1 (out-of-band)
+                read_short_string(bytes)?;
+                ChannelMethod::Open
+            }
+            11 => {
+                // reserved-1 (channel-id)
+                read_long_bytes(bytes)?;
+                ChannelMethod::OpenOk
+            }
+            20 => ChannelMethod::Flow(ChannelFlow {
+                active: read_bits(bytes, 1)?[0],
+            }),
+            21 => ChannelMethod::FlowOk(ChannelFlow {
+                active: read_bits(bytes, 1)?[0],
+            }),
+            40 => ChannelMethod::Close(ChannelClose {
+                reply_code: read_u16(bytes)?,
+                reply_text: read_short_string(bytes)?,
+                class_id: read_u16(bytes)?,
+                method_id: read_u16(bytes)?,
+            }),
+            41 => ChannelMethod::CloseOk,
+            _ => return Err(Error::UnknownMethod(CLASS_CHANNEL, method_id)),
+        };
+        Ok(method)
+    }
+
+    pub fn write(&self, buffer: &mut BytesMut) -> Result<(), Error> {
+        match self {
+            ChannelMethod::Open => write_short_string(buffer, "")?,
+            ChannelMethod::OpenOk => write_long_bytes(buffer, &[]),
+            ChannelMethod::Flow(flow) | ChannelMethod::FlowOk(flow) => {
+                write_bits(buffer, &[flow.active])
+            }
+            ChannelMethod::Close(close) => {
+                buffer.put_u16(close.reply_code);
+                write_short_string(buffer, &close.reply_text)?;
+                buffer.put_u16(close.class_id);
+                buffer.put_u16(close.method_id);
+            }
+            ChannelMethod::CloseOk => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::{Bytes, BytesMut};
+
+use super::super::common::Error;
+use super::super::field::{read_bits, write_bits};
+use super::CLASS_CONFIRM;
+
+/// Publisher confirms, a RabbitMQ extension of 0-9-1
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfirmMethod {
+    Select { no_wait: bool },
+    SelectOk,
+}
+
+impl ConfirmMethod {
+    pub fn method_id(&self) -> u16 {
+        match self {
+            ConfirmMethod::Select { .. } => 10,
+            ConfirmMethod::SelectOk => 11,
+        }
+    }
+
+    pub fn read(method_id: u16, bytes: &mut Bytes) -> Result<Self, Error> {
+        let method = match method_id {
+            10 => ConfirmMethod::Select {
+                no_wait: read_bits(bytes, 1)?[0],
+            },
+            11 => ConfirmMethod::SelectOk,
+            _ => return Err(Error::UnknownMethod(CLASS_CONFIRM, method_id)),
+        };
+        Ok(method)
+    }
+
+    pub fn write(&self, buffer: &mut BytesMut) -> Result<(), Error> {
+        if let ConfirmMethod::Select { no_wait } = self {
+            write_bits(buffer, &[*no_wait]);
+        }
+        Ok(())
+    }
+}
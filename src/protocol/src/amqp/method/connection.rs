@@ -0,0 +1,211 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use super::super::common::Error;
+use super::super::field::{
+    read_bits, read_field_table, read_long_bytes, read_long_string, read_short_string, read_u16,
+    read_u32, read_u8, write_bits, write_field_table, write_long_bytes, write_long_string,
+    write_short_string, FieldTable,
+};
+use super::CLASS_CONNECTION;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConnectionStart {
+    pub version_major: u8,
+    pub version_minor: u8,
+    pub server_properties: FieldTable,
+    /// Space separated list of SASL mechanisms, e.g. "PLAIN AMQPLAIN"
+    pub mechanisms: String,
+    pub locales: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConnectionStartOk {
+    pub client_properties: FieldTable,
+    pub mechanism: String,
+    pub response: Bytes,
+    pub locale: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConnectionSecure {
+    pub challenge: Bytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConnectionSecureOk {
+    pub response: Bytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConnectionTune {
+    pub channel_max: u16,
+    pub frame_max: u32,
+    pub heartbeat: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConnectionOpen {
+    pub virtual_host: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConnectionClose {
+    pub reply_code: u16,
+    pub reply_text: String,
+    /// Class and method of the method that caused the close, zero if none
+    pub class_id: u16,
+    pub method_id: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConnectionBlocked {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionMethod {
+    Start(ConnectionStart),
+    StartOk(ConnectionStartOk),
+    Secure(ConnectionSecure),
+    SecureOk(ConnectionSecureOk),
+    Tune(ConnectionTune),
+    TuneOk(ConnectionTune),
+    Open(ConnectionOpen),
+    OpenOk,
+    Close(ConnectionClose),
+    CloseOk,
+    Blocked(ConnectionBlocked),
+    Unblocked,
+}
+
+impl ConnectionMethod {
+    pub fn method_id(&self) -> u16 {
+        match self {
+            ConnectionMethod::Start(_) => 10,
+            ConnectionMethod::StartOk(_) => 11,
+            ConnectionMethod::Secure(_) => 20,
+            ConnectionMethod::SecureOk(_) => 21,
+            ConnectionMethod::Tune(_) => 30,
+            ConnectionMethod::TuneOk(_) => 31,
+            ConnectionMethod::Open(_) => 40,
+            ConnectionMethod::OpenOk => 41,
+            ConnectionMethod::Close(_) => 50,
+            ConnectionMethod::CloseOk => 51,
+            ConnectionMethod::Blocked(_) => 60,
+            ConnectionMethod::Unblocked => 61,
+        }
+    }
+
+    pub fn read(method_id: u16, bytes: &mut Bytes) -> Result<Self, Error> {
+        let method = match method_id {
+            10 => ConnectionMethod::Start(ConnectionStart {
+                version_major: read_u8(bytes)?,
+                version_minor: read_u8(bytes)?,
+                server_properties: read_field_table(bytes)?,
+                mechanisms: read_long_string(bytes)?,
+                locales: read_long_string(bytes)?,
+            }),
+            11 => ConnectionMethod::StartOk(ConnectionStartOk {
+                client_properties: read_field_table(bytes)?,
+                mechanism: read_short_string(bytes)?,
+                response: read_long_bytes(bytes)?,
+                locale: read_short_string(bytes)?,
+            }),
+            20 => ConnectionMethod::Secure(ConnectionSecure {
+                challenge: read_long_bytes(bytes)?,
+            }),
+            21 => ConnectionMethod::SecureOk(ConnectionSecureOk {
+                response: read_long_bytes(bytes)?,
+            }),
+            30 => ConnectionMethod::Tune(read_tune(bytes)?),
+            31 => ConnectionMethod::TuneOk(read_tune(bytes)?),
+            40 => {
+                let virtual_host = read_short_string(bytes)?;
+                // reserved-1 (capabilities) and reserved-2 (insist)
+                read_short_string(bytes)?;
+                read_bits(bytes, 1)?;
+                ConnectionMethod::Open(ConnectionOpen { virtual_host })
+            }
+            41 => {
+                // reserved-1 (known-hosts)
+                read_short_string(bytes)?;
+                ConnectionMethod::OpenOk
+            }
+            50 => ConnectionMethod::Close(ConnectionClose {
+                reply_code: read_u16(bytes)?,
+                reply_text: read_short_string(bytes)?,
+                class_id: read_u16(bytes)?,
+                method_id: read_u16(bytes)?,
+            }),
+            51 => ConnectionMethod::CloseOk,
+            60 => ConnectionMethod::Blocked(ConnectionBlocked {
+                reason: read_short_string(bytes)?,
+            }),
+            61 => ConnectionMethod::Unblocked,
+            _ => return Err(Error::UnknownMethod(CLASS_CONNECTION, method_id)),
+        };
+        Ok(method)
+    }
+
+    pub fn write(&self, buffer: &mut BytesMut) -> Result<(), Error> {
+        match self {
+            ConnectionMethod::Start(start) => {
+                buffer.put_u8(start.version_major);
+                buffer.put_u8(start.version_minor);
+                write_field_table(buffer, &start.server_properties)?;
+                write_long_string(buffer, &start.mechanisms);
+                write_long_string(buffer, &start.locales);
+            }
+            ConnectionMethod::StartOk(start_ok) => {
+                write_field_table(buffer, &start_ok.client_properties)?;
+                write_short_string(buffer, &start_ok.mechanism)?;
+                write_long_bytes(buffer, &start_ok.response);
+                write_short_string(buffer, &start_ok.locale)?;
+            }
+            ConnectionMethod::Secure(secure) => write_long_bytes(buffer, &secure.challenge),
+            ConnectionMethod::SecureOk(secure_ok) => write_long_bytes(buffer, &secure_ok.response),
+            ConnectionMethod::Tune(tune) | ConnectionMethod::TuneOk(tune) => {
+                buffer.put_u16(tune.channel_max);
+                buffer.put_u32(tune.frame_max);
+                buffer.put_u16(tune.heartbeat);
+            }
+            ConnectionMethod::Open(open) => {
+                write_short_string(buffer, &open.virtual_host)?;
+                write_short_string(buffer, "")?;
+                write_bits(buffer, &[false]);
+            }
+            ConnectionMethod::OpenOk => write_short_string(buffer, "")?,
+            ConnectionMethod::Close(close) => {
+                buffer.put_u16(close.reply_code);
+                write_short_string(buffer, &close.reply_text)?;
+                buffer.put_u16(close.class_id);
+                buffer.put_u16(close.method_id);
+            }
+            ConnectionMethod::Blocked(blocked) => write_short_string(buffer, &blocked.reason)?,
+            ConnectionMethod::CloseOk | ConnectionMethod::Unblocked => {}
+        }
+        Ok(())
+    }
+}
+
+fn read_tune(bytes: &mut Bytes) -> Result<ConnectionTune, Error> {
+    Ok(ConnectionTune {
+        channel_max: read_u16(bytes)?,
+        frame_max: read_u32(bytes)?,
+        heartbeat: read_u16(bytes)?,
+    })
+}
@@ -0,0 +1,173 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use super::super::common::Error;
+use super::super::field::{
+    read_bits, read_field_table, read_short_string, read_u16, write_bits, write_field_table,
+    write_short_string, FieldTable,
+};
+use super::CLASS_EXCHANGE;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExchangeDeclare {
+    pub exchange: String,
+    /// direct, fanout, topic or headers
+    pub kind: String,
+    pub passive: bool,
+    pub durable: bool,
+    pub auto_delete: bool,
+    pub internal: bool,
+    pub no_wait: bool,
+    pub arguments: FieldTable,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExchangeDelete {
+    pub exchange: String,
+    pub if_unused: bool,
+    pub no_wait: bool,
+}
+
+/// Arguments of exchange.bind and exchange.unbind
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExchangeBind {
+    pub destination: String,
+    pub source: String,
+    pub routing_key: String,
+    pub no_wait: bool,
+    pub arguments: FieldTable,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExchangeMethod {
+    Declare(ExchangeDeclare),
+    DeclareOk,
+    Delete(ExchangeDelete),
+    DeleteOk,
+    Bind(ExchangeBind),
+    BindOk,
+    Unbind(ExchangeBind),
+    UnbindOk,
+}
+
+impl ExchangeMethod {
+    pub fn method_id(&self) -> u16 {
+        match self {
+            ExchangeMethod::Declare(_) => 10,
+            ExchangeMethod::DeclareOk => 11,
+            ExchangeMethod::Delete(_) => 20,
+            ExchangeMethod::DeleteOk => 21,
+            ExchangeMethod::Bind(_) => 30,
+            ExchangeMethod::BindOk => 31,
+            ExchangeMethod::Unbind(_) => 40,
+            ExchangeMethod::UnbindOk => 51,
+        }
+    }
+
+    pub fn read(method_id: u16, bytes: &mut Bytes) -> Result<Self, Error> {
+        let method = match method_id {
+            10 => {
+                // reserved-1 (ticket)
+                read_u16(bytes)?;
+                let exchange = read_short_string(bytes)?;
+                let kind = read_short_string(bytes)?;
+                let bits = read_bits(bytes, 5)?;
+                ExchangeMethod::Declare(ExchangeDeclare {
+                    exchange,
+                    kind,
+                    passive: bits[0],
+                    durable: bits[1],
+                    auto_delete: bits[2],
+                    internal: bits[3],
+                    no_wait: bits[4],
+                    arguments: read_field_table(bytes)?,
+                })
+            }
+            11 => ExchangeMethod::DeclareOk,
+            20 => {
+                read_u16(bytes)?;
+                let exchange = read_short_string(bytes)?;
+                let bits = read_bits(bytes, 2)?;
+                ExchangeMethod::Delete(ExchangeDelete {
+                    exchange,
+                    if_unused: bits[0],
+                    no_wait: bits[1],
+                })
+            }
+            21 => ExchangeMethod::DeleteOk,
+            30 => ExchangeMethod::Bind(read_bind(bytes)?),
+            31 => ExchangeMethod::BindOk,
+            40 => ExchangeMethod::Unbind(read_bind(bytes)?),
+            51 => ExchangeMethod::UnbindOk,
+            _ => return Err(Error::UnknownMethod(CLASS_EXCHANGE, method_id)),
+        };
+        Ok(method)
+    }
+
+    pub fn write(&self, buffer: &mut BytesMut) -> Result<(), Error> {
+        match self {
+            ExchangeMethod::Declare(declare) => {
+                buffer.put_u16(0);
+                write_short_string(buffer, &declare.exchange)?;
+                write_short_string(buffer, &declare.kind)?;
+                write_bits(
+                    buffer,
+                    &[
+                        declare.passive,
+                        declare.durable,
+                        declare.auto_delete,
+                        declare.internal,
+                        declare.no_wait,
+                    ],
+                );
+                write_field_table(buffer, &declare.arguments)?;
+            }
+            ExchangeMethod::Delete(delete) => {
+                buffer.put_u16(0);
+                write_short_string(buffer, &delete.exchange)?;
+                write_bits(buffer, &[delete.if_unused, delete.no_wait]);
+            }
+            ExchangeMethod::Bind(bind) | ExchangeMethod::Unbind(bind) => {
+                buffer.put_u16(0);
+                write_short_string(buffer, &bind.destination)?;
+                write_short_string(buffer, &bind.source)?;
+                write_short_string(buffer, &bind.routing_key)?;
+                write_bits(buffer, &[bind.no_wait]);
+                write_field_table(buffer, &bind.arguments)?;
+            }
+            ExchangeMethod::DeclareOk
+            | ExchangeMethod::DeleteOk
+            | ExchangeMethod::BindOk
+            | ExchangeMethod::UnbindOk => {}
+        }
+        Ok(())
+    }
+}
+
+fn read_bind(bytes: &mut Bytes) -> Result<ExchangeBind, Error> {
+    read_u16(bytes)?;
+    let destination = read_short_string(bytes)?;
+    let source = read_short_string(bytes)?;
+    let routing_key = read_short_string(bytes)?;
+    let no_wait = read_bits(bytes, 1)?[0];
+    Ok(ExchangeBind {
+        destination,
+        source,
+        routing_key,
+        no_wait,
+        arguments: read_field_table(bytes)?,
+    })
+}
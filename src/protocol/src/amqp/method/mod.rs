@@ -0,0 +1,125 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use super::common::Error;
+use super::field::read_u16;
+
+pub mod basic;
+pub mod channel;
+pub mod confirm;
+pub mod connection;
+pub mod exchange;
+pub mod queue;
+pub mod tx;
+
+pub use basic::BasicMethod;
+pub use channel::ChannelMethod;
+pub use confirm::ConfirmMethod;
+pub use connection::ConnectionMethod;
+pub use exchange::ExchangeMethod;
+pub use queue::QueueMethod;
+pub use tx::TxMethod;
+
+pub const CLASS_CONNECTION: u16 = 10;
+pub const CLASS_CHANNEL: u16 = 20;
+pub const CLASS_EXCHANGE: u16 = 40;
+pub const CLASS_QUEUE: u16 = 50;
+pub const CLASS_BASIC: u16 = 60;
+pub const CLASS_CONFIRM: u16 = 85;
+pub const CLASS_TX: u16 = 90;
+
+/// Payload of a method frame: class-id(2) + method-id(2) + arguments
+#[derive(Debug, Clone, PartialEq)]
+pub enum AmqpMethod {
+    Connection(ConnectionMethod),
+    Channel(ChannelMethod),
+    Exchange(ExchangeMethod),
+    Queue(QueueMethod),
+    Basic(BasicMethod),
+    Confirm(ConfirmMethod),
+    Tx(TxMethod),
+}
+
+impl AmqpMethod {
+    pub fn class_id(&self) -> u16 {
+        match self {
+            AmqpMethod::Connection(_) => CLASS_CONNECTION,
+            AmqpMethod::Channel(_) => CLASS_CHANNEL,
+            AmqpMethod::Exchange(_) => CLASS_EXCHANGE,
+            AmqpMethod::Queue(_) => CLASS_QUEUE,
+            AmqpMethod::Basic(_) => CLASS_BASIC,
+            AmqpMethod::Confirm(_) => CLASS_CONFIRM,
+            AmqpMethod::Tx(_) => CLASS_TX,
+        }
+    }
+
+    pub fn method_id(&self) -> u16 {
+        match self {
+            AmqpMethod::Connection(method) => method.method_id(),
+            AmqpMethod::Channel(method) => method.method_id(),
+            AmqpMethod::Exchange(method) => method.method_id(),
+            AmqpMethod::Queue(method) => method.method_id(),
+            AmqpMethod::Basic(method) => method.method_id(),
+            AmqpMethod::Confirm(method) => method.method_id(),
+            AmqpMethod::Tx(method) => method.method_id(),
+        }
+    }
+
+    /// Whether the method is followed by a content header and body frames
+    pub fn has_content(&self) -> bool {
+        matches!(
+            self,
+            AmqpMethod::Basic(
+                BasicMethod::Publish(_)
+                    | BasicMethod::Return(_)
+                    | BasicMethod::Deliver(_)
+                    | BasicMethod::GetOk(_)
+            )
+        )
+    }
+
+    pub fn read(mut bytes: Bytes) -> Result<AmqpMethod, Error> {
+        let class_id = read_u16(&mut bytes)?;
+        let method_id = read_u16(&mut bytes)?;
+        let method = match class_id {
+            CLASS_CONNECTION => {
+                AmqpMethod::Connection(ConnectionMethod::read(method_id, &mut bytes)?)
+            }
+            CLASS_CHANNEL => AmqpMethod::Channel(ChannelMethod::read(method_id, &mut bytes)?),
+            CLASS_EXCHANGE => AmqpMethod::Exchange(ExchangeMethod::read(method_id, &mut bytes)?),
+            CLASS_QUEUE => AmqpMethod::Queue(QueueMethod::read(method_id, &mut bytes)?),
+            CLASS_BASIC => AmqpMethod::Basic(BasicMethod::read(method_id, &mut bytes)?),
+            CLASS_CONFIRM => AmqpMethod::Confirm(ConfirmMethod::read(method_id, &mut bytes)?),
+            CLASS_TX => AmqpMethod::Tx(TxMethod::read(method_id, &mut bytes)?),
+            _ => return Err(Error::UnknownMethod(class_id, method_id)),
+        };
+        Ok(method)
+    }
+
+    pub fn write(&self, buffer: &mut BytesMut) -> Result<(), Error> {
+        buffer.put_u16(self.class_id());
+        buffer.put_u16(self.method_id());
+        match self {
+            AmqpMethod::Connection(method) => method.write(buffer),
+            AmqpMethod::Channel(method) => method.write(buffer),
+            AmqpMethod::Exchange(method) => method.write(buffer),
+            AmqpMethod::Queue(method) => method.write(buffer),
+            AmqpMethod::Basic(method) => method.write(buffer),
+            AmqpMethod::Confirm(method) => method.write(buffer),
+            AmqpMethod::Tx(method) => method.write(buffer),
+        }
+    }
+}
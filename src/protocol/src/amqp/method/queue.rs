@@ -0,0 +1,231 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use super::super::common::Error;
+use super::super::field::{
+    read_bits, read_field_table, read_short_string, read_u16, read_u32, write_bits,
+    write_field_table, write_short_string, FieldTable,
+};
+use super::CLASS_QUEUE;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueueDeclare {
+    /// An empty name asks the server to generate one
+    pub queue: String,
+    pub passive: bool,
+    pub durable: bool,
+    pub exclusive: bool,
+    pub auto_delete: bool,
+    pub no_wait: bool,
+    pub arguments: FieldTable,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueueDeclareOk {
+    pub queue: String,
+    pub message_count: u32,
+    pub consumer_count: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueueBind {
+    pub queue: String,
+    pub exchange: String,
+    pub routing_key: String,
+    pub no_wait: bool,
+    pub arguments: FieldTable,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueueUnbind {
+    pub queue: String,
+    pub exchange: String,
+    pub routing_key: String,
+    pub arguments: FieldTable,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueuePurge {
+    pub queue: String,
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueueDelete {
+    pub queue: String,
+    pub if_unused: bool,
+    pub if_empty: bool,
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueueMethod {
+    Declare(QueueDeclare),
+    DeclareOk(QueueDeclareOk),
+    Bind(QueueBind),
+    BindOk,
+    Purge(QueuePurge),
+    PurgeOk(u32),
+    Delete(QueueDelete),
+    DeleteOk(u32),
+    Unbind(QueueUnbind),
+    UnbindOk,
+}
+
+impl QueueMethod {
+    pub fn method_id(&self) -> u16 {
+        match self {
+            QueueMethod::Declare(_) => 10,
+            QueueMethod::DeclareOk(_) => 11,
+            QueueMethod::Bind(_) => 20,
+            QueueMethod::BindOk => 21,
+            QueueMethod::Purge(_) => 30,
+            QueueMethod::PurgeOk(_) => 31,
+            QueueMethod::Delete(_) => 40,
+            QueueMethod::DeleteOk(_) => 41,
+            QueueMethod::Unbind(_) => 50,
+            QueueMethod::UnbindOk => 51,
+        }
+    }
+
+    pub fn read(method_id: u16, bytes: &mut Bytes) -> Result<Self, Error> {
+        let method = match method_id {
+            10 => {
+                // reserved-1 (ticket)
+                read_u16(bytes)?;
+                let queue = read_short_string(bytes)?;
+                let bits = read_bits(bytes, 5)?;
+                QueueMethod::Declare(QueueDeclare {
+                    queue,
+                    passive: bits[0],
+                    durable: bits[1],
+                    exclusive: bits[2],
+                    auto_delete: bits[3],
+                    no_wait: bits[4],
+                    arguments: read_field_table(bytes)?,
+                })
+            }
+            11 => QueueMethod::DeclareOk(QueueDeclareOk {
+                queue: read_short_string(bytes)?,
+                message_count: read_u32(bytes)?,
+                consumer_count: read_u32(bytes)?,
+            }),
+            20 => {
+                read_u16(bytes)?;
+                let queue = read_short_string(bytes)?;
+                let exchange = read_short_string(bytes)?;
+                let routing_key = read_short_string(bytes)?;
+                let no_wait = read_bits(bytes, 1)?[0];
+                QueueMethod::Bind(QueueBind {
+                    queue,
+                    exchange,
+                    routing_key,
+                    no_wait,
+                    arguments: read_field_table(bytes)?,
+                })
+            }
+            21 => QueueMethod::BindOk,
+            30 => {
+                read_u16(bytes)?;
+                let queue = read_short_string(bytes)?;
+                QueueMethod::Purge(QueuePurge {
+                    queue,
+                    no_wait: read_bits(bytes, 1)?[0],
+                })
+            }
+            31 => QueueMethod::PurgeOk(read_u32(bytes)?),
+            40 => {
+                read_u16(bytes)?;
+                let queue = read_short_string(bytes)?;
+                let bits = read_bits(bytes, 3)?;
+                QueueMethod::Delete(QueueDelete {
+                    queue,
+                    if_unused: bits[0],
+                    if_empty: bits[1],
+                    no_wait: bits[2],
+                })
+            }
+            41 => QueueMethod::DeleteOk(read_u32(bytes)?),
+            50 => {
+                read_u16(bytes)?;
+                QueueMethod::Unbind(QueueUnbind {
+                    queue: read_short_string(bytes)?,
+                    exchange: read_short_string(bytes)?,
+                    routing_key: read_short_string(bytes)?,
+                    arguments: read_field_table(bytes)?,
+                })
+            }
+            51 => QueueMethod::UnbindOk,
+            _ => return Err(Error::UnknownMethod(CLASS_QUEUE, method_id)),
+        };
+        Ok(method)
+    }
+
+    pub fn write(&self, buffer: &mut BytesMut) -> Result<(), Error> {
+        match self {
+            QueueMethod::Declare(declare) => {
+                buffer.put_u16(0);
+                write_short_string(buffer, &declare.queue)?;
+                write_bits(
+                    buffer,
+                    &[
+                        declare.passive,
+                        declare.durable,
+                        declare.exclusive,
+                        declare.auto_delete,
+                        declare.no_wait,
+                    ],
+                );
+                write_field_table(buffer, &declare.arguments)?;
+            }
+            QueueMethod::DeclareOk(declare_ok) => {
+                write_short_string(buffer, &declare_ok.queue)?;
+                buffer.put_u32(declare_ok.message_count);
+                buffer.put_u32(declare_ok.consumer_count);
+            }
+            QueueMethod::Bind(bind) => {
+                buffer.put_u16(0);
+                write_short_string(buffer, &bind.queue)?;
+                write_short_string(buffer, &bind.exchange)?;
+                write_short_string(buffer, &bind.routing_key)?;
+                write_bits(buffer, &[bind.no_wait]);
+                write_field_table(buffer, &bind.arguments)?;
+            }
+            QueueMethod::Purge(purge) => {
+                buffer.put_u16(0);
+                write_short_string(buffer, &purge.queue)?;
+                write_bits(buffer, &[purge.no_wait]);
+            }
+            QueueMethod::PurgeOk(message_count) | QueueMethod::DeleteOk(message_count) => {
+                buffer.put_u32(*message_count)
+            }
+            QueueMethod::Delete(delete) => {
+                buffer.put_u16(0);
+                write_short_string(buffer, &delete.queue)?;
+                write_bits(buffer, &[delete.if_unused, delete.if_empty, delete.no_wait]);
+            }
+            QueueMethod::Unbind(unbind) => {
+                buffer.put_u16(0);
+                write_short_string(buffer, &unbind.queue)?;
+                write_short_string(buffer, &unbind.exchange)?;
+                write_short_string(buffer, &unbind.routing_key)?;
+                write_field_table(buffer, &unbind.arguments)?;
+            }
+            QueueMethod::BindOk | QueueMethod::UnbindOk => {}
+        }
+        Ok(())
+    }
+}
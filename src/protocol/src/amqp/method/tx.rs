@@ -0,0 +1,58 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::{Bytes, BytesMut};
+
+use super::super::common::Error;
+use super::CLASS_TX;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TxMethod {
+    Select,
+    SelectOk,
+    Commit,
+    CommitOk,
+    Rollback,
+    RollbackOk,
+}
+
+impl TxMethod {
+    pub fn method_id(&self) -> u16 {
+        match self {
+            TxMethod::Select => 10,
+            TxMethod::SelectOk => 11,
+            TxMethod::Commit => 20,
+            TxMethod::CommitOk => 21,
+            TxMethod::Rollback => 30,
+            TxMethod::RollbackOk => 31,
+        }
+    }
+
+    pub fn read(method_id: u16, _: &mut Bytes) -> Result<Self, Error> {
+        let method = match method_id {
+            10 => TxMethod::Select,
+            11 => TxMethod::SelectOk,
+            20 => TxMethod::Commit,
+            21 => TxMethod::CommitOk,
+            30 => TxMethod::Rollback,
+            31 => TxMethod::RollbackOk,
+            _ => return Err(Error::UnknownMethod(CLASS_TX, method_id)),
+        };
+        Ok(method)
+    }
+
+    pub fn write(&self, _: &mut BytesMut) -> Result<(), Error> {
+        Ok(())
+    }
+}
@@ -13,3 +13,6 @@
 // limitations under the License.
 
 pub mod codec;
+pub mod common;
+pub mod field;
+pub mod method;
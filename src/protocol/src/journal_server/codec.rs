@@ -645,6 +645,7 @@ mod tests {
         let header = ReqHeader {
             api_key: ApiKey::Write.into(),
             api_version: ApiVersion::V0.into(),
+            ..Default::default()
         };
 
         let body: WriteReqBody = WriteReqBody::default();
@@ -666,6 +667,7 @@ mod tests {
         let header = ReqHeader {
             api_key: ApiKey::GetClusterMetadata.into(),
             api_version: ApiVersion::V0.into(),
+            ..Default::default()
         };
 
         let req = GetClusterMetadataReq {
@@ -685,6 +687,7 @@ mod tests {
         let header = ReqHeader {
             api_key: ApiKey::Read.into(),
             api_version: ApiVersion::V0.into(),
+            ..Default::default()
         };
 
         let source = JournalEnginePacket::ReadReq(ReadReq {
@@ -767,6 +770,7 @@ mod tests {
         let header = ReqHeader {
             api_key: ApiKey::Write.into(),
             api_version: ApiVersion::V0.into(),
+            ..Default::default()
         };
 
         let body: WriteReqBody = WriteReqBody::default();
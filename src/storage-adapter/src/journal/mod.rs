@@ -100,6 +100,16 @@ impl StorageAdapter for JournalStorageAdapter {
         }
     }
 
+    #[tracing::instrument(
+        level = "info",
+        skip_all,
+        fields(
+            adapter = "journal",
+            namespace = %namespace,
+            shard_name = %shard_name,
+            records = records.len()
+        )
+    )]
     async fn batch_write(
         &self,
         namespace: String,
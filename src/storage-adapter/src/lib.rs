@@ -17,7 +17,7 @@ use std::str::FromStr;
 pub mod journal;
 pub mod memory;
 // pub mod mysql;
-// pub mod rocksdb;
+pub mod rocksdb;
 pub mod storage;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -69,6 +69,16 @@ impl StorageAdapter for MemoryStorageAdapter {
         return Ok(());
     }
 
+    #[tracing::instrument(
+        level = "info",
+        skip_all,
+        fields(
+            adapter = "memory",
+            namespace = %namespace,
+            shard_name = %shard_name,
+            records = messages.len()
+        )
+    )]
     async fn batch_write(
         &self,
         namespace: String,
@@ -12,391 +12,682 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-// use std::fmt::Display;
-// use std::sync::Arc;
-
-// use axum::async_trait;
-// use common_base::error::common::CommonError;
-// use metadata_struct::adapter::record::Record;
-// use rocksdb_engine::RocksDBEngine;
-
-// use crate::storage::{ShardConfig, StorageAdapter};
-
-// const DB_COLUMN_FAMILY_KV: &str = "kv";
-// const DB_COLUMN_FAMILY_RECORD: &str = "record";
-
-// fn column_family_list() -> Vec<String> {
-//     vec![
-//         DB_COLUMN_FAMILY_KV.to_string(),
-//         DB_COLUMN_FAMILY_RECORD.to_string(),
-//     ]
-// }
-
-// #[derive(Clone)]
-// pub struct RocksDBStorageAdapter {
-//     pub db: Arc<RocksDBEngine>,
-// }
-
-// impl RocksDBStorageAdapter {
-//     pub fn new(db_path: &str, max_open_files: i32) -> Self {
-//         RocksDBStorageAdapter {
-//             db: Arc::new(RocksDBEngine::new(
-//                 db_path,
-//                 max_open_files,
-//                 column_family_list(),
-//             )),
-//         }
-//     }
-
-//     #[inline(always)]
-//     pub fn record_key<S1: Display>(&self, namespace: S1, shard_name: S1, index: u128) -> String {
-//         format!("{}_{}_record_{}", namespace, shard_name, index)
-//     }
-
-//     #[inline(always)]
-//     pub fn offset_shard_key<S1: Display>(&self, namespace: S1, shard_name: S1) -> String {
-//         format!("{}_{}_{}", namespace, shard_name, "shard_offset")
-//     }
-
-//     #[inline(always)]
-//     pub fn offset_key<S1: Display, S2: Display>(
-//         &self,
-//         namespace: S1,
-//         shard_name: S1,
-//         group_id: S2,
-//     ) -> String {
-//         format!("{}_{}_{}", namespace, shard_name, group_id)
-//     }
-
-//     pub fn get_offset<S1: Display, S2: Display>(
-//         &self,
-//         namespace: S1,
-//         shard_name: S1,
-//         group_id: S2,
-//     ) -> Option<u128> {
-//         let key = self.offset_key(namespace, shard_name, group_id);
-//         self.db
-//             .cf_handle(DB_COLUMN_FAMILY_KV)
-//             .and_then(|cf| self.db.read(cf, &key).ok()?)
-//     }
-// }
-
-// impl RocksDBStorageAdapter {}
-
-// #[async_trait]
-// impl StorageAdapter for RocksDBStorageAdapter {
-//     async fn create_shard(
-//         &self,
-//         namespace: String,
-//         shard_name: String,
-//         _: ShardConfig,
-//     ) -> Result<(), CommonError> {
-//         let cf = self.db.cf_handle(DB_COLUMN_FAMILY_RECORD).unwrap();
-//         let key = self.offset_shard_key(namespace, shard_name);
-//         self.db.write(cf, key.as_str(), &0_u128)
-//     }
-
-//     async fn delete_shard(&self, namespace: String, shard_name: String) -> Result<(), CommonError> {
-//         let cf = self.db.cf_handle(DB_COLUMN_FAMILY_RECORD).unwrap();
-//         let key = self.offset_shard_key(namespace, shard_name);
-//         self.db.delete_prefix(cf, &key)
-//     }
-
-//     async fn write(
-//         &self,
-//         namespace: String,
-//         shard_name: String,
-//         message: Vec<Record>,
-//     ) -> Result<Vec<usize>, CommonError> {
-//         let cf = self.db.cf_handle(DB_COLUMN_FAMILY_RECORD).unwrap();
-//         let key_shard_offset = self.offset_shard_key(namespace.clone(), shard_name.clone());
-//         let offset = self
-//             .db
-//             .read::<u128>(cf, key_shard_offset.as_str())?
-//             .unwrap_or(0);
-
-//         let mut start_offset = offset;
-
-//         let mut offset_res = Vec::new();
-//         for mut msg in message {
-//             offset_res.push(start_offset as usize);
-//             msg.offset = start_offset;
-
-//             let record_key = self.record_key(namespace.clone(), shard_name.clone(), start_offset);
-//             self.db.write(cf, &record_key, &msg)?;
-//             start_offset += 1;
-//         }
-
-//         self.db
-//             .write(cf, key_shard_offset.as_str(), &start_offset)?;
-
-//         Ok(offset_res)
-//     }
-
-//     async fn stream_read(
-//         &self,
-//         namespace: String,
-//         shard_name: String,
-//         group_id: String,
-//         record_num: Option<u128>,
-//         _: Option<usize>,
-//     ) -> Result<Option<Vec<Record>>, CommonError> {
-//         let cf = self.db.cf_handle(DB_COLUMN_FAMILY_RECORD).unwrap();
-//         let group_offset_key = self.offset_key(namespace.clone(), shard_name.clone(), group_id);
-//         let offset = self
-//             .db
-//             .read::<u128>(cf, group_offset_key.as_str())?
-//             .unwrap_or(0);
-
-//         let num = record_num.unwrap_or(10);
-
-//         let mut cur_offset = 0;
-//         let mut result = Vec::new();
-//         for i in offset..(offset + num) {
-//             let record_key = self.record_key(namespace.clone(), shard_name.clone(), i);
-//             let value = self.db.read::<Record>(cf, &record_key)?;
-
-//             if let Some(value) = value {
-//                 result.push(value.clone());
-//                 cur_offset += 1;
-//             } else {
-//                 break;
-//             }
-//         }
-
-//         if cur_offset > 0 {
-//             self.db
-//                 .write(cf, group_offset_key.as_str(), &(offset + cur_offset))?;
-//         }
-//         Ok(Some(result))
-//     }
-
-//     async fn stream_commit_offset(
-//         &self,
-//         namespace: String,
-//         shard_name: String,
-//         group_id: String,
-//         offset: u128,
-//     ) -> Result<bool, CommonError> {
-//         let cf = self.db.cf_handle(DB_COLUMN_FAMILY_RECORD).unwrap();
-//         let key = self.offset_key(namespace, group_id, shard_name);
-//         self.db.write(cf, key.as_str(), &offset)?;
-//         Ok(true)
-//     }
-
-//     async fn stream_read_by_offset(
-//         &self,
-//         namespace: String,
-//         shard_name: String,
-//         offset: usize,
-//     ) -> Result<Option<Record>, CommonError> {
-//         let cf = self.db.cf_handle(DB_COLUMN_FAMILY_RECORD).unwrap();
-//         self.db.read::<Record>(
-//             cf,
-//             self.record_key(namespace, shard_name, offset as u128)
-//                 .as_str(),
-//         )
-//     }
-
-//     async fn stream_read_by_timestamp(
-//         &self,
-//         _: String,
-//         _: String,
-//         _: u128,
-//         _: u128,
-//         _: Option<usize>,
-//         _: Option<usize>,
-//     ) -> Result<Option<Vec<Record>>, CommonError> {
-//         Ok(None)
-//     }
-
-//     async fn stream_read_by_key(
-//         &self,
-//         _: String,
-//         _: String,
-//         _: String,
-//     ) -> Result<Option<Record>, CommonError> {
-//         Ok(None)
-//     }
-//     async fn close(&self) -> Result<(), CommonError> {
-//         Ok(())
-//     }
-// }
-
-// #[cfg(test)]
-// mod tests {
-//     use common_base::tools::unique_id;
-//     use metadata_struct::adapter::record::Record;
-
-//     use super::RocksDBStorageAdapter;
-//     use crate::storage::StorageAdapter;
-//     #[tokio::test]
-//     async fn stream_read_write() {
-//         let db_path = format!("/tmp/robustmq_{}", unique_id());
-
-//         let storage_adapter = RocksDBStorageAdapter::new(db_path.as_str(), 100);
-//         let namespace = unique_id();
-//         let shard_name = "test-11".to_string();
-//         let ms1 = "test1".to_string();
-//         let ms2 = "test2".to_string();
-//         let data = vec![
-//             Record::build_b(ms1.clone().as_bytes().to_vec()),
-//             Record::build_b(ms2.clone().as_bytes().to_vec()),
-//         ];
-
-//         let result = storage_adapter
-//             .stream_write(namespace.clone(), shard_name.clone(), data)
-//             .await
-//             .unwrap();
-
-//         assert_eq!(result.first().unwrap().clone(), 0);
-//         assert_eq!(result.get(1).unwrap().clone(), 1);
-//         assert_eq!(
-//             storage_adapter
-//                 .stream_read(
-//                     namespace.clone(),
-//                     shard_name.clone(),
-//                     "test_".to_string(),
-//                     Some(10),
-//                     None
-//                 )
-//                 .await
-//                 .unwrap()
-//                 .unwrap()
-//                 .len(),
-//             2
-//         );
-
-//         let ms3 = "test3".to_string();
-//         let ms4 = "test4".to_string();
-//         let data = vec![
-//             Record::build_b(ms3.clone().as_bytes().to_vec()),
-//             Record::build_b(ms4.clone().as_bytes().to_vec()),
-//         ];
-
-//         let result = storage_adapter
-//             .stream_write(namespace.clone(), shard_name.clone(), data)
-//             .await
-//             .unwrap();
-//         let result_read = storage_adapter
-//             .stream_read(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 "test_".to_string(),
-//                 Some(10),
-//                 None,
-//             )
-//             .await
-//             .unwrap();
-//         println!("{:?}", result_read);
-
-//         assert_eq!(result.first().unwrap().clone(), 2);
-//         assert_eq!(result.get(1).unwrap().clone(), 3);
-//         assert_eq!(result_read.unwrap().len(), 2);
-
-//         let group_id = "test_group_id".to_string();
-//         let record_num = Some(1);
-//         let record_size = None;
-//         let res = storage_adapter
-//             .stream_read(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 record_num,
-//                 record_size,
-//             )
-//             .await
-//             .unwrap()
-//             .unwrap();
-//         assert_eq!(
-//             String::from_utf8(res.first().unwrap().clone().data).unwrap(),
-//             ms1
-//         );
-//         storage_adapter
-//             .stream_commit_offset(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 res.first().unwrap().clone().offset,
-//             )
-//             .await
-//             .unwrap();
-
-//         let res = storage_adapter
-//             .stream_read(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 record_num,
-//                 record_size,
-//             )
-//             .await
-//             .unwrap()
-//             .unwrap();
-//         assert_eq!(
-//             String::from_utf8(res.first().unwrap().clone().data).unwrap(),
-//             ms2
-//         );
-//         storage_adapter
-//             .stream_commit_offset(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 res.first().unwrap().clone().offset,
-//             )
-//             .await
-//             .unwrap();
-
-//         let res = storage_adapter
-//             .stream_read(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 record_num,
-//                 record_size,
-//             )
-//             .await
-//             .unwrap()
-//             .unwrap();
-//         assert_eq!(
-//             String::from_utf8(res.first().unwrap().clone().data).unwrap(),
-//             ms3
-//         );
-//         storage_adapter
-//             .stream_commit_offset(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 res.first().unwrap().clone().offset,
-//             )
-//             .await
-//             .unwrap();
-
-//         let res = storage_adapter
-//             .stream_read(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 record_num,
-//                 record_size,
-//             )
-//             .await
-//             .unwrap()
-//             .unwrap();
-//         assert_eq!(
-//             String::from_utf8(res.first().unwrap().clone().data).unwrap(),
-//             ms4
-//         );
-//         storage_adapter
-//             .stream_commit_offset(
-//                 namespace.clone(),
-//                 shard_name.clone(),
-//                 group_id.clone(),
-//                 res.first().unwrap().clone().offset,
-//             )
-//             .await
-//             .unwrap();
-
-//         let _ = std::fs::remove_dir_all(&db_path);
-//     }
-// }
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::async_trait;
+use common_base::error::common::CommonError;
+use metadata_struct::adapter::read_config::ReadConfig;
+use metadata_struct::adapter::record::Record;
+use rocksdb::{ColumnFamily, WriteBatch};
+use rocksdb_engine::RocksDBEngine;
+use tokio::sync::Mutex;
+
+use crate::storage::{ShardConfig, ShardOffset, StorageAdapter};
+
+const DB_COLUMN_FAMILY_RECORD: &str = "record";
+const DB_COLUMN_FAMILY_OFFSET: &str = "offset";
+const DB_COLUMN_FAMILY_TAG: &str = "tag";
+const DB_COLUMN_FAMILY_KEY: &str = "key";
+const DB_COLUMN_FAMILY_TIMESTAMP: &str = "timestamp";
+
+fn column_family_list() -> Vec<String> {
+    vec![
+        DB_COLUMN_FAMILY_RECORD.to_string(),
+        DB_COLUMN_FAMILY_OFFSET.to_string(),
+        DB_COLUMN_FAMILY_TAG.to_string(),
+        DB_COLUMN_FAMILY_KEY.to_string(),
+        DB_COLUMN_FAMILY_TIMESTAMP.to_string(),
+    ]
+}
+
+// All keys of a shard share a prefix ending with '/', and '0' is the byte right after '/',
+// so replacing the trailing '/' gives the exclusive upper bound of the shard's key range.
+fn prefix_range_end(prefix: &str) -> String {
+    format!("{}0", prefix.trim_end_matches('/'))
+}
+
+// Index keys end with the zero-padded offset, anything else under the prefix belongs to a
+// longer tag or key that happens to share the same leading characters.
+fn index_key_offset(key: &str, prefix: &str) -> Option<u64> {
+    let suffix = key.strip_prefix(prefix)?;
+    if suffix.len() != 20 {
+        return None;
+    }
+    suffix.parse::<u64>().ok()
+}
+
+#[derive(Clone)]
+pub struct RocksDBStorageAdapter {
+    pub db: Arc<RocksDBEngine>,
+    // Serializes offset allocation so that concurrent writers never hand out the same offset
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl RocksDBStorageAdapter {
+    pub fn new(db_path: &str, max_open_files: i32) -> Self {
+        RocksDBStorageAdapter {
+            db: Arc::new(RocksDBEngine::new(
+                db_path,
+                max_open_files,
+                column_family_list(),
+            )),
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    #[inline(always)]
+    pub fn shard_record_prefix(&self, namespace: &str, shard_name: &str) -> String {
+        format!("/record/{}/{}/", namespace, shard_name)
+    }
+
+    // The offset is zero-padded so that the lexicographic order of the keys matches the offset order
+    #[inline(always)]
+    pub fn record_key(&self, namespace: &str, shard_name: &str, offset: u64) -> String {
+        format!(
+            "{}{:020}",
+            self.shard_record_prefix(namespace, shard_name),
+            offset
+        )
+    }
+
+    #[inline(always)]
+    pub fn shard_tag_prefix(&self, namespace: &str, shard_name: &str) -> String {
+        format!("/tag/{}/{}/", namespace, shard_name)
+    }
+
+    #[inline(always)]
+    pub fn tag_prefix(&self, namespace: &str, shard_name: &str, tag: &str) -> String {
+        format!("{}{}/", self.shard_tag_prefix(namespace, shard_name), tag)
+    }
+
+    #[inline(always)]
+    pub fn shard_key_prefix(&self, namespace: &str, shard_name: &str) -> String {
+        format!("/key/{}/{}/", namespace, shard_name)
+    }
+
+    #[inline(always)]
+    pub fn key_prefix(&self, namespace: &str, shard_name: &str, key: &str) -> String {
+        format!("{}{}/", self.shard_key_prefix(namespace, shard_name), key)
+    }
+
+    #[inline(always)]
+    pub fn shard_timestamp_prefix(&self, namespace: &str, shard_name: &str) -> String {
+        format!("/timestamp/{}/{}/", namespace, shard_name)
+    }
+
+    #[inline(always)]
+    pub fn timestamp_key(
+        &self,
+        namespace: &str,
+        shard_name: &str,
+        timestamp: u64,
+        offset: u64,
+    ) -> String {
+        format!(
+            "{}{:020}/{:020}",
+            self.shard_timestamp_prefix(namespace, shard_name),
+            timestamp,
+            offset
+        )
+    }
+
+    #[inline(always)]
+    pub fn shard_offset_key(&self, namespace: &str, shard_name: &str) -> String {
+        format!("/shard_offset/{}/{}", namespace, shard_name)
+    }
+
+    #[inline(always)]
+    pub fn group_offset_prefix(&self, group_name: &str) -> String {
+        format!("/group_offset/{}/", group_name)
+    }
+
+    #[inline(always)]
+    pub fn group_offset_key(&self, group_name: &str, namespace: &str, shard_name: &str) -> String {
+        format!(
+            "{}{}/{}",
+            self.group_offset_prefix(group_name),
+            namespace,
+            shard_name
+        )
+    }
+
+    fn cf(&self, name: &str) -> Result<&ColumnFamily, CommonError> {
+        self.db
+            .cf_handle(name)
+            .ok_or(CommonError::RocksDBFamilyNotAvailable(name.to_string()))
+    }
+
+    // Walks an index column family from `start`, collecting the offsets of the index entries
+    // under `prefix` until the read limit is reached, then loads the records they point to.
+    fn read_by_index(
+        &self,
+        index_cf: &str,
+        namespace: &str,
+        shard_name: &str,
+        prefix: &str,
+        start: &str,
+        read_config: &ReadConfig,
+    ) -> Result<Vec<Record>, CommonError> {
+        let index_cf = self.cf(index_cf)?;
+        let record_cf = self.cf(DB_COLUMN_FAMILY_RECORD)?;
+
+        let mut iter = self.db.db.raw_iterator_cf(index_cf);
+        iter.seek(start);
+
+        let mut results = Vec::new();
+        let mut size = 0;
+        while iter.valid() && (results.len() as u64) < read_config.max_record_num {
+            let key = match iter.key() {
+                Some(key) => String::from_utf8(key.to_vec())?,
+                None => break,
+            };
+            if !key.starts_with(prefix) {
+                break;
+            }
+            if let Some(offset) = index_key_offset(&key, prefix) {
+                let record_key = self.record_key(namespace, shard_name, offset);
+                if let Some(record) = self.db.read::<Record>(record_cf, &record_key)? {
+                    size += record.data.len() as u64;
+                    results.push(record);
+                    if size >= read_config.max_size {
+                        break;
+                    }
+                }
+            }
+            iter.next();
+        }
+        Ok(results)
+    }
+
+    async fn write_records(
+        &self,
+        namespace: &str,
+        shard_name: &str,
+        records: Vec<Record>,
+    ) -> Result<Vec<u64>, CommonError> {
+        let _guard = self.write_lock.lock().await;
+        let record_cf = self.cf(DB_COLUMN_FAMILY_RECORD)?;
+        let offset_cf = self.cf(DB_COLUMN_FAMILY_OFFSET)?;
+        let tag_cf = self.cf(DB_COLUMN_FAMILY_TAG)?;
+        let key_cf = self.cf(DB_COLUMN_FAMILY_KEY)?;
+        let timestamp_cf = self.cf(DB_COLUMN_FAMILY_TIMESTAMP)?;
+
+        let shard_offset_key = self.shard_offset_key(namespace, shard_name);
+        let mut next_offset = self
+            .db
+            .read::<u64>(offset_cf, &shard_offset_key)?
+            .unwrap_or(0);
+
+        // Records, their index entries and the shard offset are written in one batch,
+        // so the indexes never point to a record that was not persisted.
+        let mut batch = WriteBatch::default();
+        let mut offset_res = Vec::new();
+        for mut record in records {
+            let offset = next_offset;
+            record.offset = Some(offset);
+            let offset_value = serde_json::to_vec(&offset)?;
+
+            for tag in record.tags.iter() {
+                let tag_key = format!(
+                    "{}{:020}",
+                    self.tag_prefix(namespace, shard_name, tag),
+                    offset
+                );
+                batch.put_cf(tag_cf, tag_key, &offset_value);
+            }
+            if !record.key.is_empty() {
+                let key_key = format!(
+                    "{}{:020}",
+                    self.key_prefix(namespace, shard_name, &record.key),
+                    offset
+                );
+                batch.put_cf(key_cf, key_key, &offset_value);
+            }
+            batch.put_cf(
+                timestamp_cf,
+                self.timestamp_key(namespace, shard_name, record.timestamp, offset),
+                &offset_value,
+            );
+            batch.put_cf(
+                record_cf,
+                self.record_key(namespace, shard_name, offset),
+                serde_json::to_vec(&record)?,
+            );
+
+            offset_res.push(offset);
+            next_offset += 1;
+        }
+
+        batch.put_cf(
+            offset_cf,
+            &shard_offset_key,
+            serde_json::to_vec(&next_offset)?,
+        );
+        self.db.db.write(batch)?;
+        Ok(offset_res)
+    }
+}
+
+#[async_trait]
+impl StorageAdapter for RocksDBStorageAdapter {
+    async fn create_shard(
+        &self,
+        namespace: String,
+        shard_name: String,
+        _: ShardConfig,
+    ) -> Result<(), CommonError> {
+        let cf = self.cf(DB_COLUMN_FAMILY_OFFSET)?;
+        let key = self.shard_offset_key(&namespace, &shard_name);
+        if self.db.read::<u64>(cf, &key)?.is_none() {
+            self.db.write(cf, &key, &0_u64)?;
+        }
+        Ok(())
+    }
+
+    async fn delete_shard(&self, namespace: String, shard_name: String) -> Result<(), CommonError> {
+        let _guard = self.write_lock.lock().await;
+        let mut batch = WriteBatch::default();
+        for (cf_name, prefix) in [
+            (
+                DB_COLUMN_FAMILY_RECORD,
+                self.shard_record_prefix(&namespace, &shard_name),
+            ),
+            (
+                DB_COLUMN_FAMILY_TAG,
+                self.shard_tag_prefix(&namespace, &shard_name),
+            ),
+            (
+                DB_COLUMN_FAMILY_KEY,
+                self.shard_key_prefix(&namespace, &shard_name),
+            ),
+            (
+                DB_COLUMN_FAMILY_TIMESTAMP,
+                self.shard_timestamp_prefix(&namespace, &shard_name),
+            ),
+        ] {
+            batch.delete_range_cf(self.cf(cf_name)?, prefix.clone(), prefix_range_end(&prefix));
+        }
+        batch.delete_cf(
+            self.cf(DB_COLUMN_FAMILY_OFFSET)?,
+            self.shard_offset_key(&namespace, &shard_name),
+        );
+        self.db.db.write(batch)?;
+        Ok(())
+    }
+
+    async fn write(
+        &self,
+        namespace: String,
+        shard_name: String,
+        data: Record,
+    ) -> Result<u64, CommonError> {
+        let offsets = self
+            .write_records(&namespace, &shard_name, vec![data])
+            .await?;
+        match offsets.first() {
+            Some(offset) => Ok(*offset),
+            None => Err(CommonError::CommonError(format!(
+                "Failed to write record to shard {}/{}",
+                namespace, shard_name
+            ))),
+        }
+    }
+
+    #[tracing::instrument(
+        level = "info",
+        skip_all,
+        fields(
+            adapter = "rocksdb",
+            namespace = %namespace,
+            shard_name = %shard_name,
+            records = data.len()
+        )
+    )]
+    async fn batch_write(
+        &self,
+        namespace: String,
+        shard_name: String,
+        data: Vec<Record>,
+    ) -> Result<Vec<u64>, CommonError> {
+        self.write_records(&namespace, &shard_name, data).await
+    }
+
+    async fn read_by_offset(
+        &self,
+        namespace: String,
+        shard_name: String,
+        offset: u64,
+        read_config: ReadConfig,
+    ) -> Result<Vec<Record>, CommonError> {
+        let cf = self.cf(DB_COLUMN_FAMILY_RECORD)?;
+        let mut results = Vec::new();
+        let mut size = 0;
+        for i in offset..(offset + read_config.max_record_num) {
+            let key = self.record_key(&namespace, &shard_name, i);
+            match self.db.read::<Record>(cf, &key)? {
+                Some(record) => {
+                    size += record.data.len() as u64;
+                    results.push(record);
+                    if size >= read_config.max_size {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        Ok(results)
+    }
+
+    async fn read_by_tag(
+        &self,
+        namespace: String,
+        shard_name: String,
+        offset: u64,
+        tag: String,
+        read_config: ReadConfig,
+    ) -> Result<Vec<Record>, CommonError> {
+        let prefix = self.tag_prefix(&namespace, &shard_name, &tag);
+        let start = format!("{}{:020}", prefix, offset);
+        self.read_by_index(
+            DB_COLUMN_FAMILY_TAG,
+            &namespace,
+            &shard_name,
+            &prefix,
+            &start,
+            &read_config,
+        )
+    }
+
+    async fn read_by_key(
+        &self,
+        namespace: String,
+        shard_name: String,
+        key: String,
+        read_config: ReadConfig,
+    ) -> Result<Vec<Record>, CommonError> {
+        let prefix = self.key_prefix(&namespace, &shard_name, &key);
+        self.read_by_index(
+            DB_COLUMN_FAMILY_KEY,
+            &namespace,
+            &shard_name,
+            &prefix,
+            &prefix,
+            &read_config,
+        )
+    }
+
+    async fn get_offset_by_timestamp(
+        &self,
+        namespace: String,
+        shard_name: String,
+        timestamp: u64,
+    ) -> Result<Option<ShardOffset>, CommonError> {
+        let cf = self.cf(DB_COLUMN_FAMILY_TIMESTAMP)?;
+        let prefix = self.shard_timestamp_prefix(&namespace, &shard_name);
+        let mut iter = self.db.db.raw_iterator_cf(cf);
+        iter.seek(format!("{}{:020}", prefix, timestamp));
+        if !iter.valid() {
+            return Ok(None);
+        }
+        if let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+            if key.starts_with(prefix.as_bytes()) {
+                return Ok(Some(ShardOffset {
+                    namespace,
+                    shard_name,
+                    offset: serde_json::from_slice::<u64>(value)?,
+                    ..Default::default()
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn get_offset_by_group(
+        &self,
+        group_name: String,
+    ) -> Result<Vec<ShardOffset>, CommonError> {
+        let cf = self.cf(DB_COLUMN_FAMILY_OFFSET)?;
+        let prefix = self.group_offset_prefix(&group_name);
+        let mut results = Vec::new();
+        for (key, value) in self.db.read_prefix(cf, &prefix)? {
+            let offset = serde_json::from_slice::<u64>(&value)?;
+            let (namespace, shard_name) = key
+                .trim_start_matches(&prefix)
+                .split_once('/')
+                .unwrap_or_default();
+            results.push(ShardOffset {
+                namespace: namespace.to_string(),
+                shard_name: shard_name.to_string(),
+                offset,
+                ..Default::default()
+            });
+        }
+        Ok(results)
+    }
+
+    async fn commit_offset(
+        &self,
+        group_name: String,
+        namespace: String,
+        offset: HashMap<String, u64>,
+    ) -> Result<(), CommonError> {
+        let cf = self.cf(DB_COLUMN_FAMILY_OFFSET)?;
+        for (shard_name, offset) in offset.iter() {
+            let key = self.group_offset_key(&group_name, &namespace, shard_name);
+            self.db.write(cf, &key, offset)?;
+        }
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<(), CommonError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use common_base::tools::unique_id;
+    use metadata_struct::adapter::read_config::ReadConfig;
+    use metadata_struct::adapter::record::Record;
+
+    use super::RocksDBStorageAdapter;
+    use crate::storage::StorageAdapter;
+
+    #[tokio::test]
+    async fn stream_read_write() {
+        let db_path = format!("/tmp/robustmq_{}", unique_id());
+        let storage_adapter = RocksDBStorageAdapter::new(db_path.as_str(), 100);
+        let namespace = unique_id();
+        let shard_name = "test-11".to_string();
+
+        let ms1 = "test1".to_string();
+        let ms2 = "test2".to_string();
+        let data = vec![
+            Record::build_byte(ms1.clone().as_bytes().to_vec()),
+            Record::build_byte(ms2.clone().as_bytes().to_vec()),
+        ];
+        let result = storage_adapter
+            .batch_write(namespace.clone(), shard_name.clone(), data)
+            .await
+            .unwrap();
+        assert_eq!(result, vec![0, 1]);
+
+        let ms3 = "test3".to_string();
+        let mut record = Record::build_byte(ms3.clone().as_bytes().to_vec());
+        record.set_tags(vec!["tag1".to_string()]);
+        record.set_key("key1".to_string());
+        let offset = storage_adapter
+            .write(namespace.clone(), shard_name.clone(), record)
+            .await
+            .unwrap();
+        assert_eq!(offset, 2);
+
+        let mut read_config = ReadConfig::new();
+        read_config.max_record_num = 2;
+        let res = storage_adapter
+            .read_by_offset(
+                namespace.clone(),
+                shard_name.clone(),
+                1,
+                read_config.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 2);
+        assert_eq!(String::from_utf8(res[0].data.clone()).unwrap(), ms2);
+        assert_eq!(String::from_utf8(res[1].data.clone()).unwrap(), ms3);
+
+        let res = storage_adapter
+            .read_by_tag(
+                namespace.clone(),
+                shard_name.clone(),
+                0,
+                "tag1".to_string(),
+                read_config.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].offset, Some(2));
+
+        let res = storage_adapter
+            .read_by_key(
+                namespace.clone(),
+                shard_name.clone(),
+                "key1".to_string(),
+                read_config.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 1);
+
+        let group_id = "test_group_id".to_string();
+        let mut offset_data = HashMap::new();
+        offset_data.insert(shard_name.clone(), 1);
+        storage_adapter
+            .commit_offset(group_id.clone(), namespace.clone(), offset_data)
+            .await
+            .unwrap();
+
+        let offsets = storage_adapter
+            .get_offset_by_group(group_id.clone())
+            .await
+            .unwrap();
+        assert_eq!(offsets.len(), 1);
+        assert_eq!(offsets[0].namespace, namespace);
+        assert_eq!(offsets[0].shard_name, shard_name);
+        assert_eq!(offsets[0].offset, 1);
+
+        storage_adapter
+            .delete_shard(namespace.clone(), shard_name.clone())
+            .await
+            .unwrap();
+        let res = storage_adapter
+            .read_by_offset(namespace.clone(), shard_name.clone(), 0, read_config)
+            .await
+            .unwrap();
+        assert!(res.is_empty());
+
+        let _ = std::fs::remove_dir_all(&db_path);
+    }
+
+    #[tokio::test]
+    async fn index_read_test() {
+        let db_path = format!("/tmp/robustmq_{}", unique_id());
+        let storage_adapter = RocksDBStorageAdapter::new(db_path.as_str(), 100);
+        let namespace = unique_id();
+        let shard_name = "test-index".to_string();
+
+        let mut data = Vec::new();
+        for (i, tag) in ["a", "a/b", "a", "c"].iter().enumerate() {
+            let mut record = Record::build_byte(format!("msg{}", i).as_bytes().to_vec());
+            record.set_tags(vec![tag.to_string()]);
+            record.set_key(tag.to_string());
+            record.timestamp = 100 + i as u64 * 10;
+            data.push(record);
+        }
+        storage_adapter
+            .batch_write(namespace.clone(), shard_name.clone(), data)
+            .await
+            .unwrap();
+
+        let read_config = ReadConfig::new();
+        let res = storage_adapter
+            .read_by_tag(
+                namespace.clone(),
+                shard_name.clone(),
+                0,
+                "a".to_string(),
+                read_config.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            res.iter().map(|r| r.offset.unwrap()).collect::<Vec<u64>>(),
+            vec![0, 2]
+        );
+
+        let res = storage_adapter
+            .read_by_tag(
+                namespace.clone(),
+                shard_name.clone(),
+                1,
+                "a".to_string(),
+                read_config.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].offset, Some(2));
+
+        let res = storage_adapter
+            .read_by_key(
+                namespace.clone(),
+                shard_name.clone(),
+                "a/b".to_string(),
+                read_config.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].offset, Some(1));
+
+        let res = storage_adapter
+            .get_offset_by_timestamp(namespace.clone(), shard_name.clone(), 115)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.offset, 2);
+        let res = storage_adapter
+            .get_offset_by_timestamp(namespace.clone(), shard_name.clone(), 200)
+            .await
+            .unwrap();
+        assert!(res.is_none());
+
+        storage_adapter
+            .delete_shard(namespace.clone(), shard_name.clone())
+            .await
+            .unwrap();
+        let res = storage_adapter
+            .read_by_tag(
+                namespace.clone(),
+                shard_name.clone(),
+                0,
+                "a".to_string(),
+                read_config,
+            )
+            .await
+            .unwrap();
+        assert!(res.is_empty());
+        let res = storage_adapter
+            .get_offset_by_timestamp(namespace.clone(), shard_name.clone(), 0)
+            .await
+            .unwrap();
+        assert!(res.is_none());
+
+        let _ = std::fs::remove_dir_all(&db_path);
+    }
+}
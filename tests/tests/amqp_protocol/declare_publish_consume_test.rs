@@ -0,0 +1,285 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use common_base::tools::unique_id;
+    use futures::{SinkExt, StreamExt};
+    use protocol::amqp::codec::AmqpCodec;
+    use protocol::amqp::common::{AmqpFrame, ContentHeader, ProtocolVersion};
+    use protocol::amqp::method::basic::{BasicConsume, BasicPublish};
+    use protocol::amqp::method::connection::{ConnectionClose, ConnectionOpen, ConnectionStartOk};
+    use protocol::amqp::method::exchange::{ExchangeDeclare, ExchangeDelete};
+    use protocol::amqp::method::queue::{QueueBind, QueueDeclare, QueueDelete};
+    use protocol::amqp::method::{
+        AmqpMethod, BasicMethod, ChannelMethod, ConnectionMethod, ExchangeMethod, QueueMethod,
+        CLASS_BASIC,
+    };
+    use tokio::net::TcpStream;
+    use tokio::time::timeout;
+    use tokio_util::codec::Framed;
+
+    const CHANNEL_ID: u16 = 1;
+
+    #[tokio::test]
+    async fn amqp_declare_bind_publish_consume_test() {
+        let socket = TcpStream::connect("127.0.0.1:5672").await.unwrap();
+        let mut stream = Framed::new(socket, AmqpCodec::new(None));
+        open_connection(&mut stream).await;
+
+        let unique = unique_id();
+        let exchange_name = format!("test-exchange-{}", unique);
+        let queue_name = format!("test-queue-{}", unique);
+        let routing_key = "test.key".to_string();
+
+        let frame = request(
+            &mut stream,
+            AmqpMethod::Exchange(ExchangeMethod::Declare(ExchangeDeclare {
+                exchange: exchange_name.clone(),
+                kind: "direct".to_string(),
+                durable: true,
+                ..Default::default()
+            })),
+        )
+        .await;
+        assert_eq!(
+            frame,
+            AmqpFrame::Method(CHANNEL_ID, AmqpMethod::Exchange(ExchangeMethod::DeclareOk))
+        );
+
+        let frame = request(
+            &mut stream,
+            AmqpMethod::Queue(QueueMethod::Declare(QueueDeclare {
+                queue: queue_name.clone(),
+                durable: true,
+                ..Default::default()
+            })),
+        )
+        .await;
+        match frame {
+            AmqpFrame::Method(_, AmqpMethod::Queue(QueueMethod::DeclareOk(declare_ok))) => {
+                assert_eq!(declare_ok.queue, queue_name);
+            }
+            other => panic!("unexpected frame {:?}", other),
+        }
+
+        let frame = request(
+            &mut stream,
+            AmqpMethod::Queue(QueueMethod::Bind(QueueBind {
+                queue: queue_name.clone(),
+                exchange: exchange_name.clone(),
+                routing_key: routing_key.clone(),
+                ..Default::default()
+            })),
+        )
+        .await;
+        assert_eq!(
+            frame,
+            AmqpFrame::Method(CHANNEL_ID, AmqpMethod::Queue(QueueMethod::BindOk))
+        );
+
+        let frame = request(
+            &mut stream,
+            AmqpMethod::Basic(BasicMethod::Consume(BasicConsume {
+                queue: queue_name.clone(),
+                consumer_tag: "test-consumer".to_string(),
+                no_ack: true,
+                ..Default::default()
+            })),
+        )
+        .await;
+        assert_eq!(
+            frame,
+            AmqpFrame::Method(
+                CHANNEL_ID,
+                AmqpMethod::Basic(BasicMethod::ConsumeOk("test-consumer".to_string()))
+            )
+        );
+
+        let payload = Bytes::from("hello robustmq amqp");
+        send(
+            &mut stream,
+            AmqpFrame::Method(
+                CHANNEL_ID,
+                AmqpMethod::Basic(BasicMethod::Publish(BasicPublish {
+                    exchange: exchange_name.clone(),
+                    routing_key: routing_key.clone(),
+                    ..Default::default()
+                })),
+            ),
+        )
+        .await;
+        send(
+            &mut stream,
+            AmqpFrame::Header(
+                CHANNEL_ID,
+                ContentHeader {
+                    class_id: CLASS_BASIC,
+                    body_size: payload.len() as u64,
+                    ..Default::default()
+                },
+            ),
+        )
+        .await;
+        send(&mut stream, AmqpFrame::Body(CHANNEL_ID, payload.clone())).await;
+
+        match next_frame(&mut stream).await {
+            AmqpFrame::Method(_, AmqpMethod::Basic(BasicMethod::Deliver(deliver))) => {
+                assert_eq!(deliver.consumer_tag, "test-consumer");
+                assert_eq!(deliver.exchange, exchange_name);
+                assert_eq!(deliver.routing_key, routing_key);
+            }
+            other => panic!("unexpected frame {:?}", other),
+        }
+        match next_frame(&mut stream).await {
+            AmqpFrame::Header(_, header) => {
+                assert_eq!(header.body_size, payload.len() as u64);
+            }
+            other => panic!("unexpected frame {:?}", other),
+        }
+        assert_eq!(
+            next_frame(&mut stream).await,
+            AmqpFrame::Body(CHANNEL_ID, payload)
+        );
+
+        let frame = request(
+            &mut stream,
+            AmqpMethod::Queue(QueueMethod::Delete(QueueDelete {
+                queue: queue_name.clone(),
+                ..Default::default()
+            })),
+        )
+        .await;
+        assert!(matches!(
+            frame,
+            AmqpFrame::Method(_, AmqpMethod::Queue(QueueMethod::DeleteOk(_)))
+        ));
+
+        let frame = request(
+            &mut stream,
+            AmqpMethod::Exchange(ExchangeMethod::Delete(ExchangeDelete {
+                exchange: exchange_name.clone(),
+                ..Default::default()
+            })),
+        )
+        .await;
+        assert_eq!(
+            frame,
+            AmqpFrame::Method(CHANNEL_ID, AmqpMethod::Exchange(ExchangeMethod::DeleteOk))
+        );
+
+        send(
+            &mut stream,
+            AmqpFrame::Method(
+                0,
+                AmqpMethod::Connection(ConnectionMethod::Close(ConnectionClose {
+                    reply_code: 200,
+                    reply_text: "bye".to_string(),
+                    ..Default::default()
+                })),
+            ),
+        )
+        .await;
+        assert_eq!(
+            next_frame(&mut stream).await,
+            AmqpFrame::Method(0, AmqpMethod::Connection(ConnectionMethod::CloseOk))
+        );
+    }
+
+    /// Runs the handshake up to connection.open-ok and opens CHANNEL_ID
+    async fn open_connection(stream: &mut Framed<TcpStream, AmqpCodec>) {
+        send(
+            stream,
+            AmqpFrame::ProtocolHeader(ProtocolVersion::amqp_0_9_1()),
+        )
+        .await;
+        match next_frame(stream).await {
+            AmqpFrame::Method(0, AmqpMethod::Connection(ConnectionMethod::Start(start))) => {
+                assert!(start.mechanisms.contains("PLAIN"));
+            }
+            other => panic!("unexpected frame {:?}", other),
+        }
+
+        send(
+            stream,
+            AmqpFrame::Method(
+                0,
+                AmqpMethod::Connection(ConnectionMethod::StartOk(ConnectionStartOk {
+                    mechanism: "PLAIN".to_string(),
+                    response: Bytes::from("\0admin\0pwd123"),
+                    locale: "en_US".to_string(),
+                    ..Default::default()
+                })),
+            ),
+        )
+        .await;
+        let tune = match next_frame(stream).await {
+            AmqpFrame::Method(0, AmqpMethod::Connection(ConnectionMethod::Tune(tune))) => tune,
+            other => panic!("unexpected frame {:?}", other),
+        };
+        stream.codec_mut().set_frame_max(tune.frame_max);
+        send(
+            stream,
+            AmqpFrame::Method(0, AmqpMethod::Connection(ConnectionMethod::TuneOk(tune))),
+        )
+        .await;
+
+        send(
+            stream,
+            AmqpFrame::Method(
+                0,
+                AmqpMethod::Connection(ConnectionMethod::Open(ConnectionOpen {
+                    virtual_host: "/".to_string(),
+                })),
+            ),
+        )
+        .await;
+        assert_eq!(
+            next_frame(stream).await,
+            AmqpFrame::Method(0, AmqpMethod::Connection(ConnectionMethod::OpenOk))
+        );
+
+        let frame = request(stream, AmqpMethod::Channel(ChannelMethod::Open)).await;
+        assert_eq!(
+            frame,
+            AmqpFrame::Method(CHANNEL_ID, AmqpMethod::Channel(ChannelMethod::OpenOk))
+        );
+    }
+
+    async fn request(stream: &mut Framed<TcpStream, AmqpCodec>, method: AmqpMethod) -> AmqpFrame {
+        send(stream, AmqpFrame::Method(CHANNEL_ID, method)).await;
+        next_frame(stream).await
+    }
+
+    async fn send(stream: &mut Framed<TcpStream, AmqpCodec>, frame: AmqpFrame) {
+        stream.send(frame).await.unwrap();
+    }
+
+    /// Next frame other than a heartbeat, failing the test after 10 seconds
+    async fn next_frame(stream: &mut Framed<TcpStream, AmqpCodec>) -> AmqpFrame {
+        loop {
+            let frame = timeout(Duration::from_secs(10), stream.next())
+                .await
+                .expect("timed out waiting for a frame")
+                .expect("connection closed by the broker")
+                .unwrap();
+            if frame != AmqpFrame::Heartbeat {
+                return frame;
+            }
+        }
+    }
+}
@@ -39,6 +39,7 @@ mod tests {
             header: Some(ReqHeader {
                 api_key: ApiKey::GetClusterMetadata.into(),
                 api_version: ApiVersion::V0.into(),
+                ..Default::default()
             }),
         });
 
@@ -60,6 +61,7 @@ mod tests {
             header: Some(ReqHeader {
                 api_key: ApiKey::CreateShard.into(),
                 api_version: ApiVersion::V0.into(),
+                ..Default::default()
             }),
             body: Some(CreateShardReqBody {
                 namespace: "b1".to_string(),
@@ -90,6 +92,7 @@ mod tests {
             header: Some(ReqHeader {
                 api_key: ApiKey::GetShardMetadata.into(),
                 api_version: ApiVersion::V0.into(),
+                ..Default::default()
             }),
             body: Some(GetShardMetadataReqBody { shards }),
         });
@@ -111,6 +114,7 @@ mod tests {
             header: Some(ReqHeader {
                 api_key: ApiKey::DeleteShard.into(),
                 api_version: ApiVersion::V0.into(),
+                ..Default::default()
             }),
             body: Some(DeleteShardReqBody {
                 namespace: "b1".to_string(),
@@ -135,6 +139,7 @@ mod tests {
             header: Some(ReqHeader {
                 api_key: ApiKey::Write.into(),
                 api_version: ApiVersion::V0.into(),
+                ..Default::default()
             }),
             body: Some(WriteReqBody {
                 data: vec![WriteReqSegmentMessages {
@@ -168,6 +173,7 @@ mod tests {
             header: Some(ReqHeader {
                 api_key: ApiKey::Read.into(),
                 api_version: ApiVersion::V0.into(),
+                ..Default::default()
             }),
             body: Some(ReadReqBody {
                 messages: vec![ReadReqMessage {
@@ -201,6 +207,7 @@ mod tests {
             header: Some(ReqHeader {
                 api_key: ApiKey::Read.into(),
                 api_version: ApiVersion::V0.into(),
+                ..Default::default()
             }),
             body: Some(FetchOffsetReqBody {
                 group_name: "g1".to_string(),
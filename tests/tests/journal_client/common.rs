@@ -15,3 +15,14 @@
 pub fn journal_tcp_addr() -> String {
     "127.0.0.1:3110".to_string()
 }
+
+pub fn journal_quic_addr() -> String {
+    "127.0.0.1:3112".to_string()
+}
+
+pub fn journal_quic_ca_cert() -> String {
+    format!(
+        "{}/../config/example/certs/ca.pem",
+        env!("CARGO_MANIFEST_DIR")
+    )
+}
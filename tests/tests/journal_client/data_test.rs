@@ -44,6 +44,7 @@ mod tests {
             header: Some(ReqHeader {
                 api_key: ApiKey::GetClusterMetadata.into(),
                 api_version: ApiVersion::V0.into(),
+                ..Default::default()
             }),
         });
 
@@ -75,6 +76,7 @@ mod tests {
             header: Some(ReqHeader {
                 api_key: ApiKey::CreateShard.into(),
                 api_version: ApiVersion::V0.into(),
+                ..Default::default()
             }),
             body: Some(CreateShardReqBody {
                 namespace: namespace.clone(),
@@ -102,6 +104,7 @@ mod tests {
             header: Some(ReqHeader {
                 api_key: ApiKey::GetShardMetadata.into(),
                 api_version: ApiVersion::V0.into(),
+                ..Default::default()
             }),
             body: Some(GetShardMetadataReqBody {
                 shards: vec![GetShardMetadataReqShard {
@@ -148,6 +151,7 @@ mod tests {
                 header: Some(ReqHeader {
                     api_key: ApiKey::Write.into(),
                     api_version: ApiVersion::V0.into(),
+                    ..Default::default()
                 }),
                 body: Some(WriteReqBody {
                     data: vec![WriteReqSegmentMessages {
@@ -185,6 +189,7 @@ mod tests {
             header: Some(ReqHeader {
                 api_key: ApiKey::Read.into(),
                 api_version: ApiVersion::V0.into(),
+                ..Default::default()
             }),
             body: Some(ReadReqBody {
                 messages: vec![ReadReqMessage {
@@ -15,4 +15,5 @@
 pub mod base_test;
 pub mod common;
 pub mod data_test;
+pub mod quic_test;
 pub mod shard;
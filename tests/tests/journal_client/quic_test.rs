@@ -0,0 +1,115 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::sync::Arc;
+
+    use common_base::tools::unique_id;
+    use futures::{SinkExt, StreamExt};
+    use journal_client::client::JournalClient;
+    use journal_client::option::JournalClientOption;
+    use journal_client::tool::resp_header_error;
+    use protocol::journal_server::codec::{JournalEnginePacket, JournalServerCodec};
+    use protocol::journal_server::journal_engine::{
+        ApiKey, ApiVersion, GetClusterMetadataReq, ReqHeader,
+    };
+    use quinn::crypto::rustls::QuicClientConfig;
+    use quinn::Endpoint;
+    use rustls_pemfile::certs;
+    use tokio_rustls::rustls::{self, ClientConfig, RootCertStore};
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    use crate::journal_client::common::{
+        journal_quic_addr, journal_quic_ca_cert, journal_tcp_addr,
+    };
+
+    #[tokio::test]
+    async fn quic_get_cluster_metadata_test() {
+        let endpoint = build_client_endpoint();
+        let connection = endpoint
+            .connect(journal_quic_addr().parse().unwrap(), "localhost")
+            .unwrap()
+            .await
+            .unwrap();
+        let (send_stream, recv_stream) = connection.open_bi().await.unwrap();
+        let mut write_stream = FramedWrite::new(send_stream, JournalServerCodec::new());
+        let mut read_stream = FramedRead::new(recv_stream, JournalServerCodec::new());
+
+        let req_packet = JournalEnginePacket::GetClusterMetadataReq(GetClusterMetadataReq {
+            header: Some(ReqHeader {
+                api_key: ApiKey::GetClusterMetadata.into(),
+                api_version: ApiVersion::V0.into(),
+                ..Default::default()
+            }),
+        });
+        write_stream.send(req_packet.clone()).await.unwrap();
+
+        if let Some(Ok(JournalEnginePacket::GetClusterMetadataResp(data))) =
+            read_stream.next().await
+        {
+            assert!(resp_header_error(&data.header, req_packet).is_ok());
+            let nodes = data.body.unwrap().nodes;
+            assert!(!nodes.is_empty());
+            for node in nodes {
+                assert!(!node.quic_addr.is_empty());
+            }
+        } else {
+            panic!()
+        }
+
+        connection.close(0u32.into(), b"");
+        endpoint.wait_idle().await;
+    }
+
+    #[tokio::test]
+    async fn quic_client_create_delete_shard_test() {
+        let mut option = JournalClientOption::build();
+        option.set_addrs(vec![journal_tcp_addr()]);
+        option.set_quic(journal_quic_ca_cert());
+
+        let client = JournalClient::new_with_option(option);
+        client.connect().await.unwrap();
+
+        let namespace = unique_id();
+        let shard_name = "s1".to_string();
+        client
+            .create_shard(&namespace, &shard_name, 1)
+            .await
+            .unwrap();
+        client.delete_shard(&namespace, &shard_name).await.unwrap();
+        client.close().await.unwrap();
+    }
+
+    fn build_client_endpoint() -> Endpoint {
+        let mut roots = RootCertStore::empty();
+        let mut reader = BufReader::new(File::open(journal_quic_ca_cert()).unwrap());
+        for cert in certs(&mut reader) {
+            roots.add(cert.unwrap()).unwrap();
+        }
+
+        let mut tls_config =
+            ClientConfig::builder_with_protocol_versions(&[&rustls::version::TLS13])
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        tls_config.alpn_protocols = vec![b"robust-journal".to_vec()];
+
+        let crypto = QuicClientConfig::try_from(tls_config).unwrap();
+        let mut endpoint = Endpoint::client("0.0.0.0:0".parse().unwrap()).unwrap();
+        endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(crypto)));
+        endpoint
+    }
+}
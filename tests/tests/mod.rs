@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod amqp_protocol;
 pub mod journal_client;
 pub mod journal_server;
 pub mod mqtt_client;
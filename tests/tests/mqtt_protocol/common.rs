@@ -39,6 +39,11 @@ pub fn broker_ws_addr() -> String {
 pub fn broker_wss_addr() -> String {
     "wss://127.0.0.1:8094".to_string()
 }
+
+#[allow(dead_code)]
+pub fn broker_quic_addr() -> String {
+    "127.0.0.1:9083".to_string()
+}
 #[allow(dead_code)]
 pub fn broker_grpc_addr() -> String {
     "127.0.0.1:9981".to_string()
@@ -394,7 +399,7 @@ pub fn connect_server5_packet_size(
                 assert_eq!(format!("tcp://{}", resp.server_uri), broker_addr());
             }
             assert_eq!(mqtt_version, resp.mqtt_version);
-            assert!(resp.session_present);
+            assert!(!resp.session_present);
             assert_eq!(response.reason_code(), ReasonCode::Success);
         }
         Err(e) => {
@@ -426,7 +431,7 @@ pub fn connect_server5_response_information(client_id: &str, addr: &str) -> (Cli
 
             assert_eq!(format!("tcp://{}", resp.server_uri), broker_addr());
             assert_eq!(mqtt_version, resp.mqtt_version);
-            assert!(resp.session_present);
+            assert!(!resp.session_present);
             assert_eq!(response.reason_code(), ReasonCode::Success);
 
             let resp_pros = response.properties();
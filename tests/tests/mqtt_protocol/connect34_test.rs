@@ -15,13 +15,14 @@
 #[cfg(test)]
 mod tests {
     use std::process;
+    use std::time::Duration;
 
     use common_base::tools::unique_id;
-    use paho_mqtt::{Client, ReasonCode};
+    use paho_mqtt::{Client, ConnectOptionsBuilder, ReasonCode};
 
     use crate::mqtt_protocol::common::{
         broker_addr, broker_ssl_addr, broker_ws_addr, broker_wss_addr, build_create_pros,
-        build_v3_conn_pros, distinct_conn,
+        build_v3_conn_pros, distinct_conn, password, username,
     };
 
     #[tokio::test]
@@ -39,6 +40,15 @@ mod tests {
         v3_session_present_test(mqtt_version, &client_id, &addr, false, false);
     }
 
+    #[tokio::test]
+    async fn client34_clean_session_test() {
+        for mqtt_version in [3, 4] {
+            let client_id = unique_id();
+            let addr = broker_addr();
+            v3_clean_session_test(mqtt_version, &client_id, &addr);
+        }
+    }
+
     #[tokio::test]
     async fn client34_connect_ssl_test() {
         let mqtt_version = 3;
@@ -86,6 +96,27 @@ mod tests {
         println!("Unable to connect:\n\t{:?}", err);
     }
 
+    // session_present is only set when a client connecting with clean_session=false
+    // resumes a stored session, a clean session always starts without one.
+    fn v3_clean_session_test(mqtt_version: u32, client_id: &str, addr: &str) {
+        for (clean_session, session_present) in [(false, false), (false, true), (true, false)] {
+            let create_opts = build_create_pros(client_id, addr);
+            let cli = Client::new(create_opts).unwrap();
+            let conn_opts = ConnectOptionsBuilder::with_mqtt_version(mqtt_version)
+                .keep_alive_interval(Duration::from_secs(600))
+                .clean_session(clean_session)
+                .connect_timeout(Duration::from_secs(50))
+                .user_name(username())
+                .password(password())
+                .finalize();
+            let response = cli.connect(conn_opts).unwrap();
+            let resp = response.connect_response().unwrap();
+            assert_eq!(session_present, resp.session_present);
+            assert_eq!(response.reason_code(), ReasonCode::Success);
+            distinct_conn(cli);
+        }
+    }
+
     fn v3_session_present_test(
         mqtt_version: u32,
         client_id: &str,
@@ -113,7 +144,7 @@ mod tests {
             }
             assert_eq!(mqtt_version, resp.mqtt_version);
         }
-        assert!(resp.session_present);
+        assert!(!resp.session_present);
         assert_eq!(response.reason_code(), ReasonCode::Success);
         distinct_conn(cli);
 
@@ -17,7 +17,7 @@ mod tests {
     use std::process;
 
     use common_base::tools::unique_id;
-    use mqtt_broker::handler::connection::REQUEST_RESPONSE_PREFIX_NAME;
+    use mqtt_broker::security::acl::response_topic::build_response_information;
     use paho_mqtt::{Client, PropertyCode, ReasonCode};
 
     use crate::mqtt_protocol::common::{
@@ -386,7 +386,7 @@ mod tests {
                         .unwrap()
                         .get_string()
                         .unwrap(),
-                    REQUEST_RESPONSE_PREFIX_NAME.to_string()
+                    build_response_information(client_id)
                 );
 
                 assert!(resp_pros.get(PropertyCode::ServerReference).is_none());
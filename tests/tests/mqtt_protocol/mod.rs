@@ -22,6 +22,7 @@ pub mod lastwill_message_test;
 pub mod permission34_test;
 pub mod permission5_test;
 pub mod pub_qos_test;
+pub mod quic_sub_pub_test;
 pub mod req_resp_test;
 pub mod retain_message_test;
 pub mod share_sub_test;
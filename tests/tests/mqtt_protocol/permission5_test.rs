@@ -20,7 +20,7 @@ mod tests {
     use common_base::tools::unique_id;
     use grpc_clients::mqtt::admin::call::{mqtt_broker_create_user, mqtt_broker_delete_user};
     use grpc_clients::pool::ClientPool;
-    use mqtt_broker::handler::connection::REQUEST_RESPONSE_PREFIX_NAME;
+    use mqtt_broker::security::acl::response_topic::build_response_information;
     use paho_mqtt::{Client, PropertyCode, ReasonCode};
     use protocol::broker_mqtt::broker_mqtt_admin::{CreateUserRequest, DeleteUserRequest};
 
@@ -444,7 +444,7 @@ mod tests {
                         .unwrap()
                         .get_string()
                         .unwrap(),
-                    REQUEST_RESPONSE_PREFIX_NAME.to_string()
+                    build_response_information(client_id)
                 );
 
                 assert!(resp_pros.get(PropertyCode::ServerReference).is_none());
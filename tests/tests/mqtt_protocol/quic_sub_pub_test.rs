@@ -0,0 +1,127 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use common_base::tools::unique_id;
+    use futures::{SinkExt, StreamExt};
+    use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
+    use protocol::mqtt::common::{
+        Connect, ConnectReturnCode, Login, MqttPacket, PubAckReason, Publish, QoS,
+    };
+    use quinn::crypto::rustls::QuicClientConfig;
+    use quinn::Endpoint;
+    use rustls_pemfile::certs;
+    use tokio_rustls::rustls::{self, ClientConfig, RootCertStore};
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    use crate::mqtt_protocol::common::broker_quic_addr;
+
+    #[tokio::test]
+    async fn quic_connect_publish_test() {
+        let endpoint = build_client_endpoint();
+        let connection = endpoint
+            .connect(broker_quic_addr().parse().unwrap(), "localhost")
+            .unwrap()
+            .await
+            .unwrap();
+        let (send_stream, recv_stream) = connection.open_bi().await.unwrap();
+        let mut write_stream = FramedWrite::new(send_stream, MqttCodec::new(Some(4)));
+        let mut read_stream = FramedRead::new(recv_stream, MqttCodec::new(Some(4)));
+
+        // connect
+        let connect = Connect {
+            keep_alive: 30,
+            client_id: unique_id(),
+            clean_session: true,
+        };
+        let login = Some(Login {
+            username: "admin".to_string(),
+            password: "pwd123".to_string(),
+        });
+        write_stream
+            .send(wrap(MqttPacket::Connect(
+                4, connect, None, None, None, login,
+            )))
+            .await
+            .unwrap();
+        match read_stream.next().await {
+            Some(Ok(MqttPacket::ConnAck(conn_ack, _))) => {
+                assert_eq!(conn_ack.code, ConnectReturnCode::Success);
+            }
+            val => panic!("unexpected connack response: {:?}", val),
+        }
+
+        // publish
+        let publish = Publish {
+            dup: false,
+            qos: QoS::AtLeastOnce,
+            pkid: 1,
+            retain: false,
+            topic: Bytes::from(format!("/tests/quic/{}", unique_id())),
+            payload: Bytes::from("quic round trip"),
+        };
+        write_stream
+            .send(wrap(MqttPacket::Publish(publish, None)))
+            .await
+            .unwrap();
+        match read_stream.next().await {
+            Some(Ok(MqttPacket::PubAck(pub_ack, _))) => {
+                assert_eq!(pub_ack.pkid, 1);
+                if let Some(reason) = pub_ack.reason {
+                    assert_eq!(reason, PubAckReason::Success);
+                }
+            }
+            val => panic!("unexpected puback response: {:?}", val),
+        }
+
+        connection.close(0u32.into(), b"");
+        endpoint.wait_idle().await;
+    }
+
+    fn wrap(packet: MqttPacket) -> MqttPacketWrapper {
+        MqttPacketWrapper {
+            protocol_version: 4,
+            packet,
+        }
+    }
+
+    fn build_client_endpoint() -> Endpoint {
+        let ca_path = format!(
+            "{}/../config/example/certs/ca.pem",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mut roots = RootCertStore::empty();
+        let mut reader = BufReader::new(File::open(ca_path).unwrap());
+        for cert in certs(&mut reader) {
+            roots.add(cert.unwrap()).unwrap();
+        }
+
+        let mut tls_config =
+            ClientConfig::builder_with_protocol_versions(&[&rustls::version::TLS13])
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        tls_config.alpn_protocols = vec![b"mqtt".to_vec()];
+
+        let crypto = QuicClientConfig::try_from(tls_config).unwrap();
+        let mut endpoint = Endpoint::client("0.0.0.0:0".parse().unwrap()).unwrap();
+        endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(crypto)));
+        endpoint
+    }
+}
@@ -21,6 +21,29 @@ mod tests {
         broker_addr, connect_server5, connect_server5_response_information, distinct_conn,
     };
 
+    #[tokio::test]
+    async fn client5_response_topic_acl_test() {
+        let addr = broker_addr();
+        let owner_client_id = unique_id();
+        let (owner_cli, response_information) =
+            connect_server5_response_information(&owner_client_id, &addr);
+        let response_topic = format!("{response_information}{}", unique_id());
+
+        // the owner may subscribe to its own response topic
+        assert!(owner_cli.subscribe(response_topic.as_str(), QOS_1).is_ok());
+
+        // other clients may neither subscribe nor publish without a request
+        let other_client_id = unique_id();
+        let other_cli = connect_server5(&other_client_id, &addr, false, false);
+        assert!(other_cli.subscribe(response_topic.as_str(), QOS_1).is_err());
+
+        let msg = Message::new(response_topic.clone(), "unsolicited", QOS_1);
+        assert!(other_cli.publish(msg).is_err());
+
+        distinct_conn(other_cli);
+        distinct_conn(owner_cli);
+    }
+
     #[tokio::test]
     async fn client5_reuqset_response_test() {
         let sub_qos = &[0, 0];